@@ -0,0 +1,6 @@
+// build.rs
+fn main() {
+    // `sqlx::migrate!` embeds the migration files, so rebuild when they change
+    println!("cargo:rerun-if-changed=migrations");
+    println!("cargo:rerun-if-changed=migrations_postgres");
+}
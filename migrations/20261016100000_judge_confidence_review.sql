@@ -0,0 +1,11 @@
+-- ========================================
+-- 20261016100000_judge_confidence_review.sql
+-- Judge confidence and human review queue
+-- ========================================
+
+ALTER TABLE evaluations ADD COLUMN judge_confidence REAL;
+ALTER TABLE evaluations ADD COLUMN needs_review BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE evaluations ADD COLUMN human_verdict TEXT;
+ALTER TABLE evaluations ADD COLUMN reviewed_at TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_evaluations_needs_review ON evaluations(needs_review);
@@ -0,0 +1,7 @@
+-- ========================================
+-- 20261016100100_evaluation_costs.sql
+-- Per-evaluation cost tracking (USD)
+-- ========================================
+
+ALTER TABLE evaluations ADD COLUMN cost_usd REAL;
+ALTER TABLE evaluations ADD COLUMN judge_cost_usd REAL;
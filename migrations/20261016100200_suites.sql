@@ -0,0 +1,28 @@
+-- ========================================
+-- 20261016100200_suites.sql
+-- Named evaluation suites and their runs
+-- ========================================
+
+CREATE TABLE IF NOT EXISTS suites (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL UNIQUE,
+    description TEXT,
+    configs TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS suite_runs (
+    id TEXT PRIMARY KEY NOT NULL,
+    suite_id TEXT NOT NULL REFERENCES suites(id) ON DELETE CASCADE,
+    batch_id TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS suite_run_evaluations (
+    suite_run_id TEXT NOT NULL REFERENCES suite_runs(id) ON DELETE CASCADE,
+    evaluation_id TEXT NOT NULL,
+    PRIMARY KEY (suite_run_id, evaluation_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_suite_runs_suite_id ON suite_runs(suite_id, created_at);
@@ -0,0 +1,26 @@
+-- ========================================
+-- 20261016100300_prompt_versions.sql
+-- Prompt version tables
+-- ========================================
+
+-- prompt_versions and prompt_evaluations are queried by the prompt-version API
+-- but were missing from the initial schema
+CREATE TABLE IF NOT EXISTS prompt_versions (
+    version INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    prompt_template TEXT NOT NULL,
+    description TEXT,
+    tags TEXT,
+    metadata TEXT,
+    is_active BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TEXT NOT NULL,
+    created_by TEXT
+);
+
+CREATE TABLE IF NOT EXISTS prompt_evaluations (
+    evaluation_id TEXT NOT NULL,
+    prompt_version INTEGER NOT NULL REFERENCES prompt_versions(version),
+    PRIMARY KEY (evaluation_id, prompt_version)
+);
+
+CREATE INDEX IF NOT EXISTS idx_prompt_evaluations_version ON prompt_evaluations(prompt_version);
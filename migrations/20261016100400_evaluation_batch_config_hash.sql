@@ -0,0 +1,9 @@
+-- ========================================
+-- 20261016100400_evaluation_batch_config_hash.sql
+-- Batch membership and stable config keys for run comparison
+-- ========================================
+
+ALTER TABLE evaluations ADD COLUMN batch_id TEXT;
+ALTER TABLE evaluations ADD COLUMN config_hash TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_evaluations_batch_id ON evaluations(batch_id);
@@ -0,0 +1,6 @@
+-- ========================================
+-- 20261016100500_judge_raw_response.sql
+-- Keep the judge's full response alongside the parsed verdict
+-- ========================================
+
+ALTER TABLE evaluations ADD COLUMN judge_raw_response TEXT;
@@ -0,0 +1,15 @@
+-- ========================================
+-- 20261016100600_batches.sql
+-- One row per batch run, summarizing its member evaluations
+-- ========================================
+
+CREATE TABLE IF NOT EXISTS batches (
+    batch_id TEXT PRIMARY KEY NOT NULL,
+    created_at TEXT NOT NULL,
+    total INTEGER NOT NULL,
+    passed INTEGER NOT NULL DEFAULT 0,
+    failed INTEGER NOT NULL DEFAULT 0,
+    status TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_batches_created_at ON batches(created_at);
@@ -0,0 +1,8 @@
+-- ========================================
+-- 20261016100700_evaluation_truncation.sql
+-- Record context-window truncation applied to an evaluation's input
+-- ========================================
+
+ALTER TABLE evaluations ADD COLUMN truncation_strategy TEXT;
+ALTER TABLE evaluations ADD COLUMN truncation_original_tokens INTEGER;
+ALTER TABLE evaluations ADD COLUMN truncation_truncated_tokens INTEGER;
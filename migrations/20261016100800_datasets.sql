@@ -0,0 +1,20 @@
+-- ========================================
+-- 20261016100800_datasets.sql
+-- Uploaded datasets whose rows drive batch runs
+-- ========================================
+
+CREATE TABLE IF NOT EXISTS datasets (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    format TEXT NOT NULL,
+    row_count INTEGER NOT NULL,
+    columns TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS dataset_rows (
+    dataset_id TEXT NOT NULL REFERENCES datasets(id) ON DELETE CASCADE,
+    row_index INTEGER NOT NULL,
+    data TEXT NOT NULL,
+    PRIMARY KEY (dataset_id, row_index)
+);
@@ -0,0 +1,19 @@
+-- ========================================
+-- 20261016100900_provider_errors.sql
+-- Every failed provider call, including attempts that a retry later recovered from
+-- ========================================
+
+CREATE TABLE IF NOT EXISTS provider_errors (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    provider TEXT NOT NULL,
+    model TEXT NOT NULL,
+    status_code INTEGER,
+    error_class TEXT NOT NULL,
+    body TEXT NOT NULL,
+    attempt INTEGER NOT NULL,
+    recovered BOOLEAN NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_provider_errors_created_at ON provider_errors(created_at);
+CREATE INDEX IF NOT EXISTS idx_provider_errors_provider ON provider_errors(provider);
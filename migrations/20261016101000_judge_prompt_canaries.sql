@@ -0,0 +1,14 @@
+-- ========================================
+-- 20261016101000_judge_prompt_canaries.sql
+-- Percentage rollouts of a candidate judge prompt, and the arm each evaluation landed on
+-- ========================================
+
+CREATE TABLE IF NOT EXISTS judge_prompt_canaries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    candidate_version INTEGER NOT NULL REFERENCES judge_prompts(version),
+    percentage REAL NOT NULL,
+    started_at TEXT NOT NULL,
+    stopped_at TEXT
+);
+
+ALTER TABLE evaluations ADD COLUMN judge_prompt_arm TEXT;
@@ -0,0 +1,6 @@
+-- ========================================
+-- 20261016101100_evaluation_tags.sql
+-- Keep an evaluation's tags (JSON array) so stats can be filtered by tag
+-- ========================================
+
+ALTER TABLE evaluations ADD COLUMN tags TEXT;
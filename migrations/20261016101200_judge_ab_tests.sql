@@ -0,0 +1,30 @@
+-- ========================================
+-- 20261016101200_judge_ab_tests.sql
+-- Offline A/B tests re-judging stored model outputs with two judge prompt versions
+-- ========================================
+
+CREATE TABLE IF NOT EXISTS judge_ab_tests (
+    id TEXT PRIMARY KEY,
+    version_a INTEGER NOT NULL REFERENCES judge_prompts(version),
+    version_b INTEGER NOT NULL REFERENCES judge_prompts(version),
+    -- NULL when each evaluation was re-judged with the judge model it was originally judged by
+    judge_model TEXT,
+    created_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS judge_ab_results (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    test_id TEXT NOT NULL REFERENCES judge_ab_tests(id),
+    evaluation_id TEXT NOT NULL REFERENCES evaluations(id),
+    version INTEGER NOT NULL,
+    judge_model TEXT NOT NULL,
+    verdict TEXT,
+    confidence REAL,
+    reasoning TEXT,
+    latency_ms INTEGER,
+    cost_usd REAL,
+    error_message TEXT,
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_judge_ab_results_test ON judge_ab_results(test_id);
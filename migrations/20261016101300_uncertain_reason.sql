@@ -0,0 +1,8 @@
+-- ========================================
+-- 20261016101300_uncertain_reason.sql
+-- Why a verdict is uncertain: judge_abstained, unparseable, low_confidence or human_review
+-- ========================================
+
+ALTER TABLE evaluations ADD COLUMN uncertain_reason TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_evaluations_uncertain_reason ON evaluations(uncertain_reason);
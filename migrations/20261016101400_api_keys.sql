@@ -0,0 +1,15 @@
+-- ========================================
+-- 20261016101400_api_keys.sql
+-- API keys with a role (viewer, runner or admin); only a hash of each key is stored
+-- ========================================
+
+CREATE TABLE IF NOT EXISTS api_keys (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    role TEXT NOT NULL,
+    key_hash TEXT NOT NULL UNIQUE,
+    -- First characters of the key, so a key can be recognized in listings
+    key_prefix TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    revoked_at TEXT
+);
@@ -0,0 +1,25 @@
+-- ========================================
+-- 20261016101500_judgements.sql
+-- Judge verdicts replaced by a re-judge, kept for audit
+-- ========================================
+
+CREATE TABLE IF NOT EXISTS judgements (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    evaluation_id TEXT NOT NULL REFERENCES evaluations(id),
+    status TEXT,
+    judge_model TEXT,
+    judge_prompt_version INTEGER,
+    judge_verdict TEXT,
+    judge_confidence REAL,
+    judge_reasoning TEXT,
+    judge_raw_response TEXT,
+    uncertain_reason TEXT,
+    judge_latency_ms INTEGER,
+    judge_input_tokens INTEGER,
+    judge_output_tokens INTEGER,
+    judge_cost_usd REAL,
+    -- When the re-judge replaced this verdict
+    replaced_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_judgements_evaluation ON judgements(evaluation_id);
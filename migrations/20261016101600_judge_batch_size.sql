@@ -0,0 +1,6 @@
+-- ========================================
+-- 20261016101600_judge_batch_size.sql
+-- Number of evals judged together in one batched judge call; NULL when judged on its own
+-- ========================================
+
+ALTER TABLE evaluations ADD COLUMN judge_batch_size INTEGER;
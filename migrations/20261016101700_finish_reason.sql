@@ -0,0 +1,7 @@
+-- ========================================
+-- 20261016101700_finish_reason.sql
+-- Why the provider stopped generating, normalized across providers
+-- ========================================
+
+-- stop, length, content_filter, tool_calls, or a provider value passed through verbatim
+ALTER TABLE evaluations ADD COLUMN finish_reason TEXT;
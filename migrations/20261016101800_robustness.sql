@@ -0,0 +1,29 @@
+-- ========================================
+-- 20261016101800_robustness.sql
+-- Robustness runs: one model against a base prompt and rephrasings of it
+-- ========================================
+
+CREATE TABLE IF NOT EXISTS robustness_groups (
+    id TEXT PRIMARY KEY NOT NULL,
+    batch_id TEXT NOT NULL,
+    model TEXT NOT NULL,
+    base_prompt TEXT NOT NULL,
+    expected TEXT,
+    -- NULL when the variants were supplied by the user
+    paraphrase_model TEXT,
+    paraphrase_input_tokens INTEGER,
+    paraphrase_output_tokens INTEGER,
+    paraphrase_cost_usd REAL,
+    created_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS robustness_variants (
+    group_id TEXT NOT NULL REFERENCES robustness_groups(id) ON DELETE CASCADE,
+    -- 0 is the base prompt
+    variant_index INTEGER NOT NULL,
+    prompt TEXT NOT NULL,
+    -- base, paraphrase or user
+    source TEXT NOT NULL,
+    evaluation_id TEXT NOT NULL,
+    PRIMARY KEY (group_id, variant_index)
+);
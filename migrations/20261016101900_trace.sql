@@ -0,0 +1,7 @@
+-- ========================================
+-- 20261016101900_trace.sql
+-- Timed execution phases of traced evaluations
+-- ========================================
+
+-- JSON array of trace events, capped in size; NULL unless the eval was traced and PERSIST_TRACES is on
+ALTER TABLE evaluations ADD COLUMN trace TEXT;
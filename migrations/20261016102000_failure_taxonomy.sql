@@ -0,0 +1,20 @@
+-- ========================================
+-- 20261016102000_failure_taxonomy.sql
+-- Failure categories of failed evaluations, and the taxonomy they come from
+-- ========================================
+
+-- NULL unless the evaluation failed and was classified, by a model, the judge or a human
+ALTER TABLE evaluations ADD COLUMN failure_category TEXT;
+ALTER TABLE evaluations ADD COLUMN failure_justification TEXT;
+-- Model that classified the failure, 'judge' or 'human'
+ALTER TABLE evaluations ADD COLUMN failure_classifier TEXT;
+ALTER TABLE evaluations ADD COLUMN failure_classification_cost_usd REAL;
+
+CREATE INDEX IF NOT EXISTS idx_evaluations_failure_category ON evaluations(failure_category);
+
+-- The editable taxonomy; seeded with the built-in categories on startup when empty
+CREATE TABLE IF NOT EXISTS failure_categories (
+    name TEXT PRIMARY KEY NOT NULL,
+    description TEXT NOT NULL,
+    position INTEGER NOT NULL
+);
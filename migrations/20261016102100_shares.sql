@@ -0,0 +1,16 @@
+-- ========================================
+-- 20261016102100_shares.sql
+-- Expiring, revocable links giving read-only access to one evaluation
+-- ========================================
+
+CREATE TABLE IF NOT EXISTS shares (
+    id TEXT PRIMARY KEY,
+    evaluation_id TEXT NOT NULL,
+    -- Name of the API key that created the share
+    created_by TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    expires_at TEXT NOT NULL,
+    revoked_at TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_shares_evaluation_id ON shares(evaluation_id);
@@ -0,0 +1,10 @@
+-- ========================================
+-- 20261016102300_evaluation_config_fields.sql
+-- Keep the parsed output and the criteria and metadata of the eval config with each evaluation
+-- ========================================
+
+-- JSON extracted from the model output, when it contained any
+ALTER TABLE evaluations ADD COLUMN parsed_output TEXT;
+ALTER TABLE evaluations ADD COLUMN criteria TEXT;
+-- JSON object of the eval config's metadata
+ALTER TABLE evaluations ADD COLUMN metadata TEXT;
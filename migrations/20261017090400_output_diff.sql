@@ -0,0 +1,6 @@
+-- ========================================
+-- 20261017090400_output_diff.sql
+-- Structural diff of an evaluation's parsed output against its JSON expected value
+-- ========================================
+
+ALTER TABLE evaluations ADD COLUMN output_diff TEXT;
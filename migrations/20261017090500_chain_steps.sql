@@ -0,0 +1,9 @@
+-- ========================================
+-- 20261017090500_chain_steps.sql
+-- The chain an evaluation ran in and its step, counted from 1
+-- ========================================
+
+ALTER TABLE evaluations ADD COLUMN chain_id TEXT;
+ALTER TABLE evaluations ADD COLUMN chain_step INTEGER;
+
+CREATE INDEX IF NOT EXISTS idx_evaluations_chain_id ON evaluations(chain_id);
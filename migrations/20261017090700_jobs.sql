@@ -0,0 +1,30 @@
+-- ========================================
+-- 20261017090700_jobs.sql
+-- Queued batch and suite runs, and the evals each has finished so far
+-- ========================================
+
+CREATE TABLE IF NOT EXISTS jobs (
+    job_id TEXT PRIMARY KEY NOT NULL,
+    kind TEXT NOT NULL,
+    suite_id TEXT,
+    configs TEXT NOT NULL,
+    total INTEGER NOT NULL,
+    batch_id TEXT NOT NULL,
+    status TEXT NOT NULL,
+    worker_id TEXT,
+    heartbeat_at TEXT,
+    created_at TEXT NOT NULL,
+    started_at TEXT,
+    finished_at TEXT,
+    error TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_jobs_status_created_at ON jobs(status, created_at);
+
+CREATE TABLE IF NOT EXISTS job_evals (
+    job_id TEXT NOT NULL REFERENCES jobs(job_id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    eval_id TEXT NOT NULL,
+    status TEXT NOT NULL,
+    PRIMARY KEY (job_id, position)
+);
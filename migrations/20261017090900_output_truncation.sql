@@ -0,0 +1,7 @@
+-- ========================================
+-- 20261017090900_output_truncation.sql
+-- Length of the model output and whether the saved copy was cut to MAX_STORED_OUTPUT_CHARS
+-- ========================================
+
+ALTER TABLE evaluations ADD COLUMN output_chars INTEGER;
+ALTER TABLE evaluations ADD COLUMN output_truncated BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,6 @@
+-- ========================================
+-- 20261017091300_evaluation_prompt_version.sql
+-- Prompt version an evaluation's prompt was rendered from, when it named one instead of a prompt
+-- ========================================
+
+ALTER TABLE evaluations ADD COLUMN prompt_version INTEGER;
@@ -0,0 +1,19 @@
+-- ========================================
+-- 20261017091400_schedules.sql
+-- Suites run on a cron schedule, and the schedule a queued job was started by
+-- ========================================
+
+CREATE TABLE IF NOT EXISTS schedules (
+    id TEXT PRIMARY KEY NOT NULL,
+    suite_id TEXT NOT NULL REFERENCES suites(id) ON DELETE CASCADE,
+    cron TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    notify_slack BOOLEAN NOT NULL DEFAULT FALSE,
+    callback_url TEXT,
+    last_run_at TEXT,
+    last_job_id TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+ALTER TABLE jobs ADD COLUMN schedule_id TEXT;
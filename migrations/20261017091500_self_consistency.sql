@@ -0,0 +1,6 @@
+-- ========================================
+-- 20261017091500_self_consistency.sql
+-- Samples of a self-consistency evaluation and how their verdicts combined
+-- ========================================
+
+ALTER TABLE evaluations ADD COLUMN self_consistency TEXT;
@@ -0,0 +1,25 @@
+-- ========================================
+-- 20261017091600_judge_calibrations.sql
+-- Calibrations of a judge prompt version against human verdicts, with position and
+-- verbosity bias probes
+-- ========================================
+
+CREATE TABLE IF NOT EXISTS judge_calibrations (
+    id TEXT PRIMARY KEY,
+    version INTEGER NOT NULL REFERENCES judge_prompts(version),
+    -- NULL when each evaluation was judged with the judge model it was originally judged by
+    judge_model TEXT,
+    evaluations INTEGER NOT NULL,
+    judged INTEGER NOT NULL,
+    accuracy REAL,
+    false_pass_rate REAL,
+    false_fail_rate REAL,
+    position_flip_rate REAL,
+    length_pass_correlation REAL,
+    -- JSON CalibrationSummary and array of CalibrationSample
+    summary TEXT NOT NULL,
+    samples TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_judge_calibrations_version ON judge_calibrations(version);
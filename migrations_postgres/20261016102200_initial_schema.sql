@@ -0,0 +1,294 @@
+-- ========================================
+-- 20261016102200_initial_schema.sql
+-- Schema for a fresh Postgres database, equivalent to the SQLite migrations up to
+-- 20261016102100_shares.sql. Timestamps are stored as RFC 3339 text, as in SQLite.
+-- ========================================
+
+CREATE TABLE IF NOT EXISTS judge_prompts (
+    version BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    name TEXT NOT NULL,
+    template TEXT NOT NULL,
+    description TEXT,
+    is_active BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TEXT NOT NULL DEFAULT (to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS'))
+);
+
+INSERT INTO judge_prompts (name, template, description, is_active)
+VALUES (
+    'Default Judge Prompt',
+    'You are an expert evaluator comparing two text outputs.
+
+EVALUATION CRITERIA:
+{{criteria}}
+
+EXPECTED OUTPUT:
+{{expected}}
+
+ACTUAL OUTPUT:
+{{actual}}
+
+INSTRUCTIONS:
+1. Carefully compare both outputs
+2. Consider semantic equivalence, not just exact wording
+3. Provide your verdict as the first line: "Verdict: PASS" or "Verdict: FAIL"
+4. Then explain your reasoning in 2-3 sentences
+
+Your evaluation:',
+    'Default prompt for LLM-as-a-judge evaluation',
+    TRUE
+);
+
+CREATE TABLE IF NOT EXISTS evaluations (
+    id TEXT PRIMARY KEY NOT NULL,
+    status TEXT,
+    model TEXT,
+    prompt TEXT,
+    model_output TEXT,
+    expected TEXT,
+    judge_model TEXT,
+    judge_verdict TEXT,
+    judge_reasoning TEXT,
+    error_message TEXT,
+    latency_ms BIGINT,
+    judge_latency_ms BIGINT,
+    input_tokens BIGINT,
+    output_tokens BIGINT,
+    judge_input_tokens BIGINT,
+    judge_output_tokens BIGINT,
+    created_at TEXT NOT NULL,
+    judge_prompt_version BIGINT,
+    judge_confidence DOUBLE PRECISION,
+    needs_review BOOLEAN NOT NULL DEFAULT FALSE,
+    human_verdict TEXT,
+    reviewed_at TEXT,
+    cost_usd DOUBLE PRECISION,
+    judge_cost_usd DOUBLE PRECISION,
+    batch_id TEXT,
+    config_hash TEXT,
+    judge_raw_response TEXT,
+    truncation_strategy TEXT,
+    truncation_original_tokens BIGINT,
+    truncation_truncated_tokens BIGINT,
+    judge_prompt_arm TEXT,
+    -- JSON array of the evaluation's tags
+    tags TEXT,
+    -- judge_abstained, unparseable, low_confidence or human_review
+    uncertain_reason TEXT,
+    -- Number of evals judged together in one batched judge call; NULL when judged on its own
+    judge_batch_size BIGINT,
+    -- stop, length, content_filter, tool_calls, or a provider value passed through verbatim
+    finish_reason TEXT,
+    -- JSON array of trace events, capped in size; NULL unless the eval was traced and PERSIST_TRACES is on
+    trace TEXT,
+    -- NULL unless the evaluation failed and was classified, by a model, the judge or a human
+    failure_category TEXT,
+    failure_justification TEXT,
+    -- Model that classified the failure, 'judge' or 'human'
+    failure_classifier TEXT,
+    failure_classification_cost_usd DOUBLE PRECISION
+);
+
+CREATE INDEX IF NOT EXISTS idx_evaluations_created_at ON evaluations(created_at);
+CREATE INDEX IF NOT EXISTS idx_evaluations_status ON evaluations(status);
+CREATE INDEX IF NOT EXISTS idx_evaluations_model ON evaluations(model);
+CREATE INDEX IF NOT EXISTS idx_evaluations_judge_prompt_version ON evaluations(judge_prompt_version);
+CREATE INDEX IF NOT EXISTS idx_evaluations_needs_review ON evaluations(needs_review);
+CREATE INDEX IF NOT EXISTS idx_evaluations_batch_id ON evaluations(batch_id);
+CREATE INDEX IF NOT EXISTS idx_evaluations_uncertain_reason ON evaluations(uncertain_reason);
+CREATE INDEX IF NOT EXISTS idx_evaluations_failure_category ON evaluations(failure_category);
+
+CREATE TABLE IF NOT EXISTS suites (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL UNIQUE,
+    description TEXT,
+    configs TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS suite_runs (
+    id TEXT PRIMARY KEY NOT NULL,
+    suite_id TEXT NOT NULL REFERENCES suites(id) ON DELETE CASCADE,
+    batch_id TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS suite_run_evaluations (
+    suite_run_id TEXT NOT NULL REFERENCES suite_runs(id) ON DELETE CASCADE,
+    evaluation_id TEXT NOT NULL,
+    PRIMARY KEY (suite_run_id, evaluation_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_suite_runs_suite_id ON suite_runs(suite_id, created_at);
+
+CREATE TABLE IF NOT EXISTS prompt_versions (
+    version BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    name TEXT NOT NULL,
+    prompt_template TEXT NOT NULL,
+    description TEXT,
+    tags TEXT,
+    metadata TEXT,
+    is_active BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TEXT NOT NULL,
+    created_by TEXT
+);
+
+CREATE TABLE IF NOT EXISTS prompt_evaluations (
+    evaluation_id TEXT NOT NULL,
+    prompt_version BIGINT NOT NULL REFERENCES prompt_versions(version),
+    PRIMARY KEY (evaluation_id, prompt_version)
+);
+
+CREATE INDEX IF NOT EXISTS idx_prompt_evaluations_version ON prompt_evaluations(prompt_version);
+
+CREATE TABLE IF NOT EXISTS batches (
+    batch_id TEXT PRIMARY KEY NOT NULL,
+    created_at TEXT NOT NULL,
+    total BIGINT NOT NULL,
+    passed BIGINT NOT NULL DEFAULT 0,
+    failed BIGINT NOT NULL DEFAULT 0,
+    status TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_batches_created_at ON batches(created_at);
+
+CREATE TABLE IF NOT EXISTS datasets (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    format TEXT NOT NULL,
+    row_count BIGINT NOT NULL,
+    columns TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS dataset_rows (
+    dataset_id TEXT NOT NULL REFERENCES datasets(id) ON DELETE CASCADE,
+    row_index BIGINT NOT NULL,
+    data TEXT NOT NULL,
+    PRIMARY KEY (dataset_id, row_index)
+);
+
+CREATE TABLE IF NOT EXISTS provider_errors (
+    id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    provider TEXT NOT NULL,
+    model TEXT NOT NULL,
+    status_code BIGINT,
+    error_class TEXT NOT NULL,
+    body TEXT NOT NULL,
+    attempt BIGINT NOT NULL,
+    recovered BOOLEAN NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_provider_errors_created_at ON provider_errors(created_at);
+CREATE INDEX IF NOT EXISTS idx_provider_errors_provider ON provider_errors(provider);
+
+CREATE TABLE IF NOT EXISTS judge_prompt_canaries (
+    id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    candidate_version BIGINT NOT NULL REFERENCES judge_prompts(version),
+    percentage DOUBLE PRECISION NOT NULL,
+    started_at TEXT NOT NULL,
+    stopped_at TEXT
+);
+
+CREATE TABLE IF NOT EXISTS judge_ab_tests (
+    id TEXT PRIMARY KEY,
+    version_a BIGINT NOT NULL REFERENCES judge_prompts(version),
+    version_b BIGINT NOT NULL REFERENCES judge_prompts(version),
+    -- NULL when each evaluation was re-judged with the judge model it was originally judged by
+    judge_model TEXT,
+    created_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS judge_ab_results (
+    id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    test_id TEXT NOT NULL REFERENCES judge_ab_tests(id),
+    evaluation_id TEXT NOT NULL REFERENCES evaluations(id),
+    version BIGINT NOT NULL,
+    judge_model TEXT NOT NULL,
+    verdict TEXT,
+    confidence DOUBLE PRECISION,
+    reasoning TEXT,
+    latency_ms BIGINT,
+    cost_usd DOUBLE PRECISION,
+    error_message TEXT,
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_judge_ab_results_test ON judge_ab_results(test_id);
+
+CREATE TABLE IF NOT EXISTS api_keys (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    role TEXT NOT NULL,
+    key_hash TEXT NOT NULL UNIQUE,
+    -- First characters of the key, so a key can be recognized in listings
+    key_prefix TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    revoked_at TEXT
+);
+
+CREATE TABLE IF NOT EXISTS judgements (
+    id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    evaluation_id TEXT NOT NULL REFERENCES evaluations(id),
+    status TEXT,
+    judge_model TEXT,
+    judge_prompt_version BIGINT,
+    judge_verdict TEXT,
+    judge_confidence DOUBLE PRECISION,
+    judge_reasoning TEXT,
+    judge_raw_response TEXT,
+    uncertain_reason TEXT,
+    judge_latency_ms BIGINT,
+    judge_input_tokens BIGINT,
+    judge_output_tokens BIGINT,
+    judge_cost_usd DOUBLE PRECISION,
+    -- When the re-judge replaced this verdict
+    replaced_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_judgements_evaluation ON judgements(evaluation_id);
+
+CREATE TABLE IF NOT EXISTS robustness_groups (
+    id TEXT PRIMARY KEY NOT NULL,
+    batch_id TEXT NOT NULL,
+    model TEXT NOT NULL,
+    base_prompt TEXT NOT NULL,
+    expected TEXT,
+    -- NULL when the variants were supplied by the user
+    paraphrase_model TEXT,
+    paraphrase_input_tokens BIGINT,
+    paraphrase_output_tokens BIGINT,
+    paraphrase_cost_usd DOUBLE PRECISION,
+    created_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS robustness_variants (
+    group_id TEXT NOT NULL REFERENCES robustness_groups(id) ON DELETE CASCADE,
+    -- 0 is the base prompt
+    variant_index BIGINT NOT NULL,
+    prompt TEXT NOT NULL,
+    -- base, paraphrase or user
+    source TEXT NOT NULL,
+    evaluation_id TEXT NOT NULL,
+    PRIMARY KEY (group_id, variant_index)
+);
+
+-- The editable taxonomy; seeded with the built-in categories on startup when empty
+CREATE TABLE IF NOT EXISTS failure_categories (
+    name TEXT PRIMARY KEY NOT NULL,
+    description TEXT NOT NULL,
+    position BIGINT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS shares (
+    id TEXT PRIMARY KEY,
+    evaluation_id TEXT NOT NULL,
+    -- Name of the API key that created the share
+    created_by TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    expires_at TEXT NOT NULL,
+    revoked_at TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_shares_evaluation_id ON shares(evaluation_id);
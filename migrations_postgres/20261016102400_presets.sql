@@ -0,0 +1,24 @@
+-- ========================================
+-- 20261016102400_presets.sql
+-- Named defaults for run and batch requests, owned by an API key or shared
+-- ========================================
+
+CREATE TABLE IF NOT EXISTS presets (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    -- API key the preset belongs to; NULL for presets shared by every caller
+    owner_key_id TEXT,
+    description TEXT,
+    -- JSON object of eval config fields
+    defaults TEXT NOT NULL,
+    -- Name of the API key that created the preset
+    created_by TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+-- One preset per name and owner, and one shared preset per name
+CREATE UNIQUE INDEX IF NOT EXISTS idx_presets_owner_name ON presets(COALESCE(owner_key_id, ''), name);
+
+-- JSON record of the preset an evaluation's request was merged with
+ALTER TABLE evaluations ADD COLUMN preset TEXT;
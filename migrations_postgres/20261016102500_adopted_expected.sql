@@ -0,0 +1,15 @@
+-- ========================================
+-- 20261016102500_adopted_expected.sql
+-- Expected outputs adopted from reviewed model outputs, and the dataset row of each evaluation
+-- ========================================
+
+-- JSON record of the model output a row's expected value was adopted from; NULL when a
+-- person wrote it
+ALTER TABLE dataset_rows ADD COLUMN expected_source TEXT;
+
+ALTER TABLE evaluations ADD COLUMN dataset_id TEXT;
+ALTER TABLE evaluations ADD COLUMN dataset_row_index BIGINT;
+-- Copy of the row's expected_source when the evaluation's expected output came from it
+ALTER TABLE evaluations ADD COLUMN expected_source TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_evaluations_dataset_id ON evaluations(dataset_id);
@@ -0,0 +1,17 @@
+-- ========================================
+-- 20261016102600_response_cache.sql
+-- Model responses reused by evals that opt into caching
+-- ========================================
+
+CREATE TABLE IF NOT EXISTS response_cache (
+    -- SHA-256 of the provider, model and prompt; see runner::response_cache_key
+    key TEXT PRIMARY KEY,
+    model TEXT NOT NULL,
+    output TEXT NOT NULL,
+    -- Latency of the call that produced the response
+    latency_ms BIGINT NOT NULL,
+    input_tokens BIGINT,
+    output_tokens BIGINT,
+    finish_reason TEXT,
+    created_at TEXT NOT NULL
+);
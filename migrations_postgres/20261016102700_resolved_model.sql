@@ -0,0 +1,7 @@
+-- ========================================
+-- 20261016102700_resolved_model.sql
+-- The model a routing provider such as OpenRouter actually answered with
+-- ========================================
+
+ALTER TABLE evaluations ADD COLUMN resolved_model TEXT;
+ALTER TABLE response_cache ADD COLUMN resolved_model TEXT;
@@ -0,0 +1,6 @@
+-- ========================================
+-- 20261016102800_response_format.sql
+-- The response format (json or json_schema) the model was asked for
+-- ========================================
+
+ALTER TABLE evaluations ADD COLUMN response_format TEXT;
@@ -0,0 +1,7 @@
+-- ========================================
+-- 20261016102900_reasoning.sql
+-- Model reasoning, stored apart from the answer
+-- ========================================
+
+ALTER TABLE evaluations ADD COLUMN reasoning TEXT;
+ALTER TABLE response_cache ADD COLUMN reasoning TEXT;
@@ -0,0 +1,6 @@
+-- ========================================
+-- 20261016103000_images.sql
+-- Images sent with the prompt: URLs, and inline images by hash and size
+-- ========================================
+
+ALTER TABLE evaluations ADD COLUMN images TEXT;
@@ -0,0 +1,7 @@
+-- ========================================
+-- 20261016103100_judge_prompt_rendered.sql
+-- The judge prompt as rendered and sent, for evaluations and for verdicts a re-judge replaced
+-- ========================================
+
+ALTER TABLE evaluations ADD COLUMN judge_prompt_rendered TEXT;
+ALTER TABLE judgements ADD COLUMN judge_prompt_rendered TEXT;
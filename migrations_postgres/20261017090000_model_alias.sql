@@ -0,0 +1,6 @@
+-- ========================================
+-- 20261017090000_model_alias.sql
+-- The alias an eval config named its model by; `model` holds the provider:model it resolved to
+-- ========================================
+
+ALTER TABLE evaluations ADD COLUMN model_alias TEXT;
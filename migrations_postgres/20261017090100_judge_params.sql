@@ -0,0 +1,7 @@
+-- ========================================
+-- 20261017090100_judge_params.sql
+-- The judge's temperature, max tokens, seed and Uncertain retry as JSON, for evaluations and replaced verdicts
+-- ========================================
+
+ALTER TABLE evaluations ADD COLUMN judge_params TEXT;
+ALTER TABLE judgements ADD COLUMN judge_params TEXT;
@@ -0,0 +1,6 @@
+-- ========================================
+-- 20261017090200_judge_error.sql
+-- Why the judge call of an evaluation failed, as JSON
+-- ========================================
+
+ALTER TABLE evaluations ADD COLUMN judge_error TEXT;
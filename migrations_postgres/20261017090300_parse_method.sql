@@ -0,0 +1,6 @@
+-- ========================================
+-- 20261017090300_parse_method.sql
+-- Which extractor produced the parsed output of an evaluation
+-- ========================================
+
+ALTER TABLE evaluations ADD COLUMN parse_method TEXT;
@@ -0,0 +1,6 @@
+-- ========================================
+-- 20261017090600_wait_ms.sql
+-- Time a model call spent on rate limits and retries beyond the provider's latency
+-- ========================================
+
+ALTER TABLE evaluations ADD COLUMN wait_ms BIGINT;
@@ -0,0 +1,6 @@
+-- ========================================
+-- 20261017090800_provider_override.sql
+-- Providers whose credentials a request overrode, and the API key that supplied them
+-- ========================================
+
+ALTER TABLE evaluations ADD COLUMN provider_override TEXT;
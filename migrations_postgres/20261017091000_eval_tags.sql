@@ -0,0 +1,20 @@
+-- ========================================
+-- 20261017091000_eval_tags.sql
+-- One row per tag of an evaluation, so tags can be counted and filtered with joins;
+-- evaluations.tags keeps the JSON array the entry is read with
+-- ========================================
+
+CREATE TABLE IF NOT EXISTS eval_tags (
+    evaluation_id TEXT NOT NULL REFERENCES evaluations(id),
+    tag TEXT NOT NULL,
+    -- When the tag was saved with the evaluation or added to it
+    tagged_at TEXT NOT NULL,
+    PRIMARY KEY (evaluation_id, tag)
+);
+
+CREATE INDEX IF NOT EXISTS idx_eval_tags_tag ON eval_tags(tag);
+
+INSERT INTO eval_tags (evaluation_id, tag, tagged_at)
+SELECT DISTINCT evaluations.id, json_each.value, evaluations.created_at
+FROM evaluations, json_array_elements_text(CAST(evaluations.tags AS json)) AS json_each(value)
+WHERE evaluations.tags IS NOT NULL;
@@ -0,0 +1,8 @@
+-- ========================================
+-- 20261017091100_triage_index.sql
+-- The triage queue: unreviewed evaluations with an Uncertain verdict or a failed judge call,
+-- indexed alone so its count stays cheap to poll
+-- ========================================
+
+CREATE INDEX IF NOT EXISTS idx_evaluations_triage ON evaluations(created_at)
+WHERE human_verdict IS NULL AND (judge_verdict = 'Uncertain' OR status = 'judge_error');
@@ -0,0 +1,26 @@
+-- ========================================
+-- 20261017091200_raw_exchanges.sql
+-- Provider requests and responses of evals run with raw capture on, kept for debugging;
+-- saved before the evaluation, so evaluation_id has no foreign key
+-- ========================================
+
+CREATE TABLE IF NOT EXISTS raw_exchanges (
+    evaluation_id TEXT NOT NULL,
+    -- Order of the call within the eval: model call, retries, then the judge
+    seq INTEGER NOT NULL,
+    provider TEXT NOT NULL,
+    model TEXT NOT NULL,
+    method TEXT NOT NULL,
+    url TEXT NOT NULL,
+    request_body TEXT,
+    request_truncated BOOLEAN NOT NULL DEFAULT FALSE,
+    status INTEGER NOT NULL,
+    -- JSON object of the response headers kept
+    response_headers TEXT NOT NULL,
+    response_body TEXT NOT NULL,
+    response_truncated BOOLEAN NOT NULL DEFAULT FALSE,
+    captured_at TEXT NOT NULL,
+    PRIMARY KEY (evaluation_id, seq)
+);
+
+CREATE INDEX IF NOT EXISTS idx_raw_exchanges_captured_at ON raw_exchanges(captured_at);
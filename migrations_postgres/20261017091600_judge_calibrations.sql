@@ -0,0 +1,25 @@
+-- ========================================
+-- 20261017091600_judge_calibrations.sql
+-- Calibrations of a judge prompt version against human verdicts, with position and
+-- verbosity bias probes
+-- ========================================
+
+CREATE TABLE IF NOT EXISTS judge_calibrations (
+    id TEXT PRIMARY KEY,
+    version BIGINT NOT NULL REFERENCES judge_prompts(version),
+    -- NULL when each evaluation was judged with the judge model it was originally judged by
+    judge_model TEXT,
+    evaluations BIGINT NOT NULL,
+    judged BIGINT NOT NULL,
+    accuracy DOUBLE PRECISION,
+    false_pass_rate DOUBLE PRECISION,
+    false_fail_rate DOUBLE PRECISION,
+    position_flip_rate DOUBLE PRECISION,
+    length_pass_correlation DOUBLE PRECISION,
+    -- JSON CalibrationSummary and array of CalibrationSample
+    summary TEXT NOT NULL,
+    samples TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_judge_calibrations_version ON judge_calibrations(version);
@@ -0,0 +1,6 @@
+-- ========================================
+-- 20261017091700_prompt_template.sql
+-- The prompt template an eval's prompt was rendered from, so explain can re-render it
+-- ========================================
+
+ALTER TABLE evaluations ADD COLUMN prompt_template TEXT;
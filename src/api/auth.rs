@@ -0,0 +1,481 @@
+// src/api/auth.rs
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::header,
+    middleware::Next,
+    web, Error, HttpMessage, HttpResponse, ResponseError,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::api::routes;
+use crate::api::AppState;
+use crate::database;
+use crate::errors::ApiError;
+
+/// Header carrying the API key, as an alternative to `Authorization: Bearer <key>`.
+pub const API_KEY_HEADER: &str = "X-API-Key";
+
+/// Prefix of generated keys, so they are recognizable in configs and logs.
+const KEY_PREFIX: &str = "ek_";
+
+/// What a key may do. Each role includes everything the roles before it may do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Read-only endpoints
+    Viewer,
+    /// Submitting evals, batches and reviews
+    Runner,
+    /// Judge prompts, deletions, configuration, imports and key management
+    Admin,
+}
+
+impl Role {
+    pub const ALL: [Role; 3] = [Role::Viewer, Role::Runner, Role::Admin];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Runner => "runner",
+            Role::Admin => "admin",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|role| role.as_str() == s)
+    }
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Who made a request; stored in the request extensions by `authorize`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Caller {
+    /// `None` for the `ADMIN_API_KEY` key and when authentication is off.
+    pub key_id: Option<String>,
+    pub name: String,
+    pub role: Role,
+    pub auth_enabled: bool,
+    /// Listed in `AGGREGATE_ONLY_KEYS`: limited to routes of `routes::AGGREGATE_ROUTES`.
+    pub aggregate_only: bool,
+}
+
+/// Generates a new random API key.
+pub fn generate_key() -> String {
+    format!("{}{}{}", KEY_PREFIX, uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple())
+}
+
+/// The hash a key is stored and looked up by.
+pub fn hash_key(key: &str) -> String {
+    format!("{:x}", Sha256::digest(key.as_bytes()))
+}
+
+/// Whether `a` equals `b`, in time that does not depend on where they differ.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// The key sent with a request, from `Authorization: Bearer` or `X-API-Key`.
+fn request_key(req: &ServiceRequest) -> Option<String> {
+    let headers = req.headers();
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .or_else(|| headers.get(API_KEY_HEADER).and_then(|v| v.to_str().ok()))
+        .map(|key| key.trim().to_string())
+        .filter(|key| !key.is_empty())
+}
+
+/// Resolves the caller of a request, or the error to answer instead.
+async fn authenticate(req: &ServiceRequest, state: &AppState) -> Result<Caller, ApiError> {
+    let Some(admin_key) = state.config.admin_api_key.as_deref() else {
+        return Ok(Caller {
+            key_id: None,
+            name: "anonymous".to_string(),
+            role: Role::Admin,
+            auth_enabled: false,
+            aggregate_only: false,
+        });
+    };
+
+    let Some(key) = request_key(req) else {
+        return Err(ApiError::unauthorized(format!(
+            "Missing API key; send it as 'Authorization: Bearer <key>' or '{}'",
+            API_KEY_HEADER
+        )));
+    };
+    // Compared by hash and in constant time, so response timing reveals nothing of the admin key
+    let key_hash = hash_key(&key);
+    if constant_time_eq(key_hash.as_bytes(), hash_key(admin_key).as_bytes()) {
+        return Ok(Caller {
+            key_id: None,
+            name: "ADMIN_API_KEY".to_string(),
+            role: Role::Admin,
+            auth_enabled: true,
+            aggregate_only: false,
+        });
+    }
+
+    let pool = state.pool()?;
+    match database::find_api_key(pool, &key_hash).await {
+        Ok(Some(api_key)) => match Role::parse(&api_key.role) {
+            Some(role) => {
+                let aggregate_only = state.config.aggregate_only_keys.iter().any(|k| *k == api_key.name || *k == api_key.id);
+                Ok(Caller { key_id: Some(api_key.id), name: api_key.name, role, auth_enabled: true, aggregate_only })
+            }
+            None => {
+                tracing::error!("API key {} has unknown role '{}'", api_key.id, api_key.role);
+                Err(ApiError::forbidden("API key has an unknown role"))
+            }
+        },
+        Ok(None) => Err(ApiError::unauthorized("Invalid or revoked API key")),
+        Err(e) => Err(ApiError::database("Failed to look up API key", e)),
+    }
+}
+
+/// Middleware checking every `/api/v1` request against the role its route requires
+/// (see `routes::required_role`). Answers 401 without a valid key and 403 naming the
+/// missing role, or 403 when an aggregate-only key calls a row-level route (see
+/// `routes::data_sensitivity`). Everything else, such as the dashboard, passes through.
+pub async fn authorize<B: MessageBody>(
+    req: ServiceRequest,
+    next: Next<B>,
+) -> Result<ServiceResponse<EitherBody<B>>, Error> {
+    let pattern = req.match_pattern().unwrap_or_else(|| req.path().to_string());
+    if !pattern.starts_with(routes::API_PREFIX) {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    }
+    let Some(required) = routes::required_role(req.method().as_str(), &pattern) else {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    };
+
+    let Some(state) = req.app_data::<web::Data<AppState>>().cloned() else {
+        return Ok(req.into_response(HttpResponse::InternalServerError().finish()).map_into_right_body());
+    };
+    let caller = match authenticate(&req, &state).await {
+        Ok(caller) => caller,
+        Err(error) => return Ok(req.into_response(error.error_response()).map_into_right_body()),
+    };
+
+    if caller.role < required {
+        let error = ApiError::forbidden(format!(
+            "This endpoint requires the '{}' role; your key has '{}'",
+            required, caller.role
+        ))
+        .with_details(serde_json::json!({ "required_role": required, "role": caller.role }));
+        return Ok(req.into_response(error.error_response()).map_into_right_body());
+    }
+    if caller.aggregate_only
+        && routes::data_sensitivity(req.method().as_str(), &pattern) == routes::DataSensitivity::RowLevel
+    {
+        let error = ApiError::forbidden("This key may only read aggregate statistics")
+            .with_details(serde_json::json!({ "aggregate_only": true }));
+        return Ok(req.into_response(error.error_response()).map_into_right_body());
+    }
+
+    req.extensions_mut().insert(caller);
+    next.call(req).await.map(ServiceResponse::map_into_left_body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{middleware::from_fn, test, App};
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+
+    const ADMIN_KEY: &str = "bootstrap-admin-key";
+
+    async fn state_with_keys() -> (AppState, Vec<(Role, String)>) {
+        let pool = database::test_pool().await;
+        let mut keys = vec![(Role::Admin, ADMIN_KEY.to_string())];
+        for role in [Role::Viewer, Role::Runner] {
+            let key = generate_key();
+            database::create_api_key(&pool, format!("{} key", role), role.as_str(), &hash_key(&key), &key[..8])
+                .await
+                .unwrap();
+            keys.push((role, key));
+        }
+        let state = AppState {
+            config: Arc::new(crate::config::AppConfig {
+                admin_api_key: Some(ADMIN_KEY.to_string()),
+                ..Default::default()
+            }),
+            client: reqwest::Client::new(),
+            db_pool: Some(Arc::new(pool)),
+            backup_in_progress: Arc::new(AtomicBool::new(false)),
+            active_batches: Default::default(),
+            retention: Default::default(),
+            rate_limiter: Default::default(),
+            provider_health: Default::default(),
+            progress: Default::default(),
+        };
+        (state, keys)
+    }
+
+    /// A concrete path for a route pattern, with every `{placeholder}` filled in.
+    fn concrete_path(pattern: &str) -> String {
+        pattern
+            .split('/')
+            .map(|segment| if segment.starts_with('{') { "x" } else { segment })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    #[actix_web::test]
+    async fn test_every_route_enforces_its_minimum_role() {
+        let (state, keys) = state_with_keys().await;
+        let key_for = |role: Role| keys.iter().find(|(r, _)| *r == role).unwrap().1.clone();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(crate::api::handlers::WsBroker::new()))
+                .wrap(from_fn(authorize))
+                .configure(routes::configure_routes),
+        )
+        .await;
+
+        for &(method, pattern, required) in routes::ROUTE_ROLES {
+            let method = actix_web::http::Method::from_bytes(method.as_bytes()).unwrap();
+            let path = concrete_path(pattern);
+
+            let anonymous = test::TestRequest::default().method(method.clone()).uri(&path).to_request();
+            let res = test::call_service(&app, anonymous).await;
+            assert_eq!(res.status(), 401, "{} {} without a key", method, pattern);
+
+            let below = Role::ALL.into_iter().rev().find(|role| *role < required);
+            if let Some(below) = below {
+                let req = test::TestRequest::default()
+                    .method(method.clone())
+                    .uri(&path)
+                    .insert_header((header::AUTHORIZATION, format!("Bearer {}", key_for(below))))
+                    .to_request();
+                let res = test::call_service(&app, req).await;
+                assert_eq!(res.status(), 403, "{} {} as {}", method, pattern, below);
+                let body: serde_json::Value = test::read_body_json(res).await;
+                assert_eq!(body["code"], "forbidden", "{} {}", method, pattern);
+                assert_eq!(body["details"]["required_role"], required.as_str(), "{} {}", method, pattern);
+            }
+        }
+
+        let viewer = key_for(Role::Viewer);
+        let req = test::TestRequest::get().uri("/api/v1/me").insert_header((API_KEY_HEADER, viewer.as_str())).to_request();
+        let me: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(me["role"], "viewer");
+        assert_eq!(me["auth_enabled"], true);
+
+        let req = test::TestRequest::get().uri("/api/v1/health").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 200);
+
+        let req = test::TestRequest::get()
+            .uri("/api/v1/me")
+            .insert_header((header::AUTHORIZATION, "Bearer not-a-key"))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 401);
+    }
+
+    const SECRET_TEXT: &str = "confidential-customer-text";
+
+    /// An aggregate-only viewer key, and a state holding an evaluation, a failure
+    /// classification and a provider error that all carry `SECRET_TEXT`.
+    async fn state_with_aggregate_only_key() -> (AppState, String) {
+        use crate::failure_taxonomy::FailureClassification;
+        use crate::models::{ApiResponse, EvalResult};
+        use crate::providers::middleware::ProviderErrorEvent;
+        use crate::runner::{self, JudgeResult, JudgeVerdict};
+
+        let (state, _) = state_with_keys().await;
+        let pool = state.db_pool.clone().unwrap();
+        let key = generate_key();
+        database::create_api_key(&pool, "dashboard".to_string(), Role::Viewer.as_str(), &hash_key(&key), &key[..8])
+            .await
+            .unwrap();
+
+        let result = runner::EvalResult {
+            id: "eval-1".to_string(),
+            model: "ollama:llama3".to_string(),
+            prompt: format!("prompt {}", SECRET_TEXT),
+            model_output: format!("output {}", SECRET_TEXT),
+            parsed_output: None,
+            parse_method: None,
+            output_diff: None,
+            expected: Some(format!("expected {}", SECRET_TEXT)),
+            judge_result: Some(JudgeResult {
+                judge_model: "ollama:llama3".to_string(),
+                verdict: JudgeVerdict::Fail,
+                reasoning: Some(format!("reasoning {}", SECRET_TEXT)),
+                confidence: Some(0.9),
+                raw_response: Some(format!("raw {}", SECRET_TEXT)),
+                uncertain_reason: None,
+            }),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            latency_ms: 10,
+            judge_latency_ms: Some(5),
+            token_usage: None,
+            judge_token_usage: None,
+            total_latency_ms: 15,
+            wait_ms: None,
+            cost_usd: None,
+            judge_cost_usd: None,
+            judge_prompt_version: Some(1),
+            prompt_version: None,
+            prompt_template: None,
+            judge_prompt_arm: None,
+            judge_batch_size: None,
+            early_abort: None,
+            finish_reason: None,
+            needs_review: false,
+            truncation: None,
+            tags: vec!["dashboards".to_string()],
+            criteria: None,
+            metadata: None,
+            trace: None,
+            failure_classification: Some(FailureClassification {
+                category: "factual_error".to_string(),
+                justification: Some(format!("justification {}", SECRET_TEXT)),
+                classifier: "judge".to_string(),
+                cost_usd: None,
+            }),
+            dataset_row: None,
+            chain_step: None,
+            provider_override: None,
+            full_output_chars: None,
+            cache_hit: false,
+            resolved_model: None,
+            response_format: None,
+            reasoning: None,
+            images: Vec::new(),
+            judge_prompt_rendered: Some(format!("judge prompt {}", SECRET_TEXT)),
+            model_alias: None,
+            judge_params: None,
+            judge_error: None,
+            self_consistency: None,
+        };
+        let response = ApiResponse {
+            id: "eval-1".to_string(),
+            status: runner::eval_status(&result).to_string(),
+            result: EvalResult::Success(result),
+            batch_id: None,
+            config_hash: None,
+            preset: None,
+        };
+        database::save_evaluation(&pool, &response).await.unwrap();
+        database::save_provider_error(&pool, &ProviderErrorEvent {
+            provider: "ollama".to_string(),
+            model: "llama3".to_string(),
+            status_code: Some(500),
+            error_class: "server_error",
+            body: format!("body {}", SECRET_TEXT),
+            attempt: 0,
+            recovered: false,
+        })
+        .await
+        .unwrap();
+
+        let config = crate::config::AppConfig {
+            aggregate_only_keys: vec!["dashboard".to_string()],
+            ..(*state.config).clone()
+        };
+        (AppState { config: Arc::new(config), ..state }, key)
+    }
+
+    #[actix_web::test]
+    async fn test_aggregate_only_keys_are_limited_to_aggregate_routes() {
+        let (state, key) = state_with_aggregate_only_key().await;
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(crate::api::handlers::WsBroker::new()))
+                .wrap(from_fn(authorize))
+                .configure(routes::configure_routes),
+        )
+        .await;
+
+        for &(method, pattern, required) in routes::ROUTE_ROLES {
+            if required > Role::Viewer {
+                continue;
+            }
+            let req = test::TestRequest::default()
+                .method(actix_web::http::Method::from_bytes(method.as_bytes()).unwrap())
+                .uri(&concrete_path(pattern))
+                .insert_header((API_KEY_HEADER, key.as_str()))
+                .to_request();
+            let res = test::call_service(&app, req).await;
+            match routes::data_sensitivity(method, pattern) {
+                routes::DataSensitivity::Aggregate => {
+                    assert_ne!(res.status(), 403, "{} {} should be open to aggregate-only keys", method, pattern);
+                }
+                routes::DataSensitivity::RowLevel => {
+                    assert_eq!(res.status(), 403, "{} {} should be closed to aggregate-only keys", method, pattern);
+                    let body: serde_json::Value = test::read_body_json(res).await;
+                    assert_eq!(body["details"]["aggregate_only"], true, "{} {}", method, pattern);
+                }
+            }
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_no_evaluation_text_leaks_through_aggregate_routes() {
+        let (state, key) = state_with_aggregate_only_key().await;
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(crate::api::handlers::WsBroker::new()))
+                .wrap(from_fn(authorize))
+                .configure(routes::configure_routes),
+        )
+        .await;
+
+        // The evaluation shows up where the key may not look...
+        let req = test::TestRequest::get().uri("/api/v1/evals/history").insert_header((API_KEY_HEADER, ADMIN_KEY)).to_request();
+        let history = test::call_service(&app, req).await;
+        assert!(String::from_utf8_lossy(&test::read_body(history).await).contains(SECRET_TEXT));
+
+        // ...but never where it may; the judge prompt routes need versions that exist
+        let req = test::TestRequest::post()
+            .uri("/api/v1/judge-prompts")
+            .insert_header((API_KEY_HEADER, ADMIN_KEY))
+            .set_json(serde_json::json!({ "name": "lenient", "template": "{{expected}} {{actual}}" }))
+            .to_request();
+        assert!(test::call_service(&app, req).await.status().is_success());
+        for &(method, pattern) in routes::AGGREGATE_ROUTES {
+            let uri = match pattern {
+                "/api/v1/judge-prompts/compare" => format!("{}?a=1&b=2", pattern),
+                _ => pattern.replace("{version}", "1"),
+            };
+            let req = test::TestRequest::default()
+                .method(actix_web::http::Method::from_bytes(method.as_bytes()).unwrap())
+                .uri(&uri)
+                .insert_header((API_KEY_HEADER, key.as_str()))
+                .to_request();
+            let res = test::call_service(&app, req).await;
+            assert_eq!(res.status(), 200, "{} {}", method, pattern);
+            let body = String::from_utf8_lossy(&test::read_body(res).await).into_owned();
+            assert!(!body.contains(SECRET_TEXT), "{} {} leaked evaluation text: {}", method, pattern, body);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"key", b"key"));
+        assert!(!constant_time_eq(b"key", b"kez"));
+        assert!(!constant_time_eq(b"key", b"keys"));
+    }
+
+    #[actix_web::test]
+    async fn test_unlisted_routes_require_admin() {
+        assert_eq!(routes::required_role("GET", "/api/v1/health"), None);
+        assert_eq!(routes::required_role("GET", "/api/v1/health/live"), None);
+        assert_eq!(routes::required_role("GET", "/api/v1/evals/history"), Some(Role::Viewer));
+        assert_eq!(routes::required_role("POST", "/api/v1/evals/run"), Some(Role::Runner));
+        assert_eq!(routes::required_role("DELETE", "/api/v1/evals/history"), Some(Role::Admin));
+        assert_eq!(routes::required_role("GET", "/api/v1/not-a-route"), Some(Role::Admin));
+    }
+}
@@ -0,0 +1,53 @@
+// src/api/etag.rs
+//! Conditional GETs of responses computed from the stored evaluations. Their ETag is
+//! `database::evaluations_etag`, so a dashboard polling an unchanged database gets `304 Not
+//! Modified` without the query behind the response being run.
+use actix_web::http::header::{self, CacheControl, CacheDirective, EntityTag, IfNoneMatch};
+use actix_web::{HttpMessage, HttpRequest, HttpResponse};
+use std::future::Future;
+
+use crate::database::{self, DbPool};
+use crate::errors::ApiError;
+
+/// Answers `304` when the `If-None-Match` of `http_req` holds the current tag of the
+/// evaluations, and otherwise the response of `respond`, tagged. Should reading the tag fail,
+/// the response is answered untagged.
+pub async fn conditional<F, Fut>(pool: &DbPool, http_req: &HttpRequest, respond: F) -> Result<HttpResponse, ApiError>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<HttpResponse, ApiError>>,
+{
+    let tag = match database::evaluations_etag(pool).await {
+        // Weak, as the compressed and plain bodies differ byte for byte
+        Ok(tag) => EntityTag::new_weak(tag),
+        Err(e) => {
+            tracing::warn!("⚠️  Failed to read the ETag of the evaluations: {}", e);
+            return respond().await;
+        }
+    };
+    let unchanged = match http_req.get_header::<IfNoneMatch>() {
+        Some(IfNoneMatch::Any) => true,
+        Some(IfNoneMatch::Items(tags)) => tags.iter().any(|held| held.weak_eq(&tag)),
+        None => false,
+    };
+    if unchanged {
+        return Ok(HttpResponse::NotModified().insert_header(header::ETag(tag)).insert_header(revalidate()).finish());
+    }
+
+    let mut response = respond().await?;
+    if response.status().is_success() {
+        let headers = response.headers_mut();
+        if let Ok(value) = tag.to_string().parse() {
+            headers.insert(header::ETAG, value);
+        }
+        if let Ok(value) = revalidate().to_string().parse() {
+            headers.insert(header::CACHE_CONTROL, value);
+        }
+    }
+    Ok(response)
+}
+
+/// Clients may keep the response but must check the tag before reusing it.
+fn revalidate() -> CacheControl {
+    CacheControl(vec![CacheDirective::NoCache])
+}
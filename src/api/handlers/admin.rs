@@ -0,0 +1,94 @@
+// src/api/handlers/admin.rs
+use actix_files::NamedFile;
+use actix_web::http::StatusCode;
+use actix_web::http::header::{ContentDisposition, DispositionParam, DispositionType};
+use actix_web::{web, HttpRequest, HttpResponse};
+use serde::Deserialize;
+use std::sync::atomic::Ordering;
+use crate::api::AppState;
+use crate::backup;
+use crate::database;
+use crate::errors::ApiError;
+
+#[derive(Deserialize)]
+pub struct BackupQuery {
+    /// Stream the snapshot back as the response body instead of returning its metadata.
+    #[serde(default)]
+    pub download: bool,
+}
+
+/// Clears the in-progress flag when the backup finishes, however it finishes.
+struct BackupGuard<'a>(&'a std::sync::atomic::AtomicBool);
+
+impl Drop for BackupGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+/// POST /api/v1/admin/backup - Write a consistent snapshot of the database
+pub async fn create_backup(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    query: web::Query<BackupQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let pool = state.pool()?;
+
+    if state.backup_in_progress.swap(true, Ordering::SeqCst) {
+        return Err(ApiError::conflict("A backup is already in progress"));
+    }
+    let _guard = BackupGuard(&state.backup_in_progress);
+
+    let result = backup::create_backup(pool, &state.config.backup_dir, state.config.backup_retention)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database backup failed: {}", e);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "backup_failed", format!("Database backup failed: {}", e))
+        })?;
+    if !query.download {
+        return Ok(HttpResponse::Created().json(result));
+    }
+    let file = NamedFile::open(&result.backup.path)
+        .map_err(|e| ApiError::internal("Failed to open the backup", e))?
+        .set_content_disposition(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(result.backup.file_name.clone())],
+        });
+    Ok(file.into_response(&req))
+}
+
+/// GET /api/v1/admin/backups - List existing database snapshots
+pub async fn list_backups(
+    state: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let backups = backup::list_backups(&state.config.backup_dir)
+        .map_err(|e| ApiError::internal("Failed to list backups", e))?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "directory": state.config.backup_dir,
+        "retention": state.config.backup_retention,
+        "backups": backups
+    })))
+}
+
+/// GET /api/v1/admin/retention - Report the retention policy and what its last runs purged
+pub async fn get_retention(
+    state: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let status = state.retention.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone();
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "active_batches": state.active_batches.load(Ordering::SeqCst),
+        "retention": status
+    })))
+}
+
+/// DELETE /api/v1/cache - Drop every cached model and judge response
+pub async fn clear_cache(
+    state: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let pool = state.pool()?;
+    let deleted = database::clear_response_cache(pool)
+        .await
+        .map_err(|e| ApiError::database("Failed to clear the response cache", e))?;
+    tracing::info!("🧹 Cleared {} cached responses", deleted);
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "deleted": deleted })))
+}
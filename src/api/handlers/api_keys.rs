@@ -0,0 +1,78 @@
+// src/api/handlers/api_keys.rs
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse};
+use serde::{Deserialize, Serialize};
+use crate::api::auth::{self, Caller, Role};
+use crate::api::AppState;
+use crate::database;
+use crate::errors::ApiError;
+
+/// Characters of a new key kept in plain text, so it can be recognized in listings.
+const KEY_PREFIX_LEN: usize = 8;
+
+#[derive(Deserialize)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    /// `viewer`, `runner` or `admin`
+    pub role: Role,
+}
+
+#[derive(Serialize)]
+pub struct CreateApiKeyResponse {
+    pub api_key: database::ApiKey,
+    /// The key itself; it is shown only once.
+    pub key: String,
+}
+
+/// GET /api/v1/me - The caller's key name and role
+pub async fn get_me(req: HttpRequest) -> Result<HttpResponse, ApiError> {
+    match req.extensions().get::<Caller>() {
+        Some(caller) => Ok(HttpResponse::Ok().json(caller)),
+        None => Err(ApiError::unauthorized("Request was not authenticated")),
+    }
+}
+
+/// GET /api/v1/api-keys - List API keys, newest first; secrets are never returned
+pub async fn get_api_keys(
+    state: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let pool = state.pool()?;
+    let api_keys = database::get_api_keys(pool)
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch API keys", e))?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "api_keys": api_keys })))
+}
+
+/// POST /api/v1/api-keys - Create a key with a role
+pub async fn create_api_key(
+    state: web::Data<AppState>,
+    req: web::Json<CreateApiKeyRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let req = req.into_inner();
+    if req.name.trim().is_empty() {
+        return Err(ApiError::validation("name must not be empty"));
+    }
+
+    let pool = state.pool()?;
+    let key = auth::generate_key();
+    let api_key = database::create_api_key(pool, req.name, req.role.as_str(), &auth::hash_key(&key), &key[..KEY_PREFIX_LEN])
+        .await
+        .map_err(|e| ApiError::database("Failed to create API key", e))?;
+    tracing::info!("🔑 Created {} API key '{}'", api_key.role, api_key.name);
+    Ok(HttpResponse::Created().json(CreateApiKeyResponse { api_key, key }))
+}
+
+/// DELETE /api/v1/api-keys/{id} - Revoke a key
+pub async fn revoke_api_key(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+
+    let pool = state.pool()?;
+    let api_key = database::revoke_api_key(pool, &id).await.map_err(|e| match e {
+        sqlx::Error::RowNotFound => ApiError::not_found(format!("Active API key '{}' not found", id)),
+        e => ApiError::database("Failed to revoke API key", e),
+    })?;
+    tracing::info!("🔑 Revoked API key '{}'", api_key.name);
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "api_key": api_key })))
+}
@@ -0,0 +1,37 @@
+// src/api/handlers/compare.rs
+use actix_web::{web, HttpResponse};
+use serde::Deserialize;
+use crate::api::AppState;
+use crate::{compare, database};
+use crate::errors::ApiError;
+
+#[derive(Deserialize)]
+pub struct CompareQuery {
+    /// Batch id or suite run id of the baseline run
+    pub run_a: String,
+    /// Batch id or suite run id of the run to compare against the baseline
+    pub run_b: String,
+}
+
+/// GET /api/v1/compare - Compare two batch or suite runs eval by eval
+pub async fn compare_runs(
+    state: web::Data<AppState>,
+    query: web::Query<CompareQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let pool = state.pool()?;
+
+    let mut runs = Vec::with_capacity(2);
+    for run_id in [&query.run_a, &query.run_b] {
+        let evaluations = match database::resolve_batch_id(pool, run_id).await {
+            Ok(batch_id) => database::get_batch_evaluations(pool, &batch_id).await,
+            Err(e) => Err(e),
+        };
+        let evaluations = evaluations.map_err(|e| ApiError::database(format!("Failed to load run '{}'", run_id), e))?;
+        if evaluations.is_empty() {
+            return Err(ApiError::not_found(format!("No evaluations found for run '{}'", run_id)));
+        }
+        runs.push(evaluations);
+    }
+
+    Ok(HttpResponse::Ok().json(compare::compare_runs(&query.run_a, &runs[0], &query.run_b, &runs[1])))
+}
@@ -0,0 +1,296 @@
+// src/api/handlers/datasets.rs
+use actix_web::{web, HttpRequest, HttpResponse};
+use serde::{Deserialize, Serialize};
+use crate::api::AppState;
+use crate::api::handlers::evals::{execute_batch, BatchEvalResponse};
+use crate::api::handlers::ws::WsBroker;
+use crate::config::EvalConfig;
+use crate::dataset::{self, DatasetFormat, RowError, StoredRow};
+use crate::database;
+use crate::errors::ApiError;
+use crate::runner;
+
+/// Rows returned by a dataset preview when no limit is given.
+const DEFAULT_PREVIEW_ROWS: i64 = 10;
+
+#[derive(Deserialize)]
+pub struct UploadQuery {
+    /// Dataset name; defaults to the uploaded file name
+    pub name: Option<String>,
+    /// `jsonl` or `csv`; detected from the content type, file name or content when omitted
+    pub format: Option<DatasetFormat>,
+}
+
+#[derive(Deserialize)]
+pub struct PreviewQuery {
+    pub limit: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct DatasetsResponse {
+    pub datasets: Vec<database::Dataset>,
+}
+
+#[derive(Serialize)]
+pub struct DatasetPreviewResponse {
+    pub dataset: database::Dataset,
+    pub rows: Vec<StoredRow>,
+}
+
+#[derive(Deserialize)]
+pub struct RunDatasetRequest {
+    pub dataset_id: String,
+    /// Prompt template whose `{{placeholders}}` are filled from each row
+    pub prompt: String,
+    pub models: Vec<String>,
+    /// Expected output template; defaults to each row's `expected` column
+    pub expected: Option<String>,
+    pub judge_model: Option<String>,
+    pub criteria: Option<String>,
+    pub min_judge_confidence: Option<f32>,
+    /// Generate reference outputs instead of judging: nothing is judged and every output
+    /// goes to the review queue, ready to be adopted as the rows' expected values
+    #[serde(default)]
+    pub reference: bool,
+    /// Reuse cached responses for rows whose prompt has not changed; see `EvalConfig::cache`
+    #[serde(default)]
+    pub cache: bool,
+}
+
+#[derive(Deserialize)]
+pub struct AdoptExpectedQuery {
+    /// The reference run whose approved outputs to adopt
+    pub batch_id: String,
+    /// Also replace expected values written by a person
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+#[derive(Serialize)]
+pub struct AdoptExpectedResponse {
+    pub dataset_id: String,
+    pub batch_id: String,
+    #[serde(flatten)]
+    pub report: dataset::AdoptionReport,
+}
+
+#[derive(Serialize)]
+pub struct RunDatasetResponse {
+    pub dataset_id: String,
+    /// Rows skipped because they could not be expanded into evals
+    pub row_errors: Vec<RowError>,
+    #[serde(flatten)]
+    pub batch: BatchEvalResponse,
+}
+
+fn dataset_not_found(id: &str) -> ApiError {
+    ApiError::not_found(format!("Dataset '{}' not found", id))
+}
+
+/// POST /api/v1/datasets - Upload a JSONL or CSV dataset, as a raw body or a multipart file
+pub async fn upload_dataset(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    query: web::Query<UploadQuery>,
+    body: web::Bytes,
+) -> Result<HttpResponse, ApiError> {
+    let pool = state.pool()?;
+
+    let content_type = req
+        .headers()
+        .get(actix_web::http::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+
+    let (file_name, contents) = match content_type.as_deref() {
+        Some(ct) if ct.starts_with("multipart/form-data") => match dataset::extract_multipart_file(ct, &body) {
+            Some(file) => file,
+            None => return Err(ApiError::validation("Could not find a file in the multipart body")),
+        },
+        _ => (None, body.to_vec()),
+    };
+    let Ok(text) = String::from_utf8(contents) else {
+        return Err(ApiError::validation("Dataset must be UTF-8 text"));
+    };
+
+    let format = query.format.unwrap_or_else(|| {
+        // A multipart body's own content type says nothing about the file inside it
+        let ct = content_type.as_deref().filter(|_| file_name.is_none());
+        DatasetFormat::detect(ct, file_name.as_deref(), &text)
+    });
+    let rows = dataset::parse_rows(format, &text).map_err(ApiError::validation)?;
+
+    let name = query
+        .name
+        .clone()
+        .or(file_name)
+        .unwrap_or_else(|| format!("dataset-{}", chrono::Utc::now().format("%Y%m%d-%H%M%S")));
+
+    let dataset = database::create_dataset(pool, name, format, &rows)
+        .await
+        .map_err(|e| ApiError::database("Failed to save dataset", e))?;
+    tracing::info!("📚 Stored dataset '{}' with {} rows", dataset.name, dataset.row_count);
+    Ok(HttpResponse::Created().json(serde_json::json!({ "dataset": dataset })))
+}
+
+/// GET /api/v1/datasets - List datasets, newest first
+pub async fn get_all_datasets(
+    state: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let pool = state.pool()?;
+    let datasets = database::get_all_datasets(pool)
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch datasets", e))?;
+    Ok(HttpResponse::Ok().json(DatasetsResponse { datasets }))
+}
+
+/// GET /api/v1/datasets/{id} - A dataset and a preview of its first rows (`?limit=N`, default 10)
+pub async fn get_dataset(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+    query: web::Query<PreviewQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+
+    let pool = state.pool()?;
+
+    let dataset = database::get_dataset(pool, &id).await.map_err(|e| match e {
+        sqlx::Error::RowNotFound => dataset_not_found(&id),
+        e => ApiError::database("Failed to fetch dataset", e),
+    })?;
+
+    let limit = query.limit.unwrap_or(DEFAULT_PREVIEW_ROWS).max(0);
+    let rows = database::get_dataset_rows(pool, &id, Some(limit))
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch dataset rows", e))?;
+    Ok(HttpResponse::Ok().json(DatasetPreviewResponse { dataset, rows }))
+}
+
+/// POST /api/v1/evals/run-dataset - Run a prompt template over every dataset row × model as a batch
+pub async fn run_dataset(
+    state: web::Data<AppState>,
+    broker: web::Data<WsBroker>,
+    req: web::Json<RunDatasetRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let req = req.into_inner();
+
+    let pool = state.pool()?;
+
+    if req.models.is_empty() {
+        return Err(ApiError::validation("At least one model is required"));
+    }
+    if req.reference && (req.expected.is_some() || req.judge_model.is_some()) {
+        return Err(ApiError::validation("A reference run is not judged; leave out expected and judge_model"));
+    }
+
+    let rows = database::get_dataset_rows(pool, &req.dataset_id, None)
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch dataset rows", e))?;
+    if rows.is_empty() {
+        return Err(dataset_not_found(&req.dataset_id));
+    }
+
+    let template = EvalConfig {
+        model: String::new(),
+        prompt_version: None,
+        prompt: req.prompt,
+        expected: req.expected,
+        judge_model: req.judge_model,
+        criteria: req.criteria,
+        tags: vec![format!("dataset:{}", req.dataset_id)],
+        metadata: None,
+        format_rule: None,
+        stream: false,
+        min_judge_confidence: req.min_judge_confidence,
+        truncation: None,
+        truncate_field: None,
+        context_window: None,
+        on_length_limit: None,
+        trace: false,
+        skip_failure_classification: false,
+        capture_raw: false,
+        require_review: req.reference,
+        cache: req.cache,
+        cache_judge: false,
+        safety_threshold: None,
+        response_format: None,
+        stop: None,
+        max_output_tokens: None,
+        thinking: None,
+        images: Vec::new(),
+        include_reasoning: false,
+        allow_missing_vars: false,
+        judge_params: None,
+        match_rule: None,
+        diff_rule: None,
+        self_consistency: None,
+        preset: None,
+        dataset_row: None,
+        chain_step: None,
+        provider_overrides: Default::default(),
+        overrides_supplied_by: None,
+    };
+    let (mut configs, row_errors) = dataset::expand(&req.dataset_id, &rows, &template, &req.models);
+    if req.reference {
+        // The rows' own expected values would otherwise be carried along
+        for config in &mut configs {
+            config.expected = None;
+            if let Some(row) = config.dataset_row.as_mut() {
+                row.expected_source = None;
+            }
+        }
+    }
+    if configs.is_empty() {
+        return Err(ApiError::validation("No dataset rows could be expanded into evals")
+            .with_details(serde_json::json!({ "row_errors": row_errors })));
+    }
+    runner::validate_providers(&state.config, &configs)?;
+
+    tracing::info!(
+        "📚 Running dataset {}{} ({} evals, {} rows skipped)",
+        req.dataset_id,
+        if req.reference { " for reference outputs" } else { "" },
+        configs.len(),
+        row_errors.len()
+    );
+    let batch = execute_batch(&state, &broker, configs, None).await;
+
+    Ok(HttpResponse::Ok().json(RunDatasetResponse {
+        dataset_id: req.dataset_id,
+        row_errors,
+        batch,
+    }))
+}
+
+/// POST /api/v1/datasets/{id}/adopt-expected?batch_id=... - Copy the outputs of a reference
+/// run that reviewers passed into the rows' expected values, recording where each came from
+pub async fn adopt_expected(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+    query: web::Query<AdoptExpectedQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+    let query = query.into_inner();
+
+    let pool = state.pool()?;
+
+    let fetched = async {
+        let rows = database::get_dataset_rows(pool, &id, None).await?;
+        let outputs = database::get_reference_outputs(pool, &id, &query.batch_id).await?;
+        Ok::<_, sqlx::Error>((rows, outputs))
+    };
+    let (rows, outputs) = fetched.await.map_err(|e| ApiError::database("Failed to fetch reference outputs", e))?;
+    if rows.is_empty() {
+        return Err(dataset_not_found(&id));
+    }
+    if outputs.is_empty() {
+        return Err(ApiError::not_found(format!("Batch '{}' has no outputs for dataset '{}'", query.batch_id, id)));
+    }
+
+    let report = dataset::plan_adoption(&rows, &outputs, query.overwrite, &chrono::Utc::now().to_rfc3339());
+    database::adopt_expected(pool, &id, &report.adopted)
+        .await
+        .map_err(|e| ApiError::database("Failed to adopt expected values", e))?;
+    tracing::info!("📚 Adopted {} expected values into dataset {} from batch {}", report.adopted.len(), id, query.batch_id);
+    Ok(HttpResponse::Ok().json(AdoptExpectedResponse { dataset_id: id, batch_id: query.batch_id, report }))
+}
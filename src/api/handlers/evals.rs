@@ -1,21 +1,118 @@
 // src/api/handlers/evals.rs - Complete fixed version
-use actix_web::{web, HttpResponse, Result};
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use crate::api::AppState;
-use crate::api::handlers::ws::{WsBroker, EvalUpdate};
-use crate::config::EvalConfig;
+use crate::api::progress::{ProgressStage, ProgressState};
+use crate::api::auth::Caller;
+use crate::api::handlers::presets::PresetResolver;
+use crate::api::handlers::prompt_versions::apply_prompt_version;
+use crate::api::handlers::ws::{self, EvalUpdate, WsBroker, WsEvent};
+use crate::budget::{Budget, BudgetUsage};
+use crate::chain;
+use crate::config::{EvalConfig, JudgeParams, LengthLimitPolicy, SafetyThreshold};
+use crate::diff::DiffRule;
+use crate::format_rule::FormatRule;
+use crate::match_rule::MatchRule;
+use crate::providers::response_format::ResponseFormat;
+use crate::providers::ThinkingParams;
+use crate::matrix;
+use crate::pricing::CharsPerToken;
+use crate::provider_override::{ProviderOverrides, SuppliedBy};
 use crate::runner;
-use crate::errors::EvalError;
+use crate::self_consistency::SelfConsistency;
+use crate::stats;
+use crate::errors::{ApiError, EvalError};
+use crate::trace::{self, BatchTrace, Span, TracePhase};
+use crate::truncation::TruncationStrategy;
+use crate::models::{BatchWebhook, EvalWebhook, FieldError, WebhookPayload};
+use crate::notifier::{self, BatchReport, FailedEval};
+use crate::webhook;
 use serde_json::json;
+use std::time::Instant;
 
 #[derive(Clone, Deserialize)]
 pub struct RunEvalRequest {
     pub model: String,
+    #[serde(default)]
     pub prompt: String,
+    /// Prompt version whose template is used instead of `prompt`
+    pub prompt_version: Option<i64>,
     pub expected: Option<String>,
     pub judge_model: Option<String>,
     pub criteria: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub metadata: Option<serde_json::Value>,
+    pub format_rule: Option<FormatRule>,
+    #[serde(default)]
+    pub stream: bool,
+    pub min_judge_confidence: Option<f32>,
+    pub truncation: Option<TruncationStrategy>,
+    pub truncate_field: Option<String>,
+    pub context_window: Option<u32>,
+    pub on_length_limit: Option<LengthLimitPolicy>,
+    #[serde(default)]
+    pub trace: bool,
+    #[serde(default)]
+    pub skip_failure_classification: bool,
+    #[serde(default)]
+    pub capture_raw: bool,
+    #[serde(default)]
+    pub require_review: bool,
+    #[serde(default)]
+    pub cache: bool,
+    #[serde(default)]
+    pub cache_judge: bool,
+    pub safety_threshold: Option<SafetyThreshold>,
+    pub response_format: Option<ResponseFormat>,
+    pub stop: Option<Vec<String>>,
+    pub max_output_tokens: Option<u32>,
+    pub thinking: Option<ThinkingParams>,
+    #[serde(default)]
+    pub images: Vec<String>,
+    #[serde(default)]
+    pub include_reasoning: bool,
+    #[serde(default)]
+    pub allow_missing_vars: bool,
+    pub judge_params: Option<JudgeParams>,
+    #[serde(rename = "match")]
+    pub match_rule: Option<MatchRule>,
+    #[serde(rename = "diff")]
+    pub diff_rule: Option<DiffRule>,
+    pub self_consistency: Option<SelfConsistency>,
+    /// Credentials used in place of the configured ones for this request only
+    #[serde(default)]
+    pub provider_overrides: ProviderOverrides,
+    /// URL POSTed a signed `eval.completed` summary once the eval finishes
+    pub callback_url: Option<String>,
+}
+
+/// Query of `POST /evals/batch`, whose body is the list of configs.
+#[derive(Deserialize)]
+pub struct BatchQuery {
+    /// URL POSTed a signed `batch.completed` summary once the batch finishes
+    pub callback_url: Option<String>,
+}
+
+/// Body of `POST /evals/batch`: the list of configs, or the list under `evals` with a `budget`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum BatchRequest {
+    Configs(Vec<serde_json::Value>),
+    Budgeted {
+        evals: Vec<serde_json::Value>,
+        budget: Option<Budget>,
+    },
+}
+
+impl BatchRequest {
+    fn into_parts(self) -> (Vec<serde_json::Value>, Option<Budget>) {
+        match self {
+            BatchRequest::Configs(evals) => (evals, None),
+            BatchRequest::Budgeted { evals, budget } => (evals, budget),
+        }
+    }
 }
 
 #[derive(Serialize)]
@@ -24,6 +121,12 @@ pub struct EvalResponse {
     pub status: String,
     pub result: Option<runner::EvalResult>,
     pub error: Option<String>,
+    /// Stable code of `error`, as in `ApiErrorResponse`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
+    /// The preset the request was merged with, including any fields it overrode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preset: Option<crate::preset::PresetApplication>,
 }
 
 #[derive(Serialize)]
@@ -34,130 +137,318 @@ pub struct BatchEvalResponse {
     pub completed: usize,
     pub passed: usize,
     pub failed: usize,
+    pub needs_review: usize,
     pub average_model_latency_ms: u64,
     pub average_judge_latency_ms: u64,
+    /// Percentiles of model, judge, total and wait latency, also per model when the batch
+    /// mixes models
+    pub latency: stats::BatchLatency,
+    pub total_cost_usd: f64,
+    pub early_aborts: usize,
+    pub estimated_tokens_saved: u64,
+    pub estimated_cost_saved_usd: f64,
+    /// Phase timings aggregated over the evals that asked for a trace
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trace_summary: Option<BatchTrace>,
+    /// What the batch used of its budget, the cap that stopped it and the evals skipped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub budget: Option<BudgetUsage>,
     pub results: Vec<EvalResponse>,
 }
 
+/// The copy of `result` to save: it keeps its trace only when traces are persisted, and its
+/// output is cut to `MAX_STORED_OUTPUT_CHARS`.
+pub(crate) fn stored_result(config: &crate::config::AppConfig, mut result: runner::EvalResult) -> runner::EvalResult {
+    if !config.persist_traces {
+        result.trace = None;
+    }
+    let cut = config.max_stored_output_chars.and_then(|max| result.model_output.char_indices().nth(max));
+    if let Some((end, _)) = cut {
+        result.full_output_chars = Some(result.model_output.chars().count());
+        result.model_output.truncate(end);
+    }
+    result
+}
+
+/// Appends the time spent saving to the trace of `response`'s result, if it is traced.
+fn record_persistence(response: &mut EvalResponse, span: Span, saved: &std::result::Result<(), sqlx::Error>) {
+    if let Some(result) = response.result.as_mut() {
+        trace::record(&mut result.trace, span, saved.as_ref().err().map(|e| format!("failed: {}", e)));
+    }
+}
+
+/// Posts `payload` to `callback_url` in the background, when the request gave one.
+fn notify(state: &AppState, callback_url: Option<&str>, payload: WebhookPayload) {
+    if let (Some(url), Some(secret)) = (callback_url, state.config.webhook_secret.as_ref()) {
+        webhook::spawn(state.client.clone(), secret.clone(), url.to_string(), payload);
+    }
+}
+
+/// Records the API key of `http_req` as the supplier of `config`'s provider overrides, if any.
+fn attribute_overrides(config: &mut EvalConfig, http_req: &HttpRequest) {
+    if config.provider_overrides.is_empty() {
+        return;
+    }
+    config.overrides_supplied_by = Some(http_req.extensions().get::<Caller>().map_or_else(
+        || SuppliedBy { key_id: None, key_name: "anonymous".to_string() },
+        |caller| SuppliedBy { key_id: caller.key_id.clone(), key_name: caller.name.clone() },
+    ));
+}
+
+/// `errors`, plus the error of `callback_url` when it cannot be used.
+fn with_callback_error(state: &AppState, mut errors: Vec<FieldError>, callback_url: Option<&str>) -> Vec<FieldError> {
+    if let Some(Err(error)) = callback_url.map(|url| webhook::check_callback_url(&state.config, url)) {
+        errors.push(error);
+    }
+    errors
+}
+
+/// The model and prompt of each config, to describe a batch's evals that errored without a result.
+pub(crate) fn labels(configs: &[EvalConfig]) -> Vec<(String, String)> {
+    configs.iter().map(|config| (config.model.clone(), config.prompt.clone())).collect()
+}
+
+/// Reports a finished batch to the configured notifiers when it has failures and fell below
+/// `NOTIFY_MIN_PASS_RATE`. `labels` come from `labels`, in batch order.
+pub(crate) fn notify_failures(
+    state: &AppState,
+    http_req: &HttpRequest,
+    name: String,
+    batch: &BatchEvalResponse,
+    labels: &[(String, String)],
+) {
+    let notifiers = notifier::from_config(&state.config, &state.client);
+    if notifiers.is_empty() {
+        return;
+    }
+    let failures = batch
+        .results
+        .iter()
+        .zip(labels)
+        .filter(|(r, _)| matches!(r.status.as_str(), "failed" | "error" | "blocked"))
+        .take(notifier::MAX_LISTED_FAILURES)
+        .map(|(r, (model, prompt))| FailedEval {
+            eval_id: r.id.clone(),
+            model: r.result.as_ref().map_or_else(|| model.clone(), |result| result.model.clone()),
+            prompt: r.result.as_ref().map_or_else(|| prompt.clone(), |result| result.prompt.clone()),
+            status: r.status.clone(),
+        })
+        .collect();
+    let connection = http_req.connection_info();
+    let report = BatchReport {
+        name,
+        batch_id: batch.batch_id.clone(),
+        total: batch.total,
+        passed: batch.passed,
+        failed: batch.failed,
+        failures,
+        link: Some(format!("{}://{}/results.html", connection.scheme(), connection.host())),
+    };
+    if notifier::should_notify(&state.config, &report) {
+        notifier::dispatch(notifiers, report);
+    }
+}
+
+impl From<&BatchEvalResponse> for BatchWebhook {
+    fn from(batch: &BatchEvalResponse) -> Self {
+        BatchWebhook {
+            batch_id: batch.batch_id.clone(),
+            status: batch.status.clone(),
+            total: batch.total,
+            completed: batch.completed,
+            passed: batch.passed,
+            failed: batch.failed,
+            needs_review: batch.needs_review,
+            total_cost_usd: batch.total_cost_usd,
+            average_model_latency_ms: batch.average_model_latency_ms,
+            completed_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+/// The error answered for a failed eval, carrying the eval's id so its stored record can be
+/// found, and the preset it was merged with.
+fn eval_error(e: EvalError, eval_id: &str, preset: Option<&crate::preset::PresetApplication>) -> ApiError {
+    let mut error = ApiError::from(e);
+    let mut details = error.details.take().unwrap_or_else(|| json!({}));
+    details["eval_id"] = json!(eval_id);
+    if let Some(preset) = preset {
+        details["preset"] = json!(preset);
+    }
+    error.with_details(details)
+}
+
 pub async fn run_eval(
     state: web::Data<AppState>,
     broker: web::Data<WsBroker>,
-    req: web::Json<RunEvalRequest>,
-) -> Result<HttpResponse> {
-    let eval_id = Uuid::new_v4().to_string();
-    let req_body = req.into_inner();
-    let eval_config = EvalConfig {
+    http_req: HttpRequest,
+    req: web::Json<serde_json::Value>,
+) -> Result<HttpResponse, ApiError> {
+    let eval_id = runner::new_eval_id();
+    let (req_body, preset) = PresetResolver::new(&state, &http_req).resolve::<RunEvalRequest>(req.into_inner(), None).await?;
+    let callback_url = req_body.callback_url;
+    let mut eval_config = EvalConfig {
         model: req_body.model.clone(),
         prompt: req_body.prompt,
+        prompt_version: req_body.prompt_version,
         expected: req_body.expected,
         judge_model: req_body.judge_model,
         criteria: req_body.criteria,
-        tags: Vec::new(),
-        metadata: None,
+        tags: req_body.tags,
+        metadata: req_body.metadata,
+        format_rule: req_body.format_rule,
+        stream: req_body.stream,
+        min_judge_confidence: req_body.min_judge_confidence,
+        truncation: req_body.truncation,
+        truncate_field: req_body.truncate_field,
+        context_window: req_body.context_window,
+        on_length_limit: req_body.on_length_limit,
+        trace: req_body.trace,
+        skip_failure_classification: req_body.skip_failure_classification,
+        capture_raw: req_body.capture_raw,
+        require_review: req_body.require_review,
+        cache: req_body.cache,
+        cache_judge: req_body.cache_judge,
+        safety_threshold: req_body.safety_threshold,
+        response_format: req_body.response_format,
+        stop: req_body.stop,
+        max_output_tokens: req_body.max_output_tokens,
+        thinking: req_body.thinking,
+        images: req_body.images,
+        include_reasoning: req_body.include_reasoning,
+        allow_missing_vars: req_body.allow_missing_vars,
+        judge_params: req_body.judge_params,
+        match_rule: req_body.match_rule,
+        diff_rule: req_body.diff_rule,
+        self_consistency: req_body.self_consistency,
+        preset,
+        dataset_row: None,
+        chain_step: None,
+        provider_overrides: req_body.provider_overrides,
+        overrides_supplied_by: None,
     };
+    apply_prompt_version(&state, &mut eval_config, None).await?;
+    attribute_overrides(&mut eval_config, &http_req);
+    let errors = with_callback_error(&state, eval_config.validate(&state.config).err().unwrap_or_default(), callback_url.as_deref());
+    if !errors.is_empty() {
+        return Err(ApiError::invalid_fields(errors));
+    }
 
     // Extract the pool reference properly for the new Option<Arc<SqlitePool>> structure
     let db_pool_ref = state.db_pool.as_ref().map(|arc| arc.as_ref());
-    
-    match runner::run_eval_with_pool(&state.config, &eval_config, &state.client, db_pool_ref).await {
+
+    state.progress.eval_stage(&eval_id, None, ProgressStage::Queued);
+    let (progress, forwarding) = ws::forward_progress(&broker, &state.progress, None);
+    let outcome =
+        runner::run_eval_with_id(&state.config, &eval_config, &state.client, db_pool_ref, Some(&eval_id), Some(&progress)).await;
+    drop(progress);
+    let _ = forwarding.await;
+
+    match outcome {
         Ok(result) => {
-            let status = if let Some(judge) = &result.judge_result {
-                match judge.verdict {
-                    runner::JudgeVerdict::Pass => "passed",
-                    runner::JudgeVerdict::Fail => "failed",
-                    runner::JudgeVerdict::Uncertain => "uncertain",
-                }
-            } else {
-                "completed"
-            };
+            let status = runner::eval_status(&result);
 
             // Broadcast via WebSocket
-            broker.broadcast(EvalUpdate {
-                id: eval_id.clone(),
-                status: status.to_string(),
-                model: Some(req_body.model),
-                verdict: result.judge_result.as_ref().map(|j| j.verdict.to_string()),
-                latency_ms: Some(result.latency_ms),
-            }).await;
+            let update = EvalUpdate::completed(&eval_id, status, &result, None, state.config.ws_max_output_chars);
+            broker.broadcast(WsEvent::EvalCompleted(update)).await;
 
-            let response = EvalResponse {
+            let mut response = EvalResponse {
                 id: eval_id.clone(),
                 status: status.to_string(),
                 result: Some(result.clone()),
                 error: None,
+                error_code: None,
+                preset: eval_config.preset.clone(),
+            };
+            let webhook = EvalWebhook {
+                eval_id: eval_id.clone(),
+                status: status.to_string(),
+                model: result.model.clone(),
+                verdict: result.judge_result.as_ref().map(|j| j.verdict.to_string()),
+                latency_ms: Some(result.latency_ms),
+                cost_usd: result.cost_usd.map(|cost| cost + result.judge_cost_usd.unwrap_or(0.0)),
+                error: result.judge_error.as_ref().map(runner::JudgeError::to_response),
+                completed_at: chrono::Utc::now().to_rfc3339(),
             };
 
             // Save to database
+            state.progress.eval_stage(&eval_id, None, ProgressStage::Saving);
             if let Some(pool_arc) = state.db_pool.as_ref() {
-                println!("💾 Saving successful evaluation to database: {}", eval_id);
+                tracing::info!("💾 Saving successful evaluation to database: {}", eval_id);
+                let span = Span::start(TracePhase::Persistence);
                 let api_response = crate::models::ApiResponse {
                     id: eval_id.clone(),
                     status: status.to_string(),
-                    result: crate::models::EvalResult::Success(result),
+                    result: crate::models::EvalResult::Success(stored_result(&state.config, result)),
+                    batch_id: None,
+                    config_hash: Some(eval_config.config_hash()),
+                    preset: eval_config.preset.clone(),
                 };
-                match crate::database::save_evaluation(pool_arc, &api_response).await {
-                    Ok(_) => println!("✅ Successfully saved evaluation {} to database", eval_id),
+                let saved = crate::database::save_evaluation(pool_arc, &api_response).await;
+                match &saved {
+                    Ok(_) => {
+                        tracing::info!("✅ Successfully saved evaluation {} to database", eval_id);
+                        link_prompt_version(pool_arc, &eval_id, eval_config.prompt_version).await;
+                    }
                     Err(e) => {
-                        eprintln!("❌ Failed to save evaluation to database: {}", e);
-                        log::error!("Database save error: {:?}", e);
+                        tracing::error!("❌ Failed to save evaluation to database: {}", e);
+                        tracing::error!("Database save error: {:?}", e);
                     }
                 }
+                record_persistence(&mut response, span, &saved);
             } else {
-                eprintln!("⚠️  Database pool is None - evaluation not saved!");
+                tracing::warn!("⚠️  Database pool is None - evaluation not saved!");
             }
+            state.progress.eval_stage(&eval_id, None, ProgressStage::Done);
 
+            notify(&state, callback_url.as_deref(), WebhookPayload::EvalCompleted(webhook));
             Ok(HttpResponse::Ok().json(response))
         }
         Err(e) => {
-            let error_string = e.to_string();
-            
-            let status_code = match &e {
-                EvalError::ProviderNotFound(_) | EvalError::Config(_) => 400,
-                EvalError::ModelFailure { .. } => 400,
-                _ => 500,
-            };
-
-            // Broadcast error via WebSocket
-            broker.broadcast(EvalUpdate {
-                id: eval_id.clone(),
-                status: "error".to_string(),
-                model: Some(req_body.model.clone()),
+            let error = e.to_response();
+            let webhook = EvalWebhook {
+                eval_id: eval_id.clone(),
+                status: e.status().to_string(),
+                model: req_body.model.clone(),
                 verdict: None,
                 latency_ms: None,
-            }).await;
-
-            let response = EvalResponse {
-                id: eval_id.clone(),
-                status: "error".to_string(),
-                result: None,
-                error: Some(error_string.clone()),
+                cost_usd: None,
+                error: Some(error.clone()),
+                completed_at: chrono::Utc::now().to_rfc3339(),
             };
 
+            // Broadcast error via WebSocket
+            broker.broadcast(WsEvent::error(&eval_id, Some(req_body.model.clone()), None, &error)).await;
+
             // Save error to database
+            state.progress.eval_stage(&eval_id, None, ProgressStage::Saving);
             if let Some(pool_arc) = state.db_pool.as_ref() {
-                println!("💾 Saving error evaluation to database: {}", eval_id);
+                tracing::info!("💾 Saving error evaluation to database: {}", eval_id);
                 let api_response = crate::models::ApiResponse {
                     id: eval_id.clone(),
-                    status: "error".to_string(),
-                    result: crate::models::EvalResult::Error(crate::models::ApiError {
-                        message: error_string.clone(),
-                    }),
+                    status: e.status().to_string(),
+                    result: crate::models::EvalResult::Error(error),
+                    batch_id: None,
+                    config_hash: Some(eval_config.config_hash()),
+                    preset: eval_config.preset.clone(),
                 };
                 match crate::database::save_evaluation(pool_arc, &api_response).await {
-                    Ok(_) => println!("✅ Successfully saved error evaluation {} to database", eval_id),
+                    Ok(_) => {
+                        tracing::info!("✅ Successfully saved error evaluation {} to database", eval_id);
+                        link_prompt_version(pool_arc, &eval_id, eval_config.prompt_version).await;
+                    }
                     Err(e) => {
-                        eprintln!("❌ Failed to save error evaluation to database: {}", e);
-                        log::error!("Database save error: {:?}", e);
+                        tracing::error!("❌ Failed to save error evaluation to database: {}", e);
+                        tracing::error!("Database save error: {:?}", e);
                     }
                 }
             } else {
-                eprintln!("⚠️  Database pool is None - error evaluation not saved!");
+                tracing::warn!("⚠️  Database pool is None - error evaluation not saved!");
             }
+            state.progress.eval_stage(&eval_id, None, ProgressStage::Error);
 
-            match status_code {
-                400 => Ok(HttpResponse::BadRequest().json(response)),
-                _ => Ok(HttpResponse::InternalServerError().json(response)),
-            }
+            notify(&state, callback_url.as_deref(), WebhookPayload::EvalCompleted(webhook));
+            Err(eval_error(e, &eval_id, eval_config.preset.as_ref()))
         }
     }
 }
@@ -165,184 +456,2876 @@ pub async fn run_eval(
 pub async fn run_batch(
     state: web::Data<AppState>,
     broker: web::Data<WsBroker>,
-    eval_configs: web::Json<Vec<EvalConfig>>,
-) -> Result<HttpResponse> {
+    http_req: HttpRequest,
+    query: web::Query<BatchQuery>,
+    req: web::Json<BatchRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let (eval_configs, budget) = req.into_inner().into_parts();
+    let mut resolver = PresetResolver::new(&state, &http_req);
+    let mut configs = Vec::new();
+    let mut errors = budget.map(|budget| budget.validate()).unwrap_or_default();
+    for (i, body) in eval_configs.into_iter().enumerate() {
+        let (mut config, preset) = resolver.resolve::<EvalConfig>(body, Some(i)).await?;
+        apply_prompt_version(&state, &mut config, Some(&format!("[{}]", i))).await?;
+        if let Err(invalid) = config.validate(&state.config) {
+            errors.extend(invalid.into_iter().map(|error| error.within(&format!("[{}]", i))));
+        }
+        config.preset = preset;
+        attribute_overrides(&mut config, &http_req);
+        configs.push(config);
+    }
+    let errors = with_callback_error(&state, errors, query.callback_url.as_deref());
+    if !errors.is_empty() {
+        return Err(ApiError::invalid_fields(errors));
+    }
+
+    let labels = labels(&configs);
+    let batch = execute_batch(&state, &broker, configs, budget).await;
+    notify(&state, query.callback_url.as_deref(), WebhookPayload::BatchCompleted(BatchWebhook::from(&batch)));
+    notify_failures(&state, &http_req, format!("Batch {}", batch.batch_id), &batch, &labels);
+    Ok(HttpResponse::Ok().json(batch))
+}
+
+#[derive(Serialize)]
+pub struct MatrixResponse {
+    #[serde(flatten)]
+    pub grouping: matrix::MatrixGrouping,
+    #[serde(flatten)]
+    pub batch: BatchEvalResponse,
+}
+
+/// POST /api/v1/evals/matrix - Run every prompt against every model and group the results
+pub async fn run_matrix(
+    state: web::Data<AppState>,
+    broker: web::Data<WsBroker>,
+    http_req: HttpRequest,
+    req: web::Json<matrix::MatrixRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let req = req.into_inner();
+    let (cells, configs) = matrix::expand(&req).map_err(ApiError::validation)?;
+    let errors = matrix::validate(&req, &cells, &configs, &state.config).err().unwrap_or_default();
+    let errors = with_callback_error(&state, errors, req.callback_url.as_deref());
+    if !errors.is_empty() {
+        return Err(ApiError::invalid_fields(errors));
+    }
+
+    tracing::info!("🧮 Running {} prompts × {} models", req.prompts.len(), req.models.len());
+    let labels = labels(&configs);
+    let batch = execute_batch(&state, &broker, configs, None).await;
+
+    let outcomes: Vec<matrix::CellOutcome> = batch
+        .results
+        .iter()
+        .map(|r| matrix::CellOutcome {
+            eval_id: r.id.clone(),
+            status: r.status.clone(),
+            latency_ms: r.result.as_ref().map(|result| result.latency_ms),
+        })
+        .collect();
+    let grouping = matrix::group(&req, &cells, &outcomes);
+    notify(&state, req.callback_url.as_deref(), WebhookPayload::BatchCompleted(BatchWebhook::from(&batch)));
+    notify_failures(&state, &http_req, format!("Matrix {}", batch.batch_id), &batch, &labels);
+
+    Ok(HttpResponse::Ok().json(MatrixResponse { grouping, batch }))
+}
+
+/// Body of `POST /evals/chain`.
+#[derive(Deserialize)]
+pub struct ChainRequest {
+    /// Eval configs of the steps, run in order
+    pub steps: Vec<serde_json::Value>,
+    /// End the chain after the first step that fails or errors
+    #[serde(default)]
+    pub stop_on_failure: bool,
+}
+
+#[derive(Serialize)]
+pub struct ChainResponse {
+    pub chain_id: String,
+    /// Overall verdict of the chain; see `chain::chain_status`
+    pub status: String,
+    pub total: usize,
+    /// Steps not run because an earlier step failed and `stop_on_failure` was set
+    pub skipped: usize,
+    pub steps: Vec<EvalResponse>,
+}
+
+/// POST /api/v1/evals/chain - Run evals one after another, each step's prompt reading the
+/// outputs of the steps before it
+pub async fn run_chain(
+    state: web::Data<AppState>,
+    broker: web::Data<WsBroker>,
+    http_req: HttpRequest,
+    req: web::Json<ChainRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let ChainRequest { steps: bodies, stop_on_failure } = req.into_inner();
+    let mut resolver = PresetResolver::new(&state, &http_req);
+    let mut steps = Vec::new();
+    let mut errors = Vec::new();
+    for (i, body) in bodies.into_iter().enumerate() {
+        let (mut step, preset) = resolver.resolve::<EvalConfig>(body, Some(i)).await?;
+        apply_prompt_version(&state, &mut step, Some(&format!("steps[{}]", i))).await?;
+        if let Err(invalid) = step.validate(&state.config) {
+            errors.extend(invalid.into_iter().map(|error| error.within(&format!("steps[{}]", i))));
+        }
+        step.preset = preset;
+        attribute_overrides(&mut step, &http_req);
+        steps.push(step);
+    }
+    errors.extend(chain::validate(&steps).err().unwrap_or_default());
+    if !errors.is_empty() {
+        return Err(ApiError::invalid_fields(errors));
+    }
+
+    let _active = state.start_batch();
+    let chain_id = Uuid::new_v4().to_string();
+    tracing::info!("🔗 Running chain {} of {} steps", chain_id, steps.len());
+    let db_pool_ref = state.db_pool.as_ref().map(|arc| arc.as_ref());
+    let (progress, forwarding) = ws::forward_progress(&broker, &state.progress, None);
+    let results =
+        runner::run_chain(&state.config, &steps, &chain_id, stop_on_failure, &state.client, db_pool_ref, Some(&progress)).await;
+    drop(progress);
+    let _ = forwarding.await;
+
+    let skipped = steps.len() - results.len();
+    let mut responses = Vec::with_capacity(results.len());
+    for ((eval_id, outcome), step) in results.into_iter().zip(&steps) {
+        let response =
+            finish_eval(&state, &broker, eval_id, outcome, None, step.config_hash(), step.preset.clone(), step.model.clone(), step.prompt_version)
+                .await;
+        responses.push(response);
+    }
+
+    Ok(HttpResponse::Ok().json(ChainResponse {
+        status: chain::chain_status(responses.iter().map(|r| r.status.as_str()), skipped).to_string(),
+        chain_id,
+        total: steps.len(),
+        skipped,
+        steps: responses,
+    }))
+}
+
+/// Runs a batch concurrently, broadcasting and saving each result, and summarizes it. With a
+/// `budget`, evals stop being started once it is used up; see `runner::run_batch_evals_within_budget`.
+pub(crate) async fn execute_batch(
+    state: &AppState,
+    broker: &WsBroker,
+    eval_configs: Vec<EvalConfig>,
+    budget: Option<Budget>,
+) -> BatchEvalResponse {
+    let _active = state.start_batch();
+    let batch_start = Instant::now();
     let batch_id = Uuid::new_v4().to_string();
     let total = eval_configs.len();
+    let config_hashes: Vec<String> = eval_configs.iter().map(EvalConfig::config_hash).collect();
+    let presets: Vec<_> = eval_configs.iter().map(|config| config.preset.clone()).collect();
+    let models: Vec<String> = eval_configs.iter().map(|config| config.model.clone()).collect();
+    let prompt_versions: Vec<Option<i64>> = eval_configs.iter().map(|config| config.prompt_version).collect();
+
+    if let Some(pool_arc) = state.db_pool.as_ref()
+        && let Err(e) = crate::database::create_batch(pool_arc, &batch_id, total).await
+    {
+        tracing::error!("Failed to save batch {} to database: {}", batch_id, e);
+    }
 
     // Extract the pool reference properly for the new Option<Arc<SqlitePool>> structure
     let db_pool_ref = state.db_pool.as_ref().map(|arc| arc.as_ref());
-    
-    let results = runner::run_batch_evals_with_pool(
-        &state.config,
-        eval_configs.into_inner(),
-        &state.client,
-        db_pool_ref,
-    ).await;
+
+    let (progress, forwarding) = ws::forward_progress(broker, &state.progress, Some((&batch_id, total)));
+    let (results, budget) = match budget {
+        Some(budget) => {
+            let (results, usage) = runner::run_batch_evals_within_budget(
+                &state.config,
+                eval_configs,
+                Some(&batch_id),
+                &state.client,
+                db_pool_ref,
+                Some(&progress),
+                budget,
+            ).await;
+            (results, Some(usage))
+        }
+        None => {
+            let results = runner::run_batch_evals_with_ids(
+                &state.config,
+                eval_configs,
+                Some(&batch_id),
+                &state.client,
+                db_pool_ref,
+                Some(&progress),
+            ).await;
+            (results, None)
+        }
+    };
+    drop(progress);
+    let _ = forwarding.await;
+    state.progress.batch_stage(&batch_id, ProgressStage::Saving);
 
     let mut responses = Vec::new();
     let mut completed = 0;
     let mut passed = 0;
     let mut failed = 0;
+    let mut needs_review = 0;
     let mut total_model_latency = 0;
     let mut model_latency_count = 0;
     let mut total_judge_latency = 0;
     let mut judge_latency_count = 0;
 
-    for result in results {
-        let eval_id = Uuid::new_v4().to_string();
-        
-        match result {
-            Ok(eval_result) => {
-                completed += 1;
-                total_model_latency += eval_result.latency_ms;
-                model_latency_count += 1;
-                if let Some(judge_latency) = eval_result.judge_latency_ms {
-                    total_judge_latency += judge_latency;
-                    judge_latency_count += 1;
-                }
-                
-                let status = if let Some(judge) = &eval_result.judge_result {
-                    match judge.verdict {
-                        runner::JudgeVerdict::Pass => {
-                            passed += 1;
-                            "passed"
-                        }
-                        runner::JudgeVerdict::Fail => {
-                            failed += 1;
-                            "failed"
-                        }
-                        runner::JudgeVerdict::Uncertain => "uncertain",
-                    }
-                } else {
-                    "completed"
-                };
-
-                broker.broadcast(EvalUpdate {
-                    id: eval_id.clone(),
-                    status: status.to_string(),
-                    model: Some(eval_result.model.clone()),
-                    verdict: eval_result.judge_result.as_ref().map(|j| j.verdict.to_string()),
-                    latency_ms: Some(eval_result.latency_ms),
-                }).await;
-
-                let response = EvalResponse {
-                    id: eval_id.clone(),
-                    status: status.to_string(),
-                    result: Some(eval_result.clone()),
-                    error: None,
-                };
-
-                if let Some(pool_arc) = state.db_pool.as_ref() {
-                    let api_response = crate::models::ApiResponse {
-                        id: eval_id,
-                        status: status.to_string(),
-                        result: crate::models::EvalResult::Success(eval_result),
-                    };
-                    if let Err(e) = crate::database::save_evaluation(pool_arc, &api_response).await {
-                        log::error!("Failed to save batch evaluation to database: {}", e);
-                    }
-                }
-                responses.push(response);
-            }
-            Err(e) => {
-                failed += 1;
-                let error_string = e.to_string();
-                
-                broker.broadcast(EvalUpdate {
-                    id: eval_id.clone(),
-                    status: "error".to_string(),
-                    model: None,
-                    verdict: None,
-                    latency_ms: None,
-                }).await;
-
-                let response = EvalResponse {
-                    id: eval_id.clone(),
-                    status: "error".to_string(),
-                    result: None,
-                    error: Some(error_string.clone()),
-                };
-
-                if let Some(pool_arc) = state.db_pool.as_ref() {
-                    let api_response = crate::models::ApiResponse {
-                        id: eval_id,
-                        status: "error".to_string(),
-                        result: crate::models::EvalResult::Error(crate::models::ApiError {
-                            message: error_string,
-                        }),
-                    };
-                    if let Err(e) = crate::database::save_evaluation(pool_arc, &api_response).await {
-                        log::error!("Failed to save batch error to database: {}", e);
-                    }
-                }
-
-                responses.push(response);
+    // Results come back in config order
+    let members = results.into_iter().zip(config_hashes).zip(presets).zip(models).zip(prompt_versions);
+    for (((((eval_id, result), config_hash), preset), model), prompt_version) in members {
+        if let Ok(eval_result) = &result {
+            completed += 1;
+            total_model_latency += eval_result.latency_ms;
+            model_latency_count += 1;
+            if let Some(judge_latency) = eval_result.judge_latency_ms {
+                total_judge_latency += judge_latency;
+                judge_latency_count += 1;
             }
         }
+        let response = finish_eval(state, broker, eval_id, result, Some(&batch_id), config_hash, preset, model, prompt_version).await;
+        match response.status.as_str() {
+            "skipped_budget" => {}
+            _ if response.result.is_none() => failed += 1,
+            "passed" => passed += 1,
+            "failed" => failed += 1,
+            "needs_review" => needs_review += 1,
+            _ => {}
+        }
+        responses.push(response);
     }
 
     let average_model_latency_ms = if model_latency_count > 0 { total_model_latency / model_latency_count as u64 } else { 0 };
     let average_judge_latency_ms = if judge_latency_count > 0 { total_judge_latency / judge_latency_count as u64 } else { 0 };
+    let total_cost_usd: f64 = responses
+        .iter()
+        .filter_map(|r| r.result.as_ref())
+        .map(|r| r.cost_usd.unwrap_or(0.0) + r.judge_cost_usd.unwrap_or(0.0))
+        .sum();
+    let savings = runner::estimate_early_abort_savings(
+        responses.iter().filter_map(|r| r.result.as_ref()),
+        &state.config.pricing,
+    );
+
+    if let Some(pool_arc) = state.db_pool.as_ref()
+        && let Err(e) = crate::database::finish_batch(pool_arc, &batch_id, passed, failed, "completed").await
+    {
+        tracing::error!("Failed to update batch {} in database: {}", batch_id, e);
+    }
+    state.progress.batch_stage(&batch_id, ProgressStage::Done);
 
-    Ok(HttpResponse::Ok().json(BatchEvalResponse {
+    BatchEvalResponse {
         batch_id,
         status: "completed".to_string(),
         total,
         completed,
         passed,
         failed,
+        needs_review,
         average_model_latency_ms,
         average_judge_latency_ms,
+        latency: stats::batch_latency(&responses.iter().filter_map(|r| r.result.as_ref()).collect::<Vec<_>>()),
+        total_cost_usd,
+        early_aborts: savings.early_aborts,
+        estimated_tokens_saved: savings.estimated_tokens_saved,
+        estimated_cost_saved_usd: savings.estimated_cost_saved_usd,
+        trace_summary: trace::summarize(
+            responses.iter().filter_map(|r| r.result.as_ref()?.trace.as_deref()),
+            batch_start.elapsed().as_millis() as u64,
+        ),
+        budget,
         results: responses,
-    }))
+    }
 }
 
-pub async fn get_eval(path: web::Path<String>) -> Result<HttpResponse> {
-    let eval_id = path.into_inner();
-    
+/// Links the saved evaluation `eval_id` to the prompt version its prompt came from, if any, so
+/// the version's stats count it.
+async fn link_prompt_version(pool: &crate::database::DbPool, eval_id: &str, prompt_version: Option<i64>) {
+    let Some(version) = prompt_version else {
+        return;
+    };
+    if let Err(e) = crate::database::link_evaluation_to_prompt(pool, eval_id, version).await {
+        tracing::error!("Failed to link evaluation {} to prompt version {}: {}", eval_id, version, e);
+    }
+}
+
+/// Broadcasts the outcome of an eval that ran under `eval_id` and saves it with `batch_id`,
+/// linked to its `prompt_version`, returning its response. `model` is named in the live
+/// update of a failed eval.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn finish_eval(
+    state: &AppState,
+    broker: &WsBroker,
+    eval_id: String,
+    outcome: crate::errors::Result<runner::EvalResult>,
+    batch_id: Option<&str>,
+    config_hash: String,
+    preset: Option<crate::preset::PresetApplication>,
+    model: String,
+    prompt_version: Option<i64>,
+) -> EvalResponse {
+    match outcome {
+        Ok(eval_result) => {
+            let status = runner::eval_status(&eval_result);
+            let update = EvalUpdate::completed(&eval_id, status, &eval_result, batch_id, state.config.ws_max_output_chars);
+            broker.broadcast(WsEvent::EvalCompleted(update)).await;
+
+            let mut response = EvalResponse {
+                id: eval_id.clone(),
+                status: status.to_string(),
+                result: Some(eval_result.clone()),
+                error: None,
+                error_code: None,
+                preset: preset.clone(),
+            };
+
+            state.progress.eval_stage(&eval_id, batch_id, ProgressStage::Saving);
+            if let Some(pool_arc) = state.db_pool.as_ref() {
+                let span = Span::start(TracePhase::Persistence);
+                let api_response = crate::models::ApiResponse {
+                    id: eval_id,
+                    status: status.to_string(),
+                    result: crate::models::EvalResult::Success(stored_result(&state.config, eval_result)),
+                    batch_id: batch_id.map(str::to_string),
+                    config_hash: Some(config_hash),
+                    preset,
+                };
+                let saved = crate::database::save_evaluation_retrying(pool_arc, &api_response).await;
+                match &saved {
+                    Ok(()) => link_prompt_version(pool_arc, &response.id, prompt_version).await,
+                    Err(e) => tracing::error!("Failed to save evaluation to database: {}", e),
+                }
+                record_persistence(&mut response, span, &saved);
+            }
+            state.progress.eval_stage(&response.id, batch_id, ProgressStage::Done);
+            response
+        }
+        Err(e) => {
+            let error = e.to_response();
+
+            broker.broadcast(WsEvent::error(&eval_id, Some(model), batch_id, &error)).await;
+            state.progress.eval_stage(&eval_id, batch_id, ProgressStage::Saving);
+
+            let response = EvalResponse {
+                id: eval_id.clone(),
+                status: e.status().to_string(),
+                result: None,
+                error: Some(error.message.clone()),
+                error_code: Some(error.code.clone()),
+                preset: preset.clone(),
+            };
+
+            if let Some(pool_arc) = state.db_pool.as_ref() {
+                let api_response = crate::models::ApiResponse {
+                    id: eval_id,
+                    status: e.status().to_string(),
+                    result: crate::models::EvalResult::Error(error),
+                    batch_id: batch_id.map(str::to_string),
+                    config_hash: Some(config_hash),
+                    preset,
+                };
+                match crate::database::save_evaluation_retrying(pool_arc, &api_response).await {
+                    Ok(()) => link_prompt_version(pool_arc, &response.id, prompt_version).await,
+                    Err(e) => tracing::error!("Failed to save error evaluation to database: {}", e),
+                }
+            }
+            state.progress.eval_stage(&response.id, batch_id, ProgressStage::Error);
+            response
+        }
+    }
+}
+
+/// GET /api/v1/evals/batch/{batch_id} - Stored batch summary and its member evaluations
+pub async fn get_batch(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let batch_id = path.into_inner();
+
+    let pool = state.pool()?;
+    let batch = match crate::database::get_batch(pool, &batch_id).await {
+        Ok(batch) => batch,
+        Err(sqlx::Error::RowNotFound) => return Err(ApiError::not_found(format!("Batch {} not found", batch_id))),
+        Err(e) => return Err(ApiError::database("Failed to fetch batch", e)),
+    };
+
+    let evaluations = crate::database::get_batch_evaluations(pool, &batch_id)
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch batch evaluations", e))?;
     Ok(HttpResponse::Ok().json(json!({
-        "id": eval_id,
-        "status": "completed",
-        "message": "This endpoint would return stored eval results"
+        "batch": batch,
+        "evaluations": evaluations
     })))
 }
 
-pub async fn get_status(path: web::Path<String>) -> Result<HttpResponse> {
+#[derive(Deserialize)]
+pub struct BatchReportQuery {
+    #[serde(default)]
+    pub format: crate::report::ReportFormat,
+}
+
+/// GET /api/v1/evals/batch/{batch_id}/report - A Markdown (or `?format=html`) report of a batch:
+/// a summary per model, then each failed evaluation with its texts and the judge's reasoning
+pub async fn get_batch_report(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+    query: web::Query<BatchReportQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let batch_id = path.into_inner();
+
+    let pool = state.pool()?;
+    let batch = match crate::database::get_batch(pool, &batch_id).await {
+        Ok(batch) => batch,
+        Err(sqlx::Error::RowNotFound) => return Err(ApiError::not_found(format!("Batch {} not found", batch_id))),
+        Err(e) => return Err(ApiError::database("Failed to fetch batch", e)),
+    };
+    let evaluations = crate::database::get_batch_evaluations(pool, &batch_id)
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch batch evaluations", e))?;
+    Ok(HttpResponse::Ok()
+        .content_type(query.format.content_type())
+        .body(crate::report::render(&batch, &evaluations, query.format)))
+}
+
+/// POST /api/v1/evals/estimate - Estimate tokens and cost for a batch without running it
+pub async fn estimate_batch(
+    state: web::Data<AppState>,
+    eval_configs: web::Json<Vec<EvalConfig>>,
+) -> Result<HttpResponse, ApiError> {
+    let db_pool_ref = state.db_pool.as_ref().map(|arc| arc.as_ref());
+
+    let estimate = runner::estimate_batch(&state.config, &eval_configs, &CharsPerToken::default(), db_pool_ref).await?;
+    Ok(HttpResponse::Ok().json(estimate))
+}
+
+/// POST /api/v1/evals/{id}/explain - Replay rendering and parsing of a stored evaluation with the current code
+pub async fn explain_eval(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
     let eval_id = path.into_inner();
-    
-    Ok(HttpResponse::Ok().json(json!({
-        "id": eval_id,
-        "status": "completed",
-        "progress": 100
-    })))
+
+    let pool = state.pool()?;
+    let entry = match crate::database::get_evaluation(pool, &eval_id).await {
+        Ok(entry) => entry,
+        Err(sqlx::Error::RowNotFound) => return Err(ApiError::not_found(format!("Evaluation {} not found", eval_id))),
+        Err(e) => return Err(ApiError::database("Failed to fetch evaluation", e)),
+    };
+
+    // Rows saved before templates were stored fall back to their prompt version's template
+    let prompt_template = match (&entry.prompt_template, entry.prompt_version) {
+        (Some(template), _) => Some(template.clone()),
+        (None, Some(version)) => match crate::database::get_prompt_version(pool, version).await {
+            Ok(prompt) => Some(prompt.prompt_template),
+            Err(sqlx::Error::RowNotFound) => None,
+            Err(e) => return Err(ApiError::database("Failed to fetch prompt version", e)),
+        },
+        (None, None) => None,
+    };
+    let judge_template = match entry.judge_prompt_version {
+        Some(version) => match crate::database::get_judge_prompt_by_version(pool, version).await {
+            Ok(prompt) => Some(prompt.template),
+            Err(sqlx::Error::RowNotFound) => None,
+            Err(e) => return Err(ApiError::database("Failed to fetch judge prompt", e)),
+        },
+        None => None,
+    };
+
+    Ok(HttpResponse::Ok().json(runner::explain_evaluation(&entry, prompt_template.as_deref(), judge_template.as_deref())))
 }
 
-#[derive(Serialize)]
-pub struct HistoryResponse {
-    pub results: Vec<crate::database::HistoryEntry>,
-}
-
-pub async fn get_history(state: web::Data<AppState>) -> Result<HttpResponse> {
-    if let Some(pool_arc) = state.db_pool.as_ref() {
-        match crate::database::get_all_evaluations(pool_arc).await {
-            Ok(history) => Ok(HttpResponse::Ok().json(HistoryResponse { results: history })),
-            Err(e) => {
-                log::error!("Failed to fetch evaluation history: {}", e);
-                Ok(HttpResponse::InternalServerError()
-                    .json(json!({"error": "Failed to load history from database."})))
+/// GET /api/v1/evals/{id} - A stored evaluation, with its rendered judge prompt in full
+pub async fn get_eval(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let eval_id = path.into_inner();
+
+    let pool = state.pool()?;
+    match crate::database::get_evaluation(pool, &eval_id).await {
+        Ok(entry) => Ok(HttpResponse::Ok().json(entry)),
+        Err(sqlx::Error::RowNotFound) => Err(ApiError::not_found(format!("Evaluation {} not found", eval_id))),
+        Err(e) => Err(ApiError::database("Failed to fetch evaluation", e)),
+    }
+}
+
+/// GET /api/v1/evals/{id}/raw - The provider requests and responses captured for an evaluation
+/// run with `capture_raw` or `EVAL_CAPTURE_RAW`, in the order they were made
+pub async fn get_raw_exchanges(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let eval_id = path.into_inner();
+    let pool = state.pool()?;
+    let exchanges = crate::database::get_raw_exchanges(pool, &eval_id)
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch raw exchanges", e))?;
+    if exchanges.is_empty() {
+        return Err(ApiError::not_found(format!("No raw exchanges were captured for evaluation {}", eval_id)));
+    }
+    Ok(HttpResponse::Ok().json(json!({ "id": eval_id, "exchanges": exchanges })))
+}
+
+/// GET /api/v1/evals/{id}/status - Live progress of an eval or batch, or, once it is no
+/// longer tracked, its stored outcome
+pub async fn get_status(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+    if let Some(progress) = state.progress.get(&id) {
+        return Ok(HttpResponse::Ok().json(progress));
+    }
+
+    let pool = state.pool()?;
+    let failed = |e| ApiError::database("Failed to fetch status", e);
+    let progress = match crate::database::get_evaluation(pool, &id).await {
+        Ok(entry) => ProgressState::of_evaluation(&entry),
+        Err(sqlx::Error::RowNotFound) => match crate::database::get_batch(pool, &id).await {
+            Ok(batch) => {
+                let stored = crate::database::get_batch_evaluations(pool, &id).await.map_err(failed)?;
+                ProgressState::of_batch(&batch, stored.len())
             }
-        }
-    } else {
-        Ok(HttpResponse::Ok().json(HistoryResponse { results: vec![] }))
+            Err(sqlx::Error::RowNotFound) => {
+                return Err(ApiError::not_found(format!("No evaluation or batch {} found", id)));
+            }
+            Err(e) => return Err(failed(e)),
+        },
+        Err(e) => return Err(failed(e)),
+    };
+    Ok(HttpResponse::Ok().json(progress))
+}
+
+#[derive(Serialize)]
+pub struct HistoryResponse<T = crate::database::HistorySummary> {
+    pub results: Vec<T>,
+}
+
+#[derive(Deserialize)]
+pub struct HistoryQuery {
+    #[serde(flatten)]
+    pub filter: crate::database::HistoryFilter,
+    /// `true` for whole entries, as the history was listed before summaries; deprecated. A
+    /// string, as flattened query structs cannot take booleans
+    pub full: Option<String>,
+    /// Comma-separated `HistoryEntry` fields to add to each summary
+    pub fields: Option<String>,
+}
+
+/// GET /api/v1/evals/history - Evaluation summaries, newest first (`?uncertain_reason=`, `?tag=`
+/// and `?finish_reason=` to filter, `?fields=` to add fields of the entries, `?full=true` for
+/// the entries themselves)
+pub async fn get_history(
+    state: web::Data<AppState>,
+    http_req: HttpRequest,
+    query: web::Query<HistoryQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let HistoryQuery { filter, full, fields } = query.into_inner();
+    if let Some(reason) = filter.uncertain_reason.as_deref()
+        && runner::UncertainReason::parse(reason).is_none()
+    {
+        let valid: Vec<&str> = runner::UncertainReason::ALL.iter().map(|r| r.as_str()).collect();
+        return Err(ApiError::validation(format!("uncertain_reason must be one of: {}", valid.join(", "))));
+    }
+    let full = match full.as_deref() {
+        None | Some("false") => false,
+        Some("true") => true,
+        Some(other) => return Err(ApiError::validation(format!("full must be true or false, not '{}'", other))),
+    };
+    let fields: Vec<&str> = fields.as_deref().unwrap_or_default().split(',').map(str::trim).filter(|f| !f.is_empty()).collect();
+    if let Some(unknown) = fields.iter().find(|f| !crate::database::HISTORY_FIELDS.contains(f)) {
+        return Err(ApiError::validation(format!("Unknown history field '{}'", unknown)));
     }
+
+    let Some(pool_arc) = state.db_pool.as_ref() else {
+        return Ok(HttpResponse::Ok().json(HistoryResponse::<crate::database::HistorySummary> { results: vec![] }));
+    };
+    crate::api::etag::conditional(pool_arc, &http_req, || async {
+        let failed = |e| ApiError::database("Failed to load history from database.", e);
+        if !full && fields.is_empty() {
+            let results = crate::database::get_evaluation_summaries(pool_arc, &filter).await.map_err(failed)?;
+            return Ok(HttpResponse::Ok().json(HistoryResponse { results }));
+        }
+        let history = crate::database::get_evaluations(pool_arc, &filter).await.map_err(failed)?;
+        let entries = history.into_iter().map(crate::database::HistoryEntry::abbreviated);
+        if full {
+            return Ok(HttpResponse::Ok().json(HistoryResponse { results: entries.collect() }));
+        }
+        let results = entries
+            .map(|entry| {
+                let mut summary = json!(crate::database::HistorySummary::of(&entry));
+                let entry = json!(entry);
+                for field in &fields {
+                    summary[*field] = entry.get(*field).cloned().unwrap_or(serde_json::Value::Null);
+                }
+                summary
+            })
+            .collect();
+        Ok(HttpResponse::Ok().json(HistoryResponse::<serde_json::Value> { results }))
+    })
+    .await
 }
 
 #[derive(Serialize)]
 pub struct ModelsResponse {
     pub models: Vec<String>,
+    /// Model aliases and the `provider:model` each resolves to.
+    pub aliases: std::collections::BTreeMap<String, String>,
 }
 
-pub async fn get_models(state: web::Data<AppState>) -> Result<HttpResponse> {
-    Ok(HttpResponse::Ok().json(ModelsResponse { models: state.config.models.clone() }))
+pub async fn get_models(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    let aliases = state
+        .config
+        .model_aliases
+        .keys()
+        .map(|alias| (alias.clone(), state.config.resolve_model(alias).to_string()))
+        .collect();
+    Ok(HttpResponse::Ok().json(ModelsResponse { models: state.config.models.clone(), aliases }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::configure_routes;
+    use crate::database;
+    use actix_web::{http::StatusCode, test, App};
+    use std::sync::Arc;
+
+    async fn state() -> AppState {
+        AppState {
+            config: Arc::new(crate::config::AppConfig::default()),
+            client: reqwest::Client::new(),
+            db_pool: Some(Arc::new(database::test_pool().await)),
+            backup_in_progress: Default::default(),
+            active_batches: Default::default(),
+            retention: Default::default(),
+            rate_limiter: Default::default(),
+            provider_health: Default::default(),
+            progress: Default::default(),
+        }
+    }
+
+    fn invalid_fields(body: &serde_json::Value) -> Vec<&str> {
+        body["details"]["errors"].as_array().unwrap().iter().map(|e| e["field"].as_str().unwrap()).collect()
+    }
+
+    #[actix_web::test]
+    async fn test_unconfigured_provider_is_answered_as_provider_not_found() {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state().await))
+                .app_data(web::Data::new(WsBroker::new()))
+                .configure(configure_routes),
+        )
+        .await;
+        let suite = json!({ "name": "smoke", "configs": [{ "model": "anthropic:claude", "prompt": "Hi" }] });
+        let req = test::TestRequest::post().uri("/api/v1/suites").set_json(suite).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!(body["code"], "provider_not_found");
+        assert_eq!(body["details"]["provider"], "anthropic");
+        assert_eq!(body["details"]["index"], 0);
+    }
+
+    #[actix_web::test]
+    async fn test_invalid_requests_are_answered_as_validation_failed() {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state().await))
+                .app_data(web::Data::new(WsBroker::new()))
+                .configure(configure_routes),
+        )
+        .await;
+        let requests = [
+            (test::TestRequest::get().uri("/api/v1/evals/history?uncertain_reason=bogus"), StatusCode::BAD_REQUEST),
+            (test::TestRequest::get().uri("/api/v1/evals/history?fields=prompt,password"), StatusCode::BAD_REQUEST),
+            (test::TestRequest::get().uri("/api/v1/evals/history?full=yes"), StatusCode::BAD_REQUEST),
+            (test::TestRequest::get().uri("/api/v1/evals/history?tag=a&tag_match=most"), StatusCode::BAD_REQUEST),
+            (test::TestRequest::patch().uri("/api/v1/evals/eval-1/tags").set_json(json!({ "add": ["a,b"] })), StatusCode::BAD_REQUEST),
+            (test::TestRequest::patch().uri("/api/v1/evals/eval-1/tags").set_json(json!({})), StatusCode::BAD_REQUEST),
+            (test::TestRequest::get().uri("/api/v1/judge-prompts/compare?a=1&b=1"), StatusCode::BAD_REQUEST),
+            (test::TestRequest::get().uri("/api/v1/judge-prompts/compare?a=1"), StatusCode::BAD_REQUEST),
+            (test::TestRequest::get().uri("/api/v1/stats/token-distribution?bucket_width=0"), StatusCode::BAD_REQUEST),
+            (test::TestRequest::get().uri("/api/v1/stats/timeseries?bucket=fortnight"), StatusCode::BAD_REQUEST),
+            (
+                test::TestRequest::post().uri("/api/v1/evals/run").set_json(json!({ "prompt": "no model" })),
+                StatusCode::UNPROCESSABLE_ENTITY,
+            ),
+        ];
+        for (req, status) in requests {
+            let req = req.to_request();
+            let uri = req.uri().to_string();
+            let res = test::call_service(&app, req).await;
+            assert_eq!(res.status(), status, "{}", uri);
+            let body: serde_json::Value = test::read_body_json(res).await;
+            assert_eq!(body["code"], "validation_failed", "{}", uri);
+            assert!(body["message"].as_str().is_some_and(|m| !m.is_empty()), "{}", uri);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_eval_requests_list_every_invalid_field() {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state().await))
+                .app_data(web::Data::new(WsBroker::new()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(json!({ "model": "", "prompt": "" }));
+        let res = test::call_service(&app, req.to_request()).await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!(invalid_fields(&body), ["model", "prompt"]);
+        assert_eq!(body["details"]["errors"][0]["message"], "must not be empty");
+
+        let batch = json!([
+            { "model": "ollama:llama3", "prompt": "Hi", "metadata": [1] },
+            { "prompt": "no model" },
+        ]);
+        let req = test::TestRequest::post().uri("/api/v1/evals/batch").set_json(batch);
+        let body: serde_json::Value = test::read_body_json(test::call_service(&app, req.to_request()).await).await;
+        assert_eq!(invalid_fields(&body), ["[1].model"]);
+
+        let batch = json!([{ "model": "ollama:llama3", "prompt": "Hi", "metadata": [1] }, { "model": "x:", "prompt": "Hi" }]);
+        let req = test::TestRequest::post().uri("/api/v1/evals/batch").set_json(batch);
+        let body: serde_json::Value = test::read_body_json(test::call_service(&app, req.to_request()).await).await;
+        assert_eq!(invalid_fields(&body), ["[0].model", "[0].metadata", "[1].model"]);
+
+        // Webhooks are off without WEBHOOK_SECRET
+        let req = test::TestRequest::post()
+            .uri("/api/v1/evals/batch?callback_url=https://ci.example.com/hook")
+            .set_json(json!([{ "model": "", "prompt": "Hi" }]));
+        let body: serde_json::Value = test::read_body_json(test::call_service(&app, req.to_request()).await).await;
+        assert_eq!(invalid_fields(&body), ["[0].model", "callback_url"]);
+
+        let matrix = json!({ "models": ["ollama:llama3"], "prompts": [{ "prompt": "" }] });
+        let req = test::TestRequest::post().uri("/api/v1/evals/matrix").set_json(matrix);
+        let body: serde_json::Value = test::read_body_json(test::call_service(&app, req.to_request()).await).await;
+        assert_eq!(invalid_fields(&body), ["models[0]", "prompts[0].prompt"]);
+    }
+
+    #[actix_web::test]
+    async fn test_malformed_json_bodies_name_the_line_and_field() {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state().await))
+                .app_data(web::Data::new(WsBroker::new()))
+                .configure(configure_routes),
+        )
+        .await;
+        let post = |uri: &str, body: &str| {
+            test::TestRequest::post()
+                .uri(uri)
+                .insert_header((actix_web::http::header::CONTENT_TYPE, "application/json"))
+                .set_payload(body.to_string())
+                .to_request()
+        };
+
+        let res = test::call_service(&app, post("/api/v1/evals/run", "{\n  \"model\": \"ollama:llama3\",\n  \"prompt\": }")).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!(body["code"], "invalid_json");
+        assert_eq!(body["details"]["line"], 3);
+
+        let res = test::call_service(&app, post("/api/v1/evals/matrix", r#"{"prompts": []}"#)).await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!(body["code"], "validation_failed");
+        assert_eq!(invalid_fields(&body), ["models"]);
+    }
+
+    #[actix_web::test]
+    async fn test_finished_runs_are_posted_to_their_callback_url() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "response": "4", "done": true })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST")).and(path("/hook")).respond_with(ResponseTemplate::new(204)).mount(&server).await;
+
+        let secret = "ci-webhook-secret";
+        let state = AppState {
+            config: Arc::new(crate::config::AppConfig {
+                ollama: Some(crate::config::OllamaConfig { api_base: server.uri(), models: vec![] }),
+                webhook_secret: Some(secret.to_string()),
+                ..Default::default()
+            }),
+            ..state().await
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(WsBroker::new()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let hooks = || async {
+            for _ in 0..100 {
+                let requests = server.received_requests().await.unwrap();
+                let hooks: Vec<_> = requests.into_iter().filter(|r| r.url.path() == "/hook").collect();
+                if !hooks.is_empty() {
+                    return hooks;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            }
+            panic!("no webhook was delivered");
+        };
+
+        let callback_url = format!("{}/hook", server.uri());
+        let run = json!({ "model": "ollama:llama3", "prompt": "What is 2+2?", "callback_url": callback_url });
+        let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(&run);
+        let res = test::call_service(&app, req.to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let eval: serde_json::Value = test::read_body_json(res).await;
+
+        let delivered = hooks().await;
+        assert_eq!(delivered.len(), 1);
+        let signature = delivered[0].headers.get(webhook::SIGNATURE_HEADER).unwrap().to_str().unwrap();
+        assert_eq!(signature, webhook::sign(secret, &delivered[0].body));
+        let payload: WebhookPayload = serde_json::from_slice(&delivered[0].body).unwrap();
+        let WebhookPayload::EvalCompleted(summary) = payload else { panic!("expected eval.completed") };
+        assert_eq!(summary.eval_id, eval["id"].as_str().unwrap());
+        assert_eq!(summary.model, "ollama:llama3");
+
+        server.reset().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "response": "4", "done": true })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST")).and(path("/hook")).respond_with(ResponseTemplate::new(204)).mount(&server).await;
+
+        let uri = format!("/api/v1/evals/batch?callback_url={}", callback_url);
+        let req = test::TestRequest::post().uri(&uri).set_json(json!([run, run]));
+        let batch: serde_json::Value = test::read_body_json(test::call_service(&app, req.to_request()).await).await;
+
+        let delivered = hooks().await;
+        assert_eq!(webhook::sign(secret, &delivered[0].body), delivered[0].headers.get(webhook::SIGNATURE_HEADER).unwrap().to_str().unwrap());
+        let payload: serde_json::Value = serde_json::from_slice(&delivered[0].body).unwrap();
+        assert_eq!(payload["event"], "batch.completed");
+        assert_eq!(payload["batch_id"], batch["batch_id"]);
+        assert_eq!(payload["total"], 2);
+    }
+
+    #[actix_web::test]
+    async fn test_unfilled_placeholders_fail_only_their_own_eval_of_a_batch() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "response": "4", "done": true })))
+            .mount(&server)
+            .await;
+        let state = AppState {
+            config: Arc::new(crate::config::AppConfig {
+                ollama: Some(crate::config::OllamaConfig { api_base: server.uri(), models: vec![] }),
+                ..Default::default()
+            }),
+            ..state().await
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(WsBroker::new()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let filled = json!({ "model": "ollama:llama3", "prompt": "What is {{a}}+2?", "metadata": { "a": 2 } });
+        let unfilled = json!({ "model": "ollama:llama3", "prompt": "What is {{a}}+{{b}}?", "metadata": { "a": 2 } });
+        let req = test::TestRequest::post().uri("/api/v1/evals/batch").set_json(json!([filled, unfilled]));
+        let res = test::call_service(&app, req.to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let batch: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!(batch["results"][0]["result"]["prompt"], "What is 2+2?");
+        assert_eq!(batch["results"][1]["result"], serde_json::Value::Null);
+        assert_eq!(batch["results"][1]["error_code"], "invalid_config");
+        assert!(batch["results"][1]["error"].as_str().unwrap().contains("{{b}} (missing)"));
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+
+        let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(&unfilled);
+        assert_eq!(test::call_service(&app, req.to_request()).await.status(), StatusCode::BAD_REQUEST);
+        let mut lenient = unfilled;
+        lenient["allow_missing_vars"] = json!(true);
+        let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(&lenient);
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req.to_request()).await;
+        assert_eq!(body["result"]["prompt"], "What is 2+{{b}}?");
+    }
+
+    #[actix_web::test]
+    async fn test_cached_evals_skip_the_provider_and_judges_opt_in_separately() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "response": "Verdict: PASS", "done": true })))
+            .mount(&server)
+            .await;
+        let state = AppState {
+            config: Arc::new(crate::config::AppConfig {
+                ollama: Some(crate::config::OllamaConfig { api_base: server.uri(), models: vec![] }),
+                ..Default::default()
+            }),
+            ..state().await
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(WsBroker::new()))
+                .configure(configure_routes),
+        )
+        .await;
+        let calls = || async { server.received_requests().await.unwrap().len() };
+        let run = |body: serde_json::Value| {
+            let app = &app;
+            async move {
+                let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(body);
+                let res = test::call_service(app, req.to_request()).await;
+                assert_eq!(res.status(), StatusCode::OK);
+                test::read_body_json::<serde_json::Value, _>(res).await["result"].clone()
+            }
+        };
+
+        let eval = json!({ "model": "ollama:llama3", "prompt": "What is 2+2?", "cache": true });
+        let first = run(eval.clone()).await;
+        assert_eq!(first["cache_hit"], false);
+        assert_eq!(calls().await, 1);
+        let second = run(eval.clone()).await;
+        assert_eq!(calls().await, 1);
+        assert_eq!(second["cache_hit"], true);
+        assert_eq!(second["model_output"], first["model_output"]);
+        assert_eq!(second["latency_ms"], first["latency_ms"]);
+
+        // Without the flag the cache is neither read nor written
+        run(json!({ "model": "ollama:llama3", "prompt": "What is 2+2?" })).await;
+        assert_eq!(calls().await, 2);
+
+        // The judge is called every time unless it opts in too
+        let judged = json!({ "model": "ollama:llama3", "prompt": "What is 2+2?", "expected": "4", "judge_model": "ollama:llama3", "cache": true });
+        run(judged.clone()).await;
+        run(judged.clone()).await;
+        assert_eq!(calls().await, 4);
+        let mut judged_cached = judged.clone();
+        judged_cached["cache_judge"] = json!(true);
+        run(judged_cached.clone()).await;
+        let result = run(judged_cached).await;
+        assert_eq!(calls().await, 5);
+        assert_eq!(result["judge_result"]["verdict"], "Pass");
+
+        let req = test::TestRequest::delete().uri("/api/v1/cache").to_request();
+        let cleared: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(cleared["deleted"], 2);
+        run(eval).await;
+        assert_eq!(calls().await, 6);
+    }
+
+    #[actix_web::test]
+    async fn test_blocked_content_is_answered_as_422_and_recorded_as_blocked() {
+        use crate::config::GeminiConfig;
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let blocked = json!({
+            "promptFeedback": {
+                "blockReason": "SAFETY",
+                "safetyRatings": [{ "category": "HARM_CATEGORY_DANGEROUS_CONTENT", "probability": "HIGH", "blocked": true }]
+            }
+        });
+        Mock::given(method("POST")).respond_with(ResponseTemplate::new(200).set_body_json(blocked)).mount(&server).await;
+        let state = AppState {
+            config: Arc::new(crate::config::AppConfig {
+                gemini: Some(GeminiConfig {
+                    api_base: server.uri(),
+                    api_key: "test-key".to_string(),
+                    models: vec![],
+                    safety_threshold: SafetyThreshold::BlockOnlyHigh,
+                }),
+                ..Default::default()
+            }),
+            ..state().await
+        };
+        let pool = state.db_pool.clone().unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(WsBroker::new()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let eval = json!({ "model": "gemini:gemini-1.5-flash", "prompt": "How do I pick a lock?", "safety_threshold": "BLOCK_LOW_AND_ABOVE" });
+        let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(eval).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!(body["code"], "content_blocked");
+        assert_eq!(body["details"]["reason"], "SAFETY");
+        assert_eq!(body["details"]["categories"], json!(["HARM_CATEGORY_DANGEROUS_CONTENT"]));
+
+        // The eval's threshold replaces the configured one, and blocks are not retried
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests.len(), 1);
+        let sent: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(sent["safetySettings"][0]["threshold"], "BLOCK_LOW_AND_ABOVE");
+
+        let eval_id = body["details"]["eval_id"].as_str().unwrap();
+        let entry = database::get_evaluation(&pool, eval_id).await.unwrap();
+        assert_eq!(entry.status.as_deref(), Some("blocked"));
+    }
+
+    #[actix_web::test]
+    async fn test_response_format_is_asked_for_natively_or_in_the_prompt_and_stored() {
+        use crate::config::{AnthropicConfig, OpenAIConfig};
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "choices": [{ "message": { "content": "{\"answer\": 4}" }, "finish_reason": "stop" }]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "content": [{ "type": "text", "text": "{\"answer\": 4}" }],
+                "stop_reason": "end_turn",
+                "usage": { "input_tokens": 30, "output_tokens": 6 }
+            })))
+            .mount(&server)
+            .await;
+        let state = AppState {
+            config: Arc::new(crate::config::AppConfig {
+                openai: Some(OpenAIConfig { api_base: format!("{}/v1", server.uri()), api_key: "sk-test".to_string(), models: vec![] }),
+                anthropic: Some(AnthropicConfig { api_base: server.uri(), api_key: "sk-ant-test".to_string(), models: vec![] }),
+                ..Default::default()
+            }),
+            ..state().await
+        };
+        let pool = state.db_pool.clone().unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(WsBroker::new()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let schema = json!({ "type": "object", "properties": { "answer": { "type": "integer" } } });
+        let mut sent = Vec::new();
+        for model in ["openai:gpt-4o-mini", "anthropic:claude-haiku-4"] {
+            let eval = json!({
+                "model": model,
+                "prompt": "What is 2+2?",
+                "response_format": { "type": "json_schema", "schema": schema },
+            });
+            let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(eval).to_request();
+            let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+            assert_eq!(body["result"]["response_format"], "json_schema");
+            let entry = database::get_evaluation(&pool, body["id"].as_str().unwrap()).await.unwrap();
+            assert_eq!(entry.response_format.as_deref(), Some("json_schema"));
+            let requests = server.received_requests().await.unwrap();
+            sent.push(serde_json::from_slice::<serde_json::Value>(&requests.last().unwrap().body).unwrap());
+        }
+
+        // OpenAI gets structured outputs and the prompt as it was
+        assert_eq!(sent[0]["response_format"]["type"], "json_schema");
+        assert_eq!(sent[0]["response_format"]["json_schema"]["schema"], schema);
+        assert_eq!(sent[0]["messages"][0]["content"], "What is 2+2?");
+        // Anthropic has no JSON mode, so it is told in the prompt
+        assert!(sent[1].get("response_format").is_none());
+        let prompt = sent[1]["messages"][0]["content"].as_str().unwrap();
+        assert!(prompt.starts_with("What is 2+2?\n\nRespond only with JSON matching this JSON schema"), "{}", prompt);
+
+        let invalid = json!({ "model": "openai:gpt-4o-mini", "prompt": "Hi", "response_format": { "type": "json_schema", "schema": "object" } });
+        let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(invalid).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!(invalid_fields(&body), ["response_format.schema"]);
+    }
+
+    #[actix_web::test]
+    async fn test_stop_sequences_and_output_limits_are_sent_in_each_providers_terms() {
+        use crate::config::{AnthropicConfig, OllamaConfig, OpenAIConfig};
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "choices": [{ "message": { "content": "4" }, "finish_reason": "stop" }]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "content": [{ "type": "text", "text": "4" }],
+                "stop_reason": "stop_sequence",
+                "stop_sequence": "###",
+                "usage": { "input_tokens": 12, "output_tokens": 2 }
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "response": "4", "done": true, "done_reason": "length" })))
+            .mount(&server)
+            .await;
+        let state = AppState {
+            config: Arc::new(crate::config::AppConfig {
+                openai: Some(OpenAIConfig { api_base: format!("{}/v1", server.uri()), api_key: "sk-test".to_string(), models: vec![] }),
+                anthropic: Some(AnthropicConfig { api_base: server.uri(), api_key: "sk-ant-test".to_string(), models: vec![] }),
+                ollama: Some(OllamaConfig { api_base: server.uri(), models: vec![] }),
+                ..Default::default()
+            }),
+            ..state().await
+        };
+        let pool = state.db_pool.clone().unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(WsBroker::new()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let mut sent = Vec::new();
+        let mut finish_reasons = Vec::new();
+        for model in ["openai:gpt-4o-mini", "anthropic:claude-haiku-4", "ollama:llama3"] {
+            let eval = json!({ "model": model, "prompt": "What is 2+2?", "stop": ["###"], "max_output_tokens": 64 });
+            let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(eval).to_request();
+            let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+            let entry = database::get_evaluation(&pool, body["id"].as_str().unwrap()).await.unwrap();
+            finish_reasons.push(entry.finish_reason);
+            let requests = server.received_requests().await.unwrap();
+            sent.push(serde_json::from_slice::<serde_json::Value>(&requests.last().unwrap().body).unwrap());
+        }
+
+        assert_eq!(sent[0]["stop"], json!(["###"]));
+        assert_eq!(sent[0]["max_tokens"], 64);
+        assert_eq!(sent[1]["stop_sequences"], json!(["###"]));
+        assert_eq!(sent[1]["max_tokens"], 64);
+        assert_eq!(sent[2]["options"], json!({ "stop": ["###"], "num_predict": 64 }));
+        assert_eq!(finish_reasons, [Some("stop".to_string()), Some("stop_sequence".to_string()), Some("length".to_string())]);
+
+        let invalid = json!({ "model": "openai:gpt-4o-mini", "prompt": "Hi", "stop": [""], "max_output_tokens": 0 });
+        let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(invalid).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!(invalid_fields(&body), ["stop", "max_output_tokens"]);
+    }
+
+    #[actix_web::test]
+    async fn test_refused_and_cut_off_outputs_are_uncertain_without_a_judge_call() {
+        use crate::config::{OllamaConfig, OpenAIConfig};
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "choices": [{
+                    "message": { "role": "assistant", "content": null, "refusal": "I can't help with that." },
+                    "finish_reason": "stop"
+                }]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .and(body_partial_json(json!({ "model": "qwen3" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "response": "The answer", "done": true, "done_reason": "length" })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .and(body_partial_json(json!({ "model": "llama3" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "response": "Verdict: PASS", "done": true })))
+            .expect(0)
+            .mount(&server)
+            .await;
+        let state = AppState {
+            config: Arc::new(crate::config::AppConfig {
+                openai: Some(OpenAIConfig { api_base: format!("{}/v1", server.uri()), api_key: "sk-test".to_string(), models: vec![] }),
+                ollama: Some(OllamaConfig { api_base: server.uri(), models: vec![] }),
+                ..Default::default()
+            }),
+            ..state().await
+        };
+        let pool = state.db_pool.clone().unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(WsBroker::new()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        for (model, finish_reason) in [("openai:gpt-4o-mini", "refusal"), ("ollama:qwen3", "length")] {
+            let eval = json!({ "model": model, "prompt": "What is 2+2?", "expected": "4", "judge_model": "ollama:llama3" });
+            let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(eval).to_request();
+            let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+            assert_eq!(body["result"]["judge_result"]["verdict"], "Uncertain", "{}", model);
+            assert_eq!(body["result"]["judge_result"]["judge_model"], "finish-reason");
+            let entry = database::get_evaluation(&pool, body["id"].as_str().unwrap()).await.unwrap();
+            assert_eq!(entry.finish_reason.as_deref(), Some(finish_reason));
+            let expected_reason = if finish_reason == "length" { "length_limit" } else { finish_reason };
+            assert_eq!(entry.uncertain_reason.as_deref(), Some(expected_reason));
+        }
+
+        let req = test::TestRequest::get().uri("/api/v1/evals/history?finish_reason=refusal").to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let results = body["results"].as_array().unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0]["output_preview"], "I can't help with that.");
+    }
+
+    #[actix_web::test]
+    async fn test_images_are_sent_and_stored_by_hash_and_size() {
+        use crate::config::{OllamaConfig, OpenAIConfig};
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "choices": [{ "message": { "content": "A cat" }, "finish_reason": "stop" }]
+            })))
+            .mount(&server)
+            .await;
+        let state = AppState {
+            config: Arc::new(crate::config::AppConfig {
+                openai: Some(OpenAIConfig { api_base: format!("{}/v1", server.uri()), api_key: "sk-test".to_string(), models: vec![] }),
+                ollama: Some(OllamaConfig { api_base: server.uri(), models: vec![] }),
+                ..Default::default()
+            }),
+            ..state().await
+        };
+        let pool = state.db_pool.clone().unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(WsBroker::new()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let image = "data:image/png;base64,iVBORw0KGgo=";
+        let eval = json!({ "model": "openai:gpt-4o", "prompt": "What animal is this?", "images": [image] });
+        let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(eval).to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let sent: serde_json::Value = serde_json::from_slice(&server.received_requests().await.unwrap()[0].body).unwrap();
+        assert_eq!(sent["messages"][0]["content"][1]["image_url"]["url"], image);
+
+        let entry = database::get_evaluation(&pool, body["id"].as_str().unwrap()).await.unwrap();
+        let stored = serde_json::to_value(&entry.images).unwrap();
+        assert_eq!(stored[0]["type"], "inline");
+        assert_eq!(stored[0]["bytes"], 8);
+        assert!(!stored.to_string().contains("iVBORw0KGgo="), "{}", stored);
+
+        let url = json!({ "model": "ollama:llava", "prompt": "What animal is this?", "images": ["https://example.com/cat.png"] });
+        let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(url).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!(body["code"], "invalid_config");
+        assert!(body["message"].as_str().unwrap().contains("'ollama'"), "{}", body);
+
+        let too_many = vec![image; crate::providers::images::MAX_IMAGES + 1];
+        let invalid = json!({ "model": "openai:gpt-4o", "prompt": "Hi", "images": too_many });
+        let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(invalid).to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(invalid_fields(&body), ["images"]);
+        let invalid = json!({ "model": "openai:gpt-4o", "prompt": "Hi", "images": ["cat.png"] });
+        let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(invalid).to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(invalid_fields(&body), ["images[0]"]);
+    }
+
+    #[actix_web::test]
+    async fn test_reasoning_is_stored_apart_and_judged_only_when_asked() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .and(body_partial_json(json!({ "model": "qwen3" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "response": "<think>\n2 plus 2 is 4.\n</think>\n\nThe answer is 4.", "done": true
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .and(body_partial_json(json!({ "model": "llama3" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "response": "Verdict: PASS", "done": true })))
+            .mount(&server)
+            .await;
+        let state = AppState {
+            config: Arc::new(crate::config::AppConfig {
+                ollama: Some(crate::config::OllamaConfig { api_base: server.uri(), models: vec![] }),
+                ..Default::default()
+            }),
+            ..state().await
+        };
+        let pool = state.db_pool.clone().unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(WsBroker::new()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let mut judge_prompts = Vec::new();
+        for include_reasoning in [false, true] {
+            let eval = json!({
+                "model": "ollama:qwen3",
+                "prompt": "What is 2+2?",
+                "expected": "4",
+                "judge_model": "ollama:llama3",
+                "include_reasoning": include_reasoning,
+            });
+            let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(eval).to_request();
+            let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+            assert_eq!(body["result"]["model_output"], "The answer is 4.");
+            assert_eq!(body["result"]["reasoning"], "2 plus 2 is 4.");
+            let entry = database::get_evaluation(&pool, body["id"].as_str().unwrap()).await.unwrap();
+            assert_eq!(entry.model_output.as_deref(), Some("The answer is 4."));
+            assert_eq!(entry.reasoning.as_deref(), Some("2 plus 2 is 4."));
+            let requests = server.received_requests().await.unwrap();
+            let judge_call: serde_json::Value = serde_json::from_slice(&requests.last().unwrap().body).unwrap();
+            judge_prompts.push(judge_call["prompt"].as_str().unwrap().to_string());
+        }
+
+        assert!(judge_prompts[0].contains("The answer is 4."));
+        assert!(!judge_prompts[0].contains("2 plus 2 is 4."), "{}", judge_prompts[0]);
+        assert!(judge_prompts[1].contains("<think>\n2 plus 2 is 4.\n</think>\n\nThe answer is 4."), "{}", judge_prompts[1]);
+    }
+
+    #[actix_web::test]
+    async fn test_rendered_judge_prompt_is_stored_whole_and_shortened_in_history() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .and(body_partial_json(json!({ "model": "qwen3" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "response": "The answer is 4.", "done": true })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .and(body_partial_json(json!({ "model": "llama3" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "response": "Verdict: PASS", "done": true })))
+            .mount(&server)
+            .await;
+        let state = AppState {
+            config: Arc::new(crate::config::AppConfig {
+                ollama: Some(crate::config::OllamaConfig { api_base: server.uri(), models: vec![] }),
+                ..Default::default()
+            }),
+            ..state().await
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(WsBroker::new()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let criteria = "The answer must be the number four. ".repeat(20);
+        let eval = json!({
+            "model": "ollama:qwen3",
+            "prompt": "What is 2+2?",
+            "expected": "4",
+            "judge_model": "ollama:llama3",
+            "criteria": criteria,
+        });
+        let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(eval).to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let requests = server.received_requests().await.unwrap();
+        let judge_call: serde_json::Value = serde_json::from_slice(&requests.last().unwrap().body).unwrap();
+        let sent = judge_call["prompt"].as_str().unwrap();
+        assert!(sent.contains(criteria.trim_end()), "{}", sent);
+        assert_eq!(body["result"]["judge_prompt_rendered"], sent);
+
+        let req = test::TestRequest::get().uri(&format!("/api/v1/evals/{}", body["id"].as_str().unwrap())).to_request();
+        let detail: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(detail["judge_prompt_rendered"], sent);
+        assert_eq!(detail["judge_raw_response"], "Verdict: PASS");
+
+        let req = test::TestRequest::get().uri("/api/v1/evals/history?fields=judge_prompt_rendered").to_request();
+        let history: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let preview = history["results"][0]["judge_prompt_rendered"].as_str().unwrap();
+        assert_eq!(preview.chars().count(), database::JUDGE_PROMPT_PREVIEW_CHARS + 1);
+        assert!(preview.ends_with('…') && sent.starts_with(preview.trim_end_matches('…')));
+
+        let req = test::TestRequest::get().uri("/api/v1/evals/missing").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn test_aliased_models_run_and_are_stored_as_their_target() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .and(body_partial_json(json!({ "model": "qwen3" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "response": "The answer is 4.", "done": true })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .and(body_partial_json(json!({ "model": "llama3" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "response": "Verdict: PASS", "done": true })))
+            .mount(&server)
+            .await;
+        let state = AppState {
+            config: Arc::new(crate::config::AppConfig {
+                ollama: Some(crate::config::OllamaConfig { api_base: server.uri(), models: vec![] }),
+                model_aliases: [("fast", "ollama:qwen3"), ("judge", "strict"), ("strict", "ollama:llama3")]
+                    .map(|(alias, target)| (alias.to_string(), target.to_string()))
+                    .into(),
+                ..Default::default()
+            }),
+            ..state().await
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(WsBroker::new()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let eval = json!({ "model": "fast", "prompt": "What is 2+2?", "expected": "4", "judge_model": "judge" });
+        let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(eval).to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["status"], "passed", "{}", body);
+        assert_eq!(body["result"]["model"], "ollama:qwen3");
+        assert_eq!(body["result"]["model_alias"], "fast");
+        assert_eq!(body["result"]["judge_result"]["judge_model"], "ollama:llama3");
+
+        let req = test::TestRequest::get().uri(&format!("/api/v1/evals/{}", body["id"].as_str().unwrap())).to_request();
+        let detail: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!((&detail["model"], &detail["model_alias"]), (&json!("ollama:qwen3"), &json!("fast")));
+
+        let req = test::TestRequest::get().uri("/api/v1/models").to_request();
+        let models: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(models["aliases"], json!({ "fast": "ollama:qwen3", "judge": "ollama:llama3", "strict": "ollama:llama3" }));
+    }
+
+    #[actix_web::test]
+    async fn test_judge_params_are_sent_and_an_uncertain_verdict_is_retried() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .and(body_partial_json(json!({ "model": "qwen3" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "response": "The answer is 4.", "done": true })))
+            .mount(&server)
+            .await;
+        let judge_options = json!({ "temperature": 0.0, "num_predict": 64, "seed": 7 });
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .and(body_partial_json(json!({ "model": "llama3", "options": judge_options })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "response": "I cannot tell.", "done": true })))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .and(body_partial_json(json!({ "model": "llama3", "options": judge_options })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "response": "Verdict: PASS", "done": true })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let state = AppState {
+            config: Arc::new(crate::config::AppConfig {
+                ollama: Some(crate::config::OllamaConfig { api_base: server.uri(), models: vec![] }),
+                judge_params: crate::config::JudgeParams { seed: Some(7), ..Default::default() },
+                ..Default::default()
+            }),
+            ..state().await
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(WsBroker::new()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let eval = json!({
+            "model": "ollama:qwen3",
+            "prompt": "What is 2+2?",
+            "expected": "4",
+            "judge_model": "ollama:llama3",
+            "judge_params": { "max_tokens": 64, "retry_uncertain": true },
+        });
+        let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(eval).to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["status"], "passed", "{}", body);
+        let params = json!({ "temperature": 0.0, "max_tokens": 64, "seed": 7, "retry_uncertain": true });
+        assert_eq!(body["result"]["judge_params"], params);
+
+        let req = test::TestRequest::get().uri(&format!("/api/v1/evals/{}", body["id"].as_str().unwrap())).to_request();
+        let detail: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(detail["judge_params"], params);
+        assert_eq!(detail["judge_verdict"], "Pass");
+
+        let eval = json!({ "model": "ollama:qwen3", "prompt": "2+2?", "judge_params": { "temperature": 3.0, "max_tokens": 0 } });
+        let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(eval).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!(invalid_fields(&body), ["judge_params.temperature", "judge_params.max_tokens"]);
+    }
+
+    #[actix_web::test]
+    async fn test_batches_keep_the_judge_prompt_they_started_with() {
+        use std::time::Duration;
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .and(body_partial_json(json!({ "model": "qwen3" })))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "response": "The answer is 4.", "done": true }))
+                    .set_delay(Duration::from_millis(300)),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .and(body_partial_json(json!({ "model": "llama3" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "response": "Verdict: PASS", "done": true })))
+            .mount(&server)
+            .await;
+        let state = AppState {
+            config: Arc::new(crate::config::AppConfig {
+                ollama: Some(crate::config::OllamaConfig { api_base: server.uri(), models: vec![] }),
+                ..Default::default()
+            }),
+            ..state().await
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(WsBroker::new()))
+                .configure(configure_routes),
+        )
+        .await;
+        let create = |name: &str| {
+            let prompt = json!({ "name": name, "template": format!("{}: {{{{expected}}}} vs {{{{actual}}}}", name), "set_active": true });
+            test::TestRequest::post().uri("/api/v1/judge-prompts").set_json(prompt).to_request()
+        };
+        let eval = json!({ "model": "ollama:qwen3", "prompt": "What is 2+2?", "expected": "4", "judge_model": "ollama:llama3" });
+
+        let created: serde_json::Value = test::call_and_read_body_json(&app, create("First")).await;
+        let first = created["prompt"]["version"].clone();
+        let in_flight = async {
+            let req = test::TestRequest::post().uri("/api/v1/evals/batch").set_json(json!([eval, eval])).to_request();
+            test::call_and_read_body_json::<_, _, serde_json::Value>(&app, req).await
+        };
+        let change = async {
+            // The batch has taken its snapshot once its first model call is in
+            while server.received_requests().await.unwrap().is_empty() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            let created: serde_json::Value = test::call_and_read_body_json(&app, create("Second")).await;
+            let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(&eval).to_request();
+            let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+            (created["prompt"]["version"].clone(), body)
+        };
+        let (batch, (second, after_change)) = futures::join!(in_flight, change);
+
+        assert_ne!(first, second);
+        for result in batch["results"].as_array().unwrap() {
+            assert_eq!(result["result"]["judge_prompt_version"], first, "{}", result);
+            assert!(result["result"]["judge_prompt_rendered"].as_str().unwrap().starts_with("First: 4 vs The answer is 4."));
+        }
+        assert_eq!(after_change["result"]["judge_prompt_version"], second, "{}", after_change);
+        assert!(after_change["result"]["judge_prompt_rendered"].as_str().unwrap().starts_with("Second: 4 vs The answer is 4."));
+
+        let req = test::TestRequest::put().uri("/api/v1/judge-prompts/active").set_json(json!({ "version": first })).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+        let req = test::TestRequest::post().uri("/api/v1/evals/batch").set_json(json!([eval])).to_request();
+        let batch: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(batch["results"][0]["result"]["judge_prompt_version"], first);
+    }
+
+    #[actix_web::test]
+    async fn test_eval_ids_match_across_the_response_live_updates_and_stored_record() {
+        use crate::api::handlers::ws::Subscription;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "response": "4", "done": true })))
+            .mount(&server)
+            .await;
+        let state = AppState {
+            config: Arc::new(crate::config::AppConfig {
+                ollama: Some(crate::config::OllamaConfig { api_base: server.uri(), models: vec![] }),
+                ..Default::default()
+            }),
+            ..state().await
+        };
+        let pool = state.db_pool.clone().unwrap();
+        let broker = WsBroker::new();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(broker.clone()))
+                .configure(configure_routes),
+        )
+        .await;
+        let ids_of = |event: &str, events: &[WsEvent]| -> Vec<String> {
+            events
+                .iter()
+                .map(|e| serde_json::to_value(e).unwrap())
+                .filter(|e| e["event"] == event)
+                .map(|e| e["id"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        let eval = json!({ "model": "ollama:llama3", "prompt": "What is 2+2?" });
+        let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(&eval).to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let id = body["id"].as_str().unwrap().to_string();
+        assert_eq!(body["result"]["id"], id);
+        assert_eq!(database::get_evaluation(&pool, &id).await.unwrap().id, id);
+        let events = broker.replay(usize::MAX, &Subscription::default()).await;
+        for event in ["eval_started", "model_completed", "eval_completed"] {
+            assert_eq!(ids_of(event, &events), std::slice::from_ref(&id), "{}", event);
+        }
+
+        let unfilled = json!({ "model": "ollama:llama3", "prompt": "What is {{a}}+2?" });
+        let req = test::TestRequest::post().uri("/api/v1/evals/batch").set_json(json!([eval, unfilled])).to_request();
+        let batch: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let ids: Vec<String> = batch["results"].as_array().unwrap().iter().map(|r| r["id"].as_str().unwrap().to_string()).collect();
+        assert_eq!(batch["results"][0]["result"]["id"], ids[0]);
+        assert_eq!(batch["results"][1]["error_code"], "invalid_config");
+        let stored = database::get_batch_evaluations(&pool, batch["batch_id"].as_str().unwrap()).await.unwrap();
+        let mut stored_ids: Vec<String> = stored.into_iter().map(|entry| entry.id).collect();
+        let mut expected = ids.clone();
+        stored_ids.sort();
+        expected.sort();
+        assert_eq!(stored_ids, expected);
+        let subscription = Subscription { batch_id: batch["batch_id"].as_str().map(str::to_string), model: None };
+        let events = broker.replay(usize::MAX, &subscription).await;
+        assert_eq!(ids_of("eval_started", &events), ids);
+        assert_eq!(ids_of("eval_completed", &events), std::slice::from_ref(&ids[0]));
+        assert_eq!(ids_of("error", &events), std::slice::from_ref(&ids[1]));
+    }
+
+    #[actix_web::test]
+    async fn test_a_failed_judge_call_is_kept_apart_from_an_unjudged_eval() {
+        use crate::api::handlers::ws::Subscription;
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .and(body_partial_json(json!({ "model": "qwen3" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "response": "The answer is 4.", "done": true })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .and(body_partial_json(json!({ "model": "llama3" })))
+            .respond_with(ResponseTemplate::new(500).set_body_string("judge down"))
+            .mount(&server)
+            .await;
+        let state = AppState {
+            config: Arc::new(crate::config::AppConfig {
+                ollama: Some(crate::config::OllamaConfig { api_base: server.uri(), models: vec![] }),
+                provider_retry: crate::providers::middleware::RetryPolicy { max_retries: 0, ..Default::default() },
+                ..Default::default()
+            }),
+            ..state().await
+        };
+        let broker = WsBroker::new();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(broker.clone()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let judged = json!({ "model": "ollama:qwen3", "prompt": "What is 2+2?", "expected": "4", "judge_model": "ollama:llama3" });
+        let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(&judged).to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["status"], "judge_error", "{}", body);
+        assert_eq!(body["result"]["model_output"], "The answer is 4.");
+        assert_eq!(body["result"]["judge_result"], serde_json::Value::Null);
+        let judge_error = &body["result"]["judge_error"];
+        assert_eq!((&judge_error["judge_model"], &judge_error["code"], &judge_error["status"]), (&json!("ollama:llama3"), &json!("provider_error"), &json!(500)));
+        assert!(judge_error["message"].as_str().unwrap().contains("judge down"), "{}", judge_error);
+
+        let req = test::TestRequest::get().uri(&format!("/api/v1/evals/{}", body["id"].as_str().unwrap())).to_request();
+        let detail: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!((&detail["status"], &detail["judge_error"]), (&json!("judge_error"), judge_error));
+        let events = broker.replay(usize::MAX, &Subscription::default()).await;
+        let completed = events.iter().map(|e| serde_json::to_value(e).unwrap()).find(|e| e["event"] == "eval_completed").unwrap();
+        assert_eq!((&completed["status"], &completed["judge_error"]), (&json!("judge_error"), judge_error));
+
+        let unjudged = json!({ "model": "ollama:qwen3", "prompt": "What is 2+2?" });
+        let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(&unjudged).to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["status"], "completed");
+        assert!(body["result"].get("judge_error").is_none(), "{}", body);
+    }
+
+    #[actix_web::test]
+    async fn test_outputs_matching_the_expected_output_pass_without_a_judge_call() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .and(body_partial_json(json!({ "model": "qwen3" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "response": " 4.0\n", "done": true })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .and(body_partial_json(json!({ "model": "llama3" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "response": "Verdict: PASS", "done": true })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let state = AppState {
+            config: Arc::new(crate::config::AppConfig {
+                ollama: Some(crate::config::OllamaConfig { api_base: server.uri(), models: vec![] }),
+                ..Default::default()
+            }),
+            ..state().await
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(WsBroker::new()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let mut eval = json!({ "model": "ollama:qwen3", "prompt": "What is 2+2?", "expected": "4", "judge_model": "ollama:llama3" });
+        let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(&eval).to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["status"], "passed", "{}", body);
+        assert_eq!(body["result"]["judge_result"]["judge_model"], "exact-match");
+        assert_eq!((&body["result"]["judge_latency_ms"], &body["result"]["judge_cost_usd"]), (&json!(0), &json!(0.0)));
+        assert_eq!(body["result"]["judge_prompt_rendered"], serde_json::Value::Null);
+        let req = test::TestRequest::get().uri(&format!("/api/v1/evals/{}", body["id"].as_str().unwrap())).to_request();
+        let detail: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!((&detail["judge_model"], &detail["judge_verdict"]), (&json!("exact-match"), &json!("Pass")));
+
+        eval["match"] = json!({ "mode": "exact" });
+        let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(&eval).to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["result"]["judge_result"]["judge_model"], "ollama:llama3", "{}", body);
+
+        eval["match"] = json!({ "mode": "numeric", "tolerance": -1 });
+        let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(&eval).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!(invalid_fields(&body), ["match.tolerance"]);
+    }
+
+    #[actix_web::test]
+    async fn test_chain_steps_read_earlier_outputs_and_stop_on_failure() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let generate = |prompt: &str, response: &str| {
+            Mock::given(method("POST"))
+                .and(path("/api/generate"))
+                .and(body_partial_json(json!({ "prompt": prompt })))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "response": response, "done": true })))
+        };
+        generate("List the cities in: I flew from Paris to Lyon.", "Paris, Lyon").mount(&server).await;
+        generate("Summarize: Paris, Lyon", "Two French cities").mount(&server).await;
+        generate("Paris, Lyon | Two French cities", "Done").mount(&server).await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .and(body_partial_json(json!({ "model": "llama3" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "response": "Verdict: FAIL", "done": true })))
+            .mount(&server)
+            .await;
+        let state = AppState {
+            config: Arc::new(crate::config::AppConfig {
+                ollama: Some(crate::config::OllamaConfig { api_base: server.uri(), models: vec![] }),
+                ..Default::default()
+            }),
+            ..state().await
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(WsBroker::new()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let mut chain = json!({ "steps": [
+            { "model": "ollama:qwen3", "prompt": "List the cities in: {{text}}", "metadata": { "text": "I flew from Paris to Lyon." } },
+            { "model": "ollama:qwen3", "prompt": "Summarize: {{previous_output}}", "expected": "Two French cities", "judge_model": "ollama:llama3" },
+            { "model": "ollama:qwen3", "prompt": "{{step_1_output}} | {{step_2_output}}" },
+        ]});
+        let req = test::TestRequest::post().uri("/api/v1/evals/chain").set_json(&chain).to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!((&body["status"], &body["total"], &body["skipped"]), (&json!("completed"), &json!(3), &json!(0)), "{}", body);
+        let outputs: Vec<&serde_json::Value> = body["steps"].as_array().unwrap().iter().map(|s| &s["result"]["model_output"]).collect();
+        assert_eq!(outputs, [&json!("Paris, Lyon"), &json!("Two French cities"), &json!("Done")]);
+        assert_eq!(body["steps"][1]["status"], "passed");
+        let req = test::TestRequest::get().uri(&format!("/api/v1/evals/{}", body["steps"][2]["id"].as_str().unwrap())).to_request();
+        let detail: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!((&detail["chain_id"], &detail["chain_step"]), (&body["chain_id"], &json!(3)));
+
+        chain["steps"][0]["expected"] = json!("Berlin");
+        chain["steps"][0]["judge_model"] = json!("ollama:llama3");
+        chain["stop_on_failure"] = json!(true);
+        let req = test::TestRequest::post().uri("/api/v1/evals/chain").set_json(&chain).to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!((&body["status"], &body["skipped"]), (&json!("failed"), &json!(2)), "{}", body);
+        assert_eq!(body["steps"].as_array().unwrap().len(), 1);
+
+        chain["steps"][0]["prompt"] = json!("{{previous_output}}");
+        let req = test::TestRequest::post().uri("/api/v1/evals/chain").set_json(&chain).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!(invalid_fields(&body), ["steps[0].prompt"]);
+    }
+
+    #[actix_web::test]
+    async fn test_json_outputs_are_diffed_and_can_be_judged_by_the_diff() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "response": "Here it is: {\"city\": \"Paris\", \"population\": 2.1, \"id\": 7}",
+                "done": true,
+            })))
+            .mount(&server)
+            .await;
+        let state = AppState {
+            config: Arc::new(crate::config::AppConfig {
+                ollama: Some(crate::config::OllamaConfig { api_base: server.uri(), models: vec![] }),
+                ..Default::default()
+            }),
+            ..state().await
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(WsBroker::new()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let mut eval = json!({
+            "model": "ollama:qwen3",
+            "prompt": "Describe Paris as JSON",
+            "expected": "{\"city\": \"Paris\", \"population\": 2.1, \"id\": 1}",
+            "diff": { "verdict": true, "ignored_paths": ["$.id"] },
+        });
+        let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(&eval).to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["status"], "passed", "{}", body);
+        assert_eq!(body["result"]["judge_result"]["judge_model"], "json-diff");
+        let req = test::TestRequest::get().uri(&format!("/api/v1/evals/{}", body["id"].as_str().unwrap())).to_request();
+        let detail: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(detail["parse_method"], "embedded_json");
+        assert_eq!(detail["output_diff"], json!({
+            "matches": true,
+            "differences": [{ "path": "$.id", "kind": "value_mismatch", "expected": 1, "actual": 7, "ignored": true }],
+        }));
+
+        eval["diff"] = json!({ "verdict": true });
+        let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(&eval).to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["status"], "failed", "{}", body);
+        assert_eq!(body["result"]["judge_result"]["reasoning"], "Parsed output differs from the expected JSON at $.id");
+
+        eval["diff"] = json!({ "ignored_paths": ["id"] });
+        let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(&eval).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!(invalid_fields(&body), ["diff.ignored_paths[0]"]);
+    }
+
+    #[actix_web::test]
+    async fn test_provider_rate_limits_space_out_calls_across_a_batch() {
+        use crate::providers::rate_limit::{ProviderLimiters, ProviderRateLimit};
+        use std::sync::Mutex;
+        use std::time::{Duration, Instant};
+        use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+        /// Answers every call, noting when it arrived.
+        struct Arrivals(Arc<Mutex<Vec<Instant>>>);
+        impl Respond for Arrivals {
+            fn respond(&self, _request: &Request) -> ResponseTemplate {
+                self.0.lock().unwrap().push(Instant::now());
+                ResponseTemplate::new(200).set_body_json(json!({ "response": "4", "done": true }))
+            }
+        }
+
+        let server = MockServer::start().await;
+        let arrivals = Arc::new(Mutex::new(Vec::new()));
+        Mock::given(wiremock::matchers::method("POST")).respond_with(Arrivals(arrivals.clone())).mount(&server).await;
+        // 300 requests a minute: one every 200ms
+        let limit = ProviderRateLimit { rpm: Some(300), tpm: None };
+        let state = AppState {
+            config: Arc::new(crate::config::AppConfig {
+                ollama: Some(crate::config::OllamaConfig { api_base: server.uri(), models: vec![] }),
+                provider_limiters: Arc::new(ProviderLimiters::new([("ollama".to_string(), limit)])),
+                ..Default::default()
+            }),
+            ..state().await
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(WsBroker::new()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let batch: Vec<_> = (0..3).map(|i| json!({ "model": "ollama:llama3", "prompt": format!("Question {}", i) })).collect();
+        let req = test::TestRequest::post().uri("/api/v1/evals/batch").set_json(batch).to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        // The last call waited for two permits, which shows as wait time rather than model latency
+        let latency = &body["latency"];
+        assert_eq!((&latency["model"]["count"], &latency["wait"]["count"]), (&json!(3), &json!(3)), "{}", body);
+        assert!(latency["wait"]["max"].as_u64().unwrap() >= 350, "{}", latency);
+        assert!(latency["model"]["max"].as_u64().unwrap() < 350, "{}", latency);
+        assert!(latency.get("by_model").is_none());
+
+        let arrivals = arrivals.lock().unwrap().clone();
+        assert_eq!(arrivals.len(), 3);
+        for pair in arrivals.windows(2) {
+            let gap = pair[1] - pair[0];
+            assert!(gap >= Duration::from_millis(180), "calls only {:?} apart", gap);
+        }
+
+        let req = test::TestRequest::get().uri("/api/v1/metrics").to_request();
+        let metrics = String::from_utf8(test::call_and_read_body(&app, req).await.to_vec()).unwrap();
+        assert!(metrics.contains("eval_provider_queue_depth{provider=\"ollama\"} 0\n"), "{}", metrics);
+        assert!(metrics.contains("eval_provider_rpm_limit{provider=\"ollama\"} 300\n"), "{}", metrics);
+        assert!(!metrics.contains("eval_provider_tpm_limit{"), "{}", metrics);
+    }
+
+    #[actix_web::test]
+    async fn test_self_consistency_combines_sample_verdicts_by_strategy() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+        /// Answers model calls with "4", "4" and "5" in turn, and passes the judged outputs that are "4".
+        struct Alternating(AtomicUsize);
+        impl Respond for Alternating {
+            fn respond(&self, request: &Request) -> ResponseTemplate {
+                let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+                let prompt = body["prompt"].as_str().unwrap_or_default();
+                let response = if prompt.contains("ACTUAL OUTPUT:") {
+                    let verdict = if prompt.contains("ACTUAL OUTPUT:\n4\n") { "PASS" } else { "FAIL" };
+                    json!({ "response": format!("Verdict: {}\nCompared the answers.", verdict), "done": true })
+                } else {
+                    let output = if self.0.fetch_add(1, Ordering::SeqCst) % 3 == 2 { "5" } else { "4" };
+                    json!({ "response": output, "done": true, "prompt_eval_count": 10, "eval_count": 2 })
+                };
+                ResponseTemplate::new(200).set_body_json(response)
+            }
+        }
+
+        let server = MockServer::start().await;
+        Mock::given(wiremock::matchers::method("POST")).respond_with(Alternating(AtomicUsize::new(0))).mount(&server).await;
+        let state = AppState {
+            config: Arc::new(crate::config::AppConfig {
+                ollama: Some(crate::config::OllamaConfig { api_base: server.uri(), models: vec![] }),
+                ..Default::default()
+            }),
+            ..state().await
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(WsBroker::new()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        // Each eval draws three samples, so two of them answer "4" and one "5"
+        for (strategy, status) in [("majority", "passed"), ("any", "passed"), ("all", "failed")] {
+            let eval = json!({
+                "model": "ollama:llama3",
+                "prompt": "What is 2+2?",
+                "expected": "4",
+                "judge_model": "ollama:llama3",
+                "self_consistency": { "samples": 3, "strategy": strategy },
+            });
+            let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(&eval).to_request();
+            let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+            assert_eq!(body["status"], status, "{}", body);
+            let result = &body["result"];
+            let report = &result["self_consistency"];
+            assert_eq!((&report["passed"], &report["failed"], &report["strategy"]), (&json!(2), &json!(1), &json!(strategy)), "{}", body);
+            assert_eq!(report["details"].as_array().unwrap().len(), 3);
+            assert_eq!(result["token_usage"], json!({ "input_tokens": 30, "output_tokens": 6 }));
+            assert!(result["judge_result"]["reasoning"].as_str().unwrap().starts_with(&format!("2 of 3 samples passed ({})", strategy)));
+            assert_eq!(result["model_output"], if status == "passed" { "4" } else { "5" });
+
+            let req = test::TestRequest::get().uri(&format!("/api/v1/evals/{}", body["id"].as_str().unwrap())).to_request();
+            let detail: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+            assert_eq!(detail["self_consistency"], *report);
+        }
+
+        // The samples of an eval count as one eval of the batch
+        let eval = json!({
+            "model": "ollama:llama3",
+            "prompt": "What is 2+2?",
+            "expected": "4",
+            "judge_model": "ollama:llama3",
+            "self_consistency": { "samples": 3, "strategy": "all" },
+        });
+        let req = test::TestRequest::post().uri("/api/v1/evals/batch").set_json(json!([eval])).to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!((&body["total"], &body["completed"], &body["failed"]), (&json!(1), &json!(1), &json!(1)), "{}", body);
+        assert_eq!(body["results"][0]["result"]["self_consistency"]["passed"], 2);
+
+        for (consistency, field) in [
+            (json!({ "samples": 1 }), "self_consistency.samples"),
+            (json!({ "samples": 3, "strategy": "most" }), ""),
+        ] {
+            let eval = json!({ "model": "ollama:llama3", "prompt": "What is 2+2?", "expected": "4", "self_consistency": consistency });
+            let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(&eval).to_request();
+            let res = test::call_service(&app, req).await;
+            assert!(res.status().is_client_error(), "{}", consistency);
+            if !field.is_empty() {
+                let body: serde_json::Value = test::read_body_json(res).await;
+                assert_eq!(invalid_fields(&body), [field]);
+            }
+        }
+        let eval = json!({ "model": "ollama:llama3", "prompt": "What is 2+2?", "self_consistency": { "samples": 3 } });
+        let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(&eval).to_request();
+        let body: serde_json::Value = test::read_body_json(test::call_service(&app, req).await).await;
+        assert_eq!(invalid_fields(&body), ["self_consistency"]);
+    }
+
+    #[actix_web::test]
+    async fn test_provider_overrides_reach_the_provider_but_are_never_saved_or_logged() {
+        use crate::config::OpenAIConfig;
+        use std::io::Write;
+        use std::sync::Mutex;
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        #[derive(Clone, Default)]
+        struct Captured(Arc<Mutex<Vec<u8>>>);
+        impl Write for Captured {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().write(buf)
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+        let logs = Captured::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt().with_max_level(tracing::Level::TRACE).with_writer(move || writer.clone()).finish();
+        let _logging = tracing::subscriber::set_default(subscriber);
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(header("authorization", "Bearer sk-alice-secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "choices": [{ "message": { "content": "4" }, "finish_reason": "stop" }]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(header("authorization", "Bearer sk-revoked-secret"))
+            .respond_with(ResponseTemplate::new(401).set_body_json(json!({
+                "error": { "message": "Incorrect API key provided: sk-revoked-secret." }
+            })))
+            .mount(&server)
+            .await;
+        let state = AppState {
+            config: Arc::new(crate::config::AppConfig {
+                openai: Some(OpenAIConfig { api_base: "http://openai.invalid/v1".to_string(), api_key: "sk-server".to_string(), models: vec![] }),
+                ..Default::default()
+            }),
+            ..state().await
+        };
+        let pool = state.db_pool.clone().unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(WsBroker::new()))
+                .configure(configure_routes),
+        )
+        .await;
+        let eval = |key: &str| json!({
+            "model": "openai:gpt-4o-mini",
+            "prompt": "What is 2+2?",
+            "provider_overrides": { "openai": { "api_key": key, "api_base": format!("{}/v1", server.uri()) } },
+        });
+
+        let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(eval("sk-alice-secret")).to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["result"]["model_output"], "4");
+        let entry = database::get_evaluation(&pool, body["id"].as_str().unwrap()).await.unwrap();
+        let used = entry.provider_override.as_ref().unwrap();
+        assert_eq!(used.providers, ["openai"]);
+        assert_eq!(used.supplied_by.as_ref().unwrap().key_name, "anonymous");
+        let saved = serde_json::to_string(&entry).unwrap();
+        assert!(!saved.contains("sk-alice-secret"), "{}", saved);
+
+        let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(eval("sk-revoked-secret")).to_request();
+        let res = test::call_service(&app, req).await;
+        assert!(res.status().is_client_error() || res.status().is_server_error());
+        let body = String::from_utf8(test::read_body(res).await.to_vec()).unwrap();
+        assert!(!body.contains("sk-revoked-secret"), "{}", body);
+        let errors = database::get_provider_errors(&pool, &Default::default()).await.unwrap();
+        assert!(!errors.is_empty());
+        let saved = serde_json::to_string(&errors).unwrap() + &serde_json::to_string(&database::get_all_evaluations(&pool).await.unwrap()).unwrap();
+        assert!(saved.contains("Incorrect API key provided: [redacted]."), "{}", saved);
+        assert!(!saved.contains("sk-revoked-secret"), "{}", saved);
+
+        let logged = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        assert!(logged.contains("Calling openai"), "{}", logged);
+        assert!(!logged.contains("sk-alice-secret") && !logged.contains("sk-revoked-secret"), "{}", logged);
+
+        // Overrides must name a provider the eval calls, here not its judge's
+        let mut unused = eval("sk-alice-secret");
+        unused["provider_overrides"]["anthropic"] = json!({ "api_key": "sk-ant-secret" });
+        let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(unused).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!(invalid_fields(&body), ["provider_overrides.anthropic"]);
+    }
+
+    #[actix_web::test]
+    async fn test_base_url_overrides_never_receive_the_configured_key() {
+        use crate::config::OpenAIConfig;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(wiremock::matchers::any())
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "choices": [{ "message": { "content": "4" }, "finish_reason": "stop" }]
+            })))
+            .mount(&server)
+            .await;
+        let state = AppState {
+            config: Arc::new(crate::config::AppConfig {
+                openai: Some(OpenAIConfig { api_base: "http://openai.invalid/v1".to_string(), api_key: "sk-server".to_string(), models: vec![] }),
+                ..Default::default()
+            }),
+            ..state().await
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(WsBroker::new()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let eval = json!({
+            "model": "openai:gpt-4o-mini",
+            "prompt": "What is 2+2?",
+            "provider_overrides": { "openai": { "api_base": format!("{}/v1", server.uri()) } },
+        });
+        let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(&eval).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!(invalid_fields(&body), ["provider_overrides.openai.api_key"]);
+
+        // With a key of its own the request reaches the base URL, carrying only that key
+        let mut eval = eval;
+        eval["provider_overrides"]["openai"]["api_key"] = json!("sk-alice");
+        let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(&eval).to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["result"]["model_output"], "4");
+
+        let received = server.received_requests().await.unwrap();
+        assert_eq!(received.len(), 1);
+        for request in &received {
+            let sent = format!("{:?}{}", request.headers, String::from_utf8_lossy(&request.body));
+            assert!(!sent.contains("sk-server"), "{}", sent);
+            assert!(sent.contains("sk-alice"), "{}", sent);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_bodies_over_the_limit_are_answered_as_413_naming_it() {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state().await))
+                .app_data(web::Data::new(WsBroker::new()))
+                .configure(crate::api::routes_with_body_limit(1024)),
+        )
+        .await;
+
+        let evals: Vec<serde_json::Value> = (0..50).map(|i| json!({ "model": "ollama:llama3", "prompt": format!("Question {}", i) })).collect();
+        let req = test::TestRequest::post().uri("/api/v1/evals/batch").set_json(&evals).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!(body["code"], "payload_too_large");
+        assert_eq!(body["details"]["limit_bytes"], 1024);
+        assert!(body["message"].as_str().unwrap().contains("1024 bytes"), "{}", body);
+    }
+
+    #[actix_web::test]
+    async fn test_long_outputs_are_saved_cut_but_judged_whole() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let output = format!("Verdict: PASS{}", " because 2+2 is 4.".repeat(20));
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "response": output, "done": true })))
+            .mount(&server)
+            .await;
+        let state = AppState {
+            config: Arc::new(crate::config::AppConfig {
+                ollama: Some(crate::config::OllamaConfig { api_base: server.uri(), models: vec![] }),
+                max_stored_output_chars: Some(13),
+                ..Default::default()
+            }),
+            ..state().await
+        };
+        let pool = state.db_pool.clone().unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(WsBroker::new()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let eval = json!({ "model": "ollama:llama3", "prompt": "What is 2+2?", "expected": "4", "judge_model": "ollama:llama3" });
+        let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(eval).to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["result"]["model_output"], output.as_str());
+        let requests = server.received_requests().await.unwrap();
+        let judge_request: serde_json::Value = serde_json::from_slice(&requests[1].body).unwrap();
+        assert!(judge_request["prompt"].as_str().unwrap().contains(&output));
+
+        let entry = database::get_evaluation(&pool, body["id"].as_str().unwrap()).await.unwrap();
+        assert_eq!(entry.model_output.as_deref(), Some("Verdict: PASS"));
+        assert_eq!((entry.output_chars, entry.output_truncated), (Some(output.chars().count() as i64), true));
+
+        let req = test::TestRequest::get().uri("/api/v1/evals/history?fields=output_truncated,output_chars").to_request();
+        let history: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(history["results"][0]["output_truncated"], true);
+        assert_eq!(history["results"][0]["output_chars"], output.chars().count());
+    }
+
+    #[actix_web::test]
+    async fn test_history_and_stats_are_not_modified_until_an_evaluation_is_saved() {
+        use actix_web::http::header;
+
+        let state = state().await;
+        let pool = state.db_pool.clone().unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(WsBroker::new()))
+                .configure(configure_routes),
+        )
+        .await;
+        let etag_of = |res: &actix_web::dev::ServiceResponse| res.headers().get(header::ETAG).unwrap().to_str().unwrap().to_string();
+
+        for uri in ["/api/v1/evals/history", "/api/v1/stats/leaderboard"] {
+            let res = test::call_service(&app, test::TestRequest::get().uri(uri).to_request()).await;
+            assert_eq!(res.status(), StatusCode::OK);
+            let etag = etag_of(&res);
+            assert!(etag.starts_with("W/\""), "{}", etag);
+            let req = test::TestRequest::get().uri(uri).insert_header((header::IF_NONE_MATCH, etag.clone())).to_request();
+            let res = test::call_service(&app, req).await;
+            assert_eq!(res.status(), StatusCode::NOT_MODIFIED, "{}", uri);
+            assert_eq!(etag_of(&res), etag);
+        }
+
+        let req = test::TestRequest::get().uri("/api/v1/evals/history").to_request();
+        let etag = etag_of(&test::call_service(&app, req).await);
+        let response = crate::models::ApiResponse {
+            id: "eval-1".to_string(),
+            status: "error".to_string(),
+            result: crate::models::EvalResult::Error(crate::models::ApiErrorResponse {
+                code: "provider_error".to_string(),
+                message: "connection refused".to_string(),
+                details: None,
+            }),
+            batch_id: None,
+            config_hash: None,
+            preset: None,
+        };
+        database::save_evaluation(&pool, &response).await.unwrap();
+
+        let req = test::TestRequest::get().uri("/api/v1/evals/history").insert_header((header::IF_NONE_MATCH, etag.clone())).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_ne!(etag_of(&res), etag);
+        let history: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!(history["results"][0]["id"], "eval-1");
+    }
+
+    #[actix_web::test]
+    async fn test_history_lists_summaries_far_smaller_than_the_full_entries() {
+        let state = state().await;
+        let pool = state.db_pool.clone().unwrap();
+        for i in 0..20 {
+            let result: runner::EvalResult = serde_json::from_value(json!({
+                "model": "ollama:llama3",
+                "prompt": format!("Question {}: {}", i, "summarize the following contract clause. ".repeat(40)),
+                "model_output": "The clause limits liability to direct damages. ".repeat(60),
+                "expected": "A limitation of liability.",
+                "judge_result": null,
+                "timestamp": format!("2026-10-17T09:00:{:02}Z", i),
+                "latency_ms": 1200,
+                "judge_latency_ms": null,
+                "total_latency_ms": 1200,
+                "tags": ["contracts"],
+            }))
+            .unwrap();
+            let response = crate::models::ApiResponse {
+                id: format!("eval-{:02}", i),
+                status: "completed".to_string(),
+                result: crate::models::EvalResult::Success(result),
+                batch_id: None,
+                config_hash: None,
+                preset: None,
+            };
+            database::save_evaluation(&pool, &response).await.unwrap();
+        }
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(WsBroker::new()))
+                .configure(configure_routes),
+        )
+        .await;
+        let get = |uri: &str| test::TestRequest::get().uri(uri).to_request();
+
+        let summaries = test::call_and_read_body(&app, get("/api/v1/evals/history")).await;
+        let full = test::call_and_read_body(&app, get("/api/v1/evals/history?full=true")).await;
+        assert!(summaries.len() * 5 < full.len(), "{} bytes of summaries against {} of entries", summaries.len(), full.len());
+
+        let summaries: serde_json::Value = serde_json::from_slice(&summaries).unwrap();
+        let newest = &summaries["results"][0];
+        assert_eq!((&newest["id"], &newest["latency_ms"], &newest["tags"]), (&json!("eval-19"), &json!(1200), &json!(["contracts"])));
+        let preview = newest["prompt_preview"].as_str().unwrap();
+        assert_eq!(preview.chars().count(), database::SUMMARY_PREVIEW_CHARS + 1);
+        assert!(preview.starts_with("Question 19: summarize") && preview.ends_with('…'));
+        assert!(newest.get("prompt").is_none() && newest.get("model_output").is_none());
+
+        let with_fields: serde_json::Value = test::call_and_read_body_json(&app, get("/api/v1/evals/history?fields=expected,%20tags")).await;
+        assert_eq!(with_fields["results"][0]["expected"], "A limitation of liability.");
+        assert_eq!(with_fields["results"][0]["prompt_preview"], preview);
+        let detail: serde_json::Value = test::call_and_read_body_json(&app, get("/api/v1/evals/eval-19")).await;
+        assert!(detail["prompt"].as_str().unwrap().ends_with("clause. "));
+
+        // Every field of an entry may be asked for
+        let full: serde_json::Value = serde_json::from_slice(&full).unwrap();
+        let mut keys: Vec<&str> = full["results"][0].as_object().unwrap().keys().map(String::as_str).collect();
+        keys.extend(["images", "judge_error"]);
+        keys.sort_unstable();
+        let mut fields = database::HISTORY_FIELDS.to_vec();
+        fields.sort_unstable();
+        assert_eq!(keys, fields);
+    }
+
+    #[actix_web::test]
+    async fn test_tags_are_counted_changed_and_filtered_on() {
+        let state = state().await;
+        let pool = state.db_pool.clone().unwrap();
+        for (id, tags) in [("eval-1", json!(["formatting"])), ("eval-2", json!(["formatting", "nightly"]))] {
+            let result: runner::EvalResult = serde_json::from_value(json!({
+                "model": "ollama:llama3",
+                "prompt": "List three colours",
+                "model_output": "red, green",
+                "expected": "Three colours",
+                "judge_result": null,
+                "timestamp": "2026-10-17T09:00:00Z",
+                "latency_ms": 100,
+                "judge_latency_ms": null,
+                "total_latency_ms": 100,
+                "tags": tags,
+            }))
+            .unwrap();
+            let response = crate::models::ApiResponse {
+                id: id.to_string(),
+                status: "completed".to_string(),
+                result: crate::models::EvalResult::Success(result),
+                batch_id: None,
+                config_hash: None,
+                preset: None,
+            };
+            database::save_evaluation(&pool, &response).await.unwrap();
+        }
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(WsBroker::new()))
+                .configure(configure_routes),
+        )
+        .await;
+        let ids = |history: serde_json::Value| -> Vec<String> {
+            history["results"].as_array().unwrap().iter().map(|e| e["id"].as_str().unwrap().to_string()).collect()
+        };
+
+        let req = test::TestRequest::patch()
+            .uri("/api/v1/evals/eval-1/tags")
+            .set_json(json!({ "add": ["hallucination"], "remove": ["formatting"] }))
+            .to_request();
+        let updated: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(updated, json!({ "id": "eval-1", "tags": ["hallucination"] }));
+        let req = test::TestRequest::patch().uri("/api/v1/evals/missing/tags").set_json(json!({ "add": ["x"] })).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::get().uri("/api/v1/tags").to_request();
+        let tags: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(
+            tags["tags"],
+            json!([
+                { "tag": "formatting", "evaluations": 1 },
+                { "tag": "hallucination", "evaluations": 1 },
+                { "tag": "nightly", "evaluations": 1 },
+            ])
+        );
+
+        let req = test::TestRequest::get().uri("/api/v1/evals/history?tag=hallucination,nightly&tag_match=any").to_request();
+        let mut any = ids(test::call_and_read_body_json(&app, req).await);
+        any.sort();
+        assert_eq!(any, ["eval-1", "eval-2"]);
+        let req = test::TestRequest::get().uri("/api/v1/evals/history?tag=formatting,nightly").to_request();
+        assert_eq!(ids(test::call_and_read_body_json(&app, req).await), ["eval-2"]);
+        let req = test::TestRequest::get().uri("/api/v1/stats/leaderboard?tag=hallucination,nightly&tag_match=all").to_request();
+        let board: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(board["models"], json!([]));
+    }
+
+    #[actix_web::test]
+    async fn test_triage_queue_empties_as_evaluations_are_reviewed_and_rejudged() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let generate = |response: &str| {
+            Mock::given(method("POST"))
+                .and(path("/api/generate"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "response": response, "done": true })))
+        };
+        // The judge answers without a verdict, so both evaluations end Uncertain
+        generate("4").mount(&server).await;
+        let state = AppState {
+            config: Arc::new(crate::config::AppConfig {
+                ollama: Some(crate::config::OllamaConfig { api_base: server.uri(), models: vec![] }),
+                ..Default::default()
+            }),
+            ..state().await
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(WsBroker::new()))
+                .configure(configure_routes),
+        )
+        .await;
+        let count = || async {
+            let req = test::TestRequest::get().uri("/api/v1/evals/triage/count").to_request();
+            test::call_and_read_body_json::<_, _, serde_json::Value>(&app, req).await["count"].clone()
+        };
+
+        let eval = json!({ "model": "ollama:llama3", "prompt": "What is 2+2?", "expected": "Four", "judge_model": "ollama:llama3" });
+        let mut ids = Vec::new();
+        for _ in 0..2 {
+            let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(&eval).to_request();
+            let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+            assert_eq!(body["result"]["judge_result"]["verdict"], "Uncertain", "{}", body);
+            ids.push(body["id"].clone());
+        }
+
+        let req = test::TestRequest::get().uri("/api/v1/evals/triage").to_request();
+        let queue: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(queue["total"], 2);
+        let queued: Vec<_> = queue["results"].as_array().unwrap().iter().map(|e| e["id"].clone()).collect();
+        assert_eq!(queued, ids, "oldest first");
+        let req = test::TestRequest::get().uri("/api/v1/evals/triage?limit=1&offset=1").to_request();
+        let page: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!((&page["total"], &page["results"][0]["id"]), (&json!(2), &ids[1]));
+        assert_eq!(count().await, 2);
+        let req = test::TestRequest::get().uri("/api/v1/evals/triage?limit=0").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::BAD_REQUEST);
+
+        let uri = format!("/api/v1/evals/triage/{}", ids[0].as_str().unwrap());
+        let req = test::TestRequest::patch().uri(&uri).set_json(json!({ "verdict": "Fail" })).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+        assert_eq!(count().await, 1);
+
+        server.reset().await;
+        generate("The answer matches.\nVERDICT: PASS").mount(&server).await;
+        let req = test::TestRequest::post().uri("/api/v1/evals/triage/rejudge").to_request();
+        let rejudged: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!((&rejudged["rejudged"], &rejudged["resolved"], &rejudged["remaining"]), (&json!(1), &json!(1), &json!(0)), "{}", rejudged);
+        assert_eq!(rejudged["results"][0]["evaluation_id"], ids[1]);
+        assert_eq!(count().await, 0);
+    }
+
+    #[actix_web::test]
+    async fn test_raw_exchanges_are_captured_only_when_asked_for() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("<html>gateway timeout</html>"))
+            .mount(&server)
+            .await;
+        let state = AppState {
+            config: Arc::new(crate::config::AppConfig {
+                ollama: Some(crate::config::OllamaConfig { api_base: server.uri(), models: vec![] }),
+                ..Default::default()
+            }),
+            ..state().await
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(WsBroker::new()))
+                .configure(configure_routes),
+        )
+        .await;
+        let run = |capture_raw: bool| {
+            let eval = json!({ "model": "ollama:llama3", "prompt": "What is 2+2?", "capture_raw": capture_raw });
+            test::TestRequest::post().uri("/api/v1/evals/run").set_json(eval).to_request()
+        };
+
+        // The model failing with an unparseable response is what capture is for
+        let body: serde_json::Value = test::call_and_read_body_json(&app, run(true)).await;
+        let raw_uri = |body: &serde_json::Value| format!("/api/v1/evals/{}/raw", body["details"]["eval_id"].as_str().unwrap());
+        let req = test::TestRequest::get().uri(&raw_uri(&body)).to_request();
+        let raw: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let exchange = &raw["exchanges"][0];
+        assert_eq!((&exchange["provider"], &exchange["status"]), (&json!("ollama"), &json!(200)), "{}", raw);
+        assert_eq!(exchange["response_body"], "<html>gateway timeout</html>");
+        assert!(exchange["request_body"].as_str().unwrap().contains("What is 2+2?"));
+        assert_eq!(exchange["url"], format!("{}/api/generate", server.uri()));
+
+        let body: serde_json::Value = test::call_and_read_body_json(&app, run(false)).await;
+        let req = test::TestRequest::get().uri(&raw_uri(&body)).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn test_batch_reports_list_failures_in_markdown_or_html() {
+        use database::test_support::evaluation;
+        let state = state().await;
+        let pool = state.db_pool.clone().unwrap();
+        database::create_batch(&pool, "batch-1", 2).await.unwrap();
+        evaluation("eval-1").batch("batch-1").save(&pool).await;
+        evaluation("eval-2").batch("batch-1").output("<b>5</b>").verdict(runner::JudgeVerdict::Fail).save(&pool).await;
+        database::finish_batch(&pool, "batch-1", 1, 1, "completed").await.unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(WsBroker::new()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/api/v1/evals/batch/batch-1/report").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.headers().get("content-type").unwrap(), "text/markdown; charset=utf-8");
+        let markdown = String::from_utf8(test::read_body(res).await.to_vec()).unwrap();
+        assert!(markdown.contains("| ollama:llama3 | 2 | 1 | 50.0% |"), "{}", markdown);
+        assert!(markdown.contains("### eval-2") && !markdown.contains("### eval-1"), "{}", markdown);
+
+        let req = test::TestRequest::get().uri("/api/v1/evals/batch/batch-1/report?format=html").to_request();
+        let html = String::from_utf8(test::read_body(test::call_service(&app, req).await).await.to_vec()).unwrap();
+        assert!(html.contains("<pre>&lt;b&gt;5&lt;/b&gt;</pre>"), "{}", html);
+
+        let req = test::TestRequest::get().uri("/api/v1/evals/batch/batch-1/report?format=pdf").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::BAD_REQUEST);
+        let req = test::TestRequest::get().uri("/api/v1/evals/batch/missing/report").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn test_status_follows_a_batch_through_its_stages() {
+        use crate::api::handlers::ws::Subscription;
+        use crate::api::progress::ProgressTracker;
+        use std::time::Duration;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "response": "4", "done": true }))
+                    .set_delay(Duration::from_millis(300)),
+            )
+            .mount(&server)
+            .await;
+        let grace = Duration::from_millis(500);
+        let state = AppState {
+            config: Arc::new(crate::config::AppConfig {
+                ollama: Some(crate::config::OllamaConfig { api_base: server.uri(), models: vec![] }),
+                ..Default::default()
+            }),
+            progress: Arc::new(ProgressTracker::new(grace)),
+            ..state().await
+        };
+        let broker = WsBroker::new();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(broker.clone()))
+                .configure(configure_routes),
+        )
+        .await;
+        let status = |id: &str| {
+            let req = test::TestRequest::get().uri(&format!("/api/v1/evals/{}/status", id)).to_request();
+            test::call_and_read_body_json::<_, _, serde_json::Value>(&app, req)
+        };
+
+        // An expected output the answer does not match exactly, so the judge is asked
+        let eval = json!({ "model": "ollama:llama3", "prompt": "What is 2+2?", "expected": "Four", "judge_model": "ollama:llama3" });
+        let req = test::TestRequest::post().uri("/api/v1/evals/batch").set_json(json!([eval, eval])).to_request();
+        let run = test::call_and_read_body_json::<_, _, serde_json::Value>(&app, req);
+        let poll = async {
+            let mut stages: Vec<String> = Vec::new();
+            for _ in 0..500 {
+                // Clients learn the batch id from its first events
+                let events = broker.replay(usize::MAX, &Subscription::default()).await;
+                if let Some(batch_id) = events.iter().find_map(|event| event.batch_id().map(str::to_string)) {
+                    let progress = status(&batch_id).await;
+                    let stage = progress["stage"].as_str().unwrap().to_string();
+                    if stages.last() != Some(&stage) {
+                        stages.push(stage.clone());
+                    }
+                    if stage == "done" {
+                        return (batch_id, stages, progress);
+                    }
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            panic!("the batch never finished: {:?}", stages);
+        };
+        let (batch, (batch_id, stages, done)) = futures::join!(run, poll);
+
+        // Saving is over too quickly to be seen every time
+        let order = ["generating", "judging", "saving", "done"];
+        assert_eq!((stages.first().unwrap().as_str(), stages.last().unwrap().as_str()), ("generating", "done"), "{:?}", stages);
+        assert!(stages.contains(&"judging".to_string()), "{:?}", stages);
+        assert!(stages.windows(2).all(|w| order.iter().position(|s| *s == w[0]) < order.iter().position(|s| *s == w[1])));
+        assert_eq!((&done["completed"], &done["total"], &done["progress"], &done["live"]), (&json!(2), &json!(2), &json!(100), &json!(true)));
+        assert_eq!(batch["batch_id"], batch_id);
+
+        // The WebSocket counted the same evals as the endpoint
+        let events = broker.replay(usize::MAX, &Subscription::default()).await;
+        let counts: Vec<usize> = events
+            .iter()
+            .filter_map(|event| match event {
+                WsEvent::BatchProgress { completed, .. } => Some(*completed),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(counts, [1, 2]);
+        let eval_id = batch["results"][0]["id"].as_str().unwrap().to_string();
+        let eval = status(&eval_id).await;
+        assert_eq!((&eval["kind"], &eval["stage"], &eval["batch_id"], &eval["live"]), (&json!("eval"), &json!("done"), &json!(batch_id), &json!(true)));
+
+        // Past the grace period, the database answers
+        tokio::time::sleep(grace + Duration::from_millis(100)).await;
+        let stored = status(&batch_id).await;
+        assert_eq!((&stored["stage"], &stored["completed"], &stored["live"]), (&json!("done"), &json!(2), &json!(false)));
+        let eval = status(&eval_id).await;
+        assert_eq!((&eval["stage"], &eval["progress"], &eval["live"]), (&json!("done"), &json!(100), &json!(false)));
+        let req = test::TestRequest::get().uri("/api/v1/evals/missing/status").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn test_evals_can_name_a_prompt_version_instead_of_a_prompt() {
+        use database::test_support::prompt_version;
+        use wiremock::matchers::{body_string_contains, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .and(body_string_contains("What is the capital of France?"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "response": "Paris", "done": true })))
+            .mount(&server)
+            .await;
+        let state = AppState {
+            config: Arc::new(crate::config::AppConfig {
+                ollama: Some(crate::config::OllamaConfig { api_base: server.uri(), models: vec![] }),
+                ..Default::default()
+            }),
+            ..state().await
+        };
+        let pool = state.db_pool.clone().unwrap();
+        let capitals = prompt_version("Capitals").template("What is the capital of {{country}}?").save(&pool).await;
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(WsBroker::new()))
+                .configure(configure_routes),
+        )
+        .await;
+        let eval = json!({ "model": "ollama:llama3", "prompt_version": capitals.version, "metadata": { "country": "France" } });
+
+        // The template is rendered with the metadata, and the eval linked to the version
+        let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(&eval).to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["result"]["prompt"], "What is the capital of France?", "{}", body);
+        assert_eq!(body["result"]["prompt_version"], capitals.version);
+        let entry = database::get_evaluation(&pool, body["id"].as_str().unwrap()).await.unwrap();
+        assert_eq!(entry.prompt_version, Some(capitals.version));
+
+        let req = test::TestRequest::post().uri("/api/v1/evals/batch").set_json(json!([eval, eval])).to_request();
+        let batch: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(batch["completed"], 2, "{}", batch);
+        let uri = format!("/api/v1/prompt-versions/{}/stats", capitals.version);
+        let stats: serde_json::Value = test::call_and_read_body_json(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+        assert_eq!(stats["stats"]["total_evaluations"], 3);
+
+        let req = test::TestRequest::post().uri("/api/v1/evals/run").set_json(json!({ "model": "ollama:llama3", "prompt_version": 999 })).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        let both = json!([{ "model": "ollama:llama3", "prompt": "Hi", "prompt_version": capitals.version }]);
+        let res = test::call_service(&app, test::TestRequest::post().uri("/api/v1/evals/batch").set_json(both).to_request()).await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(invalid_fields(&test::read_body_json(res).await), ["[0].prompt_version"]);
+    }
+
+    #[actix_web::test]
+    async fn test_budgeted_batches_skip_the_evals_left_once_a_cap_is_hit() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        // Every call uses 100 tokens
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "response": "4", "done": true, "prompt_eval_count": 40, "eval_count": 60 })))
+            .mount(&server)
+            .await;
+        let state = AppState {
+            config: Arc::new(crate::config::AppConfig {
+                ollama: Some(crate::config::OllamaConfig { api_base: server.uri(), models: vec![] }),
+                ..Default::default()
+            }),
+            ..state().await
+        };
+        let pool = state.db_pool.clone().unwrap();
+        let suite = database::create_suite(&pool, "smoke".to_string(), None, &[
+            serde_json::from_value(json!({ "model": "ollama:llama3", "prompt": "2+2?" })).unwrap(),
+        ])
+        .await
+        .unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(WsBroker::new()))
+                .configure(configure_routes),
+        )
+        .await;
+        let eval = json!({ "model": "ollama:llama3", "prompt": "What is 2+2?" });
+
+        // 4 evals start at once; each one finishing lets another start until 250 tokens are
+        // used up by the third, so 6 run and 4 are skipped
+        let body = json!({ "evals": vec![eval.clone(); 10], "budget": { "max_total_tokens": 250, "max_cost_usd": 5.0 } });
+        let req = test::TestRequest::post().uri("/api/v1/evals/batch").set_json(&body).to_request();
+        let batch: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!((&batch["total"], &batch["completed"], &batch["failed"]), (&json!(10), &json!(6), &json!(0)), "{}", batch);
+        let budget = &batch["budget"];
+        assert_eq!((&budget["triggered_by"], &budget["total_tokens"], &budget["skipped"]), (&json!("max_total_tokens"), &json!(600), &json!(4)));
+        assert_eq!((&budget["max_total_tokens"], &budget["max_cost_usd"], &budget["max_duration_secs"]), (&json!(250), &json!(5.0), &json!(null)));
+        let statuses: Vec<&str> = batch["results"].as_array().unwrap().iter().map(|r| r["status"].as_str().unwrap()).collect();
+        assert_eq!(statuses[6..], ["skipped_budget"; 4]);
+        assert!(statuses[..6].iter().all(|status| *status != "skipped_budget"));
+        assert_eq!(batch["results"][9]["error_code"], "skipped_budget");
+        assert_eq!(server.received_requests().await.unwrap().len(), 6);
+
+        let stored = database::get_batch_evaluations(&pool, batch["batch_id"].as_str().unwrap()).await.unwrap();
+        assert_eq!(stored.iter().filter(|e| e.status.as_deref() == Some("skipped_budget")).count(), 4);
+
+        // A budget that is never reached runs everything, and a plain list has no budget
+        let body = json!({ "evals": [eval, eval], "budget": { "max_total_tokens": 10000 } });
+        let batch: serde_json::Value = test::call_and_read_body_json(&app, test::TestRequest::post().uri("/api/v1/evals/batch").set_json(&body).to_request()).await;
+        assert_eq!((&batch["completed"], &batch["budget"]["triggered_by"], &batch["budget"]["skipped"]), (&json!(2), &json!(null), &json!(0)));
+        let batch: serde_json::Value = test::call_and_read_body_json(&app, test::TestRequest::post().uri("/api/v1/evals/batch").set_json(json!([eval])).to_request()).await;
+        assert!(batch.get("budget").is_none(), "{}", batch);
+
+        // Suite runs take the same budget
+        let uri = format!("/api/v1/suites/{}/run", suite.id);
+        let run: serde_json::Value = test::call_and_read_body_json(&app, test::TestRequest::post().uri(&uri).set_json(json!({ "budget": { "max_duration_secs": 60 } })).to_request()).await;
+        assert_eq!((&run["completed"], &run["budget"]["max_duration_secs"]), (&json!(1), &json!(60)), "{}", run);
+
+        for (uri, body) in [("/api/v1/evals/batch", json!({ "evals": [eval], "budget": { "max_total_tokens": 0 } })), (uri.as_str(), json!({ "budget": {} }))] {
+            let res = test::call_service(&app, test::TestRequest::post().uri(uri).set_json(&body).to_request()).await;
+            assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY, "{}", body);
+        }
+    }
 }
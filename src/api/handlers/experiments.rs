@@ -1,8 +1,9 @@
 // src/api/handlers/experiments.rs
-use actix_web::{web, HttpResponse, Result};
+use actix_web::{web, HttpResponse};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use uuid::Uuid;
+use crate::errors::ApiError;
 
 #[derive(Deserialize)]
 pub struct CreateExperimentRequest {
@@ -21,7 +22,7 @@ pub struct ExperimentResponse {
 
 pub async fn create_experiment(
     req: web::Json<CreateExperimentRequest>,
-) -> Result<HttpResponse> {
+) -> Result<HttpResponse, ApiError> {
     let experiment_id = Uuid::new_v4().to_string();
     
     Ok(HttpResponse::Created().json(ExperimentResponse {
@@ -32,7 +33,7 @@ pub async fn create_experiment(
     }))
 }
 
-pub async fn get_experiment(path: web::Path<String>) -> Result<HttpResponse> {
+pub async fn get_experiment(path: web::Path<String>) -> Result<HttpResponse, ApiError> {
     let experiment_id = path.into_inner();
     
     Ok(HttpResponse::Ok().json(json!({
@@ -0,0 +1,42 @@
+// src/api/handlers/failure_taxonomy.rs
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use crate::api::AppState;
+use crate::database;
+use crate::errors::ApiError;
+use crate::failure_taxonomy::{self, FailureCategory};
+
+#[derive(Serialize)]
+pub struct FailureTaxonomyResponse {
+    pub categories: Vec<FailureCategory>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateFailureTaxonomyRequest {
+    pub categories: Vec<FailureCategory>,
+}
+
+/// GET /api/v1/failure-taxonomy - The categories failed evaluations are classified into
+pub async fn get_failure_taxonomy(
+    state: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let categories = failure_taxonomy::load_taxonomy(state.db_pool.as_deref()).await;
+    Ok(HttpResponse::Ok().json(FailureTaxonomyResponse { categories }))
+}
+
+/// PUT /api/v1/failure-taxonomy - Replace the failure taxonomy. Evaluations keep the
+/// categories they were already given.
+pub async fn update_failure_taxonomy(
+    state: web::Data<AppState>,
+    req: web::Json<UpdateFailureTaxonomyRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let categories = req.into_inner().categories;
+    failure_taxonomy::validate_taxonomy(&categories).map_err(ApiError::validation)?;
+
+    let pool = state.pool()?;
+    database::replace_failure_categories(pool, &categories)
+        .await
+        .map_err(|e| ApiError::database("Failed to update the failure taxonomy", e))?;
+    tracing::info!("🏷️  Failure taxonomy replaced with {} categories", categories.len());
+    Ok(HttpResponse::Ok().json(FailureTaxonomyResponse { categories }))
+}
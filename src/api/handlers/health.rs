@@ -1,11 +1,143 @@
 // src/api/handlers/health.rs
-use actix_web::{HttpResponse, Result};
+use actix_web::{web, HttpResponse};
 use serde_json::json;
+use crate::api::AppState;
+use crate::api::handlers::WsBroker;
+use crate::errors::ApiError;
+use crate::health::{self, Readiness};
+use crate::providers::rate_limit::ProviderQueue;
 
-pub async fn health_check() -> Result<HttpResponse> {
-    Ok(HttpResponse::Ok().json(json!({
-        "status": "healthy",
+/// GET /api/v1/health - Readiness: pings the database and checks every configured provider
+/// (cached for a minute). Answers 503 when the database is down or no provider is reachable.
+pub async fn health_check(state: web::Data<AppState>, broker: web::Data<WsBroker>) -> Result<HttpResponse, ApiError> {
+    let readiness = Readiness {
+        database: health::check_database(state.db_pool.as_deref()).await,
+        providers: state.provider_health.check(&state.config, &state.client).await,
+    };
+    let body = json!({
+        "status": readiness.status(),
+        "service": "eval-api",
+        "version": env!("CARGO_PKG_VERSION"),
+        "database": readiness.database,
+        "providers": readiness.providers,
+        "provider_queues": state.config.provider_limiters.queues(),
+        "websocket_clients": broker.client_count().await
+    });
+    if readiness.is_ready() {
+        Ok(HttpResponse::Ok().json(body))
+    } else {
+        Ok(HttpResponse::ServiceUnavailable().json(body))
+    }
+}
+
+/// GET /api/v1/health/live - Liveness: answers as long as the server is running, without
+/// touching any dependency
+pub async fn liveness() -> HttpResponse {
+    HttpResponse::Ok().json(json!({
+        "status": "alive",
         "service": "eval-api",
         "version": env!("CARGO_PKG_VERSION")
-    })))
+    }))
+}
+
+/// GET /api/v1/metrics - Prometheus gauges: the rate limits of each rate-limited provider
+/// and how many calls are queued for them
+pub async fn metrics(state: web::Data<AppState>, broker: web::Data<WsBroker>) -> HttpResponse {
+    let queues = state.config.provider_limiters.queues();
+    let mut body = String::new();
+    body.push_str("# HELP eval_provider_queue_depth Provider calls waiting for a rate limit permit.\n");
+    body.push_str("# TYPE eval_provider_queue_depth gauge\n");
+    for (provider, queue) in &queues {
+        body.push_str(&format!("eval_provider_queue_depth{{provider=\"{}\"}} {}\n", provider, queue.queue_depth));
+    }
+    type LimitGauge = (&'static str, &'static str, fn(&ProviderQueue) -> Option<u32>);
+    let limits: [LimitGauge; 2] = [
+        ("eval_provider_rpm_limit", "Requests per minute a provider may be sent.", |queue| queue.limit.rpm),
+        ("eval_provider_tpm_limit", "Tokens per minute a provider may be sent.", |queue| queue.limit.tpm),
+    ];
+    for (name, help, limit_of) in limits {
+        body.push_str(&format!("# HELP {} {}\n# TYPE {} gauge\n", name, help, name));
+        for (provider, queue) in &queues {
+            if let Some(limit) = limit_of(queue) {
+                body.push_str(&format!("{}{{provider=\"{}\"}} {}\n", name, provider, limit));
+            }
+        }
+    }
+    body.push_str("# HELP eval_websocket_clients Open WebSocket connections.\n");
+    body.push_str("# TYPE eval_websocket_clients gauge\n");
+    body.push_str(&format!("eval_websocket_clients {}\n", broker.client_count().await));
+    HttpResponse::Ok().content_type("text/plain; version=0.0.4").body(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::configure_routes;
+    use crate::config::{AppConfig, OllamaConfig};
+    use crate::database;
+    use actix_web::{test, App};
+    use std::sync::Arc;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn app_state(db: bool, ollama: &MockServer) -> AppState {
+        AppState {
+            config: Arc::new(AppConfig {
+                ollama: Some(OllamaConfig { api_base: ollama.uri(), models: vec!["llama3".to_string()] }),
+                ..Default::default()
+            }),
+            client: reqwest::Client::new(),
+            db_pool: if db { Some(Arc::new(database::test_pool().await)) } else { None },
+            backup_in_progress: Default::default(),
+            active_batches: Default::default(),
+            retention: Default::default(),
+            rate_limiter: Default::default(),
+            provider_health: Default::default(),
+            progress: Default::default(),
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_health_reports_each_component_and_fails_without_a_database() {
+        let ollama = MockServer::start().await;
+        Mock::given(method("GET")).and(path("/api/tags")).respond_with(ResponseTemplate::new(200)).mount(&ollama).await;
+
+        for (db, status, overall) in [(true, 200, "healthy"), (false, 503, "unhealthy")] {
+            let app = test::init_service(
+                App::new()
+                    .app_data(web::Data::new(app_state(db, &ollama).await))
+                    .app_data(web::Data::new(WsBroker::new()))
+                    .configure(configure_routes),
+            )
+            .await;
+            let res = test::call_service(&app, test::TestRequest::get().uri("/api/v1/health").to_request()).await;
+            assert_eq!(res.status(), status);
+            let body: serde_json::Value = test::read_body_json(res).await;
+            assert_eq!(body["status"], overall);
+            assert_eq!(body["providers"]["ollama"]["status"], "ok");
+            assert_eq!(body["database"]["status"], if db { "ok" } else { "disabled" });
+
+            let res = test::call_service(&app, test::TestRequest::get().uri("/api/v1/health/live").to_request()).await;
+            assert_eq!(res.status(), 200);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_health_fails_when_no_provider_is_reachable() {
+        let ollama = MockServer::start().await;
+        Mock::given(method("GET")).respond_with(ResponseTemplate::new(500)).mount(&ollama).await;
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(app_state(true, &ollama).await))
+                .app_data(web::Data::new(WsBroker::new()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let res = test::call_service(&app, test::TestRequest::get().uri("/api/v1/health").to_request()).await;
+        assert_eq!(res.status(), 503);
+        let body: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!(body["database"]["status"], "ok");
+        assert_eq!(body["providers"]["ollama"]["status"], "error");
+    }
 }
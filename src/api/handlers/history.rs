@@ -1,6 +1,7 @@
-use actix_web::{web, HttpResponse, Result};
+use actix_web::{web, HttpResponse};
 use serde::Serialize;
 use crate::api::AppState;
+use crate::errors::ApiError;
 
 #[derive(Serialize)]
 pub struct HistoryResponse {
@@ -9,21 +10,10 @@ pub struct HistoryResponse {
 
 pub async fn get_history(
     state: web::Data<AppState>,
-) -> Result<HttpResponse> {
-    match state.db_pool.as_ref() {
-        Some(pool) => {
-            match crate::database::get_all_evaluations(pool).await {
-                Ok(results) => Ok(HttpResponse::Ok().json(HistoryResponse { results })),
-                Err(e) => {
-                    eprintln!("Database error: {}", e);
-                    Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                        "error": "Failed to fetch history"
-                    })))
-                }
-            }
-        }
-        None => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-            "error": "Database not initialized"
-        }))),
-    }
+) -> Result<HttpResponse, ApiError> {
+    let pool = state.pool()?;
+    let results = crate::database::get_all_evaluations(pool)
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch history", e))?;
+    Ok(HttpResponse::Ok().json(HistoryResponse { results }))
 }
@@ -0,0 +1,199 @@
+// src/api/handlers/jobs.rs
+use actix_web::{web, HttpRequest, HttpResponse};
+use serde::{Deserialize, Serialize};
+use crate::api::AppState;
+use crate::api::progress::ProgressStage;
+use crate::api::handlers::presets::PresetResolver;
+use crate::api::handlers::prompt_versions::apply_prompt_version;
+use crate::api::handlers::suites::find_suite;
+use crate::config::EvalConfig;
+use crate::database::{self, DbPool};
+use crate::errors::ApiError;
+use crate::models::FieldError;
+
+/// Body of `POST /jobs`: the evals of a batch, or the suite to run.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobRequest {
+    pub evals: Option<Vec<serde_json::Value>>,
+    /// Id or name of the suite to run
+    pub suite: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct JobResponse {
+    #[serde(flatten)]
+    pub job: database::Job,
+    pub completed: usize,
+    pub passed: usize,
+    /// Failed verdicts and errored evals
+    pub failed: usize,
+    /// The evals finished so far, in config order
+    pub evals: Vec<database::JobEval>,
+}
+
+fn job_not_found(id: &str) -> ApiError {
+    ApiError::not_found(format!("Job '{}' not found", id))
+}
+
+async fn job_response(pool: &DbPool, id: &str) -> Result<JobResponse, ApiError> {
+    let job = database::get_job(pool, id).await.map_err(|e| match e {
+        sqlx::Error::RowNotFound => job_not_found(id),
+        e => ApiError::database("Failed to fetch job", e),
+    })?;
+    let evals = database::get_job_evals(pool, id)
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch the evals of the job", e))?;
+    Ok(JobResponse {
+        completed: evals.len(),
+        passed: evals.iter().filter(|eval| eval.status == "passed").count(),
+        failed: evals.iter().filter(|eval| matches!(eval.status.as_str(), "failed" | "error" | "blocked")).count(),
+        job,
+        evals,
+    })
+}
+
+/// POST /api/v1/jobs - Queue a batch or suite run for the job worker
+pub async fn create_job(
+    state: web::Data<AppState>,
+    http_req: HttpRequest,
+    req: web::Json<JobRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let pool = state.pool()?;
+    let (configs, suite_id) = match req.into_inner() {
+        JobRequest { evals: Some(bodies), suite: None } => {
+            let mut resolver = PresetResolver::new(&state, &http_req);
+            let mut configs = Vec::new();
+            let mut errors = Vec::new();
+            if bodies.is_empty() {
+                errors.push(FieldError::new("evals", "must hold at least one eval"));
+            }
+            for (i, body) in bodies.into_iter().enumerate() {
+                let (mut config, preset) = resolver.resolve::<EvalConfig>(body, Some(i)).await?;
+                apply_prompt_version(&state, &mut config, Some(&format!("evals[{}]", i))).await?;
+                let mut invalid = config.validate(&state.config).err().unwrap_or_default();
+                if !config.provider_overrides.is_empty() {
+                    // Queued configs are stored, and overriding credentials never are
+                    invalid.push(FieldError::new("provider_overrides", "cannot be used with a queued job"));
+                }
+                errors.extend(invalid.into_iter().map(|error| error.within(&format!("evals[{}]", i))));
+                config.preset = preset;
+                configs.push(config);
+            }
+            if !errors.is_empty() {
+                return Err(ApiError::invalid_fields(errors));
+            }
+            (configs, None)
+        }
+        JobRequest { evals: None, suite: Some(id) } => {
+            let suite = find_suite(pool, &id).await?;
+            (suite.configs, Some(suite.id))
+        }
+        _ => return Err(ApiError::invalid_fields(vec![FieldError::new("evals", "give either evals or suite")])),
+    };
+
+    let job = database::enqueue_job(pool, suite_id.as_deref(), &configs)
+        .await
+        .map_err(|e| ApiError::database("Failed to queue job", e))?;
+    tracing::info!("📬 Queued job {} of {} evals", job.job_id, job.total);
+    state.progress.track_batch(&job.batch_id, ProgressStage::Queued, 0, configs.len());
+    Ok(HttpResponse::Accepted().json(job_response(pool, &job.job_id).await?))
+}
+
+/// GET /api/v1/jobs/{id} - A job's status and the evals it has finished
+pub async fn get_job(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let pool = state.pool()?;
+    Ok(HttpResponse::Ok().json(job_response(pool, &path.into_inner()).await?))
+}
+
+/// POST /api/v1/jobs/{id}/cancel - Cancel a queued or running job; evals it finished are kept
+pub async fn cancel_job(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+    let pool = state.pool()?;
+    let cancelled = database::cancel_job(pool, &id)
+        .await
+        .map_err(|e| ApiError::database("Failed to cancel job", e))?;
+    let response = job_response(pool, &id).await?;
+    if !cancelled {
+        return Err(ApiError::conflict(format!("Job '{}' has already {}", id, response.job.status)));
+    }
+    state.progress.forget(&response.job.batch_id);
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::configure_routes;
+    use actix_web::{http::StatusCode, test, App};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[actix_web::test]
+    async fn test_jobs_are_queued_read_back_and_cancelled() {
+        let pool = Arc::new(database::test_pool().await);
+        let suite = database::create_suite(&pool, "smoke".to_string(), None, &[
+            serde_json::from_value(json!({ "model": "ollama:llama3", "prompt": "2+2?" })).unwrap(),
+        ])
+        .await
+        .unwrap();
+        let state = AppState {
+            config: Arc::new(crate::config::AppConfig {
+                ollama: Some(crate::config::OllamaConfig { api_base: "http://localhost:11434".to_string(), models: vec![] }),
+                ..Default::default()
+            }),
+            client: reqwest::Client::new(),
+            db_pool: Some(pool.clone()),
+            backup_in_progress: Default::default(),
+            active_batches: Default::default(),
+            retention: Default::default(),
+            rate_limiter: Default::default(),
+            provider_health: Default::default(),
+            progress: Default::default(),
+        };
+        let app = test::init_service(App::new().app_data(web::Data::new(state)).configure(configure_routes)).await;
+
+        let evals = json!({ "evals": [{ "model": "ollama:llama3", "prompt": "2+2?" }, { "model": "ollama:llama3", "prompt": "3+3?" }] });
+        let req = test::TestRequest::post().uri("/api/v1/jobs").set_json(&evals).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::ACCEPTED);
+        let job: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!((&job["kind"], &job["status"], &job["total"], &job["completed"]), (&json!("batch"), &json!("queued"), &json!(2), &json!(0)));
+        assert!(job.get("configs").is_none());
+        let status_uri = format!("/api/v1/evals/{}/status", job["batch_id"].as_str().unwrap());
+        let status: serde_json::Value = test::call_and_read_body_json(&app, test::TestRequest::get().uri(&status_uri).to_request()).await;
+        assert_eq!((&status["kind"], &status["stage"], &status["progress"]), (&json!("batch"), &json!("queued"), &json!(0)));
+
+        let req = test::TestRequest::post().uri("/api/v1/jobs").set_json(json!({ "suite": "smoke" })).to_request();
+        let queued: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!((&queued["kind"], &queued["suite_id"], &queued["total"]), (&json!("suite"), &json!(suite.id), &json!(1)));
+
+        let uri = format!("/api/v1/jobs/{}/cancel", job["job_id"].as_str().unwrap());
+        let cancelled: serde_json::Value = test::call_and_read_body_json(&app, test::TestRequest::post().uri(&uri).to_request()).await;
+        assert_eq!(cancelled["status"], "cancelled");
+        let res = test::call_service(&app, test::TestRequest::get().uri(&status_uri).to_request()).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        let res = test::call_service(&app, test::TestRequest::post().uri(&uri).to_request()).await;
+        assert_eq!(res.status(), StatusCode::CONFLICT);
+        let req = test::TestRequest::get().uri(&format!("/api/v1/jobs/{}", job["job_id"].as_str().unwrap())).to_request();
+        let read: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(read["status"], "cancelled");
+
+        for (body, status) in [
+            (json!({ "evals": [{ "model": "ollama:llama3", "prompt": "" }] }), StatusCode::UNPROCESSABLE_ENTITY),
+            (json!({ "evals": [], "suite": "smoke" }), StatusCode::UNPROCESSABLE_ENTITY),
+            (json!({ "suite": "missing" }), StatusCode::NOT_FOUND),
+        ] {
+            let res = test::call_service(&app, test::TestRequest::post().uri("/api/v1/jobs").set_json(&body).to_request()).await;
+            assert_eq!(res.status(), status, "{}", body);
+        }
+        let res = test::call_service(&app, test::TestRequest::get().uri("/api/v1/jobs/missing").to_request()).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+}
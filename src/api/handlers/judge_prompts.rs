@@ -1,8 +1,12 @@
 // src/api/handlers/judge_prompts.rs
-use actix_web::{web, HttpResponse, Result};
+use actix_web::{web, HttpResponse};
 use serde::{Deserialize, Serialize};
+use crate::api::state::ActiveBatch;
 use crate::api::AppState;
 use crate::database;
+use crate::errors::ApiError;
+use crate::judge_ab;
+use crate::judge_calibration;
 
 #[derive(Serialize)]
 pub struct JudgePromptsResponse {
@@ -28,136 +32,606 @@ pub struct SetActiveRequest {
     pub version: i64,
 }
 
+fn judge_prompt_not_found(version: i64) -> ApiError {
+    ApiError::not_found(format!("Judge prompt version {} not found", version))
+}
+
 /// GET /api/v1/judge-prompts - Get all judge prompt versions
 pub async fn get_all_judge_prompts(
     state: web::Data<AppState>,
-) -> Result<HttpResponse> {
-    match state.db_pool.as_ref() {
-        Some(pool) => {
-            match database::get_all_judge_prompts(pool).await {
-                Ok(prompts) => Ok(HttpResponse::Ok().json(JudgePromptsResponse { prompts })),
-                Err(e) => {
-                    log::error!("Failed to fetch judge prompts: {}", e);
-                    Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                        "error": "Failed to fetch judge prompts"
-                    })))
-                }
-            }
-        }
-        None => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-            "error": "Database not initialized"
-        }))),
-    }
+) -> Result<HttpResponse, ApiError> {
+    let pool = state.pool()?;
+    let prompts = database::get_all_judge_prompts(pool)
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch judge prompts", e))?;
+    Ok(HttpResponse::Ok().json(JudgePromptsResponse { prompts }))
 }
 
 /// GET /api/v1/judge-prompts/active - Get the active judge prompt
 pub async fn get_active_judge_prompt(
     state: web::Data<AppState>,
-) -> Result<HttpResponse> {
-    match state.db_pool.as_ref() {
-        Some(pool) => {
-            match database::get_active_judge_prompt(pool).await {
-                Ok(prompt) => Ok(HttpResponse::Ok().json(JudgePromptResponse { prompt })),
-                Err(e) => {
-                    log::error!("Failed to fetch active judge prompt: {}", e);
-                    Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                        "error": "Failed to fetch active judge prompt"
-                    })))
-                }
-            }
-        }
-        None => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-            "error": "Database not initialized"
-        }))),
-    }
+) -> Result<HttpResponse, ApiError> {
+    let pool = state.pool()?;
+    let prompt = database::get_active_judge_prompt(pool)
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch active judge prompt", e))?;
+    Ok(HttpResponse::Ok().json(JudgePromptResponse { prompt }))
 }
 
 /// GET /api/v1/judge-prompts/{version} - Get a specific judge prompt by version
 pub async fn get_judge_prompt_by_version(
     state: web::Data<AppState>,
     path: web::Path<i64>,
-) -> Result<HttpResponse> {
+) -> Result<HttpResponse, ApiError> {
     let version = path.into_inner();
     
-    match state.db_pool.as_ref() {
-        Some(pool) => {
-            match database::get_judge_prompt_by_version(pool, version).await {
-                Ok(prompt) => Ok(HttpResponse::Ok().json(JudgePromptResponse { prompt })),
-                Err(sqlx::Error::RowNotFound) => {
-                    Ok(HttpResponse::NotFound().json(serde_json::json!({
-                        "error": format!("Judge prompt version {} not found", version)
-                    })))
-                }
-                Err(e) => {
-                    log::error!("Failed to fetch judge prompt: {}", e);
-                    Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                        "error": "Failed to fetch judge prompt"
-                    })))
-                }
-            }
-        }
-        None => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-            "error": "Database not initialized"
-        }))),
-    }
+    let pool = state.pool()?;
+    let prompt = database::get_judge_prompt_by_version(pool, version).await.map_err(|e| match e {
+        sqlx::Error::RowNotFound => judge_prompt_not_found(version),
+        e => ApiError::database("Failed to fetch judge prompt", e),
+    })?;
+    Ok(HttpResponse::Ok().json(JudgePromptResponse { prompt }))
 }
 
 /// POST /api/v1/judge-prompts - Create a new judge prompt version
 pub async fn create_judge_prompt(
     state: web::Data<AppState>,
     req: web::Json<CreateJudgePromptRequest>,
-) -> Result<HttpResponse> {
-    match state.db_pool.as_ref() {
-        Some(pool) => {
-            match database::create_judge_prompt(
-                pool,
-                req.name.clone(),
-                req.template.clone(),
-                req.description.clone(),
-                req.set_active,
-            ).await {
-                Ok(prompt) => {
-                    println!("✅ Created judge prompt version {}: {}", prompt.version, prompt.name);
-                    Ok(HttpResponse::Created().json(JudgePromptResponse { prompt }))
-                }
-                Err(e) => {
-                    log::error!("Failed to create judge prompt: {}", e);
-                    Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                        "error": "Failed to create judge prompt"
-                    })))
-                }
-            }
-        }
-        None => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-            "error": "Database not initialized"
-        }))),
+) -> Result<HttpResponse, ApiError> {
+    let pool = state.pool()?;
+    let prompt = database::create_judge_prompt(
+        pool,
+        req.name.clone(),
+        req.template.clone(),
+        req.description.clone(),
+        req.set_active,
+    )
+    .await
+    .map_err(|e| ApiError::database("Failed to create judge prompt", e))?;
+    if prompt.is_active {
+        state.config.judge_prompts.invalidate();
     }
+    tracing::info!("✅ Created judge prompt version {}: {}", prompt.version, prompt.name);
+    Ok(HttpResponse::Created().json(JudgePromptResponse { prompt }))
 }
 
 /// PUT /api/v1/judge-prompts/active - Set a judge prompt version as active
 pub async fn set_active_judge_prompt(
     state: web::Data<AppState>,
     req: web::Json<SetActiveRequest>,
-) -> Result<HttpResponse> {
-    match state.db_pool.as_ref() {
-        Some(pool) => {
-            match database::set_active_judge_prompt(pool, req.version).await {
-                Ok(_) => {
-                    println!("✅ Set judge prompt version {} as active", req.version);
-                    Ok(HttpResponse::Ok().json(serde_json::json!({
-                        "message": format!("Judge prompt version {} is now active", req.version)
-                    })))
-                }
-                Err(e) => {
-                    log::error!("Failed to set active judge prompt: {}", e);
-                    Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                        "error": "Failed to set active judge prompt"
-                    })))
-                }
-            }
+) -> Result<HttpResponse, ApiError> {
+    let pool = state.pool()?;
+    database::set_active_judge_prompt(pool, req.version)
+        .await
+        .map_err(|e| ApiError::database("Failed to set active judge prompt", e))?;
+    state.config.judge_prompts.invalidate();
+    tracing::info!("✅ Set judge prompt version {} as active", req.version);
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": format!("Judge prompt version {} is now active", req.version)
+    })))
+}
+
+fn judge_prompt_conflict(version: i64, conflict: database::JudgePromptConflict) -> ApiError {
+    match conflict {
+        database::JudgePromptConflict::Active => ApiError::conflict(format!(
+            "Judge prompt version {} is active; activate another version first",
+            version
+        )),
+        database::JudgePromptConflict::InUse(usage) => ApiError::conflict(format!(
+            "Judge prompt version {} is referenced by {} rows; create a new version instead",
+            version,
+            usage.total()
+        ))
+        .with_details(serde_json::json!({
+            "blocking_rows": usage.total(),
+            "blocking": usage,
+        })),
+    }
+}
+
+/// PUT /api/v1/judge-prompts/{version} - Update the name, template or description of an unused version
+pub async fn update_judge_prompt(
+    state: web::Data<AppState>,
+    path: web::Path<i64>,
+    req: web::Json<database::JudgePromptUpdate>,
+) -> Result<HttpResponse, ApiError> {
+    let version = path.into_inner();
+    if req.name.is_none() && req.template.is_none() && req.description.is_none() {
+        return Err(ApiError::validation("Give at least one of name, template or description"));
+    }
+
+    let pool = state.pool()?;
+    let prompt = database::update_judge_prompt(pool, version, &req)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => judge_prompt_not_found(version),
+            e => ApiError::database("Failed to update judge prompt", e),
+        })?
+        .map_err(|conflict| judge_prompt_conflict(version, conflict))?;
+    tracing::info!("✏️  Updated judge prompt version {}: {}", prompt.version, prompt.name);
+    Ok(HttpResponse::Ok().json(JudgePromptResponse { prompt }))
+}
+
+/// DELETE /api/v1/judge-prompts/{version} - Delete a version that is neither active nor referenced
+pub async fn delete_judge_prompt(
+    state: web::Data<AppState>,
+    path: web::Path<i64>,
+) -> Result<HttpResponse, ApiError> {
+    let version = path.into_inner();
+
+    let pool = state.pool()?;
+    database::delete_judge_prompt(pool, version)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => judge_prompt_not_found(version),
+            e => ApiError::database("Failed to delete judge prompt", e),
+        })?
+        .map_err(|conflict| judge_prompt_conflict(version, conflict))?;
+    tracing::info!("🗑️  Deleted judge prompt version {}", version);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// GET /api/v1/judge-prompts/{version}/stats - Verdict distribution, judge latency, tokens and
+/// cost over every verdict a version gave
+pub async fn get_judge_prompt_stats(
+    state: web::Data<AppState>,
+    path: web::Path<i64>,
+) -> Result<HttpResponse, ApiError> {
+    let pool = state.pool()?;
+    let stats = judge_prompt_stats(pool, path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(stats))
+}
+
+#[derive(Deserialize)]
+pub struct CompareJudgePromptsQuery {
+    pub a: i64,
+    pub b: i64,
+}
+
+/// GET /api/v1/judge-prompts/compare?a=&b= - Verdict distributions of two versions and their
+/// deltas, overall and on the evaluations both judged
+pub async fn compare_judge_prompts(
+    state: web::Data<AppState>,
+    query: web::Query<CompareJudgePromptsQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let CompareJudgePromptsQuery { a, b } = query.into_inner();
+    if a == b {
+        return Err(ApiError::validation("a and b must differ"));
+    }
+
+    let pool = state.pool()?;
+    let version_a = judge_prompt_stats(pool, a).await?;
+    let version_b = judge_prompt_stats(pool, b).await?;
+    let pairs = database::get_judge_prompt_verdict_pairs(pool, a, b)
+        .await
+        .map_err(|e| ApiError::database("Failed to pair judge prompt verdicts", e))?;
+    Ok(HttpResponse::Ok().json(judge_ab::compare_versions(version_a, version_b, &pairs)))
+}
+
+/// The stats of an existing judge prompt version.
+async fn judge_prompt_stats(pool: &database::DbPool, version: i64) -> Result<database::JudgePromptStats, ApiError> {
+    database::get_judge_prompt_by_version(pool, version).await.map_err(|e| match e {
+        sqlx::Error::RowNotFound => judge_prompt_not_found(version),
+        e => ApiError::database("Failed to fetch judge prompt", e),
+    })?;
+    database::get_judge_prompt_stats(pool, version)
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch judge prompt stats", e))
+}
+
+#[derive(Deserialize)]
+pub struct StartCanaryRequest {
+    /// Candidate judge prompt version
+    pub version: i64,
+    /// Share of new evaluations (0-100) judged with the candidate
+    pub percentage: f64,
+}
+
+#[derive(Deserialize)]
+pub struct CanaryReportQuery {
+    /// Canary to report on; defaults to the most recent one
+    pub id: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct CanaryReport {
+    pub canary: database::JudgePromptCanary,
+    pub arms: Vec<database::CanaryArmStats>,
+}
+
+/// GET /api/v1/judge-prompts/canary - Get the running canary, or `null`
+pub async fn get_canary(
+    state: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let pool = state.pool()?;
+    let canary = database::get_active_canary(pool)
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch judge prompt canary", e))?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "canary": canary })))
+}
+
+/// PUT /api/v1/judge-prompts/canary - Judge a percentage of new evaluations with a candidate version
+pub async fn start_canary(
+    state: web::Data<AppState>,
+    req: web::Json<StartCanaryRequest>,
+) -> Result<HttpResponse, ApiError> {
+    if !(0.0..=100.0).contains(&req.percentage) {
+        return Err(ApiError::validation("percentage must be between 0 and 100"));
+    }
+
+    let pool = state.pool()?;
+    let canary = database::start_canary(pool, req.version, req.percentage).await.map_err(|e| match e {
+        sqlx::Error::RowNotFound => judge_prompt_not_found(req.version),
+        e => ApiError::database("Failed to start judge prompt canary", e),
+    })?;
+    state.config.judge_prompts.invalidate();
+    tracing::info!("🐤 Started canary: {}% of evaluations judged with v{}", canary.percentage, canary.candidate_version);
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "canary": canary })))
+}
+
+/// DELETE /api/v1/judge-prompts/canary - Stop the running canary
+pub async fn stop_canary(
+    state: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let pool = state.pool()?;
+    let canary = database::stop_canary(pool)
+        .await
+        .map_err(|e| ApiError::database("Failed to stop judge prompt canary", e))?
+        .ok_or_else(|| ApiError::not_found("No canary is running"))?;
+    state.config.judge_prompts.invalidate();
+    tracing::info!("🐤 Stopped canary for v{}", canary.candidate_version);
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "canary": canary })))
+}
+
+/// GET /api/v1/judge-prompts/canary/report - Compare verdicts and judge latency/cost between canary arms
+pub async fn get_canary_report(
+    state: web::Data<AppState>,
+    query: web::Query<CanaryReportQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let pool = state.pool()?;
+
+    let canary = database::get_canary(pool, query.id)
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch judge prompt canary", e))?
+        .ok_or_else(|| ApiError::not_found("Canary not found"))?;
+
+    let arms = database::get_canary_arm_stats(pool, &canary)
+        .await
+        .map_err(|e| ApiError::database("Failed to build canary report", e))?;
+    Ok(HttpResponse::Ok().json(CanaryReport { canary, arms }))
+}
+
+/// The stored evaluations `ids`, failing with a 404 on the first that does not exist.
+async fn evaluations_by_id(pool: &database::DbPool, ids: &[String]) -> Result<Vec<database::HistoryEntry>, ApiError> {
+    let mut entries = Vec::with_capacity(ids.len());
+    for id in ids {
+        let entry = database::get_evaluation(pool, id).await.map_err(|e| match e {
+            sqlx::Error::RowNotFound => ApiError::not_found(format!("Evaluation {} not found", id)),
+            e => ApiError::database("Failed to fetch evaluation", e),
+        })?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// The evaluations `ids`, or `sample_size` of them drawn by `sample`, with the batch guard
+/// that keeps retention from purging them while they are judged.
+async fn select_evaluations(
+    state: &AppState,
+    pool: &database::DbPool,
+    ids: Option<&[String]>,
+    sample_size: Option<i64>,
+    max_sample: i64,
+    sample: impl AsyncFnOnce(&database::DbPool, i64) -> Result<Vec<database::HistoryEntry>, sqlx::Error>,
+) -> Result<(ActiveBatch, Vec<database::HistoryEntry>), ApiError> {
+    // Counted from before the fetch, so retention cannot purge the evaluations mid-run
+    let active = state.start_batch();
+    let entries = match (ids, sample_size) {
+        (Some(ids), None) if !ids.is_empty() => evaluations_by_id(pool, ids).await?,
+        (None, Some(size)) if (1..=max_sample).contains(&size) => {
+            sample(pool, size).await.map_err(|e| ApiError::database("Failed to sample evaluations", e))?
+        }
+        (None, Some(_)) => {
+            return Err(ApiError::validation(format!("sample_size must be between 1 and {}", max_sample)));
+        }
+        _ => return Err(ApiError::validation("Give either a non-empty evaluation_ids list or a sample_size")),
+    };
+    Ok((active, entries))
+}
+
+/// `entries` paired with what `inputs` makes of them, and the ones it rejected with its reason.
+fn split_runnable<'a, T>(
+    entries: &'a [database::HistoryEntry],
+    inputs: impl Fn(&'a database::HistoryEntry) -> Result<T, String>,
+) -> (Vec<(&'a database::HistoryEntry, T)>, Vec<judge_ab::SkippedEvaluation>) {
+    let mut skipped = Vec::new();
+    let mut runnable = Vec::with_capacity(entries.len());
+    for entry in entries {
+        match inputs(entry) {
+            Ok(inputs) => runnable.push((entry, inputs)),
+            Err(reason) => skipped.push(judge_ab::SkippedEvaluation { evaluation_id: entry.id.clone(), reason }),
+        }
+    }
+    (runnable, skipped)
+}
+
+/// Largest `sample_size` an A/B test accepts; every sampled evaluation costs two judge calls.
+const MAX_AB_TEST_SAMPLE: i64 = 500;
+
+#[derive(Deserialize)]
+pub struct AbTestRequest {
+    pub version_a: i64,
+    pub version_b: i64,
+    /// Stored evaluations to re-judge; alternatively give `sample_size`
+    pub evaluation_ids: Option<Vec<String>>,
+    /// Number of randomly chosen judged evaluations to re-judge
+    pub sample_size: Option<i64>,
+    /// Judge model for both versions; defaults to the model that originally judged each evaluation
+    pub judge_model: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct AbTestResponse {
+    pub test: database::JudgeAbTest,
+    #[serde(flatten)]
+    pub summary: judge_ab::AbSummary,
+    /// Evaluations that could not be re-judged
+    pub skipped: Vec<judge_ab::SkippedEvaluation>,
+    pub results: Vec<judge_ab::AbComparison>,
+}
+
+/// POST /api/v1/judge-prompts/ab-test - Re-judge stored model outputs with two judge prompt versions and compare verdicts
+pub async fn run_ab_test(
+    state: web::Data<AppState>,
+    req: web::Json<AbTestRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let req = req.into_inner();
+
+    let pool = state.pool()?;
+
+    if req.version_a == req.version_b {
+        return Err(ApiError::validation("version_a and version_b must differ"));
+    }
+    if let Some(model) = &req.judge_model {
+        let (provider, _) = state.config.parse_model(model);
+        if !state.config.is_provider_configured(&provider) {
+            return Err(ApiError::provider_not_configured(&provider, model));
+        }
+    }
+
+    let mut templates = Vec::with_capacity(2);
+    for version in [req.version_a, req.version_b] {
+        let prompt = database::get_judge_prompt_by_version(pool, version).await.map_err(|e| match e {
+            sqlx::Error::RowNotFound => judge_prompt_not_found(version),
+            e => ApiError::database("Failed to fetch judge prompt", e),
+        })?;
+        templates.push(prompt.template);
+    }
+
+    let (_active, entries) = select_evaluations(
+        &state,
+        pool,
+        req.evaluation_ids.as_deref(),
+        req.sample_size,
+        MAX_AB_TEST_SAMPLE,
+        database::sample_judged_evaluations,
+    )
+    .await?;
+    let (runnable, skipped) = split_runnable(&entries, |entry| judge_ab::judge_inputs(entry, req.judge_model.as_deref()));
+    if runnable.is_empty() {
+        return Err(ApiError::validation("None of the evaluations can be re-judged")
+            .with_details(serde_json::json!({ "skipped": skipped })));
+    }
+
+    let test = database::create_judge_ab_test(pool, req.version_a, req.version_b, req.judge_model.as_deref())
+        .await
+        .map_err(|e| ApiError::database("Failed to create judge A/B test", e))?;
+    tracing::info!(
+        "🆎 Judge A/B test {}: v{} vs v{} over {} evaluations",
+        test.id, test.version_a, test.version_b, runnable.len()
+    );
+
+    let pool_ref = Some(pool.as_ref());
+    let results = futures::future::join_all(runnable.iter().map(|(entry, (judge_model, model_output, expected))| {
+        judge_ab::compare(
+            &state.config,
+            &state.client,
+            pool_ref,
+            entry,
+            judge_model,
+            model_output,
+            expected,
+            (templates[0].as_str(), templates[1].as_str()),
+        )
+    }))
+    .await;
+
+    for comparison in &results {
+        if let Err(e) = database::save_judge_ab_comparison(pool, &test, comparison).await {
+            tracing::error!("Failed to save judge A/B result for {}: {}", comparison.evaluation_id, e);
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(AbTestResponse {
+        summary: judge_ab::summarize(test.version_a, test.version_b, &results),
+        test,
+        skipped,
+        results,
+    }))
+}
+
+/// Largest `sample_size` a calibration accepts; every sampled evaluation costs two judge calls.
+const MAX_CALIBRATION_SAMPLE: i64 = 500;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CalibrateRequest {
+    /// Reviewed evaluations to judge; alternatively give `sample_size`
+    pub evaluation_ids: Option<Vec<String>>,
+    /// Number of randomly chosen evaluations a reviewer passed or failed
+    pub sample_size: Option<i64>,
+    /// Judge model to calibrate; defaults to the model that originally judged each evaluation
+    pub judge_model: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct CalibrationResponse {
+    pub calibration: database::JudgeCalibration,
+    #[serde(flatten)]
+    pub summary: judge_calibration::CalibrationSummary,
+    /// Evaluations that could not be judged against a human verdict
+    pub skipped: Vec<judge_ab::SkippedEvaluation>,
+    pub samples: Vec<judge_calibration::CalibrationSample>,
+}
+
+/// POST /api/v1/judge-prompts/{version}/calibrate - Measure a version's agreement with human verdicts
+pub async fn calibrate_judge_prompt(
+    state: web::Data<AppState>,
+    path: web::Path<i64>,
+    req: web::Json<CalibrateRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let version = path.into_inner();
+    let req = req.into_inner();
+
+    let pool = state.pool()?;
+    if let Some(model) = &req.judge_model {
+        let (provider, _) = state.config.parse_model(model);
+        if !state.config.is_provider_configured(&provider) {
+            return Err(ApiError::provider_not_configured(&provider, model));
+        }
+    }
+    let prompt = database::get_judge_prompt_by_version(pool, version).await.map_err(|e| match e {
+        sqlx::Error::RowNotFound => judge_prompt_not_found(version),
+        e => ApiError::database("Failed to fetch judge prompt", e),
+    })?;
+
+    let (_active, entries) = select_evaluations(
+        &state,
+        pool,
+        req.evaluation_ids.as_deref(),
+        req.sample_size,
+        MAX_CALIBRATION_SAMPLE,
+        database::sample_reviewed_evaluations,
+    )
+    .await?;
+    let (runnable, skipped) = split_runnable(&entries, |entry| {
+        let human = judge_calibration::human_verdict(entry).ok_or_else(|| "No human verdict of Pass or Fail".to_string())?;
+        Ok((human, judge_ab::judge_inputs(entry, req.judge_model.as_deref())?))
+    });
+    if runnable.is_empty() {
+        return Err(ApiError::validation("None of the evaluations can be calibrated against")
+            .with_details(serde_json::json!({ "skipped": skipped })));
+    }
+
+    tracing::info!("🎯 Calibrating judge prompt v{} against {} reviewed evaluations", version, runnable.len());
+    let pool_ref = Some(pool.as_ref());
+    let samples = futures::future::join_all(runnable.iter().map(|(entry, (human, (judge_model, model_output, expected)))| {
+        judge_calibration::calibrate_one(
+            &state.config,
+            &state.client,
+            pool_ref,
+            entry,
+            *human,
+            judge_model,
+            model_output,
+            expected,
+            &prompt.template,
+        )
+    }))
+    .await;
+
+    let summary = judge_calibration::summarize(&samples);
+    let calibration = database::create_judge_calibration(pool, version, req.judge_model.as_deref(), &summary, &samples)
+        .await
+        .map_err(|e| ApiError::database("Failed to save judge calibration", e))?;
+    Ok(HttpResponse::Ok().json(CalibrationResponse { calibration, summary, skipped, samples }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::configure_routes;
+    use crate::database::test_support::{evaluation, judge_prompt};
+    use actix_web::{http::StatusCode, test, App};
+    use serde_json::json;
+    use std::sync::Arc;
+    use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+    /// A judge that passes any actual output longer than ten characters, whatever it says.
+    struct Verbose;
+    impl Respond for Verbose {
+        fn respond(&self, request: &Request) -> ResponseTemplate {
+            let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+            let (_, actual) = body["prompt"].as_str().unwrap().split_once(" vs ").unwrap();
+            let verdict = if actual.chars().count() > 10 { "PASS" } else { "FAIL" };
+            ResponseTemplate::new(200).set_body_json(json!({ "response": format!("Verdict: {}", verdict), "done": true }))
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_calibration_measures_agreement_and_biases_against_reviews() {
+        let server = MockServer::start().await;
+        Mock::given(wiremock::matchers::method("POST")).respond_with(Verbose).mount(&server).await;
+        let pool = database::test_pool().await;
+        let prompt = judge_prompt("Lenient").save(&pool).await;
+        let reviewed = [
+            ("padded", "The answer is four, clearly.", "Fail"),
+            ("terse", "4", "Pass"),
+            ("explained", "Four, since two plus two is four.", "Pass"),
+        ];
+        for (id, output, verdict) in reviewed {
+            evaluation(id).output(output).save(&pool).await;
+            database::review_evaluation(&pool, id, verdict, None).await.unwrap();
+        }
+        evaluation("unreviewed").save(&pool).await;
+        let state = AppState {
+            config: Arc::new(crate::config::AppConfig {
+                ollama: Some(crate::config::OllamaConfig { api_base: server.uri(), models: vec![] }),
+                ..Default::default()
+            }),
+            client: reqwest::Client::new(),
+            db_pool: Some(Arc::new(pool)),
+            backup_in_progress: Default::default(),
+            active_batches: Default::default(),
+            retention: Default::default(),
+            rate_limiter: Default::default(),
+            provider_health: Default::default(),
+            progress: Default::default(),
+        };
+        let app = test::init_service(App::new().app_data(web::Data::new(state)).configure(configure_routes)).await;
+
+        let uri = format!("/api/v1/judge-prompts/{}/calibrate", prompt.version);
+        let ids = json!({ "evaluation_ids": ["padded", "terse", "explained", "unreviewed"] });
+        let body: serde_json::Value = test::call_and_read_body_json(&app, test::TestRequest::post().uri(&uri).set_json(&ids).to_request()).await;
+        assert_eq!((&body["evaluations"], &body["judged"], &body["correct"]), (&json!(3), &json!(3), &json!(1)), "{}", body);
+        assert_eq!((&body["false_pass_rate"], &body["false_fail_rate"]), (&json!(1.0), &json!(0.5)));
+        assert_eq!(body["skipped"][0]["evaluation_id"], "unreviewed");
+        // Swapped, every judged output is "4": the long outputs lose their Pass
+        assert_eq!(body["position_bias"], json!({ "compared": 3, "flips": 2, "flip_rate": 2.0 / 3.0 }));
+        assert!(body["verbosity_bias"]["length_pass_correlation"].as_f64().unwrap() > 0.9, "{}", body["verbosity_bias"]);
+        assert_eq!(body["calibration"]["version"], prompt.version);
+
+        // The calibration is kept, so the version can no longer be deleted
+        let res = test::call_service(&app, test::TestRequest::delete().uri(&format!("/api/v1/judge-prompts/{}", prompt.version)).to_request()).await;
+        assert_eq!(res.status(), StatusCode::CONFLICT);
+        let body: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!(body["details"]["blocking"]["calibrations"], 1);
+
+        let sampled: serde_json::Value = test::call_and_read_body_json(
+            &app,
+            test::TestRequest::post().uri(&uri).set_json(json!({ "sample_size": 10 })).to_request(),
+        )
+        .await;
+        assert_eq!((&sampled["evaluations"], &sampled["skipped"]), (&json!(3), &json!([])));
+
+        for (uri, body, status) in [
+            ("/api/v1/judge-prompts/999/calibrate", json!({ "sample_size": 10 }), StatusCode::NOT_FOUND),
+            (uri.as_str(), json!({}), StatusCode::BAD_REQUEST),
+            (uri.as_str(), json!({ "sample_size": 0 }), StatusCode::BAD_REQUEST),
+            (uri.as_str(), json!({ "evaluation_ids": ["unreviewed"] }), StatusCode::BAD_REQUEST),
+        ] {
+            let res = test::call_service(&app, test::TestRequest::post().uri(uri).set_json(&body).to_request()).await;
+            assert_eq!(res.status(), status, "{} {}", uri, body);
         }
-        None => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-            "error": "Database not initialized"
-        }))),
     }
 }
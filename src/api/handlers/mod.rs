@@ -1,16 +1,49 @@
 // src/api/handlers/mod.rs
+mod admin;
+mod api_keys;
+mod compare;
+mod datasets;
 mod health;
 mod evals;
 mod experiments;
+mod failure_taxonomy;
 mod history;
+mod jobs;
 pub mod ws;
 mod judge_prompts;
+mod presets;
 mod prompt_versions;
+mod rejudge;
+mod robustness;
+mod reviews;
+mod schedules;
+mod shares;
+mod stats;
+mod suites;
+mod tags;
+mod triage;
 
-pub use health::health_check;
-pub use evals::{run_eval, run_batch, get_eval, get_status, get_history, get_models};
+pub use admin::{clear_cache, create_backup, list_backups, get_retention};
+pub use api_keys::{get_me, get_api_keys, create_api_key, revoke_api_key};
+pub use compare::compare_runs;
+pub use datasets::{upload_dataset, get_all_datasets, get_dataset, run_dataset, adopt_expected};
+pub use health::{health_check, liveness, metrics};
+pub use evals::{EvalResponse, run_eval, run_batch, run_chain, run_matrix, get_batch, get_batch_report, estimate_batch, explain_eval, get_eval, get_raw_exchanges, get_status, get_history, get_models};
+pub(crate) use evals::{finish_eval, stored_result};
 pub use experiments::{create_experiment, get_experiment};
-pub use ws::{ws_handler, WsBroker};
+pub use jobs::{create_job, get_job, cancel_job};
+pub use failure_taxonomy::{get_failure_taxonomy, update_failure_taxonomy};
+pub use presets::{get_presets, create_preset, get_preset, update_preset, delete_preset};
+pub use schedules::{get_all_schedules, get_schedule, create_schedule, update_schedule, delete_schedule};
+pub use rejudge::{rejudge_eval, rejudge_evals, get_replaced_judgements};
+pub use robustness::{run_robustness, get_robustness_group};
+pub use shares::{create_share, get_shares, revoke_share, get_shared_evaluation};
+pub use tags::{get_tags, update_eval_tags};
+pub use triage::{get_triage_queue, get_triage_count, rejudge_triage};
+pub use ws::{ws_handler, ws_stats, WsBroker};
 
 pub use judge_prompts::*;
-pub use prompt_versions::*; 
+pub use prompt_versions::*;
+pub use reviews::*;
+pub use stats::*;
+pub use suites::*;
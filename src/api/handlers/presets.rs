@@ -0,0 +1,380 @@
+// src/api/handlers/presets.rs
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::HashMap;
+use crate::api::auth::{Caller, Role};
+use crate::api::AppState;
+use crate::database::{self, DbPool, Preset};
+use crate::errors::ApiError;
+use crate::models::FieldError;
+use crate::preset::{self, PresetApplication, PRESET_FIELD};
+
+#[derive(Deserialize)]
+pub struct CreatePresetRequest {
+    pub name: String,
+    pub description: Option<String>,
+    pub defaults: serde_json::Value,
+    /// Share the preset with every caller instead of scoping it to the calling key (admin only)
+    #[serde(default)]
+    pub shared: bool,
+}
+
+#[derive(Deserialize)]
+pub struct UpdatePresetRequest {
+    pub description: Option<String>,
+    pub defaults: serde_json::Value,
+}
+
+/// The caller of a request; requests that skipped `authorize` count as anonymous admins,
+/// as when authentication is off.
+fn caller(http_req: &HttpRequest) -> Caller {
+    http_req.extensions().get::<Caller>().cloned().unwrap_or_else(|| Caller {
+        key_id: None,
+        name: "anonymous".to_string(),
+        role: Role::Admin,
+        auth_enabled: false,
+        aggregate_only: false,
+    })
+}
+
+/// Forbidden, unless the caller may change `preset`: shared presets are managed by admins.
+fn check_can_manage(caller: &Caller, preset: &Preset) -> Result<(), ApiError> {
+    if preset.owner_key_id.is_none() && caller.role < Role::Admin {
+        return Err(ApiError::forbidden(format!(
+            "Preset '{}' is shared; changing it requires the 'admin' role",
+            preset.name
+        ))
+        .with_details(serde_json::json!({ "required_role": Role::Admin })));
+    }
+    Ok(())
+}
+
+fn preset_not_found(name: &str) -> ApiError {
+    ApiError::not_found(format!("Preset '{}' not found", name))
+}
+
+fn preset_error(action: &str, e: sqlx::Error) -> ApiError {
+    ApiError::database(format!("Failed to {} preset", action), e)
+}
+
+/// The preset `name` as `caller` sees it.
+async fn find_preset(pool: &DbPool, caller: &Caller, name: &str) -> Result<Preset, ApiError> {
+    database::find_preset(pool, name, caller.key_id.as_deref())
+        .await
+        .map_err(|e| preset_error("fetch", e))?
+        .ok_or_else(|| preset_not_found(name))
+}
+
+/// GET /api/v1/presets - Presets of the calling key and shared presets
+pub async fn get_presets(
+    state: web::Data<AppState>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let caller = caller(&http_req);
+
+    let pool = state.pool()?;
+    let presets = database::get_presets(pool, caller.key_id.as_deref())
+        .await
+        .map_err(|e| preset_error("fetch", e))?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "presets": presets })))
+}
+
+/// POST /api/v1/presets - Save named defaults for run and batch requests
+pub async fn create_preset(
+    state: web::Data<AppState>,
+    http_req: HttpRequest,
+    req: web::Json<CreatePresetRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let caller = caller(&http_req);
+    let req = req.into_inner();
+    let name = req.name.trim();
+    if name.is_empty() {
+        return Err(ApiError::validation("Preset name must not be empty"));
+    }
+    preset::validate_defaults(&req.defaults).map_err(ApiError::validation)?;
+    if req.shared && caller.role < Role::Admin {
+        return Err(ApiError::forbidden("Creating a shared preset requires the 'admin' role")
+            .with_details(serde_json::json!({ "required_role": Role::Admin })));
+    }
+    // Keys without an id of their own (the bootstrap admin key, or no auth) can only share
+    let owner_key_id = if req.shared { None } else { caller.key_id.as_deref() };
+
+    let pool = state.pool()?;
+    let preset = database::create_preset(pool, name, owner_key_id, req.description.as_deref(), &req.defaults, &caller.name)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db) if db.is_unique_violation() => {
+                ApiError::conflict(format!("A preset named '{}' already exists", name))
+            }
+            e => preset_error("save", e),
+        })?;
+    tracing::info!("🧩 {} saved preset '{}'", caller.name, preset.name);
+    Ok(HttpResponse::Created().json(serde_json::json!({ "preset": preset })))
+}
+
+/// GET /api/v1/presets/{name} - The preset a run naming it would use
+pub async fn get_preset(
+    state: web::Data<AppState>,
+    http_req: HttpRequest,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let caller = caller(&http_req);
+    let name = path.into_inner();
+
+    let preset = find_preset(state.pool()?, &caller, &name).await?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "preset": preset })))
+}
+
+/// PUT /api/v1/presets/{name} - Replace a preset's description and defaults
+pub async fn update_preset(
+    state: web::Data<AppState>,
+    http_req: HttpRequest,
+    path: web::Path<String>,
+    req: web::Json<UpdatePresetRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let caller = caller(&http_req);
+    let name = path.into_inner();
+    let req = req.into_inner();
+    preset::validate_defaults(&req.defaults).map_err(ApiError::validation)?;
+
+    let pool = state.pool()?;
+    let preset = find_preset(pool, &caller, &name).await?;
+    check_can_manage(&caller, &preset)?;
+
+    let preset = database::update_preset(pool, &preset.id, req.description.as_deref(), &req.defaults)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => preset_not_found(&name),
+            e => preset_error("save", e),
+        })?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "preset": preset })))
+}
+
+/// DELETE /api/v1/presets/{name} - Delete a preset; evaluations keep their record of it
+pub async fn delete_preset(
+    state: web::Data<AppState>,
+    http_req: HttpRequest,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let caller = caller(&http_req);
+    let name = path.into_inner();
+
+    let pool = state.pool()?;
+    let preset = find_preset(pool, &caller, &name).await?;
+    check_can_manage(&caller, &preset)?;
+
+    database::delete_preset(pool, &preset.id).await.map_err(|e| match e {
+        sqlx::Error::RowNotFound => preset_not_found(&name),
+        e => preset_error("delete", e),
+    })?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Parses run and batch request bodies, merging the preset each names beneath it. Each
+/// preset is looked up once per request.
+pub(crate) struct PresetResolver<'a> {
+    pool: Option<&'a DbPool>,
+    owner_key_id: Option<String>,
+    presets: HashMap<String, Preset>,
+}
+
+impl<'a> PresetResolver<'a> {
+    pub(crate) fn new(state: &'a AppState, http_req: &HttpRequest) -> Self {
+        Self {
+            pool: state.db_pool.as_deref(),
+            owner_key_id: caller(http_req).key_id,
+            presets: HashMap::new(),
+        }
+    }
+
+    /// `body` as a `T`, with the preset it names merged beneath it, and how the preset
+    /// was applied. `index` is the body's position in a batch, which errors name.
+    pub(crate) async fn resolve<T: DeserializeOwned>(
+        &mut self,
+        body: serde_json::Value,
+        index: Option<usize>,
+    ) -> Result<(T, Option<PresetApplication>), ApiError> {
+        let context = index.map(|i| format!("Eval config {}: ", i)).unwrap_or_default();
+        let context = context.as_str();
+        let serde_json::Value::Object(mut request) = body else {
+            return Err(ApiError::validation(format!("{}expected a JSON object", context)));
+        };
+        let (request, application) = match request.remove(PRESET_FIELD) {
+            None | Some(serde_json::Value::Null) => (request, None),
+            Some(serde_json::Value::String(name)) => {
+                let preset = self.preset(&name, context).await?;
+                let (merged, application) = preset::merge(preset, request);
+                (merged, Some(application))
+            }
+            Some(_) => return Err(ApiError::validation(format!("{}'preset' must be a preset name", context))),
+        };
+
+        serde_json::from_value(serde_json::Value::Object(request))
+            .map(|parsed| (parsed, application))
+            .map_err(|e| {
+                let error = FieldError::from_serde(&e);
+                ApiError::invalid_fields(vec![match index {
+                    Some(i) => error.within(&format!("[{}]", i)),
+                    None => error,
+                }])
+            })
+    }
+
+    async fn preset(&mut self, name: &str, context: &str) -> Result<&Preset, ApiError> {
+        if !self.presets.contains_key(name) {
+            let pool = self.pool.ok_or_else(ApiError::db_unavailable)?;
+            match database::find_preset(pool, name, self.owner_key_id.as_deref()).await {
+                Ok(Some(preset)) => {
+                    self.presets.insert(name.to_string(), preset);
+                }
+                Ok(None) => {
+                    return Err(ApiError::new(
+                        actix_web::http::StatusCode::BAD_REQUEST,
+                        "preset_not_found",
+                        format!("{}Preset '{}' not found", context, name),
+                    ));
+                }
+                Err(e) => return Err(preset_error("fetch", e)),
+            }
+        }
+        Ok(&self.presets[name])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::auth::{authorize, generate_key, hash_key};
+    use crate::api::configure_routes;
+    use crate::api::handlers::WsBroker;
+    use actix_web::middleware::from_fn;
+    use actix_web::{http::header, http::StatusCode, test, App};
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+
+    const ADMIN_KEY: &str = "bootstrap-admin-key";
+
+    async fn state_with_runner_keys() -> (AppState, String, String) {
+        let pool = database::test_pool().await;
+        let mut keys = Vec::new();
+        for name in ["ci", "notebook"] {
+            let key = generate_key();
+            database::create_api_key(&pool, name.to_string(), Role::Runner.as_str(), &hash_key(&key), &key[..8])
+                .await
+                .unwrap();
+            keys.push(key);
+        }
+        let state = AppState {
+            config: Arc::new(crate::config::AppConfig {
+                admin_api_key: Some(ADMIN_KEY.to_string()),
+                // Nothing listens here, so runs fail at the provider call
+                ollama: Some(crate::config::OllamaConfig { api_base: "http://127.0.0.1:9".to_string(), models: vec![] }),
+                provider_retry: crate::providers::middleware::RetryPolicy { max_retries: 0, ..Default::default() },
+                ..Default::default()
+            }),
+            client: reqwest::Client::new(),
+            db_pool: Some(Arc::new(pool)),
+            backup_in_progress: Arc::new(AtomicBool::new(false)),
+            active_batches: Default::default(),
+            retention: Default::default(),
+            rate_limiter: Default::default(),
+            provider_health: Default::default(),
+            progress: Default::default(),
+        };
+        let notebook = keys.pop().unwrap();
+        (state, keys.pop().unwrap(), notebook)
+    }
+
+    fn as_key(req: test::TestRequest, key: &str) -> test::TestRequest {
+        req.insert_header((header::AUTHORIZATION, format!("Bearer {}", key)))
+    }
+
+    #[actix_web::test]
+    async fn test_presets_are_scoped_to_their_key_and_shared_ones_need_admin() {
+        let (state, ci, notebook) = state_with_runner_keys().await;
+        let app = test::init_service(
+            App::new().app_data(web::Data::new(state)).wrap(from_fn(authorize)).configure(configure_routes),
+        ).await;
+
+        let shared = serde_json::json!({ "name": "nightly", "defaults": { "criteria": "Shared" }, "shared": true });
+        let res = test::call_service(&app, as_key(test::TestRequest::post().uri("/api/v1/presets").set_json(&shared), &ci).to_request()).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        let res = test::call_service(&app, as_key(test::TestRequest::post().uri("/api/v1/presets").set_json(&shared), ADMIN_KEY).to_request()).await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+
+        let own = serde_json::json!({ "name": "nightly", "defaults": { "criteria": "Mine" } });
+        let res = test::call_service(&app, as_key(test::TestRequest::post().uri("/api/v1/presets").set_json(&own), &ci).to_request()).await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+        let res = test::call_service(&app, as_key(test::TestRequest::post().uri("/api/v1/presets").set_json(&own), &ci).to_request()).await;
+        assert_eq!(res.status(), StatusCode::CONFLICT);
+        let typo = serde_json::json!({ "name": "typo", "defaults": { "critera": "Mine" } });
+        let res = test::call_service(&app, as_key(test::TestRequest::post().uri("/api/v1/presets").set_json(&typo), &ci).to_request()).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        // Each key sees its own preset over the shared one of the same name
+        let mine: serde_json::Value = test::call_and_read_body_json(&app, as_key(test::TestRequest::get().uri("/api/v1/presets/nightly"), &ci).to_request()).await;
+        assert_eq!(mine["preset"]["defaults"]["criteria"], "Mine");
+        let theirs: serde_json::Value = test::call_and_read_body_json(&app, as_key(test::TestRequest::get().uri("/api/v1/presets/nightly"), &notebook).to_request()).await;
+        assert_eq!(theirs["preset"]["defaults"]["criteria"], "Shared");
+        let listed: serde_json::Value = test::call_and_read_body_json(&app, as_key(test::TestRequest::get().uri("/api/v1/presets"), &notebook).to_request()).await;
+        assert_eq!(listed["presets"].as_array().unwrap().len(), 1);
+
+        let update = serde_json::json!({ "defaults": { "criteria": "Changed" } });
+        let res = test::call_service(&app, as_key(test::TestRequest::put().uri("/api/v1/presets/nightly").set_json(&update), &notebook).to_request()).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        let res = test::call_service(&app, as_key(test::TestRequest::delete().uri("/api/v1/presets/nightly"), &ci).to_request()).await;
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+        let fallback: serde_json::Value = test::call_and_read_body_json(&app, as_key(test::TestRequest::get().uri("/api/v1/presets/nightly"), &ci).to_request()).await;
+        assert_eq!(fallback["preset"]["defaults"]["criteria"], "Shared");
+    }
+
+    #[actix_web::test]
+    async fn test_runs_record_the_merged_preset_and_its_overrides() {
+        let (state, ci, _) = state_with_runner_keys().await;
+        let pool = state.db_pool.clone().unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(WsBroker::new()))
+                .wrap(from_fn(authorize))
+                .configure(configure_routes),
+        ).await;
+
+        let preset = serde_json::json!({
+            "name": "strict",
+            "defaults": { "judge_model": "anthropic:claude-sonnet-4", "criteria": "Be strict" },
+        });
+        let res = test::call_service(&app, as_key(test::TestRequest::post().uri("/api/v1/presets").set_json(&preset), &ci).to_request()).await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+
+        // The provider is unreachable, so the eval errors, but it is still saved with its preset
+        let run = serde_json::json!({
+            "preset": "strict",
+            "model": "ollama:llama3",
+            "prompt": "What is 2+2?",
+            "judge_model": "openai:gpt-4",
+        });
+        let res = test::call_service(&app, as_key(test::TestRequest::post().uri("/api/v1/evals/run").set_json(&run), &ci).to_request()).await;
+        assert_eq!(res.status(), StatusCode::BAD_GATEWAY);
+        let body: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!(body["details"]["preset"]["overrides"][0]["field"], "judge_model");
+
+        let stored = database::get_evaluation(&pool, body["details"]["eval_id"].as_str().unwrap()).await.unwrap();
+        let application = stored.preset.unwrap();
+        assert_eq!(application.name, "strict");
+        assert!(!application.shared);
+        assert_eq!(application.applied, ["criteria"]);
+        assert_eq!(application.overrides[0].preset_value, "anthropic:claude-sonnet-4");
+        assert_eq!(application.overrides[0].request_value, "openai:gpt-4");
+        assert_eq!(application.effective_config["judge_model"], "openai:gpt-4");
+        assert_eq!(application.effective_config["criteria"], "Be strict");
+
+        let batch = serde_json::json!([{ "preset": "missing", "model": "ollama:llama3", "prompt": "p" }]);
+        let res = test::call_service(&app, as_key(test::TestRequest::post().uri("/api/v1/evals/batch").set_json(&batch), &ci).to_request()).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!(body["code"], "preset_not_found");
+        assert_eq!(body["message"], "Eval config 0: Preset 'missing' not found");
+    }
+}
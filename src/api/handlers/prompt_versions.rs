@@ -1,7 +1,11 @@
-use actix_web::{web, HttpResponse, Result};
+use actix_web::{web, HttpResponse};
 use serde::{Deserialize, Serialize};
 use crate::api::AppState;
+use crate::config::EvalConfig;
 use crate::database;
+use crate::errors::ApiError;
+use crate::models::FieldError;
+use crate::text_diff::{self, LineDiff};
 
 #[derive(Serialize)]
 pub struct PromptVersionsResponse {
@@ -18,6 +22,26 @@ pub struct PromptStatsResponse {
     pub stats: database::PromptStats,
 }
 
+/// What changed from prompt version `from` to `to`.
+#[derive(Serialize)]
+pub struct PromptVersionDiffResponse {
+    pub from: i64,
+    pub to: i64,
+    /// The templates' unified diff, headed `v{from}` and `v{to}`
+    pub template: LineDiff,
+    /// `name`, `description` and `metadata` where they differ
+    pub changes: Vec<PromptFieldChange>,
+    pub tags_added: Vec<String>,
+    pub tags_removed: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct PromptFieldChange {
+    pub field: &'static str,
+    pub from: serde_json::Value,
+    pub to: serde_json::Value,
+}
+
 #[derive(Deserialize)]
 pub struct CreatePromptVersionRequest {
     pub name: String,
@@ -28,104 +52,248 @@ pub struct CreatePromptVersionRequest {
     pub set_active: bool,
 }
 
+/// Fills in the prompt of `config` from the template of the prompt version it names, if any;
+/// the runner renders it with the metadata like any prompt. `within` locates the config in
+/// field errors, as `[2]` does in a batch.
+pub(crate) async fn apply_prompt_version(
+    state: &AppState,
+    config: &mut EvalConfig,
+    within: Option<&str>,
+) -> Result<(), ApiError> {
+    let Some(version) = config.prompt_version else {
+        return Ok(());
+    };
+    if !config.prompt.is_empty() {
+        let error = FieldError::new("prompt_version", "give either prompt or prompt_version, not both");
+        return Err(ApiError::invalid_fields(vec![match within {
+            Some(within) => error.within(within),
+            None => error,
+        }]));
+    }
+    let prompt = fetch_prompt_version(state.pool()?, version).await?;
+    config.prompt = prompt.prompt_template;
+    Ok(())
+}
+
+async fn fetch_prompt_version(pool: &database::DbPool, version: i64) -> Result<database::PromptVersion, ApiError> {
+    database::get_prompt_version(pool, version).await.map_err(|e| match e {
+        sqlx::Error::RowNotFound => prompt_version_not_found(version),
+        e => ApiError::database("Failed to fetch prompt version", e),
+    })
+}
+
+fn prompt_version_not_found(version: i64) -> ApiError {
+    ApiError::not_found(format!("Prompt version {} not found", version))
+}
+
+/// The differences between two prompt versions besides their templates.
+fn field_changes(from: &database::PromptVersion, to: &database::PromptVersion) -> Vec<PromptFieldChange> {
+    let text = |value: Option<&str>| value.map_or(serde_json::Value::Null, serde_json::Value::from);
+    // Metadata is stored as JSON text, so key order or spacing alone is no change
+    let json = |value: Option<&str>| value.map_or(serde_json::Value::Null, |v| serde_json::from_str(v).unwrap_or_else(|_| v.into()));
+    [
+        ("name", text(Some(&from.name)), text(Some(&to.name))),
+        ("description", text(from.description.as_deref()), text(to.description.as_deref())),
+        ("metadata", json(from.metadata.as_deref()), json(to.metadata.as_deref())),
+    ]
+    .into_iter()
+    .filter(|(_, from, to)| from != to)
+    .map(|(field, from, to)| PromptFieldChange { field, from, to })
+    .collect()
+}
+
+/// The tags of a prompt version, stored as a JSON array.
+fn tags(prompt: &database::PromptVersion) -> Vec<String> {
+    prompt.tags.as_deref().and_then(|tags| serde_json::from_str(tags).ok()).unwrap_or_default()
+}
+
 /// GET /api/v1/prompt-versions - Get all prompt versions
 pub async fn get_all_prompt_versions(
     state: web::Data<AppState>,
-) -> Result<HttpResponse> {
-    match state.db_pool.as_ref() {
-        Some(pool) => {
-            match database::get_all_prompt_versions(pool).await {
-                Ok(prompts) => Ok(HttpResponse::Ok().json(PromptVersionsResponse { prompts })),
-                Err(e) => {
-                    log::error!("Failed to fetch prompt versions: {}", e);
-                    Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                        "error": "Failed to fetch prompt versions"
-                    })))
-                }
-            }
-        }
-        None => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-            "error": "Database not initialized"
-        }))),
-    }
+) -> Result<HttpResponse, ApiError> {
+    let pool = state.pool()?;
+    let prompts = database::get_all_prompt_versions(pool)
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch prompt versions", e))?;
+    Ok(HttpResponse::Ok().json(PromptVersionsResponse { prompts }))
 }
 
 /// GET /api/v1/prompt-versions/active - Get active prompt version
 pub async fn get_active_prompt_version(
     state: web::Data<AppState>,
-) -> Result<HttpResponse> {
-    match state.db_pool.as_ref() {
-        Some(pool) => {
-            match database::get_active_prompt_version(pool).await {
-                Ok(prompt) => Ok(HttpResponse::Ok().json(PromptVersionResponse { prompt })),
-                Err(e) => {
-                    log::error!("Failed to fetch active prompt version: {}", e);
-                    Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                        "error": "Failed to fetch active prompt version"
-                    })))
-                }
-            }
-        }
-        None => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-            "error": "Database not initialized"
-        }))),
-    }
+) -> Result<HttpResponse, ApiError> {
+    let pool = state.pool()?;
+    let prompt = database::get_active_prompt_version(pool)
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch active prompt version", e))?;
+    Ok(HttpResponse::Ok().json(PromptVersionResponse { prompt }))
+}
+
+/// GET /api/v1/prompt-versions/{version} - Get a specific prompt version
+pub async fn get_prompt_version(
+    state: web::Data<AppState>,
+    path: web::Path<i64>,
+) -> Result<HttpResponse, ApiError> {
+    let prompt = fetch_prompt_version(state.pool()?, path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(PromptVersionResponse { prompt }))
+}
+
+/// GET /api/v1/prompt-versions/{a}/diff/{b} - Diff the templates, tags and other fields of two versions
+pub async fn diff_prompt_versions(
+    state: web::Data<AppState>,
+    path: web::Path<(i64, i64)>,
+) -> Result<HttpResponse, ApiError> {
+    let (a, b) = path.into_inner();
+    let pool = state.pool()?;
+    let from = fetch_prompt_version(pool, a).await?;
+    let to = fetch_prompt_version(pool, b).await?;
+
+    let template = text_diff::unified_diff(
+        &from.prompt_template,
+        &to.prompt_template,
+        &format!("v{}", a),
+        &format!("v{}", b),
+    );
+    let (from_tags, to_tags) = (tags(&from), tags(&to));
+    Ok(HttpResponse::Ok().json(PromptVersionDiffResponse {
+        from: a,
+        to: b,
+        template,
+        changes: field_changes(&from, &to),
+        tags_added: to_tags.iter().filter(|tag| !from_tags.contains(tag)).cloned().collect(),
+        tags_removed: from_tags.iter().filter(|tag| !to_tags.contains(tag)).cloned().collect(),
+    }))
+}
+
+/// POST /api/v1/prompt-versions/{version}/activate - Make a version the active one, as to roll back
+pub async fn activate_prompt_version(
+    state: web::Data<AppState>,
+    path: web::Path<i64>,
+) -> Result<HttpResponse, ApiError> {
+    let version = path.into_inner();
+    let pool = state.pool()?;
+    database::set_active_prompt_version(pool, version).await.map_err(|e| match e {
+        sqlx::Error::RowNotFound => prompt_version_not_found(version),
+        e => ApiError::database("Failed to set active prompt version", e),
+    })?;
+    let prompt = fetch_prompt_version(pool, version).await?;
+    tracing::info!("✅ Set prompt version {} as active", version);
+    Ok(HttpResponse::Ok().json(PromptVersionResponse { prompt }))
 }
 
 /// POST /api/v1/prompt-versions - Create new prompt version
 pub async fn create_prompt_version(
     state: web::Data<AppState>,
     req: web::Json<CreatePromptVersionRequest>,
-) -> Result<HttpResponse> {
-    match state.db_pool.as_ref() {
-        Some(pool) => {
-            match database::create_prompt_version(
-                pool,
-                req.name.clone(),
-                req.prompt_template.clone(),
-                req.description.clone(),
-                req.tags.clone(),
-                req.set_active,
-            ).await {
-                Ok(prompt) => {
-                    println!("✅ Created prompt version {}: {}", prompt.version, prompt.name);
-                    Ok(HttpResponse::Created().json(PromptVersionResponse { prompt }))
-                }
-                Err(e) => {
-                    log::error!("Failed to create prompt version: {}", e);
-                    Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                        "error": "Failed to create prompt version"
-                    })))
-                }
-            }
-        }
-        None => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-            "error": "Database not initialized"
-        }))),
-    }
+) -> Result<HttpResponse, ApiError> {
+    let pool = state.pool()?;
+    let prompt = database::create_prompt_version(
+        pool,
+        req.name.clone(),
+        req.prompt_template.clone(),
+        req.description.clone(),
+        req.tags.clone(),
+        req.set_active,
+    )
+    .await
+    .map_err(|e| ApiError::database("Failed to create prompt version", e))?;
+    tracing::info!("✅ Created prompt version {}: {}", prompt.version, prompt.name);
+    Ok(HttpResponse::Created().json(PromptVersionResponse { prompt }))
 }
 
 /// GET /api/v1/prompt-versions/{version}/stats - Get performance stats for a prompt version
 pub async fn get_prompt_version_stats(
     state: web::Data<AppState>,
     path: web::Path<i64>,
-) -> Result<HttpResponse> {
+) -> Result<HttpResponse, ApiError> {
     let version = path.into_inner();
     
-    match state.db_pool.as_ref() {
-        Some(pool) => {
-            match database::get_prompt_version_stats(pool, version).await {
-                Ok(stats) => Ok(HttpResponse::Ok().json(PromptStatsResponse { stats })),
-                Err(e) => {
-                    log::error!("Failed to fetch prompt version stats: {}", e);
-                    Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                        "error": "Failed to fetch prompt version stats"
-                    })))
-                }
-            }
-        }
-        None => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-            "error": "Database not initialized"
-        }))),
+    let pool = state.pool()?;
+    let stats = database::get_prompt_version_stats(pool, version)
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch prompt version stats", e))?;
+    Ok(HttpResponse::Ok().json(PromptStatsResponse { stats }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::configure_routes;
+    use crate::database::test_support::prompt_version;
+    use actix_web::{http::StatusCode, test, App};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[actix_web::test]
+    async fn test_prompt_versions_can_be_fetched_diffed_and_activated() {
+        let pool = database::test_pool().await;
+        let v1 = prompt_version("Direct")
+            .template("Answer the question.\n{{prompt}}\n")
+            .tags(&["direct", "math"])
+            .active()
+            .save(&pool)
+            .await;
+        let v2 = prompt_version("Chain-of-Thought")
+            .template("Answer the question.\nThink step by step.\n{{prompt}}")
+            .description("Reasons first")
+            .tags(&["math", "cot"])
+            .active()
+            .save(&pool)
+            .await;
+        let state = AppState {
+            config: Arc::new(crate::config::AppConfig::default()),
+            client: reqwest::Client::new(),
+            db_pool: Some(Arc::new(pool)),
+            backup_in_progress: Default::default(),
+            active_batches: Default::default(),
+            retention: Default::default(),
+            rate_limiter: Default::default(),
+            provider_health: Default::default(),
+            progress: Default::default(),
+        };
+        let app = test::init_service(App::new().app_data(web::Data::new(state)).configure(configure_routes)).await;
+
+        let uri = format!("/api/v1/prompt-versions/{}", v1.version);
+        let fetched: serde_json::Value = test::call_and_read_body_json(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+        assert_eq!(fetched["prompt"]["name"], "Direct");
+        assert_eq!(fetched["prompt"]["is_active"], false);
+        let res = test::call_service(&app, test::TestRequest::get().uri("/api/v1/prompt-versions/999").to_request()).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        let uri = format!("/api/v1/prompt-versions/{}/diff/{}", v1.version, v2.version);
+        let diff: serde_json::Value = test::call_and_read_body_json(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+        assert_eq!(
+            diff["template"]["unified"],
+            format!(
+                "--- v{}\n+++ v{}\n@@ -1,2 +1,3 @@\n Answer the question.\n-{{{{prompt}}}}\n+Think step by step.\n+{{{{prompt}}}}\n\\ No newline at end of file\n",
+                v1.version, v2.version
+            )
+        );
+        assert_eq!((diff["template"]["added"].as_u64(), diff["template"]["removed"].as_u64()), (Some(2), Some(1)));
+        assert_eq!(
+            diff["changes"],
+            json!([
+                { "field": "name", "from": "Direct", "to": "Chain-of-Thought" },
+                { "field": "description", "from": null, "to": "Reasons first" },
+            ])
+        );
+        assert_eq!((&diff["tags_added"], &diff["tags_removed"]), (&json!(["cot"]), &json!(["direct"])));
+        let uri = format!("/api/v1/prompt-versions/{}/diff/999", v1.version);
+        let res = test::call_service(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        // Rolling back to v1 leaves it the only active version
+        let uri = format!("/api/v1/prompt-versions/{}/activate", v1.version);
+        let activated: serde_json::Value = test::call_and_read_body_json(&app, test::TestRequest::post().uri(&uri).to_request()).await;
+        assert_eq!(activated["prompt"]["version"], v1.version);
+        assert_eq!(activated["prompt"]["is_active"], true);
+        let active: serde_json::Value =
+            test::call_and_read_body_json(&app, test::TestRequest::get().uri("/api/v1/prompt-versions/active").to_request()).await;
+        assert_eq!(active["prompt"]["version"], v1.version);
+        let all: serde_json::Value = test::call_and_read_body_json(&app, test::TestRequest::get().uri("/api/v1/prompt-versions").to_request()).await;
+        let active_count = all["prompts"].as_array().unwrap().iter().filter(|prompt| prompt["is_active"] == true).count();
+        assert_eq!(active_count, 1);
+        let res = test::call_service(&app, test::TestRequest::post().uri("/api/v1/prompt-versions/999/activate").to_request()).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
     }
 }
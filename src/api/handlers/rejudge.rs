@@ -0,0 +1,223 @@
+// src/api/handlers/rejudge.rs
+use actix_web::{http::StatusCode, web, HttpResponse};
+use futures::stream::{FuturesUnordered, StreamExt};
+use serde::{Deserialize, Serialize};
+use crate::database::DbPool;
+use crate::api::AppState;
+use crate::api::handlers::ws::{EvalUpdate, Progress, WsBroker, WsEvent};
+use crate::database;
+use crate::errors::ApiError;
+use crate::judge_ab;
+use crate::rejudge::{self, RejudgeOutcome, RejudgePrompt};
+
+/// Most evaluations one bulk re-judge covers; each costs a judge call.
+pub(crate) const MAX_REJUDGE: i64 = 500;
+const DEFAULT_REJUDGE_LIMIT: i64 = 100;
+
+#[derive(Deserialize, Default)]
+pub struct RejudgeRequest {
+    /// Judge prompt version to render; defaults to the active version
+    pub judge_prompt_version: Option<i64>,
+    /// Judge model; defaults to the model that originally judged each evaluation
+    pub judge_model: Option<String>,
+    /// Criteria to render into the judge prompt; defaults to the criteria stored with each evaluation
+    pub criteria: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct BulkRejudgeRequest {
+    #[serde(flatten)]
+    pub options: RejudgeRequest,
+    #[serde(flatten)]
+    pub filter: database::RejudgeFilter,
+    /// Most evaluations to re-judge, newest first
+    pub limit: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct BulkRejudgeResponse {
+    pub judge_prompt_version: i64,
+    #[serde(flatten)]
+    pub summary: rejudge::RejudgeSummary,
+    /// Matching evaluations that could not be re-judged
+    pub skipped: Vec<judge_ab::SkippedEvaluation>,
+    pub results: Vec<RejudgeOutcome>,
+}
+
+/// Checks the options shared by single and bulk re-judges and loads the judge prompt.
+pub(crate) async fn prepare(state: &AppState, pool: &DbPool, req: &RejudgeRequest) -> Result<RejudgePrompt, ApiError> {
+    if let Some(model) = &req.judge_model {
+        let (provider, _) = state.config.parse_model(model);
+        if !state.config.is_provider_configured(&provider) {
+            return Err(ApiError::provider_not_configured(&provider, model));
+        }
+    }
+
+    let prompt = match req.judge_prompt_version {
+        Some(version) => database::get_judge_prompt_by_version(pool, version).await,
+        None => database::get_active_judge_prompt(pool).await,
+    };
+    let prompt = prompt.map_err(|e| match e {
+        sqlx::Error::RowNotFound => ApiError::not_found(match req.judge_prompt_version {
+            Some(version) => format!("Judge prompt version {} not found", version),
+            None => "No active judge prompt".to_string(),
+        }),
+        e => ApiError::database("Failed to fetch judge prompt", e),
+    })?;
+    Ok(RejudgePrompt { template: prompt.template, version: prompt.version })
+}
+
+fn update_for(entry: &database::HistoryEntry, outcome: &RejudgeOutcome, progress: Option<Progress>) -> WsEvent {
+    WsEvent::EvalCompleted(EvalUpdate {
+        id: outcome.evaluation_id.clone(),
+        status: outcome.status.clone().unwrap_or_else(|| "completed".to_string()),
+        model: entry.model.clone(),
+        verdict: outcome.verdict.map(|v| v.to_string()).or_else(|| entry.judge_verdict.clone()),
+        latency_ms: entry.latency_ms.map(|ms| ms as u64),
+        batch_id: entry.batch_id.clone(),
+        progress,
+        error_code: outcome.error.as_ref().map(|_| "judge_failure".to_string()),
+        ..Default::default()
+    })
+}
+
+/// Re-judges `entries` concurrently with `prompt`, streaming progress over the WebSocket.
+/// Returns the entries that could not be re-judged and the outcomes of the others.
+pub(crate) async fn rejudge_all(
+    state: &AppState,
+    broker: &WsBroker,
+    pool: &DbPool,
+    entries: &[database::HistoryEntry],
+    options: &RejudgeRequest,
+    prompt: &RejudgePrompt,
+) -> (Vec<judge_ab::SkippedEvaluation>, Vec<RejudgeOutcome>) {
+    let mut skipped = Vec::new();
+    let mut runnable = Vec::with_capacity(entries.len());
+    for entry in entries {
+        match judge_ab::judge_inputs(entry, options.judge_model.as_deref()) {
+            Ok(inputs) => runnable.push((entry, inputs)),
+            Err(reason) => skipped.push(judge_ab::SkippedEvaluation { evaluation_id: entry.id.clone(), reason }),
+        }
+    }
+    tracing::info!("⚖️  Re-judging {} evaluations with judge prompt v{}", runnable.len(), prompt.version);
+
+    let total = runnable.len();
+    let criteria = options.criteria.as_deref();
+    let mut pending: FuturesUnordered<_> = runnable
+        .iter()
+        .map(|&(entry, (judge_model, model_output, expected))| async move {
+            let outcome = rejudge::rejudge(
+                &state.config,
+                &state.client,
+                pool,
+                entry,
+                judge_model,
+                model_output,
+                expected,
+                criteria,
+                prompt,
+            ).await;
+            (entry, outcome)
+        })
+        .collect();
+
+    let mut results = Vec::with_capacity(total);
+    while let Some((entry, outcome)) = pending.next().await {
+        let progress = Progress { completed: results.len() + 1, total };
+        broker.broadcast(update_for(entry, &outcome, Some(progress))).await;
+        results.push(outcome);
+    }
+    (skipped, results)
+}
+
+/// POST /api/v1/evals/{id}/rejudge - Re-judge a stored evaluation with the active (or given) judge prompt
+pub async fn rejudge_eval(
+    state: web::Data<AppState>,
+    broker: web::Data<WsBroker>,
+    path: web::Path<String>,
+    req: Option<web::Json<RejudgeRequest>>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+    let req = req.map(web::Json::into_inner).unwrap_or_default();
+
+    let pool = state.pool()?;
+    let prompt = prepare(&state, pool, &req).await?;
+
+    let entry = database::get_evaluation(pool, &id).await.map_err(|e| match e {
+        sqlx::Error::RowNotFound => ApiError::not_found(format!("Evaluation {} not found", id)),
+        e => ApiError::database("Failed to fetch evaluation", e),
+    })?;
+    let (judge_model, model_output, expected) = judge_ab::judge_inputs(&entry, req.judge_model.as_deref())
+        .map_err(|reason| ApiError::validation(format!("Evaluation {} cannot be re-judged: {}", id, reason)))?;
+
+    let outcome = rejudge::rejudge(
+        &state.config,
+        &state.client,
+        pool,
+        &entry,
+        judge_model,
+        model_output,
+        expected,
+        req.criteria.as_deref(),
+        &prompt,
+    ).await;
+
+    if let Some(error) = &outcome.error {
+        return Err(ApiError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "judge_failure",
+            format!("Re-judge failed: {}", error),
+        ));
+    }
+    broker.broadcast(update_for(&entry, &outcome, None)).await;
+    Ok(HttpResponse::Ok().json(outcome))
+}
+
+/// POST /api/v1/evals/rejudge - Re-judge stored evaluations matching a filter, streaming progress over the WebSocket
+pub async fn rejudge_evals(
+    state: web::Data<AppState>,
+    broker: web::Data<WsBroker>,
+    req: web::Json<BulkRejudgeRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let req = req.into_inner();
+
+    let pool = state.pool()?;
+    let limit = req.limit.unwrap_or(DEFAULT_REJUDGE_LIMIT);
+    if !(1..=MAX_REJUDGE).contains(&limit) {
+        return Err(ApiError::validation(format!("limit must be between 1 and {}", MAX_REJUDGE)));
+    }
+    if let Some(verdict) = req.filter.verdict.as_deref()
+        && !matches!(verdict, "Pass" | "Fail" | "Uncertain")
+    {
+        return Err(ApiError::validation("verdict must be one of: Pass, Fail, Uncertain"));
+    }
+    let prompt = prepare(&state, pool, &req.options).await?;
+
+    // Counted from before the fetch, so retention cannot purge the evaluations mid-run
+    let _active = state.start_batch();
+    let entries = database::get_rejudge_candidates(pool, &req.filter, limit)
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch evaluations to re-judge", e))?;
+
+    let (skipped, results) = rejudge_all(&state, &broker, pool, &entries, &req.options, &prompt).await;
+
+    Ok(HttpResponse::Ok().json(BulkRejudgeResponse {
+        judge_prompt_version: prompt.version,
+        summary: rejudge::summarize(&results),
+        skipped,
+        results,
+    }))
+}
+
+/// GET /api/v1/evals/{id}/judgements - Judge verdicts that re-judges replaced, oldest first
+pub async fn get_replaced_judgements(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+    let pool = state.pool()?;
+    let judgements = database::get_replaced_judgements(pool, &id)
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch judgements", e))?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "judgements": judgements })))
+}
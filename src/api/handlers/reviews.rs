@@ -0,0 +1,102 @@
+// src/api/handlers/reviews.rs
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use crate::api::AppState;
+use crate::database;
+use crate::errors::ApiError;
+use crate::failure_taxonomy;
+
+#[derive(Serialize)]
+pub struct ReviewQueueResponse {
+    pub results: Vec<database::HistoryEntry>,
+}
+
+#[derive(Serialize)]
+pub struct ReviewStatsResponse {
+    pub stats: Vec<database::ReviewStats>,
+}
+
+#[derive(Deserialize)]
+pub struct ReviewRequest {
+    pub verdict: String,
+    /// Corrects the failure category of a `Fail` verdict; must name a category of the taxonomy.
+    pub failure_category: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct BulkAcceptRequest {
+    pub ids: Vec<String>,
+}
+
+/// GET /api/v1/reviews - List evaluations awaiting human review
+pub async fn get_review_queue(
+    state: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let pool = state.pool()?;
+    let results = database::get_review_queue(pool)
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch review queue", e))?;
+    let results = results.into_iter().map(database::HistoryEntry::abbreviated).collect();
+    Ok(HttpResponse::Ok().json(ReviewQueueResponse { results }))
+}
+
+/// PUT /api/v1/reviews/{id} - Record a human verdict for an evaluation
+pub async fn review_evaluation(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+    req: web::Json<ReviewRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+
+    if !matches!(req.verdict.as_str(), "Pass" | "Fail" | "Uncertain") {
+        return Err(ApiError::validation("verdict must be one of: Pass, Fail, Uncertain"));
+    }
+
+    if let Some(category) = &req.failure_category {
+        if req.verdict != "Fail" {
+            return Err(ApiError::validation("failure_category can only be given with a Fail verdict"));
+        }
+        let taxonomy = failure_taxonomy::load_taxonomy(state.db_pool.as_deref()).await;
+        if !taxonomy.iter().any(|c| &c.name == category) {
+            return Err(ApiError::validation(format!("'{}' is not a category of the failure taxonomy", category)));
+        }
+    }
+
+    let pool = state.pool()?;
+    database::review_evaluation(pool, &id, &req.verdict, req.failure_category.as_deref())
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => ApiError::not_found(format!("Evaluation {} not found", id)),
+            e => ApiError::database("Failed to review evaluation", e),
+        })?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": format!("Evaluation {} reviewed as {}", id, req.verdict)
+    })))
+}
+
+/// POST /api/v1/reviews/accept - Accept the judge's provisional verdicts in bulk
+pub async fn accept_provisional_verdicts(
+    state: web::Data<AppState>,
+    req: web::Json<BulkAcceptRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let pool = state.pool()?;
+    let accepted = database::accept_provisional_verdicts(pool, &req.ids)
+        .await
+        .map_err(|e| ApiError::database("Failed to accept provisional verdicts", e))?;
+    tracing::info!("✅ Accepted {} provisional verdicts", accepted);
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "requested": req.ids.len(),
+        "accepted": accepted
+    })))
+}
+
+/// GET /api/v1/reviews/stats - Review routing rate per judge prompt version
+pub async fn get_review_stats(
+    state: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let pool = state.pool()?;
+    let stats = database::get_review_stats(pool)
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch review stats", e))?;
+    Ok(HttpResponse::Ok().json(ReviewStatsResponse { stats }))
+}
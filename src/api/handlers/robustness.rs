@@ -0,0 +1,135 @@
+// src/api/handlers/robustness.rs
+use actix_web::{web, HttpResponse};
+use chrono::Utc;
+use serde::Serialize;
+use crate::api::AppState;
+use crate::api::handlers::evals::{execute_batch, BatchEvalResponse};
+use crate::api::handlers::ws::WsBroker;
+use crate::database;
+use crate::errors::ApiError;
+use crate::robustness::{self, Paraphrasing, RobustnessReport, RobustnessRequest, VariantSource};
+use crate::runner;
+
+#[derive(Serialize)]
+pub struct RobustnessRunResponse {
+    #[serde(flatten)]
+    pub report: RobustnessReport,
+    /// The paraphrase call; `None` when the variants were supplied by the user
+    pub paraphrasing: Option<Paraphrasing>,
+    /// The batch of evals, its `total_cost_usd` including the paraphrase call
+    pub batch: BatchEvalResponse,
+}
+
+#[derive(Serialize)]
+pub struct RobustnessGroupResponse {
+    pub group: database::RobustnessGroup,
+    #[serde(flatten)]
+    pub report: RobustnessReport,
+}
+
+/// POST /api/v1/evals/robustness - Run a model against a base prompt and paraphrases of it, scoring how many pass
+pub async fn run_robustness(
+    state: web::Data<AppState>,
+    broker: web::Data<WsBroker>,
+    req: web::Json<RobustnessRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let req = req.into_inner();
+    req.validate().map_err(ApiError::validation)?;
+    let pool = state.pool()?;
+
+    let (phrasings, source, paraphrasing) = match &req.variants {
+        Some(variants) => (variants.clone(), VariantSource::User, None),
+        None => {
+            let Some(model) = req.resolve_paraphrase_model(&state.config) else {
+                return Err(ApiError::validation("Give variants, or a paraphrase_model (or judge_model) to generate them"));
+            };
+            let (provider, _) = state.config.parse_model(&model);
+            if !state.config.is_provider_configured(&provider) {
+                return Err(ApiError::provider_not_configured(&provider, &model));
+            }
+            let paraphrasing = robustness::paraphrase(
+                &state.config,
+                &state.client,
+                &model,
+                &req.prompt,
+                req.paraphrase_count(),
+                Some(pool),
+            )
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to generate paraphrases: {}", e);
+                let mut error = ApiError::from(e);
+                error.message = format!("Failed to generate paraphrases: {}", error.message);
+                error
+            })?;
+            (paraphrasing.paraphrases.clone(), VariantSource::Paraphrase, Some(paraphrasing))
+        }
+    };
+
+    let variants = robustness::variants(&req.prompt, phrasings, source);
+    if variants.len() < 2 {
+        return Err(ApiError::validation("No phrasing differs from the base prompt"));
+    }
+    let group_id = uuid::Uuid::new_v4().to_string();
+    let configs = robustness::expand(&req, &group_id, &variants);
+    runner::validate_providers(&state.config, &configs)?;
+
+    tracing::info!("🔀 Running {} phrasings of one prompt against {}", variants.len(), req.model);
+    let mut batch = execute_batch(&state, &broker, configs, None).await;
+    if let Some(cost) = paraphrasing.as_ref().and_then(|p| p.cost_usd) {
+        batch.total_cost_usd += cost;
+    }
+
+    let outcomes = variants
+        .into_iter()
+        .zip(&batch.results)
+        .map(|(variant, result)| robustness::VariantOutcome {
+            variant_index: variant.index,
+            prompt: variant.prompt,
+            source: variant.source,
+            eval_id: result.id.clone(),
+            status: result.status.clone(),
+            verdict: result
+                .result
+                .as_ref()
+                .and_then(|r| r.judge_result.as_ref())
+                .map(|j| j.verdict.to_string()),
+            flips_verdict: None,
+        })
+        .collect();
+    let report = robustness::report(&group_id, outcomes);
+
+    let group = database::RobustnessGroup {
+        id: group_id,
+        batch_id: batch.batch_id.clone(),
+        model: req.model.clone(),
+        base_prompt: req.prompt.clone(),
+        expected: req.expected.clone(),
+        paraphrase_model: paraphrasing.as_ref().map(|p| p.model.clone()),
+        paraphrase_input_tokens: paraphrasing.as_ref().and_then(|p| p.token_usage.input_tokens).map(i64::from),
+        paraphrase_output_tokens: paraphrasing.as_ref().and_then(|p| p.token_usage.output_tokens).map(i64::from),
+        paraphrase_cost_usd: paraphrasing.as_ref().and_then(|p| p.cost_usd),
+        created_at: Utc::now().to_rfc3339(),
+    };
+    database::create_robustness_group(pool, &group, &report.variants).await.map_err(|e| {
+        ApiError::database("Robustness run finished but the group could not be recorded", e)
+            .with_details(serde_json::json!({ "batch_id": batch.batch_id }))
+    })?;
+    Ok(HttpResponse::Ok().json(RobustnessRunResponse { report, paraphrasing, batch }))
+}
+
+/// GET /api/v1/evals/robustness/{id} - A stored robustness run with its current verdicts
+pub async fn get_robustness_group(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+
+    let pool = state.pool()?;
+    let (group, variants) = database::get_robustness_group(pool, &id).await.map_err(|e| match e {
+        sqlx::Error::RowNotFound => ApiError::not_found(format!("Robustness group {} not found", id)),
+        e => ApiError::database("Failed to fetch robustness group", e),
+    })?;
+    let report = robustness::report(&group.id, variants);
+    Ok(HttpResponse::Ok().json(RobustnessGroupResponse { group, report }))
+}
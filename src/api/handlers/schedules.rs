@@ -0,0 +1,217 @@
+// src/api/handlers/schedules.rs
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use crate::api::AppState;
+use crate::api::handlers::suites::find_suite;
+use crate::database::{self, DbPool, ScheduleSettings};
+use crate::errors::ApiError;
+use crate::models::FieldError;
+use crate::scheduler;
+use crate::webhook;
+
+/// Body of `POST /schedules` and `PUT /schedules/{id}`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScheduleRequest {
+    /// Id or name of the suite to run
+    pub suite: String,
+    pub cron: String,
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub notify_slack: bool,
+    pub callback_url: Option<String>,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+#[derive(Serialize)]
+pub struct ScheduleResponse {
+    #[serde(flatten)]
+    pub schedule: database::Schedule,
+    /// When the scheduler next queues a run; `None` while the schedule is disabled.
+    pub next_run_at: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct SchedulesResponse {
+    pub schedules: Vec<ScheduleResponse>,
+}
+
+impl From<database::Schedule> for ScheduleResponse {
+    fn from(schedule: database::Schedule) -> Self {
+        let since = schedule.last_run_at.clone().unwrap_or_else(|| schedule.created_at.clone());
+        let next_run_at = scheduler::next_run_after(&schedule, &since)
+            .filter(|_| schedule.enabled)
+            .map(|at| at.to_rfc3339());
+        ScheduleResponse { schedule, next_run_at }
+    }
+}
+
+fn schedule_not_found(id: &str) -> ApiError {
+    ApiError::not_found(format!("Schedule '{}' not found", id))
+}
+
+/// Checks a create/update request and resolves its suite into the settings to store.
+async fn schedule_settings(state: &AppState, pool: &DbPool, req: ScheduleRequest) -> Result<ScheduleSettings, ApiError> {
+    let mut errors = Vec::new();
+    if let Err(e) = scheduler::parse_cron(&req.cron) {
+        errors.push(FieldError::new("cron", format!("is not a valid cron expression: {}", e)));
+    }
+    if req.notify_slack && state.config.slack_webhook_url.is_none() {
+        errors.push(FieldError::new("notify_slack", "Slack notifications are disabled; set SLACK_WEBHOOK_URL to enable them"));
+    }
+    if let Some(url) = &req.callback_url {
+        errors.extend(webhook::check_callback_url(&state.config, url).err());
+    }
+    if !errors.is_empty() {
+        return Err(ApiError::invalid_fields(errors));
+    }
+
+    let suite = find_suite(pool, &req.suite).await?;
+    Ok(ScheduleSettings {
+        suite_id: suite.id,
+        cron: req.cron.trim().to_string(),
+        enabled: req.enabled,
+        notify_slack: req.notify_slack,
+        callback_url: req.callback_url,
+    })
+}
+
+/// GET /api/v1/schedules - List schedules, oldest first
+pub async fn get_all_schedules(
+    state: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let pool = state.pool()?;
+    let schedules = database::get_all_schedules(pool)
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch schedules", e))?;
+    Ok(HttpResponse::Ok().json(SchedulesResponse { schedules: schedules.into_iter().map(ScheduleResponse::from).collect() }))
+}
+
+/// GET /api/v1/schedules/{id} - Get a schedule
+pub async fn get_schedule(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+
+    let schedule = database::get_schedule(state.pool()?, &id).await.map_err(|e| match e {
+        sqlx::Error::RowNotFound => schedule_not_found(&id),
+        e => ApiError::database("Failed to fetch schedule", e),
+    })?;
+    Ok(HttpResponse::Ok().json(ScheduleResponse::from(schedule)))
+}
+
+/// POST /api/v1/schedules - Run a suite on a cron schedule
+pub async fn create_schedule(
+    state: web::Data<AppState>,
+    req: web::Json<ScheduleRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let pool = state.pool()?;
+    let settings = schedule_settings(&state, pool, req.into_inner()).await?;
+    let schedule = database::create_schedule(pool, &settings)
+        .await
+        .map_err(|e| ApiError::database("Failed to save schedule", e))?;
+    tracing::info!("⏰ Created schedule {} running suite {} on '{}'", schedule.id, schedule.suite_id, schedule.cron);
+    Ok(HttpResponse::Created().json(ScheduleResponse::from(schedule)))
+}
+
+/// PUT /api/v1/schedules/{id} - Replace a schedule's suite, cron expression and notifications
+pub async fn update_schedule(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+    req: web::Json<ScheduleRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+
+    let pool = state.pool()?;
+    let settings = schedule_settings(&state, pool, req.into_inner()).await?;
+    let schedule = database::update_schedule(pool, &id, &settings).await.map_err(|e| match e {
+        sqlx::Error::RowNotFound => schedule_not_found(&id),
+        e => ApiError::database("Failed to save schedule", e),
+    })?;
+    Ok(HttpResponse::Ok().json(ScheduleResponse::from(schedule)))
+}
+
+/// DELETE /api/v1/schedules/{id} - Delete a schedule; runs it already queued still finish
+pub async fn delete_schedule(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+
+    database::delete_schedule(state.pool()?, &id).await.map_err(|e| match e {
+        sqlx::Error::RowNotFound => schedule_not_found(&id),
+        e => ApiError::database("Failed to delete schedule", e),
+    })?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::configure_routes;
+    use actix_web::{http::StatusCode, test, App};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[actix_web::test]
+    async fn test_schedules_are_created_read_updated_and_deleted() {
+        let pool = Arc::new(database::test_pool().await);
+        let suite = database::create_suite(&pool, "nightly".to_string(), None, &[
+            serde_json::from_value(json!({ "model": "ollama:llama3", "prompt": "2+2?" })).unwrap(),
+        ])
+        .await
+        .unwrap();
+        let state = AppState {
+            config: Arc::new(crate::config::AppConfig { webhook_secret: Some("secret".to_string()), ..Default::default() }),
+            client: reqwest::Client::new(),
+            db_pool: Some(pool.clone()),
+            backup_in_progress: Default::default(),
+            active_batches: Default::default(),
+            retention: Default::default(),
+            rate_limiter: Default::default(),
+            provider_health: Default::default(),
+            progress: Default::default(),
+        };
+        let app = test::init_service(App::new().app_data(web::Data::new(state)).configure(configure_routes)).await;
+
+        let body = json!({ "suite": "nightly", "cron": "0 2 * * *", "callback_url": "https://ci.example.com/hooks/nightly" });
+        let res = test::call_service(&app, test::TestRequest::post().uri("/api/v1/schedules").set_json(&body).to_request()).await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+        let created: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!((&created["suite_id"], &created["enabled"], &created["notify_slack"]), (&json!(suite.id), &json!(true), &json!(false)));
+        assert!(created["next_run_at"].as_str().unwrap().contains("T02:00:00"));
+        assert!(created["last_run_at"].is_null());
+
+        let uri = format!("/api/v1/schedules/{}", created["id"].as_str().unwrap());
+        let listed: serde_json::Value = test::call_and_read_body_json(&app, test::TestRequest::get().uri("/api/v1/schedules").to_request()).await;
+        assert_eq!(listed["schedules"][0]["id"], created["id"]);
+
+        let disabled = json!({ "suite": suite.id, "cron": "*/30 * * * * *", "enabled": false });
+        let updated: serde_json::Value = test::call_and_read_body_json(&app, test::TestRequest::put().uri(&uri).set_json(&disabled).to_request()).await;
+        assert_eq!((&updated["cron"], &updated["enabled"], &updated["callback_url"], &updated["next_run_at"]), (&json!("*/30 * * * * *"), &json!(false), &json!(null), &json!(null)));
+        let read: serde_json::Value = test::call_and_read_body_json(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+        assert_eq!(read["enabled"], false);
+
+        for (body, status) in [
+            (json!({ "suite": "nightly", "cron": "every night" }), StatusCode::UNPROCESSABLE_ENTITY),
+            (json!({ "suite": "nightly", "cron": "0 2 * * *", "notify_slack": true }), StatusCode::UNPROCESSABLE_ENTITY),
+            (json!({ "suite": "nightly", "cron": "0 2 * * *", "callback_url": "ftp://example.com" }), StatusCode::UNPROCESSABLE_ENTITY),
+            (json!({ "suite": "missing", "cron": "0 2 * * *" }), StatusCode::NOT_FOUND),
+        ] {
+            let res = test::call_service(&app, test::TestRequest::post().uri("/api/v1/schedules").set_json(&body).to_request()).await;
+            assert_eq!(res.status(), status, "{}", body);
+        }
+
+        let res = test::call_service(&app, test::TestRequest::delete().uri(&uri).to_request()).await;
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+        let res = test::call_service(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        let res = test::call_service(&app, test::TestRequest::delete().uri(&uri).to_request()).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,295 @@
+// src/api/handlers/shares.rs
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use crate::api::auth::Caller;
+use crate::api::AppState;
+use crate::database;
+use crate::errors::ApiError;
+use crate::share::{self, SharedEvaluation, TokenError};
+
+#[derive(Deserialize, Default)]
+pub struct CreateShareRequest {
+    /// Lifetime of the link in hours (defaults to `SHARE_TTL_HOURS`)
+    pub ttl_hours: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct CreateShareResponse {
+    pub share: database::Share,
+    pub token: String,
+    /// Link anyone can open, without credentials, until it expires or is revoked.
+    pub url: String,
+}
+
+fn sharing_disabled() -> ApiError {
+    ApiError::not_found("Sharing is disabled; set SHARE_SIGNING_SECRET to enable it")
+}
+
+fn share_not_found() -> ApiError {
+    ApiError::not_found("Share link not found")
+}
+
+/// POST /api/v1/evals/{id}/share - Create an expiring, read-only link to one evaluation
+pub async fn create_share(
+    state: web::Data<AppState>,
+    http_req: HttpRequest,
+    path: web::Path<String>,
+    req: Option<web::Json<CreateShareRequest>>,
+) -> Result<HttpResponse, ApiError> {
+    let secret = state.config.share_signing_secret.as_deref().ok_or_else(sharing_disabled)?;
+    let id = path.into_inner();
+    let req = req.map(web::Json::into_inner).unwrap_or_default();
+
+    let ttl_hours = req.ttl_hours.unwrap_or(state.config.share_ttl_hours);
+    if ttl_hours == 0 || ttl_hours > i64::MAX as u64 / 3600 {
+        return Err(ApiError::validation("ttl_hours must be a positive number of hours"));
+    }
+    // Whole seconds, as that is what the token carries
+    let expires_at = DateTime::from_timestamp(Utc::now().timestamp(), 0).unwrap_or_default()
+        + Duration::hours(ttl_hours as i64);
+
+    let created_by = http_req
+        .extensions()
+        .get::<Caller>()
+        .map_or_else(|| "anonymous".to_string(), |caller| caller.name.clone());
+
+    let pool = state.pool()?;
+    let share = database::create_share(pool, &id, &created_by, expires_at).await.map_err(|e| match e {
+        sqlx::Error::RowNotFound => ApiError::not_found(format!("Evaluation {} not found", id)),
+        e => ApiError::database("Failed to create share", e),
+    })?;
+    let token = share::sign(secret, &share.id, expires_at);
+    let connection = http_req.connection_info();
+    let url = format!("{}://{}/share/{}", connection.scheme(), connection.host(), token);
+    tracing::info!("🔗 {} shared evaluation {} for {}h", created_by, id, ttl_hours);
+    Ok(HttpResponse::Created().json(CreateShareResponse { share, token, url }))
+}
+
+/// GET /api/v1/evals/{id}/shares - Every share of an evaluation, revoked ones included
+pub async fn get_shares(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    if state.config.share_signing_secret.is_none() {
+        return Err(sharing_disabled());
+    }
+    let id = path.into_inner();
+
+    let pool = state.pool()?;
+    let shares = database::get_shares_for_evaluation(pool, &id)
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch shares", e))?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "shares": shares })))
+}
+
+/// DELETE /api/v1/shares/{id} - Revoke a share; its link answers 410 from then on
+pub async fn revoke_share(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    if state.config.share_signing_secret.is_none() {
+        return Err(sharing_disabled());
+    }
+    let id = path.into_inner();
+
+    let pool = state.pool()?;
+    let share = database::revoke_share(pool, &id).await.map_err(|e| match e {
+        sqlx::Error::RowNotFound => ApiError::not_found(format!("Active share '{}' not found", id)),
+        e => ApiError::database("Failed to revoke share", e),
+    })?;
+    tracing::info!("🔗 Revoked share {} of evaluation {}", share.id, share.evaluation_id);
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "share": share })))
+}
+
+/// GET /share/{token} - The shared evaluation, for anyone holding the link. Public, so it
+/// answers only with the fields of `SharedEvaluation`.
+pub async fn get_shared_evaluation(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let secret = state.config.share_signing_secret.as_deref().ok_or_else(share_not_found)?;
+    let token = match share::verify(secret, &path.into_inner(), Utc::now()) {
+        Ok(token) => token,
+        Err(TokenError::Invalid) => return Err(share_not_found()),
+        Err(TokenError::Expired) => return Err(ApiError::gone("This share link has expired")),
+    };
+
+    let pool = state.pool()?;
+
+    let share = database::get_share(pool, &token.share_id)
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch share", e))?
+        .ok_or_else(share_not_found)?;
+    if share.revoked_at.is_some() {
+        return Err(ApiError::gone("This share link has been revoked"));
+    }
+
+    let entry = database::get_evaluation(pool, &share.evaluation_id).await.map_err(|e| match e {
+        sqlx::Error::RowNotFound => ApiError::gone("The shared evaluation no longer exists"),
+        e => ApiError::database("Failed to fetch shared evaluation", e),
+    })?;
+    Ok(HttpResponse::Ok()
+        .insert_header(("Cache-Control", "no-store"))
+        .json(SharedEvaluation::new(entry, token.expires_at.to_rfc3339())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::configure_routes;
+    use crate::models::{ApiResponse, EvalResult};
+    use crate::runner::{self, JudgeResult, JudgeVerdict};
+    use actix_web::{http::StatusCode, test, App};
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+
+    async fn state_with_evaluation(secret: Option<&str>) -> AppState {
+        let pool = database::test_pool().await;
+        let result = runner::EvalResult {
+            id: "eval-1".to_string(),
+            model: "ollama:llama3".to_string(),
+            prompt: "What is 2+2?".to_string(),
+            model_output: "5".to_string(),
+            parsed_output: None,
+            parse_method: None,
+            output_diff: None,
+            expected: Some("4".to_string()),
+            judge_result: Some(JudgeResult {
+                judge_model: "ollama:llama3".to_string(),
+                verdict: JudgeVerdict::Fail,
+                reasoning: Some("Off by one".to_string()),
+                confidence: None,
+                raw_response: None,
+                uncertain_reason: None,
+            }),
+            timestamp: Utc::now().to_rfc3339(),
+            latency_ms: 10,
+            judge_latency_ms: None,
+            token_usage: None,
+            judge_token_usage: None,
+            total_latency_ms: 10,
+            wait_ms: None,
+            cost_usd: Some(0.5),
+            judge_cost_usd: None,
+            judge_prompt_version: None,
+            prompt_version: None,
+            prompt_template: None,
+            judge_prompt_arm: None,
+            judge_batch_size: None,
+            early_abort: None,
+            finish_reason: None,
+            needs_review: false,
+            truncation: None,
+            tags: Vec::new(),
+            criteria: None,
+            metadata: None,
+            trace: None,
+            failure_classification: None,
+            dataset_row: None,
+            chain_step: None,
+            provider_override: None,
+            full_output_chars: None,
+            cache_hit: false,
+            resolved_model: None,
+            response_format: None,
+            reasoning: None,
+            images: Vec::new(),
+            judge_prompt_rendered: None,
+            model_alias: None,
+            judge_params: None,
+            judge_error: None,
+            self_consistency: None,
+        };
+        let response = ApiResponse {
+            id: "eval-1".to_string(),
+            status: runner::eval_status(&result).to_string(),
+            result: EvalResult::Success(result),
+            batch_id: None,
+            config_hash: None,
+            preset: None,
+        };
+        database::save_evaluation(&pool, &response).await.unwrap();
+
+        AppState {
+            config: Arc::new(crate::config::AppConfig {
+                share_signing_secret: secret.map(str::to_string),
+                share_ttl_hours: 1,
+                ..Default::default()
+            }),
+            client: reqwest::Client::new(),
+            db_pool: Some(Arc::new(pool)),
+            backup_in_progress: Arc::new(AtomicBool::new(false)),
+            active_batches: Default::default(),
+            retention: Default::default(),
+            rate_limiter: Default::default(),
+            provider_health: Default::default(),
+            progress: Default::default(),
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_share_link_shows_the_evaluation_until_revoked() {
+        let state = state_with_evaluation(Some("secret")).await;
+        let app = test::init_service(
+            App::new().app_data(web::Data::new(state)).configure(configure_routes),
+        ).await;
+
+        let created: serde_json::Value = test::call_and_read_body_json(
+            &app,
+            test::TestRequest::post().uri("/api/v1/evals/eval-1/share").to_request(),
+        ).await;
+        let token = created["token"].as_str().unwrap();
+        let share_id = created["share"]["id"].as_str().unwrap();
+
+        let shared: serde_json::Value = test::call_and_read_body_json(
+            &app,
+            test::TestRequest::get().uri(&format!("/share/{}", token)).to_request(),
+        ).await;
+        assert_eq!(shared["output"], "5");
+        assert_eq!(shared["expected"], "4");
+        assert_eq!(shared["verdict"], "Fail");
+        assert_eq!(shared["reasoning"], "Off by one");
+        assert!(shared.get("cost_usd").is_none());
+
+        let revoke = test::TestRequest::delete().uri(&format!("/api/v1/shares/{}", share_id)).to_request();
+        assert_eq!(test::call_service(&app, revoke).await.status(), StatusCode::OK);
+        let after = test::TestRequest::get().uri(&format!("/share/{}", token)).to_request();
+        assert_eq!(test::call_service(&app, after).await.status(), StatusCode::GONE);
+    }
+
+    #[actix_web::test]
+    async fn test_expired_and_forged_links_are_rejected() {
+        let state = state_with_evaluation(Some("secret")).await;
+        let pool = state.db_pool.clone().unwrap();
+        let app = test::init_service(
+            App::new().app_data(web::Data::new(state)).configure(configure_routes),
+        ).await;
+
+        let expired_at = Utc::now() - Duration::hours(1);
+        let share = database::create_share(&pool, "eval-1", "test", expired_at).await.unwrap();
+        let expired = test::TestRequest::get()
+            .uri(&format!("/share/{}", share::sign("secret", &share.id, expired_at)))
+            .to_request();
+        assert_eq!(test::call_service(&app, expired).await.status(), StatusCode::GONE);
+
+        let forged = test::TestRequest::get()
+            .uri(&format!("/share/{}", share::sign("guess", &share.id, Utc::now() + Duration::hours(1))))
+            .to_request();
+        assert_eq!(test::call_service(&app, forged).await.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn test_sharing_is_disabled_without_a_secret() {
+        let state = state_with_evaluation(None).await;
+        let app = test::init_service(
+            App::new().app_data(web::Data::new(state)).configure(configure_routes),
+        ).await;
+
+        let create = test::TestRequest::post().uri("/api/v1/evals/eval-1/share").to_request();
+        assert_eq!(test::call_service(&app, create).await.status(), StatusCode::NOT_FOUND);
+        let token = share::sign("secret", "share-1", Utc::now() + Duration::hours(1));
+        let view = test::TestRequest::get().uri(&format!("/share/{}", token)).to_request();
+        assert_eq!(test::call_service(&app, view).await.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,364 @@
+// src/api/handlers/stats.rs
+use actix_web::{web, HttpRequest, HttpResponse};
+use serde::Deserialize;
+use crate::api::{etag, AppState};
+use crate::database;
+use crate::drift::{self, DriftThresholds};
+use crate::errors::ApiError;
+use crate::leaderboard::{self, RankingMetric};
+
+/// GET /api/v1/stats/costs - Evaluation cost aggregated by model and by day
+pub async fn get_cost_stats(
+    state: web::Data<AppState>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let pool = state.pool()?;
+    etag::conditional(pool, &http_req, || async {
+        let stats = database::get_cost_stats(pool)
+            .await
+            .map_err(|e| ApiError::database("Failed to fetch cost stats", e))?;
+        Ok(HttpResponse::Ok().json(stats))
+    })
+    .await
+}
+
+#[derive(Deserialize)]
+pub struct ProviderErrorStatsQuery {
+    pub group_by: Option<database::ProviderErrorGroup>,
+    /// Only `day` is supported
+    pub bucket: Option<String>,
+}
+
+/// GET /api/v1/stats/provider-errors - Provider error counts by provider or status, optionally per day
+pub async fn get_provider_error_stats(
+    state: web::Data<AppState>,
+    query: web::Query<ProviderErrorStatsQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let by_day = match query.bucket.as_deref() {
+        None => false,
+        Some("day") => true,
+        Some(other) => {
+            return Err(ApiError::validation(format!("Unsupported bucket '{}', expected 'day'", other)));
+        }
+    };
+    let group_by = query.group_by.unwrap_or(database::ProviderErrorGroup::Provider);
+
+    let pool = state.pool()?;
+    let buckets = database::get_provider_error_stats(pool, group_by, by_day)
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch provider error stats", e))?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "buckets": buckets })))
+}
+
+/// GET /api/v1/provider-errors - Individual provider errors, newest first, filtered by query parameters
+pub async fn get_provider_errors(
+    state: web::Data<AppState>,
+    query: web::Query<database::ProviderErrorFilter>,
+) -> Result<HttpResponse, ApiError> {
+    let pool = state.pool()?;
+    let errors = database::get_provider_errors(pool, &query)
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch provider errors", e))?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "errors": errors })))
+}
+
+#[derive(Deserialize)]
+pub struct LeaderboardQuery {
+    #[serde(flatten)]
+    pub filter: database::StatsFilter,
+    /// `pass_rate` (default), `latency` or `cost`
+    pub order_by: Option<database::LeaderboardOrder>,
+}
+
+/// GET /api/v1/stats/leaderboard - Per-model pass rate, latency, tokens and cost from history
+pub async fn get_leaderboard(
+    state: web::Data<AppState>,
+    http_req: HttpRequest,
+    query: web::Query<LeaderboardQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let order = query.order_by.unwrap_or(database::LeaderboardOrder::PassRate);
+
+    let pool = state.pool()?;
+    etag::conditional(pool, &http_req, || async {
+        let models = database::get_leaderboard(pool, &query.filter, order)
+            .await
+            .map_err(|e| ApiError::database("Failed to build leaderboard", e))?;
+        Ok(HttpResponse::Ok().json(serde_json::json!({ "models": models })))
+    })
+    .await
+}
+
+#[derive(Deserialize)]
+pub struct RankedLeaderboardQuery {
+    /// `pass_rate` (default), `avg_score` or `cost_per_pass`
+    pub metric: Option<RankingMetric>,
+    /// RFC 3339 timestamp or `YYYY-MM-DD`, inclusive
+    pub since: Option<String>,
+    pub tag: Option<String>,
+    #[serde(default)]
+    pub tag_match: database::TagMatch,
+    /// Judged evaluations a model needs to be ranked
+    pub min_samples: Option<u32>,
+}
+
+/// GET /api/v1/leaderboard - Models ranked by a metric, with confidence intervals
+pub async fn get_ranked_leaderboard(
+    state: web::Data<AppState>,
+    http_req: HttpRequest,
+    query: web::Query<RankedLeaderboardQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let query = query.into_inner();
+    let metric = query.metric.unwrap_or(RankingMetric::PassRate);
+    let min_samples = query.min_samples.unwrap_or(leaderboard::DEFAULT_MIN_SAMPLES);
+    let filter = database::StatsFilter {
+        from: query.since.clone(),
+        to: None,
+        tag: query.tag.clone(),
+        tag_match: query.tag_match,
+    };
+
+    let pool = state.pool()?;
+    etag::conditional(pool, &http_req, || async {
+        let entries = database::get_leaderboard(pool, &filter, database::LeaderboardOrder::PassRate)
+            .await
+            .map_err(|e| ApiError::database("Failed to build leaderboard", e))?;
+        let board = leaderboard::rank(entries, metric, min_samples);
+        Ok(HttpResponse::Ok().json(serde_json::json!({
+            "metric": board.metric,
+            "since": query.since,
+            "tag": query.tag,
+            "min_samples": board.min_samples,
+            "models": board.models
+        })))
+    })
+    .await
+}
+
+/// GET /api/v1/stats/uncertain-reasons - Evaluations per uncertain reason (`?from=&to=&tag=`)
+pub async fn get_uncertain_reason_stats(
+    state: web::Data<AppState>,
+    http_req: HttpRequest,
+    query: web::Query<database::StatsFilter>,
+) -> Result<HttpResponse, ApiError> {
+    let pool = state.pool()?;
+    etag::conditional(pool, &http_req, || async {
+        let reasons = database::get_uncertain_reason_stats(pool, &query)
+            .await
+            .map_err(|e| ApiError::database("Failed to fetch uncertain reason stats", e))?;
+        Ok(HttpResponse::Ok().json(serde_json::json!({ "reasons": reasons })))
+    })
+    .await
+}
+
+/// GET /api/v1/stats/judge-batching - Batched vs individually judged verdicts per judge model (`?from=&to=&tag=`)
+pub async fn get_judge_batching_stats(
+    state: web::Data<AppState>,
+    http_req: HttpRequest,
+    query: web::Query<database::StatsFilter>,
+) -> Result<HttpResponse, ApiError> {
+    let pool = state.pool()?;
+    etag::conditional(pool, &http_req, || async {
+        let judges = database::get_judge_batching_stats(pool, &query)
+            .await
+            .map_err(|e| ApiError::database("Failed to fetch judge batching stats", e))?;
+        Ok(HttpResponse::Ok().json(serde_json::json!({ "judges": judges })))
+    })
+    .await
+}
+
+/// GET /api/v1/stats/finish-reasons - Finish reason counts per model (`?from=&to=&tag=`)
+pub async fn get_finish_reason_stats(
+    state: web::Data<AppState>,
+    http_req: HttpRequest,
+    query: web::Query<database::StatsFilter>,
+) -> Result<HttpResponse, ApiError> {
+    let pool = state.pool()?;
+    etag::conditional(pool, &http_req, || async {
+        let models = database::get_finish_reason_stats(pool, &query)
+            .await
+            .map_err(|e| ApiError::database("Failed to fetch finish reason stats", e))?;
+        Ok(HttpResponse::Ok().json(serde_json::json!({ "models": models })))
+    })
+    .await
+}
+
+#[derive(Deserialize)]
+pub struct FailureCategoryQuery {
+    #[serde(flatten)]
+    pub filter: database::StatsFilter,
+    pub model: Option<String>,
+}
+
+/// GET /api/v1/stats/failure-categories - Failed evaluations per failure category and model
+/// (`?model=&from=&to=&tag=`)
+pub async fn get_failure_category_stats(
+    state: web::Data<AppState>,
+    http_req: HttpRequest,
+    query: web::Query<FailureCategoryQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let pool = state.pool()?;
+    etag::conditional(pool, &http_req, || async {
+        let models = database::get_failure_category_stats(pool, &query.filter, query.model.as_deref())
+            .await
+            .map_err(|e| ApiError::database("Failed to fetch failure category stats", e))?;
+        Ok(HttpResponse::Ok().json(serde_json::json!({ "models": models })))
+    })
+    .await
+}
+
+/// Default output-token bucket width for the token distribution.
+const DEFAULT_TOKEN_BUCKET_WIDTH: i64 = 100;
+
+#[derive(Deserialize)]
+pub struct TimeSeriesQuery {
+    /// `day` (default) or `hour`
+    pub bucket: Option<database::TimeBucket>,
+    pub model: Option<String>,
+}
+
+/// GET /api/v1/stats/timeseries - Pass rate, volume, tokens and latency per day or hour
+pub async fn get_timeseries(
+    state: web::Data<AppState>,
+    http_req: HttpRequest,
+    query: web::Query<TimeSeriesQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let bucket = query.bucket.unwrap_or(database::TimeBucket::Day);
+
+    let pool = state.pool()?;
+    etag::conditional(pool, &http_req, || async {
+        let points = database::get_timeseries(pool, bucket, query.model.as_deref())
+            .await
+            .map_err(|e| ApiError::database("Failed to fetch timeseries", e))?;
+        Ok(HttpResponse::Ok().json(serde_json::json!({ "points": points })))
+    })
+    .await
+}
+
+#[derive(Deserialize)]
+pub struct TokenDistributionQuery {
+    pub model: Option<String>,
+    /// Output tokens per histogram bucket (default 100)
+    pub bucket_width: Option<i64>,
+}
+
+/// GET /api/v1/stats/token-distribution - Histogram of output token counts
+pub async fn get_token_distribution(
+    state: web::Data<AppState>,
+    http_req: HttpRequest,
+    query: web::Query<TokenDistributionQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let bucket_width = query.bucket_width.unwrap_or(DEFAULT_TOKEN_BUCKET_WIDTH);
+    if bucket_width < 1 {
+        return Err(ApiError::validation("bucket_width must be at least 1"));
+    }
+
+    let pool = state.pool()?;
+    etag::conditional(pool, &http_req, || async {
+        let buckets = database::get_token_distribution(pool, bucket_width, query.model.as_deref())
+            .await
+            .map_err(|e| ApiError::database("Failed to fetch token distribution", e))?;
+        Ok(HttpResponse::Ok().json(serde_json::json!({
+            "bucket_width": bucket_width,
+            "buckets": buckets
+        })))
+    })
+    .await
+}
+
+/// Weeks of output tokens returned when `weeks` is not given.
+const DEFAULT_OUTPUT_TOKEN_WEEKS: u32 = 8;
+
+/// Most weeks of output tokens a request may ask for.
+const MAX_OUTPUT_TOKEN_WEEKS: u32 = 104;
+
+#[derive(Deserialize)]
+pub struct OutputTokensQuery {
+    pub model: Option<String>,
+    /// Calendar weeks to return, the current one included (default 8)
+    pub weeks: Option<u32>,
+    /// Output tokens per histogram bucket (default 100)
+    pub bucket_width: Option<i64>,
+}
+
+/// GET /api/v1/stats/output-tokens - Output-token histogram, mean and pass rate per model and week
+pub async fn get_weekly_output_tokens(
+    state: web::Data<AppState>,
+    http_req: HttpRequest,
+    query: web::Query<OutputTokensQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let weeks = query.weeks.unwrap_or(DEFAULT_OUTPUT_TOKEN_WEEKS);
+    if !(1..=MAX_OUTPUT_TOKEN_WEEKS).contains(&weeks) {
+        return Err(ApiError::validation(format!("weeks must be from 1 to {}", MAX_OUTPUT_TOKEN_WEEKS)));
+    }
+    let bucket_width = query.bucket_width.unwrap_or(DEFAULT_TOKEN_BUCKET_WIDTH);
+    if bucket_width < 1 {
+        return Err(ApiError::validation("bucket_width must be at least 1"));
+    }
+    let since = drift::lookback_start(chrono::Utc::now().date_naive(), weeks);
+
+    let pool = state.pool()?;
+    etag::conditional(pool, &http_req, || async {
+        let weeks = database::get_weekly_output_tokens(pool, query.model.as_deref(), Some(&since), Some(bucket_width))
+            .await
+            .map_err(|e| ApiError::database("Failed to fetch weekly output tokens", e))?;
+        Ok(HttpResponse::Ok().json(serde_json::json!({
+            "since": since,
+            "bucket_width": bucket_width,
+            "weeks": weeks
+        })))
+    })
+    .await
+}
+
+#[derive(Deserialize)]
+pub struct DriftQuery {
+    pub model: Option<String>,
+    /// Relative change of the mean output tokens that counts as drift (default `DRIFT_OUTPUT_TOKENS_THRESHOLD`)
+    pub output_tokens_threshold: Option<f64>,
+    /// Absolute change of the pass rate that counts as drift (default `DRIFT_PASS_RATE_THRESHOLD`)
+    pub pass_rate_threshold: Option<f64>,
+    /// Evaluations a model needs in both weeks (default `DRIFT_MIN_EVALUATIONS`)
+    pub min_evaluations: Option<u32>,
+}
+
+impl DriftQuery {
+    /// The server's thresholds with the ones given in the query.
+    fn thresholds(&self, defaults: DriftThresholds) -> Result<DriftThresholds, ApiError> {
+        if self.output_tokens_threshold.is_some_and(|change| !(0.0..).contains(&change)) {
+            return Err(ApiError::validation("output_tokens_threshold must be a non-negative number"));
+        }
+        if self.pass_rate_threshold.is_some_and(|change| !(0.0..=1.0).contains(&change)) {
+            return Err(ApiError::validation("pass_rate_threshold must be a number from 0 to 1"));
+        }
+        Ok(DriftThresholds {
+            output_tokens: self.output_tokens_threshold.unwrap_or(defaults.output_tokens),
+            pass_rate: self.pass_rate_threshold.unwrap_or(defaults.pass_rate),
+            min_evaluations: self.min_evaluations.map_or(defaults.min_evaluations, i64::from),
+        })
+    }
+}
+
+/// GET /api/v1/stats/drift - Models whose mean output tokens or pass rate moved past a threshold
+/// since the week before
+pub async fn get_drift(
+    state: web::Data<AppState>,
+    http_req: HttpRequest,
+    query: web::Query<DriftQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let thresholds = query.thresholds(state.config.drift_thresholds)?;
+    let since = drift::lookback_start(chrono::Utc::now().date_naive(), drift::LOOKBACK_WEEKS);
+
+    let pool = state.pool()?;
+    etag::conditional(pool, &http_req, || async {
+        let weeks = database::get_weekly_output_tokens(pool, query.model.as_deref(), Some(&since), None)
+            .await
+            .map_err(|e| ApiError::database("Failed to fetch weekly output tokens", e))?;
+        let models = drift::detect(&weeks, &thresholds);
+        Ok(HttpResponse::Ok().json(serde_json::json!({
+            "thresholds": thresholds,
+            "drifted": models.iter().filter(|m| m.drifted).map(|m| &m.model).collect::<Vec<_>>(),
+            "models": models
+        })))
+    })
+    .await
+}
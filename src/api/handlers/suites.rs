@@ -0,0 +1,223 @@
+// src/api/handlers/suites.rs
+use actix_web::{web, HttpRequest, HttpResponse};
+use serde::{Deserialize, Serialize};
+use crate::api::AppState;
+use crate::api::handlers::evals::{execute_batch, labels, notify_failures, BatchEvalResponse};
+use crate::api::handlers::prompt_versions::apply_prompt_version;
+use crate::api::handlers::ws::WsBroker;
+use crate::budget::Budget;
+use crate::config::EvalConfig;
+use crate::database;
+use crate::errors::ApiError;
+use crate::runner;
+
+#[derive(Deserialize)]
+pub struct SuiteRequest {
+    pub name: String,
+    pub description: Option<String>,
+    pub configs: Vec<EvalConfig>,
+}
+
+#[derive(Serialize)]
+pub struct SuitesResponse {
+    pub suites: Vec<database::Suite>,
+}
+
+#[derive(Serialize)]
+pub struct SuiteResponse {
+    pub suite: database::Suite,
+}
+
+/// Optional body of `POST /suites/{id}/run`.
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct SuiteRunRequest {
+    /// Caps on the run's cost, tokens and duration
+    pub budget: Option<Budget>,
+}
+
+#[derive(Serialize)]
+pub struct SuiteRunResponse {
+    pub suite_run_id: String,
+    pub suite_id: String,
+    #[serde(flatten)]
+    pub batch: BatchEvalResponse,
+}
+
+#[derive(Serialize)]
+pub struct SuiteRunsResponse {
+    pub suite_id: String,
+    pub runs: Vec<database::SuiteRunSummary>,
+}
+
+/// Validates a create/update request against the loaded provider configuration.
+fn validate_suite_request(state: &AppState, req: &SuiteRequest) -> Result<(), ApiError> {
+    if req.name.trim().is_empty() {
+        return Err(ApiError::validation("Suite name must not be empty"));
+    }
+    if req.configs.is_empty() {
+        return Err(ApiError::validation("Suite must contain at least one eval config"));
+    }
+    if req.configs.iter().any(|config| !config.provider_overrides.is_empty()) {
+        // Suite configs are stored, and overriding credentials never are
+        return Err(ApiError::validation("Suite configs cannot carry provider_overrides"));
+    }
+    runner::validate_providers(&state.config, &req.configs)?;
+    Ok(())
+}
+
+/// Fills in the prompts of configs naming a prompt version. Versions never change, so the
+/// suite runs the same template as if it were looked up on every run.
+async fn apply_prompt_versions(state: &AppState, configs: &mut [EvalConfig]) -> Result<(), ApiError> {
+    for (i, config) in configs.iter_mut().enumerate() {
+        apply_prompt_version(state, config, Some(&format!("configs[{}]", i))).await?;
+    }
+    Ok(())
+}
+
+fn suite_not_found(id: &str) -> ApiError {
+    ApiError::not_found(format!("Suite '{}' not found", id))
+}
+
+fn suite_write_error(name: &str, e: sqlx::Error) -> ApiError {
+    match e {
+        sqlx::Error::RowNotFound => ApiError::not_found("Suite not found"),
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            ApiError::conflict(format!("A suite named '{}' already exists", name))
+        }
+        e => ApiError::database("Failed to save suite", e),
+    }
+}
+
+/// The suite with id or name `id`.
+pub(crate) async fn find_suite(pool: &database::DbPool, id: &str) -> Result<database::Suite, ApiError> {
+    database::get_suite(pool, id).await.map_err(|e| match e {
+        sqlx::Error::RowNotFound => suite_not_found(id),
+        e => ApiError::database("Failed to fetch suite", e),
+    })
+}
+
+/// GET /api/v1/suites - List all suites
+pub async fn get_all_suites(
+    state: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let pool = state.pool()?;
+    let suites = database::get_all_suites(pool)
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch suites", e))?;
+    Ok(HttpResponse::Ok().json(SuitesResponse { suites }))
+}
+
+/// GET /api/v1/suites/{id} - Get a suite by id or name
+pub async fn get_suite(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+
+    let suite = find_suite(state.pool()?, &id).await?;
+    Ok(HttpResponse::Ok().json(SuiteResponse { suite }))
+}
+
+/// POST /api/v1/suites - Create a suite
+pub async fn create_suite(
+    state: web::Data<AppState>,
+    req: web::Json<SuiteRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let mut req = req.into_inner();
+    validate_suite_request(&state, &req)?;
+    apply_prompt_versions(&state, &mut req.configs).await?;
+
+    let pool = state.pool()?;
+    let suite = database::create_suite(pool, req.name.clone(), req.description.clone(), &req.configs)
+        .await
+        .map_err(|e| suite_write_error(&req.name, e))?;
+    tracing::info!("✅ Created suite '{}' with {} configs", suite.name, suite.configs.len());
+    Ok(HttpResponse::Created().json(SuiteResponse { suite }))
+}
+
+/// PUT /api/v1/suites/{id} - Replace a suite's name, description and configs
+pub async fn update_suite(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+    req: web::Json<SuiteRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let mut req = req.into_inner();
+    validate_suite_request(&state, &req)?;
+    apply_prompt_versions(&state, &mut req.configs).await?;
+
+    let pool = state.pool()?;
+    let suite = database::update_suite(pool, &path, req.name.clone(), req.description.clone(), &req.configs)
+        .await
+        .map_err(|e| suite_write_error(&req.name, e))?;
+    Ok(HttpResponse::Ok().json(SuiteResponse { suite }))
+}
+
+/// DELETE /api/v1/suites/{id} - Delete a suite and its run history
+pub async fn delete_suite(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+
+    let pool = state.pool()?;
+    database::delete_suite(pool, &id).await.map_err(|e| match e {
+        sqlx::Error::RowNotFound => suite_not_found(&id),
+        e => ApiError::database("Failed to delete suite", e),
+    })?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// POST /api/v1/suites/{id}/run - Run a suite's configs as a batch, within an optional budget, and record the run
+pub async fn run_suite(
+    state: web::Data<AppState>,
+    broker: web::Data<WsBroker>,
+    http_req: HttpRequest,
+    path: web::Path<String>,
+    req: Option<web::Json<SuiteRunRequest>>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+    let req = req.map(web::Json::into_inner).unwrap_or_default();
+    if let Some(budget) = &req.budget {
+        let errors = budget.validate();
+        if !errors.is_empty() {
+            return Err(ApiError::invalid_fields(errors));
+        }
+    }
+
+    let pool = state.pool()?;
+    let suite = find_suite(pool, &id).await?;
+
+    tracing::info!("🧪 Running suite '{}' ({} configs)", suite.name, suite.configs.len());
+    let labels = labels(&suite.configs);
+    let batch = execute_batch(&state, &broker, suite.configs, req.budget).await;
+    notify_failures(&state, &http_req, format!("Suite '{}'", suite.name), &batch, &labels);
+    let evaluation_ids: Vec<String> = batch.results.iter().map(|r| r.id.clone()).collect();
+
+    let suite_run_id = database::create_suite_run(pool, &suite.id, &batch.batch_id, &evaluation_ids)
+        .await
+        .map_err(|e| {
+            ApiError::database("Suite ran but the run could not be recorded", e)
+                .with_details(serde_json::json!({ "batch_id": batch.batch_id }))
+        })?;
+    Ok(HttpResponse::Ok().json(SuiteRunResponse {
+        suite_run_id,
+        suite_id: suite.id,
+        batch,
+    }))
+}
+
+/// GET /api/v1/suites/{id}/runs - List a suite's runs with pass rates, newest first
+pub async fn get_suite_runs(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+
+    let pool = state.pool()?;
+    let suite = find_suite(pool, &id).await?;
+    let runs = database::get_suite_runs(pool, &suite.id)
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch suite runs", e))?;
+    Ok(HttpResponse::Ok().json(SuiteRunsResponse { suite_id: suite.id, runs }))
+}
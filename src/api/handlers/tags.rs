@@ -0,0 +1,68 @@
+// src/api/handlers/tags.rs
+use actix_web::{web, HttpRequest, HttpResponse};
+use serde::Deserialize;
+use crate::api::{etag, AppState};
+use crate::database;
+use crate::errors::ApiError;
+
+#[derive(Deserialize)]
+pub struct UpdateTagsRequest {
+    #[serde(default)]
+    pub add: Vec<String>,
+    #[serde(default)]
+    pub remove: Vec<String>,
+}
+
+/// GET /api/v1/tags - Every tag of the stored evaluations with how many carry it, most used first
+pub async fn get_tags(
+    state: web::Data<AppState>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let pool = state.pool()?;
+    etag::conditional(pool, &http_req, || async {
+        let tags = database::get_tag_counts(pool)
+            .await
+            .map_err(|e| ApiError::database("Failed to fetch tags", e))?;
+        Ok(HttpResponse::Ok().json(serde_json::json!({ "tags": tags })))
+    })
+    .await
+}
+
+/// A tag that can be filtered on: not blank, and without the commas `tag=` filters split on.
+fn validate_tag(tag: &str) -> Result<(), ApiError> {
+    if tag.trim().is_empty() || tag.trim() != tag {
+        return Err(ApiError::validation(format!("Tag '{}' must not be blank or start or end with spaces", tag)));
+    }
+    if tag.contains(',') {
+        return Err(ApiError::validation(format!("Tag '{}' must not contain commas", tag)));
+    }
+    Ok(())
+}
+
+/// PATCH /api/v1/evals/{id}/tags - Add tags to and remove tags from a stored evaluation
+pub async fn update_eval_tags(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+    req: web::Json<UpdateTagsRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+    let UpdateTagsRequest { add, remove } = req.into_inner();
+    if add.is_empty() && remove.is_empty() {
+        return Err(ApiError::validation("Give tags to add or remove"));
+    }
+    for tag in add.iter().chain(&remove) {
+        validate_tag(tag)?;
+    }
+    if let Some(tag) = add.iter().find(|tag| remove.contains(tag)) {
+        return Err(ApiError::validation(format!("Tag '{}' is both added and removed", tag)));
+    }
+
+    let pool = state.pool()?;
+    let tags = database::update_evaluation_tags(pool, &id, &add, &remove)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => ApiError::not_found(format!("Evaluation {} not found", id)),
+            e => ApiError::database("Failed to update tags", e),
+        })?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "id": id, "tags": tags })))
+}
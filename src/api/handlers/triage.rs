@@ -0,0 +1,112 @@
+// src/api/handlers/triage.rs
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use crate::api::AppState;
+use crate::api::handlers::rejudge::{prepare, rejudge_all, RejudgeRequest, MAX_REJUDGE};
+use crate::api::handlers::ws::WsBroker;
+use crate::database;
+use crate::errors::ApiError;
+use crate::judge_ab;
+use crate::rejudge::{self, RejudgeOutcome};
+use crate::runner::JudgeVerdict;
+
+const DEFAULT_TRIAGE_LIMIT: i64 = 50;
+
+#[derive(Deserialize)]
+pub struct TriageQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct TriageQueueResponse {
+    /// Evaluations in the whole queue, not just this page
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+    pub results: Vec<database::HistorySummary>,
+}
+
+#[derive(Serialize)]
+pub struct TriageRejudgeResponse {
+    pub judge_prompt_version: i64,
+    #[serde(flatten)]
+    pub summary: rejudge::RejudgeSummary,
+    /// Re-judged evaluations that now have a `Pass` or `Fail` verdict, and so left the queue
+    pub resolved: usize,
+    /// Evaluations still in the queue after the run
+    pub remaining: i64,
+    /// Queued evaluations that could not be re-judged
+    pub skipped: Vec<judge_ab::SkippedEvaluation>,
+    pub results: Vec<RejudgeOutcome>,
+}
+
+/// GET /api/v1/evals/triage - Unreviewed evaluations with an Uncertain verdict or a failed
+/// judge call, oldest first (`?limit=` and `?offset=` to page)
+pub async fn get_triage_queue(
+    state: web::Data<AppState>,
+    query: web::Query<TriageQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let limit = query.limit.unwrap_or(DEFAULT_TRIAGE_LIMIT);
+    if !(1..=MAX_REJUDGE).contains(&limit) {
+        return Err(ApiError::validation(format!("limit must be between 1 and {}", MAX_REJUDGE)));
+    }
+    let offset = query.offset.unwrap_or(0);
+    if offset < 0 {
+        return Err(ApiError::validation("offset must not be negative"));
+    }
+
+    let pool = state.pool()?;
+    let failed = |e| ApiError::database("Failed to fetch triage queue", e);
+    let total = database::count_triage_queue(pool).await.map_err(failed)?;
+    let entries = database::get_triage_queue(pool, limit, offset).await.map_err(failed)?;
+    let results = entries.iter().map(database::HistorySummary::of).collect();
+    Ok(HttpResponse::Ok().json(TriageQueueResponse { total, limit, offset, results }))
+}
+
+/// GET /api/v1/evals/triage/count - How many evaluations await triage, cheap enough to poll
+pub async fn get_triage_count(
+    state: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let pool = state.pool()?;
+    let count = database::count_triage_queue(pool)
+        .await
+        .map_err(|e| ApiError::database("Failed to count triage queue", e))?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "count": count })))
+}
+
+/// POST /api/v1/evals/triage/rejudge - Re-judge the queued evaluations, oldest first, with the
+/// active judge prompt, streaming progress over the WebSocket
+pub async fn rejudge_triage(
+    state: web::Data<AppState>,
+    broker: web::Data<WsBroker>,
+) -> Result<HttpResponse, ApiError> {
+    let pool = state.pool()?;
+    let options = RejudgeRequest::default();
+    let prompt = prepare(&state, pool, &options).await?;
+
+    // Counted from before the fetch, so retention cannot purge the evaluations mid-run
+    let _active = state.start_batch();
+    let entries = database::get_triage_queue(pool, MAX_REJUDGE, 0)
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch triage queue", e))?;
+    let (skipped, results) = rejudge_all(&state, &broker, pool, &entries, &options, &prompt).await;
+
+    let resolved = results
+        .iter()
+        .filter(|o| matches!(o.verdict, Some(JudgeVerdict::Pass | JudgeVerdict::Fail)))
+        .count();
+    let remaining = database::count_triage_queue(pool)
+        .await
+        .map_err(|e| ApiError::database("Failed to count triage queue", e))?;
+    tracing::info!("🩺 Triage re-judge resolved {} of {} evaluations", resolved, results.len());
+
+    Ok(HttpResponse::Ok().json(TriageRejudgeResponse {
+        judge_prompt_version: prompt.version,
+        summary: rejudge::summarize(&results),
+        resolved,
+        remaining,
+        skipped,
+        results,
+    }))
+}
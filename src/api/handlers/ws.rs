@@ -1,58 +1,448 @@
 // src/api/handlers/ws.rs
-use actix::{Actor, StreamHandler, Handler, Message, Addr, AsyncContext};
+use actix::{fut, Actor, ActorContext, ActorFutureExt, StreamHandler, Handler, Message, Addr, AsyncContext};
 use actix_web::{web, HttpRequest, HttpResponse, Error};
 use actix_web_actors::ws;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 
-#[derive(Message, Clone, Serialize)]
+use crate::api::progress::{ProgressStage, ProgressTracker};
+use crate::errors::ApiError;
+use crate::models::ApiErrorResponse;
+use crate::providers::{FinishReason, TokenUsage};
+use crate::runner::{EvalProgress, EvalResult, JudgeError, ProgressSink};
+
+/// Updates kept for replay to clients that connect late.
+pub const REPLAY_CAPACITY: usize = 200;
+
+/// How often connections are pinged, and how long one may stay silent before it is closed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Heartbeat {
+    pub interval: Duration,
+    pub timeout: Duration,
+}
+
+impl Default for Heartbeat {
+    fn default() -> Self {
+        Heartbeat { interval: Duration::from_secs(5), timeout: Duration::from_secs(30) }
+    }
+}
+
+/// A message pushed to WebSocket clients, tagged by `event`.
+#[derive(Message, Clone, Debug, Serialize)]
 #[rtype(result = "()")]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum WsEvent {
+    EvalStarted {
+        id: String,
+        model: String,
+        batch_id: Option<String>,
+    },
+    /// The model answered; its output follows in `eval_completed`.
+    ModelCompleted {
+        id: String,
+        model: String,
+        batch_id: Option<String>,
+        latency_ms: u64,
+        token_usage: Option<TokenUsage>,
+        finish_reason: Option<FinishReason>,
+    },
+    /// The eval got its verdict, from the judge or from a format rule or finish reason.
+    JudgeCompleted {
+        id: String,
+        model: String,
+        batch_id: Option<String>,
+        judge_model: String,
+        verdict: String,
+        confidence: Option<f32>,
+        reasoning: Option<String>,
+        judge_latency_ms: Option<u64>,
+    },
+    /// Carries the fields sent before events were tagged, so older clients keep working.
+    EvalCompleted(EvalUpdate),
+    BatchProgress {
+        batch_id: String,
+        completed: usize,
+        total: usize,
+    },
+    /// The eval could not run.
+    Error {
+        id: String,
+        model: Option<String>,
+        batch_id: Option<String>,
+        error_code: String,
+        message: String,
+    },
+}
+
+impl WsEvent {
+    pub fn batch_id(&self) -> Option<&str> {
+        match self {
+            WsEvent::EvalStarted { batch_id, .. }
+            | WsEvent::ModelCompleted { batch_id, .. }
+            | WsEvent::JudgeCompleted { batch_id, .. }
+            | WsEvent::Error { batch_id, .. } => batch_id.as_deref(),
+            WsEvent::EvalCompleted(update) => update.batch_id.as_deref(),
+            WsEvent::BatchProgress { batch_id, .. } => Some(batch_id),
+        }
+    }
+
+    pub fn model(&self) -> Option<&str> {
+        match self {
+            WsEvent::EvalStarted { model, .. } | WsEvent::ModelCompleted { model, .. } | WsEvent::JudgeCompleted { model, .. } => {
+                Some(model)
+            }
+            WsEvent::EvalCompleted(update) => update.model.as_deref(),
+            WsEvent::Error { model, .. } => model.as_deref(),
+            WsEvent::BatchProgress { .. } => None,
+        }
+    }
+
+    /// The `error` event of an eval that failed with `error`.
+    pub fn error(id: &str, model: Option<String>, batch_id: Option<&str>, error: &ApiErrorResponse) -> Self {
+        WsEvent::Error {
+            id: id.to_string(),
+            model,
+            batch_id: batch_id.map(str::to_string),
+            error_code: error.code.clone(),
+            message: error.message.clone(),
+        }
+    }
+
+    /// The event of a runner's `progress` in the batch `batch_id`, if any. `Finished` has none.
+    fn from_progress(progress: EvalProgress, batch_id: Option<&str>) -> Option<Self> {
+        let batch_id = batch_id.map(str::to_string);
+        match progress {
+            EvalProgress::Started { eval_id, model } => Some(WsEvent::EvalStarted { id: eval_id, model, batch_id }),
+            EvalProgress::ModelCompleted { eval_id, model, latency_ms, token_usage, finish_reason } => Some(WsEvent::ModelCompleted {
+                id: eval_id,
+                model,
+                batch_id,
+                latency_ms,
+                token_usage,
+                finish_reason,
+            }),
+            EvalProgress::JudgeCompleted { eval_id, model, judge, judge_latency_ms } => Some(WsEvent::JudgeCompleted {
+                id: eval_id,
+                model,
+                batch_id,
+                judge_model: judge.judge_model,
+                verdict: judge.verdict.to_string(),
+                confidence: judge.confidence,
+                reasoning: judge.reasoning,
+                judge_latency_ms,
+            }),
+            EvalProgress::Finished { .. } => None,
+        }
+    }
+}
+
+/// The fields of an `eval_completed` event.
+#[derive(Clone, Debug, Default, Serialize)]
 pub struct EvalUpdate {
     pub id: String,
     pub status: String,
     pub model: Option<String>,
     pub verdict: Option<String>,
     pub latency_ms: Option<u64>,
+    /// The batch this evaluation belongs to, if it ran as part of one.
+    pub batch_id: Option<String>,
+    /// How far a bulk operation such as a re-judge has got.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub progress: Option<Progress>,
+    /// The `ApiErrorResponse` code of a failed evaluation.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
+    /// The model's output, cut to `WS_MAX_OUTPUT_CHARS` characters.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model_output: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_usage: Option<TokenUsage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub judge_token_usage: Option<TokenUsage>,
+    /// Cost of the model and judge calls together.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost_usd: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub judge_reasoning: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub judge_prompt_version: Option<i64>,
+    /// Why the judge call failed, for an eval with the status `judge_error`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub judge_error: Option<Box<JudgeError>>,
+}
+
+impl EvalUpdate {
+    /// The update of an eval that finished with `result`, its output cut to `max_output_chars`.
+    pub fn completed(id: &str, status: &str, result: &EvalResult, batch_id: Option<&str>, max_output_chars: usize) -> Self {
+        let judge = result.judge_result.as_ref();
+        EvalUpdate {
+            id: id.to_string(),
+            status: status.to_string(),
+            model: Some(result.model.clone()),
+            verdict: judge.map(|j| j.verdict.to_string()),
+            latency_ms: Some(result.latency_ms),
+            batch_id: batch_id.map(str::to_string),
+            progress: None,
+            error_code: None,
+            model_output: Some(truncate_chars(&result.model_output, max_output_chars)),
+            token_usage: result.token_usage.clone(),
+            judge_token_usage: result.judge_token_usage.clone(),
+            cost_usd: result.cost_usd.map(|cost| cost + result.judge_cost_usd.unwrap_or(0.0)),
+            judge_reasoning: judge.and_then(|j| j.reasoning.clone()),
+            judge_prompt_version: result.judge_prompt_version,
+            judge_error: result.judge_error.clone().map(Box::new),
+        }
+    }
+}
+
+/// The first `max_chars` characters of `text`, with an ellipsis when cut.
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
 }
 
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Progress {
+    pub completed: usize,
+    pub total: usize,
+}
+
+/// Broadcasts the `EvalProgress` sent to the returned sink until the sink is dropped, and
+/// records it in `tracker`; await the handle to be sure every event is out. For a batch of
+/// `total` evals, each finished eval also sends a `batch_progress` event with the tracker's count.
+pub fn forward_progress(
+    broker: &WsBroker,
+    tracker: &Arc<ProgressTracker>,
+    batch: Option<(&str, usize)>,
+) -> (ProgressSink, tokio::task::JoinHandle<()>) {
+    spawn_forwarding(broker, tracker, batch.map(|(batch_id, total)| (batch_id.to_string(), total)), 0)
+}
+
+/// `forward_progress` for part of a batch whose first `completed` evals already finished, as
+/// when a job resumes; its `batch_progress` events count on from there.
+pub fn forward_resumed_progress(
+    broker: &WsBroker,
+    tracker: &Arc<ProgressTracker>,
+    batch_id: &str,
+    completed: usize,
+    total: usize,
+) -> (ProgressSink, tokio::task::JoinHandle<()>) {
+    spawn_forwarding(broker, tracker, Some((batch_id.to_string(), total)), completed)
+}
+
+fn spawn_forwarding(
+    broker: &WsBroker,
+    tracker: &Arc<ProgressTracker>,
+    batch: Option<(String, usize)>,
+    completed: usize,
+) -> (ProgressSink, tokio::task::JoinHandle<()>) {
+    if let Some((batch_id, total)) = &batch {
+        tracker.track_batch(batch_id, ProgressStage::Generating, completed, *total);
+    }
+    let (sink, mut progress) = tokio::sync::mpsc::unbounded_channel();
+    let broker = broker.clone();
+    let tracker = tracker.clone();
+    let handle = tokio::spawn(async move {
+        let batch_id = batch.as_ref().map(|(batch_id, _)| batch_id.as_str());
+        while let Some(event) = progress.recv().await {
+            match &event {
+                EvalProgress::Started { eval_id, .. } => tracker.eval_stage(eval_id, batch_id, ProgressStage::Generating),
+                EvalProgress::ModelCompleted { eval_id, .. } | EvalProgress::JudgeCompleted { eval_id, .. } => {
+                    tracker.eval_stage(eval_id, batch_id, ProgressStage::Judging)
+                }
+                EvalProgress::Finished { eval_id } => {
+                    if let Some(batch_id) = batch_id
+                        && let Some((completed, total)) = tracker.eval_finished(eval_id, batch_id)
+                    {
+                        let batch_id = batch_id.to_string();
+                        broker.broadcast(WsEvent::BatchProgress { batch_id, completed, total }).await;
+                    }
+                    continue;
+                }
+            }
+            if let Some(event) = WsEvent::from_progress(event, batch_id) {
+                broker.broadcast(event).await;
+            }
+        }
+    });
+    (sink, handle)
+}
+
+/// The updates a connection wants; unset fields match every update.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Subscription {
+    pub batch_id: Option<String>,
+    pub model: Option<String>,
+}
+
+impl Subscription {
+    pub fn matches(&self, event: &WsEvent) -> bool {
+        let batch_matches = self.batch_id.is_none() || self.batch_id.as_deref() == event.batch_id();
+        let model_matches = self.model.is_none() || self.model.as_deref() == event.model();
+        batch_matches && model_matches
+    }
+}
+
+/// A message from a client: `{"subscribe": {"batch_id": "…", "model": "…"}}` or `{"replay": 50}`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ClientMessage {
+    /// Forward only updates matching the subscription from now on; `{}` forwards all again.
+    Subscribe(Subscription),
+    /// Send the last N buffered updates matching the current subscription, oldest first.
+    Replay(usize),
+}
+
+/// A connection and the updates it subscribed to.
+type Client = (Addr<WsConnection>, Subscription);
+
 #[derive(Clone)]
 pub struct WsBroker {
-    clients: Arc<RwLock<Vec<Addr<WsConnection>>>>,
+    clients: Arc<RwLock<Vec<Client>>>,
+    /// The last `REPLAY_CAPACITY` updates, oldest first.
+    recent: Arc<RwLock<VecDeque<WsEvent>>>,
+    heartbeat: Heartbeat,
 }
 
 impl WsBroker {
     pub fn new() -> Self {
+        Self::with_heartbeat(Heartbeat::default())
+    }
+
+    pub fn with_heartbeat(heartbeat: Heartbeat) -> Self {
         Self {
             clients: Arc::new(RwLock::new(Vec::new())),
+            recent: Arc::new(RwLock::new(VecDeque::with_capacity(REPLAY_CAPACITY))),
+            heartbeat,
         }
     }
 
     pub async fn register(&self, addr: Addr<WsConnection>) {
         let mut clients = self.clients.write().await;
-        clients.push(addr);
+        clients.push((addr, Subscription::default()));
     }
 
     pub async fn unregister(&self, addr: &Addr<WsConnection>) {
         let mut clients = self.clients.write().await;
-        clients.retain(|c| c != addr);
+        clients.retain(|(c, _)| c != addr);
     }
 
-    pub async fn broadcast(&self, msg: EvalUpdate) {
-        let clients = self.clients.read().await;
-        for client in clients.iter() {
-            client.do_send(msg.clone());
+    /// Replaces the subscription of a registered connection.
+    pub async fn subscribe(&self, addr: &Addr<WsConnection>, subscription: Subscription) {
+        let mut clients = self.clients.write().await;
+        for (_, current) in clients.iter_mut().filter(|(c, _)| c == addr) {
+            *current = subscription.clone();
         }
     }
+
+    /// Buffers `msg` for replay and sends it to every connection subscribed to it. Connections
+    /// whose actor has stopped are dropped, so their mailboxes stop filling up.
+    pub async fn broadcast(&self, msg: WsEvent) {
+        {
+            let mut recent = self.recent.write().await;
+            if recent.len() == REPLAY_CAPACITY {
+                recent.pop_front();
+            }
+            recent.push_back(msg.clone());
+        }
+        let mut clients = self.clients.write().await;
+        clients.retain(|(client, _)| client.connected());
+        for (client, subscription) in clients.iter() {
+            if subscription.matches(&msg) {
+                client.do_send(msg.clone());
+            }
+        }
+    }
+
+    /// Connections currently open.
+    pub async fn client_count(&self) -> usize {
+        let clients = self.clients.read().await;
+        clients.iter().filter(|(client, _)| client.connected()).count()
+    }
+
+    /// Updates currently held for replay.
+    pub async fn buffered_count(&self) -> usize {
+        self.recent.read().await.len()
+    }
+
+    /// The last `count` buffered updates matching `subscription`, oldest first.
+    pub async fn replay(&self, count: usize, subscription: &Subscription) -> Vec<WsEvent> {
+        let recent = self.recent.read().await;
+        let mut matching: Vec<WsEvent> = recent.iter().rev().filter(|u| subscription.matches(u)).take(count).cloned().collect();
+        matching.reverse();
+        matching
+    }
 }
 
 pub struct WsConnection {
     broker: WsBroker,
+    subscription: Subscription,
+    /// When the client last sent anything, pongs included.
+    last_heard: Instant,
 }
 
 impl WsConnection {
     pub fn new(broker: WsBroker) -> Self {
-        Self { broker }
+        Self { broker, subscription: Subscription::default(), last_heard: Instant::now() }
+    }
+
+    /// Pings the client every heartbeat interval, and closes the connection once it has been
+    /// silent for the heartbeat timeout, e.g. because it vanished without a Close frame.
+    fn start_heartbeat(&self, ctx: &mut ws::WebsocketContext<Self>) {
+        let heartbeat = self.broker.heartbeat;
+        ctx.run_interval(heartbeat.interval, move |connection, ctx| {
+            if connection.last_heard.elapsed() > heartbeat.timeout {
+                tracing::info!("🔌 Closing WebSocket connection silent for over {:?}", heartbeat.timeout);
+                ctx.stop();
+                return;
+            }
+            ctx.ping(b"");
+        });
+    }
+
+    /// Answers a client's text frame. Anything but a `ClientMessage` gets an error back.
+    fn handle_text(&mut self, text: &str, ctx: &mut ws::WebsocketContext<Self>) {
+        let message = match serde_json::from_str::<ClientMessage>(text) {
+            Ok(message) => message,
+            Err(e) => {
+                let error = format!("Unsupported message, expected a subscribe or replay object: {}", e);
+                ctx.text(serde_json::json!({ "error": error }).to_string());
+                return;
+            }
+        };
+
+        // `wait` holds back later messages and updates until the broker has answered
+        let broker = self.broker.clone();
+        let addr = ctx.address();
+        match message {
+            ClientMessage::Subscribe(subscription) => {
+                self.subscription = subscription.clone();
+                let ack = serde_json::json!({ "subscribed": subscription }).to_string();
+                ctx.wait(
+                    fut::wrap_future(async move { broker.subscribe(&addr, subscription).await })
+                        .map(move |(), _: &mut Self, ctx: &mut ws::WebsocketContext<Self>| ctx.text(ack)),
+                );
+            }
+            ClientMessage::Replay(count) => {
+                let subscription = self.subscription.clone();
+                let count = count.min(REPLAY_CAPACITY);
+                ctx.wait(fut::wrap_future(async move { broker.replay(count, &subscription).await }).map(
+                    |updates: Vec<WsEvent>, _: &mut Self, ctx: &mut ws::WebsocketContext<Self>| {
+                        for update in updates {
+                            if let Ok(json) = serde_json::to_string(&update) {
+                                ctx.text(json);
+                            }
+                        }
+                    },
+                ));
+            }
+        }
     }
 }
 
@@ -60,11 +450,13 @@ impl Actor for WsConnection {
     type Context = ws::WebsocketContext<Self>;
 
     fn started(&mut self, ctx: &mut Self::Context) {
+        self.start_heartbeat(ctx);
         let addr = ctx.address();
         let broker = self.broker.clone();
-        actix::spawn(async move {
+        // Registered before the first client message is handled, so it can subscribe
+        ctx.wait(fut::wrap_future(async move {
             broker.register(addr).await;
-        });
+        }));
     }
 
     fn stopped(&mut self, ctx: &mut Self::Context) {
@@ -78,19 +470,24 @@ impl Actor for WsConnection {
 
 impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WsConnection {
     fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        self.last_heard = Instant::now();
         match msg {
             Ok(ws::Message::Ping(msg)) => ctx.pong(&msg),
-            Ok(ws::Message::Text(text)) => ctx.text(text),
-            Ok(ws::Message::Close(reason)) => ctx.close(reason),
+            Ok(ws::Message::Text(text)) => self.handle_text(&text, ctx),
+            Ok(ws::Message::Close(reason)) => {
+                ctx.close(reason);
+                ctx.stop();
+            }
+            Err(_) => ctx.stop(),
             _ => (),
         }
     }
 }
 
-impl Handler<EvalUpdate> for WsConnection {
+impl Handler<WsEvent> for WsConnection {
     type Result = ();
 
-    fn handle(&mut self, msg: EvalUpdate, ctx: &mut Self::Context) {
+    fn handle(&mut self, msg: WsEvent, ctx: &mut Self::Context) {
         if let Ok(json) = serde_json::to_string(&msg) {
             ctx.text(json);
         }
@@ -105,3 +502,319 @@ pub async fn ws_handler(
     let conn = WsConnection::new(broker.get_ref().clone());
     ws::start(conn, &req, stream)
 }
+
+/// GET /api/v1/ws/stats - Open WebSocket connections and updates held for replay
+pub async fn ws_stats(broker: web::Data<WsBroker>) -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "connected_clients": broker.client_count().await,
+        "replay_buffered": broker.buffered_count().await
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{App, HttpServer};
+    use serde_json::Value;
+    use std::net::TcpStream;
+    use tungstenite::stream::MaybeTlsStream;
+
+    type Socket = tungstenite::WebSocket<MaybeTlsStream<TcpStream>>;
+
+    fn update(id: &str, batch_id: &str, model: &str) -> WsEvent {
+        WsEvent::EvalCompleted(EvalUpdate {
+            id: id.to_string(),
+            status: "passed".to_string(),
+            model: Some(model.to_string()),
+            batch_id: Some(batch_id.to_string()),
+            ..Default::default()
+        })
+    }
+
+    fn result() -> EvalResult {
+        serde_json::from_value(serde_json::json!({
+            "model": "openai:gpt-4o",
+            "prompt": "What is 2+2?",
+            "model_output": "The answer is four.",
+            "expected": "4",
+            "judge_result": {
+                "judge_model": "openai:gpt-4o-mini",
+                "verdict": "Pass",
+                "reasoning": "Four is 4.",
+                "confidence": 0.9
+            },
+            "timestamp": "2025-01-01T00:00:00Z",
+            "latency_ms": 120,
+            "judge_latency_ms": 80,
+            "token_usage": { "input_tokens": 10, "output_tokens": 5 },
+            "judge_token_usage": { "input_tokens": 40, "output_tokens": 12 },
+            "cost_usd": 0.002,
+            "judge_cost_usd": 0.0005,
+            "total_latency_ms": 200,
+            "judge_prompt_version": 3,
+            "early_abort": null
+        }))
+        .unwrap()
+    }
+
+    /// Sends `text`, if any, then reads `count` text frames, off the runtime's thread.
+    async fn exchange(mut socket: Socket, text: Option<&'static str>, count: usize) -> (Socket, Vec<Value>) {
+        tokio::task::spawn_blocking(move || {
+            if let Some(text) = text {
+                socket.send(tungstenite::Message::text(text)).unwrap();
+            }
+            let mut received = Vec::new();
+            while received.len() < count {
+                if let tungstenite::Message::Text(frame) = socket.read().unwrap() {
+                    received.push(serde_json::from_str(&frame).unwrap());
+                }
+            }
+            (socket, received)
+        })
+        .await
+        .unwrap()
+    }
+
+    fn ids(updates: &[Value]) -> Vec<&str> {
+        updates.iter().map(|u| u["id"].as_str().unwrap()).collect()
+    }
+
+    #[actix_web::test]
+    async fn test_subscriptions_filter_updates_and_replay_buffered_ones() {
+        let broker = WsBroker::new();
+        let server_broker = broker.clone();
+        let server = HttpServer::new(move || {
+            App::new().app_data(web::Data::new(server_broker.clone())).route("/ws", web::get().to(ws_handler))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let url = format!("ws://{}/ws", server.addrs()[0]);
+        actix_web::rt::spawn(server.run());
+
+        // Sent before the client connects, so only a replay delivers them
+        broker.broadcast(update("1", "a", "ollama:llama3")).await;
+        broker.broadcast(update("2", "b", "ollama:llama3")).await;
+        broker.broadcast(update("3", "a", "openai:gpt-4o")).await;
+
+        let (socket, _) = tokio::task::spawn_blocking(move || tungstenite::connect(url).unwrap()).await.unwrap();
+        let (socket, ack) = exchange(socket, Some(r#"{"subscribe": {"batch_id": "a"}}"#), 1).await;
+        assert_eq!(ack[0]["subscribed"]["batch_id"], "a");
+
+        let (socket, replayed) = exchange(socket, Some(r#"{"replay": 50}"#), 2).await;
+        assert_eq!(ids(&replayed), ["1", "3"]);
+        let (socket, replayed) = exchange(socket, Some(r#"{"replay": 1}"#), 1).await;
+        assert_eq!(ids(&replayed), ["3"]);
+
+        // Updates arrive in order, so "4" would come first if it were forwarded
+        broker.broadcast(update("4", "b", "ollama:llama3")).await;
+        broker.broadcast(update("5", "a", "ollama:llama3")).await;
+        let (socket, live) = exchange(socket, None, 1).await;
+        assert_eq!(ids(&live), ["5"]);
+
+        let (socket, ack) = exchange(socket, Some(r#"{"subscribe": {"model": "openai:gpt-4o"}}"#), 1).await;
+        assert_eq!(ack[0]["subscribed"]["model"], "openai:gpt-4o");
+        broker.broadcast(update("6", "a", "ollama:llama3")).await;
+        broker.broadcast(update("7", "c", "openai:gpt-4o")).await;
+        let (socket, live) = exchange(socket, None, 1).await;
+        assert_eq!(ids(&live), ["7"]);
+
+        // Arbitrary text is no longer echoed back
+        let (_, reply) = exchange(socket, Some("hello"), 1).await;
+        assert!(reply[0]["error"].as_str().unwrap().starts_with("Unsupported message"));
+    }
+
+    #[actix_web::test]
+    async fn test_vanished_clients_are_dropped_after_the_heartbeat_timeout() {
+        let heartbeat = Heartbeat { interval: Duration::from_millis(50), timeout: Duration::from_millis(300) };
+        let broker = WsBroker::with_heartbeat(heartbeat);
+        let server_broker = broker.clone();
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(web::Data::new(server_broker.clone()))
+                .route("/ws", web::get().to(ws_handler))
+                .route("/ws/stats", web::get().to(ws_stats))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let addr = server.addrs()[0];
+        actix_web::rt::spawn(server.run());
+
+        let url = format!("ws://{}/ws", addr);
+        let connected = Instant::now();
+        let (dropped, silent) = tokio::task::spawn_blocking(move || {
+            (tungstenite::connect(&url).unwrap().0, tungstenite::connect(&url).unwrap().0)
+        })
+        .await
+        .unwrap();
+        let stats = || async {
+            let body = reqwest::get(format!("http://{}/ws/stats", addr)).await.unwrap();
+            body.json::<Value>().await.unwrap()["connected_clients"].as_u64().unwrap()
+        };
+        assert_eq!(stats().await, 2);
+
+        let wait_for = |count: usize| {
+            let broker = broker.clone();
+            async move {
+                while broker.client_count().await > count {
+                    assert!(connected.elapsed() < Duration::from_secs(5), "{} clients still registered", broker.client_count().await);
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                }
+            }
+        };
+
+        // One client goes away without a Close frame...
+        drop(dropped);
+        wait_for(1).await;
+        // ...the other stays connected but never reads, so it never answers a ping
+        wait_for(0).await;
+        assert!(connected.elapsed() >= heartbeat.timeout);
+        assert_eq!(stats().await, 0);
+        assert!(broker.clients.read().await.is_empty());
+
+        broker.broadcast(update("1", "a", "ollama:llama3")).await;
+        drop(silent);
+    }
+
+    #[actix_web::test]
+    async fn test_replay_buffer_keeps_the_latest_updates() {
+        let broker = WsBroker::new();
+        for i in 0..REPLAY_CAPACITY + 5 {
+            broker.broadcast(update(&i.to_string(), "a", "ollama:llama3")).await;
+        }
+        let all = broker.replay(usize::MAX, &Subscription::default()).await;
+        assert_eq!(all.len(), REPLAY_CAPACITY);
+        let id = |event: &WsEvent| match event {
+            WsEvent::EvalCompleted(update) => update.id.clone(),
+            other => panic!("unexpected event {:?}", other),
+        };
+        assert_eq!(id(&all[0]), "5");
+        assert_eq!(id(all.last().unwrap()), (REPLAY_CAPACITY + 4).to_string());
+    }
+
+    fn json(event: WsEvent) -> Value {
+        serde_json::to_value(event).unwrap()
+    }
+
+    #[test]
+    fn test_eval_completed_keeps_the_old_fields_and_adds_output_usage_and_reasoning() {
+        let update = EvalUpdate::completed("e1", "passed", &result(), Some("b1"), 10);
+        assert_eq!(
+            json(WsEvent::EvalCompleted(update)),
+            serde_json::json!({
+                "event": "eval_completed",
+                "id": "e1",
+                "status": "passed",
+                "model": "openai:gpt-4o",
+                "verdict": "Pass",
+                "latency_ms": 120,
+                "batch_id": "b1",
+                "model_output": "The answer…",
+                "token_usage": { "input_tokens": 10, "output_tokens": 5 },
+                "judge_token_usage": { "input_tokens": 40, "output_tokens": 12 },
+                "cost_usd": 0.0025,
+                "judge_reasoning": "Four is 4.",
+                "judge_prompt_version": 3
+            })
+        );
+        let whole = EvalUpdate::completed("e1", "passed", &result(), None, 500);
+        assert_eq!(whole.model_output.as_deref(), Some("The answer is four."));
+    }
+
+    #[test]
+    fn test_progress_events_serialize_with_their_event_tag() {
+        assert_eq!(
+            json(WsEvent::EvalStarted { id: "e1".to_string(), model: "openai:gpt-4o".to_string(), batch_id: None }),
+            serde_json::json!({ "event": "eval_started", "id": "e1", "model": "openai:gpt-4o", "batch_id": null })
+        );
+        let started = EvalProgress::Started { eval_id: "e1".to_string(), model: "openai:gpt-4o".to_string() };
+        assert_eq!(
+            json(WsEvent::from_progress(started, Some("b1")).unwrap()),
+            serde_json::json!({ "event": "eval_started", "id": "e1", "model": "openai:gpt-4o", "batch_id": "b1" })
+        );
+        assert_eq!(
+            json(WsEvent::ModelCompleted {
+                id: "e1".to_string(),
+                model: "openai:gpt-4o".to_string(),
+                batch_id: Some("b1".to_string()),
+                latency_ms: 120,
+                token_usage: result().token_usage,
+                finish_reason: Some(FinishReason::Length),
+            }),
+            serde_json::json!({
+                "event": "model_completed",
+                "id": "e1",
+                "model": "openai:gpt-4o",
+                "batch_id": "b1",
+                "latency_ms": 120,
+                "token_usage": { "input_tokens": 10, "output_tokens": 5 },
+                "finish_reason": "length"
+            })
+        );
+        let judge = result().judge_result.unwrap();
+        let judged = EvalProgress::JudgeCompleted {
+            eval_id: "e1".to_string(),
+            model: "openai:gpt-4o".to_string(),
+            judge,
+            judge_latency_ms: Some(80),
+        };
+        assert_eq!(
+            json(WsEvent::from_progress(judged, Some("b1")).unwrap()),
+            serde_json::json!({
+                "event": "judge_completed",
+                "id": "e1",
+                "model": "openai:gpt-4o",
+                "batch_id": "b1",
+                "judge_model": "openai:gpt-4o-mini",
+                "verdict": "Pass",
+                "confidence": 0.8999999761581421,
+                "reasoning": "Four is 4.",
+                "judge_latency_ms": 80
+            })
+        );
+        assert_eq!(
+            json(WsEvent::BatchProgress { batch_id: "b1".to_string(), completed: 2, total: 5 }),
+            serde_json::json!({ "event": "batch_progress", "batch_id": "b1", "completed": 2, "total": 5 })
+        );
+        let error = ApiErrorResponse { code: "provider_error".to_string(), message: "Model unavailable".to_string(), details: None };
+        assert_eq!(
+            json(WsEvent::error("e2", Some("openai:gpt-4o".to_string()), Some("b1"), &error)),
+            serde_json::json!({
+                "event": "error",
+                "id": "e2",
+                "model": "openai:gpt-4o",
+                "batch_id": "b1",
+                "error_code": "provider_error",
+                "message": "Model unavailable"
+            })
+        );
+        assert!(WsEvent::from_progress(EvalProgress::Finished { eval_id: "e1".to_string() }, None).is_none());
+    }
+
+    #[actix_web::test]
+    async fn test_forwarded_progress_counts_finished_evals_of_a_batch() {
+        let broker = WsBroker::new();
+        let (sink, forwarding) = forward_progress(&broker, &Arc::default(), Some(("b1", 2)));
+        for eval_id in ["e1", "e2"] {
+            let model = EvalProgress::ModelCompleted {
+                eval_id: eval_id.to_string(),
+                model: "ollama:llama3".to_string(),
+                latency_ms: 5,
+                token_usage: None,
+                finish_reason: None,
+            };
+            sink.send(model).unwrap();
+            sink.send(EvalProgress::Finished { eval_id: eval_id.to_string() }).unwrap();
+        }
+        drop(sink);
+        forwarding.await.unwrap();
+
+        let events: Vec<Value> = broker.replay(usize::MAX, &Subscription::default()).await.into_iter().map(json).collect();
+        let tags: Vec<&str> = events.iter().map(|e| e["event"].as_str().unwrap()).collect();
+        assert_eq!(tags, ["model_completed", "batch_progress", "model_completed", "batch_progress"]);
+        assert_eq!(events[3]["completed"], 2);
+        assert_eq!(events[3]["total"], 2);
+        assert_eq!(events[2]["batch_id"], "b1");
+    }
+}
@@ -1,7 +1,14 @@
 // src/api/mod.rs
+pub mod auth;
+pub mod etag;
 pub mod handlers;
+pub mod progress;
+pub mod rate_limit;
+pub mod request_id;
 mod routes;
 mod state;
 
+#[cfg(test)]
 pub use routes::configure_routes;
+pub use routes::routes_with_body_limit;
 pub use state::AppState;
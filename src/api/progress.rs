@@ -0,0 +1,332 @@
+// src/api/progress.rs
+//! Live progress of the evals and batches running now, for `GET /api/v1/evals/{id}/status`.
+//! The progress forwarding task of `handlers::ws` records every runner event here and takes
+//! its `batch_progress` events from the counts kept, so the endpoint and the WebSocket always
+//! agree. Finished work stays for a grace period, then only the database knows of it.
+use dashmap::DashMap;
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::database::{BatchSummary, HistoryEntry};
+
+/// How long a finished eval or batch is still answered from the tracker.
+pub const PROGRESS_GRACE: Duration = Duration::from_secs(300);
+
+/// Where an eval or batch is. A batch is `generating` until each of its evals has an answer
+/// or has failed, then `judging` until the runner returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgressStage {
+    Queued,
+    Generating,
+    Judging,
+    Saving,
+    Done,
+    Error,
+}
+
+impl ProgressStage {
+    pub fn is_finished(self) -> bool {
+        matches!(self, ProgressStage::Done | ProgressStage::Error)
+    }
+
+    /// The progress of a single eval at this stage, in percent.
+    fn percent(self) -> u8 {
+        match self {
+            ProgressStage::Queued => 0,
+            ProgressStage::Generating => 25,
+            ProgressStage::Judging => 50,
+            ProgressStage::Saving => 75,
+            ProgressStage::Done | ProgressStage::Error => 100,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgressKind {
+    Eval,
+    Batch,
+}
+
+/// The progress of an eval or batch, as `GET /api/v1/evals/{id}/status` returns it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProgressState {
+    pub id: String,
+    pub kind: ProgressKind,
+    pub stage: ProgressStage,
+    /// Finished evals over all evals for a batch, the stage reached for an eval; 0 to 100.
+    pub progress: u8,
+    /// The batch an eval runs in.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch_id: Option<String>,
+    /// Evals of a batch that have finished.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completed: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<usize>,
+    /// Whether this came from the tracker rather than the database.
+    pub live: bool,
+    pub updated_at: String,
+}
+
+impl ProgressState {
+    /// The progress of an evaluation no longer tracked, from what was stored of it.
+    pub fn of_evaluation(entry: &HistoryEntry) -> Self {
+        let stage = match entry.status.as_deref() {
+            Some("error") => ProgressStage::Error,
+            _ => ProgressStage::Done,
+        };
+        ProgressState {
+            id: entry.id.clone(),
+            kind: ProgressKind::Eval,
+            stage,
+            progress: stage.percent(),
+            batch_id: entry.batch_id.clone(),
+            completed: None,
+            total: None,
+            live: false,
+            updated_at: entry.created_at.clone(),
+        }
+    }
+
+    /// The progress of a batch no longer tracked, of which `completed` evaluations are stored.
+    /// A batch still `running` there is being run by another server, or was cut short.
+    pub fn of_batch(batch: &BatchSummary, completed: usize) -> Self {
+        let stage = match batch.status.as_str() {
+            "completed" => ProgressStage::Done,
+            "running" => ProgressStage::Generating,
+            _ => ProgressStage::Error,
+        };
+        let total = batch.total.max(0) as usize;
+        ProgressState {
+            id: batch.batch_id.clone(),
+            kind: ProgressKind::Batch,
+            stage,
+            progress: batch_percent(completed, total),
+            batch_id: None,
+            completed: Some(completed.min(total)),
+            total: Some(total),
+            live: false,
+            updated_at: batch.created_at.clone(),
+        }
+    }
+}
+
+struct Entry {
+    state: ProgressState,
+    /// Evals of a batch past generating; for an eval, 1 once counted in its batch.
+    generated: usize,
+    finished_at: Option<Instant>,
+}
+
+impl Entry {
+    fn set_stage(&mut self, stage: ProgressStage) {
+        self.state.stage = stage;
+        if self.state.kind == ProgressKind::Eval {
+            self.state.progress = stage.percent();
+        }
+        self.finished_at = stage.is_finished().then(Instant::now);
+        self.touch();
+    }
+
+    fn touch(&mut self) {
+        self.state.updated_at = chrono::Utc::now().to_rfc3339();
+    }
+}
+
+/// Progress by eval and batch id; see the module docs.
+pub struct ProgressTracker {
+    grace: Duration,
+    entries: DashMap<String, Entry>,
+    last_eviction: Mutex<Instant>,
+}
+
+impl Default for ProgressTracker {
+    fn default() -> Self {
+        ProgressTracker::new(PROGRESS_GRACE)
+    }
+}
+
+impl ProgressTracker {
+    /// Finished entries are evicted once `grace` has passed.
+    pub fn new(grace: Duration) -> Self {
+        ProgressTracker { grace, entries: DashMap::new(), last_eviction: Mutex::new(Instant::now()) }
+    }
+
+    /// Tracks the batch `batch_id` of `total` evals at `stage`, the first `completed` of which
+    /// already finished, as when a job resumes.
+    pub fn track_batch(&self, batch_id: &str, stage: ProgressStage, completed: usize, total: usize) {
+        self.evict_stale();
+        let mut entry = Entry {
+            state: ProgressState {
+                id: batch_id.to_string(),
+                kind: ProgressKind::Batch,
+                stage,
+                progress: batch_percent(completed, total),
+                batch_id: None,
+                completed: Some(completed),
+                total: Some(total),
+                live: true,
+                updated_at: String::new(),
+            },
+            generated: completed,
+            finished_at: None,
+        };
+        entry.set_stage(stage);
+        self.entries.insert(batch_id.to_string(), entry);
+    }
+
+    /// Moves the eval `eval_id` to `stage`, tracking it from there if it is new. The first time
+    /// an eval of a batch moves past generating counts towards the batch's own stage.
+    pub fn eval_stage(&self, eval_id: &str, batch_id: Option<&str>, stage: ProgressStage) {
+        if !self.entries.contains_key(eval_id) {
+            self.evict_stale();
+            let entry = Entry {
+                state: ProgressState {
+                    id: eval_id.to_string(),
+                    kind: ProgressKind::Eval,
+                    stage,
+                    progress: 0,
+                    batch_id: batch_id.map(str::to_string),
+                    completed: None,
+                    total: None,
+                    live: true,
+                    updated_at: String::new(),
+                },
+                generated: 0,
+                finished_at: None,
+            };
+            self.entries.insert(eval_id.to_string(), entry);
+        }
+        if let Some(mut entry) = self.entries.get_mut(eval_id) {
+            entry.set_stage(stage);
+        }
+        if stage > ProgressStage::Generating {
+            self.count_generated(eval_id, batch_id);
+        }
+    }
+
+    /// Counts the eval `eval_id` of the batch `batch_id` as finished by the runner, returning
+    /// the batch's finished and total evals, or `None` when the batch is not tracked.
+    pub fn eval_finished(&self, eval_id: &str, batch_id: &str) -> Option<(usize, usize)> {
+        // An eval that failed before the model answered never left generating
+        self.count_generated(eval_id, Some(batch_id));
+        let mut entry = self.entries.get_mut(batch_id)?;
+        let total = entry.state.total.unwrap_or_default();
+        let completed = (entry.state.completed.unwrap_or_default() + 1).min(total);
+        entry.state.completed = Some(completed);
+        entry.state.progress = batch_percent(completed, total);
+        entry.touch();
+        Some((completed, total))
+    }
+
+    /// Moves the tracked batch `batch_id` to `stage`.
+    pub fn batch_stage(&self, batch_id: &str, stage: ProgressStage) {
+        if let Some(mut entry) = self.entries.get_mut(batch_id) {
+            entry.set_stage(stage);
+        }
+    }
+
+    /// The progress of `id`, unless it finished longer than the grace period ago.
+    pub fn get(&self, id: &str) -> Option<ProgressState> {
+        self.evict_stale();
+        let entry = self.entries.get(id)?;
+        let stale = entry.finished_at.is_some_and(|finished| finished.elapsed() >= self.grace);
+        (!stale).then(|| entry.state.clone())
+    }
+
+    /// Stops tracking `id`, as when its job was cancelled.
+    pub fn forget(&self, id: &str) {
+        self.entries.remove(id);
+    }
+
+    /// Counts the eval `eval_id` as past generating in its batch, once.
+    fn count_generated(&self, eval_id: &str, batch_id: Option<&str>) {
+        let Some(batch_id) = batch_id else {
+            return;
+        };
+        match self.entries.get_mut(eval_id) {
+            Some(mut eval) if eval.generated == 0 => eval.generated = 1,
+            _ => return,
+        }
+        if let Some(mut batch) = self.entries.get_mut(batch_id) {
+            batch.generated += 1;
+            if batch.state.stage == ProgressStage::Generating && Some(batch.generated) >= batch.state.total {
+                batch.set_stage(ProgressStage::Judging);
+            }
+        }
+    }
+
+    /// Once a grace period, drops entries that finished longer than a grace period ago.
+    fn evict_stale(&self) {
+        let Ok(mut last_eviction) = self.last_eviction.try_lock() else {
+            return;
+        };
+        if last_eviction.elapsed() < self.grace {
+            return;
+        }
+        *last_eviction = Instant::now();
+        self.entries.retain(|_, entry| entry.finished_at.is_none_or(|finished| finished.elapsed() < self.grace));
+    }
+}
+
+fn batch_percent(completed: usize, total: usize) -> u8 {
+    if total == 0 {
+        return 100;
+    }
+    (completed.min(total) * 100 / total) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_stages_follow_its_evals() {
+        let tracker = ProgressTracker::default();
+        tracker.track_batch("b1", ProgressStage::Queued, 0, 2);
+        assert_eq!(tracker.get("b1").unwrap().stage, ProgressStage::Queued);
+
+        tracker.track_batch("b1", ProgressStage::Generating, 0, 2);
+        for eval_id in ["e1", "e2"] {
+            tracker.eval_stage(eval_id, Some("b1"), ProgressStage::Generating);
+        }
+        tracker.eval_stage("e1", Some("b1"), ProgressStage::Judging);
+        assert_eq!(tracker.get("b1").unwrap().stage, ProgressStage::Generating);
+        assert_eq!(tracker.get("e1").unwrap().progress, 50);
+        assert_eq!(tracker.eval_finished("e1", "b1"), Some((1, 2)));
+        assert_eq!(tracker.get("b1").unwrap().progress, 50);
+
+        // e2 failed before its model answered, which still ends generating for the batch
+        assert_eq!(tracker.eval_finished("e2", "b1"), Some((2, 2)));
+        let batch = tracker.get("b1").unwrap();
+        assert_eq!((batch.stage, batch.completed, batch.progress), (ProgressStage::Judging, Some(2), 100));
+        tracker.eval_stage("e2", Some("b1"), ProgressStage::Error);
+        assert_eq!(tracker.get("e2").unwrap().batch_id.as_deref(), Some("b1"));
+
+        tracker.batch_stage("b1", ProgressStage::Done);
+        assert_eq!(tracker.get("b1").unwrap().stage, ProgressStage::Done);
+        assert_eq!(tracker.eval_finished("e1", "b2"), None);
+        tracker.forget("b1");
+        assert!(tracker.get("b1").is_none());
+    }
+
+    #[test]
+    fn test_finished_entries_are_evicted_after_the_grace_period() {
+        let tracker = ProgressTracker::new(Duration::from_millis(50));
+        tracker.track_batch("b1", ProgressStage::Generating, 1, 3);
+        assert_eq!(tracker.get("b1").unwrap().progress, 33);
+        tracker.eval_stage("e1", None, ProgressStage::Generating);
+        tracker.eval_stage("e2", None, ProgressStage::Saving);
+        tracker.eval_stage("e2", None, ProgressStage::Done);
+
+        std::thread::sleep(Duration::from_millis(80));
+        assert!(tracker.get("e2").is_none());
+        assert!(!tracker.entries.contains_key("e2"));
+        // Work still running is never evicted
+        assert_eq!(tracker.get("e1").unwrap().stage, ProgressStage::Generating);
+        assert_eq!(tracker.get("b1").unwrap().completed, Some(1));
+    }
+}
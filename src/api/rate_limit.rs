@@ -0,0 +1,272 @@
+// src/api/rate_limit.rs
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::header,
+    middleware::Next,
+    web, Error, HttpMessage, ResponseError,
+};
+use dashmap::DashMap;
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::api::auth::Caller;
+use crate::api::routes;
+use crate::api::AppState;
+use crate::config::AppConfig;
+use crate::errors::ApiError;
+
+/// Which limit a rate-limited route counts against; see `routes::RATE_LIMITED_ROUTES`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitClass {
+    /// Single evals, limited by `RATE_LIMIT_PER_MINUTE`.
+    Eval,
+    /// Batches and matrices, limited by `BATCH_RATE_LIMIT_PER_MINUTE`.
+    Batch,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token buckets per client and class. A bucket holds up to a window's worth of requests
+/// and refills continuously, so a client can burst up to the limit and then sustain it.
+pub struct RateLimiter {
+    eval_limit: Option<u32>,
+    batch_limit: Option<u32>,
+    window: Duration,
+    buckets: DashMap<(RateLimitClass, String), Bucket>,
+    last_cleanup: Mutex<Instant>,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        RateLimiter::new(None, None, Duration::from_secs(60))
+    }
+}
+
+impl RateLimiter {
+    /// `None` leaves a class unlimited. Limits are requests per `window`.
+    pub fn new(eval_limit: Option<u32>, batch_limit: Option<u32>, window: Duration) -> Self {
+        RateLimiter {
+            eval_limit,
+            batch_limit,
+            window,
+            buckets: DashMap::new(),
+            last_cleanup: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub fn from_config(config: &AppConfig) -> Self {
+        RateLimiter::new(config.rate_limit_per_minute, config.batch_rate_limit_per_minute, Duration::from_secs(60))
+    }
+
+    pub fn limit(&self, class: RateLimitClass) -> Option<u32> {
+        match class {
+            RateLimitClass::Eval => self.eval_limit,
+            RateLimitClass::Batch => self.batch_limit,
+        }
+    }
+
+    /// Takes a request from `client`'s bucket for `class`. When the bucket is empty, returns
+    /// how long until the next request is allowed.
+    pub fn check(&self, class: RateLimitClass, client: &str) -> Result<(), Duration> {
+        let Some(limit) = self.limit(class) else {
+            return Ok(());
+        };
+        let now = Instant::now();
+        self.remove_idle(now);
+
+        let capacity = f64::from(limit);
+        let per_second = capacity / self.window.as_secs_f64();
+        let mut bucket = self
+            .buckets
+            .entry((class, client.to_string()))
+            .or_insert(Bucket { tokens: capacity, updated: now });
+        let refilled = now.saturating_duration_since(bucket.updated).as_secs_f64() * per_second;
+        bucket.tokens = (bucket.tokens + refilled).min(capacity);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / per_second))
+        }
+    }
+
+    /// Once a window, drops buckets untouched for a window: they have refilled completely,
+    /// so a fresh bucket behaves the same.
+    fn remove_idle(&self, now: Instant) {
+        let Ok(mut last_cleanup) = self.last_cleanup.try_lock() else {
+            return;
+        };
+        if now.saturating_duration_since(*last_cleanup) < self.window {
+            return;
+        }
+        *last_cleanup = now;
+        self.buckets.retain(|_, bucket| now.saturating_duration_since(bucket.updated) < self.window);
+    }
+}
+
+/// Who a request is counted against: the API key when authentication is on, otherwise
+/// the peer's IP address. Forwarding headers are ignored, as clients can set them freely.
+fn client_key(req: &ServiceRequest) -> String {
+    match req.extensions().get::<Caller>() {
+        Some(caller) if caller.auth_enabled => format!("key:{}", caller.key_id.as_deref().unwrap_or(&caller.name)),
+        _ => format!("ip:{}", req.peer_addr().map_or_else(|| "unknown".to_string(), |addr| addr.ip().to_string())),
+    }
+}
+
+/// Rejects requests to the routes of `routes::RATE_LIMITED_ROUTES` with `429 Too Many
+/// Requests` and a `Retry-After` header once the caller has used up its limit. Must run
+/// after `auth::authorize`, which identifies the caller.
+pub async fn rate_limit<B: MessageBody>(
+    req: ServiceRequest,
+    next: Next<B>,
+) -> Result<ServiceResponse<EitherBody<B>>, Error> {
+    let pattern = req.match_pattern().unwrap_or_else(|| req.path().to_string());
+    let Some(class) = routes::rate_limit_class(req.method().as_str(), &pattern) else {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    };
+    let Some(state) = req.app_data::<web::Data<AppState>>().cloned() else {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    };
+
+    let client = client_key(&req);
+    if let Err(wait) = state.rate_limiter.check(class, &client) {
+        let retry_after_secs = (wait.as_secs_f64().ceil() as u64).max(1);
+        tracing::warn!("🚦 Rate limited {} on {} ({:?}); retry in {}s", client, pattern, class, retry_after_secs);
+        let error = ApiError::rate_limited(format!(
+            "Rate limit of {} requests per minute exceeded; retry in {}s",
+            state.rate_limiter.limit(class).unwrap_or_default(),
+            retry_after_secs
+        ))
+        .with_details(serde_json::json!({ "limit": class, "retry_after_secs": retry_after_secs }));
+        let mut response = error.error_response();
+        response.headers_mut().insert(header::RETRY_AFTER, header::HeaderValue::from(retry_after_secs));
+        return Ok(req.into_response(response).map_into_right_body());
+    }
+    next.call(req).await.map(ServiceResponse::map_into_left_body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{auth::authorize, configure_routes};
+    use crate::api::handlers::WsBroker;
+    use crate::config::OllamaConfig;
+    use crate::database;
+    use actix_web::{http::StatusCode, middleware::from_fn, test, App};
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// An Ollama stand-in answering every generate call with "4", and health checks too.
+    async fn mock_ollama() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut request = Vec::new();
+                    let mut chunk = [0u8; 4096];
+                    // Requests are small; read until the JSON body, or a GET's headers, are complete
+                    let complete = |r: &[u8]| r.ends_with(b"}") || (r.starts_with(b"GET") && r.ends_with(b"\r\n\r\n"));
+                    while !complete(&request) {
+                        match socket.read(&mut chunk).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(n) => request.extend_from_slice(&chunk[..n]),
+                        }
+                    }
+                    let body = r#"{"response":"4","done":true,"done_reason":"stop"}"#;
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    );
+                    let _ = socket.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+        base_url
+    }
+
+    #[actix_web::test]
+    async fn test_eval_runs_are_limited_per_client_and_recover() {
+        let window = Duration::from_millis(600);
+        let state = AppState {
+            config: Arc::new(AppConfig {
+                ollama: Some(OllamaConfig { api_base: mock_ollama().await, models: vec!["llama3".to_string()] }),
+                ..Default::default()
+            }),
+            client: reqwest::Client::new(),
+            db_pool: Some(Arc::new(database::test_pool().await)),
+            backup_in_progress: Default::default(),
+            active_batches: Default::default(),
+            retention: Default::default(),
+            rate_limiter: Arc::new(RateLimiter::new(Some(3), Some(1), window)),
+            provider_health: Default::default(),
+            progress: Default::default(),
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .app_data(web::Data::new(WsBroker::new()))
+                .wrap(from_fn(rate_limit))
+                .wrap(from_fn(authorize))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let run = serde_json::json!({ "model": "ollama:llama3", "prompt": "What is 2+2?" });
+        let request = |ip: &str| {
+            test::TestRequest::post()
+                .uri("/api/v1/evals/run")
+                .peer_addr(format!("{}:5000", ip).parse().unwrap())
+                .set_json(&run)
+                .to_request()
+        };
+
+        for _ in 0..3 {
+            let res = test::call_service(&app, request("10.0.0.1")).await;
+            assert_eq!(res.status(), StatusCode::OK);
+        }
+        let res = test::call_service(&app, request("10.0.0.1")).await;
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(res.headers().get(header::RETRY_AFTER).unwrap(), "1");
+        let body: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!(body["code"], "rate_limited");
+        assert_eq!(body["details"]["limit"], "eval");
+        assert_eq!(body["details"]["retry_after_secs"], 1);
+
+        // Other clients and other limits are counted separately
+        let res = test::call_service(&app, request("10.0.0.2")).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let batch = test::TestRequest::post()
+            .uri("/api/v1/evals/batch")
+            .peer_addr("10.0.0.1:5000".parse().unwrap())
+            .set_json(serde_json::json!([run]))
+            .to_request();
+        assert_eq!(test::call_service(&app, batch).await.status(), StatusCode::OK);
+        let health = test::TestRequest::get().uri("/api/v1/health").peer_addr("10.0.0.1:5000".parse().unwrap());
+        assert_eq!(test::call_service(&app, health.to_request()).await.status(), StatusCode::OK);
+
+        tokio::time::sleep(window).await;
+        let res = test::call_service(&app, request("10.0.0.1")).await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    async fn test_idle_buckets_are_removed_after_a_window() {
+        let limiter = RateLimiter::new(Some(1), None, Duration::from_millis(20));
+        assert!(limiter.check(RateLimitClass::Eval, "a").is_ok());
+        assert!(limiter.check(RateLimitClass::Eval, "a").is_err());
+        assert!(limiter.check(RateLimitClass::Batch, "a").is_ok());
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert!(limiter.check(RateLimitClass::Eval, "b").is_ok());
+        assert_eq!(limiter.buckets.len(), 1);
+    }
+}
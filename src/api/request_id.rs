@@ -0,0 +1,83 @@
+// src/api/request_id.rs
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::{HeaderName, HeaderValue},
+    middleware::Next,
+    Error, HttpMessage,
+};
+use tracing::Instrument;
+
+/// Header a request id is read from and echoed back in.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest caller-supplied request id kept; longer ones are replaced.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// The id of the request being handled, stored in its extensions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+/// A caller's id is kept when it is short printable ASCII, so it cannot forge log lines.
+fn is_valid(id: &str) -> bool {
+    !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN && id.bytes().all(|b| b.is_ascii_graphic())
+}
+
+/// Takes the caller's `x-request-id`, or generates one, and runs the request inside a span
+/// carrying it, so every log line of the request can be found by id. The id is returned
+/// in the response's `x-request-id` header.
+pub async fn propagate_request_id<B: MessageBody>(
+    req: ServiceRequest,
+    next: Next<B>,
+) -> Result<ServiceResponse<B>, Error> {
+    let id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| is_valid(id))
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    req.extensions_mut().insert(RequestId(id.clone()));
+
+    let span = tracing::info_span!("request", request_id = %id, method = %req.method(), path = %req.path());
+    let mut response = next.call(req).instrument(span).await?;
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+    }
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{middleware::from_fn, test, web, App, HttpRequest, HttpResponse};
+
+    async fn echo_id(req: HttpRequest) -> HttpResponse {
+        let id = req.extensions().get::<RequestId>().map(|id| id.0.clone()).unwrap_or_default();
+        HttpResponse::Ok().body(id)
+    }
+
+    #[actix_web::test]
+    async fn test_request_id_is_propagated_or_generated() {
+        let app = test::init_service(
+            App::new().wrap(from_fn(propagate_request_id)).route("/", web::get().to(echo_id)),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/").insert_header((REQUEST_ID_HEADER, "trace-123")).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.headers().get(REQUEST_ID_HEADER).unwrap(), "trace-123");
+        assert_eq!(test::read_body(resp).await, "trace-123");
+
+        for header in [None, Some("has spaces"), Some(&*"x".repeat(MAX_REQUEST_ID_LEN + 1))] {
+            let mut req = test::TestRequest::get().uri("/");
+            if let Some(header) = header {
+                req = req.insert_header((REQUEST_ID_HEADER, header));
+            }
+            let resp = test::call_service(&app, req.to_request()).await;
+            let id = resp.headers().get(REQUEST_ID_HEADER).unwrap().to_str().unwrap().to_string();
+            assert!(uuid::Uuid::parse_str(&id).is_ok(), "{}", id);
+            assert_eq!(test::read_body(resp).await, id);
+        }
+    }
+}
@@ -1,40 +1,375 @@
 // src/api/routes.rs
 use actix_web::web;
+use crate::api::auth::Role;
+use crate::api::rate_limit::RateLimitClass;
 use crate::api::handlers;
+use crate::errors::ApiError;
 
+const MAX_DATASET_UPLOAD_BYTES: usize = 50 * 1024 * 1024;
+
+pub const API_PREFIX: &str = "/api/v1";
+
+/// Routes anyone may call, even when API keys are required.
+const PUBLIC_ROUTES: &[(&str, &str)] = &[("GET", "/api/v1/health"), ("GET", "/api/v1/health/live")];
+
+/// Minimum role for every route below, by method and full route pattern. Routes missing
+/// from this list require `Admin`, so a new route stays locked down until it is listed here.
+pub const ROUTE_ROLES: &[(&str, &str, Role)] = &[
+    ("GET", "/api/v1/me", Role::Viewer),
+    ("GET", "/api/v1/metrics", Role::Viewer),
+    ("GET", "/api/v1/models", Role::Viewer),
+    ("GET", "/api/v1/ws", Role::Viewer),
+    ("GET", "/api/v1/ws/stats", Role::Viewer),
+    ("GET", "/api/v1/compare", Role::Viewer),
+    ("GET", "/api/v1/leaderboard", Role::Viewer),
+    ("POST", "/api/v1/evals/run", Role::Runner),
+    ("POST", "/api/v1/evals/batch", Role::Runner),
+    ("GET", "/api/v1/evals/batch/{batch_id}", Role::Viewer),
+    ("GET", "/api/v1/evals/batch/{batch_id}/report", Role::Viewer),
+    ("POST", "/api/v1/evals/estimate", Role::Runner),
+    ("POST", "/api/v1/evals/run-dataset", Role::Runner),
+    ("POST", "/api/v1/evals/matrix", Role::Runner),
+    ("POST", "/api/v1/evals/chain", Role::Runner),
+    ("POST", "/api/v1/evals/robustness", Role::Runner),
+    ("GET", "/api/v1/evals/robustness/{id}", Role::Viewer),
+    ("GET", "/api/v1/evals/history", Role::Viewer),
+    ("POST", "/api/v1/evals/rejudge", Role::Runner),
+    ("GET", "/api/v1/evals/triage", Role::Viewer),
+    ("GET", "/api/v1/evals/triage/count", Role::Viewer),
+    ("POST", "/api/v1/evals/triage/rejudge", Role::Runner),
+    ("PATCH", "/api/v1/evals/triage/{id}", Role::Runner),
+    ("GET", "/api/v1/evals/{id}", Role::Viewer),
+    ("GET", "/api/v1/evals/{id}/status", Role::Viewer),
+    ("GET", "/api/v1/evals/{id}/raw", Role::Viewer),
+    ("POST", "/api/v1/evals/{id}/explain", Role::Viewer),
+    ("POST", "/api/v1/evals/{id}/rejudge", Role::Runner),
+    ("GET", "/api/v1/evals/{id}/judgements", Role::Viewer),
+    ("POST", "/api/v1/evals/{id}/share", Role::Runner),
+    ("GET", "/api/v1/evals/{id}/shares", Role::Viewer),
+    ("PATCH", "/api/v1/evals/{id}/tags", Role::Runner),
+    ("GET", "/api/v1/datasets", Role::Viewer),
+    ("POST", "/api/v1/datasets", Role::Admin),
+    ("GET", "/api/v1/datasets/{id}", Role::Viewer),
+    ("POST", "/api/v1/datasets/{id}/adopt-expected", Role::Admin),
+    ("POST", "/api/v1/experiments", Role::Runner),
+    ("GET", "/api/v1/experiments/{id}", Role::Viewer),
+    ("POST", "/api/v1/jobs", Role::Runner),
+    ("GET", "/api/v1/jobs/{id}", Role::Viewer),
+    ("POST", "/api/v1/jobs/{id}/cancel", Role::Runner),
+    ("GET", "/api/v1/failure-taxonomy", Role::Viewer),
+    ("PUT", "/api/v1/failure-taxonomy", Role::Admin),
+    ("GET", "/api/v1/judge-prompts", Role::Viewer),
+    ("POST", "/api/v1/judge-prompts", Role::Admin),
+    ("GET", "/api/v1/judge-prompts/active", Role::Viewer),
+    ("PUT", "/api/v1/judge-prompts/active", Role::Admin),
+    ("GET", "/api/v1/judge-prompts/canary", Role::Viewer),
+    ("PUT", "/api/v1/judge-prompts/canary", Role::Admin),
+    ("DELETE", "/api/v1/judge-prompts/canary", Role::Admin),
+    ("GET", "/api/v1/judge-prompts/canary/report", Role::Viewer),
+    ("POST", "/api/v1/judge-prompts/ab-test", Role::Admin),
+    ("GET", "/api/v1/judge-prompts/compare", Role::Viewer),
+    ("GET", "/api/v1/judge-prompts/{version}", Role::Viewer),
+    ("PUT", "/api/v1/judge-prompts/{version}", Role::Admin),
+    ("DELETE", "/api/v1/judge-prompts/{version}", Role::Admin),
+    ("GET", "/api/v1/judge-prompts/{version}/stats", Role::Viewer),
+    ("POST", "/api/v1/judge-prompts/{version}/calibrate", Role::Admin),
+    ("GET", "/api/v1/presets", Role::Viewer),
+    ("POST", "/api/v1/presets", Role::Runner),
+    ("GET", "/api/v1/presets/{name}", Role::Viewer),
+    ("PUT", "/api/v1/presets/{name}", Role::Runner),
+    ("DELETE", "/api/v1/presets/{name}", Role::Runner),
+    ("GET", "/api/v1/prompt-versions", Role::Viewer),
+    ("POST", "/api/v1/prompt-versions", Role::Admin),
+    ("GET", "/api/v1/prompt-versions/active", Role::Viewer),
+    ("GET", "/api/v1/prompt-versions/{version}", Role::Viewer),
+    ("POST", "/api/v1/prompt-versions/{version}/activate", Role::Admin),
+    ("GET", "/api/v1/prompt-versions/{version}/stats", Role::Viewer),
+    ("GET", "/api/v1/prompt-versions/{a}/diff/{b}", Role::Viewer),
+    ("GET", "/api/v1/suites", Role::Viewer),
+    ("POST", "/api/v1/suites", Role::Admin),
+    ("GET", "/api/v1/suites/{id}", Role::Viewer),
+    ("PUT", "/api/v1/suites/{id}", Role::Admin),
+    ("DELETE", "/api/v1/suites/{id}", Role::Admin),
+    ("POST", "/api/v1/suites/{id}/run", Role::Runner),
+    ("GET", "/api/v1/suites/{id}/runs", Role::Viewer),
+    ("GET", "/api/v1/schedules", Role::Viewer),
+    ("POST", "/api/v1/schedules", Role::Admin),
+    ("GET", "/api/v1/schedules/{id}", Role::Viewer),
+    ("PUT", "/api/v1/schedules/{id}", Role::Admin),
+    ("DELETE", "/api/v1/schedules/{id}", Role::Admin),
+    ("GET", "/api/v1/reviews", Role::Viewer),
+    ("POST", "/api/v1/reviews/accept", Role::Runner),
+    ("GET", "/api/v1/reviews/stats", Role::Viewer),
+    ("PUT", "/api/v1/reviews/{id}", Role::Runner),
+    ("POST", "/api/v1/admin/backup", Role::Admin),
+    ("GET", "/api/v1/admin/backups", Role::Admin),
+    ("GET", "/api/v1/admin/retention", Role::Admin),
+    ("DELETE", "/api/v1/cache", Role::Admin),
+    ("GET", "/api/v1/api-keys", Role::Admin),
+    ("POST", "/api/v1/api-keys", Role::Admin),
+    ("DELETE", "/api/v1/api-keys/{id}", Role::Admin),
+    ("DELETE", "/api/v1/shares/{id}", Role::Runner),
+    ("GET", "/api/v1/tags", Role::Viewer),
+    ("GET", "/api/v1/stats/costs", Role::Viewer),
+    ("GET", "/api/v1/stats/provider-errors", Role::Viewer),
+    ("GET", "/api/v1/stats/leaderboard", Role::Viewer),
+    ("GET", "/api/v1/stats/timeseries", Role::Viewer),
+    ("GET", "/api/v1/stats/token-distribution", Role::Viewer),
+    ("GET", "/api/v1/stats/uncertain-reasons", Role::Viewer),
+    ("GET", "/api/v1/stats/judge-batching", Role::Viewer),
+    ("GET", "/api/v1/stats/finish-reasons", Role::Viewer),
+    ("GET", "/api/v1/stats/failure-categories", Role::Viewer),
+    ("GET", "/api/v1/stats/output-tokens", Role::Viewer),
+    ("GET", "/api/v1/stats/drift", Role::Viewer),
+    ("GET", "/api/v1/provider-errors", Role::Viewer),
+];
+
+/// What a route's response may reveal about stored evaluations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataSensitivity {
+    /// Counts, rates, latencies, tokens and costs, keyed by model, day, category or reason.
+    Aggregate,
+    /// Text of individual evaluations, such as prompts, outputs, reasoning or error bodies.
+    RowLevel,
+}
+
+/// Routes whose responses hold only aggregates, the only ones aggregate-only keys may call.
+/// Routes missing from this list count as row-level, so a new route stays closed to those
+/// keys until it is listed here.
+pub const AGGREGATE_ROUTES: &[(&str, &str)] = &[
+    ("GET", "/api/v1/me"),
+    ("GET", "/api/v1/models"),
+    ("GET", "/api/v1/ws/stats"),
+    ("GET", "/api/v1/leaderboard"),
+    ("GET", "/api/v1/evals/triage/count"),
+    ("GET", "/api/v1/judge-prompts/compare"),
+    ("GET", "/api/v1/judge-prompts/{version}/stats"),
+    ("GET", "/api/v1/reviews/stats"),
+    ("GET", "/api/v1/tags"),
+    ("GET", "/api/v1/stats/costs"),
+    ("GET", "/api/v1/stats/provider-errors"),
+    ("GET", "/api/v1/stats/leaderboard"),
+    ("GET", "/api/v1/stats/timeseries"),
+    ("GET", "/api/v1/stats/token-distribution"),
+    ("GET", "/api/v1/stats/uncertain-reasons"),
+    ("GET", "/api/v1/stats/judge-batching"),
+    ("GET", "/api/v1/stats/finish-reasons"),
+    ("GET", "/api/v1/stats/failure-categories"),
+    ("GET", "/api/v1/stats/output-tokens"),
+    ("GET", "/api/v1/stats/drift"),
+];
+
+/// The data sensitivity of a route; see `AGGREGATE_ROUTES`.
+pub fn data_sensitivity(method: &str, pattern: &str) -> DataSensitivity {
+    if AGGREGATE_ROUTES.iter().any(|(m, p)| *m == method && *p == pattern) {
+        DataSensitivity::Aggregate
+    } else {
+        DataSensitivity::RowLevel
+    }
+}
+
+/// Routes that start evals, and which per-client limit each counts against; see
+/// `rate_limit::rate_limit`. Other routes are not rate limited.
+pub const RATE_LIMITED_ROUTES: &[(&str, &str, RateLimitClass)] = &[
+    ("POST", "/api/v1/evals/run", RateLimitClass::Eval),
+    ("POST", "/api/v1/evals/batch", RateLimitClass::Batch),
+    ("POST", "/api/v1/evals/matrix", RateLimitClass::Batch),
+    ("POST", "/api/v1/evals/chain", RateLimitClass::Batch),
+    ("POST", "/api/v1/jobs", RateLimitClass::Batch),
+];
+
+/// The limit a route counts against, or `None` when it is not rate limited.
+pub fn rate_limit_class(method: &str, pattern: &str) -> Option<RateLimitClass> {
+    RATE_LIMITED_ROUTES.iter().find(|(m, p, _)| *m == method && *p == pattern).map(|(_, _, class)| *class)
+}
+
+/// The role a request needs, or `None` for public routes. `pattern` is the matched route
+/// pattern, or the raw path when no route matched.
+pub fn required_role(method: &str, pattern: &str) -> Option<Role> {
+    if PUBLIC_ROUTES.iter().any(|(m, p)| *m == method && *p == pattern) {
+        return None;
+    }
+    let role = ROUTE_ROLES
+        .iter()
+        .find(|(m, p, _)| *m == method && *p == pattern)
+        .map_or(Role::Admin, |(_, _, role)| *role);
+    Some(role)
+}
+
+/// Query strings and paths that fail to deserialize are answered as `validation_failed`.
+fn validation_error(err: impl std::fmt::Display) -> actix_web::Error {
+    ApiError::validation(err.to_string()).into()
+}
+
+/// The API routes, reading JSON bodies of up to `config::DEFAULT_MAX_BODY_BYTES`.
+#[cfg(test)]
 pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    configure_routes_limited(cfg, crate::config::DEFAULT_MAX_BODY_BYTES);
+}
+
+/// The API routes, reading JSON bodies of up to `max_body_bytes`; see `MAX_BODY_BYTES`.
+pub fn routes_with_body_limit(max_body_bytes: usize) -> impl FnOnce(&mut web::ServiceConfig) {
+    move |cfg| configure_routes_limited(cfg, max_body_bytes)
+}
+
+fn configure_routes_limited(cfg: &mut web::ServiceConfig, max_body_bytes: usize) {
     cfg.service(
-        web::scope("/api/v1")
+        web::scope(API_PREFIX)
+            .app_data(web::JsonConfig::default().limit(max_body_bytes).error_handler(|err, _| ApiError::json_body(&err).into()))
+            .app_data(web::QueryConfig::default().error_handler(|err, _| validation_error(err)))
+            .app_data(web::PathConfig::default().error_handler(|err, _| validation_error(err)))
             .route("/health", web::get().to(handlers::health_check))
+            .route("/health/live", web::get().to(handlers::liveness))
+            .route("/metrics", web::get().to(handlers::metrics))
+            .route("/me", web::get().to(handlers::get_me))
             .route("/models", web::get().to(handlers::get_models))
             .route("/ws", web::get().to(handlers::ws_handler))
+            .route("/ws/stats", web::get().to(handlers::ws_stats))
+            .route("/compare", web::get().to(handlers::compare_runs))
+            .route("/leaderboard", web::get().to(handlers::get_ranked_leaderboard))
             .service(
                 web::scope("/evals")
                     .route("/run", web::post().to(handlers::run_eval))
                     .route("/batch", web::post().to(handlers::run_batch))
+                    .route("/batch/{batch_id}", web::get().to(handlers::get_batch))
+                    .route("/batch/{batch_id}/report", web::get().to(handlers::get_batch_report))
+                    .route("/estimate", web::post().to(handlers::estimate_batch))
+                    .route("/run-dataset", web::post().to(handlers::run_dataset))
+                    .route("/matrix", web::post().to(handlers::run_matrix))
+                    .route("/chain", web::post().to(handlers::run_chain))
+                    .route("/robustness", web::post().to(handlers::run_robustness))
+                    .route("/robustness/{id}", web::get().to(handlers::get_robustness_group))
                     .route("/history", web::get().to(handlers::get_history))
+                    .route("/rejudge", web::post().to(handlers::rejudge_evals))
+                    .route("/triage", web::get().to(handlers::get_triage_queue))
+                    .route("/triage/count", web::get().to(handlers::get_triage_count))
+                    .route("/triage/rejudge", web::post().to(handlers::rejudge_triage))
+                    // Resolving an evaluation records a human verdict, as a review does
+                    .route("/triage/{id}", web::patch().to(handlers::review_evaluation))
                     .route("/{id}", web::get().to(handlers::get_eval))
                     .route("/{id}/status", web::get().to(handlers::get_status))
+                    .route("/{id}/raw", web::get().to(handlers::get_raw_exchanges))
+                    .route("/{id}/explain", web::post().to(handlers::explain_eval))
+                    .route("/{id}/rejudge", web::post().to(handlers::rejudge_eval))
+                    .route("/{id}/judgements", web::get().to(handlers::get_replaced_judgements))
+                    .route("/{id}/share", web::post().to(handlers::create_share))
+                    .route("/{id}/shares", web::get().to(handlers::get_shares))
+                    .route("/{id}/tags", web::patch().to(handlers::update_eval_tags))
+            )
+            .service(
+                web::scope("/datasets")
+                    // Uploads are read whole, so allow bodies well beyond the 256 KiB default
+                    .app_data(web::PayloadConfig::new(MAX_DATASET_UPLOAD_BYTES))
+                    .route("", web::get().to(handlers::get_all_datasets))
+                    .route("", web::post().to(handlers::upload_dataset))
+                    .route("/{id}", web::get().to(handlers::get_dataset))
+                    .route("/{id}/adopt-expected", web::post().to(handlers::adopt_expected))
             )
             .service(
                 web::scope("/experiments")
                     .route("", web::post().to(handlers::create_experiment))
                     .route("/{id}", web::get().to(handlers::get_experiment))
             )
+            .service(
+                web::scope("/jobs")
+                    .route("", web::post().to(handlers::create_job))
+                    .route("/{id}", web::get().to(handlers::get_job))
+                    .route("/{id}/cancel", web::post().to(handlers::cancel_job))
+            )
+            .route("/failure-taxonomy", web::get().to(handlers::get_failure_taxonomy))
+            .route("/failure-taxonomy", web::put().to(handlers::update_failure_taxonomy))
             .service(
                 web::scope("/judge-prompts")
                     .route("", web::get().to(handlers::get_all_judge_prompts))
                     .route("", web::post().to(handlers::create_judge_prompt))
                     .route("/active", web::get().to(handlers::get_active_judge_prompt))
                     .route("/active", web::put().to(handlers::set_active_judge_prompt))
+                    .route("/canary", web::get().to(handlers::get_canary))
+                    .route("/canary", web::put().to(handlers::start_canary))
+                    .route("/canary", web::delete().to(handlers::stop_canary))
+                    .route("/canary/report", web::get().to(handlers::get_canary_report))
+                    .route("/ab-test", web::post().to(handlers::run_ab_test))
+                    .route("/compare", web::get().to(handlers::compare_judge_prompts))
                     .route("/{version}", web::get().to(handlers::get_judge_prompt_by_version))
+                    .route("/{version}", web::put().to(handlers::update_judge_prompt))
+                    .route("/{version}", web::delete().to(handlers::delete_judge_prompt))
+                    .route("/{version}/stats", web::get().to(handlers::get_judge_prompt_stats))
+                    .route("/{version}/calibrate", web::post().to(handlers::calibrate_judge_prompt))
+            )
+            .service(
+                web::scope("/presets")
+                    .route("", web::get().to(handlers::get_presets))
+                    .route("", web::post().to(handlers::create_preset))
+                    .route("/{name}", web::get().to(handlers::get_preset))
+                    .route("/{name}", web::put().to(handlers::update_preset))
+                    .route("/{name}", web::delete().to(handlers::delete_preset))
             )
             .service(  
                 web::scope("/prompt-versions")
                     .route("", web::get().to(handlers::get_all_prompt_versions))
                     .route("", web::post().to(handlers::create_prompt_version))
                     .route("/active", web::get().to(handlers::get_active_prompt_version))
+                    .route("/{version}", web::get().to(handlers::get_prompt_version))
+                    .route("/{version}/activate", web::post().to(handlers::activate_prompt_version))
                     .route("/{version}/stats", web::get().to(handlers::get_prompt_version_stats))
-            ) 
+                    .route("/{a}/diff/{b}", web::get().to(handlers::diff_prompt_versions))
+            )
+            .service(
+                web::scope("/suites")
+                    .route("", web::get().to(handlers::get_all_suites))
+                    .route("", web::post().to(handlers::create_suite))
+                    .route("/{id}", web::get().to(handlers::get_suite))
+                    .route("/{id}", web::put().to(handlers::update_suite))
+                    .route("/{id}", web::delete().to(handlers::delete_suite))
+                    .route("/{id}/run", web::post().to(handlers::run_suite))
+                    .route("/{id}/runs", web::get().to(handlers::get_suite_runs))
+            )
+            .service(
+                web::scope("/schedules")
+                    .route("", web::get().to(handlers::get_all_schedules))
+                    .route("", web::post().to(handlers::create_schedule))
+                    .route("/{id}", web::get().to(handlers::get_schedule))
+                    .route("/{id}", web::put().to(handlers::update_schedule))
+                    .route("/{id}", web::delete().to(handlers::delete_schedule))
+            )
+            .service(
+                web::scope("/reviews")
+                    .route("", web::get().to(handlers::get_review_queue))
+                    .route("/accept", web::post().to(handlers::accept_provisional_verdicts))
+                    .route("/stats", web::get().to(handlers::get_review_stats))
+                    .route("/{id}", web::put().to(handlers::review_evaluation))
+            )
+            .service(
+                web::scope("/admin")
+                    .route("/backup", web::post().to(handlers::create_backup))
+                    .route("/backups", web::get().to(handlers::list_backups))
+                    .route("/retention", web::get().to(handlers::get_retention))
+            )
+            .service(
+                web::scope("/api-keys")
+                    .route("", web::get().to(handlers::get_api_keys))
+                    .route("", web::post().to(handlers::create_api_key))
+                    .route("/{id}", web::delete().to(handlers::revoke_api_key))
+            )
+            .route("/cache", web::delete().to(handlers::clear_cache))
+            .route("/shares/{id}", web::delete().to(handlers::revoke_share))
+            .route("/tags", web::get().to(handlers::get_tags))
+            .service(
+                web::scope("/stats")
+                    .route("/costs", web::get().to(handlers::get_cost_stats))
+                    .route("/provider-errors", web::get().to(handlers::get_provider_error_stats))
+                    .route("/leaderboard", web::get().to(handlers::get_leaderboard))
+                    .route("/timeseries", web::get().to(handlers::get_timeseries))
+                    .route("/token-distribution", web::get().to(handlers::get_token_distribution))
+                    .route("/uncertain-reasons", web::get().to(handlers::get_uncertain_reason_stats))
+                    .route("/judge-batching", web::get().to(handlers::get_judge_batching_stats))
+                    .route("/finish-reasons", web::get().to(handlers::get_finish_reason_stats))
+                    .route("/failure-categories", web::get().to(handlers::get_failure_category_stats))
+                    .route("/output-tokens", web::get().to(handlers::get_weekly_output_tokens))
+                    .route("/drift", web::get().to(handlers::get_drift))
+            )
+            .route("/provider-errors", web::get().to(handlers::get_provider_errors))
     );
+    // Outside the API scope, so share links need no credentials
+    cfg.route("/share/{token}", web::get().to(handlers::get_shared_evaluation));
 }
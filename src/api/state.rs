@@ -1,30 +1,82 @@
 use crate::config::AppConfig;
 use reqwest::Client;
-use sqlx::SqlitePool;
-use std::sync::Arc;
+use crate::database::DbPool;
+use crate::errors::ApiError;
+use crate::api::progress::ProgressTracker;
+use crate::api::rate_limit::RateLimiter;
+use crate::health::ProviderHealthCache;
+use crate::retention::RetentionStatus;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 
 #[derive(Clone)]
 pub struct AppState {
     pub config: Arc<AppConfig>,
     pub client: Client,
-    pub db_pool: Option<Arc<SqlitePool>>,
+    pub db_pool: Option<Arc<DbPool>>,
+    /// Set while a database backup runs, so concurrent backups can be rejected.
+    pub backup_in_progress: Arc<AtomicBool>,
+    /// Batches running right now; the retention task waits while any are.
+    pub active_batches: Arc<AtomicUsize>,
+    /// What the retention task has done, for `GET /api/v1/admin/retention`.
+    pub retention: Arc<Mutex<RetentionStatus>>,
+    /// Per-client limits on starting evals; see `routes::RATE_LIMITED_ROUTES`.
+    pub rate_limiter: Arc<RateLimiter>,
+    /// Recent provider health checks, reused by `GET /api/v1/health`.
+    pub provider_health: Arc<ProviderHealthCache>,
+    /// Live progress of running evals and batches, for `GET /api/v1/evals/{id}/status`.
+    pub progress: Arc<ProgressTracker>,
 }
 
 impl AppState {
     pub async fn new(config: AppConfig) -> Self {
-        // Get the pool, convert Result to Option, then wrap in Arc
+        // init_db only returns a pool once its migrations have applied
         let db_pool = match crate::database::init_db().await {
             Ok(pool) => Some(Arc::new(pool)),
             Err(e) => {
-                eprintln!("⚠️  Failed to initialize database: {}", e);
+                tracing::error!("Failed to initialize database: {}", e);
+                tracing::error!(
+                    "❌ DATABASE UNAVAILABLE: {}. Evals still run, but nothing is saved, and endpoints that need \
+                     the database answer 500. /api/v1/health reports the database as \"disabled\".",
+                    e
+                );
                 None
             }
         };
 
+        let retention = Arc::new(Mutex::new(RetentionStatus::new(config.retention.as_ref())));
+        let rate_limiter = Arc::new(RateLimiter::from_config(&config));
+        let client = config.http_client.build().expect("the HTTP client settings are checked when AppConfig is loaded");
         Self {
             config: Arc::new(config),
-            client: Client::new(),
-            db_pool,  // Now it's Option<Arc<SqlitePool>>
+            client,
+            db_pool,  // Now it's Option<Arc<DbPool>>
+            backup_in_progress: Arc::new(AtomicBool::new(false)),
+            active_batches: Arc::new(AtomicUsize::new(0)),
+            retention,
+            rate_limiter,
+            provider_health: Default::default(),
+            progress: Default::default(),
         }
     }
+
+    /// The database pool, or a `db_unavailable` error when the server started without one.
+    pub fn pool(&self) -> Result<&Arc<DbPool>, ApiError> {
+        self.db_pool.as_ref().ok_or_else(ApiError::db_unavailable)
+    }
+
+    /// Counts a batch as running until the returned guard is dropped.
+    pub fn start_batch(&self) -> ActiveBatch {
+        self.active_batches.fetch_add(1, Ordering::SeqCst);
+        ActiveBatch(self.active_batches.clone())
+    }
+}
+
+/// A running batch, counted in `AppState::active_batches` until dropped.
+pub struct ActiveBatch(Arc<AtomicUsize>);
+
+impl Drop for ActiveBatch {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
 }
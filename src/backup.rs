@@ -0,0 +1,161 @@
+// src/backup.rs
+use serde::Serialize;
+use crate::database::DbPool;
+use std::path::Path;
+use std::time::Instant;
+
+/// Prefix and extension shared by every snapshot file, used to recognize backups when listing.
+const BACKUP_PREFIX: &str = "evals-";
+const BACKUP_EXTENSION: &str = "db";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupInfo {
+    pub file_name: String,
+    pub path: String,
+    pub size_bytes: u64,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BackupResult {
+    pub backup: BackupInfo,
+    pub duration_ms: u64,
+    pub pruned: Vec<String>,
+}
+
+/// Writes a consistent snapshot of the live database into `dir` using `VACUUM INTO`,
+/// then prunes the oldest snapshots beyond `retention`.
+///
+/// `VACUUM INTO` reads the database inside a single read transaction, so the snapshot
+/// is never torn. In WAL mode writers keep committing while it runs; in rollback-journal
+/// mode they wait on the busy timeout until the snapshot completes. Postgres databases
+/// are backed up with `pg_dump` instead.
+pub async fn create_backup(
+    pool: &DbPool,
+    dir: &Path,
+    retention: usize,
+) -> Result<BackupResult, Box<dyn std::error::Error + Send + Sync>> {
+    let Some(pool) = pool.as_sqlite() else {
+        return Err("Backups are only supported for SQLite databases; use pg_dump for Postgres".into());
+    };
+    std::fs::create_dir_all(dir)?;
+
+    let now = chrono::Utc::now();
+    let file_name = format!(
+        "{}{}.{}",
+        BACKUP_PREFIX,
+        now.format("%Y%m%dT%H%M%S%.3fZ"),
+        BACKUP_EXTENSION
+    );
+    let path = dir.join(&file_name);
+
+    let start = Instant::now();
+    sqlx::query("VACUUM INTO ?")
+        .bind(path.to_string_lossy().to_string())
+        .execute(pool)
+        .await?;
+    let duration_ms = start.elapsed().as_millis() as u64;
+
+    let size_bytes = std::fs::metadata(&path)?.len();
+    tracing::info!("💾 Database backup written to {} ({} bytes, {}ms)", path.display(), size_bytes, duration_ms);
+
+    let pruned = prune_backups(dir, retention)?;
+
+    Ok(BackupResult {
+        backup: BackupInfo {
+            file_name,
+            path: path.to_string_lossy().to_string(),
+            size_bytes,
+            created_at: now.to_rfc3339(),
+        },
+        duration_ms,
+        pruned,
+    })
+}
+
+/// Lists snapshots in `dir`, newest first. A missing directory has no backups.
+pub fn list_backups(dir: &Path) -> std::io::Result<Vec<BackupInfo>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if !is_backup_file(&file_name) {
+            continue;
+        }
+        let metadata = entry.metadata()?;
+        let created_at = metadata
+            .modified()
+            .map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339())
+            .unwrap_or_default();
+        backups.push(BackupInfo {
+            path: entry.path().to_string_lossy().to_string(),
+            file_name,
+            size_bytes: metadata.len(),
+            created_at,
+        });
+    }
+
+    // Timestamped names sort chronologically
+    backups.sort_by(|a, b| b.file_name.cmp(&a.file_name));
+    Ok(backups)
+}
+
+fn is_backup_file(file_name: &str) -> bool {
+    file_name.starts_with(BACKUP_PREFIX) && file_name.ends_with(&format!(".{}", BACKUP_EXTENSION))
+}
+
+/// Deletes the oldest snapshots so at most `retention` remain, never the newest, which was
+/// just written. Returns the deleted file names.
+fn prune_backups(dir: &Path, retention: usize) -> std::io::Result<Vec<String>> {
+    let mut pruned = Vec::new();
+    for backup in list_backups(dir)?.into_iter().skip(retention.max(1)) {
+        std::fs::remove_file(&backup.path)?;
+        pruned.push(backup.file_name);
+    }
+    if !pruned.is_empty() {
+        tracing::info!("🧹 Pruned {} old database backup(s)", pruned.len());
+    }
+    Ok(pruned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_backup_snapshot_and_retention() {
+        let dir = std::env::temp_dir().join(format!("evaluate-backup-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // VACUUM INTO needs an on-disk source; in-memory databases snapshot into memory
+        let options = sqlx::sqlite::SqliteConnectOptions::new()
+            .filename(dir.join("live.sqlite"))
+            .create_if_missing(true);
+        let pool = DbPool::Sqlite(sqlx::SqlitePool::connect_with(options).await.unwrap());
+        crate::database::run_migrations(&pool).await.unwrap();
+        let dir = dir.join("backups");
+
+        for _ in 0..3 {
+            create_backup(&pool, &dir, 2).await.unwrap();
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+
+        let backups = list_backups(&dir).unwrap();
+        assert_eq!(backups.len(), 2);
+        assert!(backups[0].file_name > backups[1].file_name);
+        assert!(backups[0].size_bytes > 0);
+
+        // A retention of 0 still keeps the snapshot just written
+        let result = create_backup(&pool, &dir, 0).await.unwrap();
+        assert_eq!(result.pruned.len(), 2);
+        assert_eq!(list_backups(&dir).unwrap().len(), 1);
+        assert!(std::path::Path::new(&result.backup.path).exists());
+
+        pool.close().await;
+        std::fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,187 @@
+// src/budget.rs
+//! Caps on what a batch may spend. Once its finished evals have used up a cap no more evals
+//! are started; those in flight finish and the rest are recorded as `skipped_budget`.
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Instant;
+
+use crate::models::FieldError;
+use crate::runner::EvalResult;
+
+/// Evals of a budgeted batch in flight at once, so a cap can stop the batch part way.
+pub const BUDGETED_BATCH_CONCURRENCY: usize = 4;
+
+/// The caps of a batch; at least one must be set.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Budget {
+    /// Model plus judge cost of the finished evals, in USD.
+    pub max_cost_usd: Option<f64>,
+    /// Model plus judge input and output tokens of the finished evals.
+    pub max_total_tokens: Option<u64>,
+    /// Seconds since the batch started.
+    pub max_duration_secs: Option<u64>,
+}
+
+impl Budget {
+    /// Field errors, named under `budget`, of caps that are unset or not positive.
+    pub fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if self.max_cost_usd.is_none() && self.max_total_tokens.is_none() && self.max_duration_secs.is_none() {
+            errors.push(FieldError::new("budget", "must set max_cost_usd, max_total_tokens or max_duration_secs"));
+        }
+        if self.max_cost_usd.is_some_and(|cost| !(cost > 0.0 && cost.is_finite())) {
+            errors.push(FieldError::new("budget.max_cost_usd", "must be a positive number"));
+        }
+        if self.max_total_tokens == Some(0) {
+            errors.push(FieldError::new("budget.max_total_tokens", "must be at least 1"));
+        }
+        if self.max_duration_secs == Some(0) {
+            errors.push(FieldError::new("budget.max_duration_secs", "must be at least 1"));
+        }
+        errors
+    }
+}
+
+/// The cap that stopped a batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BudgetCap {
+    #[serde(rename = "max_cost_usd")]
+    CostUsd,
+    #[serde(rename = "max_total_tokens")]
+    TotalTokens,
+    #[serde(rename = "max_duration_secs")]
+    DurationSecs,
+}
+
+impl BudgetCap {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BudgetCap::CostUsd => "max_cost_usd",
+            BudgetCap::TotalTokens => "max_total_tokens",
+            BudgetCap::DurationSecs => "max_duration_secs",
+        }
+    }
+}
+
+impl fmt::Display for BudgetCap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// What a batch used of its budget.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BudgetUsage {
+    #[serde(flatten)]
+    pub budget: Budget,
+    pub cost_usd: f64,
+    pub total_tokens: u64,
+    pub duration_secs: f64,
+    /// The cap that stopped the batch; `None` when every eval was started.
+    pub triggered_by: Option<BudgetCap>,
+    /// Evals recorded as `skipped_budget`.
+    pub skipped: usize,
+}
+
+/// Adds up what a batch's finished evals used, and says when a cap is used up.
+#[derive(Debug)]
+pub struct BudgetTracker {
+    budget: Budget,
+    started: Instant,
+    cost_usd: f64,
+    total_tokens: u64,
+    triggered_by: Option<BudgetCap>,
+}
+
+impl BudgetTracker {
+    pub fn new(budget: Budget) -> Self {
+        BudgetTracker { budget, started: Instant::now(), cost_usd: 0.0, total_tokens: 0, triggered_by: None }
+    }
+
+    /// Adds the model and judge cost and tokens of a finished eval.
+    pub fn record(&mut self, result: &EvalResult) {
+        let tokens = |usage: Option<&crate::providers::TokenUsage>| {
+            usage.map_or(0, |u| u.input_tokens.unwrap_or(0) as u64 + u.output_tokens.unwrap_or(0) as u64)
+        };
+        self.cost_usd += result.cost_usd.unwrap_or(0.0) + result.judge_cost_usd.unwrap_or(0.0);
+        self.total_tokens += tokens(result.token_usage.as_ref()) + tokens(result.judge_token_usage.as_ref());
+    }
+
+    /// The first cap used up, checked before each eval is started. Once a cap is hit the
+    /// batch stays stopped, even if in-flight evals are all that is left.
+    pub fn exhausted(&mut self) -> Option<BudgetCap> {
+        if self.triggered_by.is_none() {
+            let budget = &self.budget;
+            self.triggered_by = if budget.max_cost_usd.is_some_and(|max| self.cost_usd >= max) {
+                Some(BudgetCap::CostUsd)
+            } else if budget.max_total_tokens.is_some_and(|max| self.total_tokens >= max) {
+                Some(BudgetCap::TotalTokens)
+            } else if budget.max_duration_secs.is_some_and(|max| self.started.elapsed().as_secs() >= max) {
+                Some(BudgetCap::DurationSecs)
+            } else {
+                None
+            };
+        }
+        self.triggered_by
+    }
+
+    pub fn usage(&self, skipped: usize) -> BudgetUsage {
+        BudgetUsage {
+            budget: self.budget,
+            cost_usd: self.cost_usd,
+            total_tokens: self.total_tokens,
+            duration_secs: self.started.elapsed().as_secs_f64(),
+            triggered_by: self.triggered_by,
+            skipped,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::TokenUsage;
+
+    fn result(input_tokens: u32, output_tokens: u32, cost_usd: f64) -> EvalResult {
+        serde_json::from_value(serde_json::json!({
+            "model": "ollama:llama3", "prompt": "2+2?", "model_output": "4", "parsed_output": null, "expected": null,
+            "judge_result": null, "timestamp": "", "latency_ms": 10, "judge_latency_ms": null,
+            "token_usage": TokenUsage { input_tokens: Some(input_tokens), output_tokens: Some(output_tokens) },
+            "judge_token_usage": null, "cost_usd": cost_usd, "total_latency_ms": 10, "judge_prompt_version": null,
+            "early_abort": null,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_the_first_cap_used_up_stops_the_batch() {
+        let mut tracker = BudgetTracker::new(Budget { max_cost_usd: Some(0.05), max_total_tokens: Some(100), max_duration_secs: None });
+        tracker.record(&result(20, 30, 0.01));
+        assert_eq!(tracker.exhausted(), None);
+        tracker.record(&result(20, 30, 0.01));
+        assert_eq!(tracker.exhausted(), Some(BudgetCap::TotalTokens));
+
+        // The trigger sticks, and in-flight evals still count
+        tracker.record(&result(0, 0, 1.0));
+        assert_eq!(tracker.exhausted(), Some(BudgetCap::TotalTokens));
+        let usage = tracker.usage(3);
+        assert_eq!((usage.total_tokens, usage.triggered_by, usage.skipped), (100, Some(BudgetCap::TotalTokens), 3));
+        assert!((usage.cost_usd - 1.02).abs() < 1e-9);
+
+        let mut tracker = BudgetTracker::new(Budget { max_cost_usd: Some(0.02), ..Default::default() });
+        tracker.record(&result(1000, 1000, 0.03));
+        assert_eq!(tracker.exhausted(), Some(BudgetCap::CostUsd));
+    }
+
+    #[test]
+    fn test_budgets_need_a_positive_cap() {
+        assert!(Budget { max_duration_secs: Some(60), ..Default::default() }.validate().is_empty());
+        let fields = |budget: Budget| budget.validate().into_iter().map(|e| e.field).collect::<Vec<_>>();
+        assert_eq!(fields(Budget::default()), ["budget"]);
+        assert_eq!(
+            fields(Budget { max_cost_usd: Some(-1.0), max_total_tokens: Some(0), max_duration_secs: Some(0) }),
+            ["budget.max_cost_usd", "budget.max_total_tokens", "budget.max_duration_secs"]
+        );
+    }
+}
@@ -0,0 +1,146 @@
+// src/chain.rs
+//! Chains of evals run one after another, where each step's prompt and expected output can
+//! use the outputs of the steps before it as `{{previous_output}}` and `{{step_N_output}}`.
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use crate::config::EvalConfig;
+use crate::models::FieldError;
+
+/// Placeholder filled with the output of the step before.
+pub const PREVIOUS_OUTPUT: &str = "previous_output";
+
+/// Step statuses that fail a chain and, with `stop_on_failure`, stop it.
+pub const FAILED_STATUSES: [&str; 4] = ["failed", "judge_error", "error", "blocked"];
+
+/// The chain and step an eval ran as.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChainStepRef {
+    pub chain_id: String,
+    /// Position of the step, from 1 as in `{{step_N_output}}`.
+    pub step: i64,
+}
+
+/// Placeholder filled with the output of step `step`, counted from 1.
+pub fn step_output_name(step: usize) -> String {
+    format!("step_{}_output", step)
+}
+
+/// The step number a `step_N_output` placeholder names.
+fn referenced_step(name: &str) -> Option<usize> {
+    name.strip_prefix("step_")?.strip_suffix("_output")?.parse().ok()
+}
+
+/// Errors for steps whose placeholders read the output of the same or a later step, or
+/// whose metadata cannot take the outputs because it is not an object.
+pub fn validate(steps: &[EvalConfig]) -> Result<(), Vec<FieldError>> {
+    let mut errors = Vec::new();
+    if steps.is_empty() {
+        errors.push(FieldError::new("steps", "must hold at least one step"));
+    }
+    for (index, step) in steps.iter().enumerate() {
+        let field = |name: &str| format!("steps[{}].{}", index, name);
+        let templates = [("prompt", Some(&step.prompt)), ("expected", step.expected.as_ref())];
+        for (name, template) in templates.into_iter().filter_map(|(name, template)| Some((name, template?))) {
+            for variable in crate::template::variables(template) {
+                if variable == PREVIOUS_OUTPUT && index == 0 {
+                    errors.push(FieldError::new(field(name), "the first step has no previous output"));
+                } else if let Some(n) = referenced_step(&variable).filter(|&n| n == 0 || n > index) {
+                    errors.push(FieldError::new(
+                        field(name),
+                        format!("{{{{{}}}}} can only read steps 1 to {}", step_output_name(n), index),
+                    ));
+                }
+            }
+        }
+        if step.metadata.as_ref().is_some_and(|metadata| !metadata.is_object()) {
+            errors.push(FieldError::new(field("metadata"), "must be an object in a chain"));
+        }
+    }
+    if errors.is_empty() { Ok(()) } else { Err(errors) }
+}
+
+/// `step` as it runs at `index` of chain `chain_id`: its metadata gains the outputs of the
+/// steps before it, `outputs[i]` being the output of step `i + 1`, or `None` when that step
+/// failed to produce one. Missing outputs stay unfilled, like any placeholder missing from
+/// the metadata.
+pub fn prepare_step(step: &EvalConfig, chain_id: &str, index: usize, outputs: &[Option<String>]) -> EvalConfig {
+    let mut step = step.clone();
+    let mut metadata = match step.metadata.take() {
+        Some(JsonValue::Object(metadata)) => metadata,
+        _ => serde_json::Map::new(),
+    };
+    for (i, output) in outputs.iter().enumerate() {
+        if let Some(output) = output {
+            metadata.insert(step_output_name(i + 1), JsonValue::String(output.clone()));
+        }
+    }
+    if let Some(Some(previous)) = index.checked_sub(1).and_then(|previous| outputs.get(previous)) {
+        metadata.insert(PREVIOUS_OUTPUT.to_string(), JsonValue::String(previous.clone()));
+    }
+    step.metadata = Some(JsonValue::Object(metadata));
+    step.chain_step = Some(ChainStepRef { chain_id: chain_id.to_string(), step: index as i64 + 1 });
+    step
+}
+
+/// Overall status of a chain from the statuses of the steps that ran: `failed` when any
+/// step failed or errored or steps were skipped, then `needs_review`, `uncertain` and
+/// `completed` when any step has that status, and `passed` when every step passed.
+pub fn chain_status<'a>(statuses: impl IntoIterator<Item = &'a str>, skipped: usize) -> &'static str {
+    let statuses: Vec<&str> = statuses.into_iter().collect();
+    if skipped > 0 || statuses.iter().any(|status| FAILED_STATUSES.contains(status)) {
+        return "failed";
+    }
+    ["needs_review", "uncertain", "completed"]
+        .into_iter()
+        .find(|wanted| statuses.contains(wanted))
+        .unwrap_or("passed")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn step(prompt: &str) -> EvalConfig {
+        serde_json::from_value(json!({ "model": "ollama:llama3", "prompt": prompt })).unwrap()
+    }
+
+    #[test]
+    fn test_steps_can_only_read_earlier_outputs() {
+        let steps = [
+            step("Extract entities from {{text}}"),
+            step("Summarize {{previous_output}}"),
+            step("Compare {{step_1_output}} with {{step_2_output}} and {{step_3_output}}"),
+        ];
+        let fields: Vec<String> = validate(&steps).unwrap_err().into_iter().map(|e| e.field).collect();
+        assert_eq!(fields, ["steps[2].prompt"]);
+
+        let errors = validate(&[step("{{previous_output}}")]).unwrap_err();
+        assert_eq!(errors[0].message, "the first step has no previous output");
+        assert!(validate(&steps[..2]).is_ok());
+        assert!(validate(&[]).is_err());
+    }
+
+    #[test]
+    fn test_prepared_steps_render_earlier_outputs() {
+        let mut third = step("{{previous_output}} / {{step_1_output}} / {{text}}");
+        third.metadata = Some(json!({ "text": "kept" }));
+        let outputs = [Some("Paris, Lyon".to_string()), Some("Two cities".to_string())];
+        let prepared = prepare_step(&third, "chain-1", 2, &outputs);
+        assert_eq!(prepared.render().unwrap().prompt, "Two cities / Paris, Lyon / kept");
+        assert_eq!(prepared.chain_step, Some(ChainStepRef { chain_id: "chain-1".to_string(), step: 3 }));
+
+        let after_failure = prepare_step(&step("{{previous_output}}"), "chain-1", 1, &[None]);
+        assert!(after_failure.render().is_err());
+    }
+
+    #[test]
+    fn test_chain_status_is_the_worst_step_status() {
+        assert_eq!(chain_status(["passed", "passed"], 0), "passed");
+        assert_eq!(chain_status(["passed", "completed"], 0), "completed");
+        assert_eq!(chain_status(["uncertain", "needs_review"], 0), "needs_review");
+        assert_eq!(chain_status(["passed", "error"], 0), "failed");
+        assert_eq!(chain_status(["failed"], 2), "failed");
+    }
+}
@@ -0,0 +1,670 @@
+// src/cli.rs
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use futures::stream::{self, StreamExt};
+use serde::Deserialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use crate::api::handlers::{stored_result, EvalResponse};
+use crate::api::AppState;
+use crate::config::EvalConfig;
+use crate::database::{self, HistoryEntry, HistoryFilter};
+use crate::models::{self, FieldError};
+use crate::report;
+use crate::runner;
+
+/// Characters of a prompt shown in the history table.
+const PROMPT_COLUMN_CHARS: usize = 40;
+
+#[derive(Parser, Debug)]
+#[command(name = "evaluate", version, about = "LLM evals with an LLM as the judge")]
+pub struct Cli {
+    /// TOML config file, merged with environment variables, which win over it; defaults to
+    /// `CONFIG_PATH` when set
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+    /// Runs the web server when omitted
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+impl Cli {
+    /// The config file to load: `--config`, else `CONFIG_PATH`. Read once `.env` is loaded,
+    /// so `CONFIG_PATH` may be set there.
+    pub fn config_path(&self) -> Option<PathBuf> {
+        self.config.clone().or_else(|| std::env::var_os("CONFIG_PATH").filter(|path| !path.is_empty()).map(PathBuf::from))
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Start the web server and dashboard on port 8080
+    Serve,
+    /// Run one eval from flags or a file and print its result
+    Run(RunArgs),
+    /// Run a file of evals and write one result per line (JSON Lines)
+    Batch(BatchArgs),
+    /// Print recent evaluations from the database
+    History(HistoryArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct RunArgs {
+    /// JSON or TOML file holding one eval config
+    #[arg(long, short, conflicts_with_all = ["model", "prompt", "expected", "judge_model", "criteria", "tags"])]
+    pub file: Option<PathBuf>,
+    /// Model to evaluate, e.g. `ollama:llama3`
+    #[arg(long, required_unless_present = "file")]
+    pub model: Option<String>,
+    #[arg(long, required_unless_present = "file")]
+    pub prompt: Option<String>,
+    #[arg(long)]
+    pub expected: Option<String>,
+    #[arg(long)]
+    pub judge_model: Option<String>,
+    #[arg(long)]
+    pub criteria: Option<String>,
+    #[arg(long = "tag")]
+    pub tags: Vec<String>,
+    /// Write the result to this file instead of stdout
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+    /// Do not save the evaluation to the database
+    #[arg(long)]
+    pub no_save: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct BatchArgs {
+    /// JSON array, JSON Lines (`.jsonl`) or TOML (`[[evals]]`) file of eval configs
+    #[arg(long, short)]
+    pub file: PathBuf,
+    /// Evals running at the same time
+    #[arg(long, short, default_value_t = 4, value_parser = clap::value_parser!(u32).range(1..))]
+    pub concurrency: u32,
+    /// Write the results to this file instead of stdout
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+    /// Also write a report of the batch to this file: HTML for `.html` files, else Markdown
+    #[arg(long, conflicts_with = "no_save")]
+    pub report: Option<PathBuf>,
+    /// Do not save the evaluations to the database
+    #[arg(long)]
+    pub no_save: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct HistoryArgs {
+    /// Evaluations shown, newest first
+    #[arg(long, short, default_value_t = 20)]
+    pub limit: usize,
+    /// Only evaluations carrying this tag
+    #[arg(long)]
+    pub tag: Option<String>,
+    #[arg(long, value_enum, default_value_t = HistoryFormat::Table)]
+    pub format: HistoryFormat,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryFormat {
+    Table,
+    Json,
+}
+
+/// Exit status of a CLI command, for gating CI jobs on eval results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exit {
+    /// Every eval ran and none was judged a fail.
+    Passed = 0,
+    /// At least one eval was judged a fail.
+    Failed = 1,
+    /// Bad arguments, or an eval file that cannot be read or is invalid. Nothing ran.
+    Invalid = 2,
+    /// At least one eval could not run, e.g. its provider errored, or `history` has no
+    /// database. Wins over `Failed`, as the results are incomplete.
+    Errored = 3,
+}
+
+impl Exit {
+    /// The exit of a run whose evals ended with `statuses`, as returned by `runner::eval_status`
+    /// or `EvalError::status`. Uncertain verdicts and evals waiting for review do not fail a run.
+    pub fn for_statuses<'a>(statuses: impl IntoIterator<Item = &'a str>) -> Exit {
+        statuses.into_iter().fold(Exit::Passed, |exit, status| match (exit, status) {
+            (_, "error" | "blocked" | "judge_error") | (Exit::Errored, _) => Exit::Errored,
+            (_, "failed") | (Exit::Failed, _) => Exit::Failed,
+            _ => exit,
+        })
+    }
+}
+
+impl From<Exit> for std::process::ExitCode {
+    fn from(exit: Exit) -> Self {
+        std::process::ExitCode::from(exit as u8)
+    }
+}
+
+/// Formats an eval file can be written in, picked by extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileFormat {
+    /// A single config object or an array of them.
+    Json,
+    /// One config object per line; blank lines are skipped.
+    JsonLines,
+    /// Top-level config fields, or an `[[evals]]` array of tables.
+    Toml,
+}
+
+impl FileFormat {
+    /// The format of `path` by its extension; anything but `.toml` and `.jsonl` is read as JSON.
+    pub fn of(path: &Path) -> FileFormat {
+        match path.extension().and_then(|ext| ext.to_str()).map(str::to_ascii_lowercase).as_deref() {
+            Some("toml") => FileFormat::Toml,
+            Some("jsonl") | Some("ndjson") => FileFormat::JsonLines,
+            _ => FileFormat::Json,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TomlEvals {
+    evals: Vec<EvalConfig>,
+}
+
+/// The eval configs of an eval file's contents. Errors name the config they are about.
+pub fn parse_evals(text: &str, format: FileFormat) -> Result<Vec<EvalConfig>, String> {
+    match format {
+        FileFormat::Json => match serde_json::from_str::<serde_json::Value>(text).map_err(|e| e.to_string())? {
+            serde_json::Value::Array(items) => items
+                .into_iter()
+                .enumerate()
+                .map(|(i, item)| serde_json::from_value(item).map_err(|e| format!("[{}]: {}", i, e)))
+                .collect(),
+            item => serde_json::from_value(item).map(|eval| vec![eval]).map_err(|e| e.to_string()),
+        },
+        FileFormat::JsonLines => text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| serde_json::from_str(line).map_err(|e| format!("line {}: {}", i + 1, e)))
+            .collect(),
+        FileFormat::Toml => {
+            let table: toml::Table = toml::from_str(text).map_err(|e| e.to_string())?;
+            if table.contains_key("evals") {
+                toml::from_str::<TomlEvals>(text).map(|file| file.evals).map_err(|e| e.to_string())
+            } else {
+                toml::from_str::<EvalConfig>(text).map(|eval| vec![eval]).map_err(|e| e.to_string())
+            }
+        }
+    }
+}
+
+/// The eval configs of the file at `path`.
+pub fn load_evals(path: &Path) -> Result<Vec<EvalConfig>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+    parse_evals(&text, FileFormat::of(path)).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+impl RunArgs {
+    /// The eval to run: the file's single config, or one built from the flags.
+    fn eval_config(&self) -> Result<EvalConfig, String> {
+        if let Some(path) = &self.file {
+            let mut evals = load_evals(path)?;
+            if evals.len() != 1 {
+                return Err(format!("{} holds {} evals; `run` takes one, use `batch` for more", path.display(), evals.len()));
+            }
+            return Ok(evals.remove(0));
+        }
+        Ok(EvalConfig {
+            model: self.model.clone().unwrap_or_default(),
+            prompt: self.prompt.clone().unwrap_or_default(),
+            prompt_version: None,
+            expected: self.expected.clone(),
+            judge_model: self.judge_model.clone(),
+            criteria: self.criteria.clone(),
+            tags: self.tags.clone(),
+            metadata: None,
+            format_rule: None,
+            stream: false,
+            min_judge_confidence: None,
+            truncation: None,
+            truncate_field: None,
+            context_window: None,
+            on_length_limit: None,
+            trace: false,
+            skip_failure_classification: false,
+            capture_raw: false,
+            require_review: false,
+            cache: false,
+            cache_judge: false,
+            safety_threshold: None,
+            response_format: None,
+            stop: None,
+            max_output_tokens: None,
+            thinking: None,
+            images: Vec::new(),
+            include_reasoning: false,
+            allow_missing_vars: false,
+            judge_params: None,
+            match_rule: None,
+            diff_rule: None,
+            self_consistency: None,
+            preset: None,
+            dataset_row: None,
+            chain_step: None,
+            provider_overrides: Default::default(),
+            overrides_supplied_by: None,
+        })
+    }
+}
+
+/// Runs a command other than `serve` and returns how the process should exit.
+pub async fn execute(command: Command, state: AppState) -> Exit {
+    match command {
+        Command::Serve => unreachable!("serve is handled by main"),
+        Command::Run(args) => run(args, &state).await,
+        Command::Batch(args) => batch(args, &state).await,
+        Command::History(args) => history(args, &state).await,
+    }
+}
+
+/// Prints `errors` and returns `Exit::Invalid` when there are any.
+fn report_invalid(errors: &[FieldError]) -> Option<Exit> {
+    if errors.is_empty() {
+        return None;
+    }
+    for error in errors {
+        eprintln!("❌ {}: {}", error.field, error.message);
+    }
+    Some(Exit::Invalid)
+}
+
+/// Writes `contents` to `output`, or to stdout without one.
+fn write_output(output: Option<&Path>, contents: &str) -> Result<(), String> {
+    match output {
+        Some(path) => std::fs::write(path, contents).map_err(|e| format!("cannot write {}: {}", path.display(), e)),
+        None => std::io::stdout().write_all(contents.as_bytes()).map_err(|e| e.to_string()),
+    }
+}
+
+async fn run(args: RunArgs, state: &AppState) -> Exit {
+    let eval = match args.eval_config() {
+        Ok(eval) => eval,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return Exit::Invalid;
+        }
+    };
+    if let Some(exit) = report_invalid(&eval.validate(&state.config).err().unwrap_or_default()) {
+        return exit;
+    }
+
+    let response = run_one(state, &eval, runner::new_eval_id(), None, !args.no_save).await;
+    eprintln!("{}", summary_line(&response));
+    let json = serde_json::to_string_pretty(&response).unwrap_or_default() + "\n";
+    if let Err(e) = write_output(args.output.as_deref(), &json) {
+        eprintln!("❌ {}", e);
+        return Exit::Errored;
+    }
+    Exit::for_statuses([response.status.as_str()])
+}
+
+async fn batch(args: BatchArgs, state: &AppState) -> Exit {
+    let evals = match load_evals(&args.file) {
+        Ok(evals) => evals,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return Exit::Invalid;
+        }
+    };
+    let errors: Vec<FieldError> = evals
+        .iter()
+        .enumerate()
+        .flat_map(|(i, eval)| {
+            let errors = eval.validate(&state.config).err().unwrap_or_default();
+            errors.into_iter().map(move |error| error.within(&format!("[{}]", i)))
+        })
+        .collect();
+    if let Some(exit) = report_invalid(&errors) {
+        return exit;
+    }
+
+    let batch_id = Uuid::new_v4().to_string();
+    let pool = state.db_pool.as_ref().filter(|_| !args.no_save);
+    let created = match pool {
+        Some(pool) => database::create_batch(pool, &batch_id, evals.len()).await,
+        None => Ok(()),
+    };
+    if let Err(e) = created {
+        tracing::error!("Failed to save batch {} to database: {}", batch_id, e);
+    }
+    tracing::info!("📦 Running {} evals, {} at a time (batch {})", evals.len(), args.concurrency, batch_id);
+
+    let responses: Vec<EvalResponse> = stream::iter(&evals)
+        .map(|eval| run_one(state, eval, runner::new_eval_id(), Some(&batch_id), !args.no_save))
+        .buffered(args.concurrency as usize)
+        .collect()
+        .await;
+
+    let passed = responses.iter().filter(|r| r.status == "passed").count();
+    let failed = responses.iter().filter(|r| matches!(r.status.as_str(), "failed" | "error" | "blocked")).count();
+    let finished = match pool {
+        Some(pool) => database::finish_batch(pool, &batch_id, passed, failed, "completed").await,
+        None => Ok(()),
+    };
+    if let Err(e) = finished {
+        tracing::error!("Failed to finish batch {}: {}", batch_id, e);
+    }
+
+    for response in &responses {
+        eprintln!("{}", summary_line(response));
+    }
+    eprintln!("📊 {} of {} passed, {} failed or errored", passed, responses.len(), failed);
+    if let Some(path) = args.report.as_deref() {
+        match write_report(pool.map(|pool| pool.as_ref()), &batch_id, path).await {
+            Ok(()) => eprintln!("📝 Report written to {}", path.display()),
+            Err(e) => {
+                eprintln!("❌ {}", e);
+                return Exit::Errored;
+            }
+        }
+    }
+
+    let lines: String = responses
+        .iter()
+        .map(|response| serde_json::to_string(response).unwrap_or_default() + "\n")
+        .collect();
+    if let Err(e) = write_output(args.output.as_deref(), &lines) {
+        eprintln!("❌ {}", e);
+        return Exit::Errored;
+    }
+    Exit::for_statuses(responses.iter().map(|r| r.status.as_str()))
+}
+
+/// Writes the report of the saved batch `batch_id` to `path`; see `report::render`.
+async fn write_report(pool: Option<&database::DbPool>, batch_id: &str, path: &Path) -> Result<(), String> {
+    let pool = pool.ok_or("Cannot write a report: the database is unavailable; check DATABASE_URL")?;
+    let batch = database::get_batch(pool, batch_id).await.map_err(|e| format!("Failed to fetch batch {}: {}", batch_id, e))?;
+    let evaluations = database::get_batch_evaluations(pool, batch_id)
+        .await
+        .map_err(|e| format!("Failed to fetch the evaluations of batch {}: {}", batch_id, e))?;
+    let contents = report::render(&batch, &evaluations, report::ReportFormat::of(path));
+    write_output(Some(path), &contents)
+}
+
+async fn history(args: HistoryArgs, state: &AppState) -> Exit {
+    let Some(pool) = state.db_pool.as_ref() else {
+        eprintln!("❌ The database is unavailable; check DATABASE_URL");
+        return Exit::Errored;
+    };
+    let filter = HistoryFilter { tag: args.tag.clone(), ..Default::default() };
+    let mut entries = match database::get_evaluations(pool, &filter).await {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("❌ Failed to fetch evaluations: {}", e);
+            return Exit::Errored;
+        }
+    };
+    entries.truncate(args.limit);
+
+    let output = match args.format {
+        HistoryFormat::Table => history_table(&entries),
+        HistoryFormat::Json => serde_json::to_string_pretty(&entries).unwrap_or_default() + "\n",
+    };
+    match write_output(None, &output) {
+        Ok(()) => Exit::Passed,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            Exit::Errored
+        }
+    }
+}
+
+/// Runs one eval and saves it unless `save` is off, as `POST /evals/run` does.
+async fn run_one(state: &AppState, eval: &EvalConfig, eval_id: String, batch_id: Option<&str>, save: bool) -> EvalResponse {
+    let db_pool = state.db_pool.as_ref().map(|arc| arc.as_ref());
+    let (response, result) = match runner::run_eval_with_id(&state.config, eval, &state.client, db_pool, Some(&eval_id), None).await {
+        Ok(result) => {
+            let status = runner::eval_status(&result).to_string();
+            let response = EvalResponse {
+                id: eval_id.clone(),
+                status: status.clone(),
+                result: Some(result.clone()),
+                error: None,
+                error_code: None,
+                preset: None,
+            };
+            (response, models::EvalResult::Success(stored_result(&state.config, result)))
+        }
+        Err(e) => {
+            let error = e.to_response();
+            let response = EvalResponse {
+                id: eval_id.clone(),
+                status: e.status().to_string(),
+                result: None,
+                error: Some(error.message.clone()),
+                error_code: Some(error.code.clone()),
+                preset: None,
+            };
+            (response, models::EvalResult::Error(error))
+        }
+    };
+
+    if let Some(pool) = state.db_pool.as_ref().filter(|_| save) {
+        let api_response = models::ApiResponse {
+            id: eval_id,
+            status: response.status.clone(),
+            result,
+            batch_id: batch_id.map(str::to_string),
+            config_hash: Some(eval.config_hash()),
+            preset: None,
+        };
+        if let Err(e) = database::save_evaluation_retrying(pool, &api_response).await {
+            tracing::error!("❌ Failed to save evaluation {} to database: {}", api_response.id, e);
+        }
+    }
+    response
+}
+
+/// One line per eval for the terminal, e.g. `✅ passed ollama:llama3 (812ms)`.
+fn summary_line(response: &EvalResponse) -> String {
+    let icon = match response.status.as_str() {
+        "passed" => "✅",
+        "failed" | "error" | "blocked" => "❌",
+        _ => "⚠️ ",
+    };
+    match (&response.result, &response.error) {
+        (Some(result), _) => format!("{} {} {} ({}ms)", icon, response.status, result.model, result.latency_ms),
+        (None, error) => format!("{} {}: {}", icon, response.status, error.as_deref().unwrap_or("unknown error")),
+    }
+}
+
+/// `entries` as an aligned table, with prompts cut to one short line.
+pub fn history_table(entries: &[HistoryEntry]) -> String {
+    let header = ["CREATED", "ID", "STATUS", "MODEL", "LATENCY", "PROMPT"].map(str::to_string);
+    let rows: Vec<[String; 6]> = entries
+        .iter()
+        .map(|entry| {
+            let prompt = entry.prompt.as_deref().unwrap_or("").split_whitespace().collect::<Vec<_>>().join(" ");
+            let prompt = if prompt.chars().count() > PROMPT_COLUMN_CHARS {
+                format!("{}…", prompt.chars().take(PROMPT_COLUMN_CHARS).collect::<String>().trim_end())
+            } else {
+                prompt
+            };
+            [
+                entry.created_at.chars().take(19).collect(),
+                entry.id.chars().take(8).collect(),
+                entry.status.clone().unwrap_or_default(),
+                entry.model.clone().unwrap_or_default(),
+                entry.latency_ms.map(|ms| format!("{}ms", ms)).unwrap_or_default(),
+                prompt,
+            ]
+        })
+        .collect();
+
+    let mut widths = header.clone().map(|cell| cell.chars().count());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let mut table = String::new();
+    for row in std::iter::once(&header).chain(&rows) {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{}{}", cell, " ".repeat(width - cell.chars().count())))
+            .collect();
+        table.push_str(cells.join("  ").trim_end());
+        table.push('\n');
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AppConfig, OllamaConfig};
+    use std::sync::Arc;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_exit_codes_gate_on_failures_and_errors() {
+        assert_eq!(Exit::for_statuses([]), Exit::Passed);
+        assert_eq!(Exit::for_statuses(["passed", "uncertain", "needs_review", "completed"]), Exit::Passed);
+        assert_eq!(Exit::for_statuses(["passed", "failed"]), Exit::Failed);
+        assert_eq!(Exit::for_statuses(["error", "failed"]), Exit::Errored);
+        assert_eq!(Exit::for_statuses(["failed", "error", "passed"]), Exit::Errored);
+        assert_eq!(Exit::for_statuses(["passed", "judge_error"]), Exit::Errored);
+        assert_eq!([Exit::Passed, Exit::Failed, Exit::Invalid, Exit::Errored].map(|exit| exit as u8), [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_eval_files_are_read_as_json_json_lines_or_toml() {
+        let one = parse_evals(r#"{"model": "ollama:llama3", "prompt": "What is 2+2?"}"#, FileFormat::Json).unwrap();
+        assert_eq!(one.len(), 1);
+        assert_eq!(one[0].prompt, "What is 2+2?");
+
+        let many = parse_evals(r#"[{"model": "a:x", "prompt": "1"}, {"model": "b:y", "prompt": "2"}]"#, FileFormat::Json);
+        assert_eq!(many.unwrap().iter().map(|e| e.model.as_str()).collect::<Vec<_>>(), ["a:x", "b:y"]);
+        let broken = parse_evals(r#"[{"model": "a:x", "prompt": "1"}, {"prompt": "2"}]"#, FileFormat::Json);
+        assert!(broken.unwrap_err().starts_with("[1]: missing field `model`"));
+
+        let lines = "{\"model\": \"a:x\", \"prompt\": \"1\"}\n\n{\"model\": \"b:y\", \"prompt\": \"2\", \"tags\": [\"ci\"]}\n";
+        let lines = parse_evals(lines, FileFormat::JsonLines).unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1].tags, ["ci"]);
+        assert!(parse_evals("{\"prompt\": \"1\"}\nnot json", FileFormat::JsonLines).unwrap_err().starts_with("line 1:"));
+
+        let single = parse_evals("model = \"ollama:llama3\"\nprompt = \"What is 2+2?\"\nexpected = \"4\"\n", FileFormat::Toml).unwrap();
+        assert_eq!(single[0].expected.as_deref(), Some("4"));
+        let table = "[[evals]]\nmodel = \"a:x\"\nprompt = \"1\"\n\n[[evals]]\nmodel = \"b:y\"\nprompt = \"2\"\n";
+        assert_eq!(parse_evals(table, FileFormat::Toml).unwrap().len(), 2);
+
+        assert_eq!(FileFormat::of(Path::new("evals.TOML")), FileFormat::Toml);
+        assert_eq!(FileFormat::of(Path::new("evals.jsonl")), FileFormat::JsonLines);
+        assert_eq!(FileFormat::of(Path::new("evals.json")), FileFormat::Json);
+    }
+
+    #[test]
+    fn test_arguments_are_parsed_and_checked() {
+        let cli = Cli::try_parse_from(["evaluate"]).unwrap();
+        assert!(cli.command.is_none());
+
+        let cli = Cli::try_parse_from(["evaluate", "run", "--model", "ollama:llama3", "--prompt", "Hi", "--tag", "ci"]).unwrap();
+        let Some(Command::Run(args)) = cli.command else { panic!("expected run") };
+        let eval = args.eval_config().unwrap();
+        assert_eq!((eval.model.as_str(), eval.prompt.as_str(), eval.tags.as_slice()), ("ollama:llama3", "Hi", &["ci".to_string()][..]));
+
+        // Usage errors exit with 2, like an invalid eval file
+        let missing_prompt = Cli::try_parse_from(["evaluate", "run", "--model", "ollama:llama3"]).unwrap_err();
+        assert_eq!(missing_prompt.exit_code(), Exit::Invalid as i32);
+        assert!(Cli::try_parse_from(["evaluate", "run", "--file", "e.json", "--model", "a:x"]).is_err());
+        assert!(Cli::try_parse_from(["evaluate", "batch", "--file", "e.json", "--concurrency", "0"]).is_err());
+        let cli = Cli::try_parse_from(["evaluate", "history", "--format", "json", "--limit", "5"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::History(HistoryArgs { limit: 5, format: HistoryFormat::Json, .. }))));
+    }
+
+    #[test]
+    fn test_history_table_aligns_columns() {
+        let entries = vec![
+            HistoryEntry {
+                id: "0123456789abcdef".to_string(),
+                status: Some("passed".to_string()),
+                model: Some("ollama:llama3".to_string()),
+                prompt: Some(format!("Summarize\n{}", "word ".repeat(20))),
+                latency_ms: Some(812),
+                created_at: "2025-01-01T10:00:00.123+00:00".to_string(),
+                ..Default::default()
+            },
+            HistoryEntry {
+                id: "fedcba98".to_string(),
+                status: Some("error".to_string()),
+                model: Some("openai:gpt-4o".to_string()),
+                prompt: Some("Hi".to_string()),
+                created_at: "2025-01-01T09:00:00+00:00".to_string(),
+                ..Default::default()
+            },
+        ];
+        let table = history_table(&entries);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines[0], "CREATED              ID        STATUS  MODEL          LATENCY  PROMPT");
+        assert_eq!(lines[1], "2025-01-01T10:00:00  01234567  passed  ollama:llama3  812ms    Summarize word word word word word word…");
+        assert_eq!(lines[2], "2025-01-01T09:00:00  fedcba98  error   openai:gpt-4o           Hi");
+    }
+
+    #[tokio::test]
+    async fn test_batch_writes_json_lines_saves_them_and_exits_failed() {
+        // The model's answer and the judge's verdict both come from this stand-in
+        let ollama = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"response": "Verdict: FAIL", "done": true})))
+            .mount(&ollama)
+            .await;
+        let state = AppState {
+            config: Arc::new(AppConfig {
+                ollama: Some(OllamaConfig { api_base: ollama.uri(), models: vec!["llama3".to_string()] }),
+                ..Default::default()
+            }),
+            client: reqwest::Client::new(),
+            db_pool: Some(Arc::new(database::test_pool().await)),
+            backup_in_progress: Default::default(),
+            active_batches: Default::default(),
+            retention: Default::default(),
+            rate_limiter: Default::default(),
+            provider_health: Default::default(),
+            progress: Default::default(),
+        };
+
+        let dir = std::env::temp_dir().join(format!("evaluate-cli-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("evals.jsonl");
+        let output = dir.join("results.jsonl");
+        let eval = r#"{"model": "ollama:llama3", "prompt": "What is 2+2?", "expected": "4", "judge_model": "ollama:llama3"}"#;
+        std::fs::write(&file, format!("{}\n{}\n", eval, eval)).unwrap();
+
+        let report = dir.join("report.md");
+        let args = BatchArgs { file: file.clone(), concurrency: 2, output: Some(output.clone()), report: Some(report.clone()), no_save: false };
+        assert_eq!(execute(Command::Batch(args), state.clone()).await, Exit::Failed);
+
+        let results: Vec<serde_json::Value> = std::fs::read_to_string(&output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r["status"] == "failed" && r["result"]["judge_result"]["verdict"] == "Fail"));
+        let saved = database::get_evaluations(state.db_pool.as_ref().unwrap(), &HistoryFilter::default()).await.unwrap();
+        assert_eq!(saved.len(), 2);
+        assert!(saved.iter().all(|entry| entry.batch_id.is_some()));
+        let report = std::fs::read_to_string(&report).unwrap();
+        assert!(report.contains("| ollama:llama3 | 2 | 0 | 0.0% |") && report.contains("## Failures (2)"), "{}", report);
+
+        std::fs::write(&file, r#"{"model": "nowhere:x", "prompt": ""}"#).unwrap();
+        let args = BatchArgs { file: file.clone(), concurrency: 1, output: Some(output), report: None, no_save: false };
+        assert_eq!(execute(Command::Batch(args), state).await, Exit::Invalid);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,320 @@
+// src/compare.rs
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use crate::database::HistoryEntry;
+
+/// One eval matched across two runs by its config hash.
+#[derive(Debug, Serialize)]
+pub struct EvalDiff {
+    pub config_hash: String,
+    pub model: Option<String>,
+    pub prompt: Option<String>,
+    pub evaluation_a: String,
+    pub evaluation_b: String,
+    pub status_a: Option<String>,
+    pub status_b: Option<String>,
+    pub verdict_a: Option<String>,
+    pub verdict_b: Option<String>,
+    /// `b - a`; `None` when either run lacks the measurement.
+    pub latency_delta_ms: Option<i64>,
+    pub input_tokens_delta: Option<i64>,
+    pub output_tokens_delta: Option<i64>,
+}
+
+/// Evals of one run that share a config hash; only the first was compared.
+#[derive(Debug, Serialize)]
+pub struct DuplicateEvals {
+    pub config_hash: String,
+    pub prompt: Option<String>,
+    /// In run order, the compared one first.
+    pub evaluation_ids: Vec<String>,
+}
+
+/// An eval present in only one of the two runs.
+#[derive(Debug, Serialize)]
+pub struct UnmatchedEval {
+    pub config_hash: String,
+    pub evaluation_id: String,
+    pub model: Option<String>,
+    pub prompt: Option<String>,
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RunComparison {
+    pub run_a: String,
+    pub run_b: String,
+    /// Passed in run A, not passed in run B.
+    pub regressions: Vec<EvalDiff>,
+    /// Not passed in run A, passed in run B.
+    pub improvements: Vec<EvalDiff>,
+    pub unchanged: Vec<EvalDiff>,
+    /// Only in run B.
+    pub new: Vec<UnmatchedEval>,
+    /// Only in run A.
+    pub removed: Vec<UnmatchedEval>,
+    pub judge_prompt_versions_a: Vec<i64>,
+    pub judge_prompt_versions_b: Vec<i64>,
+    /// The runs were judged with different judge prompt versions, so verdict flips
+    /// may come from the judge rather than the model.
+    pub judge_prompt_versions_differ: bool,
+    /// Configs that ran more than once in run A or B.
+    pub duplicates_a: Vec<DuplicateEvals>,
+    pub duplicates_b: Vec<DuplicateEvals>,
+}
+
+/// Compares two runs eval by eval, matching on `config_hash`.
+/// Evaluations saved without a hash are ignored; if a config appears more than once
+/// in a run, its first evaluation is used and the rest are listed in `duplicates_a` or
+/// `duplicates_b`.
+pub fn compare_runs(run_a: &str, a: &[HistoryEntry], run_b: &str, b: &[HistoryEntry]) -> RunComparison {
+    let index_b = index_by_hash(b);
+    let index_a = index_by_hash(a);
+
+    let mut comparison = RunComparison {
+        run_a: run_a.to_string(),
+        run_b: run_b.to_string(),
+        regressions: Vec::new(),
+        improvements: Vec::new(),
+        unchanged: Vec::new(),
+        new: Vec::new(),
+        removed: Vec::new(),
+        judge_prompt_versions_a: judge_prompt_versions(a),
+        judge_prompt_versions_b: judge_prompt_versions(b),
+        judge_prompt_versions_differ: false,
+        duplicates_a: duplicates(a),
+        duplicates_b: duplicates(b),
+    };
+    comparison.judge_prompt_versions_differ =
+        comparison.judge_prompt_versions_a != comparison.judge_prompt_versions_b;
+
+    for (hash, entry_a) in first_per_hash(a) {
+        match index_b.get(hash) {
+            Some(entry_b) => {
+                let passed_a = is_pass(entry_a);
+                let passed_b = is_pass(entry_b);
+                let diff = diff(hash, entry_a, entry_b);
+                match (passed_a, passed_b) {
+                    (true, false) => comparison.regressions.push(diff),
+                    (false, true) => comparison.improvements.push(diff),
+                    _ => comparison.unchanged.push(diff),
+                }
+            }
+            None => comparison.removed.push(unmatched(hash, entry_a)),
+        }
+    }
+
+    for (hash, entry_b) in first_per_hash(b) {
+        if !index_a.contains_key(hash) {
+            comparison.new.push(unmatched(hash, entry_b));
+        }
+    }
+
+    comparison
+}
+
+/// Entries with a config hash, first occurrence of each hash only, in run order.
+fn first_per_hash(entries: &[HistoryEntry]) -> Vec<(&str, &HistoryEntry)> {
+    let mut seen = BTreeSet::new();
+    entries
+        .iter()
+        .filter_map(|e| e.config_hash.as_deref().map(|h| (h, e)))
+        .filter(|(h, _)| seen.insert(*h))
+        .collect()
+}
+
+fn index_by_hash(entries: &[HistoryEntry]) -> HashMap<&str, &HistoryEntry> {
+    first_per_hash(entries).into_iter().collect()
+}
+
+/// Hashes with more than one evaluation, in the run order of their first.
+fn duplicates(entries: &[HistoryEntry]) -> Vec<DuplicateEvals> {
+    let mut ids: HashMap<&str, Vec<String>> = HashMap::new();
+    for entry in entries {
+        if let Some(hash) = entry.config_hash.as_deref() {
+            ids.entry(hash).or_default().push(entry.id.clone());
+        }
+    }
+    first_per_hash(entries)
+        .into_iter()
+        .filter_map(|(hash, first)| {
+            let evaluation_ids = ids.remove(hash).filter(|ids| ids.len() > 1)?;
+            Some(DuplicateEvals { config_hash: hash.to_string(), prompt: first.prompt.clone(), evaluation_ids })
+        })
+        .collect()
+}
+
+fn judge_prompt_versions(entries: &[HistoryEntry]) -> Vec<i64> {
+    entries
+        .iter()
+        .filter_map(|e| e.judge_prompt_version)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+fn is_pass(entry: &HistoryEntry) -> bool {
+    entry.status.as_deref() == Some("passed")
+}
+
+fn delta(a: Option<i64>, b: Option<i64>) -> Option<i64> {
+    Some(b? - a?)
+}
+
+fn diff(hash: &str, a: &HistoryEntry, b: &HistoryEntry) -> EvalDiff {
+    EvalDiff {
+        config_hash: hash.to_string(),
+        model: b.model.clone().or_else(|| a.model.clone()),
+        prompt: b.prompt.clone().or_else(|| a.prompt.clone()),
+        evaluation_a: a.id.clone(),
+        evaluation_b: b.id.clone(),
+        status_a: a.status.clone(),
+        status_b: b.status.clone(),
+        verdict_a: a.judge_verdict.clone(),
+        verdict_b: b.judge_verdict.clone(),
+        latency_delta_ms: delta(a.latency_ms, b.latency_ms),
+        input_tokens_delta: delta(a.input_tokens, b.input_tokens),
+        output_tokens_delta: delta(a.output_tokens, b.output_tokens),
+    }
+}
+
+fn unmatched(hash: &str, entry: &HistoryEntry) -> UnmatchedEval {
+    UnmatchedEval {
+        config_hash: hash.to_string(),
+        evaluation_id: entry.id.clone(),
+        model: entry.model.clone(),
+        prompt: entry.prompt.clone(),
+        status: entry.status.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, hash: &str, status: &str, latency_ms: i64, judge_prompt_version: i64) -> HistoryEntry {
+        HistoryEntry {
+            id: id.to_string(),
+            status: Some(status.to_string()),
+            model: Some("ollama:llama3".to_string()),
+            prompt: Some(format!("prompt {}", hash)),
+            model_output: None,
+            expected: None,
+            judge_model: None,
+            judge_verdict: None,
+            judge_reasoning: None,
+            error_message: None,
+            latency_ms: Some(latency_ms),
+            judge_latency_ms: None,
+            input_tokens: Some(10),
+            output_tokens: None,
+            judge_input_tokens: None,
+            judge_output_tokens: None,
+            created_at: "2026-01-01T00:00:00+00:00".to_string(),
+            judge_prompt_version: Some(judge_prompt_version),
+            prompt_version: None,
+            prompt_template: None,
+            judge_confidence: None,
+            needs_review: false,
+            human_verdict: None,
+            reviewed_at: None,
+            cost_usd: None,
+            judge_cost_usd: None,
+            batch_id: None,
+            config_hash: Some(hash.to_string()),
+            judge_raw_response: None,
+            truncation_strategy: None,
+            truncation_original_tokens: None,
+            truncation_truncated_tokens: None,
+            judge_prompt_arm: None,
+            tags: Vec::new(),
+            uncertain_reason: None,
+            judge_batch_size: None,
+            finish_reason: None,
+            trace: None,
+            failure_category: None,
+            failure_justification: None,
+            failure_classifier: None,
+            parsed_output: None,
+            parse_method: None,
+            output_diff: None,
+            chain_id: None,
+            chain_step: None,
+            wait_ms: None,
+            provider_override: None,
+            output_chars: None,
+            output_truncated: false,
+            criteria: None,
+            metadata: None,
+            preset: None,
+            dataset_id: None,
+            dataset_row_index: None,
+            expected_source: None,
+            resolved_model: None,
+            response_format: None,
+            reasoning: None,
+            images: Vec::new(),
+            judge_prompt_rendered: None,
+            model_alias: None,
+            judge_params: None,
+            judge_error: None,
+            self_consistency: None,
+        }
+    }
+
+    #[test]
+    fn test_compare_runs_buckets_evals() {
+        let a = vec![
+            entry("a1", "h1", "passed", 100, 1),
+            entry("a2", "h2", "failed", 100, 1),
+            entry("a3", "h3", "passed", 100, 1),
+            entry("a4", "h4", "passed", 100, 1),
+        ];
+        let b = vec![
+            entry("b1", "h1", "error", 150, 1),
+            entry("b2", "h2", "passed", 80, 1),
+            entry("b3", "h3", "passed", 100, 1),
+            entry("b5", "h5", "passed", 100, 1),
+        ];
+
+        let comparison = compare_runs("run-a", &a, "run-b", &b);
+
+        assert_eq!(comparison.regressions.len(), 1);
+        assert_eq!(comparison.regressions[0].evaluation_b, "b1");
+        assert_eq!(comparison.regressions[0].latency_delta_ms, Some(50));
+        assert_eq!(comparison.regressions[0].input_tokens_delta, Some(0));
+        assert_eq!(comparison.regressions[0].output_tokens_delta, None);
+        assert_eq!(comparison.improvements[0].latency_delta_ms, Some(-20));
+        assert_eq!(comparison.unchanged.len(), 1);
+        assert_eq!(comparison.removed[0].evaluation_id, "a4");
+        assert_eq!(comparison.new[0].evaluation_id, "b5");
+        assert!(!comparison.judge_prompt_versions_differ);
+        assert!(comparison.duplicates_a.is_empty() && comparison.duplicates_b.is_empty());
+    }
+
+    #[test]
+    fn test_compare_runs_lists_evals_sharing_a_hash() {
+        let a = vec![entry("a1", "h1", "passed", 100, 1), entry("a2", "h1", "failed", 100, 1), entry("a3", "h2", "passed", 100, 1)];
+        let b = vec![entry("b1", "h1", "passed", 100, 1), entry("b2", "h2", "passed", 100, 1)];
+
+        let comparison = compare_runs("run-a", &a, "run-b", &b);
+
+        assert_eq!(comparison.unchanged.len(), 2);
+        assert_eq!(comparison.duplicates_a.len(), 1);
+        assert_eq!(comparison.duplicates_a[0].config_hash, "h1");
+        assert_eq!(comparison.duplicates_a[0].evaluation_ids, ["a1", "a2"]);
+        assert!(comparison.duplicates_b.is_empty());
+    }
+
+    #[test]
+    fn test_compare_runs_flags_judge_prompt_change() {
+        let a = vec![entry("a1", "h1", "passed", 100, 1)];
+        let b = vec![entry("b1", "h1", "failed", 100, 2)];
+
+        let comparison = compare_runs("run-a", &a, "run-b", &b);
+
+        assert_eq!(comparison.regressions.len(), 1);
+        assert!(comparison.judge_prompt_versions_differ);
+        assert_eq!(comparison.judge_prompt_versions_b, vec![2]);
+    }
+}
@@ -1,7 +1,41 @@
 // src/config.rs
-use serde::Deserialize;
-use regex::Regex;
+use serde::{Deserialize, Serialize};
+use reqwest::header::HeaderMap;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use crate::diff::{self, DiffRule};
+use crate::drift::DriftThresholds;
 use crate::errors::{Result, EvalError};
+use crate::failure_taxonomy::FailureClassifier;
+use crate::config_file::SuiteDefinition;
+use crate::format_rule::FormatRule;
+use crate::http_client::{parse_extra_headers, HttpClientConfig};
+use crate::judge_prompt_cache::JudgePromptCache;
+use crate::match_rule::MatchRule;
+use crate::models::FieldError;
+use crate::pricing::PricingTable;
+use crate::provider_override::{self, ProviderOverrides, SuppliedBy};
+use crate::providers::images::{Image, MAX_IMAGES, MAX_IMAGE_BYTES};
+use crate::providers::response_format::ResponseFormat;
+use crate::providers::anthropic::{self, MIN_THINKING_BUDGET};
+use crate::providers::capture::CAPTURE_RAW_ENV;
+use crate::providers::{GenerationParams, ThinkingParams};
+use crate::providers::middleware::RetryPolicy;
+use crate::providers::rate_limit::{ProviderLimiters, ProviderRateLimit};
+use crate::retention::RetentionPolicy;
+use crate::self_consistency::{self, SelfConsistency};
+use crate::share::DEFAULT_SHARE_TTL_HOURS;
+use crate::truncation::TruncationStrategy;
+
+/// How long cached responses are reused when `CACHE_TTL_SECONDS` is unset: a day.
+const DEFAULT_CACHE_TTL_SECONDS: u64 = 24 * 60 * 60;
+
+/// Largest JSON request body when `MAX_BODY_BYTES` is unset: 16 MiB.
+pub const DEFAULT_MAX_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+/// Characters of model output saved when `MAX_STORED_OUTPUT_CHARS` is unset.
+const DEFAULT_MAX_STORED_OUTPUT_CHARS: usize = 100_000;
 
 /// Configuration for the Anthropic provider.
 #[derive(Debug, Clone)]
@@ -17,6 +51,28 @@ pub struct GeminiConfig {
     pub api_base: String,
     pub api_key: String,
     pub models: Vec<String>,
+    /// Threshold sent for every harm category, unless an eval overrides it.
+    pub safety_threshold: SafetyThreshold,
+}
+
+/// Configuration for the Groq provider.
+#[derive(Debug, Clone)]
+pub struct GroqConfig {
+    pub api_base: String,
+    pub api_key: String,
+    pub models: Vec<String>,
+}
+
+/// Configuration for the OpenRouter provider.
+#[derive(Debug, Clone)]
+pub struct OpenRouterConfig {
+    pub api_base: String,
+    pub api_key: String,
+    pub models: Vec<String>,
+    /// Sent as `HTTP-Referer`, identifying the app to OpenRouter.
+    pub referer: String,
+    /// Sent as `X-Title`, the app name shown in OpenRouter's rankings.
+    pub title: String,
 }
 
 /// Configuration for the Ollama provider.
@@ -26,35 +82,123 @@ pub struct OllamaConfig {
     pub models: Vec<String>,
 }
 
-/// Configuration for the OpenAI provider.
-#[derive(Debug, Clone)]
+/// Configuration for the OpenAI provider, and for OpenAI-compatible servers.
+#[derive(Debug, Clone, PartialEq)]
 pub struct OpenAIConfig {
     pub api_base: String,
+    /// Sent as a bearer token; empty for servers that need no key.
     pub api_key: String,
     pub models: Vec<String>,
 }
 
 /// High-level application configuration loaded from environment variables.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct AppConfig {
     pub anthropic: Option<AnthropicConfig>,
     pub gemini: Option<GeminiConfig>,
+    pub groq: Option<GroqConfig>,
     pub ollama: Option<OllamaConfig>,
     pub openai: Option<OpenAIConfig>,
+    pub openrouter: Option<OpenRouterConfig>,
+    /// OpenAI-compatible servers (vLLM, LM Studio, llamafile, ...) by the provider name their
+    /// models are addressed with, e.g. `vllm` for `vllm:qwen2.5-7b`.
+    pub openai_compat: BTreeMap<String, OpenAIConfig>,
     pub models: Vec<String>,
+    /// Names that eval configs may give instead of a `provider:model`, e.g. `fast` for
+    /// `gemini:gemini-2.0-flash`, from `MODEL_ALIASES`. A target may be another alias.
+    pub model_aliases: BTreeMap<String, String>,
+    /// Default minimum judge confidence; verdicts below it are routed to human review.
+    pub min_judge_confidence: Option<f32>,
+    /// Per-model token prices used for cost tracking.
+    pub pricing: PricingTable,
+    /// Directory database snapshots are written to.
+    pub backup_dir: std::path::PathBuf,
+    /// Number of database snapshots kept; older ones are pruned after each backup.
+    pub backup_retention: usize,
+    /// Retries applied to rate-limited, server-side and connection failures of provider calls.
+    pub provider_retry: RetryPolicy,
+    /// Admin key that turns on API key authentication; the API is open when unset.
+    pub admin_api_key: Option<String>,
+    /// Most evals of a batch judged together in one judge call (experimental); `None` judges
+    /// every eval with a call of its own.
+    pub judge_batch_size: Option<usize>,
+    /// Default model that writes paraphrases for robustness runs.
+    pub paraphrase_model: Option<String>,
+    /// Judge of evals that have an expected output but no `judge_model` of their own.
+    pub default_judge_model: Option<String>,
+    /// Generation settings of judge calls, from `JUDGE_TEMPERATURE`, `JUDGE_MAX_TOKENS`,
+    /// `JUDGE_SEED` and `JUDGE_RETRY_UNCERTAIN`; evals may override them.
+    pub judge_params: JudgeParams,
+    /// Store the traces of traced evals with them; otherwise traces are only returned.
+    pub persist_traces: bool,
+    /// Capture the raw provider exchanges of every eval, from `EVAL_CAPTURE_RAW`; otherwise
+    /// only evals with `capture_raw` set are captured.
+    pub capture_raw: bool,
+    /// Who puts failed evals in a failure category; `None` leaves failures unclassified.
+    pub failure_classifier: Option<FailureClassifier>,
+    /// Secret share links are signed with; sharing is disabled when unset.
+    pub share_signing_secret: Option<String>,
+    /// Default lifetime of a share link.
+    pub share_ttl_hours: u64,
+    /// Names or ids of API keys limited to aggregate statistics; see `routes::AGGREGATE_ROUTES`.
+    pub aggregate_only_keys: Vec<String>,
+    /// Purging of old evaluations by a background task; `None` keeps evaluations forever.
+    pub retention: Option<RetentionPolicy>,
+    /// Single-eval runs a client may start per minute; `None` leaves them unlimited.
+    pub rate_limit_per_minute: Option<u32>,
+    /// Batches and matrices a client may start per minute; `None` leaves them unlimited.
+    pub batch_rate_limit_per_minute: Option<u32>,
+    /// Secret webhook payloads are signed with; `callback_url`s are refused when unset.
+    pub webhook_secret: Option<String>,
+    /// Slack incoming webhook that batches with failing evals are reported to.
+    pub slack_webhook_url: Option<String>,
+    /// Batches with failures and a pass rate below this (0.0-1.0) are reported to notifiers.
+    pub notify_min_pass_rate: f64,
+    /// Week-over-week change of a model's output length or pass rate that counts as drift.
+    pub drift_thresholds: DriftThresholds,
+    /// Characters of model output sent in WebSocket `eval_completed` events.
+    pub ws_max_output_chars: usize,
+    /// Age after which cached responses are no longer reused; `None` keeps them until cleared.
+    pub cache_ttl_seconds: Option<u64>,
+    /// Largest JSON request body the API reads; larger ones are answered `413`.
+    pub max_body_bytes: usize,
+    /// Characters of model output saved with an evaluation; longer outputs are cut, and the
+    /// judge still gets them whole. `None` saves outputs whole.
+    pub max_stored_output_chars: Option<usize>,
+    /// Request and token limits per provider. Clones of the config share the limiters, so every
+    /// eval, judge call and batch of the server queues on the same ones.
+    pub provider_limiters: Arc<ProviderLimiters>,
+    /// The active judge prompt and canary, loaded once and shared by clones of the config
+    /// like the limiters; the judge prompt handlers invalidate it.
+    pub judge_prompts: Arc<JudgePromptCache>,
+    /// Headers sent with every request of a provider, by provider name, from `<PROVIDER>_EXTRA_HEADERS`.
+    pub extra_headers: BTreeMap<String, HeaderMap>,
+    /// Proxy, CA bundle and timeout of the HTTP client shared by the providers.
+    pub http_client: HttpClientConfig,
+    /// Suites of the config file, registered by name when the server starts.
+    pub suites: Vec<SuiteDefinition>,
+    /// Provider keys an eval supplied for itself, scrubbed from provider errors. Only set on
+    /// the copies `provider_override::apply` makes for such evals.
+    pub request_secrets: Vec<String>,
 }
 
 /// Contains all the information needed to run one prompt against a model
 /// The model string is expected to be in the format `provider:model_name`,
 /// e.g., `anthropic:claude-sonnet-4`, `gemini:gemini-1.5-flash`, `ollama:llama3`, or `openai:gpt-4`.
 /// If no provider is specified, it will default to `gemini`.
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct EvalConfig {
     /// The model to evaluate
     pub model: String,
     
-    /// The prompt to send to the model
+    /// The prompt to send to the model; left out when `prompt_version` supplies it
+    #[serde(default)]
     pub prompt: String,
+
+    /// Prompt version whose template is used as the prompt, rendered with `metadata` (optional,
+    /// instead of `prompt`). The evaluation is linked to the version for its stats.
+    #[serde(default)]
+    pub prompt_version: Option<i64>,
     
     /// Expected output for comparison (optional)
     #[serde(default)]
@@ -76,18 +220,277 @@ pub struct EvalConfig {
     /// Metadata for the eval
     #[serde(default)]
     pub metadata: Option<serde_json::Value>,
+
+    /// Fail-fast format rule checked against the model output (optional)
+    /// A violation records a Fail without invoking the judge.
+    #[serde(default)]
+    pub format_rule: Option<FormatRule>,
+
+    /// Stream the model output so a format rule violation can abort generation early.
+    /// Providers without streaming support ignore this.
+    #[serde(default)]
+    pub stream: bool,
+
+    /// Minimum judge confidence to auto-accept a verdict (optional, overrides the global default)
+    /// Verdicts below it are stored as "needs_review".
+    #[serde(default)]
+    pub min_judge_confidence: Option<f32>,
+
+    /// Truncate a metadata field that would overflow the model's context window (optional)
+    /// One of `head`, `tail`, `middle_out` or `sentence_boundary`.
+    #[serde(default)]
+    pub truncation: Option<TruncationStrategy>,
+
+    /// Metadata field the truncation strategy applies to (defaults to `context`)
+    #[serde(default)]
+    pub truncate_field: Option<String>,
+
+    /// Context window of the model in tokens (optional, overrides the built-in lookup)
+    #[serde(default)]
+    pub context_window: Option<u32>,
+
+    /// Verdict to record without invoking the judge when the output was cut off at the
+    /// output token limit (optional). One of `fail` or `uncertain`; judged evals default to
+    /// `uncertain`.
+    #[serde(default)]
+    pub on_length_limit: Option<LengthLimitPolicy>,
+
+    /// Record a timestamped trace of the eval's phases in the result
+    #[serde(default)]
+    pub trace: bool,
+
+    /// Leave a failed eval unclassified even when a failure classifier is configured,
+    /// saving the classification call
+    #[serde(default)]
+    pub skip_failure_classification: bool,
+
+    /// Queue the eval for human review whatever its verdict, as for reference outputs
+    /// generated without a judge
+    #[serde(default)]
+    pub require_review: bool,
+
+    /// Keep the raw provider requests and responses of the eval for debugging, as
+    /// `EVAL_CAPTURE_RAW` does for every eval; see `GET /api/v1/evals/{id}/raw`
+    #[serde(default)]
+    pub capture_raw: bool,
+
+    /// Reuse the cached response to an identical provider, model and prompt instead of
+    /// calling the model, and cache the response when there is none
+    #[serde(default)]
+    pub cache: bool,
+
+    /// Cache judge responses as well; they are never cached otherwise
+    #[serde(default)]
+    pub cache_judge: bool,
+
+    /// Gemini safety threshold for this eval (optional, overrides `GEMINI_SAFETY_THRESHOLD`)
+    /// Other providers ignore it.
+    #[serde(default)]
+    pub safety_threshold: Option<SafetyThreshold>,
+
+    /// Ask the model for JSON, or JSON matching a schema (optional). Providers without a
+    /// JSON mode are asked in the prompt.
+    #[serde(default)]
+    pub response_format: Option<ResponseFormat>,
+
+    /// Sequences that end generation when the model writes them (optional)
+    #[serde(default)]
+    pub stop: Option<Vec<String>>,
+
+    /// Most tokens the model may generate (optional, defaults to the provider's limit)
+    #[serde(default)]
+    pub max_output_tokens: Option<u32>,
+
+    /// Extended thinking before the answer, `{"budget_tokens": 2048}` (optional). Anthropic
+    /// only; other providers ignore it.
+    #[serde(default)]
+    pub thinking: Option<ThinkingParams>,
+
+    /// Images sent along with the prompt, as `http(s)` URLs or base64 `data:` URIs (optional).
+    /// Inline images are saved with the evaluation by hash and size only.
+    #[serde(default)]
+    pub images: Vec<String>,
+
+    /// Show the judge the model's reasoning before its answer; by default it sees the answer only
+    #[serde(default)]
+    pub include_reasoning: bool,
+
+    /// Leave `{{key}}` placeholders the metadata cannot fill in the prompt as they are,
+    /// instead of failing the eval
+    #[serde(default)]
+    pub allow_missing_vars: bool,
+
+    /// Temperature, max tokens, seed and Uncertain retry of the judge call (optional, each
+    /// overriding the global `JUDGE_*` setting)
+    #[serde(default)]
+    pub judge_params: Option<JudgeParams>,
+
+    /// How the output is compared with `expected` before the judge is called (optional,
+    /// `normalized` by default). A match records a Pass without a judge call.
+    #[serde(default, rename = "match")]
+    pub match_rule: Option<MatchRule>,
+
+    /// How a JSON `expected` value is diffed with the parsed output (optional). With
+    /// `verdict` set the diff decides the verdict instead of the judge.
+    #[serde(default, rename = "diff")]
+    pub diff_rule: Option<DiffRule>,
+
+    /// Generate several outputs for the prompt and judge each, `{"samples": 5, "strategy":
+    /// "majority"}` (optional). The eval's verdict is that of the samples under the strategy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub self_consistency: Option<SelfConsistency>,
+
+    /// Keys and base URLs of providers used instead of the configured ones, for this eval
+    /// only (optional). Never serialized, so never saved, hashed or queued.
+    #[serde(default, skip_serializing)]
+    pub provider_overrides: ProviderOverrides,
+
+    /// The API key whose request supplied `provider_overrides`, saved with the evaluation.
+    /// Set by the server, never read from a request.
+    #[serde(skip)]
+    pub overrides_supplied_by: Option<SuppliedBy>,
+
+    /// Preset the request was merged with, saved with the evaluation. Set by the server
+    /// after the merge, never read from a request.
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub preset: Option<crate::preset::PresetApplication>,
+
+    /// Dataset row the eval was expanded from, saved with the evaluation. Set by the server,
+    /// never read from a request.
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub dataset_row: Option<crate::dataset::DatasetRowRef>,
+
+    /// Chain and step the eval runs as, saved with the evaluation. Set by the server, never
+    /// read from a request.
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub chain_step: Option<crate::chain::ChainStepRef>,
+}
+
+/// Generation settings of judge calls. An eval's `judge_params` override the global ones field
+/// by field; see `JudgeParams::or`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JudgeParams {
+    /// Sampling temperature of the judge, from 0 to 2.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// Most tokens the judge may generate for one verdict.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    /// Sampling seed, for judges whose provider takes one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// Ask the judge once more when its verdict is Uncertain, keeping the second verdict.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_uncertain: Option<bool>,
+}
+
+/// Most tokens a judge may generate for one verdict when `JUDGE_MAX_TOKENS` is unset.
+pub const DEFAULT_JUDGE_MAX_TOKENS: u32 = 512;
+
+impl Default for JudgeParams {
+    /// Deterministic verdicts: temperature 0 and a short answer.
+    fn default() -> Self {
+        JudgeParams {
+            temperature: Some(0.0),
+            max_tokens: Some(DEFAULT_JUDGE_MAX_TOKENS),
+            seed: None,
+            retry_uncertain: None,
+        }
+    }
+}
+
+impl JudgeParams {
+    /// These params, with the fields they leave unset taken from `defaults`.
+    pub fn or(self, defaults: JudgeParams) -> JudgeParams {
+        JudgeParams {
+            temperature: self.temperature.or(defaults.temperature),
+            max_tokens: self.max_tokens.or(defaults.max_tokens),
+            seed: self.seed.or(defaults.seed),
+            retry_uncertain: self.retry_uncertain.or(defaults.retry_uncertain),
+        }
+    }
+
+    /// Whether an Uncertain verdict is asked for again.
+    pub fn retries_uncertain(&self) -> bool {
+        self.retry_uncertain.unwrap_or(false)
+    }
+
+    /// The settings sent to the judge's provider with a judge call.
+    pub fn generation_params(&self) -> GenerationParams {
+        GenerationParams {
+            temperature: self.temperature,
+            seed: self.seed,
+            max_output_tokens: self.max_tokens,
+            ..GenerationParams::default()
+        }
+    }
+}
+
+/// What to record for an output the provider cut off at the output token limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LengthLimitPolicy {
+    Fail,
+    Uncertain,
+}
+
+/// How readily Gemini blocks prompts and answers it rates as harmful, from blocking nothing
+/// to blocking anything with a low probability of harm.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SafetyThreshold {
+    Off,
+    #[default]
+    BlockNone,
+    BlockOnlyHigh,
+    BlockMediumAndAbove,
+    BlockLowAndAbove,
+}
+
+impl SafetyThreshold {
+    pub const ALL: [SafetyThreshold; 5] = [
+        SafetyThreshold::Off,
+        SafetyThreshold::BlockNone,
+        SafetyThreshold::BlockOnlyHigh,
+        SafetyThreshold::BlockMediumAndAbove,
+        SafetyThreshold::BlockLowAndAbove,
+    ];
+
+    /// The name Gemini's API uses, e.g. `BLOCK_ONLY_HIGH`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SafetyThreshold::Off => "OFF",
+            SafetyThreshold::BlockNone => "BLOCK_NONE",
+            SafetyThreshold::BlockOnlyHigh => "BLOCK_ONLY_HIGH",
+            SafetyThreshold::BlockMediumAndAbove => "BLOCK_MEDIUM_AND_ABOVE",
+            SafetyThreshold::BlockLowAndAbove => "BLOCK_LOW_AND_ABOVE",
+        }
+    }
+
+    /// Parses an API name, ignoring case.
+    pub fn parse(raw: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.as_str().eq_ignore_ascii_case(raw.trim()))
+    }
 }
 
 impl AppConfig {
     /// Load configuration from environment variables
     pub fn from_env() -> Result<Self> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// Loads configuration from the variables `lookup` returns, named as the environment
+    /// variables are.
+    pub fn from_vars(lookup: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let var = |name: &str| lookup(name).ok_or(std::env::VarError::NotPresent);
         let mut all_models = Vec::new();
         
         // Anthropic configuration
-        let anthropic_config = if let Ok(api_key) = std::env::var("ANTHROPIC_API_KEY") {
-            let api_base = std::env::var("ANTHROPIC_API_BASE")
+        let anthropic_config = if let Ok(api_key) = var("ANTHROPIC_API_KEY") {
+            let api_base = var("ANTHROPIC_API_BASE")
                 .unwrap_or_else(|_| "https://api.anthropic.com".to_string());
-            let models_str = std::env::var("ANTHROPIC_MODELS").unwrap_or_else(|_| {
+            let models_str = var("ANTHROPIC_MODELS").unwrap_or_else(|_| {
                 "claude-opus-4,claude-sonnet-4,claude-sonnet-4-5,claude-haiku-4".to_string()
             });
             let models: Vec<String> = models_str.split(',').map(|s| s.trim().to_string()).collect();
@@ -98,22 +501,46 @@ impl AppConfig {
         };
         
         // Gemini configuration
-        let gemini_config = if let Ok(api_key) = std::env::var("GEMINI_API_KEY") {
-            let api_base = std::env::var("GEMINI_API_BASE")
+        let gemini_config = if let Ok(api_key) = var("GEMINI_API_KEY") {
+            let api_base = var("GEMINI_API_BASE")
                 .unwrap_or_else(|_| "https://generativelanguage.googleapis.com".to_string());
-            let models_str = std::env::var("GEMINI_MODELS").unwrap_or_else(|_| {
+            let models_str = var("GEMINI_MODELS").unwrap_or_else(|_| {
                 "gemini-1.5-pro-latest,gemini-1.5-flash-latest".to_string()
             });
             let models: Vec<String> = models_str.split(',').map(|s| s.trim().to_string()).collect();
             all_models.extend(models.iter().map(|m| format!("gemini:{}", m)));
-            Some(GeminiConfig { api_base, api_key, models })
+            let safety_threshold = match var("GEMINI_SAFETY_THRESHOLD") {
+                Ok(raw) => SafetyThreshold::parse(&raw).ok_or_else(|| {
+                    EvalError::Config(format!(
+                        "GEMINI_SAFETY_THRESHOLD must be one of {}, got '{}'",
+                        SafetyThreshold::ALL.map(SafetyThreshold::as_str).join(", "),
+                        raw
+                    ))
+                })?,
+                Err(_) => SafetyThreshold::default(),
+            };
+            Some(GeminiConfig { api_base, api_key, models, safety_threshold })
+        } else {
+            None
+        };
+
+        // Groq configuration
+        let groq_config = if let Ok(api_key) = var("GROQ_API_KEY") {
+            let api_base = var("GROQ_API_BASE")
+                .unwrap_or_else(|_| "https://api.groq.com/openai/v1".to_string());
+            let models_str = var("GROQ_MODELS").unwrap_or_else(|_| {
+                "llama-3.3-70b-versatile".to_string()
+            });
+            let models: Vec<String> = models_str.split(',').map(|s| s.trim().to_string()).collect();
+            all_models.extend(models.iter().map(|m| format!("groq:{}", m)));
+            Some(GroqConfig { api_base, api_key, models })
         } else {
             None
         };
 
         // Ollama configuration
-        let ollama_config = if let Ok(api_base) = std::env::var("OLLAMA_API_BASE") {
-            let models_str = std::env::var("OLLAMA_MODELS").unwrap_or_else(|_| {
+        let ollama_config = if let Ok(api_base) = var("OLLAMA_API_BASE") {
+            let models_str = var("OLLAMA_MODELS").unwrap_or_else(|_| {
                 "llama3,gemma".to_string()
             });
             let models: Vec<String> = models_str.split(',').map(|s| s.trim().to_string()).collect();
@@ -124,10 +551,10 @@ impl AppConfig {
         };
 
         // OpenAI configuration
-        let openai_config = if let Ok(api_key) = std::env::var("OPENAI_API_KEY") {
-            let api_base = std::env::var("OPENAI_API_BASE")
+        let openai_config = if let Ok(api_key) = var("OPENAI_API_KEY") {
+            let api_base = var("OPENAI_API_BASE")
                 .unwrap_or_else(|_| "https://api.openai.com/v1".to_string());
-            let models_str = std::env::var("OPENAI_MODELS").unwrap_or_else(|_| {
+            let models_str = var("OPENAI_MODELS").unwrap_or_else(|_| {
                 "gpt-4o,gpt-4o-mini,gpt-3.5-turbo".to_string()
             });
             let models: Vec<String> = models_str.split(',').map(|s| s.trim().to_string()).collect();
@@ -137,79 +564,666 @@ impl AppConfig {
             None
         };
 
-        if anthropic_config.is_none() && gemini_config.is_none() && ollama_config.is_none() && openai_config.is_none() {
+        // OpenRouter configuration
+        let openrouter_config = if let Ok(api_key) = var("OPENROUTER_API_KEY") {
+            let api_base = var("OPENROUTER_API_BASE")
+                .unwrap_or_else(|_| "https://openrouter.ai/api/v1".to_string());
+            let models_str = var("OPENROUTER_MODELS").unwrap_or_else(|_| {
+                "anthropic/claude-3.5-sonnet,meta-llama/llama-3.1-70b-instruct".to_string()
+            });
+            let models: Vec<String> = models_str.split(',').map(|s| s.trim().to_string()).collect();
+            all_models.extend(models.iter().map(|m| format!("openrouter:{}", m)));
+            let referer = var("OPENROUTER_REFERER")
+                .unwrap_or_else(|_| "https://github.com/RGGH/evaluate".to_string());
+            let title = var("OPENROUTER_TITLE").unwrap_or_else(|_| "evaluate".to_string());
+            Some(OpenRouterConfig { api_base, api_key, models, referer, title })
+        } else {
+            None
+        };
+
+        // OpenAI-compatible servers
+        let openai_compat = match var("OPENAI_COMPAT_ENDPOINTS") {
+            Ok(spec) => parse_compat_endpoints(&spec, &lookup)?,
+            Err(_) => BTreeMap::new(),
+        };
+        for (name, endpoint) in &openai_compat {
+            all_models.extend(endpoint.models.iter().map(|m| format!("{}:{}", name, m)));
+        }
+
+        if anthropic_config.is_none()
+            && gemini_config.is_none()
+            && groq_config.is_none()
+            && ollama_config.is_none()
+            && openai_config.is_none()
+            && openrouter_config.is_none()
+            && openai_compat.is_empty()
+        {
             return Err(EvalError::Config(
-                "No LLM providers configured. Please set at least one of: ANTHROPIC_API_KEY, GEMINI_API_KEY, OLLAMA_API_BASE, or OPENAI_API_KEY.".to_string()
+                "No LLM providers configured. Please set at least one of: ANTHROPIC_API_KEY, GEMINI_API_KEY, GROQ_API_KEY, OLLAMA_API_BASE, OPENAI_API_KEY, OPENROUTER_API_KEY, or OPENAI_COMPAT_ENDPOINTS.".to_string()
             ));
         }
 
-        Ok(AppConfig { 
+        let min_judge_confidence = match var("MIN_JUDGE_CONFIDENCE") {
+            Ok(value) => Some(value.trim().parse::<f32>().map_err(|_| {
+                EvalError::Config(format!("MIN_JUDGE_CONFIDENCE must be a number, got '{}'", value))
+            })?),
+            Err(_) => None,
+        };
+
+        let defaults = JudgeParams::default();
+        let judge_params = JudgeParams {
+            temperature: match var("JUDGE_TEMPERATURE") {
+                Ok(value) => Some(value.trim().parse::<f32>().ok().filter(|t| (0.0..=2.0).contains(t)).ok_or_else(|| {
+                    EvalError::Config(format!("JUDGE_TEMPERATURE must be a number from 0 to 2, got '{}'", value))
+                })?),
+                Err(_) => defaults.temperature,
+            },
+            max_tokens: match var("JUDGE_MAX_TOKENS") {
+                Ok(value) => Some(value.trim().parse::<u32>().ok().filter(|tokens| *tokens >= 1).ok_or_else(|| {
+                    EvalError::Config(format!("JUDGE_MAX_TOKENS must be a positive integer, got '{}'", value))
+                })?),
+                Err(_) => defaults.max_tokens,
+            },
+            seed: match var("JUDGE_SEED") {
+                Ok(value) => Some(value.trim().parse::<u64>().map_err(|_| {
+                    EvalError::Config(format!("JUDGE_SEED must be a non-negative integer, got '{}'", value))
+                })?),
+                Err(_) => None,
+            },
+            retry_uncertain: var("JUDGE_RETRY_UNCERTAIN").ok().map(|value| value.trim().eq_ignore_ascii_case("true")),
+        };
+
+        let notify_min_pass_rate = match var("NOTIFY_MIN_PASS_RATE") {
+            Ok(value) => value
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|rate| (0.0..=1.0).contains(rate))
+                .ok_or_else(|| {
+                    EvalError::Config(format!("NOTIFY_MIN_PASS_RATE must be a number from 0 to 1, got '{}'", value))
+                })?,
+            Err(_) => 1.0,
+        };
+
+        let drift_defaults = DriftThresholds::default();
+        let drift_thresholds = DriftThresholds {
+            output_tokens: match var("DRIFT_OUTPUT_TOKENS_THRESHOLD") {
+                Ok(value) => value.trim().parse::<f64>().ok().filter(|change| *change >= 0.0).ok_or_else(|| {
+                    EvalError::Config(format!("DRIFT_OUTPUT_TOKENS_THRESHOLD must be a non-negative number, got '{}'", value))
+                })?,
+                Err(_) => drift_defaults.output_tokens,
+            },
+            pass_rate: match var("DRIFT_PASS_RATE_THRESHOLD") {
+                Ok(value) => value.trim().parse::<f64>().ok().filter(|change| (0.0..=1.0).contains(change)).ok_or_else(|| {
+                    EvalError::Config(format!("DRIFT_PASS_RATE_THRESHOLD must be a number from 0 to 1, got '{}'", value))
+                })?,
+                Err(_) => drift_defaults.pass_rate,
+            },
+            min_evaluations: match var("DRIFT_MIN_EVALUATIONS") {
+                Ok(value) => value.trim().parse::<u32>().map_err(|_| {
+                    EvalError::Config(format!("DRIFT_MIN_EVALUATIONS must be a non-negative integer, got '{}'", value))
+                })? as i64,
+                Err(_) => drift_defaults.min_evaluations,
+            },
+        };
+
+        let ws_max_output_chars = match var("WS_MAX_OUTPUT_CHARS") {
+            Ok(value) => value.trim().parse::<usize>().map_err(|_| {
+                EvalError::Config(format!("WS_MAX_OUTPUT_CHARS must be a non-negative integer, got '{}'", value))
+            })?,
+            Err(_) => 500,
+        };
+
+        let cache_ttl_seconds = match var("CACHE_TTL_SECONDS") {
+            Ok(value) => match value.trim().parse::<u64>() {
+                Ok(0) => None,
+                Ok(secs) => Some(secs),
+                Err(_) => {
+                    return Err(EvalError::Config(format!("CACHE_TTL_SECONDS must be a non-negative integer, got '{}'", value)));
+                }
+            },
+            Err(_) => Some(DEFAULT_CACHE_TTL_SECONDS),
+        };
+
+        let max_body_bytes = match var("MAX_BODY_BYTES") {
+            Ok(value) => value.trim().parse::<usize>().ok().filter(|bytes| *bytes > 0).ok_or_else(|| {
+                EvalError::Config(format!("MAX_BODY_BYTES must be a positive integer, got '{}'", value))
+            })?,
+            Err(_) => DEFAULT_MAX_BODY_BYTES,
+        };
+
+        let max_stored_output_chars = match var("MAX_STORED_OUTPUT_CHARS") {
+            Ok(value) => match value.trim().parse::<usize>() {
+                Ok(0) => None,
+                Ok(chars) => Some(chars),
+                Err(_) => {
+                    return Err(EvalError::Config(format!("MAX_STORED_OUTPUT_CHARS must be a non-negative integer, got '{}'", value)));
+                }
+            },
+            Err(_) => Some(DEFAULT_MAX_STORED_OUTPUT_CHARS),
+        };
+
+        let backup_dir = var("BACKUP_DIR")
+            .unwrap_or_else(|_| "./data/backups".to_string())
+            .into();
+        let backup_retention = match var("BACKUP_RETENTION") {
+            Ok(value) => value.trim().parse::<usize>().ok().filter(|retention| *retention > 0).ok_or_else(|| {
+                EvalError::Config(format!("BACKUP_RETENTION must be a positive integer, got '{}'", value))
+            })?,
+            Err(_) => 7,
+        };
+
+        let mut provider_retry = RetryPolicy::default();
+        if let Ok(value) = var("PROVIDER_MAX_RETRIES") {
+            provider_retry.max_retries = value.trim().parse::<u32>().map_err(|_| {
+                EvalError::Config(format!("PROVIDER_MAX_RETRIES must be a non-negative integer, got '{}'", value))
+            })?;
+        }
+        if let Ok(value) = var("PROVIDER_RETRY_BACKOFF_MS") {
+            let millis = value.trim().parse::<u64>().map_err(|_| {
+                EvalError::Config(format!("PROVIDER_RETRY_BACKOFF_MS must be a non-negative integer, got '{}'", value))
+            })?;
+            provider_retry.initial_backoff = std::time::Duration::from_millis(millis);
+        }
+
+        let judge_batching = var("JUDGE_BATCHING").is_ok_and(|value| value.trim().eq_ignore_ascii_case("true"));
+        let judge_batch_size = match var("JUDGE_BATCH_SIZE") {
+            Ok(value) => match value.trim().parse::<usize>() {
+                Ok(size) if size >= 2 => size,
+                _ => {
+                    return Err(EvalError::Config(format!("JUDGE_BATCH_SIZE must be an integer of at least 2, got '{}'", value)));
+                }
+            },
+            Err(_) => 10,
+        };
+
+        let share_ttl_hours = match var("SHARE_TTL_HOURS") {
+            Ok(value) => match value.trim().parse::<u64>() {
+                Ok(hours) if hours >= 1 => hours,
+                _ => {
+                    return Err(EvalError::Config(format!("SHARE_TTL_HOURS must be a positive integer, got '{}'", value)));
+                }
+            },
+            Err(_) => DEFAULT_SHARE_TTL_HOURS,
+        };
+
+        let retention = match var("RETENTION_DAYS") {
+            Ok(value) => {
+                let days = match value.trim().parse::<u32>() {
+                    Ok(days) if days >= 1 => days,
+                    _ => return Err(EvalError::Config(format!("RETENTION_DAYS must be a positive integer, got '{}'", value))),
+                };
+                let interval_minutes = match var("RETENTION_INTERVAL_MINUTES") {
+                    Ok(value) => match value.trim().parse::<u64>() {
+                        Ok(minutes) if minutes >= 1 => minutes,
+                        _ => {
+                            return Err(EvalError::Config(format!("RETENTION_INTERVAL_MINUTES must be a positive integer, got '{}'", value)));
+                        }
+                    },
+                    Err(_) => 60,
+                };
+                let vacuum_hours = match var("RETENTION_VACUUM_HOURS") {
+                    Ok(value) => value.trim().parse::<u64>().map_err(|_| {
+                        EvalError::Config(format!("RETENTION_VACUUM_HOURS must be a non-negative integer, got '{}'", value))
+                    })?,
+                    Err(_) => 24,
+                };
+                Some(RetentionPolicy {
+                    days,
+                    interval: std::time::Duration::from_secs(interval_minutes * 60),
+                    archive_dir: var("RETENTION_ARCHIVE_DIR").ok().filter(|dir| !dir.trim().is_empty()).map(Into::into),
+                    vacuum_interval: std::time::Duration::from_secs(vacuum_hours * 3600),
+                })
+            }
+            Err(_) => None,
+        };
+
+        let rate_limit = |name: &str| match var(name) {
+            Ok(value) => match value.trim().parse::<u32>() {
+                Ok(limit) if limit >= 1 => Ok(Some(limit)),
+                _ => Err(EvalError::Config(format!("{} must be a positive integer, got '{}'", name, value))),
+            },
+            Err(_) => Ok(None),
+        };
+        let rate_limit_per_minute = rate_limit("RATE_LIMIT_PER_MINUTE")?;
+        let batch_rate_limit_per_minute = rate_limit("BATCH_RATE_LIMIT_PER_MINUTE")?;
+        let mut provider_limits = Vec::new();
+        let mut extra_headers = BTreeMap::new();
+        for provider in BUILTIN_PROVIDERS.into_iter().chain(openai_compat.keys().map(String::as_str)) {
+            let prefix = env_prefix(provider);
+            let limit = ProviderRateLimit {
+                rpm: rate_limit(&format!("{}_RPM", prefix))?,
+                tpm: rate_limit(&format!("{}_TPM", prefix))?,
+            };
+            provider_limits.push((provider.to_string(), limit));
+            let headers_var = format!("{}_EXTRA_HEADERS", prefix);
+            if let Ok(spec) = var(&headers_var) {
+                extra_headers.insert(provider.to_string(), parse_extra_headers(&headers_var, &spec)?);
+            }
+        }
+
+        let config = AppConfig {
             anthropic: anthropic_config,
             gemini: gemini_config, 
+            groq: groq_config,
             ollama: ollama_config,
             openai: openai_config,
-            models: all_models 
-        })
+            openrouter: openrouter_config,
+            openai_compat,
+            models: all_models,
+            model_aliases: match var("MODEL_ALIASES") {
+                Ok(spec) => parse_model_aliases(&spec)?,
+                Err(_) => BTreeMap::new(),
+            },
+            min_judge_confidence,
+            pricing: PricingTable::load()?,
+            backup_dir,
+            backup_retention,
+            provider_retry,
+            admin_api_key: var("ADMIN_API_KEY").ok().filter(|key| !key.trim().is_empty()),
+            judge_batch_size: judge_batching.then_some(judge_batch_size),
+            paraphrase_model: var("PARAPHRASE_MODEL").ok().filter(|model| !model.trim().is_empty()),
+            default_judge_model: var("DEFAULT_JUDGE_MODEL").ok().map(|model| model.trim().to_string()).filter(|model| !model.is_empty()),
+            judge_params,
+            persist_traces: var("PERSIST_TRACES").is_ok_and(|value| value.trim().eq_ignore_ascii_case("true")),
+            capture_raw: var(CAPTURE_RAW_ENV).is_ok_and(|value| matches!(value.trim().to_lowercase().as_str(), "1" | "true")),
+            failure_classifier: var("FAILURE_CLASSIFIER").ok().and_then(|value| FailureClassifier::parse(&value)),
+            share_signing_secret: var("SHARE_SIGNING_SECRET").ok().filter(|secret| !secret.trim().is_empty()),
+            share_ttl_hours,
+            aggregate_only_keys: var("AGGREGATE_ONLY_KEYS")
+                .map(|keys| keys.split(',').map(|key| key.trim().to_string()).filter(|key| !key.is_empty()).collect())
+                .unwrap_or_default(),
+            retention,
+            rate_limit_per_minute,
+            batch_rate_limit_per_minute,
+            webhook_secret: var("WEBHOOK_SECRET").ok().filter(|secret| !secret.trim().is_empty()),
+            slack_webhook_url: var("SLACK_WEBHOOK_URL").ok().filter(|url| !url.trim().is_empty()),
+            notify_min_pass_rate,
+            drift_thresholds,
+            ws_max_output_chars,
+            cache_ttl_seconds,
+            max_body_bytes,
+            max_stored_output_chars,
+            provider_limiters: Arc::new(ProviderLimiters::new(provider_limits)),
+            judge_prompts: Arc::default(),
+            extra_headers,
+            http_client: HttpClientConfig::from_vars(&lookup)?,
+            suites: Vec::new(),
+            request_secrets: Vec::new(),
+        };
+        check_model_aliases(&config)?;
+        if let Some(judge) = &config.default_judge_model {
+            check_model(&config, judge).map_err(|message| EvalError::Config(format!("DEFAULT_JUDGE_MODEL {}", message)))?;
+        }
+        Ok(config)
     }
 }
 
+impl AppConfig {
+    /// Whether the named provider (e.g. `openai`) has configuration loaded.
+    pub fn is_provider_configured(&self, provider: &str) -> bool {
+        match provider {
+            "anthropic" => self.anthropic.is_some(),
+            "gemini" => self.gemini.is_some(),
+            "groq" => self.groq.is_some(),
+            "ollama" => self.ollama.is_some(),
+            "openai" => self.openai.is_some(),
+            "openrouter" => self.openrouter.is_some(),
+            other => self.openai_compat.contains_key(other),
+        }
+    }
+
+    /// Headers sent with every request of `provider`; empty when it has none configured.
+    pub fn extra_headers(&self, provider: &str) -> HeaderMap {
+        self.extra_headers.get(provider).cloned().unwrap_or_default()
+    }
+
+    /// The `provider:model` that `model` stands for: the end of its alias chain, or `model`
+    /// itself when it is no alias.
+    pub fn resolve_model<'a>(&'a self, model: &'a str) -> &'a str {
+        let mut resolved = model;
+        // Aliases are checked for cycles at startup; the bound only guards hand-built configs
+        for _ in 0..=self.model_aliases.len() {
+            match self.model_aliases.get(resolved) {
+                Some(target) => resolved = target,
+                None => break,
+            }
+        }
+        resolved
+    }
+
+    /// Provider and model name of `model`, with aliases resolved; see `runner::parse_model_string`.
+    pub fn parse_model(&self, model: &str) -> (String, String) {
+        crate::runner::parse_model_string(self.resolve_model(model))
+    }
+
+    /// Names of every configured provider, built-in ones first.
+    pub fn configured_providers(&self) -> Vec<&str> {
+        BUILTIN_PROVIDERS
+            .into_iter()
+            .filter(|provider| self.is_provider_configured(provider))
+            .chain(self.openai_compat.keys().map(String::as_str))
+            .collect()
+    }
+
+    /// Every secret of the config: provider keys, values of providers' extra headers, the
+    /// admin key and the signing secrets. Registered with `redact::register` once loaded.
+    pub fn secrets(&self) -> Vec<String> {
+        let provider_keys = [
+            self.anthropic.as_ref().map(|c| &c.api_key),
+            self.gemini.as_ref().map(|c| &c.api_key),
+            self.groq.as_ref().map(|c| &c.api_key),
+            self.openai.as_ref().map(|c| &c.api_key),
+            self.openrouter.as_ref().map(|c| &c.api_key),
+        ];
+        provider_keys
+            .into_iter()
+            .flatten()
+            .chain(self.openai_compat.values().map(|c| &c.api_key))
+            .chain([&self.admin_api_key, &self.share_signing_secret, &self.webhook_secret, &self.slack_webhook_url].into_iter().flatten())
+            .cloned()
+            .chain(self.extra_headers.values().flat_map(|headers| headers.values()).filter_map(|value| value.to_str().ok()).map(str::to_string))
+            .chain(self.request_secrets.iter().cloned())
+            .filter(|secret| !secret.is_empty())
+            .collect()
+    }
+}
+
+/// Providers with a client of their own; OpenAI-compatible servers may not take these names.
+pub const BUILTIN_PROVIDERS: [&str; 6] = ["anthropic", "gemini", "groq", "ollama", "openai", "openrouter"];
+
+/// Prefix of a provider's environment variables, e.g. `LM_STUDIO` for `lm-studio`.
+pub(crate) fn env_prefix(provider: &str) -> String {
+    provider.to_uppercase().replace('-', "_")
+}
+
+/// Parses `OPENAI_COMPAT_ENDPOINTS`, `name=base_url` pairs separated by `;`, e.g.
+/// `vllm=http://gpu-box:8000/v1;lmstudio=http://localhost:1234/v1`. Each server's key and
+/// comma-separated models are read with `var` from `<NAME>_API_KEY` and `<NAME>_MODELS`;
+/// both are optional.
+pub fn parse_compat_endpoints(
+    spec: &str,
+    var: impl Fn(&str) -> Option<String>,
+) -> Result<BTreeMap<String, OpenAIConfig>> {
+    let mut endpoints = BTreeMap::new();
+    for entry in spec.split(';').map(str::trim).filter(|entry| !entry.is_empty()) {
+        let invalid = |reason: &str| {
+            EvalError::Config(format!("OPENAI_COMPAT_ENDPOINTS entry '{}' {}", entry, reason))
+        };
+        let (name, url) = entry.split_once('=').ok_or_else(|| invalid("must look like name=url"))?;
+        let name = name.trim().to_lowercase();
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(invalid("needs a name of letters, digits, '-' and '_'"));
+        }
+        if BUILTIN_PROVIDERS.contains(&name.as_str()) {
+            return Err(invalid("takes the name of a built-in provider"));
+        }
+        let api_base = url.trim().trim_end_matches('/').to_string();
+        if !api_base.starts_with("http://") && !api_base.starts_with("https://") {
+            return Err(invalid("needs an http or https URL"));
+        }
+        let prefix = env_prefix(&name);
+        let api_key = var(&format!("{}_API_KEY", prefix)).map(|key| key.trim().to_string()).unwrap_or_default();
+        let models = var(&format!("{}_MODELS", prefix))
+            .map(|models| models.split(',').map(|m| m.trim().to_string()).filter(|m| !m.is_empty()).collect())
+            .unwrap_or_default();
+        if endpoints.insert(name.clone(), OpenAIConfig { api_base, api_key, models }).is_some() {
+            return Err(invalid("repeats an earlier name"));
+        }
+    }
+    Ok(endpoints)
+}
+
+/// Parses `MODEL_ALIASES`, `alias=target` pairs separated by `;`, e.g.
+/// `fast=gemini:gemini-2.0-flash;smart=openai:gpt-4o`. Aliases may not contain `:`, so they
+/// never shadow a `provider:model`.
+pub fn parse_model_aliases(spec: &str) -> Result<BTreeMap<String, String>> {
+    let mut aliases = BTreeMap::new();
+    for entry in spec.split(';').map(str::trim).filter(|entry| !entry.is_empty()) {
+        let invalid = |reason: &str| EvalError::Config(format!("MODEL_ALIASES entry '{}' {}", entry, reason));
+        let (alias, target) = entry.split_once('=').ok_or_else(|| invalid("must look like alias=provider:model"))?;
+        let (alias, target) = (alias.trim(), target.trim());
+        if alias.is_empty() || !alias.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')) {
+            return Err(invalid("needs an alias of letters, digits, '-', '_' and '.'"));
+        }
+        if target.is_empty() {
+            return Err(invalid("needs a target"));
+        }
+        if aliases.insert(alias.to_string(), target.to_string()).is_some() {
+            return Err(invalid("repeats an earlier alias"));
+        }
+    }
+    Ok(aliases)
+}
+
+/// Checks that every alias of `config` ends, maybe through other aliases, at a `provider:model`
+/// of a configured provider.
+fn check_model_aliases(config: &AppConfig) -> Result<()> {
+    for (alias, target) in &config.model_aliases {
+        let mut chain = vec![alias.as_str()];
+        let mut target = target.as_str();
+        while let Some(next) = config.model_aliases.get(target) {
+            if chain.contains(&target) {
+                chain.push(target);
+                return Err(EvalError::Config(format!("MODEL_ALIASES alias '{}' is circular: {}", alias, chain.join(" -> "))));
+            }
+            chain.push(target);
+            target = next;
+        }
+        if !target.contains(':') {
+            return Err(EvalError::Config(format!(
+                "MODEL_ALIASES alias '{}' points at '{}', which is neither an alias nor a provider:model",
+                alias, target
+            )));
+        }
+        check_model(config, target)
+            .map_err(|message| EvalError::Config(format!("MODEL_ALIASES alias '{}' points at '{}': {}", alias, target, message)))?;
+    }
+    Ok(())
+}
+
+/// Longest `criteria` an eval may carry, in characters.
+pub const MAX_CRITERIA_CHARS: usize = 4000;
+
 impl EvalConfig {
+    /// Checks the config before it is run: a non-empty model naming a configured provider,
+    /// a non-empty prompt, a usable judge when there is an expected output to judge against,
+    /// criteria of at most `MAX_CRITERIA_CHARS` and object metadata. Every broken rule is
+    /// reported, not just the first.
+    pub fn validate(&self, config: &AppConfig) -> std::result::Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        if let Err(message) = check_model(config, &self.model) {
+            errors.push(FieldError::new("model", message));
+        }
+        if self.prompt.trim().is_empty() {
+            errors.push(FieldError::new("prompt", "must not be empty"));
+        }
+        let judge_error = match (&self.expected, &self.judge_model) {
+            (Some(_), Some(judge)) => check_model(config, judge).err(),
+            _ => None,
+        };
+        if let Some(message) = judge_error {
+            errors.push(FieldError::new("judge_model", message));
+        }
+        if let Some(criteria) = &self.criteria {
+            let chars = criteria.chars().count();
+            if chars > MAX_CRITERIA_CHARS {
+                errors.push(FieldError::new(
+                    "criteria",
+                    format!("must be at most {} characters, got {}", MAX_CRITERIA_CHARS, chars),
+                ));
+            }
+        }
+        if self.metadata.as_ref().is_some_and(|metadata| !metadata.is_object()) {
+            errors.push(FieldError::new("metadata", "must be a JSON object"));
+        }
+        if self.response_format.as_ref().and_then(ResponseFormat::schema).is_some_and(|schema| !schema.is_object()) {
+            errors.push(FieldError::new("response_format.schema", "must be a JSON object"));
+        }
+        if self.stop.as_ref().is_some_and(|stop| stop.iter().any(String::is_empty)) {
+            errors.push(FieldError::new("stop", "must not contain empty sequences"));
+        }
+        if self.max_output_tokens == Some(0) {
+            errors.push(FieldError::new("max_output_tokens", "must be at least 1"));
+        }
+        if let Some(thinking) = self.thinking {
+            if thinking.budget_tokens < MIN_THINKING_BUDGET {
+                errors.push(FieldError::new("thinking.budget_tokens", format!("must be at least {}", MIN_THINKING_BUDGET)));
+            } else if self.max_output_tokens.is_some_and(|max| max <= thinking.budget_tokens) {
+                errors.push(FieldError::new("thinking.budget_tokens", "must be less than max_output_tokens, which it counts towards"));
+            }
+        }
+        let (provider, name) = config.parse_model(&self.model);
+        if provider == "anthropic"
+            && let Err(message) = anthropic::check_max_tokens(&name, self.max_output_tokens, self.thinking)
+        {
+            errors.push(FieldError::new("max_output_tokens", message));
+        }
+        if let Some(params) = &self.judge_params {
+            if params.temperature.is_some_and(|t| !(0.0..=2.0).contains(&t)) {
+                errors.push(FieldError::new("judge_params.temperature", "must be from 0 to 2"));
+            }
+            if params.max_tokens == Some(0) {
+                errors.push(FieldError::new("judge_params.max_tokens", "must be at least 1"));
+            }
+        }
+        if self.match_rule.and_then(|rule| rule.tolerance).is_some_and(|t| !(t.is_finite() && t >= 0.0)) {
+            errors.push(FieldError::new("match.tolerance", "must be a non-negative number"));
+        }
+        for (i, path) in self.diff_rule.iter().flat_map(|rule| rule.ignored_paths.iter()).enumerate() {
+            if !diff::is_valid_path(path) {
+                errors.push(FieldError::new(format!("diff.ignored_paths[{}]", i), "must be a JSON path like $.items[*].id"));
+            }
+        }
+        if let Some(consistency) = self.self_consistency {
+            if !(2..=self_consistency::MAX_SAMPLES).contains(&consistency.samples) {
+                errors.push(FieldError::new(
+                    "self_consistency.samples",
+                    format!("must be from 2 to {}", self_consistency::MAX_SAMPLES),
+                ));
+            }
+            if self.expected.is_none() && self.format_rule.is_none() {
+                errors.push(FieldError::new("self_consistency", "needs an expected output or format rule to give each sample a verdict"));
+            }
+            if self.cache {
+                errors.push(FieldError::new("self_consistency", "cannot be combined with cache, which would return the same output for every sample"));
+            }
+        }
+        if !self.provider_overrides.is_empty() {
+            let judge = self.expected.as_ref().and(self.judge_model.as_ref().or(config.default_judge_model.as_ref()));
+            let used: Vec<String> = std::iter::once(&self.model).chain(judge).map(|model| config.parse_model(model).0).collect();
+            errors.extend(provider_override::validate(&self.provider_overrides, &used));
+        }
+        if self.images.len() > MAX_IMAGES {
+            errors.push(FieldError::new("images", format!("must hold at most {} images, got {}", MAX_IMAGES, self.images.len())));
+        }
+        for (i, image) in self.images.iter().enumerate() {
+            match Image::parse(image) {
+                None => errors.push(FieldError::new(format!("images[{}]", i), "must be an http(s) URL or a base64 data:image/ URI")),
+                Some(image) if image.byte_size() > MAX_IMAGE_BYTES => errors.push(FieldError::new(
+                    format!("images[{}]", i),
+                    format!("must be at most {} bytes, got {}", MAX_IMAGE_BYTES, image.byte_size()),
+                )),
+                Some(_) => {}
+            }
+        }
+
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    /// A stable key for this eval across runs: a SHA-256 over model, and prompt and expected
+    /// output as rendered with the metadata, so each row of a templated run gets its own key.
+    /// Judge settings are deliberately excluded so runs judged differently still line up.
+    pub fn config_hash(&self) -> String {
+        let render = |template: &str| crate::template::render(template, self.metadata.as_ref()).0;
+        let prompt = render(&self.prompt);
+        let expected = self.expected.as_deref().map(render);
+        let mut hasher = Sha256::new();
+        for part in [Some(self.model.as_str()), Some(prompt.as_str()), expected.as_deref()] {
+            match part {
+                Some(text) => {
+                    hasher.update([1u8]);
+                    hasher.update((text.len() as u64).to_le_bytes());
+                    hasher.update(text.as_bytes());
+                }
+                None => hasher.update([0u8]),
+            }
+        }
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// The settings sent to the model's provider with the request.
+    pub fn generation_params(&self) -> GenerationParams {
+        GenerationParams {
+            response_format: self.response_format.clone(),
+            stop: self.stop.clone(),
+            max_output_tokens: self.max_output_tokens,
+            images: self.images.clone(),
+            thinking: self.thinking,
+            ..GenerationParams::default()
+        }
+    }
+
     /// Creates a new `EvalConfig` by substituting placeholders from its metadata.
-    /// Placeholders are in the format `{{key}}`.
+    /// Placeholders are in the format `{{key}}`. Fails with a `Config` error naming every
+    /// placeholder the metadata has no value for, unless `allow_missing_vars` is set.
     pub fn render(&self) -> Result<Self> {
         let mut rendered_config = self.clone();
+        let mut unresolved = Vec::new();
 
-        if let Some(metadata) = &self.metadata {
-            rendered_config.prompt = render_template(&self.prompt, metadata);
-            if let Some(expected) = &self.expected {
-                rendered_config.expected = Some(render_template(expected, metadata));
-            }
+        rendered_config.prompt = render_template(&self.prompt, self.metadata.as_ref(), &mut unresolved);
+        if let Some(expected) = &self.expected {
+            rendered_config.expected = Some(render_template(expected, self.metadata.as_ref(), &mut unresolved));
         }
 
+        if !unresolved.is_empty() && !self.allow_missing_vars {
+            return Err(EvalError::Config(format!(
+                "Template placeholders could not be filled from metadata: {} (set allow_missing_vars to send them as they are)",
+                unresolved.join(", ")
+            )));
+        }
         Ok(rendered_config)
     }
 }
 
-// /// Simple template renderer using regex.
-// /// enables parameterized test cases
-// fn render_template(template: &str, data: &serde_json::Value) -> String {
-//     let re = Regex::new(r"\{\{\s*(\w+)\s*\}\}").unwrap();
-//     re.replace_all(template, |caps: &regex::Captures| {
-//         let key = &caps[1];
-//         data.get(key)
-//             .and_then(|v| v.as_str())
-//             .map(|s| s.to_string())
-//             .unwrap_or_else(|| caps[0].to_string())
-//     }).to_string()
-// }
-
-/// Simple template renderer using regex.
-/// enables parameterized test cases
-fn render_template(template: &str, data: &serde_json::Value) -> String {
-    let re = Regex::new(r"\{\{\s*(\w+)\s*\}\}").unwrap();
-    re.replace_all(template, |caps: &regex::Captures| {
-        let key = &caps[1];
-        data.get(key)
-            .map(|v| match v {
-                serde_json::Value::String(s) => s.clone(),
-                serde_json::Value::Number(n) => n.to_string(),
-                serde_json::Value::Bool(b) => b.to_string(),
-                _ => v.to_string().trim_matches('"').to_string(),
-            })
-            .unwrap_or_else(|| caps[0].to_string())
-    }).to_string()
+/// Fills the placeholders of `template` from `data`, describing those it cannot fill in `unresolved`.
+fn render_template(template: &str, data: Option<&serde_json::Value>, unresolved: &mut Vec<String>) -> String {
+    let (rendered, problems) = crate::template::render(template, data);
+    for problem in problems {
+        if !unresolved.contains(&problem) {
+            unresolved.push(problem);
+        }
+    }
+    rendered
+}
+
+/// Checks that a `provider:model` string is complete and names a configured provider.
+fn check_model(config: &AppConfig, model: &str) -> std::result::Result<(), String> {
+    if model.trim().is_empty() {
+        return Err("must not be empty".to_string());
+    }
+    let (provider, name) = config.parse_model(model);
+    if name.trim().is_empty() {
+        return Err(format!("'{}' has no model name after the provider", model));
+    }
+    if !config.is_provider_configured(&provider) {
+        return Err(format!("provider '{}' is not configured", provider));
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use serde_json::json;
+    use std::collections::HashMap;
 
     #[test]
     fn test_eval_config_render() {
         let eval_config = EvalConfig {
             model: "gemini-2.5-flash".to_string(),
             prompt: "What is the capital of {{country}}?".to_string(),
+            prompt_version: None,
             expected: Some("The capital is {{capital}}.".to_string()),
             judge_model: Some("gemini-2.5-pro".to_string()),
             criteria: None,
@@ -218,6 +1232,36 @@ mod tests {
                 "country": "France",
                 "capital": "Paris"
             })),
+            format_rule: None,
+            stream: false,
+            min_judge_confidence: None,
+            truncation: None,
+            truncate_field: None,
+            context_window: None,
+            on_length_limit: None,
+            trace: false,
+            skip_failure_classification: false,
+            capture_raw: false,
+            require_review: false,
+            cache: false,
+            cache_judge: false,
+            safety_threshold: None,
+            response_format: None,
+            stop: None,
+            max_output_tokens: None,
+            thinking: None,
+            images: Vec::new(),
+            include_reasoning: false,
+            allow_missing_vars: false,
+            judge_params: None,
+            match_rule: None,
+            diff_rule: None,
+            self_consistency: None,
+            preset: None,
+            dataset_row: None,
+            chain_step: None,
+            provider_overrides: Default::default(),
+            overrides_supplied_by: None,
         };
 
         let rendered_config = eval_config.render().unwrap();
@@ -231,4 +1275,314 @@ mod tests {
         assert_eq!(rendered_config.model, eval_config.model);
         assert_eq!(rendered_config.metadata, eval_config.metadata);
     }
+
+    #[test]
+    fn test_render_fails_on_placeholders_the_metadata_cannot_fill() {
+        let eval = |prompt: &str, metadata: Option<serde_json::Value>| -> EvalConfig {
+            serde_json::from_value(json!({
+                "model": "ollama:llama3",
+                "prompt": prompt,
+                "expected": "{{answer}}",
+                "metadata": metadata,
+            }))
+            .unwrap()
+        };
+
+        let rendered = eval(
+            "{{count}} {{ok}} {{tags}} {{user}} {{rows}}",
+            Some(json!({
+                "count": 3, "ok": true, "tags": ["a", 1, false],
+                "user": { "name": "Ann", "roles": ["admin"] }, "rows": [{ "id": 1 }], "answer": 4.5
+            })),
+        )
+        .render()
+        .unwrap();
+        assert_eq!(rendered.prompt, r#"3 true a, 1, false {"name":"Ann","roles":["admin"]} [{"id":1}]"#);
+        assert_eq!(rendered.expected.as_deref(), Some("4.5"));
+
+        let err = eval("Hi {{name}}, {{ name }} is {{age}}", Some(json!({ "age": null }))).render().unwrap_err();
+        let EvalError::Config(message) = &err else { panic!("{}", err) };
+        assert!(message.contains("{{name}} (missing), {{age}} (null), {{answer}} (missing)"), "{}", message);
+        assert!(matches!(eval("{{name}}", None).render(), Err(EvalError::Config(_))));
+
+        let mut lenient = eval("Hi {{name}}", None);
+        lenient.allow_missing_vars = true;
+        assert_eq!(lenient.render().unwrap().prompt, "Hi {{name}}");
+        assert_eq!(eval("No placeholders", Some(json!({ "answer": "4" }))).render().unwrap().prompt, "No placeholders");
+    }
+
+    #[test]
+    fn test_config_hash_ignores_judge_settings() {
+        let config: EvalConfig = serde_json::from_value(json!({
+            "model": "ollama:llama3",
+            "prompt": "What is 2+2?",
+            "expected": "4"
+        }))
+        .unwrap();
+
+        let mut rejudged = config.clone();
+        rejudged.judge_model = Some("openai:gpt-4o".to_string());
+        assert_eq!(config.config_hash(), rejudged.config_hash());
+
+        let mut no_expected = config.clone();
+        no_expected.expected = None;
+        assert_ne!(config.config_hash(), no_expected.config_hash());
+
+        let mut empty_expected = config.clone();
+        empty_expected.expected = Some(String::new());
+        assert_ne!(no_expected.config_hash(), empty_expected.config_hash());
+    }
+
+    #[test]
+    fn test_config_hash_tells_rows_of_the_same_template_apart() {
+        let row = |a: i64, answer: &str| -> EvalConfig {
+            serde_json::from_value(json!({
+                "model": "ollama:llama3",
+                "prompt": "What is {{a}}+2?",
+                "expected": "{{answer}}",
+                "metadata": { "a": a, "answer": answer }
+            }))
+            .unwrap()
+        };
+        assert_ne!(row(2, "4").config_hash(), row(3, "5").config_hash());
+        assert_ne!(row(2, "4").config_hash(), row(2, "5").config_hash());
+
+        // A row hashes like the literal eval it renders to
+        let literal: EvalConfig = serde_json::from_value(json!({ "model": "ollama:llama3", "prompt": "What is 2+2?", "expected": "4" })).unwrap();
+        assert_eq!(row(2, "4").config_hash(), literal.config_hash());
+    }
+
+    fn ollama_config() -> AppConfig {
+        AppConfig {
+            ollama: Some(OllamaConfig { api_base: "http://localhost:11434".to_string(), models: vec![] }),
+            ..Default::default()
+        }
+    }
+
+    fn eval(body: serde_json::Value) -> EvalConfig {
+        serde_json::from_value(body).unwrap()
+    }
+
+    fn invalid_fields(config: &EvalConfig) -> Vec<String> {
+        config.validate(&ollama_config()).err().unwrap_or_default().into_iter().map(|e| e.field).collect()
+    }
+
+    #[test]
+    fn test_validate_accepts_a_complete_config() {
+        let config = eval(json!({
+            "model": "ollama:llama3",
+            "prompt": "Capital of {{country}}?",
+            "expected": "Paris",
+            "judge_model": "ollama:llama3",
+            "criteria": "Names the city",
+            "metadata": { "country": "France" }
+        }));
+        assert_eq!(config.validate(&ollama_config()), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_requires_model_and_prompt() {
+        assert_eq!(invalid_fields(&eval(json!({ "model": "", "prompt": "  " }))), ["model", "prompt"]);
+        assert_eq!(invalid_fields(&eval(json!({ "model": "ollama:", "prompt": "hi" }))), ["model"]);
+    }
+
+    #[test]
+    fn test_validate_requires_a_configured_provider() {
+        let errors = eval(json!({ "model": "openai:gpt-4o", "prompt": "hi" })).validate(&ollama_config()).unwrap_err();
+        assert_eq!(errors, [FieldError::new("model", "provider 'openai' is not configured")]);
+        // Models without a provider default to gemini
+        assert_eq!(invalid_fields(&eval(json!({ "model": "gemini-2.5-flash", "prompt": "hi" }))), ["model"]);
+    }
+
+    #[test]
+    fn test_validate_checks_the_judge_only_when_there_is_something_to_judge() {
+        let unjudged = json!({ "model": "ollama:llama3", "prompt": "hi", "judge_model": "openai:gpt-4o" });
+        assert!(invalid_fields(&eval(unjudged.clone())).is_empty());
+
+        let mut judged = unjudged;
+        judged["expected"] = json!("hello");
+        assert_eq!(invalid_fields(&eval(judged.clone())), ["judge_model"]);
+        judged["judge_model"] = json!("");
+        assert_eq!(invalid_fields(&eval(judged)), ["judge_model"]);
+    }
+
+    #[test]
+    fn test_validate_caps_criteria_length() {
+        let mut config = eval(json!({ "model": "ollama:llama3", "prompt": "hi" }));
+        config.criteria = Some("x".repeat(MAX_CRITERIA_CHARS));
+        assert!(invalid_fields(&config).is_empty());
+        config.criteria = Some("é".repeat(MAX_CRITERIA_CHARS + 1));
+        assert_eq!(invalid_fields(&config), ["criteria"]);
+    }
+
+    #[test]
+    fn test_validate_requires_object_metadata() {
+        for metadata in [json!([1, 2]), json!("text"), json!(3)] {
+            let config = eval(json!({ "model": "ollama:llama3", "prompt": "hi", "metadata": metadata }));
+            assert_eq!(invalid_fields(&config), ["metadata"]);
+        }
+    }
+
+    #[test]
+    fn test_validate_checks_thinking_budget_and_anthropic_output_ceiling() {
+        let config = AppConfig {
+            anthropic: Some(AnthropicConfig { api_base: "http://localhost".to_string(), api_key: "key".to_string(), models: vec![] }),
+            ..Default::default()
+        };
+        let invalid = |body: serde_json::Value| -> Vec<String> {
+            eval(body).validate(&config).err().unwrap_or_default().into_iter().map(|e| e.field).collect()
+        };
+        let thinking = json!({ "model": "anthropic:claude-sonnet-4-5", "prompt": "hi", "thinking": { "budget_tokens": 2048 } });
+        assert!(invalid(thinking.clone()).is_empty());
+
+        let mut small_budget = thinking.clone();
+        small_budget["thinking"]["budget_tokens"] = json!(512);
+        assert_eq!(invalid(small_budget), ["thinking.budget_tokens"]);
+        let mut over_limit = thinking.clone();
+        over_limit["max_output_tokens"] = json!(2048);
+        assert_eq!(invalid(over_limit), ["thinking.budget_tokens"]);
+
+        let mut over_ceiling = thinking;
+        over_ceiling["model"] = json!("anthropic:claude-3-5-haiku-latest");
+        over_ceiling["max_output_tokens"] = json!(16_000);
+        assert_eq!(invalid(over_ceiling), ["max_output_tokens"]);
+        // Other providers' models have no known ceiling
+        assert!(invalid_fields(&eval(json!({ "model": "ollama:llama3", "prompt": "hi", "max_output_tokens": 1_000_000 }))).is_empty());
+    }
+
+    #[test]
+    fn test_compat_endpoints_parse_names_urls_keys_and_models() {
+        let vars = |name: &str| match name {
+            "VLLM_API_KEY" => Some(" secret ".to_string()),
+            "VLLM_MODELS" => Some("qwen2.5-7b, llama3,".to_string()),
+            "LM_STUDIO_MODELS" => Some("phi-3".to_string()),
+            _ => None,
+        };
+        let endpoints = parse_compat_endpoints(
+            "vllm=http://gpu-box:8000/v1/ ; LM-Studio = http://localhost:1234/v1;;",
+            vars,
+        )
+        .unwrap();
+        assert_eq!(endpoints.keys().collect::<Vec<_>>(), ["lm-studio", "vllm"]);
+        assert_eq!(
+            endpoints["vllm"],
+            OpenAIConfig {
+                api_base: "http://gpu-box:8000/v1".to_string(),
+                api_key: "secret".to_string(),
+                models: vec!["qwen2.5-7b".to_string(), "llama3".to_string()],
+            }
+        );
+        // No key: requests go out without one
+        assert_eq!(endpoints["lm-studio"].api_key, "");
+        assert_eq!(endpoints["lm-studio"].api_base, "http://localhost:1234/v1");
+        assert_eq!(endpoints["lm-studio"].models, ["phi-3"]);
+
+        let bare = parse_compat_endpoints("llamafile=http://localhost:8080/v1", |_| None).unwrap();
+        assert!(bare["llamafile"].models.is_empty());
+        assert!(parse_compat_endpoints("", |_| None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_compat_endpoints_reject_broken_entries() {
+        for spec in [
+            "http://gpu-box:8000/v1",
+            "=http://gpu-box:8000/v1",
+            "vl lm=http://gpu-box:8000/v1",
+            "openai=http://gpu-box:8000/v1",
+            "vllm=gpu-box:8000/v1",
+            "vllm=http://a/v1;vllm=http://b/v1",
+        ] {
+            assert!(parse_compat_endpoints(spec, |_| None).is_err(), "{}", spec);
+        }
+    }
+
+    #[test]
+    fn test_compat_endpoints_count_as_configured_providers() {
+        let config = AppConfig {
+            openai_compat: parse_compat_endpoints("vllm=http://gpu-box:8000/v1", |_| None).unwrap(),
+            ..ollama_config()
+        };
+        assert!(config.is_provider_configured("vllm"));
+        assert!(!config.is_provider_configured("lmstudio"));
+        assert_eq!(config.configured_providers(), ["ollama", "vllm"]);
+        assert_eq!(eval(json!({ "model": "vllm:qwen2.5-7b", "prompt": "hi" })).validate(&config), Ok(()));
+    }
+
+    #[test]
+    fn test_judge_params_default_to_deterministic_and_evals_override_them() {
+        let load = |vars: &[(&str, &str)]| {
+            let vars: HashMap<&str, &str> = vars.iter().copied().chain([("OLLAMA_API_BASE", "http://localhost:11434")]).collect();
+            AppConfig::from_vars(|name| vars.get(name).map(|v| v.to_string()))
+        };
+        let defaults = load(&[]).unwrap().judge_params;
+        assert_eq!(defaults, JudgeParams::default());
+        assert_eq!((defaults.temperature, defaults.max_tokens, defaults.seed), (Some(0.0), Some(DEFAULT_JUDGE_MAX_TOKENS), None));
+        assert!(!defaults.retries_uncertain());
+
+        let global = load(&[("JUDGE_TEMPERATURE", "0.3"), ("JUDGE_MAX_TOKENS", "256"), ("JUDGE_SEED", "42"), ("JUDGE_RETRY_UNCERTAIN", "true")])
+            .unwrap()
+            .judge_params;
+        assert_eq!(global, JudgeParams { temperature: Some(0.3), max_tokens: Some(256), seed: Some(42), retry_uncertain: Some(true) });
+
+        let eval: JudgeParams = serde_json::from_value(json!({ "temperature": 0.0, "retry_uncertain": false })).unwrap();
+        let merged = eval.or(global);
+        assert_eq!(merged, JudgeParams { temperature: Some(0.0), max_tokens: Some(256), seed: Some(42), retry_uncertain: Some(false) });
+        let sent = merged.generation_params();
+        assert_eq!((sent.temperature, sent.max_output_tokens, sent.seed), (Some(0.0), Some(256), Some(42)));
+        assert!(serde_json::from_value::<JudgeParams>(json!({ "top_p": 0.9 })).is_err());
+
+        for (name, value) in [("JUDGE_TEMPERATURE", "hot"), ("JUDGE_TEMPERATURE", "2.5"), ("JUDGE_MAX_TOKENS", "0"), ("JUDGE_SEED", "-1")] {
+            let err = load(&[(name, value)]).unwrap_err();
+            assert!(matches!(&err, EvalError::Config(message) if message.starts_with(name)), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_model_aliases_resolve_through_chains() {
+        let aliases = parse_model_aliases("fast = gemini:gemini-2.0-flash; quick=fast;smart=openai:gpt-4o;").unwrap();
+        assert_eq!(aliases.len(), 3);
+        let config = AppConfig { model_aliases: aliases, ..Default::default() };
+        assert_eq!(config.resolve_model("quick"), "gemini:gemini-2.0-flash");
+        assert_eq!(config.resolve_model("smart"), "openai:gpt-4o");
+        assert_eq!(config.resolve_model("ollama:llama3"), "ollama:llama3");
+        assert_eq!(config.parse_model("quick"), ("gemini".to_string(), "gemini-2.0-flash".to_string()));
+
+        for spec in ["fast", "=openai:gpt-4o", "a:b=openai:gpt-4o", "fast=", "fast=openai:gpt-4o;fast=openai:gpt-4o-mini"] {
+            let err = parse_model_aliases(spec).unwrap_err();
+            assert!(matches!(&err, EvalError::Config(message) if message.starts_with("MODEL_ALIASES entry")), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_circular_and_dangling_aliases_are_rejected_at_startup() {
+        let load = |aliases: &str| {
+            let vars = HashMap::from([("OLLAMA_API_BASE", "http://localhost:11434"), ("MODEL_ALIASES", aliases)]);
+            AppConfig::from_vars(|name| vars.get(name).map(|value| value.to_string()))
+        };
+        let config = load("fast=ollama:llama3;judge=fast").unwrap();
+        assert_eq!(config.resolve_model("judge"), "ollama:llama3");
+
+        let cases = [
+            ("a=b;b=c;c=a", "MODEL_ALIASES alias 'a' is circular: a -> b -> c -> a"),
+            ("a=a", "MODEL_ALIASES alias 'a' is circular: a -> a"),
+            ("fast=llama3", "MODEL_ALIASES alias 'fast' points at 'llama3', which is neither an alias nor a provider:model"),
+            ("smart=openai:gpt-4o", "MODEL_ALIASES alias 'smart' points at 'openai:gpt-4o': provider 'openai' is not configured"),
+        ];
+        for (aliases, expected) in cases {
+            let err = load(aliases).unwrap_err();
+            assert!(matches!(&err, EvalError::Config(message) if message == expected), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_evals_may_name_their_models_by_alias() {
+        let config = AppConfig {
+            ollama: Some(OllamaConfig { api_base: "http://localhost:11434".to_string(), models: vec![] }),
+            model_aliases: BTreeMap::from([("fast".to_string(), "ollama:llama3".to_string())]),
+            ..Default::default()
+        };
+        let eval: EvalConfig =
+            serde_json::from_value(json!({ "model": "fast", "prompt": "Hi", "expected": "Hello", "judge_model": "fast" })).unwrap();
+        assert!(eval.validate(&config).is_ok());
+    }
 }
@@ -0,0 +1,374 @@
+// src/config_file.rs
+//! The TOML configuration file, an alternative to setting everything as environment variables.
+//!
+//! ```toml
+//! default_judge_model = "smart"
+//! timeout_seconds = 120
+//!
+//! [aliases]
+//! smart = "openai:gpt-4o"
+//!
+//! [providers.openai]
+//! api_key = "${OPENAI_API_KEY}"
+//! models = ["gpt-4o", "gpt-4o-mini"]
+//!
+//! [providers.vllm]            # any other name is an OpenAI-compatible server
+//! api_base = "http://gpu-box:8000/v1"
+//!
+//! [retry]
+//! max_retries = 3
+//!
+//! [[suites]]
+//! name = "smoke"
+//! [[suites.evals]]
+//! model = "openai:gpt-4o-mini"
+//! prompt = "What is 2+2?"
+//! expected = "4"
+//! ```
+//!
+//! Each setting stands in for the environment variable of the same meaning, which wins when
+//! it is set as well, e.g. `OPENAI_MODELS` over `providers.openai.models`.
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::config::{env_prefix, AppConfig, EvalConfig, BUILTIN_PROVIDERS};
+use crate::errors::{EvalError, Result};
+
+/// Where Ollama is reached when its section gives no `api_base`.
+const DEFAULT_OLLAMA_API_BASE: &str = "http://localhost:11434";
+
+/// Settings of one provider. Every field is optional, as the environment may supply it.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProviderSection {
+    api_key: Option<String>,
+    api_base: Option<String>,
+    models: Option<Vec<String>>,
+    /// Gemini only.
+    safety_threshold: Option<String>,
+    /// OpenRouter only.
+    referer: Option<String>,
+    /// OpenRouter only.
+    title: Option<String>,
+    rpm: Option<u32>,
+    tpm: Option<u32>,
+    #[serde(default)]
+    extra_headers: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RetrySection {
+    max_retries: Option<u32>,
+    backoff_ms: Option<u64>,
+}
+
+/// A suite of evals defined in the config file, registered under its name at startup.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SuiteDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub evals: Vec<EvalConfig>,
+}
+
+/// The contents of a config file, before `${VAR}` references are filled in.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    #[serde(default)]
+    providers: BTreeMap<String, ProviderSection>,
+    /// Model aliases, by alias, as in `MODEL_ALIASES`.
+    #[serde(default)]
+    aliases: BTreeMap<String, String>,
+    default_judge_model: Option<String>,
+    timeout_seconds: Option<u64>,
+    #[serde(default)]
+    retry: RetrySection,
+    #[serde(default)]
+    suites: Vec<SuiteDefinition>,
+    /// Evals registered as a suite named after the file.
+    #[serde(default)]
+    evals: Vec<EvalConfig>,
+}
+
+impl ConfigFile {
+    /// Parses a config file's contents. Errors give the line and key they are about.
+    pub fn parse(text: &str) -> Result<Self> {
+        Ok(toml::from_str(text)?)
+    }
+
+    /// The application config of this file merged with the variables `env` returns, which win
+    /// over the file. Top-level `[[evals]]` become the suite `default_suite`.
+    pub fn into_app_config(self, default_suite: &str, env: impl Fn(&str) -> Option<String>) -> Result<AppConfig> {
+        let vars = self.vars(&env)?;
+        let mut config = AppConfig::from_vars(|name| env(name).or_else(|| vars.get(name).cloned()))?;
+
+        let mut suites = self.suites;
+        if !self.evals.is_empty() {
+            suites.push(SuiteDefinition { name: default_suite.to_string(), description: None, evals: self.evals });
+        }
+        check_suites(&config, &suites)?;
+        config.suites = suites;
+        Ok(config)
+    }
+
+    /// The settings of the file, by the name of the environment variable each stands in for.
+    fn vars(&self, env: &impl Fn(&str) -> Option<String>) -> Result<BTreeMap<String, String>> {
+        let mut vars = BTreeMap::new();
+        let mut compat_endpoints = Vec::new();
+        for (name, section) in &self.providers {
+            let key = |field: &str| format!("providers.{}.{}", name, field);
+            let builtin = BUILTIN_PROVIDERS.contains(&name.as_str());
+            for (field, set, applies) in [
+                ("safety_threshold", section.safety_threshold.is_some(), name == "gemini"),
+                ("referer", section.referer.is_some(), name == "openrouter"),
+                ("title", section.title.is_some(), name == "openrouter"),
+            ] {
+                if set && !applies {
+                    return Err(EvalError::Config(format!("{} does not apply to provider '{}'", key(field), name)));
+                }
+            }
+
+            let prefix = env_prefix(name);
+            let mut set = |field: &str, suffix: &str, value: String| -> Result<()> {
+                vars.insert(format!("{}_{}", prefix, suffix), interpolate(&key(field), &value, env)?);
+                Ok(())
+            };
+            let api_base = match (&section.api_base, name.as_str()) {
+                (Some(api_base), _) => Some(api_base.clone()),
+                (None, "ollama") => Some(DEFAULT_OLLAMA_API_BASE.to_string()),
+                (None, _) => None,
+            };
+            if builtin {
+                if let Some(api_base) = api_base {
+                    set("api_base", "API_BASE", api_base)?;
+                }
+            } else {
+                let api_base = api_base.ok_or_else(|| {
+                    EvalError::Config(format!("{} is required for an OpenAI-compatible server", key("api_base")))
+                })?;
+                compat_endpoints.push(format!("{}={}", name, interpolate(&key("api_base"), &api_base, env)?));
+            }
+            if let Some(api_key) = &section.api_key {
+                set("api_key", "API_KEY", api_key.clone())?;
+            }
+            if let Some(models) = &section.models {
+                set("models", "MODELS", models.join(","))?;
+            }
+            if let Some(threshold) = &section.safety_threshold {
+                set("safety_threshold", "SAFETY_THRESHOLD", threshold.clone())?;
+            }
+            if let Some(referer) = &section.referer {
+                set("referer", "REFERER", referer.clone())?;
+            }
+            if let Some(title) = &section.title {
+                set("title", "TITLE", title.clone())?;
+            }
+            if let Some(rpm) = section.rpm {
+                set("rpm", "RPM", rpm.to_string())?;
+            }
+            if let Some(tpm) = section.tpm {
+                set("tpm", "TPM", tpm.to_string())?;
+            }
+            if !section.extra_headers.is_empty() {
+                let headers = section.extra_headers.iter().map(|(name, value)| format!("{}: {}", name, value));
+                set("extra_headers", "EXTRA_HEADERS", headers.collect::<Vec<_>>().join(";"))?;
+            }
+        }
+        if !compat_endpoints.is_empty() {
+            vars.insert("OPENAI_COMPAT_ENDPOINTS".to_string(), compat_endpoints.join(";"));
+        }
+
+        if !self.aliases.is_empty() {
+            let aliases = self
+                .aliases
+                .iter()
+                .map(|(alias, target)| Ok(format!("{}={}", alias, interpolate(&format!("aliases.{}", alias), target, env)?)))
+                .collect::<Result<Vec<_>>>()?;
+            vars.insert("MODEL_ALIASES".to_string(), aliases.join(";"));
+        }
+        if let Some(model) = &self.default_judge_model {
+            vars.insert("DEFAULT_JUDGE_MODEL".to_string(), interpolate("default_judge_model", model, env)?);
+        }
+        if let Some(secs) = self.timeout_seconds {
+            vars.insert("PROVIDER_TIMEOUT_SECONDS".to_string(), secs.to_string());
+        }
+        if let Some(retries) = self.retry.max_retries {
+            vars.insert("PROVIDER_MAX_RETRIES".to_string(), retries.to_string());
+        }
+        if let Some(millis) = self.retry.backoff_ms {
+            vars.insert("PROVIDER_RETRY_BACKOFF_MS".to_string(), millis.to_string());
+        }
+        Ok(vars)
+    }
+}
+
+/// Replaces each `${VAR}` in `value` with the variable `env` returns. `key` names the setting
+/// in errors, which an unset variable is.
+fn interpolate(key: &str, value: &str, env: &impl Fn(&str) -> Option<String>) -> Result<String> {
+    let mut interpolated = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        interpolated.push_str(&rest[..start]);
+        let (name, after) = rest[start + 2..]
+            .split_once('}')
+            .ok_or_else(|| EvalError::Config(format!("{} has an unclosed '${{' in '{}'", key, value)))?;
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(EvalError::Config(format!("{} refers to '${{{}}}', which is not a variable name", key, name)));
+        }
+        let substitute = env(name)
+            .ok_or_else(|| EvalError::Config(format!("{} refers to ${{{}}}, which is not set", key, name)))?;
+        interpolated.push_str(&substitute);
+        rest = after;
+    }
+    interpolated.push_str(rest);
+    Ok(interpolated)
+}
+
+/// Checks that suite names are unique and non-empty and that every eval is valid against
+/// `config`, reporting all problems at once.
+fn check_suites(config: &AppConfig, suites: &[SuiteDefinition]) -> Result<()> {
+    let mut problems = Vec::new();
+    for (i, suite) in suites.iter().enumerate() {
+        if suite.name.trim().is_empty() {
+            problems.push(format!("suites[{}].name must not be empty", i));
+        } else if suites[..i].iter().any(|earlier| earlier.name == suite.name) {
+            problems.push(format!("suites[{}].name repeats the suite name '{}'", i, suite.name));
+        }
+        if suite.evals.is_empty() {
+            problems.push(format!("suite '{}' must contain at least one eval", suite.name));
+        }
+        for (j, eval) in suite.evals.iter().enumerate() {
+            for error in eval.validate(config).err().unwrap_or_default() {
+                problems.push(format!("suite '{}' evals[{}].{} {}", suite.name, j, error.field, error.message));
+            }
+        }
+    }
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(EvalError::Config(format!("Invalid suites in the config file: {}", problems.join("; "))))
+    }
+}
+
+impl AppConfig {
+    /// Loads configuration from the TOML file at `path`, merged with environment variables,
+    /// which win over the file's settings.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| EvalError::Config(format!("Config file '{}' could not be read: {}", path.display(), e)))?;
+        let default_suite = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("config");
+        ConfigFile::parse(&text)?.into_app_config(default_suite, |name| std::env::var(name).ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    fn load(text: &str, env: &[(&str, &str)]) -> Result<AppConfig> {
+        let env: HashMap<String, String> = env.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        ConfigFile::parse(text)?.into_app_config("evals", |name| env.get(name).cloned())
+    }
+
+    #[test]
+    fn test_variables_are_interpolated_into_settings() {
+        let config = load(
+            r#"
+            default_judge_model = "openai:${JUDGE}"
+            [aliases]
+            smart = "openai:${JUDGE}"
+            [providers.openai]
+            api_key = "${OPENAI_KEY}"
+            api_base = "https://${GATEWAY_HOST}/v1"
+            models = ["gpt-4o"]
+            "#,
+            &[("OPENAI_KEY", "sk-test"), ("GATEWAY_HOST", "gateway.corp"), ("JUDGE", "gpt-4o")],
+        )
+        .unwrap();
+        let openai = config.openai.as_ref().unwrap();
+        assert_eq!((openai.api_key.as_str(), openai.api_base.as_str()), ("sk-test", "https://gateway.corp/v1"));
+        assert_eq!(config.default_judge_model.as_deref(), Some("openai:gpt-4o"));
+        assert_eq!(config.resolve_model("smart"), "openai:gpt-4o");
+
+        let err = load("[providers.openai]\napi_key = \"${OPENAI_KEY}\"", &[]).unwrap_err();
+        assert!(matches!(&err, EvalError::Config(m) if m == "providers.openai.api_key refers to ${OPENAI_KEY}, which is not set"), "{}", err);
+        let err = load("[providers.openai]\napi_key = \"${OPENAI_KEY\"", &[]).unwrap_err();
+        assert!(matches!(&err, EvalError::Config(m) if m.contains("unclosed")), "{}", err);
+        assert_eq!(interpolate("k", "plain $HOME {x}", &|_| None).unwrap(), "plain $HOME {x}");
+    }
+
+    #[test]
+    fn test_environment_variables_win_over_the_file() {
+        let text = r#"
+            timeout_seconds = 30
+            [providers.openai]
+            api_key = "file-key"
+            models = ["gpt-4o", "gpt-4o-mini"]
+            rpm = 10
+            [providers.ollama]
+            [providers.vllm]
+            api_base = "http://gpu-box:8000/v1"
+            models = ["qwen"]
+            [retry]
+            max_retries = 5
+            backoff_ms = 50
+        "#;
+        let config = load(text, &[]).unwrap();
+        assert_eq!(config.openai.as_ref().unwrap().api_key, "file-key");
+        assert_eq!(config.openai.as_ref().unwrap().models, ["gpt-4o", "gpt-4o-mini"]);
+        assert_eq!(config.ollama.as_ref().unwrap().api_base, DEFAULT_OLLAMA_API_BASE);
+        assert_eq!(config.openai_compat["vllm"].models, ["qwen"]);
+        assert_eq!(config.http_client.timeout, Some(Duration::from_secs(30)));
+        assert_eq!(config.provider_retry.max_retries, 5);
+        assert_eq!(config.provider_retry.initial_backoff, Duration::from_millis(50));
+
+        let config = load(text, &[("OPENAI_API_KEY", "env-key"), ("PROVIDER_MAX_RETRIES", "1"), ("OLLAMA_MODELS", "phi3")]).unwrap();
+        assert_eq!(config.openai.as_ref().unwrap().api_key, "env-key");
+        assert_eq!(config.openai.as_ref().unwrap().models, ["gpt-4o", "gpt-4o-mini"]);
+        assert_eq!(config.ollama.unwrap().models, ["phi3"]);
+        assert_eq!(config.provider_retry.max_retries, 1);
+        assert_eq!(config.provider_retry.initial_backoff, Duration::from_millis(50));
+    }
+
+    #[test]
+    fn test_parse_errors_name_the_offending_key() {
+        let err = load("[providers.openai]\napi_key = \"k\"\nrpm = \"many\"", &[]).unwrap_err();
+        assert!(matches!(&err, EvalError::TomlParse(_)), "{}", err);
+        assert!(err.to_string().contains("rpm = \"many\""), "{}", err);
+        let err = load("[providers.openai]\napi_kee = \"k\"", &[]).unwrap_err();
+        assert!(err.to_string().contains("api_kee"), "{}", err);
+        let err = load("[providers.openai]\napi_key = \"k\"\nreferer = \"x\"", &[]).unwrap_err();
+        assert!(matches!(&err, EvalError::Config(m) if m.contains("providers.openai.referer")), "{}", err);
+        let err = load("[providers.vllm]\nmodels = [\"qwen\"]", &[]).unwrap_err();
+        assert!(matches!(&err, EvalError::Config(m) if m.contains("providers.vllm.api_base")), "{}", err);
+    }
+
+    #[test]
+    fn test_suites_and_evals_are_loaded_and_checked() {
+        let text = r#"
+            [providers.ollama]
+            [[suites]]
+            name = "smoke"
+            description = "Quick checks"
+            [[suites.evals]]
+            model = "ollama:llama3"
+            prompt = "What is 2+2?"
+            [[evals]]
+            model = "ollama:llama3"
+            prompt = "Say hi"
+        "#;
+        let config = load(text, &[]).unwrap();
+        let names: Vec<_> = config.suites.iter().map(|suite| (suite.name.as_str(), suite.evals.len())).collect();
+        assert_eq!(names, [("smoke", 1), ("evals", 1)]);
+
+        let err = load(&text.replace("ollama:llama3\"\n            prompt = \"Say hi", "openai:gpt-4o\"\n            prompt = \"Say hi"), &[]).unwrap_err();
+        assert!(matches!(&err, EvalError::Config(m) if m.contains("suite 'evals' evals[0].model provider 'openai' is not configured")), "{}", err);
+    }
+}
@@ -1,78 +1,319 @@
 // src/database.rs
 
+use crate::config::{EvalConfig, JudgeParams};
+use crate::dataset::{Adoption, DatasetFormat, DatasetRow, ExpectedSource, ReferenceOutput, StoredRow, EXPECTED_COLUMN};
+use crate::failure_taxonomy::FailureCategory;
+use crate::judge_ab::AbComparison;
+use crate::judge_calibration::{CalibrationSample, CalibrationSummary};
 use crate::models::{ApiResponse, EvalResult};
+use crate::providers::capture::RawExchange;
+use crate::providers::images::ImageRecord;
+use crate::redact::redact;
+use crate::providers::middleware::{ProviderErrorEvent, ProviderErrorSink};
+use crate::providers::{FinishReason, Generation, TokenUsage};
+use crate::robustness::{VariantOutcome, VariantSource};
+use crate::self_consistency::SelfConsistencyReport;
+use crate::runner::{verdict_status, JudgeError, JudgeVerdict, Judgement, UncertainReason};
 use sqlx::{
     migrate::Migrator,
-    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
-    Row, SqlitePool,
+    sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous},
 };
 use std::{
+    collections::BTreeMap,
     path::PathBuf,
     str::FromStr,
+    time::Duration,
 };
+use sha2::{Digest, Sha256};
 use chrono::Utc; // Import chrono::Utc for use in structs and functions
 
+mod backend;
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support;
+
+pub use backend::{query, query_scalar, Backend, DbPool, DbRow, Transaction};
+
 // =======================================================
 // Database Initialization
 // =======================================================
 
-/// Initializes the SQLite database connection pool.
-/// It ensures the necessary parent directory exists and runs migrations.
-pub async fn init_db() -> Result<SqlitePool, Box<dyn std::error::Error>> {
-    let db_path = get_db_path_for_fs()?;
-    
+/// Initializes the database connection pool on `DATABASE_URL`; see `init_db_with_url`.
+pub async fn init_db() -> Result<DbPool, Box<dyn std::error::Error>> {
+    let db_url = std::env::var("DATABASE_URL").map_err(|e| {
+        tracing::error!("❌ DATABASE_URL environment variable not set: {}", e);
+        sqlx::Error::Configuration("DATABASE_URL must be set".into())
+    })?;
+    init_db_with_url(&db_url).await
+}
+
+/// Initializes the database connection pool on `db_url`: Postgres for `postgres://` URLs (with
+/// the `postgres` feature), SQLite otherwise, creating the database file's directory if needed.
+/// `sqlite::memory:` opens a private in-memory database. Runs the migrations built into the
+/// binary and seeds the defaults before handing the pool out.
+pub async fn init_db_with_url(db_url: &str) -> Result<DbPool, Box<dyn std::error::Error>> {
     // 1. Extract and create the directory FIRST
-    if let Some(parent) = db_path.parent() {
-        if !parent.exists() {
-            println!("💾 Database directory does not exist, creating: {}", parent.display());
-            std::fs::create_dir_all(parent)?;
+    if !is_postgres_url(db_url) && !is_memory_url(db_url) {
+        let db_path = get_db_path_for_fs(db_url)?;
+        if let Some(parent) = db_path.parent() {
+            if !parent.exists() {
+                tracing::info!("💾 Database directory does not exist, creating: {}", parent.display());
+                std::fs::create_dir_all(parent)?;
+            }
         }
     }
-    
+
     // 2. Build the connection options using the original URL
-    let db_url = std::env::var("DATABASE_URL")?;
-    
-    // We connect with the original URL, which sqlx handles, after ensuring the directory exists.
-    let connection_options = SqliteConnectOptions::from_str(&db_url)?
-        .create_if_missing(true);
+    let max_connections = match std::env::var("DATABASE_MAX_CONNECTIONS") {
+        Ok(value) => match value.trim().parse::<u32>() {
+            Ok(n) if n >= 1 => n,
+            _ => return Err(format!("DATABASE_MAX_CONNECTIONS must be a positive integer, got '{}'", value).into()),
+        },
+        Err(_) => DEFAULT_MAX_CONNECTIONS,
+    };
 
-    println!("📦 Connecting to database using URL: {}", db_url);
+    tracing::info!("📦 Connecting to database using URL: {} (up to {} connections)", redact_password(db_url), max_connections);
 
     // 3. Connect and create pool
-    let pool = SqlitePoolOptions::new()
-        .connect_with(connection_options)
-        .await?;
+    let pool = connect_url(db_url, max_connections).await?;
         
-    // 4. Run migrations
-    run_migrations(&pool).await?;
+    // 4. Run migrations; a pool whose schema is not up to date is never handed out
+    if let Err(e) = run_migrations(&pool).await {
+        pool.close().await;
+        return Err(e);
+    }
 
-    println!("✅ Database connection successful and migrations applied.");
+    // 5. Make sure a judge prompt and a prompt version exist
+    seed_defaults(&pool).await?;
+
+    tracing::info!("✅ Database connection successful and migrations applied.");
     
     Ok(pool)
 }
 
-/// Helper function to retrieve and clean the database file path from the DATABASE_URL 
+/// Connections in the pool when `DATABASE_MAX_CONNECTIONS` is not set.
+const DEFAULT_MAX_CONNECTIONS: u32 = 10;
+
+/// How long a connection waits for another one's write lock before failing with `SQLITE_BUSY`.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn is_postgres_url(db_url: &str) -> bool {
+    db_url.starts_with("postgres://") || db_url.starts_with("postgresql://")
+}
+
+/// Whether `db_url` names an in-memory SQLite database, which has no file and lives only as
+/// long as the connection that opened it.
+fn is_memory_url(db_url: &str) -> bool {
+    db_url == "sqlite::memory:" || db_url.contains("mode=memory")
+}
+
+/// `db_url` with the password, if any, replaced, so the URL can be logged.
+fn redact_password(db_url: &str) -> String {
+    let Some((scheme, rest)) = db_url.split_once("://") else {
+        return db_url.to_string();
+    };
+    match rest.split_once('@') {
+        Some((credentials, host)) if credentials.contains(':') => {
+            let user = credentials.split(':').next().unwrap_or_default();
+            format!("{}://{}:***@{}", scheme, user, host)
+        }
+        _ => db_url.to_string(),
+    }
+}
+
+/// Opens a pool on `db_url`, picking the backend by its scheme.
+async fn connect_url(db_url: &str, max_connections: u32) -> Result<DbPool, Box<dyn std::error::Error>> {
+    if is_memory_url(db_url) {
+        return Ok(DbPool::Sqlite(connect_memory(db_url).await?));
+    }
+    if !is_postgres_url(db_url) {
+        return Ok(DbPool::Sqlite(connect(db_url, max_connections).await?));
+    }
+
+    #[cfg(feature = "postgres")]
+    {
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .max_connections(max_connections)
+            .connect(db_url)
+            .await?;
+        Ok(DbPool::Postgres(pool))
+    }
+    #[cfg(not(feature = "postgres"))]
+    {
+        Err("DATABASE_URL is a Postgres URL, but this binary was built without the 'postgres' feature; \
+             rebuild with `cargo build --features postgres` or use a sqlite: URL"
+            .into())
+    }
+}
+
+/// Runs `SELECT 1`, to check the database still answers.
+pub async fn ping(pool: &DbPool) -> Result<(), sqlx::Error> {
+    let _: i32 = query_scalar("SELECT 1").fetch_one(pool).await?;
+    Ok(())
+}
+
+/// Opens a SQLite pool on `db_url`, creating the file if missing. WAL lets readers run
+/// alongside the single writer, and the busy timeout makes concurrent writers queue for the lock.
+pub(crate) async fn connect(db_url: &str, max_connections: u32) -> Result<sqlx::SqlitePool, sqlx::Error> {
+    // We connect with the original URL, which sqlx handles, after ensuring the directory exists.
+    let connection_options = SqliteConnectOptions::from_str(db_url)?
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Wal)
+        .synchronous(SqliteSynchronous::Normal)
+        .busy_timeout(BUSY_TIMEOUT)
+        .foreign_keys(true);
+
+    SqlitePoolOptions::new()
+        .max_connections(max_connections)
+        .connect_with(connection_options)
+        .await
+}
+
+/// Opens a pool on an in-memory SQLite database: one connection, never closed, since every
+/// new connection to `sqlite::memory:` opens a database of its own.
+pub(crate) async fn connect_memory(db_url: &str) -> Result<sqlx::SqlitePool, sqlx::Error> {
+    let connection_options = SqliteConnectOptions::from_str(db_url)?.foreign_keys(true);
+
+    SqlitePoolOptions::new()
+        .max_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect_with(connection_options)
+        .await
+}
+
+/// Helper function to retrieve and clean the database file path from a SQLite DATABASE_URL
 /// for **File System (FS) operations** (i.e., directory creation).
-fn get_db_path_for_fs() -> Result<PathBuf, sqlx::Error> {
-    let db_url = std::env::var("DATABASE_URL").map_err(|e| {
-        eprintln!("❌ DATABASE_URL environment variable not set: {}", e);
-        sqlx::Error::Configuration("DATABASE_URL must be set".into())
-    })?;
-    
+fn get_db_path_for_fs(db_url: &str) -> Result<PathBuf, sqlx::Error> {
     // Remove the "sqlite:" prefix
     let db_path_str = db_url.strip_prefix("sqlite:").ok_or_else(|| {
-        eprintln!("❌ DATABASE_URL must start with 'sqlite:' but got: {}", db_url);
-        sqlx::Error::Configuration("DATABASE_URL must start with 'sqlite:'".into())
+        tracing::error!("❌ DATABASE_URL must start with 'sqlite:' or 'postgres://' but got: {}", db_url);
+        sqlx::Error::Configuration("DATABASE_URL must start with 'sqlite:' or 'postgres://'".into())
     })?;
     
     // We return a simple PathBuf, which is what std::fs::create_dir_all expects.
     Ok(PathBuf::from(db_path_str))
 }
 
-/// Runs the database migrations located in the 'migrations' directory.
-async fn run_migrations(pool: &SqlitePool) -> Result<(), Box<dyn std::error::Error>> {
-    let migrator = Migrator::new(std::path::Path::new("./migrations")).await?;
-    migrator.run(pool).await?;
+/// The files of the 'migrations' directory, compiled into the binary.
+static EMBEDDED_MIGRATIONS: Migrator = sqlx::migrate!("./migrations");
+
+/// The files of the 'migrations_postgres' directory, the Postgres twins of 'migrations'.
+#[cfg(feature = "postgres")]
+static EMBEDDED_POSTGRES_MIGRATIONS: Migrator = sqlx::migrate!("./migrations_postgres");
+
+async fn run_migrator(migrator: &Migrator, pool: &DbPool) -> Result<(), sqlx::migrate::MigrateError> {
+    match pool {
+        DbPool::Sqlite(pool) => migrator.run(pool).await,
+        #[cfg(feature = "postgres")]
+        DbPool::Postgres(pool) => migrator.run(pool).await,
+    }
+}
+
+/// Runs the migrations compiled into the binary. When they fail and `MIGRATIONS_DIR` is set,
+/// the migrations in that directory are run instead, so a development build can apply
+/// migrations written (or already applied by another build) since it was compiled.
+pub(crate) async fn run_migrations(pool: &DbPool) -> Result<(), Box<dyn std::error::Error>> {
+    let (embedded, dir_name) = match pool.backend() {
+        Backend::Sqlite => (&EMBEDDED_MIGRATIONS, "migrations"),
+        #[cfg(feature = "postgres")]
+        Backend::Postgres => (&EMBEDDED_POSTGRES_MIGRATIONS, "migrations_postgres"),
+    };
+    let embedded_error = match run_migrator(embedded, pool).await {
+        Ok(()) => return Ok(()),
+        Err(e) => e,
+    };
+
+    let Some(dir) = std::env::var("MIGRATIONS_DIR").ok().filter(|dir| !dir.trim().is_empty()) else {
+        return Err(format!(
+            "Failed to apply the migrations built into this binary: {}. If the database was migrated \
+             by a newer build, run that build, or set MIGRATIONS_DIR to its '{}' directory",
+            embedded_error, dir_name
+        )
+        .into());
+    };
+
+    tracing::warn!("⚠️  Built-in migrations failed ({}); trying MIGRATIONS_DIR={}", embedded_error, dir);
+    let migrator = Migrator::new(std::path::Path::new(&dir)).await.map_err(|e| {
+        format!(
+            "Failed to apply the built-in migrations ({}), and could not read migrations from \
+             MIGRATIONS_DIR={} ({}). Point MIGRATIONS_DIR at a '{}' directory, or unset it",
+            embedded_error, dir, e, dir_name
+        )
+    })?;
+    run_migrator(&migrator, pool).await.map_err(|e| {
+        format!(
+            "Failed to apply the built-in migrations ({}) and the migrations in MIGRATIONS_DIR={} ({})",
+            embedded_error, dir, e
+        )
+    })?;
+    Ok(())
+}
+
+/// Name given to the prompt version seeded into an empty database.
+const DEFAULT_PROMPT_VERSION_NAME: &str = "Default Prompt";
+
+/// Inserts the default judge prompt and a pass-through prompt version as active version 1 of
+/// their tables when those tables are empty, so a fresh database has an active version of each.
+/// Tables that already hold rows are left alone, so running it again changes nothing.
+pub(crate) async fn seed_defaults(pool: &DbPool) -> Result<(), sqlx::Error> {
+    let created_at = Utc::now().to_rfc3339();
+    let mut tx = pool.begin().await?;
+
+    let judge_prompt = query(
+        r#"
+        INSERT INTO judge_prompts (version, name, template, description, is_active, created_at)
+        SELECT 1, 'Default Judge Prompt', ?, 'Default prompt for LLM-as-a-judge evaluation', TRUE, ?
+        WHERE NOT EXISTS (SELECT 1 FROM judge_prompts)
+        "#
+    )
+    .bind(crate::runner::get_default_judge_prompt_template())
+    .bind(&created_at)
+    .execute(&mut tx)
+    .await?;
+
+    let prompt_version = query(
+        r#"
+        INSERT INTO prompt_versions (version, name, prompt_template, description, is_active, created_at)
+        SELECT 1, ?, '{{prompt}}', 'Sends the prompt unchanged', TRUE, ?
+        WHERE NOT EXISTS (SELECT 1 FROM prompt_versions)
+        "#
+    )
+    .bind(DEFAULT_PROMPT_VERSION_NAME)
+    .bind(&created_at)
+    .execute(&mut tx)
+    .await?;
+
+    let has_failure_categories: bool = query_scalar("SELECT EXISTS (SELECT 1 FROM failure_categories)")
+        .fetch_one(&mut tx)
+        .await?;
+    if !has_failure_categories {
+        insert_failure_categories(&mut tx, &crate::failure_taxonomy::default_taxonomy()).await?;
+    }
+
+    // Rows inserted with an explicit version do not advance a Postgres identity column
+    #[cfg(feature = "postgres")]
+    if pool.backend() == Backend::Postgres {
+        for table in ["judge_prompts", "prompt_versions"] {
+            query(&format!(
+                "SELECT setval(pg_get_serial_sequence('{0}', 'version'), (SELECT MAX(version) FROM {0}))",
+                table
+            ))
+            .execute(&mut tx)
+            .await?;
+        }
+    }
+
+    tx.commit().await?;
+
+    if judge_prompt.rows_affected() > 0 {
+        tracing::info!("🌱 Seeded the default judge prompt as version 1");
+    }
+    if prompt_version.rows_affected() > 0 {
+        tracing::info!("🌱 Seeded the default prompt version as version 1");
+    }
+    if !has_failure_categories {
+        tracing::info!("🌱 Seeded the default failure taxonomy");
+    }
     Ok(())
 }
 
@@ -80,8 +321,9 @@ async fn run_migrations(pool: &SqlitePool) -> Result<(), Box<dyn std::error::Err
 // =======================================================
 // Save and retrieve evaluations
 // =======================================================
-
-pub async fn save_evaluation(pool: &SqlitePool, response: &ApiResponse) -> Result<(), sqlx::Error> {
+/// Saves an evaluation and its tags, with secrets masked from its error messages; see
+/// `redact::redact`.
+pub async fn save_evaluation(pool: &DbPool, response: &ApiResponse) -> Result<(), sqlx::Error> {
     let id = &response.id;
     let status = response.status.to_string();
 
@@ -102,6 +344,14 @@ pub async fn save_evaluation(pool: &SqlitePool, response: &ApiResponse) -> Resul
         judge_output_tokens,
         created_at,
         judge_prompt_version,
+        judge_confidence,
+        needs_review,
+        cost_usd,
+        judge_cost_usd,
+        judge_raw_response,
+        judge_prompt_arm,
+        tags,
+        uncertain_reason,
     ) = match &response.result {
         EvalResult::Success(res) => (
             Some(res.model.clone()),
@@ -120,25 +370,77 @@ pub async fn save_evaluation(pool: &SqlitePool, response: &ApiResponse) -> Resul
             res.judge_token_usage.as_ref().and_then(|u| u.output_tokens.map(|t| t as i64)),
             Some(res.timestamp.clone()),
             res.judge_prompt_version,
+            res.judge_result.as_ref().and_then(|j| j.confidence),
+            res.needs_review,
+            res.cost_usd,
+            res.judge_cost_usd,
+            res.judge_result.as_ref().and_then(|j| j.raw_response.clone()),
+            res.judge_prompt_arm.map(|arm| arm.as_str()),
+            (!res.tags.is_empty()).then(|| serde_json::to_string(&res.tags).unwrap_or_default()),
+            res.judge_result.as_ref().and_then(|j| j.uncertain_reason).map(|r| r.as_str()),
         ),
         EvalResult::Error(err) => (
             None, None, None, None, None, None, None,
-            Some(err.message.clone()),
-            None, None, None, None, None, None, None, None,
+            Some(redact(&err.message)),
+            None, None, None, None, None, None, None, None, None, false, None, None, None, None, None, None,
         ),
     };
 
     let created_at_str = created_at.unwrap_or_else(|| Utc::now().to_rfc3339());
+    let (truncation, judge_batch_size, finish_reason, trace, failure) = match &response.result {
+        EvalResult::Success(res) => (
+            res.truncation.as_ref(),
+            res.judge_batch_size.map(|n| n as i64),
+            res.finish_reason.as_ref().map(|r| r.as_str()),
+            res.trace.as_deref().and_then(crate::trace::to_stored_json),
+            res.failure_classification.as_ref(),
+        ),
+        EvalResult::Error(_) => (None, None, None, None, None),
+    };
+    let (parsed_output, criteria, metadata, dataset_row, resolved_model, model_alias, response_format, reasoning, images, judge_prompt_rendered, judge_params, judge_error, parse_method, output_diff, chain_step, wait_ms, provider_override, output_chars) = match &response.result {
+        EvalResult::Success(res) => (
+            res.parsed_output.as_ref().map(|parsed| parsed.to_string()),
+            res.criteria.as_deref(),
+            res.metadata.as_ref().map(|metadata| metadata.to_string()),
+            res.dataset_row.as_ref(),
+            res.resolved_model.as_deref(),
+            res.model_alias.as_deref(),
+            res.response_format.as_deref(),
+            res.reasoning.as_deref(),
+            Some(&res.images).filter(|images| !images.is_empty()).and_then(|images| serde_json::to_string(images).ok()),
+            res.judge_prompt_rendered.as_deref(),
+            res.judge_params.as_ref().and_then(|params| serde_json::to_string(params).ok()),
+            res.judge_error.as_ref().and_then(|error| serde_json::to_string(error).ok()).map(|error| redact(&error)),
+            res.parse_method.map(|method| method.as_str()),
+            res.output_diff.as_ref().map(|diff| diff.to_string()),
+            res.chain_step.as_ref(),
+            res.wait_ms.map(|ms| ms as i64),
+            res.provider_override.as_ref().and_then(|used| serde_json::to_string(used).ok()),
+            Some(res.full_output_chars.unwrap_or_else(|| res.model_output.chars().count()) as i64),
+        ),
+        EvalResult::Error(_) => (None, None, None, None, None, None, None, None, None, None, None, None, None, None, None, None, None, None),
+    };
 
-    sqlx::query(
+    let mut tx = pool.begin().await?;
+    query(
         r#" 
         INSERT INTO evaluations (
             id, status, model, prompt, model_output, expected, 
             judge_model, judge_verdict, judge_reasoning, error_message, 
             latency_ms, judge_latency_ms, input_tokens, output_tokens, 
-            judge_input_tokens, judge_output_tokens, created_at, judge_prompt_version
+            judge_input_tokens, judge_output_tokens, created_at, judge_prompt_version,
+            judge_confidence, needs_review, cost_usd, judge_cost_usd,
+            batch_id, config_hash, judge_raw_response,
+            truncation_strategy, truncation_original_tokens, truncation_truncated_tokens,
+            judge_prompt_arm, tags, uncertain_reason, judge_batch_size, finish_reason, trace,
+            failure_category, failure_justification, failure_classifier, failure_classification_cost_usd,
+            parsed_output, criteria, metadata, preset,
+            dataset_id, dataset_row_index, expected_source, resolved_model, response_format, reasoning, images,
+            judge_prompt_rendered, model_alias, judge_params, judge_error, parse_method, output_diff,
+            chain_id, chain_step, wait_ms, provider_override, output_chars, output_truncated, prompt_version,
+            self_consistency, prompt_template
         )
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#
     )
     .bind(id)
@@ -159,32 +461,137 @@ pub async fn save_evaluation(pool: &SqlitePool, response: &ApiResponse) -> Resul
     .bind(judge_output_tokens)
     .bind(&created_at_str)
     .bind(judge_prompt_version)
-    .execute(pool)
+    .bind(judge_confidence)
+    .bind(needs_review)
+    .bind(cost_usd)
+    .bind(judge_cost_usd)
+    .bind(&response.batch_id)
+    .bind(&response.config_hash)
+    .bind(&judge_raw_response)
+    .bind(truncation.map(|t| t.strategy.as_str()))
+    .bind(truncation.map(|t| t.original_tokens as i64))
+    .bind(truncation.map(|t| t.truncated_tokens as i64))
+    .bind(judge_prompt_arm)
+    .bind(&tags)
+    .bind(uncertain_reason)
+    .bind(judge_batch_size)
+    .bind(finish_reason)
+    .bind(&trace)
+    .bind(failure.map(|f| f.category.as_str()))
+    .bind(failure.and_then(|f| f.justification.as_deref()))
+    .bind(failure.map(|f| f.classifier.as_str()))
+    .bind(failure.and_then(|f| f.cost_usd))
+    .bind(&parsed_output)
+    .bind(criteria)
+    .bind(&metadata)
+    .bind(response.preset.as_ref().and_then(|preset| serde_json::to_string(preset).ok()))
+    .bind(dataset_row.map(|row| row.dataset_id.as_str()))
+    .bind(dataset_row.map(|row| row.row_index))
+    .bind(dataset_row.and_then(|row| row.expected_source.as_ref()).and_then(|source| serde_json::to_string(source).ok()))
+    .bind(resolved_model)
+    .bind(response_format)
+    .bind(reasoning)
+    .bind(&images)
+    .bind(judge_prompt_rendered)
+    .bind(model_alias)
+    .bind(&judge_params)
+    .bind(&judge_error)
+    .bind(parse_method)
+    .bind(&output_diff)
+    .bind(chain_step.map(|step| step.chain_id.as_str()))
+    .bind(chain_step.map(|step| step.step))
+    .bind(wait_ms)
+    .bind(&provider_override)
+    .bind(output_chars)
+    .bind(matches!(&response.result, EvalResult::Success(res) if res.full_output_chars.is_some()))
+    .bind(match &response.result {
+        EvalResult::Success(res) => res.prompt_version,
+        EvalResult::Error(_) => None,
+    })
+    .bind(match &response.result {
+        EvalResult::Success(res) => res.self_consistency.as_ref().and_then(|report| serde_json::to_string(report).ok()),
+        EvalResult::Error(_) => None,
+    })
+    .bind(match &response.result {
+        EvalResult::Success(res) => res.prompt_template.as_deref(),
+        EvalResult::Error(_) => None,
+    })
+    .execute(&mut tx)
     .await?;
 
+    if let EvalResult::Success(res) = &response.result {
+        insert_eval_tags(&mut tx, id, &res.tags, &created_at_str).await?;
+    }
+    tx.commit().await
+}
+
+/// Adds `tags` to the `eval_tags` rows of evaluation `id`, skipping those it already has.
+async fn insert_eval_tags(tx: &mut Transaction, id: &str, tags: &[String], tagged_at: &str) -> Result<(), sqlx::Error> {
+    for tag in tags {
+        query("INSERT INTO eval_tags (evaluation_id, tag, tagged_at) VALUES (?, ?, ?) ON CONFLICT (evaluation_id, tag) DO NOTHING")
+            .bind(id)
+            .bind(tag)
+            .bind(tagged_at)
+            .execute(&mut *tx)
+            .await?;
+    }
     Ok(())
 }
 
+/// Attempts `save_evaluation_retrying` makes before giving up on a busy database.
+const SAVE_ATTEMPTS: u32 = 3;
+
+/// Whether `error` is SQLite reporting the database as busy or locked, which waiting may resolve.
+fn is_busy(error: &sqlx::Error) -> bool {
+    let sqlx::Error::Database(e) = error else {
+        return false;
+    };
+    // Extended result codes keep the primary code (SQLITE_BUSY = 5, SQLITE_LOCKED = 6) in the low byte
+    e.code()
+        .and_then(|code| code.parse::<i32>().ok())
+        .is_some_and(|code| matches!(code & 0xff, 5 | 6))
+}
+
+/// `save_evaluation`, retried with a short backoff while the database is busy. Used for the
+/// saves of a batch, which many requests may be writing at once.
+pub async fn save_evaluation_retrying(pool: &DbPool, response: &ApiResponse) -> Result<(), sqlx::Error> {
+    let mut attempt = 1;
+    loop {
+        match save_evaluation(pool, response).await {
+            Err(e) if attempt < SAVE_ATTEMPTS && is_busy(&e) => {
+                tracing::warn!("Database busy saving evaluation {} (attempt {}): {}", response.id, attempt, e);
+                tokio::time::sleep(Duration::from_millis(50 * 2u64.pow(attempt - 1))).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
 // =======================================================
 // Query evaluations
 // =======================================================
 
-pub async fn get_all_evaluations(pool: &SqlitePool) -> Result<Vec<HistoryEntry>, sqlx::Error> {
-    let rows = sqlx::query(
-        r#"
-        SELECT 
-            id, status, model, prompt, model_output, expected, 
-            judge_model, judge_verdict, judge_reasoning, error_message, 
-            latency_ms, judge_latency_ms, input_tokens, output_tokens, 
-            judge_input_tokens, judge_output_tokens, created_at, judge_prompt_version
-        FROM evaluations
-        ORDER BY created_at DESC
-        "#
-    )
-    .fetch_all(pool)
-    .await?;
+/// Columns selected for a `HistoryEntry`, in the order `history_entry_from_row` expects.
+const HISTORY_COLUMNS: &str = r#"
+    id, status, model, prompt, model_output, expected,
+    judge_model, judge_verdict, judge_reasoning, error_message,
+    latency_ms, judge_latency_ms, input_tokens, output_tokens,
+    judge_input_tokens, judge_output_tokens, created_at, judge_prompt_version,
+    judge_confidence, needs_review, human_verdict, reviewed_at,
+    cost_usd, judge_cost_usd, batch_id, config_hash, judge_raw_response,
+    truncation_strategy, truncation_original_tokens, truncation_truncated_tokens,
+    judge_prompt_arm, tags, uncertain_reason, judge_batch_size, finish_reason, trace,
+    failure_category, failure_justification, failure_classifier,
+    parsed_output, criteria, metadata, preset,
+    dataset_id, dataset_row_index, expected_source, resolved_model, response_format, reasoning, images,
+    judge_prompt_rendered, model_alias, judge_params, judge_error, parse_method, output_diff,
+    chain_id, chain_step, wait_ms, provider_override, output_chars, output_truncated, prompt_version,
+    self_consistency, prompt_template
+"#;
 
-    Ok(rows.into_iter().map(|row| HistoryEntry {
+fn history_entry_from_row(row: &DbRow) -> HistoryEntry {
+    HistoryEntry {
         id: row.get(0),
         status: row.get(1),
         model: row.get(2),
@@ -203,7 +610,397 @@ pub async fn get_all_evaluations(pool: &SqlitePool) -> Result<Vec<HistoryEntry>,
         judge_output_tokens: row.get(15),
         created_at: row.get(16),
         judge_prompt_version: row.get(17),
-    }).collect())
+        judge_confidence: row.get(18),
+        needs_review: row.get(19),
+        human_verdict: row.get(20),
+        reviewed_at: row.get(21),
+        cost_usd: row.get(22),
+        judge_cost_usd: row.get(23),
+        batch_id: row.get(24),
+        config_hash: row.get(25),
+        judge_raw_response: row.get(26),
+        truncation_strategy: row.get(27),
+        truncation_original_tokens: row.get(28),
+        truncation_truncated_tokens: row.get(29),
+        judge_prompt_arm: row.get(30),
+        tags: row
+            .get::<Option<String>, _>(31)
+            .and_then(|tags| serde_json::from_str(&tags).ok())
+            .unwrap_or_default(),
+        uncertain_reason: row.get(32),
+        judge_batch_size: row.get(33),
+        finish_reason: row.get(34),
+        trace: row
+            .get::<Option<String>, _>(35)
+            .and_then(|trace| serde_json::from_str(&trace).ok()),
+        failure_category: row.get(36),
+        failure_justification: row.get(37),
+        failure_classifier: row.get(38),
+        parsed_output: row
+            .get::<Option<String>, _>(39)
+            .and_then(|parsed| serde_json::from_str(&parsed).ok()),
+        criteria: row.get(40),
+        metadata: row
+            .get::<Option<String>, _>(41)
+            .and_then(|metadata| serde_json::from_str(&metadata).ok()),
+        preset: row
+            .get::<Option<String>, _>(42)
+            .and_then(|preset| serde_json::from_str(&preset).ok()),
+        dataset_id: row.get(43),
+        dataset_row_index: row.get(44),
+        expected_source: row
+            .get::<Option<String>, _>(45)
+            .and_then(|source| serde_json::from_str(&source).ok()),
+        resolved_model: row.get(46),
+        response_format: row.get(47),
+        reasoning: row.get(48),
+        images: row
+            .get::<Option<String>, _>(49)
+            .and_then(|images| serde_json::from_str(&images).ok())
+            .unwrap_or_default(),
+        judge_prompt_rendered: row.get(50),
+        model_alias: row.get(51),
+        judge_params: row
+            .get::<Option<String>, _>(52)
+            .and_then(|params| serde_json::from_str(&params).ok()),
+        judge_error: row
+            .get::<Option<String>, _>(53)
+            .and_then(|error| serde_json::from_str(&error).ok()),
+        parse_method: row.get(54),
+        output_diff: row
+            .get::<Option<String>, _>(55)
+            .and_then(|diff| serde_json::from_str(&diff).ok()),
+        chain_id: row.get(56),
+        chain_step: row.get(57),
+        wait_ms: row.get(58),
+        provider_override: row
+            .get::<Option<String>, _>(59)
+            .and_then(|used| serde_json::from_str(&used).ok()),
+        output_chars: row.get(60),
+        output_truncated: row.get(61),
+        prompt_version: row.get(62),
+        self_consistency: row
+            .get::<Option<String>, _>(63)
+            .and_then(|report| serde_json::from_str(&report).ok()),
+        prompt_template: row.get(64),
+    }
+}
+
+/// Evaluations saved under `batch_id`, oldest first.
+pub async fn get_batch_evaluations(pool: &DbPool, batch_id: &str) -> Result<Vec<HistoryEntry>, sqlx::Error> {
+    let rows = query(&format!(
+        "SELECT {} FROM evaluations WHERE batch_id = ? ORDER BY created_at ASC",
+        HISTORY_COLUMNS
+    ))
+    .bind(batch_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(history_entry_from_row).collect())
+}
+
+/// Resolves a run id to a batch id: suite run ids map to their batch, anything else is
+/// taken to be a batch id already.
+pub async fn resolve_batch_id(pool: &DbPool, run_id: &str) -> Result<String, sqlx::Error> {
+    let batch_id: Option<String> = query_scalar("SELECT batch_id FROM suite_runs WHERE id = ?")
+        .bind(run_id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(batch_id.unwrap_or_else(|| run_id.to_string()))
+}
+
+pub async fn get_evaluation(pool: &DbPool, id: &str) -> Result<HistoryEntry, sqlx::Error> {
+    let row = query(&format!("SELECT {} FROM evaluations WHERE id = ?", HISTORY_COLUMNS))
+        .bind(id)
+        .fetch_one(pool)
+        .await?;
+
+    Ok(history_entry_from_row(&row))
+}
+
+pub async fn get_all_evaluations(pool: &DbPool) -> Result<Vec<HistoryEntry>, sqlx::Error> {
+    get_evaluations(pool, &HistoryFilter::default()).await
+}
+
+/// Narrows the evaluation history; unset fields match everything.
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct HistoryFilter {
+    pub uncertain_reason: Option<String>,
+    /// Only evaluations carrying these comma-separated tags, all or any of them as `tag_match` says.
+    pub tag: Option<String>,
+    #[serde(default)]
+    pub tag_match: TagMatch,
+    /// Only evaluations whose provider stopped for this normalized reason, e.g. `length`.
+    pub finish_reason: Option<String>,
+}
+
+/// How the tags of a comma-separated `tag` filter combine.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TagMatch {
+    /// Evaluations carrying every tag
+    #[default]
+    All,
+    /// Evaluations carrying at least one of the tags
+    Any,
+}
+
+/// The tags of a comma-separated `tag` filter as the JSON array `tag_condition` binds, or
+/// `None` when there are none, to match everything.
+fn tags_param(tag: Option<&str>) -> Option<String> {
+    let tags: Vec<&str> = tag?.split(',').map(str::trim).filter(|tag| !tag.is_empty()).collect();
+    (!tags.is_empty()).then(|| serde_json::Value::from(tags).to_string())
+}
+
+/// A `WHERE` condition on the `eval_tags` of `evaluations`: the `tags_param` array bound to
+/// `?{tags}` is NULL, or the evaluation carries all its tags, or any when `?{any}` is true.
+fn tag_condition(backend: Backend, tags: usize, any: usize) -> String {
+    let requested = backend.json_array_elements(&format!("?{}", tags));
+    format!(
+        r#"(?{tags} IS NULL OR CASE WHEN ?{any}
+            THEN EXISTS (
+                SELECT 1 FROM eval_tags
+                WHERE eval_tags.evaluation_id = evaluations.id
+                  AND eval_tags.tag IN (SELECT json_each.value FROM {requested})
+            )
+            ELSE NOT EXISTS (
+                SELECT 1 FROM {requested}
+                WHERE json_each.value NOT IN (SELECT eval_tags.tag FROM eval_tags WHERE eval_tags.evaluation_id = evaluations.id)
+            )
+        END)"#,
+        tags = tags,
+        any = any,
+        requested = requested
+    )
+}
+
+/// `columns` of the evaluations matching a `HistoryFilter`, newest first; bind
+/// `uncertain_reason`, the `tags_param` of `tag`, `finish_reason` and whether `tag_match` is
+/// `any`, in that order.
+fn filtered_history_sql(pool: &DbPool, columns: &str) -> String {
+    format!(
+        r#"
+        SELECT {} FROM evaluations
+        WHERE (?1 IS NULL OR uncertain_reason = ?1)
+          AND {}
+          AND (?3 IS NULL OR finish_reason = ?3)
+        ORDER BY created_at DESC
+        "#,
+        columns,
+        tag_condition(pool.backend(), 2, 4)
+    )
+}
+
+/// Evaluations matching `filter`, newest first.
+pub async fn get_evaluations(pool: &DbPool, filter: &HistoryFilter) -> Result<Vec<HistoryEntry>, sqlx::Error> {
+    let rows = query(&filtered_history_sql(pool, HISTORY_COLUMNS))
+        .bind(&filter.uncertain_reason)
+        .bind(tags_param(filter.tag.as_deref()))
+        .bind(&filter.finish_reason)
+        .bind(filter.tag_match == TagMatch::Any)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows.iter().map(history_entry_from_row).collect())
+}
+
+/// Summaries of the evaluations matching `filter`, newest first. Only the previews of the
+/// prompt, output and error are read, not the texts.
+pub async fn get_evaluation_summaries(pool: &DbPool, filter: &HistoryFilter) -> Result<Vec<HistorySummary>, sqlx::Error> {
+    let columns = format!(
+        r#"
+        id, status, model, judge_model, judge_verdict, human_verdict, needs_review, uncertain_reason,
+        latency_ms, judge_latency_ms, input_tokens, output_tokens, judge_input_tokens, judge_output_tokens,
+        cost_usd, judge_cost_usd, created_at, batch_id, tags,
+        SUBSTR(prompt, 1, {preview}), SUBSTR(model_output, 1, {preview}), SUBSTR(error_message, 1, {preview})
+        "#,
+        // One character more than is kept, to tell whether the text goes on
+        preview = SUMMARY_PREVIEW_CHARS + 1
+    );
+    let rows = query(&filtered_history_sql(pool, &columns))
+        .bind(&filter.uncertain_reason)
+        .bind(tags_param(filter.tag.as_deref()))
+        .bind(&filter.finish_reason)
+        .bind(filter.tag_match == TagMatch::Any)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| HistorySummary {
+            id: row.get(0),
+            status: row.get(1),
+            model: row.get(2),
+            judge_model: row.get(3),
+            judge_verdict: row.get(4),
+            human_verdict: row.get(5),
+            needs_review: row.get(6),
+            uncertain_reason: row.get(7),
+            latency_ms: row.get(8),
+            judge_latency_ms: row.get(9),
+            input_tokens: row.get(10),
+            output_tokens: row.get(11),
+            judge_input_tokens: row.get(12),
+            judge_output_tokens: row.get(13),
+            cost_usd: row.get(14),
+            judge_cost_usd: row.get(15),
+            created_at: row.get(16),
+            batch_id: row.get(17),
+            tags: row
+                .get::<Option<String>, _>(18)
+                .and_then(|tags| serde_json::from_str(&tags).ok())
+                .unwrap_or_default(),
+            prompt_preview: row.get::<Option<String>, _>(19).map(summary_preview),
+            output_preview: row.get::<Option<String>, _>(20).map(summary_preview),
+            error_preview: row.get::<Option<String>, _>(21).map(summary_preview),
+        })
+        .collect())
+}
+
+/// A tag of the stored evaluations that changes whenever one is saved, purged, reviewed,
+/// rejudged or retagged, read with one small query. Responses computed from the evaluations,
+/// such as the history and the stats, are cached by clients under it.
+pub async fn evaluations_etag(pool: &DbPool) -> Result<String, sqlx::Error> {
+    let row = query(
+        r#"
+        SELECT
+            (SELECT COUNT(*) FROM evaluations),
+            (SELECT MAX(created_at) FROM evaluations),
+            (SELECT MAX(reviewed_at) FROM evaluations),
+            (SELECT COUNT(*) FROM judgements),
+            (SELECT COUNT(*) FROM eval_tags),
+            (SELECT MAX(tagged_at) FROM eval_tags)
+        "#
+    )
+    .fetch_one(pool)
+    .await?;
+    let state = format!(
+        "{}|{}|{}|{}|{}|{}",
+        row.get::<i64, _>(0),
+        row.get::<Option<String>, _>(1).unwrap_or_default(),
+        row.get::<Option<String>, _>(2).unwrap_or_default(),
+        row.get::<i64, _>(3),
+        row.get::<i64, _>(4),
+        row.get::<Option<String>, _>(5).unwrap_or_default(),
+    );
+    let digest = Sha256::digest(state.as_bytes());
+    Ok(digest[..8].iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/// Up to `limit` evaluations created before `cutoff`, oldest first.
+pub async fn get_evaluations_before(pool: &DbPool, cutoff: &str, limit: i64) -> Result<Vec<HistoryEntry>, sqlx::Error> {
+    let rows = query(&format!(
+        "SELECT {} FROM evaluations WHERE created_at < ? ORDER BY created_at ASC, id ASC LIMIT ?",
+        HISTORY_COLUMNS
+    ))
+    .bind(cutoff)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(history_entry_from_row).collect())
+}
+
+/// Tables holding rows that belong to a single evaluation, deleted along with it. Robustness
+/// variants are kept: they describe their group, which reports purged variants as missing.
+const EVALUATION_DEPENDENTS: [&str; 7] = [
+    "eval_tags",
+    "judgements",
+    "judge_ab_results",
+    "raw_exchanges",
+    "shares",
+    "suite_run_evaluations",
+    "prompt_evaluations",
+];
+
+/// Deletes evaluations and the rows that belong to them, in one transaction. Returns how
+/// many evaluations were deleted.
+pub async fn purge_evaluations(pool: &DbPool, ids: &[String]) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let mut purged = 0;
+    for id in ids {
+        for table in EVALUATION_DEPENDENTS {
+            query(&format!("DELETE FROM {} WHERE evaluation_id = ?", table))
+                .bind(id)
+                .execute(&mut tx)
+                .await?;
+        }
+        purged += query("DELETE FROM evaluations WHERE id = ?")
+            .bind(id)
+            .execute(&mut tx)
+            .await?
+            .rows_affected();
+    }
+    tx.commit().await?;
+    Ok(purged)
+}
+
+// =======================================================
+// Evaluation tags
+// =======================================================
+
+/// A tag and how many evaluations carry it.
+#[derive(serde::Serialize, Debug, PartialEq)]
+pub struct TagCount {
+    pub tag: String,
+    pub evaluations: i64,
+}
+
+/// Every tag of the stored evaluations, most used first.
+pub async fn get_tag_counts(pool: &DbPool) -> Result<Vec<TagCount>, sqlx::Error> {
+    let rows = query("SELECT tag, COUNT(*) FROM eval_tags GROUP BY tag ORDER BY COUNT(*) DESC, tag ASC")
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows.iter().map(|row| TagCount { tag: row.get(0), evaluations: row.get(1) }).collect())
+}
+
+/// Adds `add` to and removes `remove` from the tags of evaluation `id`, keeping the order of
+/// the tags it keeps and appending new ones. Returns the tags it ends up with, or
+/// `RowNotFound` when there is no such evaluation.
+pub async fn update_evaluation_tags(
+    pool: &DbPool,
+    id: &str,
+    add: &[String],
+    remove: &[String],
+) -> Result<Vec<String>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let stored: Option<String> = query_scalar("SELECT tags FROM evaluations WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&mut tx)
+        .await?
+        .ok_or(sqlx::Error::RowNotFound)?;
+    let mut tags: Vec<String> = stored.and_then(|tags| serde_json::from_str(&tags).ok()).unwrap_or_default();
+    tags.retain(|tag| !remove.contains(tag));
+    let added: Vec<String> = add.iter().filter(|tag| !tags.contains(tag)).cloned().collect();
+    tags.extend(added.iter().cloned());
+
+    query("UPDATE evaluations SET tags = ? WHERE id = ?")
+        .bind((!tags.is_empty()).then(|| serde_json::to_string(&tags).unwrap_or_default()))
+        .bind(id)
+        .execute(&mut tx)
+        .await?;
+    for tag in remove {
+        query("DELETE FROM eval_tags WHERE evaluation_id = ? AND tag = ?")
+            .bind(id)
+            .bind(tag)
+            .execute(&mut tx)
+            .await?;
+    }
+    insert_eval_tags(&mut tx, id, &added, &Utc::now().to_rfc3339()).await?;
+
+    tx.commit().await?;
+    Ok(tags)
+}
+
+/// Returns the space of deleted rows to the filesystem. SQLite only; Postgres reclaims it
+/// with autovacuum.
+pub async fn vacuum(pool: &DbPool) -> Result<(), sqlx::Error> {
+    if let Some(pool) = pool.as_sqlite() {
+        sqlx::query("VACUUM").execute(pool).await?;
+    }
+    Ok(())
 }
 
 // =======================================================
@@ -212,12 +1009,14 @@ pub async fn get_all_evaluations(pool: &SqlitePool) -> Result<Vec<HistoryEntry>,
 
 // NOTE: These structs must be defined here as they are not explicitly imported
 // in the provided code snippet.
-#[derive(serde::Serialize, Clone)]
+#[derive(serde::Serialize, Clone, Default)]
 pub struct HistoryEntry {
     pub id: String,
     pub status: Option<String>,
     pub model: Option<String>,
     pub prompt: Option<String>,
+    /// The prompt before the metadata was filled in; see `EvalResult::prompt_template`.
+    pub prompt_template: Option<String>,
     pub model_output: Option<String>,
     pub expected: Option<String>,
     pub judge_model: Option<String>,
@@ -232,9 +1031,199 @@ pub struct HistoryEntry {
     pub judge_output_tokens: Option<i64>,
     pub created_at: String,
     pub judge_prompt_version: Option<i64>,
+    /// Prompt version the prompt was rendered from; see `EvalResult::prompt_version`.
+    pub prompt_version: Option<i64>,
+    pub judge_confidence: Option<f64>,
+    pub needs_review: bool,
+    pub human_verdict: Option<String>,
+    pub reviewed_at: Option<String>,
+    pub cost_usd: Option<f64>,
+    pub judge_cost_usd: Option<f64>,
+    pub batch_id: Option<String>,
+    pub config_hash: Option<String>,
+    pub judge_raw_response: Option<String>,
+    pub truncation_strategy: Option<String>,
+    pub truncation_original_tokens: Option<i64>,
+    pub truncation_truncated_tokens: Option<i64>,
+    /// `control` or `canary` when the eval was judged while a judge prompt canary ran.
+    pub judge_prompt_arm: Option<String>,
+    pub tags: Vec<String>,
+    /// Why the verdict is uncertain or was routed to review; see `runner::UncertainReason`.
+    pub uncertain_reason: Option<String>,
+    /// Number of evals judged in the same batched judge call; `None` when judged on its own.
+    pub judge_batch_size: Option<i64>,
+    /// Why the model stopped generating; see `providers::FinishReason`.
+    pub finish_reason: Option<String>,
+    /// Timed phases of the eval, stored for traced evals when `PERSIST_TRACES` is on.
+    pub trace: Option<Vec<crate::trace::TraceEvent>>,
+    /// Category of the failure taxonomy a failed eval was put in.
+    pub failure_category: Option<String>,
+    pub failure_justification: Option<String>,
+    /// The model that classified the failure, `judge` or `human`.
+    pub failure_classifier: Option<String>,
+    /// JSON extracted from the model output.
+    pub parsed_output: Option<serde_json::Value>,
+    /// The extractor that produced `parsed_output`; see `EvalResult::parse_method`.
+    pub parse_method: Option<String>,
+    /// Diff of `parsed_output` against a JSON `expected`; see `EvalResult::output_diff`.
+    pub output_diff: Option<serde_json::Value>,
+    /// The chain the eval ran in and its step, counted from 1.
+    pub chain_id: Option<String>,
+    pub chain_step: Option<i64>,
+    /// Time the model call spent on rate limits and retries; see `EvalResult::wait_ms`.
+    pub wait_ms: Option<i64>,
+    /// The providers whose credentials the request overrode, and the API key that did.
+    pub provider_override: Option<crate::provider_override::OverrideUse>,
+    /// Length of the model output in characters, which `model_output` may fall short of when
+    /// `output_truncated` is set or in history lists; see `abbreviated`.
+    pub output_chars: Option<i64>,
+    /// The output was cut to `MAX_STORED_OUTPUT_CHARS` when it was saved.
+    pub output_truncated: bool,
+    /// Criteria of the eval config, as rendered for the judge.
+    pub criteria: Option<String>,
+    pub metadata: Option<serde_json::Value>,
+    /// The preset the request was merged with, and the config it resolved to.
+    pub preset: Option<crate::preset::PresetApplication>,
+    /// The dataset and row the eval was expanded from.
+    pub dataset_id: Option<String>,
+    pub dataset_row_index: Option<i64>,
+    /// Set when `expected` was adopted from a model's output rather than written by a person.
+    pub expected_source: Option<ExpectedSource>,
+    /// The model that answered, when the provider reported one; see `EvalResult::resolved_model`.
+    pub resolved_model: Option<String>,
+    /// The response format the model was asked for; see `EvalResult::response_format`.
+    pub response_format: Option<String>,
+    /// The model's reasoning, split from `model_output`; see `EvalResult::reasoning`.
+    pub reasoning: Option<String>,
+    /// The images sent with the prompt; see `EvalResult::images`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<ImageRecord>,
+    /// The judge prompt as rendered and sent; see `EvalResult::judge_prompt_rendered`. History
+    /// lists shorten it to `JUDGE_PROMPT_PREVIEW_CHARS`.
+    pub judge_prompt_rendered: Option<String>,
+    /// The alias the eval config named its model by; see `EvalResult::model_alias`.
+    pub model_alias: Option<String>,
+    /// The judge's generation settings; see `EvalResult::judge_params`.
+    pub judge_params: Option<JudgeParams>,
+    /// Why the judge call failed; see `EvalResult::judge_error`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub judge_error: Option<JudgeError>,
+    /// The samples of a self-consistency eval; see `EvalResult::self_consistency`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub self_consistency: Option<SelfConsistencyReport>,
 }
 
-#[derive(serde::Serialize, Clone)]
+/// The fields of a `HistoryEntry`, which `GET /evals/history?fields=` may add to summaries.
+pub const HISTORY_FIELDS: &[&str] = &[
+    "id", "status", "model", "prompt", "prompt_template", "model_output", "expected", "judge_model", "judge_verdict",
+    "judge_reasoning", "error_message", "latency_ms", "judge_latency_ms", "input_tokens",
+    "output_tokens", "judge_input_tokens", "judge_output_tokens", "created_at",
+    "judge_prompt_version", "prompt_version", "judge_confidence", "needs_review", "human_verdict", "reviewed_at",
+    "cost_usd", "judge_cost_usd", "batch_id", "config_hash", "judge_raw_response",
+    "truncation_strategy", "truncation_original_tokens", "truncation_truncated_tokens",
+    "judge_prompt_arm", "tags", "uncertain_reason", "judge_batch_size", "finish_reason", "trace",
+    "failure_category", "failure_justification", "failure_classifier", "parsed_output",
+    "parse_method", "output_diff", "chain_id", "chain_step", "wait_ms", "provider_override",
+    "output_chars", "output_truncated", "criteria", "metadata", "preset", "dataset_id",
+    "dataset_row_index", "expected_source", "resolved_model", "response_format", "reasoning",
+    "images", "judge_prompt_rendered", "model_alias", "judge_params", "judge_error",
+];
+
+/// Characters of `judge_prompt_rendered` kept in history lists; `GET /evals/{id}` returns it whole.
+pub const JUDGE_PROMPT_PREVIEW_CHARS: usize = 500;
+
+/// Characters of `model_output` kept in history lists; `GET /evals/{id}` returns what was saved.
+pub const OUTPUT_PREVIEW_CHARS: usize = 2_000;
+
+/// `text` cut to its first `chars` characters and marked with `…`, when it is longer.
+fn preview(text: &mut String, chars: usize) {
+    if let Some((end, _)) = text.char_indices().nth(chars) {
+        text.truncate(end);
+        text.push('…');
+    }
+}
+
+impl HistoryEntry {
+    /// The entry with `judge_prompt_rendered` and `model_output` shortened for a list response.
+    pub fn abbreviated(mut self) -> Self {
+        if let Some(prompt) = &mut self.judge_prompt_rendered {
+            preview(prompt, JUDGE_PROMPT_PREVIEW_CHARS);
+        }
+        if let Some(output) = &mut self.model_output {
+            preview(output, OUTPUT_PREVIEW_CHARS);
+        }
+        self
+    }
+}
+
+/// Characters of the prompt, output and error kept in a `HistorySummary`.
+pub const SUMMARY_PREVIEW_CHARS: usize = 120;
+
+fn summary_preview(mut text: String) -> String {
+    preview(&mut text, SUMMARY_PREVIEW_CHARS);
+    text
+}
+
+/// A row of the history list: what is needed to list and chart an evaluation, with previews in
+/// place of its texts. `GET /evals/{id}` returns the whole `HistoryEntry`.
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct HistorySummary {
+    pub id: String,
+    pub status: Option<String>,
+    pub model: Option<String>,
+    pub judge_model: Option<String>,
+    pub judge_verdict: Option<String>,
+    pub human_verdict: Option<String>,
+    pub needs_review: bool,
+    pub uncertain_reason: Option<String>,
+    pub latency_ms: Option<i64>,
+    pub judge_latency_ms: Option<i64>,
+    pub input_tokens: Option<i64>,
+    pub output_tokens: Option<i64>,
+    pub judge_input_tokens: Option<i64>,
+    pub judge_output_tokens: Option<i64>,
+    pub cost_usd: Option<f64>,
+    pub judge_cost_usd: Option<f64>,
+    pub created_at: String,
+    pub batch_id: Option<String>,
+    pub tags: Vec<String>,
+    /// The first `SUMMARY_PREVIEW_CHARS` characters, marked with `…` when there are more.
+    pub prompt_preview: Option<String>,
+    pub output_preview: Option<String>,
+    pub error_preview: Option<String>,
+}
+
+impl HistorySummary {
+    /// The summary of a full entry, as `get_evaluation_summaries` reads it.
+    pub fn of(entry: &HistoryEntry) -> Self {
+        HistorySummary {
+            id: entry.id.clone(),
+            status: entry.status.clone(),
+            model: entry.model.clone(),
+            judge_model: entry.judge_model.clone(),
+            judge_verdict: entry.judge_verdict.clone(),
+            human_verdict: entry.human_verdict.clone(),
+            needs_review: entry.needs_review,
+            uncertain_reason: entry.uncertain_reason.clone(),
+            latency_ms: entry.latency_ms,
+            judge_latency_ms: entry.judge_latency_ms,
+            input_tokens: entry.input_tokens,
+            output_tokens: entry.output_tokens,
+            judge_input_tokens: entry.judge_input_tokens,
+            judge_output_tokens: entry.judge_output_tokens,
+            cost_usd: entry.cost_usd,
+            judge_cost_usd: entry.judge_cost_usd,
+            created_at: entry.created_at.clone(),
+            batch_id: entry.batch_id.clone(),
+            tags: entry.tags.clone(),
+            prompt_preview: entry.prompt.clone().map(summary_preview),
+            output_preview: entry.model_output.clone().map(summary_preview),
+            error_preview: entry.error_message.clone().map(summary_preview),
+        }
+    }
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
 pub struct JudgePrompt {
     pub version: i64,
     pub name: String,
@@ -248,8 +1237,8 @@ pub struct JudgePrompt {
 // Judge prompt functions
 // =======================================================
 
-pub async fn get_all_judge_prompts(pool: &SqlitePool) -> Result<Vec<JudgePrompt>, sqlx::Error> {
-    let rows = sqlx::query(
+pub async fn get_all_judge_prompts(pool: &DbPool) -> Result<Vec<JudgePrompt>, sqlx::Error> {
+    let rows = query(
         r#"
         SELECT version, name, template, description, is_active, created_at
         FROM judge_prompts
@@ -269,8 +1258,8 @@ pub async fn get_all_judge_prompts(pool: &SqlitePool) -> Result<Vec<JudgePrompt>
     }).collect())
 }
 
-pub async fn get_active_judge_prompt(pool: &SqlitePool) -> Result<JudgePrompt, sqlx::Error> {
-    let row = sqlx::query(
+pub async fn get_active_judge_prompt(pool: &DbPool) -> Result<JudgePrompt, sqlx::Error> {
+    let row = query(
         r#"
         SELECT version, name, template, description, is_active, created_at
         FROM judge_prompts
@@ -291,8 +1280,8 @@ pub async fn get_active_judge_prompt(pool: &SqlitePool) -> Result<JudgePrompt, s
     })
 }
 
-pub async fn get_judge_prompt_by_version(pool: &SqlitePool, version: i64) -> Result<JudgePrompt, sqlx::Error> {
-    let row = sqlx::query(
+pub async fn get_judge_prompt_by_version(pool: &DbPool, version: i64) -> Result<JudgePrompt, sqlx::Error> {
+    let row = query(
         r#"
         SELECT version, name, template, description, is_active, created_at
         FROM judge_prompts
@@ -314,7 +1303,7 @@ pub async fn get_judge_prompt_by_version(pool: &SqlitePool, version: i64) -> Res
 }
 
 pub async fn create_judge_prompt(
-    pool: &SqlitePool,
+    pool: &DbPool,
     name: String,
     template: String,
     description: Option<String>,
@@ -325,12 +1314,12 @@ pub async fn create_judge_prompt(
     let mut tx = pool.begin().await?;
     
     if set_active {
-        sqlx::query("UPDATE judge_prompts SET is_active = FALSE")
-            .execute(&mut *tx)
+        query("UPDATE judge_prompts SET is_active = FALSE")
+            .execute(&mut tx)
             .await?;
     }
     
-    let result = sqlx::query(
+    let result = query(
         r#"
         INSERT INTO judge_prompts (name, template, description, is_active, created_at)
         VALUES (?, ?, ?, ?, ?)
@@ -342,7 +1331,7 @@ pub async fn create_judge_prompt(
     .bind(&description)
     .bind(set_active)
     .bind(&created_at)
-    .fetch_one(&mut *tx)
+    .fetch_one(&mut tx)
     .await?;
     
     tx.commit().await?;
@@ -357,24 +1346,24 @@ pub async fn create_judge_prompt(
     })
 }
 
-pub async fn set_active_judge_prompt(pool: &SqlitePool, version: i64) -> Result<(), sqlx::Error> {
+pub async fn set_active_judge_prompt(pool: &DbPool, version: i64) -> Result<(), sqlx::Error> {
     let mut tx = pool.begin().await?;
     
     // Check if the version exists
-    sqlx::query("SELECT version FROM judge_prompts WHERE version = ?")
+    query("SELECT version FROM judge_prompts WHERE version = ?")
         .bind(version)
-        .fetch_one(&mut *tx)
+        .fetch_one(&mut tx)
         .await?;
     
     // Deactivate all others
-    sqlx::query("UPDATE judge_prompts SET is_active = FALSE")
-        .execute(&mut *tx)
+    query("UPDATE judge_prompts SET is_active = FALSE")
+        .execute(&mut tx)
         .await?;
     
     // Activate the specified one
-    sqlx::query("UPDATE judge_prompts SET is_active = TRUE WHERE version = ?")
+    query("UPDATE judge_prompts SET is_active = TRUE WHERE version = ?")
         .bind(version)
-        .execute(&mut *tx)
+        .execute(&mut tx)
         .await?;
     
     tx.commit().await?;
@@ -382,9 +1371,771 @@ pub async fn set_active_judge_prompt(pool: &SqlitePool, version: i64) -> Result<
     Ok(())
 }
 
-// =======================================================
-// Prompt Version Management
-// =======================================================
+/// Fields of a judge prompt to change; `None` keeps the current value.
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct JudgePromptUpdate {
+    pub name: Option<String>,
+    pub template: Option<String>,
+    pub description: Option<String>,
+}
+
+/// Rows that refer to a judge prompt version.
+#[derive(serde::Serialize, Debug, Default, PartialEq)]
+pub struct JudgePromptUsage {
+    pub evaluations: i64,
+    /// Verdicts replaced by a re-judge, kept in `judgements`
+    pub replaced_judgements: i64,
+    pub canaries: i64,
+    pub ab_tests: i64,
+    pub calibrations: i64,
+}
+
+impl JudgePromptUsage {
+    pub fn total(&self) -> i64 {
+        self.evaluations + self.replaced_judgements + self.canaries + self.ab_tests + self.calibrations
+    }
+}
+
+/// Why a judge prompt version cannot be changed or deleted.
+#[derive(Debug, PartialEq)]
+pub enum JudgePromptConflict {
+    /// The version is the active judge prompt.
+    Active,
+    /// Stored rows refer to the version, so changing it would rewrite their history.
+    InUse(JudgePromptUsage),
+}
+
+async fn judge_prompt_usage(conn: &mut Transaction, version: i64) -> Result<JudgePromptUsage, sqlx::Error> {
+    let row = query(
+        r#"
+        SELECT
+            (SELECT COUNT(*) FROM evaluations WHERE judge_prompt_version = ?1),
+            (SELECT COUNT(*) FROM judgements WHERE judge_prompt_version = ?1),
+            (SELECT COUNT(*) FROM judge_prompt_canaries WHERE candidate_version = ?1),
+            (SELECT COUNT(*) FROM judge_ab_tests WHERE version_a = ?1 OR version_b = ?1),
+            (SELECT COUNT(*) FROM judge_calibrations WHERE version = ?1)
+        "#
+    )
+    .bind(version)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(JudgePromptUsage {
+        evaluations: row.get(0),
+        replaced_judgements: row.get(1),
+        canaries: row.get(2),
+        ab_tests: row.get(3),
+        calibrations: row.get(4),
+    })
+}
+
+/// Updates a judge prompt version that nothing refers to yet. Fails with `RowNotFound` if the
+/// version does not exist.
+pub async fn update_judge_prompt(
+    pool: &DbPool,
+    version: i64,
+    update: &JudgePromptUpdate,
+) -> Result<Result<JudgePrompt, JudgePromptConflict>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    query("SELECT version FROM judge_prompts WHERE version = ?")
+        .bind(version)
+        .fetch_one(&mut tx)
+        .await?;
+
+    let usage = judge_prompt_usage(&mut tx, version).await?;
+    if usage.total() > 0 {
+        return Ok(Err(JudgePromptConflict::InUse(usage)));
+    }
+
+    let row = query(
+        r#"
+        UPDATE judge_prompts
+        SET name = COALESCE(?, name), template = COALESCE(?, template), description = COALESCE(?, description)
+        WHERE version = ?
+        RETURNING version, name, template, description, is_active, created_at
+        "#
+    )
+    .bind(&update.name)
+    .bind(&update.template)
+    .bind(&update.description)
+    .bind(version)
+    .fetch_one(&mut tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Ok(JudgePrompt {
+        version: row.get(0),
+        name: row.get(1),
+        template: row.get(2),
+        description: row.get(3),
+        is_active: row.get(4),
+        created_at: row.get(5),
+    }))
+}
+
+/// Deletes a judge prompt version that is not active and that nothing refers to. Fails with
+/// `RowNotFound` if the version does not exist.
+pub async fn delete_judge_prompt(pool: &DbPool, version: i64) -> Result<Result<(), JudgePromptConflict>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let is_active: bool = query_scalar("SELECT is_active FROM judge_prompts WHERE version = ?")
+        .bind(version)
+        .fetch_one(&mut tx)
+        .await?;
+    if is_active {
+        return Ok(Err(JudgePromptConflict::Active));
+    }
+
+    let usage = judge_prompt_usage(&mut tx, version).await?;
+    if usage.total() > 0 {
+        return Ok(Err(JudgePromptConflict::InUse(usage)));
+    }
+
+    query("DELETE FROM judge_prompts WHERE version = ?")
+        .bind(version)
+        .execute(&mut tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(Ok(()))
+}
+
+// =======================================================
+// Judge prompt canaries
+// =======================================================
+
+#[derive(serde::Serialize, Clone)]
+pub struct JudgePromptCanary {
+    pub id: i64,
+    pub candidate_version: i64,
+    /// Share of new evaluations (0-100) judged with the candidate.
+    pub percentage: f64,
+    pub started_at: String,
+    /// `None` while the canary is running.
+    pub stopped_at: Option<String>,
+}
+
+const CANARY_COLUMNS: &str = "id, candidate_version, percentage, started_at, stopped_at";
+
+fn canary_from_row(row: &DbRow) -> JudgePromptCanary {
+    JudgePromptCanary {
+        id: row.get(0),
+        candidate_version: row.get(1),
+        percentage: row.get(2),
+        started_at: row.get(3),
+        stopped_at: row.get(4),
+    }
+}
+
+/// Starts a canary for `candidate_version`, stopping any canary already running.
+/// Fails with `RowNotFound` if the version does not exist.
+pub async fn start_canary(pool: &DbPool, candidate_version: i64, percentage: f64) -> Result<JudgePromptCanary, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    query("SELECT version FROM judge_prompts WHERE version = ?")
+        .bind(candidate_version)
+        .fetch_one(&mut tx)
+        .await?;
+
+    let now = Utc::now().to_rfc3339();
+    query("UPDATE judge_prompt_canaries SET stopped_at = ? WHERE stopped_at IS NULL")
+        .bind(&now)
+        .execute(&mut tx)
+        .await?;
+
+    let row = query(&format!(
+        "INSERT INTO judge_prompt_canaries (candidate_version, percentage, started_at) VALUES (?, ?, ?) RETURNING {}",
+        CANARY_COLUMNS
+    ))
+    .bind(candidate_version)
+    .bind(percentage)
+    .bind(&now)
+    .fetch_one(&mut tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(canary_from_row(&row))
+}
+
+/// The running canary, if any.
+pub async fn get_active_canary(pool: &DbPool) -> Result<Option<JudgePromptCanary>, sqlx::Error> {
+    let row = query(&format!(
+        "SELECT {} FROM judge_prompt_canaries WHERE stopped_at IS NULL ORDER BY id DESC LIMIT 1",
+        CANARY_COLUMNS
+    ))
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.as_ref().map(canary_from_row))
+}
+
+/// A canary by id, or the most recent one (running or stopped) when `id` is `None`.
+pub async fn get_canary(pool: &DbPool, id: Option<i64>) -> Result<Option<JudgePromptCanary>, sqlx::Error> {
+    let row = query(&format!(
+        "SELECT {} FROM judge_prompt_canaries WHERE (?1 IS NULL OR id = ?1) ORDER BY id DESC LIMIT 1",
+        CANARY_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.as_ref().map(canary_from_row))
+}
+
+/// Stops the running canary and returns it, or `None` if none was running.
+pub async fn stop_canary(pool: &DbPool) -> Result<Option<JudgePromptCanary>, sqlx::Error> {
+    let row = query(&format!(
+        "UPDATE judge_prompt_canaries SET stopped_at = ? WHERE stopped_at IS NULL RETURNING {}",
+        CANARY_COLUMNS
+    ))
+    .bind(Utc::now().to_rfc3339())
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.as_ref().map(canary_from_row))
+}
+
+/// Verdicts and judge cost for one arm of a canary.
+#[derive(serde::Serialize)]
+pub struct CanaryArmStats {
+    pub arm: String,
+    /// Judge prompt versions the arm's evaluations were judged with.
+    pub judge_prompt_versions: Vec<i64>,
+    pub evaluations: i64,
+    pub passed: i64,
+    pub failed: i64,
+    pub uncertain: i64,
+    pub needs_review: i64,
+    pub pass_rate: f64,
+    pub uncertain_rate: f64,
+    pub avg_judge_latency_ms: Option<f64>,
+    pub avg_judge_cost_usd: Option<f64>,
+}
+
+/// Per-arm stats over evaluations judged while `canary` ran.
+pub async fn get_canary_arm_stats(pool: &DbPool, canary: &JudgePromptCanary) -> Result<Vec<CanaryArmStats>, sqlx::Error> {
+    let rows = query(&format!(
+        r#"
+        SELECT
+            judge_prompt_arm,
+            {},
+            COUNT(*),
+            SUM(CASE WHEN judge_verdict = 'Pass' THEN 1 ELSE 0 END),
+            SUM(CASE WHEN judge_verdict = 'Fail' THEN 1 ELSE 0 END),
+            SUM(CASE WHEN judge_verdict = 'Uncertain' THEN 1 ELSE 0 END),
+            SUM(CASE WHEN needs_review THEN 1 ELSE 0 END),
+            AVG(CAST(judge_latency_ms AS DOUBLE PRECISION)),
+            AVG(judge_cost_usd)
+        FROM evaluations
+        WHERE judge_prompt_arm IS NOT NULL
+          AND created_at >= ?
+          AND (? IS NULL OR created_at <= ?)
+        GROUP BY judge_prompt_arm
+        ORDER BY judge_prompt_arm
+        "#,
+        pool.backend().group_concat_distinct("judge_prompt_version")
+    ))
+    .bind(&canary.started_at)
+    .bind(&canary.stopped_at)
+    .bind(&canary.stopped_at)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|row| {
+        let versions: Option<String> = row.get(1);
+        let mut judge_prompt_versions: Vec<i64> = versions
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|v| v.trim().parse().ok())
+            .collect();
+        judge_prompt_versions.sort_unstable();
+        let evaluations: i64 = row.get(2);
+        let passed: i64 = row.get(3);
+        let uncertain: i64 = row.get(5);
+        CanaryArmStats {
+            arm: row.get(0),
+            judge_prompt_versions,
+            evaluations,
+            passed,
+            failed: row.get(4),
+            uncertain,
+            needs_review: row.get(6),
+            pass_rate: if evaluations > 0 { passed as f64 / evaluations as f64 } else { 0.0 },
+            uncertain_rate: if evaluations > 0 { uncertain as f64 / evaluations as f64 } else { 0.0 },
+            avg_judge_latency_ms: row.get(7),
+            avg_judge_cost_usd: row.get(8),
+        }
+    }).collect())
+}
+
+// =======================================================
+// Judge prompt A/B tests
+// =======================================================
+
+#[derive(serde::Serialize, Clone)]
+pub struct JudgeAbTest {
+    pub id: String,
+    pub version_a: i64,
+    pub version_b: i64,
+    /// `None` when each evaluation was re-judged by the model that originally judged it.
+    pub judge_model: Option<String>,
+    pub created_at: String,
+}
+
+pub async fn create_judge_ab_test(
+    pool: &DbPool,
+    version_a: i64,
+    version_b: i64,
+    judge_model: Option<&str>,
+) -> Result<JudgeAbTest, sqlx::Error> {
+    let test = JudgeAbTest {
+        id: uuid::Uuid::new_v4().to_string(),
+        version_a,
+        version_b,
+        judge_model: judge_model.map(str::to_string),
+        created_at: Utc::now().to_rfc3339(),
+    };
+
+    query("INSERT INTO judge_ab_tests (id, version_a, version_b, judge_model, created_at) VALUES (?, ?, ?, ?, ?)")
+        .bind(&test.id)
+        .bind(test.version_a)
+        .bind(test.version_b)
+        .bind(&test.judge_model)
+        .bind(&test.created_at)
+        .execute(pool)
+        .await?;
+
+    Ok(test)
+}
+
+/// Records both versions' verdicts on one evaluation of `test`.
+pub async fn save_judge_ab_comparison(pool: &DbPool, test: &JudgeAbTest, comparison: &AbComparison) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let now = Utc::now().to_rfc3339();
+
+    for (version, arm) in [(test.version_a, &comparison.a), (test.version_b, &comparison.b)] {
+        query(
+            r#"
+            INSERT INTO judge_ab_results (
+                test_id, evaluation_id, version, judge_model, verdict, confidence,
+                reasoning, latency_ms, cost_usd, error_message, created_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(&test.id)
+        .bind(&comparison.evaluation_id)
+        .bind(version)
+        .bind(&comparison.judge_model)
+        .bind(arm.verdict.map(|v| v.to_string()))
+        .bind(arm.confidence)
+        .bind(&arm.reasoning)
+        .bind(arm.latency_ms.map(|ms| ms as i64))
+        .bind(arm.cost_usd)
+        .bind(&arm.error)
+        .bind(&now)
+        .execute(&mut tx)
+        .await?;
+    }
+
+    tx.commit().await
+}
+
+/// Up to `limit` randomly chosen evaluations that have a model output and expected output to re-judge.
+pub async fn sample_judged_evaluations(pool: &DbPool, limit: i64) -> Result<Vec<HistoryEntry>, sqlx::Error> {
+    let rows = query(&format!(
+        r#"
+        SELECT {} FROM evaluations
+        WHERE model_output IS NOT NULL AND expected IS NOT NULL AND judge_verdict IS NOT NULL
+        ORDER BY RANDOM()
+        LIMIT ?
+        "#,
+        HISTORY_COLUMNS
+    ))
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(history_entry_from_row).collect())
+}
+
+// =======================================================
+// Judge calibrations
+// =======================================================
+
+#[derive(serde::Serialize, Clone)]
+pub struct JudgeCalibration {
+    pub id: String,
+    pub version: i64,
+    /// `None` when each evaluation was judged by the model that originally judged it.
+    pub judge_model: Option<String>,
+    pub created_at: String,
+}
+
+/// Saves a calibration of judge prompt `version` with its statistics and samples.
+pub async fn create_judge_calibration(
+    pool: &DbPool,
+    version: i64,
+    judge_model: Option<&str>,
+    summary: &CalibrationSummary,
+    samples: &[CalibrationSample],
+) -> Result<JudgeCalibration, sqlx::Error> {
+    let calibration = JudgeCalibration {
+        id: uuid::Uuid::new_v4().to_string(),
+        version,
+        judge_model: judge_model.map(str::to_string),
+        created_at: Utc::now().to_rfc3339(),
+    };
+
+    query(
+        r#"
+        INSERT INTO judge_calibrations (
+            id, version, judge_model, evaluations, judged, accuracy, false_pass_rate, false_fail_rate,
+            position_flip_rate, length_pass_correlation, summary, samples, created_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&calibration.id)
+    .bind(calibration.version)
+    .bind(&calibration.judge_model)
+    .bind(summary.evaluations as i64)
+    .bind(summary.agreement.judged as i64)
+    .bind(summary.agreement.accuracy)
+    .bind(summary.agreement.false_pass_rate)
+    .bind(summary.agreement.false_fail_rate)
+    .bind(summary.position_bias.flip_rate)
+    .bind(summary.verbosity_bias.length_pass_correlation)
+    .bind(serde_json::to_string(summary).unwrap_or_default())
+    .bind(serde_json::to_string(samples).unwrap_or_default())
+    .bind(&calibration.created_at)
+    .execute(pool)
+    .await?;
+
+    Ok(calibration)
+}
+
+/// Up to `limit` randomly chosen evaluations a reviewer passed or failed, with a model output
+/// and expected output to judge again.
+pub async fn sample_reviewed_evaluations(pool: &DbPool, limit: i64) -> Result<Vec<HistoryEntry>, sqlx::Error> {
+    let rows = query(&format!(
+        r#"
+        SELECT {} FROM evaluations
+        WHERE model_output IS NOT NULL AND expected IS NOT NULL AND human_verdict IN ('Pass', 'Fail')
+        ORDER BY RANDOM()
+        LIMIT ?
+        "#,
+        HISTORY_COLUMNS
+    ))
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(history_entry_from_row).collect())
+}
+
+// =======================================================
+// Judge prompt performance
+// =======================================================
+
+/// Every verdict a judge prompt version gave, one row each: those stored on evaluations
+/// (`current`), those a re-judge replaced (`replaced`) and those of A/B tests (`ab_test`).
+/// `judged_at` orders the verdicts on one evaluation; a current verdict is as recent as the
+/// last re-judge of its evaluation.
+const JUDGE_PROMPT_VERDICTS: &str = r#"
+    SELECT id AS evaluation_id, judge_prompt_version AS version, judge_verdict AS verdict,
+        judge_latency_ms AS latency_ms, judge_input_tokens AS input_tokens,
+        judge_output_tokens AS output_tokens, judge_cost_usd AS cost_usd, 'current' AS source,
+        COALESCE((SELECT MAX(replaced_at) FROM judgements WHERE judgements.evaluation_id = evaluations.id), created_at) AS judged_at
+    FROM evaluations
+    WHERE judge_verdict IS NOT NULL AND judge_prompt_version IS NOT NULL
+    UNION ALL
+    SELECT evaluation_id, judge_prompt_version, judge_verdict, judge_latency_ms, judge_input_tokens,
+        judge_output_tokens, judge_cost_usd, 'replaced', replaced_at
+    FROM judgements
+    WHERE judge_verdict IS NOT NULL AND judge_prompt_version IS NOT NULL
+    UNION ALL
+    SELECT evaluation_id, version, verdict, latency_ms, NULL, NULL, cost_usd, 'ab_test', created_at
+    FROM judge_ab_results
+    WHERE verdict IS NOT NULL
+"#;
+
+/// The verdicts one judge prompt version gave and what judging cost. Averages are over the
+/// verdicts that recorded the value, and zero when none did.
+#[derive(serde::Serialize, Debug, Clone, Default, PartialEq)]
+pub struct JudgePromptStats {
+    pub version: i64,
+    pub judged: i64,
+    /// Of `judged`: verdicts stored on evaluations, replaced by a re-judge, and given in A/B tests.
+    pub current: i64,
+    pub replaced: i64,
+    pub ab_test: i64,
+    #[serde(flatten)]
+    pub verdicts: crate::judge_ab::VerdictDistribution,
+    pub avg_judge_latency_ms: f64,
+    pub avg_judge_input_tokens: f64,
+    pub avg_judge_output_tokens: f64,
+    pub avg_judge_cost_usd: f64,
+    pub total_judge_cost_usd: f64,
+}
+
+/// Stats over every verdict judge prompt `version` gave; zeros for a version never used.
+pub async fn get_judge_prompt_stats(pool: &DbPool, version: i64) -> Result<JudgePromptStats, sqlx::Error> {
+    let row = query(&format!(
+        r#"
+        SELECT
+            COUNT(*),
+            SUM(CASE WHEN source = 'current' THEN 1 ELSE 0 END),
+            SUM(CASE WHEN source = 'replaced' THEN 1 ELSE 0 END),
+            SUM(CASE WHEN source = 'ab_test' THEN 1 ELSE 0 END),
+            SUM(CASE WHEN verdict = 'Pass' THEN 1 ELSE 0 END),
+            SUM(CASE WHEN verdict = 'Fail' THEN 1 ELSE 0 END),
+            SUM(CASE WHEN verdict = 'Uncertain' THEN 1 ELSE 0 END),
+            AVG(CAST(latency_ms AS DOUBLE PRECISION)),
+            AVG(CAST(input_tokens AS DOUBLE PRECISION)),
+            AVG(CAST(output_tokens AS DOUBLE PRECISION)),
+            AVG(cost_usd),
+            SUM(cost_usd)
+        FROM ({}) verdicts
+        WHERE version = ?
+        "#,
+        JUDGE_PROMPT_VERDICTS
+    ))
+    .bind(version)
+    .fetch_one(pool)
+    .await?;
+
+    let count = |index: usize| row.get::<Option<i64>, _>(index).unwrap_or(0);
+    let average = |index: usize| row.get::<Option<f64>, _>(index).unwrap_or(0.0);
+    Ok(JudgePromptStats {
+        version,
+        judged: row.get(0),
+        current: count(1),
+        replaced: count(2),
+        ab_test: count(3),
+        verdicts: crate::judge_ab::VerdictDistribution::new(count(4), count(5), count(6)),
+        avg_judge_latency_ms: average(7),
+        avg_judge_input_tokens: average(8),
+        avg_judge_output_tokens: average(9),
+        avg_judge_cost_usd: average(10),
+        total_judge_cost_usd: average(11),
+    })
+}
+
+/// The latest verdicts of `version_a` and `version_b`, in that order, on each evaluation both
+/// judged, by evaluation id.
+pub async fn get_judge_prompt_verdict_pairs(
+    pool: &DbPool,
+    version_a: i64,
+    version_b: i64,
+) -> Result<Vec<(JudgeVerdict, JudgeVerdict)>, sqlx::Error> {
+    let rows = query(&format!(
+        r#"
+        SELECT evaluation_id, version, verdict
+        FROM ({}) verdicts
+        WHERE version = ?1 OR version = ?2
+        ORDER BY judged_at ASC
+        "#,
+        JUDGE_PROMPT_VERDICTS
+    ))
+    .bind(version_a)
+    .bind(version_b)
+    .fetch_all(pool)
+    .await?;
+
+    // Later verdicts overwrite earlier ones
+    let mut latest: BTreeMap<String, (Option<JudgeVerdict>, Option<JudgeVerdict>)> = BTreeMap::new();
+    for row in &rows {
+        let Some(verdict) = JudgeVerdict::parse(&row.get::<String, _>(2)) else {
+            continue;
+        };
+        let pair = latest.entry(row.get(0)).or_default();
+        if row.get::<i64, _>(1) == version_a {
+            pair.0 = Some(verdict);
+        } else {
+            pair.1 = Some(verdict);
+        }
+    }
+    Ok(latest.into_values().filter_map(|(a, b)| Some((a?, b?))).collect())
+}
+
+// =======================================================
+// Re-judging stored evaluations
+// =======================================================
+
+/// Which stored evaluations a bulk re-judge covers; unset fields match everything.
+#[derive(Debug, Default, Clone, serde::Deserialize)]
+pub struct RejudgeFilter {
+    pub model: Option<String>,
+    /// RFC 3339 timestamp or `YYYY-MM-DD`, inclusive
+    pub from: Option<String>,
+    /// RFC 3339 timestamp or `YYYY-MM-DD`, inclusive
+    pub to: Option<String>,
+    /// `Pass`, `Fail` or `Uncertain`, matched against the current judge verdict
+    pub verdict: Option<String>,
+}
+
+/// Up to `limit` evaluations matching `filter` that have a judge verdict to replace and no
+/// human verdict overriding it, newest first.
+pub async fn get_rejudge_candidates(pool: &DbPool, filter: &RejudgeFilter, limit: i64) -> Result<Vec<HistoryEntry>, sqlx::Error> {
+    let rows = query(&format!(
+        r#"
+        SELECT {} FROM evaluations
+        WHERE model_output IS NOT NULL AND expected IS NOT NULL AND judge_verdict IS NOT NULL
+            AND human_verdict IS NULL
+            AND (?1 IS NULL OR model = ?1)
+            AND (?2 IS NULL OR created_at >= ?2)
+            AND (?3 IS NULL OR substr(created_at, 1, length(?3)) <= ?3)
+            AND (?4 IS NULL OR judge_verdict = ?4)
+        ORDER BY created_at DESC
+        LIMIT ?5
+        "#,
+        HISTORY_COLUMNS
+    ))
+    .bind(&filter.model)
+    .bind(&filter.from)
+    .bind(&filter.to)
+    .bind(&filter.verdict)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(history_entry_from_row).collect())
+}
+
+/// A judge verdict that a re-judge replaced.
+#[derive(serde::Serialize, Clone)]
+pub struct ReplacedJudgement {
+    pub id: i64,
+    pub evaluation_id: String,
+    pub status: Option<String>,
+    pub judge_model: Option<String>,
+    pub judge_prompt_version: Option<i64>,
+    pub judge_verdict: Option<String>,
+    pub judge_confidence: Option<f64>,
+    pub judge_reasoning: Option<String>,
+    pub judge_raw_response: Option<String>,
+    pub judge_prompt_rendered: Option<String>,
+    pub judge_params: Option<JudgeParams>,
+    pub uncertain_reason: Option<String>,
+    pub judge_latency_ms: Option<i64>,
+    pub judge_cost_usd: Option<f64>,
+    pub replaced_at: String,
+}
+
+/// Judge columns copied into `judgements` before a re-judge overwrites them.
+const JUDGEMENT_COLUMNS: &str = r#"
+    status, judge_model, judge_prompt_version, judge_verdict, judge_confidence,
+    judge_reasoning, judge_raw_response, uncertain_reason, judge_latency_ms,
+    judge_input_tokens, judge_output_tokens, judge_cost_usd, judge_prompt_rendered, judge_params
+"#;
+
+/// Replaces the judge verdict of evaluation `id` with `judgement`, moving the previous
+/// verdict into `judgements`. The status follows the new verdict unless a human verdict
+/// overrides it. A failure category a model gave for the old verdict is cleared; one a
+/// human gave is kept. Returns the new status.
+pub async fn save_rejudgement(
+    pool: &DbPool,
+    id: &str,
+    judgement: &Judgement,
+    judge_prompt_version: Option<i64>,
+    needs_review: bool,
+) -> Result<&'static str, sqlx::Error> {
+    let status = verdict_status(Some(judgement.result.verdict), needs_review);
+    let mut tx = pool.begin().await?;
+
+    let archived = query(&format!(
+        "INSERT INTO judgements (evaluation_id, {0}, replaced_at) SELECT id, {0}, ? FROM evaluations WHERE id = ?",
+        JUDGEMENT_COLUMNS
+    ))
+    .bind(Utc::now().to_rfc3339())
+    .bind(id)
+    .execute(&mut tx)
+    .await?;
+    if archived.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+
+    let result = &judgement.result;
+    query(
+        r#"
+        UPDATE evaluations
+        SET judge_model = ?, judge_prompt_version = ?, judge_prompt_arm = NULL, judge_batch_size = NULL, judge_verdict = ?,
+            judge_confidence = ?, judge_reasoning = ?, judge_raw_response = ?, judge_prompt_rendered = ?, judge_params = ?, judge_error = NULL, uncertain_reason = ?,
+            judge_latency_ms = ?, judge_input_tokens = ?, judge_output_tokens = ?, judge_cost_usd = ?,
+            needs_review = ?, status = CASE WHEN human_verdict IS NULL THEN ? ELSE status END,
+            failure_category = CASE WHEN failure_classifier = ? THEN failure_category END,
+            failure_justification = CASE WHEN failure_classifier = ? THEN failure_justification END,
+            failure_classifier = CASE WHEN failure_classifier = ? THEN failure_classifier END
+        WHERE id = ?
+        "#
+    )
+    .bind(&result.judge_model)
+    .bind(judge_prompt_version)
+    .bind(result.verdict.to_string())
+    .bind(result.confidence)
+    .bind(&result.reasoning)
+    .bind(&result.raw_response)
+    .bind(&judgement.prompt)
+    .bind(serde_json::to_string(&judgement.params).ok())
+    .bind(result.uncertain_reason.map(|r| r.as_str()))
+    .bind(judgement.latency_ms as i64)
+    .bind(judgement.token_usage.input_tokens.map(|t| t as i64))
+    .bind(judgement.token_usage.output_tokens.map(|t| t as i64))
+    .bind(judgement.cost_usd)
+    .bind(needs_review)
+    .bind(status)
+    .bind(crate::failure_taxonomy::HUMAN_CLASSIFIER)
+    .bind(crate::failure_taxonomy::HUMAN_CLASSIFIER)
+    .bind(crate::failure_taxonomy::HUMAN_CLASSIFIER)
+    .bind(id)
+    .execute(&mut tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(status)
+}
+
+/// Verdicts that re-judges replaced for evaluation `id`, oldest first.
+pub async fn get_replaced_judgements(pool: &DbPool, id: &str) -> Result<Vec<ReplacedJudgement>, sqlx::Error> {
+    let rows = query(
+        r#"
+        SELECT id, evaluation_id, status, judge_model, judge_prompt_version, judge_verdict,
+               judge_confidence, judge_reasoning, judge_raw_response, uncertain_reason,
+               judge_latency_ms, judge_cost_usd, replaced_at, judge_prompt_rendered, judge_params
+        FROM judgements
+        WHERE evaluation_id = ?
+        ORDER BY id ASC
+        "#
+    )
+    .bind(id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(|row| ReplacedJudgement {
+        id: row.get(0),
+        evaluation_id: row.get(1),
+        status: row.get(2),
+        judge_model: row.get(3),
+        judge_prompt_version: row.get(4),
+        judge_verdict: row.get(5),
+        judge_confidence: row.get(6),
+        judge_reasoning: row.get(7),
+        judge_raw_response: row.get(8),
+        uncertain_reason: row.get(9),
+        judge_latency_ms: row.get(10),
+        judge_cost_usd: row.get(11),
+        replaced_at: row.get(12),
+        judge_prompt_rendered: row.get(13),
+        judge_params: row
+            .get::<Option<String>, _>(14)
+            .and_then(|params| serde_json::from_str(&params).ok()),
+    }).collect())
+}
+
+// =======================================================
+// Prompt Version Management
+// =======================================================
 
 #[derive(serde::Serialize, Clone)]
 pub struct PromptVersion {
@@ -399,8 +2150,8 @@ pub struct PromptVersion {
     pub created_by: Option<String>,
 }
 
-pub async fn get_all_prompt_versions(pool: &SqlitePool) -> Result<Vec<PromptVersion>, sqlx::Error> {
-    let rows = sqlx::query(
+pub async fn get_all_prompt_versions(pool: &DbPool) -> Result<Vec<PromptVersion>, sqlx::Error> {
+    let rows = query(
         r#"
         SELECT version, name, prompt_template, description, tags, metadata, 
                is_active, created_at, created_by
@@ -424,8 +2175,8 @@ pub async fn get_all_prompt_versions(pool: &SqlitePool) -> Result<Vec<PromptVers
     }).collect())
 }
 
-pub async fn get_active_prompt_version(pool: &SqlitePool) -> Result<PromptVersion, sqlx::Error> {
-    let row = sqlx::query(
+pub async fn get_active_prompt_version(pool: &DbPool) -> Result<PromptVersion, sqlx::Error> {
+    let row = query(
         r#"
         SELECT version, name, prompt_template, description, tags, metadata,
                is_active, created_at, created_by
@@ -450,26 +2201,53 @@ pub async fn get_active_prompt_version(pool: &SqlitePool) -> Result<PromptVersio
     })
 }
 
-pub async fn create_prompt_version(
-    pool: &SqlitePool,
-    name: String,
-    prompt_template: String,
-    description: Option<String>,
-    tags: Option<Vec<String>>,
-    set_active: bool,
-) -> Result<PromptVersion, sqlx::Error> {
-    let created_at = Utc::now().to_rfc3339();
-    let tags_json = tags.map(|t| serde_json::to_string(&t).unwrap());
-    
-    let mut tx = pool.begin().await?;
-    
-    if set_active {
-        sqlx::query("UPDATE prompt_versions SET is_active = FALSE")
-            .execute(&mut *tx)
-            .await?;
-    }
+/// Prompt version `version`, or `RowNotFound`.
+pub async fn get_prompt_version(pool: &DbPool, version: i64) -> Result<PromptVersion, sqlx::Error> {
+    let row = query(
+        r#"
+        SELECT version, name, prompt_template, description, tags, metadata,
+               is_active, created_at, created_by
+        FROM prompt_versions
+        WHERE version = ?
+        "#
+    )
+    .bind(version)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(PromptVersion {
+        version: row.get(0),
+        name: row.get(1),
+        prompt_template: row.get(2),
+        description: row.get(3),
+        tags: row.get(4),
+        metadata: row.get(5),
+        is_active: row.get(6),
+        created_at: row.get(7),
+        created_by: row.get(8),
+    })
+}
+
+pub async fn create_prompt_version(
+    pool: &DbPool,
+    name: String,
+    prompt_template: String,
+    description: Option<String>,
+    tags: Option<Vec<String>>,
+    set_active: bool,
+) -> Result<PromptVersion, sqlx::Error> {
+    let created_at = Utc::now().to_rfc3339();
+    let tags_json = tags.map(|t| serde_json::to_string(&t).unwrap());
+    
+    let mut tx = pool.begin().await?;
+    
+    if set_active {
+        query("UPDATE prompt_versions SET is_active = FALSE")
+            .execute(&mut tx)
+            .await?;
+    }
     
-    let result = sqlx::query(
+    let result = query(
         r#"
         INSERT INTO prompt_versions (name, prompt_template, description, tags, is_active, created_at)
         VALUES (?, ?, ?, ?, ?, ?)
@@ -482,7 +2260,7 @@ pub async fn create_prompt_version(
     .bind(&tags_json)
     .bind(set_active)
     .bind(&created_at)
-    .fetch_one(&mut *tx)
+    .fetch_one(&mut tx)
     .await?;
     
     tx.commit().await?;
@@ -500,12 +2278,29 @@ pub async fn create_prompt_version(
     })
 }
 
+/// Makes prompt version `version` the only active one, or fails with `RowNotFound`.
+pub async fn set_active_prompt_version(pool: &DbPool, version: i64) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    query("SELECT version FROM prompt_versions WHERE version = ?")
+        .bind(version)
+        .fetch_one(&mut tx)
+        .await?;
+    query("UPDATE prompt_versions SET is_active = (version = ?)")
+        .bind(version)
+        .execute(&mut tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
 pub async fn link_evaluation_to_prompt(
-    pool: &SqlitePool,
+    pool: &DbPool,
     evaluation_id: &str,
     prompt_version: i64,
 ) -> Result<(), sqlx::Error> {
-    sqlx::query(
+    query(
         r#"
         INSERT INTO prompt_evaluations (evaluation_id, prompt_version)
         VALUES (?, ?)
@@ -519,14 +2314,14 @@ pub async fn link_evaluation_to_prompt(
     Ok(())
 }
 
-pub async fn get_prompt_version_stats(pool: &SqlitePool, version: i64) -> Result<PromptStats, sqlx::Error> {
-    let row = sqlx::query(
+pub async fn get_prompt_version_stats(pool: &DbPool, version: i64) -> Result<PromptStats, sqlx::Error> {
+    let row = query(
         r#"
         SELECT 
             COUNT(*) as total_evals,
             SUM(CASE WHEN judge_verdict = 'Pass' THEN 1 ELSE 0 END) as passed,
-            AVG(latency_ms) as avg_latency,
-            AVG(judge_latency_ms) as avg_judge_latency
+            AVG(CAST(latency_ms AS DOUBLE PRECISION)) as avg_latency,
+            AVG(CAST(judge_latency_ms AS DOUBLE PRECISION)) as avg_judge_latency
         FROM evaluations e
         JOIN prompt_evaluations pe ON e.id = pe.evaluation_id
         WHERE pe.prompt_version = ?
@@ -539,7 +2334,7 @@ pub async fn get_prompt_version_stats(pool: &SqlitePool, version: i64) -> Result
     Ok(PromptStats {
         version,
         total_evaluations: row.get(0),
-        passed: row.get(1),
+        passed: row.get::<Option<i64>, _>(1).unwrap_or(0),
         avg_latency_ms: row.get::<Option<f64>, _>(2).unwrap_or(0.0),
         avg_judge_latency_ms: row.get::<Option<f64>, _>(3).unwrap_or(0.0),
     })
@@ -553,3 +2348,3846 @@ pub struct PromptStats {
     pub avg_latency_ms: f64,
     pub avg_judge_latency_ms: f64,
 }
+
+// =======================================================
+// Cost statistics
+// =======================================================
+
+#[derive(serde::Serialize)]
+pub struct CostBucket {
+    /// The model (`provider:model`) or day (`YYYY-MM-DD`) this bucket aggregates.
+    pub key: String,
+    pub evaluations: i64,
+    pub priced_evaluations: i64,
+    pub cost_usd: f64,
+    pub judge_cost_usd: f64,
+    pub total_cost_usd: f64,
+}
+
+#[derive(serde::Serialize)]
+pub struct CostStats {
+    pub by_model: Vec<CostBucket>,
+    pub by_day: Vec<CostBucket>,
+}
+
+/// Aggregates evaluation costs by model and by day (UTC date prefix of `created_at`).
+/// Unpriced evaluations count towards `evaluations` but contribute no cost.
+pub async fn get_cost_stats(pool: &DbPool) -> Result<CostStats, sqlx::Error> {
+    let by_model = fetch_cost_buckets(pool, "model", "total_cost_usd DESC").await?;
+    let by_day = fetch_cost_buckets(pool, "substr(created_at, 1, 10)", "key ASC").await?;
+    Ok(CostStats { by_model, by_day })
+}
+
+async fn fetch_cost_buckets(pool: &DbPool, key_expr: &str, order_by: &str) -> Result<Vec<CostBucket>, sqlx::Error> {
+    let rows = query(&format!(
+        r#"
+        SELECT
+            {} as key,
+            COUNT(*) as evaluations,
+            SUM(CASE WHEN cost_usd IS NOT NULL OR judge_cost_usd IS NOT NULL THEN 1 ELSE 0 END) as priced,
+            COALESCE(SUM(cost_usd), 0.0) as cost_usd,
+            COALESCE(SUM(judge_cost_usd), 0.0) as judge_cost_usd,
+            COALESCE(SUM(cost_usd), 0.0) + COALESCE(SUM(judge_cost_usd), 0.0) as total_cost_usd
+        FROM evaluations
+        WHERE model IS NOT NULL
+        GROUP BY key
+        ORDER BY {}
+        "#,
+        key_expr, order_by
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|row| CostBucket {
+        key: row.get(0),
+        evaluations: row.get(1),
+        priced_evaluations: row.get(2),
+        cost_usd: row.get(3),
+        judge_cost_usd: row.get(4),
+        total_cost_usd: row.get(5),
+    }).collect())
+}
+
+// =======================================================
+// Model leaderboard
+// =======================================================
+
+/// Restricts stats to a time window and/or a tag. Dates compare by prefix, so `to=2026-10-16`
+/// includes the whole day.
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct StatsFilter {
+    /// RFC 3339 timestamp or `YYYY-MM-DD`, inclusive
+    pub from: Option<String>,
+    /// RFC 3339 timestamp or `YYYY-MM-DD`, inclusive
+    pub to: Option<String>,
+    /// Comma-separated tags, combined as `tag_match` says; see `HistoryFilter::tag`.
+    pub tag: Option<String>,
+    #[serde(default)]
+    pub tag_match: TagMatch,
+}
+
+/// `WHERE` conditions for a `StatsFilter`; bind `from`, `to`, the `tags_param` of `tag` and
+/// whether `tag_match` is `any`, in that order.
+fn stats_filter_conditions(backend: Backend) -> String {
+    format!(
+        r#"
+    (?1 IS NULL OR created_at >= ?1)
+    AND (?2 IS NULL OR substr(created_at, 1, length(?2)) <= ?2)
+    AND {}
+"#,
+        tag_condition(backend, 3, 4)
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LeaderboardOrder {
+    /// Highest pass rate first
+    PassRate,
+    /// Lowest average latency first
+    Latency,
+    /// Lowest average cost first
+    Cost,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct LeaderboardEntry {
+    pub model: String,
+    pub total_evaluations: i64,
+    /// Evaluations with a judge verdict; the rates below are over these.
+    pub judged_evaluations: i64,
+    pub passed_evaluations: i64,
+    pub uncertain_evaluations: i64,
+    /// `None` when no evaluation of the model was judged.
+    pub pass_rate: Option<f64>,
+    pub uncertain_rate: Option<f64>,
+    pub avg_latency_ms: Option<f64>,
+    pub p95_latency_ms: Option<i64>,
+    pub avg_output_tokens: Option<f64>,
+    /// Model plus judge cost per priced evaluation.
+    pub avg_cost_usd: Option<f64>,
+    /// Model plus judge cost of all priced evaluations; `None` when none was priced.
+    pub total_cost_usd: Option<f64>,
+}
+
+/// Per-model aggregates over evaluations matching `filter`, sorted by `order`.
+/// Models without a value for the sort key go last.
+pub async fn get_leaderboard(
+    pool: &DbPool,
+    filter: &StatsFilter,
+    order: LeaderboardOrder,
+) -> Result<Vec<LeaderboardEntry>, sqlx::Error> {
+    let rows = query(&format!(
+        r#"
+        SELECT
+            model,
+            COUNT(*),
+            COUNT(judge_verdict),
+            SUM(CASE WHEN judge_verdict = 'Pass' THEN 1 ELSE 0 END),
+            SUM(CASE WHEN judge_verdict = 'Uncertain' THEN 1 ELSE 0 END),
+            AVG(CAST(latency_ms AS DOUBLE PRECISION)),
+            AVG(CAST(output_tokens AS DOUBLE PRECISION)),
+            AVG(CASE
+                WHEN cost_usd IS NULL AND judge_cost_usd IS NULL THEN NULL
+                ELSE COALESCE(cost_usd, 0.0) + COALESCE(judge_cost_usd, 0.0)
+            END),
+            SUM(CASE
+                WHEN cost_usd IS NULL AND judge_cost_usd IS NULL THEN NULL
+                ELSE COALESCE(cost_usd, 0.0) + COALESCE(judge_cost_usd, 0.0)
+            END)
+        FROM evaluations
+        WHERE model IS NOT NULL AND {}
+        GROUP BY model
+        "#,
+        stats_filter_conditions(pool.backend())
+    ))
+    .bind(&filter.from)
+    .bind(&filter.to)
+    .bind(tags_param(filter.tag.as_deref()))
+    .bind(filter.tag_match == TagMatch::Any)
+    .fetch_all(pool)
+    .await?;
+
+    let latencies = query(&format!(
+        "SELECT model, latency_ms FROM evaluations WHERE model IS NOT NULL AND latency_ms IS NOT NULL AND {} ORDER BY model, latency_ms",
+        stats_filter_conditions(pool.backend())
+    ))
+    .bind(&filter.from)
+    .bind(&filter.to)
+    .bind(tags_param(filter.tag.as_deref()))
+    .bind(filter.tag_match == TagMatch::Any)
+    .fetch_all(pool)
+    .await?;
+    let mut latencies_by_model: std::collections::HashMap<String, Vec<i64>> = std::collections::HashMap::new();
+    for row in &latencies {
+        latencies_by_model.entry(row.get(0)).or_default().push(row.get(1));
+    }
+
+    let mut entries: Vec<LeaderboardEntry> = rows.into_iter().map(|row| {
+        let model: String = row.get(0);
+        let judged: i64 = row.get(2);
+        let passed: i64 = row.get(3);
+        let uncertain: i64 = row.get(4);
+        let rate = |count: i64| (judged > 0).then(|| count as f64 / judged as f64);
+        LeaderboardEntry {
+            p95_latency_ms: latencies_by_model.get(&model).and_then(|l| crate::stats::percentile(l, 95.0)),
+            model,
+            total_evaluations: row.get(1),
+            judged_evaluations: judged,
+            passed_evaluations: passed,
+            uncertain_evaluations: uncertain,
+            pass_rate: rate(passed),
+            uncertain_rate: rate(uncertain),
+            avg_latency_ms: row.get(5),
+            avg_output_tokens: row.get(6),
+            avg_cost_usd: row.get(7),
+            total_cost_usd: row.get(8),
+        }
+    }).collect();
+
+    // Descending for pass rate, ascending for latency and cost; missing values last either way
+    let key = |entry: &LeaderboardEntry| match order {
+        LeaderboardOrder::PassRate => entry.pass_rate.map(|r| -r),
+        LeaderboardOrder::Latency => entry.avg_latency_ms,
+        LeaderboardOrder::Cost => entry.avg_cost_usd,
+    };
+    entries.sort_by(|a, b| match (key(a), key(b)) {
+        (Some(x), Some(y)) => x.total_cmp(&y),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    }.then_with(|| a.model.cmp(&b.model)));
+
+    Ok(entries)
+}
+
+// =======================================================
+// Time series and token distribution
+// =======================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeBucket {
+    Hour,
+    Day,
+}
+
+impl TimeBucket {
+    /// Length of the `created_at` prefix that identifies a bucket. Timestamps are stored as
+    /// UTC RFC 3339, so `YYYY-MM-DDTHH` is an hour and `YYYY-MM-DD` a day.
+    fn prefix_len(&self) -> usize {
+        match self {
+            TimeBucket::Hour => 13,
+            TimeBucket::Day => 10,
+        }
+    }
+
+    fn parse(&self, key: &str) -> Option<chrono::NaiveDateTime> {
+        match self {
+            TimeBucket::Hour => chrono::NaiveDateTime::parse_from_str(&format!("{}:00", key), "%Y-%m-%dT%H:%M").ok(),
+            TimeBucket::Day => chrono::NaiveDate::parse_from_str(key, "%Y-%m-%d").ok()?.and_hms_opt(0, 0, 0),
+        }
+    }
+
+    fn format(&self, start: chrono::NaiveDateTime) -> String {
+        match self {
+            TimeBucket::Hour => start.format("%Y-%m-%dT%H").to_string(),
+            TimeBucket::Day => start.format("%Y-%m-%d").to_string(),
+        }
+    }
+
+    fn step(&self) -> chrono::Duration {
+        match self {
+            TimeBucket::Hour => chrono::Duration::hours(1),
+            TimeBucket::Day => chrono::Duration::days(1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TimeSeriesPoint {
+    /// `YYYY-MM-DD` for days, `YYYY-MM-DDTHH` for hours (UTC).
+    pub bucket: String,
+    pub evaluations: i64,
+    pub judged_evaluations: i64,
+    /// `None` when nothing in the bucket was judged.
+    pub pass_rate: Option<f64>,
+    pub avg_input_tokens: Option<f64>,
+    pub avg_output_tokens: Option<f64>,
+    pub avg_latency_ms: Option<f64>,
+}
+
+impl TimeSeriesPoint {
+    fn empty(bucket: String) -> Self {
+        Self {
+            bucket,
+            evaluations: 0,
+            judged_evaluations: 0,
+            pass_rate: None,
+            avg_input_tokens: None,
+            avg_output_tokens: None,
+            avg_latency_ms: None,
+        }
+    }
+}
+
+/// Per-bucket pass rate, volume, tokens and latency, optionally for one model. Buckets between
+/// the first and last evaluation that saw no evaluations are included with zero counts.
+pub async fn get_timeseries(
+    pool: &DbPool,
+    bucket: TimeBucket,
+    model: Option<&str>,
+) -> Result<Vec<TimeSeriesPoint>, sqlx::Error> {
+    let rows = query(
+        r#"
+        SELECT
+            substr(created_at, 1, ?1) as bucket,
+            COUNT(*),
+            COUNT(judge_verdict),
+            SUM(CASE WHEN judge_verdict = 'Pass' THEN 1 ELSE 0 END),
+            AVG(CAST(input_tokens AS DOUBLE PRECISION)),
+            AVG(CAST(output_tokens AS DOUBLE PRECISION)),
+            AVG(CAST(latency_ms AS DOUBLE PRECISION))
+        FROM evaluations
+        WHERE model IS NOT NULL AND (?2 IS NULL OR model = ?2)
+        GROUP BY bucket
+        ORDER BY bucket
+        "#
+    )
+    .bind(bucket.prefix_len() as i32)
+    .bind(model)
+    .fetch_all(pool)
+    .await?;
+
+    let points = rows.into_iter().map(|row| {
+        let judged: i64 = row.get(2);
+        let passed: i64 = row.get(3);
+        TimeSeriesPoint {
+            bucket: row.get(0),
+            evaluations: row.get(1),
+            judged_evaluations: judged,
+            pass_rate: (judged > 0).then(|| passed as f64 / judged as f64),
+            avg_input_tokens: row.get(4),
+            avg_output_tokens: row.get(5),
+            avg_latency_ms: row.get(6),
+        }
+    }).collect();
+
+    Ok(fill_time_gaps(points, bucket))
+}
+
+/// Inserts empty points for buckets missing between consecutive points.
+fn fill_time_gaps(points: Vec<TimeSeriesPoint>, bucket: TimeBucket) -> Vec<TimeSeriesPoint> {
+    let mut filled: Vec<TimeSeriesPoint> = Vec::with_capacity(points.len());
+    for point in points {
+        let previous = filled.last().and_then(|p| bucket.parse(&p.bucket));
+        if let (Some(previous), Some(current)) = (previous, bucket.parse(&point.bucket)) {
+            let mut next = previous + bucket.step();
+            while next < current {
+                filled.push(TimeSeriesPoint::empty(bucket.format(next)));
+                next += bucket.step();
+            }
+        }
+        filled.push(point);
+    }
+    filled
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TokenBucket {
+    /// Inclusive lower bound of output tokens.
+    pub start: i64,
+    /// Exclusive upper bound of output tokens.
+    pub end: i64,
+    pub evaluations: i64,
+}
+
+/// Histogram of output token counts in `bucket_width`-wide buckets, optionally for one model.
+/// Empty buckets between the smallest and largest count are included.
+pub async fn get_token_distribution(
+    pool: &DbPool,
+    bucket_width: i64,
+    model: Option<&str>,
+) -> Result<Vec<TokenBucket>, sqlx::Error> {
+    let rows = query(
+        r#"
+        SELECT (output_tokens / ?1) * ?1 as bucket_start, COUNT(*)
+        FROM evaluations
+        WHERE output_tokens IS NOT NULL AND (?2 IS NULL OR model = ?2)
+        GROUP BY bucket_start
+        ORDER BY bucket_start
+        "#
+    )
+    .bind(bucket_width)
+    .bind(model)
+    .fetch_all(pool)
+    .await?;
+
+    let counts: Vec<(i64, i64)> = rows.iter().map(|row| (row.get(0), row.get(1))).collect();
+    Ok(fill_token_buckets(&counts, bucket_width))
+}
+
+/// Buckets from the first to the last of `counts`, ascending `(bucket_start, evaluations)`
+/// pairs, with the missing ones empty.
+fn fill_token_buckets(counts: &[(i64, i64)], bucket_width: i64) -> Vec<TokenBucket> {
+    let (Some(first), Some(last)) = (counts.first(), counts.last()) else {
+        return Vec::new();
+    };
+
+    let mut counts = counts.iter().peekable();
+    (first.0..=last.0)
+        .step_by(bucket_width as usize)
+        .map(|start| TokenBucket {
+            start,
+            end: start + bucket_width,
+            evaluations: counts.next_if(|(s, _)| *s == start).map_or(0, |(_, n)| *n),
+        })
+        .collect()
+}
+
+/// A model's output length and pass rate over one week.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct WeeklyOutputTokens {
+    pub model: String,
+    /// Monday the week starts on, `YYYY-MM-DD` (UTC).
+    pub week: String,
+    pub evaluations: i64,
+    /// `None` when no evaluation of the week recorded its output tokens.
+    pub mean_output_tokens: Option<f64>,
+    pub max_output_tokens: Option<i64>,
+    pub judged_evaluations: i64,
+    /// `None` when nothing in the week was judged.
+    pub pass_rate: Option<f64>,
+    /// Histogram of the week's output tokens; empty unless a bucket width was asked for.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub histogram: Vec<TokenBucket>,
+}
+
+/// Output tokens and pass rate per model and week, optionally for one model and from weeks
+/// starting on or after `since` (`YYYY-MM-DD`), ordered by model and week. With a
+/// `bucket_width`, each week also gets its histogram of output tokens.
+pub async fn get_weekly_output_tokens(
+    pool: &DbPool,
+    model: Option<&str>,
+    since: Option<&str>,
+    bucket_width: Option<i64>,
+) -> Result<Vec<WeeklyOutputTokens>, sqlx::Error> {
+    let week = pool.backend().week_start("created_at");
+    let rows = query(&format!(
+        r#"
+        SELECT
+            model,
+            {} as week,
+            COUNT(*),
+            AVG(CAST(output_tokens AS DOUBLE PRECISION)),
+            MAX(output_tokens),
+            COUNT(judge_verdict),
+            SUM(CASE WHEN judge_verdict = 'Pass' THEN 1 ELSE 0 END)
+        FROM evaluations
+        WHERE model IS NOT NULL AND (?1 IS NULL OR model = ?1) AND (?2 IS NULL OR created_at >= ?2)
+        GROUP BY model, week
+        ORDER BY model, week
+        "#,
+        week
+    ))
+    .bind(model)
+    .bind(since)
+    .fetch_all(pool)
+    .await?;
+
+    let mut weeks: Vec<WeeklyOutputTokens> = rows.into_iter().map(|row| {
+        let judged: i64 = row.get(5);
+        let passed: i64 = row.get(6);
+        WeeklyOutputTokens {
+            model: row.get(0),
+            week: row.get(1),
+            evaluations: row.get(2),
+            mean_output_tokens: row.get(3),
+            max_output_tokens: row.get(4),
+            judged_evaluations: judged,
+            pass_rate: (judged > 0).then(|| passed as f64 / judged as f64),
+            histogram: Vec::new(),
+        }
+    }).collect();
+
+    let Some(bucket_width) = bucket_width else {
+        return Ok(weeks);
+    };
+    let rows = query(&format!(
+        r#"
+        SELECT model, {} as week, (output_tokens / ?1) * ?1 as bucket_start, COUNT(*)
+        FROM evaluations
+        WHERE model IS NOT NULL AND output_tokens IS NOT NULL
+            AND (?2 IS NULL OR model = ?2) AND (?3 IS NULL OR created_at >= ?3)
+        GROUP BY model, week, bucket_start
+        ORDER BY model, week, bucket_start
+        "#,
+        week
+    ))
+    .bind(bucket_width)
+    .bind(model)
+    .bind(since)
+    .fetch_all(pool)
+    .await?;
+
+    let mut counts: BTreeMap<(String, String), Vec<(i64, i64)>> = BTreeMap::new();
+    for row in &rows {
+        counts.entry((row.get(0), row.get(1))).or_default().push((row.get(2), row.get(3)));
+    }
+    for week in &mut weeks {
+        if let Some(counts) = counts.get(&(week.model.clone(), week.week.clone())) {
+            week.histogram = fill_token_buckets(counts, bucket_width);
+        }
+    }
+    Ok(weeks)
+}
+
+// =======================================================
+// Human review queue
+// =======================================================
+
+/// Evaluations whose judge verdict fell below the confidence threshold and still await review,
+/// oldest first.
+pub async fn get_review_queue(pool: &DbPool) -> Result<Vec<HistoryEntry>, sqlx::Error> {
+    let rows = query(&format!(
+        "SELECT {} FROM evaluations WHERE status = 'needs_review' ORDER BY created_at ASC",
+        HISTORY_COLUMNS
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(history_entry_from_row).collect())
+}
+
+/// Maps a verdict string to the status stored for a reviewed evaluation.
+fn status_for_verdict(verdict: &str) -> &'static str {
+    match verdict {
+        "Pass" => "passed",
+        "Fail" => "failed",
+        _ => "uncertain",
+    }
+}
+
+/// Records a human verdict for one evaluation, replacing its status. An `Uncertain` verdict
+/// records `human_review` as the uncertain reason; any other keeps the judge's reason.
+/// A `Fail` verdict with a `failure_category` replaces the classified category with the
+/// reviewer's; a `Fail` verdict without one keeps it, and any other verdict clears it.
+pub async fn review_evaluation(
+    pool: &DbPool,
+    id: &str,
+    verdict: &str,
+    failure_category: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    let result = query(
+        r#"
+        UPDATE evaluations
+        SET human_verdict = ?1, status = ?2, reviewed_at = ?3,
+            uncertain_reason = CASE WHEN ?1 = 'Uncertain' THEN ?4 ELSE uncertain_reason END,
+            failure_category = CASE WHEN ?1 <> 'Fail' THEN NULL ELSE COALESCE(?6, failure_category) END,
+            failure_justification = CASE WHEN ?1 <> 'Fail' OR ?6 IS NOT NULL THEN NULL ELSE failure_justification END,
+            failure_classifier = CASE WHEN ?1 <> 'Fail' THEN NULL WHEN ?6 IS NOT NULL THEN ?7 ELSE failure_classifier END
+        WHERE id = ?5
+        "#
+    )
+    .bind(verdict)
+    .bind(status_for_verdict(verdict))
+    .bind(Utc::now().to_rfc3339())
+    .bind(UncertainReason::HumanReview.as_str())
+    .bind(id)
+    .bind(failure_category)
+    .bind(crate::failure_taxonomy::HUMAN_CLASSIFIER)
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+    Ok(())
+}
+
+/// Accepts the judge's provisional verdicts for queued evaluations in bulk.
+/// Ids that are not awaiting review are ignored. Returns the number of evaluations accepted.
+pub async fn accept_provisional_verdicts(pool: &DbPool, ids: &[String]) -> Result<u64, sqlx::Error> {
+    let reviewed_at = Utc::now().to_rfc3339();
+    let mut tx = pool.begin().await?;
+    let mut accepted = 0;
+
+    for id in ids {
+        let result = query(
+            r#"
+            UPDATE evaluations
+            SET human_verdict = judge_verdict,
+                status = CASE judge_verdict WHEN 'Pass' THEN 'passed' WHEN 'Fail' THEN 'failed' ELSE 'uncertain' END,
+                reviewed_at = ?
+            WHERE id = ? AND status = 'needs_review'
+            "#
+        )
+        .bind(&reviewed_at)
+        .bind(id)
+        .execute(&mut tx)
+        .await?;
+        accepted += result.rows_affected();
+    }
+
+    tx.commit().await?;
+    Ok(accepted)
+}
+
+#[derive(serde::Serialize)]
+pub struct ReviewStats {
+    pub judge_prompt_version: Option<i64>,
+    pub judged: i64,
+    pub needs_review: i64,
+    pub needs_review_rate: f64,
+    pub pending_review: i64,
+    /// Evaluations per uncertain reason, telling prompt, parser and threshold problems apart.
+    pub uncertain_reasons: BTreeMap<String, i64>,
+}
+
+/// How often judged evaluations were routed to review, and why, per judge prompt version.
+pub async fn get_review_stats(pool: &DbPool) -> Result<Vec<ReviewStats>, sqlx::Error> {
+    let rows = query(
+        r#"
+        SELECT
+            judge_prompt_version,
+            COUNT(*) as judged,
+            SUM(CASE WHEN needs_review THEN 1 ELSE 0 END) as needs_review,
+            SUM(CASE WHEN status = 'needs_review' THEN 1 ELSE 0 END) as pending_review
+        FROM evaluations
+        WHERE judge_verdict IS NOT NULL
+        GROUP BY judge_prompt_version
+        ORDER BY judge_prompt_version DESC
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let reason_rows = query(
+        r#"
+        SELECT judge_prompt_version, uncertain_reason, COUNT(*)
+        FROM evaluations
+        WHERE judge_verdict IS NOT NULL AND uncertain_reason IS NOT NULL
+        GROUP BY judge_prompt_version, uncertain_reason
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|row| {
+        let judge_prompt_version: Option<i64> = row.get(0);
+        let judged: i64 = row.get(1);
+        let needs_review: i64 = row.get(2);
+        let uncertain_reasons = reason_rows
+            .iter()
+            .filter(|r| r.get::<Option<i64>, _>(0) == judge_prompt_version)
+            .map(|r| (r.get(1), r.get(2)))
+            .collect();
+        ReviewStats {
+            judge_prompt_version,
+            judged,
+            needs_review,
+            needs_review_rate: if judged > 0 { needs_review as f64 / judged as f64 } else { 0.0 },
+            pending_review: row.get(3),
+            uncertain_reasons,
+        }
+    }).collect())
+}
+
+// =======================================================
+// Triage queue
+// =======================================================
+
+/// Evaluations a human still has to look at: an `Uncertain` judge verdict or a failed judge
+/// call, and no human verdict yet. Matches `idx_evaluations_triage`.
+const TRIAGE_CONDITION: &str = "human_verdict IS NULL AND (judge_verdict = 'Uncertain' OR status = 'judge_error')";
+
+/// A page of the triage queue, oldest first.
+pub async fn get_triage_queue(pool: &DbPool, limit: i64, offset: i64) -> Result<Vec<HistoryEntry>, sqlx::Error> {
+    let rows = query(&format!(
+        "SELECT {} FROM evaluations WHERE {} ORDER BY created_at ASC, id ASC LIMIT ? OFFSET ?",
+        HISTORY_COLUMNS, TRIAGE_CONDITION
+    ))
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(history_entry_from_row).collect())
+}
+
+/// How many evaluations the triage queue holds.
+pub async fn count_triage_queue(pool: &DbPool) -> Result<i64, sqlx::Error> {
+    query_scalar(&format!("SELECT COUNT(*) FROM evaluations WHERE {}", TRIAGE_CONDITION))
+        .fetch_one(pool)
+        .await
+}
+
+#[derive(serde::Serialize)]
+pub struct UncertainReasonStats {
+    pub uncertain_reason: String,
+    pub evaluations: i64,
+    /// Of those, how many ended with an `Uncertain` verdict rather than a low-confidence pass or fail.
+    pub uncertain_verdicts: i64,
+    /// Share of all evaluations that have an uncertain reason.
+    pub share: f64,
+}
+
+/// Evaluations per uncertain reason, most frequent first.
+pub async fn get_uncertain_reason_stats(pool: &DbPool, filter: &StatsFilter) -> Result<Vec<UncertainReasonStats>, sqlx::Error> {
+    let rows = query(&format!(
+        r#"
+        SELECT
+            uncertain_reason,
+            COUNT(*),
+            SUM(CASE WHEN COALESCE(human_verdict, judge_verdict) = 'Uncertain' THEN 1 ELSE 0 END)
+        FROM evaluations
+        WHERE uncertain_reason IS NOT NULL AND {}
+        GROUP BY uncertain_reason
+        ORDER BY COUNT(*) DESC, uncertain_reason ASC
+        "#,
+        stats_filter_conditions(pool.backend())
+    ))
+    .bind(&filter.from)
+    .bind(&filter.to)
+    .bind(tags_param(filter.tag.as_deref()))
+    .bind(filter.tag_match == TagMatch::Any)
+    .fetch_all(pool)
+    .await?;
+
+    let total: i64 = rows.iter().map(|row| row.get::<i64, _>(1)).sum();
+    Ok(rows.iter().map(|row| {
+        let evaluations: i64 = row.get(1);
+        UncertainReasonStats {
+            uncertain_reason: row.get(0),
+            evaluations,
+            uncertain_verdicts: row.get(2),
+            share: evaluations as f64 / total as f64,
+        }
+    }).collect())
+}
+
+/// Batched and individually judged verdicts of one judge model, to measure what batching
+/// costs in quality.
+#[derive(serde::Serialize)]
+pub struct JudgeBatchingStats {
+    pub judge_model: String,
+    /// `batched` or `individual`
+    pub mode: String,
+    pub judged: i64,
+    pub avg_batch_size: Option<f64>,
+    pub pass_rate: f64,
+    pub uncertain_rate: f64,
+    pub avg_confidence: Option<f64>,
+    pub needs_review_rate: f64,
+    /// Verdicts a human has reviewed
+    pub reviewed: i64,
+    /// Share of reviewed verdicts the human agreed with; `None` when none were reviewed.
+    pub human_agreement_rate: Option<f64>,
+    pub avg_judge_latency_ms: Option<f64>,
+    pub avg_judge_cost_usd: Option<f64>,
+}
+
+/// Judge verdicts per judge model, split into batched and individually judged ones.
+pub async fn get_judge_batching_stats(pool: &DbPool, filter: &StatsFilter) -> Result<Vec<JudgeBatchingStats>, sqlx::Error> {
+    let rows = query(&format!(
+        r#"
+        SELECT
+            judge_model,
+            CASE WHEN judge_batch_size IS NULL THEN 'individual' ELSE 'batched' END AS mode,
+            COUNT(*),
+            AVG(CAST(judge_batch_size AS DOUBLE PRECISION)),
+            SUM(CASE WHEN judge_verdict = 'Pass' THEN 1 ELSE 0 END),
+            SUM(CASE WHEN judge_verdict = 'Uncertain' THEN 1 ELSE 0 END),
+            AVG(judge_confidence),
+            SUM(CASE WHEN needs_review THEN 1 ELSE 0 END),
+            SUM(CASE WHEN human_verdict IS NOT NULL THEN 1 ELSE 0 END),
+            SUM(CASE WHEN human_verdict = judge_verdict THEN 1 ELSE 0 END),
+            AVG(CAST(judge_latency_ms AS DOUBLE PRECISION)),
+            AVG(judge_cost_usd)
+        FROM evaluations
+        WHERE judge_verdict IS NOT NULL AND judge_model IS NOT NULL AND judge_model NOT IN ('format-rule', 'finish-reason') AND {}
+        GROUP BY judge_model, mode
+        ORDER BY judge_model ASC, mode ASC
+        "#,
+        stats_filter_conditions(pool.backend())
+    ))
+    .bind(&filter.from)
+    .bind(&filter.to)
+    .bind(tags_param(filter.tag.as_deref()))
+    .bind(filter.tag_match == TagMatch::Any)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(|row| {
+        let judged: i64 = row.get(2);
+        let reviewed: i64 = row.get(8);
+        let agreed: i64 = row.get(9);
+        JudgeBatchingStats {
+            judge_model: row.get(0),
+            mode: row.get(1),
+            judged,
+            avg_batch_size: row.get(3),
+            pass_rate: row.get::<i64, _>(4) as f64 / judged as f64,
+            uncertain_rate: row.get::<i64, _>(5) as f64 / judged as f64,
+            avg_confidence: row.get(6),
+            needs_review_rate: row.get::<i64, _>(7) as f64 / judged as f64,
+            reviewed,
+            human_agreement_rate: (reviewed > 0).then(|| agreed as f64 / reviewed as f64),
+            avg_judge_latency_ms: row.get(10),
+            avg_judge_cost_usd: row.get(11),
+        }
+    }).collect())
+}
+
+/// How often a model's generations ended for each finish reason.
+#[derive(serde::Serialize)]
+pub struct FinishReasonStats {
+    pub model: String,
+    pub evaluations: i64,
+    /// Evaluations per normalized finish reason, or per raw provider value for unknown ones.
+    pub finish_reasons: BTreeMap<String, i64>,
+    /// Evaluations whose provider reported no finish reason, including errors and aborted streams.
+    pub unreported: i64,
+    /// Share of evaluations cut off at the output token limit.
+    pub length_rate: f64,
+}
+
+/// Finish reason counts per model, most evaluations first.
+pub async fn get_finish_reason_stats(pool: &DbPool, filter: &StatsFilter) -> Result<Vec<FinishReasonStats>, sqlx::Error> {
+    let rows = query(&format!(
+        r#"
+        SELECT model, finish_reason, COUNT(*)
+        FROM evaluations
+        WHERE model IS NOT NULL AND {}
+        GROUP BY model, finish_reason
+        "#,
+        stats_filter_conditions(pool.backend())
+    ))
+    .bind(&filter.from)
+    .bind(&filter.to)
+    .bind(tags_param(filter.tag.as_deref()))
+    .bind(filter.tag_match == TagMatch::Any)
+    .fetch_all(pool)
+    .await?;
+
+    let mut by_model: BTreeMap<String, FinishReasonStats> = BTreeMap::new();
+    for row in &rows {
+        let model: String = row.get(0);
+        let count: i64 = row.get(2);
+        let stats = by_model.entry(model.clone()).or_insert_with(|| FinishReasonStats {
+            model,
+            evaluations: 0,
+            finish_reasons: BTreeMap::new(),
+            unreported: 0,
+            length_rate: 0.0,
+        });
+        stats.evaluations += count;
+        match row.get::<Option<String>, _>(1) {
+            Some(reason) => *stats.finish_reasons.entry(reason).or_insert(0) += count,
+            None => stats.unreported += count,
+        }
+    }
+
+    let mut stats: Vec<FinishReasonStats> = by_model.into_values().collect();
+    for model in &mut stats {
+        let cut_off = model.finish_reasons.get("length").copied().unwrap_or(0);
+        model.length_rate = cut_off as f64 / model.evaluations as f64;
+    }
+    stats.sort_by(|a, b| b.evaluations.cmp(&a.evaluations).then_with(|| a.model.cmp(&b.model)));
+    Ok(stats)
+}
+
+// =======================================================
+// Failure taxonomy
+// =======================================================
+
+/// The failure taxonomy, in its stored order.
+pub async fn get_failure_categories(pool: &DbPool) -> Result<Vec<FailureCategory>, sqlx::Error> {
+    let rows = query("SELECT name, description FROM failure_categories ORDER BY position ASC")
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows.iter().map(|row| FailureCategory {
+        name: row.get(0),
+        description: row.get(1),
+    }).collect())
+}
+
+/// Replaces the whole failure taxonomy with `categories`. Evaluations keep the categories
+/// they were given, including ones no longer in the taxonomy.
+pub async fn replace_failure_categories(pool: &DbPool, categories: &[FailureCategory]) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    query("DELETE FROM failure_categories").execute(&mut tx).await?;
+    insert_failure_categories(&mut tx, categories).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+async fn insert_failure_categories(
+    tx: &mut Transaction,
+    categories: &[FailureCategory],
+) -> Result<(), sqlx::Error> {
+    for (position, category) in categories.iter().enumerate() {
+        query("INSERT INTO failure_categories (name, description, position) VALUES (?, ?, ?)")
+            .bind(&category.name)
+            .bind(&category.description)
+            .bind(position as i64)
+            .execute(&mut *tx)
+            .await?;
+    }
+    Ok(())
+}
+
+#[derive(serde::Serialize)]
+pub struct FailureCategoryStats {
+    pub model: String,
+    /// Evaluations whose final verdict is `Fail`.
+    pub failed: i64,
+    /// Failed evaluations per failure category.
+    pub categories: BTreeMap<String, i64>,
+    /// Failed evaluations that were not classified.
+    pub unclassified: i64,
+    /// Categories a human set or corrected during review.
+    pub human_classified: i64,
+    pub classification_cost_usd: f64,
+}
+
+/// Failure categories of failed evaluations per model, most failures first. The verdict
+/// counted is the human one when an evaluation was reviewed.
+pub async fn get_failure_category_stats(
+    pool: &DbPool,
+    filter: &StatsFilter,
+    model: Option<&str>,
+) -> Result<Vec<FailureCategoryStats>, sqlx::Error> {
+    let rows = query(&format!(
+        r#"
+        SELECT
+            model,
+            failure_category,
+            COUNT(*),
+            SUM(CASE WHEN failure_classifier = ?6 THEN 1 ELSE 0 END),
+            COALESCE(SUM(failure_classification_cost_usd), 0.0)
+        FROM evaluations
+        WHERE model IS NOT NULL
+            AND COALESCE(human_verdict, judge_verdict) = 'Fail'
+            AND (?5 IS NULL OR model = ?5)
+            AND {}
+        GROUP BY model, failure_category
+        "#,
+        stats_filter_conditions(pool.backend())
+    ))
+    .bind(&filter.from)
+    .bind(&filter.to)
+    .bind(tags_param(filter.tag.as_deref()))
+    .bind(filter.tag_match == TagMatch::Any)
+    .bind(model)
+    .bind(crate::failure_taxonomy::HUMAN_CLASSIFIER)
+    .fetch_all(pool)
+    .await?;
+
+    let mut by_model: BTreeMap<String, FailureCategoryStats> = BTreeMap::new();
+    for row in &rows {
+        let model: String = row.get(0);
+        let count: i64 = row.get(2);
+        let stats = by_model.entry(model.clone()).or_insert_with(|| FailureCategoryStats {
+            model,
+            failed: 0,
+            categories: BTreeMap::new(),
+            unclassified: 0,
+            human_classified: 0,
+            classification_cost_usd: 0.0,
+        });
+        stats.failed += count;
+        stats.human_classified += row.get::<i64, _>(3);
+        stats.classification_cost_usd += row.get::<f64, _>(4);
+        match row.get::<Option<String>, _>(1) {
+            Some(category) => *stats.categories.entry(category).or_insert(0) += count,
+            None => stats.unclassified += count,
+        }
+    }
+
+    let mut stats: Vec<FailureCategoryStats> = by_model.into_values().collect();
+    stats.sort_by(|a, b| b.failed.cmp(&a.failed).then_with(|| a.model.cmp(&b.model)));
+    Ok(stats)
+}
+
+// =======================================================
+// Evaluation suites
+// =======================================================
+
+#[derive(serde::Serialize, Clone)]
+pub struct Suite {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub configs: Vec<EvalConfig>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(serde::Serialize)]
+pub struct SuiteRunSummary {
+    pub id: String,
+    pub suite_id: String,
+    pub batch_id: String,
+    pub created_at: String,
+    pub total: i64,
+    pub passed: i64,
+    pub failed: i64,
+    pub needs_review: i64,
+    pub errors: i64,
+    /// Share of the run's evaluations that passed, reflecting any later human review.
+    pub pass_rate: f64,
+}
+
+const SUITE_COLUMNS: &str = "id, name, description, configs, created_at, updated_at";
+
+fn suite_from_row(row: &DbRow) -> Result<Suite, sqlx::Error> {
+    let configs: String = row.get(3);
+    Ok(Suite {
+        id: row.get(0),
+        name: row.get(1),
+        description: row.get(2),
+        configs: serde_json::from_str(&configs).map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+        created_at: row.get(4),
+        updated_at: row.get(5),
+    })
+}
+
+fn encode_configs(configs: &[EvalConfig]) -> Result<String, sqlx::Error> {
+    serde_json::to_string(configs).map_err(|e| sqlx::Error::Encode(Box::new(e)))
+}
+
+pub async fn get_all_suites(pool: &DbPool) -> Result<Vec<Suite>, sqlx::Error> {
+    let rows = query(&format!("SELECT {} FROM suites ORDER BY name", SUITE_COLUMNS))
+        .fetch_all(pool)
+        .await?;
+
+    rows.iter().map(suite_from_row).collect()
+}
+
+/// Looks a suite up by id or by name.
+pub async fn get_suite(pool: &DbPool, id_or_name: &str) -> Result<Suite, sqlx::Error> {
+    let row = query(&format!("SELECT {} FROM suites WHERE id = ? OR name = ?", SUITE_COLUMNS))
+        .bind(id_or_name)
+        .bind(id_or_name)
+        .fetch_one(pool)
+        .await?;
+
+    suite_from_row(&row)
+}
+
+pub async fn create_suite(
+    pool: &DbPool,
+    name: String,
+    description: Option<String>,
+    configs: &[EvalConfig],
+) -> Result<Suite, sqlx::Error> {
+    let id = uuid::Uuid::new_v4().to_string();
+    let now = Utc::now().to_rfc3339();
+
+    query(
+        r#"
+        INSERT INTO suites (id, name, description, configs, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&id)
+    .bind(&name)
+    .bind(&description)
+    .bind(encode_configs(configs)?)
+    .bind(&now)
+    .bind(&now)
+    .execute(pool)
+    .await?;
+
+    Ok(Suite {
+        id,
+        name,
+        description,
+        configs: configs.to_vec(),
+        created_at: now.clone(),
+        updated_at: now,
+    })
+}
+
+/// Replaces a suite's name, description and configs.
+pub async fn update_suite(
+    pool: &DbPool,
+    id_or_name: &str,
+    name: String,
+    description: Option<String>,
+    configs: &[EvalConfig],
+) -> Result<Suite, sqlx::Error> {
+    let row = query(&format!(
+        r#"
+        UPDATE suites
+        SET name = ?, description = ?, configs = ?, updated_at = ?
+        WHERE id = ? OR name = ?
+        RETURNING {}
+        "#,
+        SUITE_COLUMNS
+    ))
+    .bind(&name)
+    .bind(&description)
+    .bind(encode_configs(configs)?)
+    .bind(Utc::now().to_rfc3339())
+    .bind(id_or_name)
+    .bind(id_or_name)
+    .fetch_one(pool)
+    .await?;
+
+    suite_from_row(&row)
+}
+
+/// Creates the suite named `name`, or replaces the description and configs of the one that
+/// exists, keeping its id and runs.
+pub async fn upsert_suite(
+    pool: &DbPool,
+    name: String,
+    description: Option<String>,
+    configs: &[EvalConfig],
+) -> Result<Suite, sqlx::Error> {
+    match update_suite(pool, &name, name.clone(), description.clone(), configs).await {
+        Err(sqlx::Error::RowNotFound) => create_suite(pool, name, description, configs).await,
+        result => result,
+    }
+}
+
+/// Deletes a suite together with its run records. The evaluations themselves are kept.
+pub async fn delete_suite(pool: &DbPool, id_or_name: &str) -> Result<(), sqlx::Error> {
+    let result = query("DELETE FROM suites WHERE id = ? OR name = ?")
+        .bind(id_or_name)
+        .bind(id_or_name)
+        .execute(pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+    Ok(())
+}
+
+/// Records a run of `suite_id` and links the evaluations it produced. Returns the run id.
+pub async fn create_suite_run(
+    pool: &DbPool,
+    suite_id: &str,
+    batch_id: &str,
+    evaluation_ids: &[String],
+) -> Result<String, sqlx::Error> {
+    let id = uuid::Uuid::new_v4().to_string();
+    let mut tx = pool.begin().await?;
+
+    query("INSERT INTO suite_runs (id, suite_id, batch_id, created_at) VALUES (?, ?, ?, ?)")
+        .bind(&id)
+        .bind(suite_id)
+        .bind(batch_id)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&mut tx)
+        .await?;
+
+    for evaluation_id in evaluation_ids {
+        query("INSERT INTO suite_run_evaluations (suite_run_id, evaluation_id) VALUES (?, ?)")
+            .bind(&id)
+            .bind(evaluation_id)
+            .execute(&mut tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+    Ok(id)
+}
+
+/// Runs of a suite, newest first, with outcome counts computed from the linked evaluations.
+pub async fn get_suite_runs(pool: &DbPool, suite_id: &str) -> Result<Vec<SuiteRunSummary>, sqlx::Error> {
+    let rows = query(
+        r#"
+        SELECT
+            sr.id, sr.suite_id, sr.batch_id, sr.created_at,
+            COUNT(sre.evaluation_id) as total,
+            COALESCE(SUM(CASE WHEN e.status = 'passed' THEN 1 ELSE 0 END), 0) as passed,
+            COALESCE(SUM(CASE WHEN e.status = 'failed' THEN 1 ELSE 0 END), 0) as failed,
+            COALESCE(SUM(CASE WHEN e.status = 'needs_review' THEN 1 ELSE 0 END), 0) as needs_review,
+            COALESCE(SUM(CASE WHEN e.status IN ('error', 'blocked') THEN 1 ELSE 0 END), 0) as errors
+        FROM suite_runs sr
+        LEFT JOIN suite_run_evaluations sre ON sre.suite_run_id = sr.id
+        LEFT JOIN evaluations e ON e.id = sre.evaluation_id
+        WHERE sr.suite_id = ?
+        GROUP BY sr.id
+        ORDER BY sr.created_at DESC
+        "#
+    )
+    .bind(suite_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|row| {
+        let total: i64 = row.get(4);
+        let passed: i64 = row.get(5);
+        SuiteRunSummary {
+            id: row.get(0),
+            suite_id: row.get(1),
+            batch_id: row.get(2),
+            created_at: row.get(3),
+            total,
+            passed,
+            failed: row.get(6),
+            needs_review: row.get(7),
+            errors: row.get(8),
+            pass_rate: if total > 0 { passed as f64 / total as f64 } else { 0.0 },
+        }
+    }).collect())
+}
+
+// =======================================================
+// Robustness runs
+// =======================================================
+
+#[derive(serde::Serialize, Clone)]
+pub struct RobustnessGroup {
+    pub id: String,
+    pub batch_id: String,
+    pub model: String,
+    pub base_prompt: String,
+    pub expected: Option<String>,
+    /// `None` when the variants were supplied by the user.
+    pub paraphrase_model: Option<String>,
+    pub paraphrase_input_tokens: Option<i64>,
+    pub paraphrase_output_tokens: Option<i64>,
+    pub paraphrase_cost_usd: Option<f64>,
+    pub created_at: String,
+}
+
+/// Records a robustness run and the evaluation each of its variants produced.
+pub async fn create_robustness_group(
+    pool: &DbPool,
+    group: &RobustnessGroup,
+    variants: &[VariantOutcome],
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    query(
+        r#"
+        INSERT INTO robustness_groups (
+            id, batch_id, model, base_prompt, expected, paraphrase_model,
+            paraphrase_input_tokens, paraphrase_output_tokens, paraphrase_cost_usd, created_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&group.id)
+    .bind(&group.batch_id)
+    .bind(&group.model)
+    .bind(&group.base_prompt)
+    .bind(&group.expected)
+    .bind(&group.paraphrase_model)
+    .bind(group.paraphrase_input_tokens)
+    .bind(group.paraphrase_output_tokens)
+    .bind(group.paraphrase_cost_usd)
+    .bind(&group.created_at)
+    .execute(&mut tx)
+    .await?;
+
+    for variant in variants {
+        query(
+            "INSERT INTO robustness_variants (group_id, variant_index, prompt, source, evaluation_id) VALUES (?, ?, ?, ?, ?)"
+        )
+        .bind(&group.id)
+        .bind(variant.variant_index as i64)
+        .bind(&variant.prompt)
+        .bind(variant.source.as_str())
+        .bind(&variant.eval_id)
+        .execute(&mut tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// A robustness run with its variants in order. Each variant's status and verdict are read from
+/// its evaluation, so human reviews made since the run are reflected.
+pub async fn get_robustness_group(
+    pool: &DbPool,
+    id: &str,
+) -> Result<(RobustnessGroup, Vec<VariantOutcome>), sqlx::Error> {
+    let row = query(
+        r#"
+        SELECT id, batch_id, model, base_prompt, expected, paraphrase_model,
+               paraphrase_input_tokens, paraphrase_output_tokens, paraphrase_cost_usd, created_at
+        FROM robustness_groups
+        WHERE id = ?
+        "#
+    )
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    let group = RobustnessGroup {
+        id: row.get(0),
+        batch_id: row.get(1),
+        model: row.get(2),
+        base_prompt: row.get(3),
+        expected: row.get(4),
+        paraphrase_model: row.get(5),
+        paraphrase_input_tokens: row.get(6),
+        paraphrase_output_tokens: row.get(7),
+        paraphrase_cost_usd: row.get(8),
+        created_at: row.get(9),
+    };
+
+    let rows = query(
+        r#"
+        SELECT rv.variant_index, rv.prompt, rv.source, rv.evaluation_id,
+               COALESCE(e.status, 'error'), COALESCE(e.human_verdict, e.judge_verdict)
+        FROM robustness_variants rv
+        LEFT JOIN evaluations e ON e.id = rv.evaluation_id
+        WHERE rv.group_id = ?
+        ORDER BY rv.variant_index
+        "#
+    )
+    .bind(id)
+    .fetch_all(pool)
+    .await?;
+
+    let variants = rows.into_iter().map(|row| {
+        let variant_index: i64 = row.get(0);
+        let source: String = row.get(2);
+        VariantOutcome {
+            variant_index: variant_index as usize,
+            prompt: row.get(1),
+            source: VariantSource::parse(&source).unwrap_or(VariantSource::User),
+            eval_id: row.get(3),
+            status: row.get(4),
+            verdict: row.get(5),
+            flips_verdict: None,
+        }
+    }).collect();
+
+    Ok((group, variants))
+}
+
+// =======================================================
+// Batches
+// =======================================================
+
+#[derive(serde::Serialize, Clone)]
+pub struct BatchSummary {
+    pub batch_id: String,
+    pub created_at: String,
+    pub total: i64,
+    pub passed: i64,
+    /// Failed verdicts and errored evaluations.
+    pub failed: i64,
+    /// `running` until every evaluation in the batch has finished, then `completed`.
+    pub status: String,
+}
+
+/// Records a batch of `total` evaluations as running.
+pub async fn create_batch(pool: &DbPool, batch_id: &str, total: usize) -> Result<(), sqlx::Error> {
+    query("INSERT INTO batches (batch_id, created_at, total, status) VALUES (?, ?, ?, 'running')")
+        .bind(batch_id)
+        .bind(Utc::now().to_rfc3339())
+        .bind(total as i64)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Stores a batch's final outcome counts and status.
+pub async fn finish_batch(
+    pool: &DbPool,
+    batch_id: &str,
+    passed: usize,
+    failed: usize,
+    status: &str,
+) -> Result<(), sqlx::Error> {
+    let result = query("UPDATE batches SET passed = ?, failed = ?, status = ? WHERE batch_id = ?")
+        .bind(passed as i64)
+        .bind(failed as i64)
+        .bind(status)
+        .bind(batch_id)
+        .execute(pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+    Ok(())
+}
+
+pub async fn get_batch(pool: &DbPool, batch_id: &str) -> Result<BatchSummary, sqlx::Error> {
+    let row = query("SELECT batch_id, created_at, total, passed, failed, status FROM batches WHERE batch_id = ?")
+        .bind(batch_id)
+        .fetch_one(pool)
+        .await?;
+
+    Ok(BatchSummary {
+        batch_id: row.get(0),
+        created_at: row.get(1),
+        total: row.get(2),
+        passed: row.get(3),
+        failed: row.get(4),
+        status: row.get(5),
+    })
+}
+
+// =======================================================
+// Jobs
+// =======================================================
+
+/// A batch or suite run queued for the job worker.
+#[derive(serde::Serialize, Clone)]
+pub struct Job {
+    pub job_id: String,
+    /// `batch`, or `suite` for a run of `suite_id`.
+    pub kind: String,
+    pub suite_id: Option<String>,
+    #[serde(skip)]
+    pub configs: Vec<EvalConfig>,
+    pub total: i64,
+    /// Batch the job's evaluations are saved under.
+    pub batch_id: String,
+    /// `queued`, `running`, `completed`, `failed` or `cancelled`.
+    pub status: String,
+    #[serde(skip)]
+    pub worker_id: Option<String>,
+    pub created_at: String,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+    pub error: Option<String>,
+    /// The schedule that queued the job, for a scheduled suite run.
+    pub schedule_id: Option<String>,
+}
+
+/// An eval of a job that has finished and been saved.
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+pub struct JobEval {
+    /// Index of the eval's config in the job.
+    pub position: i64,
+    pub eval_id: String,
+    pub status: String,
+}
+
+const JOB_COLUMNS: &str =
+    "job_id, kind, suite_id, configs, total, batch_id, status, worker_id, created_at, started_at, finished_at, error, schedule_id";
+
+fn job_from_row(row: &DbRow) -> Result<Job, sqlx::Error> {
+    let configs: String = row.get(3);
+    Ok(Job {
+        job_id: row.get(0),
+        kind: row.get(1),
+        suite_id: row.get(2),
+        configs: serde_json::from_str(&configs).map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+        total: row.get(4),
+        batch_id: row.get(5),
+        status: row.get(6),
+        worker_id: row.get(7),
+        created_at: row.get(8),
+        started_at: row.get(9),
+        finished_at: row.get(10),
+        error: row.get(11),
+        schedule_id: row.get(12),
+    })
+}
+
+/// Queues a run of `configs`; `suite_id` is set for a suite's run.
+pub async fn enqueue_job(pool: &DbPool, suite_id: Option<&str>, configs: &[EvalConfig]) -> Result<Job, sqlx::Error> {
+    insert_job(pool, suite_id, None, configs).await
+}
+
+/// Queues a run of `suite` started by the schedule `schedule_id`.
+pub async fn enqueue_scheduled_job(pool: &DbPool, suite: &Suite, schedule_id: &str) -> Result<Job, sqlx::Error> {
+    insert_job(pool, Some(&suite.id), Some(schedule_id), &suite.configs).await
+}
+
+async fn insert_job(
+    pool: &DbPool,
+    suite_id: Option<&str>,
+    schedule_id: Option<&str>,
+    configs: &[EvalConfig],
+) -> Result<Job, sqlx::Error> {
+    let row = query(&format!(
+        "INSERT INTO jobs (job_id, kind, suite_id, configs, total, batch_id, status, created_at, schedule_id)
+         VALUES (?, ?, ?, ?, ?, ?, 'queued', ?, ?)
+         RETURNING {}",
+        JOB_COLUMNS
+    ))
+    .bind(uuid::Uuid::new_v4().to_string())
+    .bind(if suite_id.is_some() { "suite" } else { "batch" })
+    .bind(suite_id)
+    .bind(encode_configs(configs)?)
+    .bind(configs.len() as i64)
+    .bind(uuid::Uuid::new_v4().to_string())
+    .bind(Utc::now().to_rfc3339())
+    .bind(schedule_id)
+    .fetch_one(pool)
+    .await?;
+
+    job_from_row(&row)
+}
+
+pub async fn get_job(pool: &DbPool, job_id: &str) -> Result<Job, sqlx::Error> {
+    let row = query(&format!("SELECT {} FROM jobs WHERE job_id = ?", JOB_COLUMNS))
+        .bind(job_id)
+        .fetch_one(pool)
+        .await?;
+
+    job_from_row(&row)
+}
+
+/// Claims the oldest queued job for `worker_id`, or a running job whose worker has sent no
+/// heartbeat since `stale_before`, as after a crash. The claim is one guarded `UPDATE`, so
+/// of workers racing for a job, on one database or several servers, only one gets it.
+pub async fn claim_job(pool: &DbPool, worker_id: &str, stale_before: &str) -> Result<Option<Job>, sqlx::Error> {
+    let now = Utc::now().to_rfc3339();
+    let row = query(&format!(
+        "UPDATE jobs
+         SET status = 'running', worker_id = ?1, heartbeat_at = ?2, started_at = COALESCE(started_at, ?2)
+         WHERE job_id = (
+             SELECT job_id FROM jobs
+             WHERE status = 'queued' OR (status = 'running' AND heartbeat_at < ?3)
+             ORDER BY created_at, job_id
+             LIMIT 1
+         )
+         AND (status = 'queued' OR (status = 'running' AND heartbeat_at < ?3))
+         RETURNING {}",
+        JOB_COLUMNS
+    ))
+    .bind(worker_id)
+    .bind(&now)
+    .bind(stale_before)
+    .fetch_optional(pool)
+    .await?;
+
+    row.as_ref().map(job_from_row).transpose()
+}
+
+/// Refreshes the heartbeat of a job `worker_id` holds. `false` when it no longer holds it:
+/// the job was cancelled or, after missing heartbeats, claimed by another worker.
+pub async fn heartbeat_job(pool: &DbPool, job_id: &str, worker_id: &str) -> Result<bool, sqlx::Error> {
+    let result = query("UPDATE jobs SET heartbeat_at = ? WHERE job_id = ? AND worker_id = ? AND status = 'running'")
+        .bind(Utc::now().to_rfc3339())
+        .bind(job_id)
+        .bind(worker_id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Records that an eval of a job `worker_id` holds has finished, refreshing the heartbeat.
+/// `false`, recording nothing, when the worker no longer holds the job.
+pub async fn checkpoint_job_eval(pool: &DbPool, job_id: &str, worker_id: &str, eval: &JobEval) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let held = query("UPDATE jobs SET heartbeat_at = ? WHERE job_id = ? AND worker_id = ? AND status = 'running'")
+        .bind(Utc::now().to_rfc3339())
+        .bind(job_id)
+        .bind(worker_id)
+        .execute(&mut tx)
+        .await?
+        .rows_affected()
+        > 0;
+    if !held {
+        return Ok(false);
+    }
+    query("INSERT INTO job_evals (job_id, position, eval_id, status) VALUES (?, ?, ?, ?)")
+        .bind(job_id)
+        .bind(eval.position)
+        .bind(&eval.eval_id)
+        .bind(&eval.status)
+        .execute(&mut tx)
+        .await?;
+    tx.commit().await?;
+    Ok(true)
+}
+
+/// The evals of a job that have finished, in config order.
+pub async fn get_job_evals(pool: &DbPool, job_id: &str) -> Result<Vec<JobEval>, sqlx::Error> {
+    let rows = query("SELECT position, eval_id, status FROM job_evals WHERE job_id = ? ORDER BY position")
+        .bind(job_id)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| JobEval { position: row.get(0), eval_id: row.get(1), status: row.get(2) })
+        .collect())
+}
+
+/// Ends a job `worker_id` holds as `completed` or `failed`. `false` when it no longer holds it.
+pub async fn finish_job(
+    pool: &DbPool,
+    job_id: &str,
+    worker_id: &str,
+    status: &str,
+    error: Option<&str>,
+) -> Result<bool, sqlx::Error> {
+    let result = query(
+        "UPDATE jobs SET status = ?, error = ?, finished_at = ?, worker_id = NULL
+         WHERE job_id = ? AND worker_id = ? AND status = 'running'",
+    )
+    .bind(status)
+    .bind(error)
+    .bind(Utc::now().to_rfc3339())
+    .bind(job_id)
+    .bind(worker_id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Puts a job `worker_id` holds back in the queue, for a worker that is shutting down.
+pub async fn release_job(pool: &DbPool, job_id: &str, worker_id: &str) -> Result<bool, sqlx::Error> {
+    let result = query(
+        "UPDATE jobs SET status = 'queued', worker_id = NULL, heartbeat_at = NULL
+         WHERE job_id = ? AND worker_id = ? AND status = 'running'",
+    )
+    .bind(job_id)
+    .bind(worker_id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Cancels a queued or running job; a running job's worker stops at its next checkpoint.
+/// `false` when the job had already finished.
+pub async fn cancel_job(pool: &DbPool, job_id: &str) -> Result<bool, sqlx::Error> {
+    let result = query(
+        "UPDATE jobs SET status = 'cancelled', finished_at = ?, worker_id = NULL
+         WHERE job_id = ? AND status IN ('queued', 'running')",
+    )
+    .bind(Utc::now().to_rfc3339())
+    .bind(job_id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+// =======================================================
+// Schedules
+// =======================================================
+
+/// A suite the scheduler runs whenever its cron expression comes due.
+#[derive(serde::Serialize, Clone)]
+pub struct Schedule {
+    pub id: String,
+    pub suite_id: String,
+    /// Cron expression, with or without a leading seconds field; see `scheduler::parse_cron`.
+    pub cron: String,
+    pub enabled: bool,
+    /// Whether finished runs are sent to Slack, when they are bad enough for `notifier::should_notify`.
+    pub notify_slack: bool,
+    /// Receives a signed `batch.completed` webhook whenever a run finishes.
+    pub callback_url: Option<String>,
+    /// When the scheduler last came to the schedule, including runs it skipped.
+    pub last_run_at: Option<String>,
+    /// The job of the last run it queued.
+    pub last_job_id: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// The settings of a schedule that can be written.
+#[derive(Debug, Clone)]
+pub struct ScheduleSettings {
+    pub suite_id: String,
+    pub cron: String,
+    pub enabled: bool,
+    pub notify_slack: bool,
+    pub callback_url: Option<String>,
+}
+
+const SCHEDULE_COLUMNS: &str =
+    "id, suite_id, cron, enabled, notify_slack, callback_url, last_run_at, last_job_id, created_at, updated_at";
+
+fn schedule_from_row(row: &DbRow) -> Schedule {
+    Schedule {
+        id: row.get(0),
+        suite_id: row.get(1),
+        cron: row.get(2),
+        enabled: row.get(3),
+        notify_slack: row.get(4),
+        callback_url: row.get(5),
+        last_run_at: row.get(6),
+        last_job_id: row.get(7),
+        created_at: row.get(8),
+        updated_at: row.get(9),
+    }
+}
+
+pub async fn get_all_schedules(pool: &DbPool) -> Result<Vec<Schedule>, sqlx::Error> {
+    let rows = query(&format!("SELECT {} FROM schedules ORDER BY created_at, id", SCHEDULE_COLUMNS))
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows.iter().map(schedule_from_row).collect())
+}
+
+/// The schedules the scheduler looks at.
+pub async fn get_enabled_schedules(pool: &DbPool) -> Result<Vec<Schedule>, sqlx::Error> {
+    let rows = query(&format!("SELECT {} FROM schedules WHERE enabled = TRUE ORDER BY created_at, id", SCHEDULE_COLUMNS))
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows.iter().map(schedule_from_row).collect())
+}
+
+pub async fn get_schedule(pool: &DbPool, id: &str) -> Result<Schedule, sqlx::Error> {
+    let row = query(&format!("SELECT {} FROM schedules WHERE id = ?", SCHEDULE_COLUMNS))
+        .bind(id)
+        .fetch_one(pool)
+        .await?;
+
+    Ok(schedule_from_row(&row))
+}
+
+pub async fn create_schedule(pool: &DbPool, settings: &ScheduleSettings) -> Result<Schedule, sqlx::Error> {
+    let now = Utc::now().to_rfc3339();
+    let row = query(&format!(
+        "INSERT INTO schedules (id, suite_id, cron, enabled, notify_slack, callback_url, created_at, updated_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+         RETURNING {}",
+        SCHEDULE_COLUMNS
+    ))
+    .bind(uuid::Uuid::new_v4().to_string())
+    .bind(&settings.suite_id)
+    .bind(&settings.cron)
+    .bind(settings.enabled)
+    .bind(settings.notify_slack)
+    .bind(&settings.callback_url)
+    .bind(&now)
+    .bind(&now)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(schedule_from_row(&row))
+}
+
+/// Replaces a schedule's settings, keeping when it last ran.
+pub async fn update_schedule(pool: &DbPool, id: &str, settings: &ScheduleSettings) -> Result<Schedule, sqlx::Error> {
+    let row = query(&format!(
+        "UPDATE schedules
+         SET suite_id = ?, cron = ?, enabled = ?, notify_slack = ?, callback_url = ?, updated_at = ?
+         WHERE id = ?
+         RETURNING {}",
+        SCHEDULE_COLUMNS
+    ))
+    .bind(&settings.suite_id)
+    .bind(&settings.cron)
+    .bind(settings.enabled)
+    .bind(settings.notify_slack)
+    .bind(&settings.callback_url)
+    .bind(Utc::now().to_rfc3339())
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(schedule_from_row(&row))
+}
+
+/// Deletes a schedule. Jobs it queued are kept.
+pub async fn delete_schedule(pool: &DbPool, id: &str) -> Result<(), sqlx::Error> {
+    let result = query("DELETE FROM schedules WHERE id = ?").bind(id).execute(pool).await?;
+
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+    Ok(())
+}
+
+/// Moves a schedule's `last_run_at` on to `run_at`, if it is still what `schedule` read.
+/// `false` when another scheduler, on this database from another server, got there first.
+pub async fn claim_schedule_run(pool: &DbPool, schedule: &Schedule, run_at: &str) -> Result<bool, sqlx::Error> {
+    let result = query("UPDATE schedules SET last_run_at = ? WHERE id = ? AND COALESCE(last_run_at, '') = ?")
+        .bind(run_at)
+        .bind(&schedule.id)
+        .bind(schedule.last_run_at.as_deref().unwrap_or(""))
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Records the job of a schedule's latest run.
+pub async fn set_schedule_job(pool: &DbPool, id: &str, job_id: &str) -> Result<(), sqlx::Error> {
+    query("UPDATE schedules SET last_job_id = ? WHERE id = ?")
+        .bind(job_id)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+// =======================================================
+// Datasets
+// =======================================================
+
+#[derive(serde::Serialize, Clone)]
+pub struct Dataset {
+    pub id: String,
+    pub name: String,
+    pub format: String,
+    pub row_count: i64,
+    /// Column names in order of first appearance across rows.
+    pub columns: Vec<String>,
+    pub created_at: String,
+}
+
+const DATASET_COLUMNS: &str = "id, name, format, row_count, columns, created_at";
+
+fn dataset_from_row(row: &DbRow) -> Result<Dataset, sqlx::Error> {
+    let columns: String = row.get(4);
+    Ok(Dataset {
+        id: row.get(0),
+        name: row.get(1),
+        format: row.get(2),
+        row_count: row.get(3),
+        columns: serde_json::from_str(&columns).map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+        created_at: row.get(5),
+    })
+}
+
+/// Stores a dataset and its rows in one transaction.
+pub async fn create_dataset(
+    pool: &DbPool,
+    name: String,
+    format: DatasetFormat,
+    rows: &[DatasetRow],
+) -> Result<Dataset, sqlx::Error> {
+    let mut columns: Vec<String> = Vec::new();
+    for key in rows.iter().flat_map(|row| row.keys()) {
+        if !columns.contains(key) {
+            columns.push(key.clone());
+        }
+    }
+
+    let dataset = Dataset {
+        id: uuid::Uuid::new_v4().to_string(),
+        name,
+        format: format.as_str().to_string(),
+        row_count: rows.len() as i64,
+        columns,
+        created_at: Utc::now().to_rfc3339(),
+    };
+
+    let mut tx = pool.begin().await?;
+    query("INSERT INTO datasets (id, name, format, row_count, columns, created_at) VALUES (?, ?, ?, ?, ?, ?)")
+        .bind(&dataset.id)
+        .bind(&dataset.name)
+        .bind(&dataset.format)
+        .bind(dataset.row_count)
+        .bind(serde_json::to_string(&dataset.columns).map_err(|e| sqlx::Error::Encode(Box::new(e)))?)
+        .bind(&dataset.created_at)
+        .execute(&mut tx)
+        .await?;
+
+    for (index, row) in rows.iter().enumerate() {
+        query("INSERT INTO dataset_rows (dataset_id, row_index, data) VALUES (?, ?, ?)")
+            .bind(&dataset.id)
+            .bind(index as i64)
+            .bind(serde_json::to_string(row).map_err(|e| sqlx::Error::Encode(Box::new(e)))?)
+            .execute(&mut tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+    Ok(dataset)
+}
+
+pub async fn get_all_datasets(pool: &DbPool) -> Result<Vec<Dataset>, sqlx::Error> {
+    let rows = query(&format!("SELECT {} FROM datasets ORDER BY created_at DESC", DATASET_COLUMNS))
+        .fetch_all(pool)
+        .await?;
+
+    rows.iter().map(dataset_from_row).collect()
+}
+
+pub async fn get_dataset(pool: &DbPool, id: &str) -> Result<Dataset, sqlx::Error> {
+    let row = query(&format!("SELECT {} FROM datasets WHERE id = ?", DATASET_COLUMNS))
+        .bind(id)
+        .fetch_one(pool)
+        .await?;
+
+    dataset_from_row(&row)
+}
+
+/// Rows of a dataset with their indexes, in upload order. `limit` caps how many are returned.
+pub async fn get_dataset_rows(
+    pool: &DbPool,
+    dataset_id: &str,
+    limit: Option<i64>,
+) -> Result<Vec<StoredRow>, sqlx::Error> {
+    let rows = query("SELECT row_index, data, expected_source FROM dataset_rows WHERE dataset_id = ? ORDER BY row_index LIMIT ?")
+        .bind(dataset_id)
+        .bind(limit.unwrap_or(i64::MAX))
+        .fetch_all(pool)
+        .await?;
+
+    rows.iter().map(dataset_row_from_row).collect()
+}
+
+fn dataset_row_from_row(row: &DbRow) -> Result<StoredRow, sqlx::Error> {
+    let data: String = row.get(1);
+    let data: DatasetRow = serde_json::from_str(&data).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+    Ok(StoredRow {
+        row_index: row.get(0),
+        data,
+        expected_source: row
+            .get::<Option<String>, _>(2)
+            .and_then(|source| serde_json::from_str(&source).ok()),
+    })
+}
+
+/// Outputs a batch generated for rows of a dataset, with their human reviews, oldest first.
+pub async fn get_reference_outputs(pool: &DbPool, dataset_id: &str, batch_id: &str) -> Result<Vec<ReferenceOutput>, sqlx::Error> {
+    let rows = query(
+        r#"
+        SELECT id, batch_id, model, dataset_row_index, model_output, human_verdict, reviewed_at
+        FROM evaluations
+        WHERE dataset_id = ? AND batch_id = ? AND dataset_row_index IS NOT NULL AND model_output IS NOT NULL
+        ORDER BY created_at ASC, id ASC
+        "#
+    )
+    .bind(dataset_id)
+    .bind(batch_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| ReferenceOutput {
+            evaluation_id: row.get(0),
+            batch_id: row.get(1),
+            model: row.get::<Option<String>, _>(2).unwrap_or_default(),
+            row_index: row.get(3),
+            output: row.get(4),
+            human_verdict: row.get(5),
+            reviewed_at: row.get(6),
+        })
+        .collect())
+}
+
+/// Writes adopted expected values and their sources into a dataset's rows, adding the
+/// `expected` column to the dataset if it had none.
+pub async fn adopt_expected(pool: &DbPool, dataset_id: &str, adoptions: &[Adoption]) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    for adoption in adoptions {
+        let data: String = query_scalar("SELECT data FROM dataset_rows WHERE dataset_id = ? AND row_index = ?")
+            .bind(dataset_id)
+            .bind(adoption.row_index)
+            .fetch_one(&mut tx)
+            .await?;
+        let mut data: DatasetRow = serde_json::from_str(&data).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+        data.insert(EXPECTED_COLUMN.to_string(), serde_json::Value::String(adoption.expected.clone()));
+
+        query("UPDATE dataset_rows SET data = ?, expected_source = ? WHERE dataset_id = ? AND row_index = ?")
+            .bind(serde_json::to_string(&data).map_err(|e| sqlx::Error::Encode(Box::new(e)))?)
+            .bind(serde_json::to_string(&adoption.expected_source).map_err(|e| sqlx::Error::Encode(Box::new(e)))?)
+            .bind(dataset_id)
+            .bind(adoption.row_index)
+            .execute(&mut tx)
+            .await?;
+    }
+
+    let columns: String = query_scalar("SELECT columns FROM datasets WHERE id = ?")
+        .bind(dataset_id)
+        .fetch_one(&mut tx)
+        .await?;
+    let mut columns: Vec<String> = serde_json::from_str(&columns).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+    if !adoptions.is_empty() && !columns.iter().any(|column| column == EXPECTED_COLUMN) {
+        columns.push(EXPECTED_COLUMN.to_string());
+        query("UPDATE datasets SET columns = ? WHERE id = ?")
+            .bind(serde_json::to_string(&columns).map_err(|e| sqlx::Error::Encode(Box::new(e)))?)
+            .bind(dataset_id)
+            .execute(&mut tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+// =======================================================
+// Provider errors
+// =======================================================
+
+/// Persists provider errors reported by the retry middleware.
+pub struct ProviderErrorLog {
+    pool: DbPool,
+}
+
+impl ProviderErrorLog {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl ProviderErrorSink for ProviderErrorLog {
+    async fn record(&self, event: ProviderErrorEvent) {
+        if let Err(e) = save_provider_error(&self.pool, &event).await {
+            tracing::error!("Failed to save provider error: {}", e);
+        }
+    }
+}
+
+pub async fn save_provider_error(pool: &DbPool, event: &ProviderErrorEvent) -> Result<(), sqlx::Error> {
+    query(
+        r#"
+        INSERT INTO provider_errors (provider, model, status_code, error_class, body, attempt, recovered, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&event.provider)
+    .bind(&event.model)
+    .bind(event.status_code.map(i64::from))
+    .bind(event.error_class)
+    .bind(redact(&event.body))
+    .bind(event.attempt as i64)
+    .bind(event.recovered)
+    .bind(Utc::now().to_rfc3339())
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[derive(serde::Serialize)]
+pub struct ProviderError {
+    pub id: i64,
+    pub provider: String,
+    pub model: String,
+    pub status_code: Option<i64>,
+    pub error_class: String,
+    pub body: String,
+    pub attempt: i64,
+    /// A later retry of the same call succeeded.
+    pub recovered: bool,
+    pub created_at: String,
+}
+
+/// Filters for `get_provider_errors`; unset fields match everything.
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct ProviderErrorFilter {
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub status_code: Option<i64>,
+    pub error_class: Option<String>,
+    pub recovered: Option<bool>,
+    /// RFC 3339 timestamp or `YYYY-MM-DD`; only errors at or after it are returned.
+    pub since: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// Provider errors matching `filter`, newest first, at most `filter.limit` (default 100).
+pub async fn get_provider_errors(pool: &DbPool, filter: &ProviderErrorFilter) -> Result<Vec<ProviderError>, sqlx::Error> {
+    let rows = query(
+        r#"
+        SELECT id, provider, model, status_code, error_class, body, attempt, recovered, created_at
+        FROM provider_errors
+        WHERE (?1 IS NULL OR provider = ?1)
+          AND (?2 IS NULL OR model = ?2)
+          AND (?3 IS NULL OR status_code = ?3)
+          AND (?4 IS NULL OR error_class = ?4)
+          AND (?5 IS NULL OR recovered = ?5)
+          AND (?6 IS NULL OR created_at >= ?6)
+        ORDER BY created_at DESC, id DESC
+        LIMIT ?7
+        "#
+    )
+    .bind(&filter.provider)
+    .bind(&filter.model)
+    .bind(filter.status_code)
+    .bind(&filter.error_class)
+    .bind(filter.recovered)
+    .bind(&filter.since)
+    .bind(filter.limit.unwrap_or(100))
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|row| ProviderError {
+        id: row.get(0),
+        provider: row.get(1),
+        model: row.get(2),
+        status_code: row.get(3),
+        error_class: row.get(4),
+        body: row.get(5),
+        attempt: row.get(6),
+        recovered: row.get(7),
+        created_at: row.get(8),
+    }).collect())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderErrorGroup {
+    Provider,
+    /// HTTP status code, or the error class for errors without one (e.g. `connection`).
+    Status,
+}
+
+#[derive(serde::Serialize)]
+pub struct ProviderErrorBucket {
+    pub key: String,
+    /// UTC day (`YYYY-MM-DD`) when bucketed by day.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub day: Option<String>,
+    pub total: i64,
+    /// Errors a later retry recovered from.
+    pub recovered: i64,
+    /// Errors that ended the call.
+    pub terminal: i64,
+}
+
+/// Counts provider errors per provider or status, optionally split by day.
+pub async fn get_provider_error_stats(
+    pool: &DbPool,
+    group_by: ProviderErrorGroup,
+    by_day: bool,
+) -> Result<Vec<ProviderErrorBucket>, sqlx::Error> {
+    let key_expr = match group_by {
+        ProviderErrorGroup::Provider => "provider",
+        ProviderErrorGroup::Status => "COALESCE(CAST(status_code AS TEXT), error_class)",
+    };
+    let day_expr = if by_day { "substr(created_at, 1, 10)" } else { "NULL" };
+
+    let rows = query(&format!(
+        r#"
+        SELECT
+            {} as key,
+            {} as day,
+            COUNT(*) as total,
+            SUM(CASE WHEN recovered THEN 1 ELSE 0 END) as recovered
+        FROM provider_errors
+        GROUP BY key, day
+        ORDER BY day ASC, total DESC, key ASC
+        "#,
+        key_expr, day_expr
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|row| {
+        let total: i64 = row.get(2);
+        let recovered: i64 = row.get(3);
+        ProviderErrorBucket {
+            key: row.get(0),
+            day: row.get(1),
+            total,
+            recovered,
+            terminal: total - recovered,
+        }
+    }).collect())
+}
+
+// =======================================================
+// API keys
+// =======================================================
+
+#[derive(serde::Serialize, Clone)]
+pub struct ApiKey {
+    pub id: String,
+    pub name: String,
+    pub role: String,
+    pub key_prefix: String,
+    pub created_at: String,
+    /// `None` while the key is usable.
+    pub revoked_at: Option<String>,
+}
+
+const API_KEY_COLUMNS: &str = "id, name, role, key_prefix, created_at, revoked_at";
+
+fn api_key_from_row(row: &DbRow) -> ApiKey {
+    ApiKey {
+        id: row.get(0),
+        name: row.get(1),
+        role: row.get(2),
+        key_prefix: row.get(3),
+        created_at: row.get(4),
+        revoked_at: row.get(5),
+    }
+}
+
+/// Stores a new key by its hash; the key itself is never saved.
+pub async fn create_api_key(
+    pool: &DbPool,
+    name: String,
+    role: &str,
+    key_hash: &str,
+    key_prefix: &str,
+) -> Result<ApiKey, sqlx::Error> {
+    let row = query(&format!(
+        "INSERT INTO api_keys (id, name, role, key_hash, key_prefix, created_at) VALUES (?, ?, ?, ?, ?, ?) RETURNING {}",
+        API_KEY_COLUMNS
+    ))
+    .bind(uuid::Uuid::new_v4().to_string())
+    .bind(name)
+    .bind(role)
+    .bind(key_hash)
+    .bind(key_prefix)
+    .bind(Utc::now().to_rfc3339())
+    .fetch_one(pool)
+    .await?;
+
+    Ok(api_key_from_row(&row))
+}
+
+/// All keys, revoked ones included, newest first.
+pub async fn get_api_keys(pool: &DbPool) -> Result<Vec<ApiKey>, sqlx::Error> {
+    let rows = query(&format!(
+        "SELECT {} FROM api_keys ORDER BY created_at DESC",
+        API_KEY_COLUMNS
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(api_key_from_row).collect())
+}
+
+/// The unrevoked key with this hash, if any.
+pub async fn find_api_key(pool: &DbPool, key_hash: &str) -> Result<Option<ApiKey>, sqlx::Error> {
+    let row = query(&format!(
+        "SELECT {} FROM api_keys WHERE key_hash = ? AND revoked_at IS NULL",
+        API_KEY_COLUMNS
+    ))
+    .bind(key_hash)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.as_ref().map(api_key_from_row))
+}
+
+/// Revokes a key. Fails with `RowNotFound` if it does not exist or is already revoked.
+pub async fn revoke_api_key(pool: &DbPool, id: &str) -> Result<ApiKey, sqlx::Error> {
+    let row = query(&format!(
+        "UPDATE api_keys SET revoked_at = ? WHERE id = ? AND revoked_at IS NULL RETURNING {}",
+        API_KEY_COLUMNS
+    ))
+    .bind(Utc::now().to_rfc3339())
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(api_key_from_row(&row))
+}
+
+// =======================================================
+// Shares
+// =======================================================
+
+#[derive(serde::Serialize, Clone)]
+pub struct Share {
+    pub id: String,
+    pub evaluation_id: String,
+    pub created_by: String,
+    pub created_at: String,
+    pub expires_at: String,
+    /// `None` until the share is revoked.
+    pub revoked_at: Option<String>,
+}
+
+const SHARE_COLUMNS: &str = "id, evaluation_id, created_by, created_at, expires_at, revoked_at";
+
+fn share_from_row(row: &DbRow) -> Share {
+    Share {
+        id: row.get(0),
+        evaluation_id: row.get(1),
+        created_by: row.get(2),
+        created_at: row.get(3),
+        expires_at: row.get(4),
+        revoked_at: row.get(5),
+    }
+}
+
+/// Records a share of an evaluation. Fails with `RowNotFound` if the evaluation does not exist.
+pub async fn create_share(
+    pool: &DbPool,
+    evaluation_id: &str,
+    created_by: &str,
+    expires_at: chrono::DateTime<Utc>,
+) -> Result<Share, sqlx::Error> {
+    let row = query(&format!(
+        "INSERT INTO shares (id, evaluation_id, created_by, created_at, expires_at)
+         SELECT ?, id, ?, ?, ? FROM evaluations WHERE id = ? RETURNING {}",
+        SHARE_COLUMNS
+    ))
+    .bind(uuid::Uuid::new_v4().simple().to_string())
+    .bind(created_by)
+    .bind(Utc::now().to_rfc3339())
+    .bind(expires_at.to_rfc3339())
+    .bind(evaluation_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(share_from_row(&row))
+}
+
+/// A share by id, revoked or not.
+pub async fn get_share(pool: &DbPool, id: &str) -> Result<Option<Share>, sqlx::Error> {
+    let row = query(&format!("SELECT {} FROM shares WHERE id = ?", SHARE_COLUMNS))
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(row.as_ref().map(share_from_row))
+}
+
+/// Every share of an evaluation, newest first.
+pub async fn get_shares_for_evaluation(pool: &DbPool, evaluation_id: &str) -> Result<Vec<Share>, sqlx::Error> {
+    let rows = query(&format!(
+        "SELECT {} FROM shares WHERE evaluation_id = ? ORDER BY created_at DESC",
+        SHARE_COLUMNS
+    ))
+    .bind(evaluation_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(share_from_row).collect())
+}
+
+/// Revokes a share. Fails with `RowNotFound` if it does not exist or is already revoked.
+pub async fn revoke_share(pool: &DbPool, id: &str) -> Result<Share, sqlx::Error> {
+    let row = query(&format!(
+        "UPDATE shares SET revoked_at = ? WHERE id = ? AND revoked_at IS NULL RETURNING {}",
+        SHARE_COLUMNS
+    ))
+    .bind(Utc::now().to_rfc3339())
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(share_from_row(&row))
+}
+
+// =======================================================
+// Presets
+// =======================================================
+
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct Preset {
+    pub id: String,
+    pub name: String,
+    /// The API key the preset belongs to; `None` for presets shared by every caller.
+    pub owner_key_id: Option<String>,
+    pub description: Option<String>,
+    /// JSON object of eval config fields; see `preset::merge`.
+    pub defaults: serde_json::Value,
+    pub created_by: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+const PRESET_COLUMNS: &str = "id, name, owner_key_id, description, defaults, created_by, created_at, updated_at";
+
+fn preset_from_row(row: &DbRow) -> Result<Preset, sqlx::Error> {
+    let defaults: String = row.get(4);
+    Ok(Preset {
+        id: row.get(0),
+        name: row.get(1),
+        owner_key_id: row.get(2),
+        description: row.get(3),
+        defaults: serde_json::from_str(&defaults).map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+        created_by: row.get(5),
+        created_at: row.get(6),
+        updated_at: row.get(7),
+    })
+}
+
+/// Presets visible to a key: its own and the shared ones, by name, its own first.
+pub async fn get_presets(pool: &DbPool, owner_key_id: Option<&str>) -> Result<Vec<Preset>, sqlx::Error> {
+    let rows = query(&format!(
+        "SELECT {} FROM presets WHERE owner_key_id IS NULL OR owner_key_id = ? ORDER BY name, owner_key_id IS NULL",
+        PRESET_COLUMNS
+    ))
+    .bind(owner_key_id)
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter().map(preset_from_row).collect()
+}
+
+/// The preset a key means by `name`: its own if it has one, the shared one otherwise.
+pub async fn find_preset(pool: &DbPool, name: &str, owner_key_id: Option<&str>) -> Result<Option<Preset>, sqlx::Error> {
+    let row = query(&format!(
+        r#"
+        SELECT {} FROM presets
+        WHERE name = ? AND (owner_key_id IS NULL OR owner_key_id = ?)
+        ORDER BY owner_key_id IS NULL
+        LIMIT 1
+        "#,
+        PRESET_COLUMNS
+    ))
+    .bind(name)
+    .bind(owner_key_id)
+    .fetch_optional(pool)
+    .await?;
+
+    row.as_ref().map(preset_from_row).transpose()
+}
+
+/// Creates a preset owned by `owner_key_id`, or a shared one for `None`. Names are unique per owner.
+pub async fn create_preset(
+    pool: &DbPool,
+    name: &str,
+    owner_key_id: Option<&str>,
+    description: Option<&str>,
+    defaults: &serde_json::Value,
+    created_by: &str,
+) -> Result<Preset, sqlx::Error> {
+    let now = Utc::now().to_rfc3339();
+    let row = query(&format!(
+        r#"
+        INSERT INTO presets (id, name, owner_key_id, description, defaults, created_by, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        RETURNING {}
+        "#,
+        PRESET_COLUMNS
+    ))
+    .bind(uuid::Uuid::new_v4().to_string())
+    .bind(name)
+    .bind(owner_key_id)
+    .bind(description)
+    .bind(defaults.to_string())
+    .bind(created_by)
+    .bind(&now)
+    .bind(&now)
+    .fetch_one(pool)
+    .await?;
+
+    preset_from_row(&row)
+}
+
+/// Replaces a preset's description and defaults.
+pub async fn update_preset(
+    pool: &DbPool,
+    id: &str,
+    description: Option<&str>,
+    defaults: &serde_json::Value,
+) -> Result<Preset, sqlx::Error> {
+    let row = query(&format!(
+        "UPDATE presets SET description = ?, defaults = ?, updated_at = ? WHERE id = ? RETURNING {}",
+        PRESET_COLUMNS
+    ))
+    .bind(description)
+    .bind(defaults.to_string())
+    .bind(Utc::now().to_rfc3339())
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    preset_from_row(&row)
+}
+
+/// Deletes a preset. Evaluations that used it keep their record of it.
+pub async fn delete_preset(pool: &DbPool, id: &str) -> Result<(), sqlx::Error> {
+    let result = query("DELETE FROM presets WHERE id = ?").bind(id).execute(pool).await?;
+
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+    Ok(())
+}
+
+// =======================================================
+// Response Cache
+// =======================================================
+
+/// The response cached under `key`, unless it is older than `max_age_secs`.
+pub async fn get_cached_response(
+    pool: &DbPool,
+    key: &str,
+    max_age_secs: Option<u64>,
+) -> Result<Option<Generation>, sqlx::Error> {
+    let oldest = max_age_secs
+        .map(|secs| (Utc::now() - chrono::Duration::seconds(secs.min(i64::MAX as u64) as i64)).to_rfc3339());
+    let row = query(
+        "SELECT output, latency_ms, input_tokens, output_tokens, finish_reason, resolved_model, reasoning FROM response_cache
+         WHERE key = ?1 AND (?2 IS NULL OR created_at >= ?2)",
+    )
+    .bind(key)
+    .bind(oldest)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|row| Generation {
+        output: row.get(0),
+        latency_ms: row.get::<i64, _>(1) as u64,
+        token_usage: TokenUsage {
+            input_tokens: row.get::<Option<i64>, _>(2).map(|tokens| tokens as u32),
+            output_tokens: row.get::<Option<i64>, _>(3).map(|tokens| tokens as u32),
+        },
+        finish_reason: row.get::<Option<String>, _>(4).map(FinishReason::from),
+        resolved_model: row.get(5),
+        reasoning: row.get(6),
+    }))
+}
+
+/// Caches `generation` under `key`, replacing any older response.
+pub async fn save_cached_response(pool: &DbPool, key: &str, model: &str, generation: &Generation) -> Result<(), sqlx::Error> {
+    query(
+        "INSERT INTO response_cache (key, model, output, latency_ms, input_tokens, output_tokens, finish_reason, resolved_model, reasoning, created_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT (key) DO UPDATE SET model = excluded.model, output = excluded.output,
+             latency_ms = excluded.latency_ms, input_tokens = excluded.input_tokens,
+             output_tokens = excluded.output_tokens, finish_reason = excluded.finish_reason,
+             resolved_model = excluded.resolved_model, reasoning = excluded.reasoning, created_at = excluded.created_at",
+    )
+    .bind(key)
+    .bind(model)
+    .bind(&generation.output)
+    .bind(generation.latency_ms as i64)
+    .bind(generation.token_usage.input_tokens.map(i64::from))
+    .bind(generation.token_usage.output_tokens.map(i64::from))
+    .bind(generation.finish_reason.as_ref().map(FinishReason::as_str))
+    .bind(&generation.resolved_model)
+    .bind(&generation.reasoning)
+    .bind(Utc::now().to_rfc3339())
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Empties the response cache, returning how many responses were removed.
+pub async fn clear_response_cache(pool: &DbPool) -> Result<u64, sqlx::Error> {
+    Ok(query("DELETE FROM response_cache").execute(pool).await?.rows_affected())
+}
+
+// =======================================================
+// Raw exchanges
+// =======================================================
+
+const RAW_EXCHANGE_COLUMNS: &str = "provider, model, method, url, request_body, request_truncated, status, \
+    response_headers, response_body, response_truncated, captured_at";
+
+fn raw_exchange_from_row(row: &DbRow) -> RawExchange {
+    RawExchange {
+        provider: row.get(0),
+        model: row.get(1),
+        method: row.get(2),
+        url: row.get(3),
+        request_body: row.get(4),
+        request_truncated: row.get(5),
+        status: row.get::<i64, _>(6) as u16,
+        response_headers: serde_json::from_str(&row.get::<String, _>(7)).unwrap_or_default(),
+        response_body: row.get(8),
+        response_truncated: row.get(9),
+        captured_at: row.get(10),
+    }
+}
+
+/// Saves the exchanges captured for an evaluation, after any it already has.
+pub async fn save_raw_exchanges(pool: &DbPool, evaluation_id: &str, exchanges: &[RawExchange]) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let saved: i64 = query_scalar("SELECT COUNT(*) FROM raw_exchanges WHERE evaluation_id = ?")
+        .bind(evaluation_id)
+        .fetch_one(&mut tx)
+        .await?;
+    for (seq, exchange) in (saved..).zip(exchanges) {
+        query(&format!(
+            "INSERT INTO raw_exchanges (evaluation_id, seq, {}) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            RAW_EXCHANGE_COLUMNS
+        ))
+        .bind(evaluation_id)
+        .bind(seq)
+        .bind(&exchange.provider)
+        .bind(&exchange.model)
+        .bind(&exchange.method)
+        .bind(&exchange.url)
+        .bind(&exchange.request_body)
+        .bind(exchange.request_truncated)
+        .bind(i64::from(exchange.status))
+        .bind(serde_json::to_string(&exchange.response_headers).unwrap_or_else(|_| "{}".to_string()))
+        .bind(&exchange.response_body)
+        .bind(exchange.response_truncated)
+        .bind(&exchange.captured_at)
+        .execute(&mut tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// The exchanges captured for an evaluation, in the order they were made.
+pub async fn get_raw_exchanges(pool: &DbPool, evaluation_id: &str) -> Result<Vec<RawExchange>, sqlx::Error> {
+    let rows = query(&format!(
+        "SELECT {} FROM raw_exchanges WHERE evaluation_id = ? ORDER BY seq ASC",
+        RAW_EXCHANGE_COLUMNS
+    ))
+    .bind(evaluation_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(raw_exchange_from_row).collect())
+}
+
+/// Deletes exchanges captured before `cutoff`, including those of evals that were never saved.
+/// Returns how many were deleted.
+pub async fn purge_raw_exchanges_before(pool: &DbPool, cutoff: &str) -> Result<u64, sqlx::Error> {
+    Ok(query("DELETE FROM raw_exchanges WHERE captured_at < ?").bind(cutoff).execute(pool).await?.rows_affected())
+}
+
+/// A fresh, migrated database for a test; see `test_support::fresh_test_pool`.
+#[cfg(test)]
+pub(crate) use test_support::fresh_test_pool as test_pool;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::judge_ab::ArmJudgement;
+    use crate::providers::FinishReason;
+    use crate::runner::{self, JudgePromptArm, JudgeResult, JudgeVerdict};
+
+    fn sample_result(verdict: JudgeVerdict, confidence: Option<f32>, needs_review: bool) -> runner::EvalResult {
+        runner::EvalResult {
+            id: String::new(),
+            model: "ollama:llama3".to_string(),
+            prompt: "What is 2+2?".to_string(),
+            model_output: "4".to_string(),
+            parsed_output: None,
+            parse_method: None,
+            output_diff: None,
+            expected: Some("4".to_string()),
+            judge_result: Some(JudgeResult {
+                judge_model: "ollama:llama3".to_string(),
+                verdict,
+                reasoning: None,
+                confidence,
+                raw_response: None,
+                uncertain_reason: None,
+            }),
+            timestamp: Utc::now().to_rfc3339(),
+            latency_ms: 10,
+            judge_latency_ms: Some(5),
+            token_usage: None,
+            judge_token_usage: None,
+            total_latency_ms: 15,
+            wait_ms: None,
+            cost_usd: None,
+            judge_cost_usd: None,
+            judge_prompt_version: Some(1),
+            prompt_version: None,
+            prompt_template: None,
+            judge_prompt_arm: None,
+            judge_batch_size: None,
+            early_abort: None,
+            finish_reason: None,
+            needs_review,
+            truncation: None,
+            tags: Vec::new(),
+            criteria: None,
+            metadata: None,
+            trace: None,
+            failure_classification: None,
+            dataset_row: None,
+            chain_step: None,
+            provider_override: None,
+            full_output_chars: None,
+            cache_hit: false,
+            resolved_model: None,
+            response_format: None,
+            reasoning: None,
+            images: Vec::new(),
+            judge_prompt_rendered: None,
+            model_alias: None,
+            judge_params: None,
+            judge_error: None,
+            self_consistency: None,
+        }
+    }
+
+    async fn save(pool: &DbPool, id: &str, result: runner::EvalResult) {
+        let response = ApiResponse {
+            id: id.to_string(),
+            status: runner::eval_status(&result).to_string(),
+            result: EvalResult::Success(result),
+            batch_id: None,
+            config_hash: None,
+            preset: None,
+        };
+        save_evaluation(pool, &response).await.unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_saves_are_not_lost() {
+        const EVALS: usize = 300;
+        let dir = std::env::temp_dir().join(format!("evaluate-stress-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let pool = DbPool::Sqlite(connect(&format!("sqlite:{}", dir.join("evals.db").display()), 8).await.unwrap());
+        run_migrations(&pool).await.unwrap();
+
+        let saves = (0..EVALS).map(|i| {
+            let pool = pool.clone();
+            tokio::spawn(async move {
+                let result = sample_result(JudgeVerdict::Pass, None, false);
+                let response = ApiResponse {
+                    id: format!("eval-{}", i),
+                    status: runner::eval_status(&result).to_string(),
+                    result: EvalResult::Success(result),
+                    batch_id: Some("stress".to_string()),
+                    config_hash: None,
+                    preset: None,
+                };
+                save_evaluation_retrying(&pool, &response).await
+            })
+        });
+        for saved in futures::future::join_all(saves).await {
+            saved.unwrap().unwrap();
+        }
+
+        let journal_mode: String = query_scalar("PRAGMA journal_mode").fetch_one(&pool).await.unwrap();
+        assert_eq!(journal_mode, "wal");
+        assert_eq!(get_batch_evaluations(&pool, "stress").await.unwrap().len(), EVALS);
+
+        pool.close().await;
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_eval_config_fields_round_trip_and_filter_history_by_tag() {
+        let pool = test_pool().await;
+        let mut configured = sample_result(JudgeVerdict::Pass, None, false);
+        configured.parsed_output = Some(serde_json::json!({"answer": 4}));
+        configured.criteria = Some("Exact number".to_string());
+        configured.metadata = Some(serde_json::json!({"source": "arithmetic"}));
+        configured.prompt_template = Some("What is {{a}}+{{b}}?".to_string());
+        configured.tags = vec!["math".to_string(), "nightly".to_string()];
+        save(&pool, "configured", configured).await;
+        save(&pool, "plain", sample_result(JudgeVerdict::Pass, None, false)).await;
+
+        let entry = get_evaluation(&pool, "configured").await.unwrap();
+        assert_eq!(entry.parsed_output, Some(serde_json::json!({"answer": 4})));
+        assert_eq!(entry.criteria.as_deref(), Some("Exact number"));
+        assert_eq!(entry.metadata, Some(serde_json::json!({"source": "arithmetic"})));
+        assert_eq!(entry.prompt_template.as_deref(), Some("What is {{a}}+{{b}}?"));
+        assert_eq!(entry.tags, ["math", "nightly"]);
+        let plain = get_evaluation(&pool, "plain").await.unwrap();
+        assert!(plain.parsed_output.is_none() && plain.criteria.is_none() && plain.metadata.is_none() && plain.prompt_template.is_none());
+
+        let filter = HistoryFilter { tag: Some("nightly".to_string()), ..Default::default() };
+        let tagged: Vec<String> = get_evaluations(&pool, &filter).await.unwrap().into_iter().map(|e| e.id).collect();
+        assert_eq!(tagged, ["configured"]);
+        let filter = HistoryFilter { tag: Some("night".to_string()), ..Default::default() };
+        assert!(get_evaluations(&pool, &filter).await.unwrap().is_empty());
+        assert_eq!(get_all_evaluations(&pool).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_tags_filter_history_and_stats_by_all_or_any_and_can_be_changed() {
+        let pool = test_pool().await;
+        for (id, tags) in [("both", vec!["math", "nightly"]), ("math", vec!["math"]), ("nightly", vec!["nightly"]), ("none", vec![])] {
+            let mut result = sample_result(JudgeVerdict::Pass, None, false);
+            result.tags = tags.into_iter().map(String::from).collect();
+            save(&pool, id, result).await;
+        }
+        let ids = |filter: HistoryFilter| {
+            let pool = pool.clone();
+            async move {
+                let mut ids: Vec<String> = get_evaluations(&pool, &filter).await.unwrap().into_iter().map(|e| e.id).collect();
+                ids.sort();
+                ids
+            }
+        };
+        let filter = |tag: &str, tag_match| HistoryFilter { tag: Some(tag.to_string()), tag_match, ..Default::default() };
+
+        assert_eq!(ids(filter("math,nightly", TagMatch::All)).await, ["both"]);
+        assert_eq!(ids(filter("math, nightly", TagMatch::Any)).await, ["both", "math", "nightly"]);
+        assert_eq!(ids(filter("math,flaky", TagMatch::All)).await, Vec::<String>::new());
+        assert_eq!(ids(filter("math,flaky", TagMatch::Any)).await, ["both", "math"]);
+        assert_eq!(ids(filter(" , ", TagMatch::All)).await.len(), 4);
+        let stats = |tag: &str, tag_match| StatsFilter { tag: Some(tag.to_string()), tag_match, ..Default::default() };
+        let evaluations = |board: Vec<LeaderboardEntry>| board.iter().map(|e| e.total_evaluations).sum::<i64>();
+        let all = get_leaderboard(&pool, &stats("math,nightly", TagMatch::All), LeaderboardOrder::PassRate).await.unwrap();
+        let any = get_leaderboard(&pool, &stats("math,nightly", TagMatch::Any), LeaderboardOrder::PassRate).await.unwrap();
+        assert_eq!((evaluations(all), evaluations(any)), (1, 3));
+
+        let count = |tag: &str, evaluations| TagCount { tag: tag.to_string(), evaluations };
+        assert_eq!(get_tag_counts(&pool).await.unwrap(), [count("math", 2), count("nightly", 2)]);
+
+        let etag = evaluations_etag(&pool).await.unwrap();
+        let tags = update_evaluation_tags(&pool, "both", &["hallucination".to_string()], &["math".to_string()]).await.unwrap();
+        assert_eq!(tags, ["nightly", "hallucination"]);
+        assert_eq!(get_evaluation(&pool, "both").await.unwrap().tags, tags);
+        assert_ne!(evaluations_etag(&pool).await.unwrap(), etag);
+        assert_eq!(ids(filter("math,nightly", TagMatch::All)).await, Vec::<String>::new());
+        assert_eq!(ids(filter("hallucination", TagMatch::All)).await, ["both"]);
+        assert_eq!(
+            get_tag_counts(&pool).await.unwrap(),
+            [count("nightly", 2), count("hallucination", 1), count("math", 1)]
+        );
+
+        // Removing every tag clears the entry's list; adding one it has changes nothing
+        assert!(update_evaluation_tags(&pool, "math", &[], &["math".to_string()]).await.unwrap().is_empty());
+        assert!(get_evaluation(&pool, "math").await.unwrap().tags.is_empty());
+        let tags = update_evaluation_tags(&pool, "nightly", &["nightly".to_string()], &[]).await.unwrap();
+        assert_eq!(tags, ["nightly"]);
+        assert!(matches!(update_evaluation_tags(&pool, "missing", &[], &[]).await, Err(sqlx::Error::RowNotFound)));
+
+        assert_eq!(purge_evaluations(&pool, &["both".to_string()]).await.unwrap(), 1);
+        assert_eq!(get_tag_counts(&pool).await.unwrap(), [count("nightly", 1)]);
+    }
+
+    #[tokio::test]
+    async fn test_trace_round_trips_through_history() {
+        use crate::trace::{Span, TracePhase};
+
+        let pool = test_pool().await;
+        let mut traced = sample_result(JudgeVerdict::Pass, None, false);
+        traced.trace = Some(vec![Span::start(TracePhase::ProviderCall).finish(Some("2 attempts".to_string()))]);
+        save(&pool, "traced", traced).await;
+        save(&pool, "untraced", sample_result(JudgeVerdict::Pass, None, false)).await;
+
+        let trace = get_evaluation(&pool, "traced").await.unwrap().trace.unwrap();
+        assert_eq!(trace.len(), 1);
+        assert_eq!(trace[0].phase, TracePhase::ProviderCall);
+        assert_eq!(trace[0].detail.as_deref(), Some("2 attempts"));
+        assert!(get_evaluation(&pool, "untraced").await.unwrap().trace.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_migrations_from_a_newer_build_give_an_actionable_error() {
+        let pool = test_pool().await;
+        // Already up to date: running the built-in migrations again changes nothing
+        run_migrations(&pool).await.unwrap();
+
+        let dir = std::env::temp_dir().join(format!("evaluate-migrations-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("99990101000000_from_the_future.sql"), "CREATE TABLE future (id INTEGER);").unwrap();
+        let mut future = Migrator::new(dir.as_path()).await.unwrap();
+        future.set_ignore_missing(true);
+        run_migrator(&future, &pool).await.unwrap();
+
+        let message = run_migrations(&pool).await.unwrap_err().to_string();
+        assert!(message.contains("migrations built into this binary"), "{}", message);
+        assert!(message.contains("MIGRATIONS_DIR"), "{}", message);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_seed_defaults_fills_empty_tables_once() {
+        let pool = test_pool().await;
+        query("DELETE FROM judge_prompts").execute(&pool).await.unwrap();
+        assert!(matches!(get_active_judge_prompt(&pool).await, Err(sqlx::Error::RowNotFound)));
+
+        // A second startup finds the seeded rows and adds nothing
+        seed_defaults(&pool).await.unwrap();
+        seed_defaults(&pool).await.unwrap();
+
+        let judge_prompts = get_all_judge_prompts(&pool).await.unwrap();
+        assert_eq!(judge_prompts.len(), 1);
+        let active = get_active_judge_prompt(&pool).await.unwrap();
+        assert_eq!(active.version, 1);
+        assert_eq!(active.template, runner::get_default_judge_prompt_template());
+
+        let prompt_versions = get_all_prompt_versions(&pool).await.unwrap();
+        assert_eq!(prompt_versions.len(), 1);
+        assert_eq!(get_active_prompt_version(&pool).await.unwrap().version, 1);
+
+        // Tables that already hold rows are left alone
+        create_judge_prompt(&pool, "strict".to_string(), "{{expected}} {{actual}}".to_string(), None, true)
+            .await
+            .unwrap();
+        seed_defaults(&pool).await.unwrap();
+        assert_eq!(get_all_judge_prompts(&pool).await.unwrap().len(), 2);
+        assert_eq!(get_active_judge_prompt(&pool).await.unwrap().name, "strict");
+
+        // New versions are numbered after the seeded ones
+        let version = create_prompt_version(&pool, "terse".to_string(), "{{prompt}}".to_string(), None, None, false)
+            .await
+            .unwrap();
+        assert_eq!(version.version, 2);
+    }
+
+    #[tokio::test]
+    async fn test_judge_prompt_update_and_delete_refuse_versions_in_use() {
+        let pool = test_pool().await;
+        let draft = create_judge_prompt(&pool, "Draft".to_string(), "{{expected}} {{actul}}".to_string(), None, false)
+            .await
+            .unwrap();
+        let fix = JudgePromptUpdate { template: Some("{{expected}} {{actual}}".to_string()), ..Default::default() };
+        let updated = update_judge_prompt(&pool, draft.version, &fix).await.unwrap().unwrap();
+        assert_eq!((updated.name.as_str(), updated.template.as_str()), ("Draft", "{{expected}} {{actual}}"));
+        assert!(matches!(update_judge_prompt(&pool, 999, &fix).await, Err(sqlx::Error::RowNotFound)));
+
+        // The seeded version 1 is active and judged the saved evaluations
+        save(&pool, "e1", sample_result(JudgeVerdict::Pass, None, false)).await;
+        save(&pool, "e2", sample_result(JudgeVerdict::Fail, None, false)).await;
+        let in_use = JudgePromptUsage { evaluations: 2, ..Default::default() };
+        assert_eq!(
+            update_judge_prompt(&pool, 1, &fix).await.unwrap().err(),
+            Some(JudgePromptConflict::InUse(in_use)),
+        );
+        assert_eq!(delete_judge_prompt(&pool, 1).await.unwrap(), Err(JudgePromptConflict::Active));
+
+        start_canary(&pool, draft.version, 10.0).await.unwrap();
+        let canaried = JudgePromptUsage { canaries: 1, ..Default::default() };
+        assert_eq!(delete_judge_prompt(&pool, draft.version).await.unwrap(), Err(JudgePromptConflict::InUse(canaried)));
+
+        let unused = create_judge_prompt(&pool, "Unused".to_string(), "{{actual}}".to_string(), None, false)
+            .await
+            .unwrap();
+        assert_eq!(delete_judge_prompt(&pool, unused.version).await.unwrap(), Ok(()));
+        assert!(matches!(get_judge_prompt_by_version(&pool, unused.version).await, Err(sqlx::Error::RowNotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_canary_report_splits_arms() {
+        let pool = test_pool().await;
+        let candidate = create_judge_prompt(&pool, "Candidate".to_string(), "{{expected}} {{actual}}".to_string(), None, false)
+            .await
+            .unwrap();
+        assert!(start_canary(&pool, 999, 10.0).await.is_err());
+        let canary = start_canary(&pool, candidate.version, 10.0).await.unwrap();
+        assert_eq!(get_active_canary(&pool).await.unwrap().unwrap().id, canary.id);
+
+        for (id, verdict, arm) in [
+            ("c1", JudgeVerdict::Pass, JudgePromptArm::Control),
+            ("c2", JudgeVerdict::Fail, JudgePromptArm::Control),
+            ("k1", JudgeVerdict::Uncertain, JudgePromptArm::Canary),
+        ] {
+            let mut result = sample_result(verdict, Some(0.9), false);
+            result.judge_prompt_arm = Some(arm);
+            save(&pool, id, result).await;
+        }
+        // Judged without a canary running, so left out of the report
+        save(&pool, "plain", sample_result(JudgeVerdict::Pass, Some(0.9), false)).await;
+
+        let stopped = stop_canary(&pool).await.unwrap().unwrap();
+        assert!(stopped.stopped_at.is_some());
+        assert!(get_active_canary(&pool).await.unwrap().is_none());
+        assert!(stop_canary(&pool).await.unwrap().is_none());
+
+        let arms = get_canary_arm_stats(&pool, &stopped).await.unwrap();
+        assert_eq!(arms.len(), 2);
+        assert_eq!(arms[0].arm, "canary");
+        assert_eq!((arms[0].evaluations, arms[0].uncertain), (1, 1));
+        assert_eq!(arms[1].arm, "control");
+        assert_eq!((arms[1].passed, arms[1].failed), (1, 1));
+        assert_eq!(arms[1].pass_rate, 0.5);
+        assert_eq!(arms[1].avg_judge_latency_ms, Some(5.0));
+    }
+
+    #[tokio::test]
+    async fn test_judge_ab_results_are_recorded_per_version() {
+        let pool = test_pool().await;
+        save(&pool, "judged", sample_result(JudgeVerdict::Pass, Some(0.9), false)).await;
+        let mut unjudged = sample_result(JudgeVerdict::Pass, None, false);
+        unjudged.judge_result = None;
+        save(&pool, "unjudged", unjudged).await;
+
+        let sampled = sample_judged_evaluations(&pool, 10).await.unwrap();
+        assert_eq!(sampled.len(), 1);
+        assert_eq!(sampled[0].id, "judged");
+
+        let test = create_judge_ab_test(&pool, 1, 1, Some("openai:gpt-4o")).await.unwrap();
+        let comparison = AbComparison {
+            evaluation_id: "judged".to_string(),
+            judge_model: "openai:gpt-4o".to_string(),
+            original_verdict: sampled[0].judge_verdict.clone(),
+            a: ArmJudgement { verdict: Some(JudgeVerdict::Pass), latency_ms: Some(10), ..Default::default() },
+            b: ArmJudgement { error: Some("timeout".to_string()), ..Default::default() },
+        };
+        save_judge_ab_comparison(&pool, &test, &comparison).await.unwrap();
+
+        let rows: Vec<(String, Option<String>, Option<String>)> =
+            query("SELECT judge_model, verdict, error_message FROM judge_ab_results WHERE test_id = ? ORDER BY id")
+                .bind(&test.id)
+                .fetch_all(&pool)
+                .await
+                .unwrap()
+                .iter()
+                .map(|row| (row.get("judge_model"), row.get("verdict"), row.get("error_message")))
+                .collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0], ("openai:gpt-4o".to_string(), Some("Pass".to_string()), None));
+        assert_eq!(rows[1].2.as_deref(), Some("timeout"));
+    }
+
+    #[tokio::test]
+    async fn test_judge_prompt_stats_and_comparison_cover_current_replaced_and_ab_verdicts() {
+        let pool = test_pool().await;
+        for name in ["v2", "v3"] {
+            create_judge_prompt(&pool, name.to_string(), "{{expected}} {{output}}".to_string(), None, false).await.unwrap();
+        }
+        save(&pool, "e1", sample_result(JudgeVerdict::Pass, None, false)).await;
+        let mut e2 = sample_result(JudgeVerdict::Uncertain, None, false);
+        e2.timestamp = "2026-10-01T00:00:00+00:00".to_string();
+        save(&pool, "e2", e2).await;
+        let mut e3 = sample_result(JudgeVerdict::Pass, None, false);
+        e3.judge_prompt_version = Some(2);
+        save(&pool, "e3", e3).await;
+
+        // e1 re-judged with v2, e2 re-judged with both in an A/B test
+        let judgement = Judgement {
+            result: JudgeResult {
+                judge_model: "openai:gpt-4o".to_string(),
+                verdict: JudgeVerdict::Fail,
+                reasoning: None,
+                confidence: None,
+                raw_response: None,
+                uncertain_reason: None,
+            },
+            prompt: "Verdict?".to_string(),
+            params: JudgeParams::default(),
+            latency_ms: 20,
+            token_usage: TokenUsage { input_tokens: Some(100), output_tokens: Some(10) },
+            cost_usd: Some(0.01),
+        };
+        save_rejudgement(&pool, "e1", &judgement, Some(2), false).await.unwrap();
+        let test = create_judge_ab_test(&pool, 1, 2, None).await.unwrap();
+        let comparison = AbComparison {
+            evaluation_id: "e2".to_string(),
+            judge_model: "ollama:llama3".to_string(),
+            original_verdict: Some("Uncertain".to_string()),
+            a: ArmJudgement { verdict: Some(JudgeVerdict::Pass), latency_ms: Some(10), ..Default::default() },
+            b: ArmJudgement { verdict: Some(JudgeVerdict::Uncertain), latency_ms: Some(30), cost_usd: Some(0.03), ..Default::default() },
+        };
+        save_judge_ab_comparison(&pool, &test, &comparison).await.unwrap();
+
+        let v1 = get_judge_prompt_stats(&pool, 1).await.unwrap();
+        assert_eq!((v1.judged, v1.current, v1.replaced, v1.ab_test), (3, 1, 1, 1));
+        assert_eq!((v1.verdicts.passed, v1.verdicts.failed, v1.verdicts.uncertain), (2, 0, 1));
+        let v2 = get_judge_prompt_stats(&pool, 2).await.unwrap();
+        assert_eq!((v2.judged, v2.current, v2.replaced, v2.ab_test), (3, 2, 0, 1));
+        assert_eq!((v2.verdicts.passed, v2.verdicts.failed, v2.verdicts.uncertain), (1, 1, 1));
+        assert!((v2.avg_judge_latency_ms - 55.0 / 3.0).abs() < 1e-9);
+        assert_eq!(v2.avg_judge_input_tokens, 100.0);
+        assert!((v2.avg_judge_cost_usd - 0.02).abs() < 1e-9 && (v2.total_judge_cost_usd - 0.04).abs() < 1e-9);
+        let unused = get_judge_prompt_stats(&pool, 3).await.unwrap();
+        assert_eq!(unused, JudgePromptStats { version: 3, ..Default::default() });
+
+        // e2's A/B verdict under v1 is later than the one it was saved with; e3 was only judged by v2
+        let pairs = get_judge_prompt_verdict_pairs(&pool, 1, 2).await.unwrap();
+        assert_eq!(pairs, [(JudgeVerdict::Pass, JudgeVerdict::Fail), (JudgeVerdict::Pass, JudgeVerdict::Uncertain)]);
+        let comparison = crate::judge_ab::compare_versions(v1, v2, &pairs);
+        assert!((comparison.deltas.pass_rate - (1.0 / 3.0 - 2.0 / 3.0)).abs() < 1e-9);
+        assert_eq!((comparison.paired.evaluations, comparison.paired.agreements, comparison.paired.agreement_rate), (2, 0, 0.0));
+        assert_eq!(comparison.paired.deltas.uncertain_rate, 0.5);
+        let flipped: Vec<_> = comparison.paired.flips.iter().filter(|f| f.count > 0).map(|f| (f.from, f.to)).collect();
+        assert_eq!(flipped, [(JudgeVerdict::Pass, JudgeVerdict::Fail), (JudgeVerdict::Pass, JudgeVerdict::Uncertain)]);
+
+        let against_unused = crate::judge_ab::compare_versions(get_judge_prompt_stats(&pool, 1).await.unwrap(), unused, &[]);
+        assert_eq!(against_unused.paired.agreement_rate, 0.0);
+        assert!((against_unused.deltas.pass_rate + 2.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_rejudge_replaces_verdict_and_keeps_the_old_one() {
+        let pool = test_pool().await;
+        save(&pool, "uncertain", sample_result(JudgeVerdict::Uncertain, Some(0.4), false)).await;
+        save(&pool, "passed", sample_result(JudgeVerdict::Pass, Some(0.9), false)).await;
+        save(&pool, "reviewed", sample_result(JudgeVerdict::Uncertain, Some(0.4), false)).await;
+        review_evaluation(&pool, "reviewed", "Pass", None).await.unwrap();
+
+        let filter = RejudgeFilter { verdict: Some("Uncertain".to_string()), ..Default::default() };
+        let candidates = get_rejudge_candidates(&pool, &filter, 10).await.unwrap();
+        assert_eq!(candidates.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), ["uncertain"]);
+        let by_model = RejudgeFilter { model: Some("openai:gpt-4o".to_string()), ..Default::default() };
+        assert!(get_rejudge_candidates(&pool, &by_model, 10).await.unwrap().is_empty());
+
+        let judgement = Judgement {
+            result: JudgeResult {
+                judge_model: "openai:gpt-4o".to_string(),
+                verdict: JudgeVerdict::Fail,
+                reasoning: Some("Wrong answer".to_string()),
+                confidence: Some(0.95),
+                raw_response: None,
+                uncertain_reason: None,
+            },
+            prompt: "Verdict?".to_string(),
+            params: JudgeParams::default(),
+            latency_ms: 20,
+            token_usage: Default::default(),
+            cost_usd: Some(0.01),
+        };
+        assert_eq!(save_rejudgement(&pool, "uncertain", &judgement, Some(2), false).await.unwrap(), "failed");
+        assert!(matches!(save_rejudgement(&pool, "missing", &judgement, Some(2), false).await, Err(sqlx::Error::RowNotFound)));
+
+        let entry = get_evaluation(&pool, "uncertain").await.unwrap();
+        assert_eq!(entry.status.as_deref(), Some("failed"));
+        assert_eq!(entry.judge_verdict.as_deref(), Some("Fail"));
+        assert_eq!(entry.judge_model.as_deref(), Some("openai:gpt-4o"));
+        assert_eq!(entry.judge_prompt_version, Some(2));
+        assert_eq!(entry.judge_prompt_rendered.as_deref(), Some("Verdict?"));
+
+        let replaced = get_replaced_judgements(&pool, "uncertain").await.unwrap();
+        assert_eq!(replaced.len(), 1);
+        assert_eq!(replaced[0].judge_verdict.as_deref(), Some("Uncertain"));
+        assert_eq!(replaced[0].status.as_deref(), Some("uncertain"));
+        assert_eq!(replaced[0].judge_prompt_version, Some(1));
+
+        // A human verdict keeps deciding the status
+        save_rejudgement(&pool, "reviewed", &judgement, Some(2), false).await.unwrap();
+        assert_eq!(get_evaluation(&pool, "reviewed").await.unwrap().status.as_deref(), Some("passed"));
+    }
+
+    #[tokio::test]
+    async fn test_judge_batching_stats_split_batched_from_individual() {
+        let pool = test_pool().await;
+        for (id, verdict, batch_size) in [
+            ("b1", JudgeVerdict::Pass, Some(4)),
+            ("b2", JudgeVerdict::Uncertain, Some(2)),
+            ("i1", JudgeVerdict::Pass, None),
+        ] {
+            let mut result = sample_result(verdict, Some(0.8), false);
+            result.judge_batch_size = batch_size;
+            save(&pool, id, result).await;
+        }
+        review_evaluation(&pool, "b1", "Fail", None).await.unwrap();
+        assert_eq!(get_evaluation(&pool, "b1").await.unwrap().judge_batch_size, Some(4));
+
+        let stats = get_judge_batching_stats(&pool, &StatsFilter::default()).await.unwrap();
+        assert_eq!(stats.len(), 2);
+        let batched = &stats[0];
+        assert_eq!((batched.mode.as_str(), batched.judged), ("batched", 2));
+        assert_eq!(batched.avg_batch_size, Some(3.0));
+        assert_eq!((batched.pass_rate, batched.uncertain_rate), (0.5, 0.5));
+        assert_eq!((batched.reviewed, batched.human_agreement_rate), (1, Some(0.0)));
+        let individual = &stats[1];
+        assert_eq!((individual.mode.as_str(), individual.judged), ("individual", 1));
+        assert_eq!(individual.avg_batch_size, None);
+        assert_eq!(individual.human_agreement_rate, None);
+    }
+
+    #[tokio::test]
+    async fn test_leaderboard_aggregates_and_orders_models() {
+        let pool = test_pool().await;
+        let seed = |id: &str, model: &str, verdict: Option<JudgeVerdict>, latency_ms: u64, cost: Option<f64>, day: &str| {
+            let mut result = sample_result(JudgeVerdict::Pass, Some(0.9), false);
+            result.model = model.to_string();
+            result.judge_result = verdict.map(|verdict| JudgeResult {
+                judge_model: "ollama:llama3".to_string(),
+                verdict,
+                reasoning: None,
+                confidence: Some(0.9),
+                raw_response: None,
+                uncertain_reason: None,
+            });
+            result.latency_ms = latency_ms;
+            result.cost_usd = cost;
+            result.judge_cost_usd = None;
+            result.token_usage = Some(crate::providers::TokenUsage { input_tokens: Some(10), output_tokens: Some(latency_ms as u32) });
+            result.timestamp = format!("{}T12:00:00+00:00", day);
+            result.tags = if day == "2026-10-02" { vec!["nightly".to_string()] } else { Vec::new() };
+            (id.to_string(), result)
+        };
+        let rows = vec![
+            seed("a1", "fast", Some(JudgeVerdict::Pass), 100, Some(0.01), "2026-10-01"),
+            seed("a2", "fast", Some(JudgeVerdict::Fail), 300, Some(0.03), "2026-10-02"),
+            seed("a3", "fast", Some(JudgeVerdict::Uncertain), 200, None, "2026-10-02"),
+            seed("b1", "accurate", Some(JudgeVerdict::Pass), 900, Some(0.10), "2026-10-01"),
+            seed("b2", "accurate", Some(JudgeVerdict::Pass), 1000, Some(0.10), "2026-10-02"),
+            seed("c1", "unjudged", None, 50, None, "2026-10-02"),
+        ];
+        for (id, result) in rows {
+            save(&pool, &id, result).await;
+        }
+
+        let all = get_leaderboard(&pool, &StatsFilter::default(), LeaderboardOrder::PassRate).await.unwrap();
+        let models: Vec<&str> = all.iter().map(|e| e.model.as_str()).collect();
+        assert_eq!(models, ["accurate", "fast", "unjudged"]);
+        let fast = &all[1];
+        assert_eq!((fast.total_evaluations, fast.judged_evaluations), (3, 3));
+        assert!((fast.pass_rate.unwrap() - 1.0 / 3.0).abs() < 1e-9);
+        assert!((fast.uncertain_rate.unwrap() - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(fast.avg_latency_ms, Some(200.0));
+        assert_eq!(fast.p95_latency_ms, Some(300));
+        assert_eq!(fast.avg_output_tokens, Some(200.0));
+        assert!((fast.avg_cost_usd.unwrap() - 0.02).abs() < 1e-9);
+        assert!((fast.total_cost_usd.unwrap() - 0.04).abs() < 1e-9);
+        assert_eq!((fast.passed_evaluations, fast.uncertain_evaluations), (1, 1));
+        let unjudged = &all[2];
+        assert_eq!((unjudged.pass_rate, unjudged.avg_cost_usd, unjudged.total_cost_usd), (None, None, None));
+
+        let by_latency = get_leaderboard(&pool, &StatsFilter::default(), LeaderboardOrder::Latency).await.unwrap();
+        assert_eq!(by_latency[0].model, "unjudged");
+        let by_cost = get_leaderboard(&pool, &StatsFilter::default(), LeaderboardOrder::Cost).await.unwrap();
+        assert_eq!(by_cost.last().unwrap().model, "unjudged");
+
+        let to_first_day = StatsFilter { to: Some("2026-10-01".to_string()), ..Default::default() };
+        let first_day = get_leaderboard(&pool, &to_first_day, LeaderboardOrder::PassRate).await.unwrap();
+        assert_eq!(first_day.iter().map(|e| e.total_evaluations).sum::<i64>(), 2);
+
+        let nightly = StatsFilter { tag: Some("nightly".to_string()), from: Some("2026-10-02".to_string()), ..Default::default() };
+        let tagged = get_leaderboard(&pool, &nightly, LeaderboardOrder::PassRate).await.unwrap();
+        assert_eq!(tagged.iter().map(|e| e.total_evaluations).sum::<i64>(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_timeseries_and_token_distribution_include_empty_buckets() {
+        let pool = test_pool().await;
+        for (id, verdict, output_tokens, timestamp) in [
+            ("d1", JudgeVerdict::Pass, 40, "2026-10-01T09:00:00+00:00"),
+            ("d2", JudgeVerdict::Fail, 60, "2026-10-01T17:30:00+00:00"),
+            ("d4", JudgeVerdict::Pass, 260, "2026-10-04T08:00:00+00:00"),
+        ] {
+            let mut result = sample_result(verdict, Some(0.9), false);
+            result.token_usage = Some(crate::providers::TokenUsage { input_tokens: Some(10), output_tokens: Some(output_tokens) });
+            result.timestamp = timestamp.to_string();
+            save(&pool, id, result).await;
+        }
+
+        let days = get_timeseries(&pool, TimeBucket::Day, Some("ollama:llama3")).await.unwrap();
+        let buckets: Vec<&str> = days.iter().map(|p| p.bucket.as_str()).collect();
+        assert_eq!(buckets, ["2026-10-01", "2026-10-02", "2026-10-03", "2026-10-04"]);
+        assert_eq!((days[0].evaluations, days[0].pass_rate), (2, Some(0.5)));
+        assert_eq!(days[0].avg_output_tokens, Some(50.0));
+        assert_eq!((days[1].evaluations, days[1].pass_rate, days[1].avg_latency_ms), (0, None, None));
+        assert_eq!(days[3].pass_rate, Some(1.0));
+
+        let hours = get_timeseries(&pool, TimeBucket::Hour, None).await.unwrap();
+        assert_eq!(hours.first().unwrap().bucket, "2026-10-01T09");
+        assert_eq!(hours.len(), 3 * 24 + 8 - 9 + 1);
+        assert!(get_timeseries(&pool, TimeBucket::Day, Some("other")).await.unwrap().is_empty());
+
+        let histogram = get_token_distribution(&pool, 100, None).await.unwrap();
+        let counts: Vec<(i64, i64, i64)> = histogram.iter().map(|b| (b.start, b.end, b.evaluations)).collect();
+        assert_eq!(counts, [(0, 100, 2), (100, 200, 0), (200, 300, 1)]);
+    }
+
+    #[tokio::test]
+    async fn test_weekly_output_tokens_group_by_model_and_monday() {
+        let pool = test_pool().await;
+        for (id, verdict, output_tokens, timestamp) in [
+            // Sunday, still the week of Monday 2026-09-28
+            ("w1", JudgeVerdict::Pass, 40, "2026-10-04T23:00:00+00:00"),
+            ("w2", JudgeVerdict::Pass, 60, "2026-09-28T00:10:00+00:00"),
+            ("w3", JudgeVerdict::Fail, 250, "2026-10-05T08:00:00+00:00"),
+            ("w4", JudgeVerdict::Pass, 450, "2026-10-11T12:00:00+00:00"),
+        ] {
+            let mut result = sample_result(verdict, Some(0.9), false);
+            result.token_usage = Some(crate::providers::TokenUsage { input_tokens: Some(10), output_tokens: Some(output_tokens) });
+            result.timestamp = timestamp.to_string();
+            save(&pool, id, result).await;
+        }
+
+        let weeks = get_weekly_output_tokens(&pool, None, None, None).await.unwrap();
+        let summary: Vec<_> = weeks
+            .iter()
+            .map(|w| (w.week.as_str(), w.evaluations, w.mean_output_tokens, w.max_output_tokens, w.pass_rate))
+            .collect();
+        assert_eq!(summary, [
+            ("2026-09-28", 2, Some(50.0), Some(60), Some(1.0)),
+            ("2026-10-05", 2, Some(350.0), Some(450), Some(0.5)),
+        ]);
+        assert!(weeks.iter().all(|w| w.model == "ollama:llama3" && w.histogram.is_empty()));
+
+        let histograms = get_weekly_output_tokens(&pool, Some("ollama:llama3"), Some("2026-10-05"), Some(100)).await.unwrap();
+        assert_eq!(histograms.len(), 1);
+        let counts: Vec<(i64, i64)> = histograms[0].histogram.iter().map(|b| (b.start, b.evaluations)).collect();
+        assert_eq!(counts, [(200, 1), (300, 0), (400, 1)]);
+        assert!(get_weekly_output_tokens(&pool, Some("other"), None, Some(100)).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_uncertain_reasons_are_persisted_filtered_and_counted() {
+        let pool = test_pool().await;
+        let with_reason = |verdict, needs_review, reason| {
+            let mut result = sample_result(verdict, Some(0.5), needs_review);
+            result.judge_result.as_mut().unwrap().uncertain_reason = reason;
+            result
+        };
+        save(&pool, "garbled", with_reason(JudgeVerdict::Uncertain, false, Some(UncertainReason::Unparseable))).await;
+        save(&pool, "hesitant", with_reason(JudgeVerdict::Pass, true, Some(UncertainReason::LowConfidence))).await;
+        save(&pool, "hesitant2", with_reason(JudgeVerdict::Fail, true, Some(UncertainReason::LowConfidence))).await;
+        save(&pool, "clear", with_reason(JudgeVerdict::Pass, false, None)).await;
+
+        review_evaluation(&pool, "hesitant2", "Uncertain", None).await.unwrap();
+        review_evaluation(&pool, "hesitant", "Pass", None).await.unwrap();
+
+        let filter = HistoryFilter { uncertain_reason: Some("low_confidence".to_string()), ..Default::default() };
+        let low = get_evaluations(&pool, &filter).await.unwrap();
+        assert_eq!(low.len(), 1);
+        assert_eq!(low[0].id, "hesitant");
+        assert_eq!(get_evaluation(&pool, "hesitant2").await.unwrap().uncertain_reason.as_deref(), Some("human_review"));
+        assert_eq!(get_all_evaluations(&pool).await.unwrap().len(), 4);
+
+        let stats = get_uncertain_reason_stats(&pool, &StatsFilter::default()).await.unwrap();
+        let by_reason: Vec<(&str, i64, i64)> = stats
+            .iter()
+            .map(|s| (s.uncertain_reason.as_str(), s.evaluations, s.uncertain_verdicts))
+            .collect();
+        assert_eq!(by_reason, [("human_review", 1, 1), ("low_confidence", 1, 0), ("unparseable", 1, 1)]);
+        assert!((stats[0].share - 1.0 / 3.0).abs() < 1e-9);
+
+        let review = get_review_stats(&pool).await.unwrap();
+        assert_eq!(review[0].uncertain_reasons.get("unparseable"), Some(&1));
+        assert_eq!(review[0].uncertain_reasons.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_resolved_model_is_stored_with_the_evaluation_and_the_cached_response() {
+        let pool = test_pool().await;
+        let mut result = sample_result(JudgeVerdict::Pass, None, false);
+        result.model = "openrouter:anthropic/claude-3.5-sonnet".to_string();
+        result.resolved_model = Some("anthropic/claude-3.5-sonnet-20240620".to_string());
+        save(&pool, "a", result).await;
+        save(&pool, "b", sample_result(JudgeVerdict::Pass, None, false)).await;
+        let stored = get_evaluation(&pool, "a").await.unwrap();
+        assert_eq!(stored.resolved_model.as_deref(), Some("anthropic/claude-3.5-sonnet-20240620"));
+        assert_eq!(get_evaluation(&pool, "b").await.unwrap().resolved_model, None);
+
+        let generation = Generation {
+            output: "4".to_string(),
+            latency_ms: 10,
+            token_usage: TokenUsage::default(),
+            finish_reason: None,
+            resolved_model: Some("anthropic/claude-3.5-sonnet-20240620".to_string()),
+            reasoning: Some("2 plus 2 is 4.".to_string()),
+        };
+        save_cached_response(&pool, "key", "openrouter:anthropic/claude-3.5-sonnet", &generation).await.unwrap();
+        let cached = get_cached_response(&pool, "key", None).await.unwrap().unwrap();
+        assert_eq!(cached.resolved_model, generation.resolved_model);
+        assert_eq!(cached.reasoning, generation.reasoning);
+    }
+
+    #[tokio::test]
+    async fn test_finish_reason_is_stored_and_counted_per_model() {
+        let pool = test_pool().await;
+        for (id, model, reason) in [
+            ("a", "ollama:llama3", Some(FinishReason::Stop)),
+            ("b", "ollama:llama3", Some(FinishReason::Length)),
+            ("c", "ollama:llama3", None),
+            ("d", "ollama:llama3", Some(FinishReason::Length)),
+            ("e", "openai:gpt-4o", Some(FinishReason::Other("pause_turn".to_string()))),
+        ] {
+            let mut result = sample_result(JudgeVerdict::Pass, None, false);
+            result.model = model.to_string();
+            result.finish_reason = reason;
+            save(&pool, id, result).await;
+        }
+        assert_eq!(get_evaluation(&pool, "b").await.unwrap().finish_reason.as_deref(), Some("length"));
+        let filter = HistoryFilter { finish_reason: Some("length".to_string()), ..Default::default() };
+        let cut_off: Vec<String> = get_evaluations(&pool, &filter).await.unwrap().into_iter().map(|e| e.id).collect();
+        assert_eq!(cut_off.len(), 2);
+        assert!(cut_off.contains(&"b".to_string()) && cut_off.contains(&"d".to_string()));
+
+        let stats = get_finish_reason_stats(&pool, &StatsFilter::default()).await.unwrap();
+        let llama = &stats[0];
+        assert_eq!((llama.model.as_str(), llama.evaluations, llama.unreported), ("ollama:llama3", 4, 1));
+        assert_eq!(llama.finish_reasons.get("length"), Some(&2));
+        assert_eq!(llama.length_rate, 0.5);
+        assert_eq!(stats[1].finish_reasons.get("pause_turn"), Some(&1));
+        assert_eq!(stats[1].length_rate, 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_failure_categories_are_counted_corrected_and_cleared() {
+        use crate::failure_taxonomy::{self, FailureClassification};
+
+        let pool = test_pool().await;
+        seed_defaults(&pool).await.unwrap();
+        assert_eq!(get_failure_categories(&pool).await.unwrap(), failure_taxonomy::default_taxonomy());
+
+        let classified = |category: &str, classifier: &str| FailureClassification {
+            category: category.to_string(),
+            justification: Some("Says 5".to_string()),
+            classifier: classifier.to_string(),
+            cost_usd: Some(0.001),
+        };
+        for (id, verdict, classification) in [
+            ("a", JudgeVerdict::Fail, Some(classified("wrong_answer", "openai:gpt-4o-mini"))),
+            ("b", JudgeVerdict::Fail, Some(classified("wrong_answer", "judge"))),
+            ("c", JudgeVerdict::Fail, None),
+            ("d", JudgeVerdict::Pass, None),
+        ] {
+            let mut result = sample_result(verdict, None, false);
+            result.failure_classification = classification;
+            save(&pool, id, result).await;
+        }
+        assert_eq!(get_evaluation(&pool, "a").await.unwrap().failure_classifier.as_deref(), Some("openai:gpt-4o-mini"));
+
+        // A reviewer corrects one category, and overturns another failure
+        review_evaluation(&pool, "c", "Fail", Some("refusal")).await.unwrap();
+        review_evaluation(&pool, "b", "Pass", None).await.unwrap();
+        let corrected = get_evaluation(&pool, "c").await.unwrap();
+        assert_eq!(corrected.failure_category.as_deref(), Some("refusal"));
+        assert_eq!(corrected.failure_classifier.as_deref(), Some(failure_taxonomy::HUMAN_CLASSIFIER));
+        assert!(get_evaluation(&pool, "b").await.unwrap().failure_category.is_none());
+
+        let stats = get_failure_category_stats(&pool, &StatsFilter::default(), Some("ollama:llama3")).await.unwrap();
+        assert_eq!(stats.len(), 1);
+        assert_eq!((stats[0].failed, stats[0].unclassified, stats[0].human_classified), (2, 0, 1));
+        assert_eq!(stats[0].categories.get("wrong_answer"), Some(&1));
+        assert_eq!(stats[0].categories.get("refusal"), Some(&1));
+        assert!(get_failure_category_stats(&pool, &StatsFilter::default(), Some("openai:gpt-4o")).await.unwrap().is_empty());
+
+        // A re-judge drops the model's category but keeps the reviewer's
+        let judgement = Judgement {
+            result: JudgeResult {
+                judge_model: "openai:gpt-4o".to_string(),
+                verdict: JudgeVerdict::Fail,
+                reasoning: None,
+                confidence: None,
+                raw_response: None,
+                uncertain_reason: None,
+            },
+            prompt: "Verdict?".to_string(),
+            params: JudgeParams::default(),
+            latency_ms: 20,
+            token_usage: Default::default(),
+            cost_usd: None,
+        };
+        save_rejudgement(&pool, "a", &judgement, Some(1), false).await.unwrap();
+        save_rejudgement(&pool, "c", &judgement, Some(1), false).await.unwrap();
+        assert!(get_evaluation(&pool, "a").await.unwrap().failure_category.is_none());
+        assert_eq!(get_evaluation(&pool, "c").await.unwrap().failure_category.as_deref(), Some("refusal"));
+
+        let edited = vec![FailureCategory { name: "refusal".to_string(), description: "Declines".to_string() }];
+        replace_failure_categories(&pool, &edited).await.unwrap();
+        seed_defaults(&pool).await.unwrap();
+        assert_eq!(get_failure_categories(&pool).await.unwrap(), edited);
+    }
+
+    #[tokio::test]
+    async fn test_bulk_accept_only_touches_queued_evaluations() {
+        let pool = test_pool().await;
+        save(&pool, "low", sample_result(JudgeVerdict::Fail, Some(0.4), true)).await;
+        save(&pool, "high", sample_result(JudgeVerdict::Pass, Some(0.95), false)).await;
+
+        assert_eq!(get_review_queue(&pool).await.unwrap().len(), 1);
+
+        let accepted = accept_provisional_verdicts(&pool, &["low".to_string(), "high".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(accepted, 1);
+        assert!(get_review_queue(&pool).await.unwrap().is_empty());
+
+        let history = get_all_evaluations(&pool).await.unwrap();
+        let low = history.iter().find(|e| e.id == "low").unwrap();
+        assert_eq!(low.status.as_deref(), Some("failed"));
+        assert_eq!(low.human_verdict.as_deref(), Some("Fail"));
+        assert_eq!(low.judge_verdict.as_deref(), Some("Fail"));
+
+        let stats = get_review_stats(&pool).await.unwrap();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].uncertain_reasons.get("low_confidence"), None);
+        assert_eq!(stats[0].judged, 2);
+        assert_eq!(stats[0].needs_review, 1);
+        assert_eq!(stats[0].pending_review, 0);
+        assert!((stats[0].needs_review_rate - 0.5).abs() < f64::EPSILON);
+    }
+
+    #[tokio::test]
+    async fn test_triage_queue_is_oldest_first_and_its_count_follows_reviews_and_rejudges() {
+        let pool = test_pool().await;
+        let at = |mut result: runner::EvalResult, timestamp: &str| {
+            result.timestamp = timestamp.to_string();
+            result
+        };
+        let mut errored = sample_result(JudgeVerdict::Pass, None, false);
+        errored.judge_result = None;
+        errored.judge_error = Some(runner::JudgeError {
+            judge_model: "ollama:llama3".to_string(),
+            code: "provider_error".to_string(),
+            message: "judge unavailable".to_string(),
+            status: Some(503),
+        });
+        save(&pool, "newest", at(sample_result(JudgeVerdict::Uncertain, None, false), "2026-10-03T09:00:00Z")).await;
+        save(&pool, "errored", at(errored, "2026-10-02T09:00:00Z")).await;
+        save(&pool, "oldest", at(sample_result(JudgeVerdict::Uncertain, None, false), "2026-10-01T09:00:00Z")).await;
+        save(&pool, "clear", at(sample_result(JudgeVerdict::Pass, Some(0.9), false), "2026-09-30T09:00:00Z")).await;
+
+        let ids = |queue: Vec<HistoryEntry>| queue.into_iter().map(|e| e.id).collect::<Vec<_>>();
+        assert_eq!(ids(get_triage_queue(&pool, 10, 0).await.unwrap()), ["oldest", "errored", "newest"]);
+        assert_eq!(ids(get_triage_queue(&pool, 2, 1).await.unwrap()), ["errored", "newest"]);
+        assert_eq!(count_triage_queue(&pool).await.unwrap(), 3);
+
+        // A human verdict, even Uncertain, resolves an evaluation
+        review_evaluation(&pool, "oldest", "Uncertain", None).await.unwrap();
+        let judgement = Judgement {
+            result: JudgeResult {
+                judge_model: "ollama:llama3".to_string(),
+                verdict: JudgeVerdict::Pass,
+                reasoning: None,
+                confidence: Some(0.9),
+                raw_response: None,
+                uncertain_reason: None,
+            },
+            prompt: "Verdict?".to_string(),
+            params: JudgeParams::default(),
+            latency_ms: 20,
+            token_usage: Default::default(),
+            cost_usd: None,
+        };
+        save_rejudgement(&pool, "errored", &judgement, Some(1), false).await.unwrap();
+
+        let queue = get_triage_queue(&pool, 10, 0).await.unwrap();
+        assert_eq!(count_triage_queue(&pool).await.unwrap(), queue.len() as i64);
+        assert_eq!(ids(queue), ["newest"]);
+    }
+
+    #[tokio::test]
+    async fn test_cost_stats_group_by_model_and_day() {
+        let pool = test_pool().await;
+
+        let mut priced = sample_result(JudgeVerdict::Pass, None, false);
+        priced.model = "openai:gpt-4o".to_string();
+        priced.timestamp = "2026-01-01T10:00:00+00:00".to_string();
+        priced.cost_usd = Some(0.02);
+        priced.judge_cost_usd = Some(0.01);
+        save(&pool, "a", priced.clone()).await;
+
+        priced.timestamp = "2026-01-02T10:00:00+00:00".to_string();
+        save(&pool, "b", priced).await;
+
+        let mut unpriced = sample_result(JudgeVerdict::Pass, None, false);
+        unpriced.timestamp = "2026-01-02T11:00:00+00:00".to_string();
+        save(&pool, "c", unpriced).await;
+
+        let stats = get_cost_stats(&pool).await.unwrap();
+
+        assert_eq!(stats.by_model[0].key, "openai:gpt-4o");
+        assert_eq!(stats.by_model[0].evaluations, 2);
+        assert!((stats.by_model[0].total_cost_usd - 0.06).abs() < 1e-9);
+        let ollama = stats.by_model.iter().find(|b| b.key == "ollama:llama3").unwrap();
+        assert_eq!(ollama.priced_evaluations, 0);
+        assert_eq!(ollama.total_cost_usd, 0.0);
+
+        let days: Vec<_> = stats.by_day.iter().map(|b| (b.key.as_str(), b.evaluations)).collect();
+        assert_eq!(days, vec![("2026-01-01", 1), ("2026-01-02", 2)]);
+    }
+
+    #[tokio::test]
+    async fn test_batch_is_retrievable_by_id() {
+        let pool = test_pool().await;
+        create_batch(&pool, "batch-1", 2).await.unwrap();
+        assert_eq!(get_batch(&pool, "batch-1").await.unwrap().status, "running");
+
+        for (id, verdict) in [("b-pass", JudgeVerdict::Pass), ("b-fail", JudgeVerdict::Fail)] {
+            let result = sample_result(verdict, None, false);
+            let response = ApiResponse {
+                id: id.to_string(),
+                status: runner::eval_status(&result).to_string(),
+                result: EvalResult::Success(result),
+                batch_id: Some("batch-1".to_string()),
+                config_hash: None,
+                preset: None,
+            };
+            save_evaluation(&pool, &response).await.unwrap();
+        }
+        save(&pool, "unbatched", sample_result(JudgeVerdict::Pass, None, false)).await;
+        finish_batch(&pool, "batch-1", 1, 1, "completed").await.unwrap();
+
+        let batch = get_batch(&pool, "batch-1").await.unwrap();
+        assert_eq!((batch.total, batch.passed, batch.failed), (2, 1, 1));
+        assert_eq!(batch.status, "completed");
+
+        let members: Vec<_> = get_batch_evaluations(&pool, "batch-1").await.unwrap().into_iter().map(|e| e.id).collect();
+        assert_eq!(members.len(), 2);
+        assert!(members.contains(&"b-pass".to_string()) && members.contains(&"b-fail".to_string()));
+
+        assert!(matches!(get_batch(&pool, "missing").await, Err(sqlx::Error::RowNotFound)));
+        assert!(matches!(finish_batch(&pool, "missing", 0, 0, "completed").await, Err(sqlx::Error::RowNotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_provider_error_stats_separate_recovered_errors() {
+        let pool = test_pool().await;
+        let event = |provider: &str, status_code: Option<u16>, error_class: &'static str, recovered: bool| ProviderErrorEvent {
+            provider: provider.to_string(),
+            model: "m".to_string(),
+            status_code,
+            error_class,
+            body: "oops".to_string(),
+            attempt: 0,
+            recovered,
+        };
+        for e in [
+            event("gemini", Some(500), "server_error", true),
+            event("gemini", Some(500), "server_error", false),
+            event("gemini", Some(429), "rate_limited", true),
+            event("openai", None, "connection", false),
+        ] {
+            save_provider_error(&pool, &e).await.unwrap();
+        }
+
+        let by_provider = get_provider_error_stats(&pool, ProviderErrorGroup::Provider, false).await.unwrap();
+        assert_eq!(by_provider[0].key, "gemini");
+        assert_eq!((by_provider[0].total, by_provider[0].recovered, by_provider[0].terminal), (3, 2, 1));
+        assert!(by_provider[0].day.is_none());
+
+        let by_status = get_provider_error_stats(&pool, ProviderErrorGroup::Status, true).await.unwrap();
+        let keys: Vec<&str> = by_status.iter().map(|b| b.key.as_str()).collect();
+        assert_eq!(keys, ["500", "429", "connection"]);
+        assert_eq!(by_status[0].day.as_deref().map(str::len), Some(10));
+
+        let filter = ProviderErrorFilter { provider: Some("gemini".to_string()), recovered: Some(false), ..Default::default() };
+        let terminal = get_provider_errors(&pool, &filter).await.unwrap();
+        assert_eq!(terminal.len(), 1);
+        assert_eq!(terminal[0].status_code, Some(500));
+    }
+
+    #[tokio::test]
+    async fn test_dataset_rows_round_trip() {
+        let pool = test_pool().await;
+        let rows: Vec<DatasetRow> = [
+            serde_json::json!({"question": "2+2?", "expected": "4"}),
+            serde_json::json!({"question": "3+3?", "note": "extra"}),
+            serde_json::json!({"question": "4+4?"}),
+        ]
+        .into_iter()
+        .map(|v| v.as_object().unwrap().clone())
+        .collect();
+
+        let dataset = create_dataset(&pool, "arithmetic".to_string(), DatasetFormat::Jsonl, &rows).await.unwrap();
+        assert_eq!(dataset.row_count, 3);
+        assert_eq!(dataset.columns.len(), 3);
+        assert!(dataset.columns.contains(&"note".to_string()));
+        assert_eq!(get_all_datasets(&pool).await.unwrap().len(), 1);
+        assert_eq!(get_dataset(&pool, &dataset.id).await.unwrap().format, "jsonl");
+
+        let preview = get_dataset_rows(&pool, &dataset.id, Some(2)).await.unwrap();
+        assert_eq!(preview.len(), 2);
+        assert_eq!(preview[1].row_index, 1);
+        assert_eq!(preview[1].data["note"], "extra");
+        assert_eq!(get_dataset_rows(&pool, &dataset.id, None).await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_reviewed_reference_outputs_are_adopted_with_their_source() {
+        let pool = test_pool().await;
+        let rows: Vec<DatasetRow> = [
+            serde_json::json!({"question": "2+2?", "expected": "four"}),
+            serde_json::json!({"question": "3+3?"}),
+        ]
+        .into_iter()
+        .map(|v| v.as_object().unwrap().clone())
+        .collect();
+        let dataset = create_dataset(&pool, "arithmetic".to_string(), DatasetFormat::Jsonl, &rows).await.unwrap();
+
+        // A reference run: unjudged outputs waiting for review
+        for (id, row_index, output) in [("ref-0", 0, "4"), ("ref-1", 1, "6")] {
+            let mut result = sample_result(JudgeVerdict::Pass, None, true);
+            result.judge_result = None;
+            result.expected = None;
+            result.model_output = output.to_string();
+            result.dataset_row = Some(crate::dataset::DatasetRowRef {
+                dataset_id: dataset.id.clone(),
+                row_index,
+                expected_source: None,
+            });
+            let response = ApiResponse {
+                id: id.to_string(),
+                status: runner::eval_status(&result).to_string(),
+                result: EvalResult::Success(result),
+                batch_id: Some("batch-ref".to_string()),
+                config_hash: None,
+                preset: None,
+            };
+            save_evaluation(&pool, &response).await.unwrap();
+            review_evaluation(&pool, id, "Pass", None).await.unwrap();
+        }
+
+        let outputs = get_reference_outputs(&pool, &dataset.id, "batch-ref").await.unwrap();
+        assert_eq!(outputs.len(), 2);
+        let stored = get_dataset_rows(&pool, &dataset.id, None).await.unwrap();
+        let report = crate::dataset::plan_adoption(&stored, &outputs, false, "2026-10-16T00:00:00+00:00");
+        assert_eq!(report.kept_rows, [0]);
+        adopt_expected(&pool, &dataset.id, &report.adopted).await.unwrap();
+
+        let stored = get_dataset_rows(&pool, &dataset.id, None).await.unwrap();
+        assert_eq!(stored[0].data["expected"], "four");
+        assert!(stored[0].expected_source.is_none());
+        assert_eq!(stored[1].data["expected"], "6");
+        let source = stored[1].expected_source.clone().unwrap();
+        assert_eq!((source.evaluation_id.as_str(), source.batch_id.as_str()), ("ref-1", "batch-ref"));
+        assert!(source.reviewed_at.is_some());
+        assert!(get_dataset(&pool, &dataset.id).await.unwrap().columns.contains(&"expected".to_string()));
+
+        // Later evals against the row carry the source through to their details
+        let mut result = sample_result(JudgeVerdict::Pass, None, false);
+        result.dataset_row = Some(crate::dataset::DatasetRowRef {
+            dataset_id: dataset.id.clone(),
+            row_index: 1,
+            expected_source: Some(source.clone()),
+        });
+        save(&pool, "later", result).await;
+        let entry = get_evaluation(&pool, "later").await.unwrap();
+        assert_eq!(entry.dataset_row_index, Some(1));
+        assert_eq!(entry.expected_source, Some(source));
+    }
+
+    #[tokio::test]
+    async fn test_suite_runs_report_pass_rate() {
+        let pool = test_pool().await;
+        let config: EvalConfig = serde_json::from_value(serde_json::json!({
+            "model": "ollama:llama3",
+            "prompt": "What is 2+2?",
+            "expected": "4"
+        }))
+        .unwrap();
+
+        let suite = create_suite(&pool, "arithmetic".to_string(), None, &[config]).await.unwrap();
+        assert_eq!(get_suite(&pool, "arithmetic").await.unwrap().id, suite.id);
+        assert!(create_suite(&pool, "arithmetic".to_string(), None, &[]).await.is_err());
+
+        save(&pool, "r1-a", sample_result(JudgeVerdict::Pass, None, false)).await;
+        save(&pool, "r1-b", sample_result(JudgeVerdict::Fail, None, false)).await;
+        save(&pool, "r2-a", sample_result(JudgeVerdict::Pass, None, false)).await;
+        create_suite_run(&pool, &suite.id, "batch-1", &["r1-a".to_string(), "r1-b".to_string()])
+            .await
+            .unwrap();
+        create_suite_run(&pool, &suite.id, "batch-2", &["r2-a".to_string()]).await.unwrap();
+
+        let runs = get_suite_runs(&pool, &suite.id).await.unwrap();
+        assert_eq!(runs.len(), 2);
+        let first = runs.iter().find(|r| r.batch_id == "batch-1").unwrap();
+        assert_eq!((first.total, first.passed, first.failed), (2, 1, 1));
+        assert!((first.pass_rate - 0.5).abs() < f64::EPSILON);
+
+        delete_suite(&pool, &suite.id).await.unwrap();
+        assert!(get_suite_runs(&pool, &suite.id).await.unwrap().is_empty());
+        assert_eq!(get_all_evaluations(&pool).await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_upserted_suites_keep_their_id_across_updates() {
+        let pool = test_pool().await;
+        let config: EvalConfig = serde_json::from_value(serde_json::json!({ "model": "ollama:llama3", "prompt": "Hi" })).unwrap();
+
+        let created = upsert_suite(&pool, "smoke".to_string(), None, std::slice::from_ref(&config)).await.unwrap();
+        let updated = upsert_suite(&pool, "smoke".to_string(), Some("From the config file".to_string()), &[config.clone(), config])
+            .await
+            .unwrap();
+        assert_eq!(updated.id, created.id);
+        assert_eq!(updated.description.as_deref(), Some("From the config file"));
+        assert_eq!(get_suite(&pool, "smoke").await.unwrap().configs.len(), 2);
+        assert_eq!(get_all_suites(&pool).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_robustness_group_reads_variant_outcomes_from_evaluations() {
+        let pool = test_pool().await;
+        save(&pool, "base", sample_result(JudgeVerdict::Pass, None, false)).await;
+        save(&pool, "reworded", sample_result(JudgeVerdict::Fail, None, false)).await;
+
+        let group = RobustnessGroup {
+            id: "group-1".to_string(),
+            batch_id: "batch-1".to_string(),
+            model: "ollama:llama3".to_string(),
+            base_prompt: "What is 2+2?".to_string(),
+            expected: Some("4".to_string()),
+            paraphrase_model: Some("ollama:llama3".to_string()),
+            paraphrase_input_tokens: Some(40),
+            paraphrase_output_tokens: Some(20),
+            paraphrase_cost_usd: Some(0.001),
+            created_at: Utc::now().to_rfc3339(),
+        };
+        let variants: Vec<VariantOutcome> = [("base", VariantSource::Base), ("reworded", VariantSource::Paraphrase)]
+            .into_iter()
+            .enumerate()
+            .map(|(i, (eval_id, source))| VariantOutcome {
+                variant_index: i,
+                prompt: format!("phrasing {}", i),
+                source,
+                eval_id: eval_id.to_string(),
+                status: String::new(),
+                verdict: None,
+                flips_verdict: None,
+            })
+            .collect();
+        create_robustness_group(&pool, &group, &variants).await.unwrap();
+
+        let (stored, outcomes) = get_robustness_group(&pool, "group-1").await.unwrap();
+        assert_eq!(stored.paraphrase_output_tokens, Some(20));
+        assert_eq!(outcomes[1].source, VariantSource::Paraphrase);
+        assert_eq!(outcomes[1].verdict.as_deref(), Some("Fail"));
+
+        let report = crate::robustness::report(&stored.id, outcomes);
+        assert_eq!(report.passing, 1);
+        assert_eq!(report.flipped, vec![1]);
+        assert!(matches!(get_robustness_group(&pool, "missing").await, Err(sqlx::Error::RowNotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_urls_are_migrated_and_seeded_on_one_lasting_connection() {
+        let pool = init_db_with_url("sqlite::memory:").await.unwrap();
+        assert_eq!(get_active_judge_prompt(&pool).await.unwrap().version, 1);
+        assert_eq!(get_active_prompt_version(&pool).await.unwrap().version, 1);
+
+        test_support::evaluation("kept").save(&pool).await;
+        assert_eq!(get_evaluation(&pool, "kept").await.unwrap().model.as_deref(), Some("ollama:llama3"));
+    }
+
+    #[tokio::test]
+    async fn test_saved_evaluations_round_trip_with_and_without_a_result() {
+        use test_support::evaluation;
+        let pool = test_pool().await;
+
+        evaluation("judged")
+            .model("openai:gpt-4o")
+            .output("Paris")
+            .expected(Some("Paris"))
+            .verdict(JudgeVerdict::Fail)
+            .confidence(0.75)
+            .latency_ms(120, Some(30))
+            .tags(&["geography"])
+            .batch("batch-1")
+            .at("2026-10-01T09:00:00Z")
+            .save(&pool)
+            .await;
+        let judged = get_evaluation(&pool, "judged").await.unwrap();
+        assert_eq!(judged.status.as_deref(), Some("failed"));
+        assert_eq!((judged.model.as_deref(), judged.model_output.as_deref()), (Some("openai:gpt-4o"), Some("Paris")));
+        assert_eq!((judged.judge_verdict.as_deref(), judged.judge_confidence), (Some("Fail"), Some(0.75)));
+        assert_eq!((judged.latency_ms, judged.judge_latency_ms), (Some(120), Some(30)));
+        assert_eq!((judged.tags, judged.batch_id.as_deref()), (vec!["geography".to_string()], Some("batch-1")));
+        assert_eq!(judged.created_at, "2026-10-01T09:00:00Z");
+        assert!(judged.error_message.is_none());
+
+        evaluation("failed").failed("provider_error", "Provider returned 500").save(&pool).await;
+        let failed = get_evaluation(&pool, "failed").await.unwrap();
+        assert_eq!(failed.status.as_deref(), Some("error"));
+        assert_eq!(failed.error_message.as_deref(), Some("Provider returned 500"));
+        assert!(failed.model.is_none() && failed.prompt.is_none() && failed.model_output.is_none());
+        assert!(failed.judge_verdict.is_none() && failed.judge_model.is_none() && failed.latency_ms.is_none());
+        assert!(failed.tags.is_empty() && !failed.needs_review);
+        assert_eq!(get_all_evaluations(&pool).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_judge_prompt_activation_keeps_exactly_one_version_active() {
+        use test_support::judge_prompt;
+        let pool = test_pool().await;
+        let active = |pool: DbPool| async move {
+            let prompts = get_all_judge_prompts(&pool).await.unwrap();
+            prompts.into_iter().filter(|p| p.is_active).map(|p| p.version).collect::<Vec<_>>()
+        };
+
+        let strict = judge_prompt("Strict").active().save(&pool).await;
+        let lenient = judge_prompt("Lenient").save(&pool).await;
+        assert_eq!(active(pool.clone()).await, [strict.version]);
+
+        set_active_judge_prompt(&pool, lenient.version).await.unwrap();
+        assert_eq!(active(pool.clone()).await, [lenient.version]);
+
+        // A missing version rolls the whole switch back
+        assert!(matches!(set_active_judge_prompt(&pool, 999).await, Err(sqlx::Error::RowNotFound)));
+        assert_eq!(active(pool.clone()).await, [lenient.version]);
+
+        let newest = judge_prompt("Newest").active().save(&pool).await;
+        assert_eq!(active(pool.clone()).await, [newest.version]);
+        assert_eq!(get_active_judge_prompt(&pool).await.unwrap().name, "Newest");
+    }
+
+    #[tokio::test]
+    async fn test_prompt_version_stats_average_only_the_linked_evaluations() {
+        use test_support::{evaluation, prompt_version};
+        let pool = test_pool().await;
+        let terse = prompt_version("Terse").save(&pool).await;
+        let other = prompt_version("Verbose").save(&pool).await;
+
+        evaluation("pass").latency_ms(100, Some(10)).prompt_version(terse.version).save(&pool).await;
+        evaluation("fail").verdict(JudgeVerdict::Fail).latency_ms(200, Some(30)).prompt_version(terse.version).save(&pool).await;
+        evaluation("unjudged").unjudged().latency_ms(300, None).prompt_version(terse.version).save(&pool).await;
+        evaluation("elsewhere").latency_ms(9000, Some(900)).prompt_version(other.version).save(&pool).await;
+
+        let stats = get_prompt_version_stats(&pool, terse.version).await.unwrap();
+        assert_eq!((stats.total_evaluations, stats.passed), (3, 1));
+        assert_eq!(stats.avg_latency_ms, 200.0);
+        // The unjudged evaluation has no judge latency to average
+        assert_eq!(stats.avg_judge_latency_ms, 20.0);
+
+        let empty = prompt_version("Unused").save(&pool).await;
+        let stats = get_prompt_version_stats(&pool, empty.version).await.unwrap();
+        assert_eq!((stats.total_evaluations, stats.passed, stats.avg_latency_ms, stats.avg_judge_latency_ms), (0, 0, 0.0, 0.0));
+    }
+}
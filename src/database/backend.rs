@@ -0,0 +1,431 @@
+// src/database/backend.rs
+//! The databases `database` can run on, behind one set of query types so every query is
+//! written once. SQL is written for SQLite, with `?` and `?N` placeholders; for Postgres
+//! they are rewritten to `$N`.
+use std::marker::PhantomData;
+
+use sqlx::error::BoxDynError;
+use sqlx::sqlite::{Sqlite, SqliteArguments, SqliteQueryResult, SqliteRow};
+use sqlx::{Arguments, ColumnIndex, Decode, Encode, Row, SqlitePool, Type};
+#[cfg(feature = "postgres")]
+use sqlx::postgres::{PgArguments, PgPool, PgQueryResult, PgRow, Postgres};
+
+/// Which database a `DbPool` talks to, for the few queries that differ between them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Sqlite,
+    #[cfg(feature = "postgres")]
+    Postgres,
+}
+
+impl Backend {
+    /// A table source of the elements of the JSON array in `column`, named `json_each`
+    /// with the element in its `value` column.
+    pub fn json_array_elements(self, column: &str) -> String {
+        match self {
+            Backend::Sqlite => format!("json_each({})", column),
+            #[cfg(feature = "postgres")]
+            Backend::Postgres => format!("json_array_elements_text(CAST({} AS json)) AS json_each(value)", column),
+        }
+    }
+
+    /// The distinct values of `expr` in a group, joined with commas.
+    pub fn group_concat_distinct(self, expr: &str) -> String {
+        match self {
+            Backend::Sqlite => format!("GROUP_CONCAT(DISTINCT {})", expr),
+            #[cfg(feature = "postgres")]
+            Backend::Postgres => format!("string_agg(DISTINCT CAST({} AS TEXT), ',')", expr),
+        }
+    }
+
+    /// The Monday starting the week of an RFC 3339 `column`, as `YYYY-MM-DD`.
+    pub fn week_start(self, column: &str) -> String {
+        match self {
+            Backend::Sqlite => format!("date(substr({}, 1, 10), 'weekday 0', '-6 days')", column),
+            #[cfg(feature = "postgres")]
+            Backend::Postgres => format!("to_char(date_trunc('week', CAST(substr({}, 1, 10) AS DATE)), 'YYYY-MM-DD')", column),
+        }
+    }
+}
+
+/// A connection pool to one of the supported databases.
+#[derive(Debug, Clone)]
+pub enum DbPool {
+    Sqlite(SqlitePool),
+    #[cfg(feature = "postgres")]
+    Postgres(PgPool),
+}
+
+impl DbPool {
+    pub fn backend(&self) -> Backend {
+        match self {
+            DbPool::Sqlite(_) => Backend::Sqlite,
+            #[cfg(feature = "postgres")]
+            DbPool::Postgres(_) => Backend::Postgres,
+        }
+    }
+
+    pub async fn begin(&self) -> Result<Transaction, sqlx::Error> {
+        match self {
+            DbPool::Sqlite(pool) => Ok(Transaction::Sqlite(pool.begin().await?)),
+            #[cfg(feature = "postgres")]
+            DbPool::Postgres(pool) => Ok(Transaction::Postgres(pool.begin().await?)),
+        }
+    }
+
+    pub async fn close(&self) {
+        match self {
+            DbPool::Sqlite(pool) => pool.close().await,
+            #[cfg(feature = "postgres")]
+            DbPool::Postgres(pool) => pool.close().await,
+        }
+    }
+
+    /// The SQLite pool, for SQLite-only operations such as backups.
+    pub fn as_sqlite(&self) -> Option<&SqlitePool> {
+        match self {
+            DbPool::Sqlite(pool) => Some(pool),
+            #[cfg(feature = "postgres")]
+            DbPool::Postgres(_) => None,
+        }
+    }
+}
+
+/// An open transaction on a `DbPool`; rolled back when dropped without `commit`.
+pub enum Transaction {
+    Sqlite(sqlx::Transaction<'static, Sqlite>),
+    #[cfg(feature = "postgres")]
+    Postgres(sqlx::Transaction<'static, Postgres>),
+}
+
+impl Transaction {
+    pub fn backend(&self) -> Backend {
+        match self {
+            Transaction::Sqlite(_) => Backend::Sqlite,
+            #[cfg(feature = "postgres")]
+            Transaction::Postgres(_) => Backend::Postgres,
+        }
+    }
+
+    pub async fn commit(self) -> Result<(), sqlx::Error> {
+        match self {
+            Transaction::Sqlite(tx) => tx.commit().await,
+            #[cfg(feature = "postgres")]
+            Transaction::Postgres(tx) => tx.commit().await,
+        }
+    }
+}
+
+/// Where a query runs: on the pool, or inside a transaction.
+pub enum Executor<'e> {
+    Pool(&'e DbPool),
+    Transaction(&'e mut Transaction),
+}
+
+impl<'e> From<&'e DbPool> for Executor<'e> {
+    fn from(pool: &'e DbPool) -> Self {
+        Executor::Pool(pool)
+    }
+}
+
+impl<'e> From<&'e mut Transaction> for Executor<'e> {
+    fn from(tx: &'e mut Transaction) -> Self {
+        Executor::Transaction(tx)
+    }
+}
+
+/// A value that can be bound to a query on every backend.
+#[cfg(not(feature = "postgres"))]
+pub trait Encodable<'q>: Encode<'q, Sqlite> + Type<Sqlite> + Send + 'q {}
+#[cfg(not(feature = "postgres"))]
+impl<'q, T> Encodable<'q> for T where T: Encode<'q, Sqlite> + Type<Sqlite> + Send + 'q {}
+
+/// A value that can be bound to a query on every backend.
+#[cfg(feature = "postgres")]
+pub trait Encodable<'q>:
+    Encode<'q, Sqlite> + Type<Sqlite> + Encode<'q, Postgres> + Type<Postgres> + Send + 'q
+{
+}
+#[cfg(feature = "postgres")]
+impl<'q, T> Encodable<'q> for T where
+    T: Encode<'q, Sqlite> + Type<Sqlite> + Encode<'q, Postgres> + Type<Postgres> + Send + 'q
+{
+}
+
+/// A value that can be read from a row of every backend.
+#[cfg(not(feature = "postgres"))]
+pub trait Decodable<'r>: Decode<'r, Sqlite> + Type<Sqlite> {}
+#[cfg(not(feature = "postgres"))]
+impl<'r, T> Decodable<'r> for T where T: Decode<'r, Sqlite> + Type<Sqlite> {}
+
+/// A value that can be read from a row of every backend.
+#[cfg(feature = "postgres")]
+pub trait Decodable<'r>: Decode<'r, Sqlite> + Type<Sqlite> + Decode<'r, Postgres> + Type<Postgres> {}
+#[cfg(feature = "postgres")]
+impl<'r, T> Decodable<'r> for T where
+    T: Decode<'r, Sqlite> + Type<Sqlite> + Decode<'r, Postgres> + Type<Postgres>
+{
+}
+
+/// A column index (position or name) into a row of every backend.
+#[cfg(not(feature = "postgres"))]
+pub trait RowIndex: ColumnIndex<SqliteRow> {}
+#[cfg(not(feature = "postgres"))]
+impl<I: ColumnIndex<SqliteRow>> RowIndex for I {}
+
+/// A column index (position or name) into a row of every backend.
+#[cfg(feature = "postgres")]
+pub trait RowIndex: ColumnIndex<SqliteRow> + ColumnIndex<PgRow> {}
+#[cfg(feature = "postgres")]
+impl<I: ColumnIndex<SqliteRow> + ColumnIndex<PgRow>> RowIndex for I {}
+
+/// A bound value, added to the arguments of whichever backend the query runs on.
+trait Argument<'q>: Send + 'q {
+    fn add_to_sqlite(self: Box<Self>, arguments: &mut SqliteArguments<'q>) -> Result<(), BoxDynError>;
+    #[cfg(feature = "postgres")]
+    fn add_to_postgres(self: Box<Self>, arguments: &mut PgArguments) -> Result<(), BoxDynError>;
+}
+
+impl<'q, T: Encodable<'q>> Argument<'q> for T {
+    fn add_to_sqlite(self: Box<Self>, arguments: &mut SqliteArguments<'q>) -> Result<(), BoxDynError> {
+        arguments.add(*self)
+    }
+
+    #[cfg(feature = "postgres")]
+    fn add_to_postgres(self: Box<Self>, arguments: &mut PgArguments) -> Result<(), BoxDynError> {
+        arguments.add(*self)
+    }
+}
+
+/// A row of a query result.
+pub enum DbRow {
+    Sqlite(SqliteRow),
+    #[cfg(feature = "postgres")]
+    Postgres(PgRow),
+}
+
+impl DbRow {
+    /// The value of a column. Panics, like `sqlx::Row::get`, if it cannot be decoded as `T`.
+    pub fn get<'r, T: Decodable<'r>, I: RowIndex>(&'r self, index: I) -> T {
+        match self {
+            DbRow::Sqlite(row) => row.get(index),
+            #[cfg(feature = "postgres")]
+            DbRow::Postgres(row) => row.get(index),
+        }
+    }
+
+    pub fn try_get<'r, T: Decodable<'r>, I: RowIndex>(&'r self, index: I) -> Result<T, sqlx::Error> {
+        match self {
+            DbRow::Sqlite(row) => row.try_get(index),
+            #[cfg(feature = "postgres")]
+            DbRow::Postgres(row) => row.try_get(index),
+        }
+    }
+}
+
+/// What an `execute` changed.
+pub struct QueryResult {
+    rows_affected: u64,
+}
+
+impl QueryResult {
+    pub fn rows_affected(&self) -> u64 {
+        self.rows_affected
+    }
+}
+
+/// A query built like `sqlx::query`, that runs on any `DbPool`.
+pub struct Query<'q> {
+    sql: &'q str,
+    arguments: Vec<Box<dyn Argument<'q>>>,
+}
+
+/// Starts a query; see `Query`.
+pub fn query(sql: &str) -> Query<'_> {
+    Query { sql, arguments: Vec::new() }
+}
+
+/// Converts what a backend's sqlx query returns into this module's types.
+trait IntoDb<T> {
+    fn into_db(self) -> T;
+}
+
+impl IntoDb<QueryResult> for SqliteQueryResult {
+    fn into_db(self) -> QueryResult {
+        QueryResult { rows_affected: self.rows_affected() }
+    }
+}
+
+impl IntoDb<DbRow> for SqliteRow {
+    fn into_db(self) -> DbRow {
+        DbRow::Sqlite(self)
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl IntoDb<QueryResult> for PgQueryResult {
+    fn into_db(self) -> QueryResult {
+        QueryResult { rows_affected: self.rows_affected() }
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl IntoDb<DbRow> for PgRow {
+    fn into_db(self) -> DbRow {
+        DbRow::Postgres(self)
+    }
+}
+
+impl<R: IntoDb<DbRow>> IntoDb<Vec<DbRow>> for Vec<R> {
+    fn into_db(self) -> Vec<DbRow> {
+        self.into_iter().map(IntoDb::into_db).collect()
+    }
+}
+
+impl<R: IntoDb<DbRow>> IntoDb<Option<DbRow>> for Option<R> {
+    fn into_db(self) -> Option<DbRow> {
+        self.map(IntoDb::into_db)
+    }
+}
+
+/// Runs `$query` with the sqlx method `$method` on `$executor`, on whichever backend that is.
+macro_rules! run {
+    ($query:ident, $executor:ident, $method:ident) => {{
+        let sql = $query.sql;
+        match $executor.into() {
+            Executor::Pool(DbPool::Sqlite(pool)) => {
+                let arguments = $query.sqlite_arguments()?;
+                Ok(sqlx::query_with(sql, arguments).$method(pool).await?.into_db())
+            }
+            Executor::Transaction(Transaction::Sqlite(tx)) => {
+                let arguments = $query.sqlite_arguments()?;
+                Ok(sqlx::query_with(sql, arguments).$method(&mut **tx).await?.into_db())
+            }
+            #[cfg(feature = "postgres")]
+            Executor::Pool(DbPool::Postgres(pool)) => {
+                let sql = postgres_placeholders(sql);
+                let arguments = $query.postgres_arguments()?;
+                Ok(sqlx::query_with(&sql, arguments).$method(pool).await?.into_db())
+            }
+            #[cfg(feature = "postgres")]
+            Executor::Transaction(Transaction::Postgres(tx)) => {
+                let sql = postgres_placeholders(sql);
+                let arguments = $query.postgres_arguments()?;
+                Ok(sqlx::query_with(&sql, arguments).$method(&mut **tx).await?.into_db())
+            }
+        }
+    }};
+}
+
+impl<'q> Query<'q> {
+    pub fn bind<T: Encodable<'q>>(mut self, value: T) -> Self {
+        self.arguments.push(Box::new(value));
+        self
+    }
+
+    fn sqlite_arguments(self) -> Result<SqliteArguments<'q>, sqlx::Error> {
+        let mut arguments = SqliteArguments::default();
+        for argument in self.arguments {
+            argument.add_to_sqlite(&mut arguments).map_err(sqlx::Error::Encode)?;
+        }
+        Ok(arguments)
+    }
+
+    #[cfg(feature = "postgres")]
+    fn postgres_arguments(self) -> Result<PgArguments, sqlx::Error> {
+        let mut arguments = PgArguments::default();
+        for argument in self.arguments {
+            argument.add_to_postgres(&mut arguments).map_err(sqlx::Error::Encode)?;
+        }
+        Ok(arguments)
+    }
+
+    pub async fn execute<'e>(self, executor: impl Into<Executor<'e>>) -> Result<QueryResult, sqlx::Error> {
+        run!(self, executor, execute)
+    }
+
+    pub async fn fetch_all<'e>(self, executor: impl Into<Executor<'e>>) -> Result<Vec<DbRow>, sqlx::Error> {
+        run!(self, executor, fetch_all)
+    }
+
+    pub async fn fetch_one<'e>(self, executor: impl Into<Executor<'e>>) -> Result<DbRow, sqlx::Error> {
+        run!(self, executor, fetch_one)
+    }
+
+    pub async fn fetch_optional<'e>(self, executor: impl Into<Executor<'e>>) -> Result<Option<DbRow>, sqlx::Error> {
+        run!(self, executor, fetch_optional)
+    }
+}
+
+/// `sql` with SQLite's `?` and `?N` placeholders replaced by Postgres' `$N`. A bare `?` is
+/// numbered one past the largest number used before it, as SQLite numbers it. Quoted
+/// strings and identifiers are left alone.
+#[cfg(feature = "postgres")]
+pub(crate) fn postgres_placeholders(sql: &str) -> String {
+    let mut rewritten = String::with_capacity(sql.len() + 16);
+    let mut chars = sql.chars().peekable();
+    let mut quote = None;
+    let mut largest = 0;
+    while let Some(c) = chars.next() {
+        match (c, quote) {
+            ('\'' | '"', None) => quote = Some(c),
+            (c, Some(open)) if c == open => quote = None,
+            ('?', None) => {
+                let mut digits = String::new();
+                while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                    digits.push(digit);
+                }
+                let number = digits.parse().unwrap_or(largest + 1);
+                largest = largest.max(number);
+                rewritten.push('$');
+                rewritten.push_str(&number.to_string());
+                continue;
+            }
+            _ => {}
+        }
+        rewritten.push(c);
+    }
+    rewritten
+}
+
+/// A query built like `sqlx::query_scalar`, returning its first column as `T`.
+pub struct QueryScalar<'q, T> {
+    query: Query<'q>,
+    output: PhantomData<T>,
+}
+
+/// Starts a scalar query; see `QueryScalar`.
+pub fn query_scalar<T>(sql: &str) -> QueryScalar<'_, T> {
+    QueryScalar { query: query(sql), output: PhantomData }
+}
+
+impl<'q, T> QueryScalar<'q, T>
+where
+    T: for<'r> Decodable<'r> + Send + Unpin,
+{
+    pub fn bind<A: Encodable<'q>>(self, value: A) -> Self {
+        QueryScalar { query: self.query.bind(value), output: PhantomData }
+    }
+
+    pub async fn fetch_one<'e>(self, executor: impl Into<Executor<'e>>) -> Result<T, sqlx::Error> {
+        self.query.fetch_one(executor).await?.try_get(0)
+    }
+
+    pub async fn fetch_optional<'e>(self, executor: impl Into<Executor<'e>>) -> Result<Option<T>, sqlx::Error> {
+        self.query.fetch_optional(executor).await?.map(|row| row.try_get(0)).transpose()
+    }
+}
+
+#[cfg(all(test, feature = "postgres"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_placeholders_are_numbered_like_sqlite_numbers_them() {
+        assert_eq!(postgres_placeholders("SELECT ? , ?"), "SELECT $1 , $2");
+        assert_eq!(postgres_placeholders("WHERE (?1 IS NULL OR a = ?1) AND b = ?2"), "WHERE ($1 IS NULL OR a = $1) AND b = $2");
+        assert_eq!(postgres_placeholders("VALUES (?3, ?)"), "VALUES ($3, $4)");
+        assert_eq!(postgres_placeholders("SELECT '?', \"a?\" FROM t WHERE x = ?"), "SELECT '?', \"a?\" FROM t WHERE x = $1");
+        assert_eq!(postgres_placeholders("SELECT 'it''s ?' WHERE x = ?"), "SELECT 'it''s ?' WHERE x = $1");
+    }
+}
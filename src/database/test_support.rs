@@ -0,0 +1,322 @@
+// src/database/test_support.rs
+//! Fresh databases and fixture builders for tests of code that reads or writes the database.
+//! Built for the crate's own tests, and with the `test-support` feature for tests outside it.
+//!
+//! ```ignore
+//! let pool = fresh_test_pool().await;
+//! let prompt = prompt_version("Terse").active().save(&pool).await;
+//! evaluation("eval-1").verdict(JudgeVerdict::Fail).prompt_version(prompt.version).save(&pool).await;
+//! ```
+use chrono::Utc;
+
+use super::{
+    connect_memory, create_judge_prompt, create_prompt_version, link_evaluation_to_prompt, run_migrations,
+    save_evaluation, DbPool, JudgePrompt, PromptVersion,
+};
+use crate::models::{ApiErrorResponse, ApiResponse, EvalResult};
+use crate::runner::{self, JudgeResult, JudgeVerdict};
+
+/// A fresh, migrated database: in-memory SQLite, or, with the `postgres` feature and
+/// `TEST_PG_URL` set, a schema of its own in that Postgres database. The defaults are not
+/// seeded beyond what the migrations insert.
+pub async fn fresh_test_pool() -> DbPool {
+    #[cfg(feature = "postgres")]
+    if let Some(url) = std::env::var("TEST_PG_URL").ok().filter(|url| !url.trim().is_empty()) {
+        return test_postgres_pool(&url).await;
+    }
+
+    let pool = DbPool::Sqlite(connect_memory("sqlite::memory:").await.expect("in-memory database"));
+    run_migrations(&pool).await.expect("migrations");
+    pool
+}
+
+/// A pool whose connections all use a new schema of the database at `url`. The schemas are
+/// left behind, named `evaluate_test_*`, for inspection.
+#[cfg(feature = "postgres")]
+async fn test_postgres_pool(url: &str) -> DbPool {
+    use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+    use std::str::FromStr;
+
+    let schema = format!("evaluate_test_{}", uuid::Uuid::new_v4().simple());
+    let options = PgConnectOptions::from_str(url).expect("TEST_PG_URL");
+    let admin = PgPoolOptions::new().max_connections(1).connect_with(options.clone()).await.expect("TEST_PG_URL database");
+    sqlx::query(&format!("CREATE SCHEMA {}", schema)).execute(&admin).await.expect("test schema");
+    admin.close().await;
+
+    let pool = PgPoolOptions::new()
+        .max_connections(4)
+        .connect_with(options.options([("search_path", schema.as_str())]))
+        .await
+        .expect("TEST_PG_URL database");
+    let pool = DbPool::Postgres(pool);
+    run_migrations(&pool).await.expect("migrations");
+    pool
+}
+
+/// An evaluation to save: `ollama:llama3` answering "4" to "What is 2+2?", judged `Pass` by
+/// `ollama:llama3` with judge prompt version 1, now.
+pub fn evaluation(id: &str) -> EvaluationFixture {
+    EvaluationFixture {
+        id: id.to_string(),
+        result: runner::EvalResult {
+            id: id.to_string(),
+            model: "ollama:llama3".to_string(),
+            prompt: "What is 2+2?".to_string(),
+            model_output: "4".to_string(),
+            parsed_output: None,
+            parse_method: None,
+            output_diff: None,
+            expected: Some("4".to_string()),
+            judge_result: Some(JudgeResult {
+                judge_model: "ollama:llama3".to_string(),
+                verdict: JudgeVerdict::Pass,
+                reasoning: None,
+                confidence: None,
+                raw_response: None,
+                uncertain_reason: None,
+            }),
+            timestamp: Utc::now().to_rfc3339(),
+            latency_ms: 10,
+            judge_latency_ms: Some(5),
+            token_usage: None,
+            judge_token_usage: None,
+            total_latency_ms: 15,
+            wait_ms: None,
+            cost_usd: None,
+            judge_cost_usd: None,
+            judge_prompt_version: Some(1),
+            prompt_version: None,
+            prompt_template: None,
+            judge_prompt_arm: None,
+            judge_batch_size: None,
+            early_abort: None,
+            finish_reason: None,
+            needs_review: false,
+            truncation: None,
+            tags: Vec::new(),
+            criteria: None,
+            metadata: None,
+            trace: None,
+            failure_classification: None,
+            dataset_row: None,
+            chain_step: None,
+            provider_override: None,
+            full_output_chars: None,
+            cache_hit: false,
+            resolved_model: None,
+            response_format: None,
+            reasoning: None,
+            images: Vec::new(),
+            judge_prompt_rendered: None,
+            model_alias: None,
+            judge_params: None,
+            judge_error: None,
+            self_consistency: None,
+        },
+        error: None,
+        batch_id: None,
+        prompt_version: None,
+    }
+}
+
+/// Builder of an evaluation saved with `save_evaluation`; see `evaluation`.
+pub struct EvaluationFixture {
+    id: String,
+    result: runner::EvalResult,
+    error: Option<ApiErrorResponse>,
+    batch_id: Option<String>,
+    prompt_version: Option<i64>,
+}
+
+impl EvaluationFixture {
+    pub fn model(mut self, model: &str) -> Self {
+        self.result.model = model.to_string();
+        self
+    }
+
+    pub fn output(mut self, output: &str) -> Self {
+        self.result.model_output = output.to_string();
+        self
+    }
+
+    pub fn expected(mut self, expected: Option<&str>) -> Self {
+        self.result.expected = expected.map(str::to_string);
+        self
+    }
+
+    pub fn verdict(mut self, verdict: JudgeVerdict) -> Self {
+        if let Some(judge) = self.result.judge_result.as_mut() {
+            judge.verdict = verdict;
+        }
+        self
+    }
+
+    pub fn confidence(mut self, confidence: f32) -> Self {
+        if let Some(judge) = self.result.judge_result.as_mut() {
+            judge.confidence = Some(confidence);
+        }
+        self
+    }
+
+    /// Stored without a judge verdict, as evals without an expected output are.
+    pub fn unjudged(mut self) -> Self {
+        self.result.judge_result = None;
+        self.result.judge_latency_ms = None;
+        self
+    }
+
+    pub fn needs_review(mut self) -> Self {
+        self.result.needs_review = true;
+        self
+    }
+
+    /// Created at `timestamp`, an RFC 3339 timestamp.
+    pub fn at(mut self, timestamp: &str) -> Self {
+        self.result.timestamp = timestamp.to_string();
+        self
+    }
+
+    pub fn latency_ms(mut self, latency_ms: u64, judge_latency_ms: Option<u64>) -> Self {
+        self.result.latency_ms = latency_ms;
+        self.result.judge_latency_ms = judge_latency_ms;
+        self
+    }
+
+    pub fn tags(mut self, tags: &[&str]) -> Self {
+        self.result.tags = tags.iter().map(|tag| tag.to_string()).collect();
+        self
+    }
+
+    pub fn judge_prompt_version(mut self, version: Option<i64>) -> Self {
+        self.result.judge_prompt_version = version;
+        self
+    }
+
+    pub fn batch(mut self, batch_id: &str) -> Self {
+        self.batch_id = Some(batch_id.to_string());
+        self
+    }
+
+    /// Linked to prompt version `version` once saved.
+    pub fn prompt_version(mut self, version: i64) -> Self {
+        self.prompt_version = Some(version);
+        self
+    }
+
+    /// Saved as a failed eval, the `Error` variant, with `code` and `message`.
+    pub fn failed(mut self, code: &str, message: &str) -> Self {
+        self.error = Some(ApiErrorResponse { code: code.to_string(), message: message.to_string(), details: None });
+        self
+    }
+
+    /// The response `save` stores.
+    pub fn response(self) -> ApiResponse {
+        let (status, result) = match self.error {
+            Some(error) => ("error".to_string(), EvalResult::Error(error)),
+            None => (runner::eval_status(&self.result).to_string(), EvalResult::Success(self.result)),
+        };
+        ApiResponse { id: self.id, status, result, batch_id: self.batch_id, config_hash: None, preset: None }
+    }
+
+    /// Saves the evaluation, linking it to its prompt version if one was given, and returns its id.
+    pub async fn save(self, pool: &DbPool) -> String {
+        let prompt_version = self.prompt_version;
+        let response = self.response();
+        save_evaluation(pool, &response).await.expect("saved evaluation");
+        if let Some(version) = prompt_version {
+            link_evaluation_to_prompt(pool, &response.id, version).await.expect("linked evaluation");
+        }
+        response.id
+    }
+}
+
+/// A judge prompt to create, with a template rendering the expected and actual outputs.
+pub fn judge_prompt(name: &str) -> JudgePromptFixture {
+    JudgePromptFixture {
+        name: name.to_string(),
+        template: format!("{}: {{{{expected}}}} vs {{{{actual}}}}", name),
+        description: None,
+        active: false,
+    }
+}
+
+/// Builder of a judge prompt created with `create_judge_prompt`; see `judge_prompt`.
+pub struct JudgePromptFixture {
+    name: String,
+    template: String,
+    description: Option<String>,
+    active: bool,
+}
+
+impl JudgePromptFixture {
+    pub fn template(mut self, template: &str) -> Self {
+        self.template = template.to_string();
+        self
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    /// Made the active version when created.
+    pub fn active(mut self) -> Self {
+        self.active = true;
+        self
+    }
+
+    pub async fn save(self, pool: &DbPool) -> JudgePrompt {
+        create_judge_prompt(pool, self.name, self.template, self.description, self.active)
+            .await
+            .expect("created judge prompt")
+    }
+}
+
+/// A prompt version to create, passing the prompt through unchanged.
+pub fn prompt_version(name: &str) -> PromptVersionFixture {
+    PromptVersionFixture {
+        name: name.to_string(),
+        template: "{{prompt}}".to_string(),
+        description: None,
+        tags: None,
+        active: false,
+    }
+}
+
+/// Builder of a prompt version created with `create_prompt_version`; see `prompt_version`.
+pub struct PromptVersionFixture {
+    name: String,
+    template: String,
+    description: Option<String>,
+    tags: Option<Vec<String>>,
+    active: bool,
+}
+
+impl PromptVersionFixture {
+    pub fn template(mut self, template: &str) -> Self {
+        self.template = template.to_string();
+        self
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    pub fn tags(mut self, tags: &[&str]) -> Self {
+        self.tags = Some(tags.iter().map(|tag| tag.to_string()).collect());
+        self
+    }
+
+    /// Made the active version when created.
+    pub fn active(mut self) -> Self {
+        self.active = true;
+        self
+    }
+
+    pub async fn save(self, pool: &DbPool) -> PromptVersion {
+        create_prompt_version(pool, self.name, self.template, self.description, self.tags, self.active)
+            .await
+            .expect("created prompt version")
+    }
+}
@@ -0,0 +1,495 @@
+// src/dataset.rs
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value as JsonValue};
+use std::collections::BTreeMap;
+
+use crate::config::EvalConfig;
+
+/// One dataset row: template variables, plus an optional `expected` column.
+pub type DatasetRow = Map<String, JsonValue>;
+
+/// Column that supplies a row's expected output.
+pub const EXPECTED_COLUMN: &str = "expected";
+
+/// Human verdict that approves a reference output for adoption.
+const APPROVED_VERDICT: &str = "Pass";
+
+/// Where a row's expected value came from when it was adopted from a model's output rather
+/// than written by a person.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExpectedSource {
+    /// The model that generated the value.
+    pub model: String,
+    pub batch_id: String,
+    pub evaluation_id: String,
+    /// When a reviewer approved the output.
+    pub reviewed_at: Option<String>,
+    pub adopted_at: String,
+}
+
+/// A dataset row as stored.
+#[derive(Debug, Clone, Serialize)]
+pub struct StoredRow {
+    pub row_index: i64,
+    pub data: DatasetRow,
+    /// Set when the row's `expected` value was adopted from a model's output.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_source: Option<ExpectedSource>,
+}
+
+/// The dataset row an eval was expanded from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DatasetRowRef {
+    pub dataset_id: String,
+    pub row_index: i64,
+    /// Where the eval's expected output came from, when it was adopted from a model's output.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_source: Option<ExpectedSource>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DatasetFormat {
+    Jsonl,
+    Csv,
+}
+
+impl DatasetFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DatasetFormat::Jsonl => "jsonl",
+            DatasetFormat::Csv => "csv",
+        }
+    }
+
+    /// Picks the format from the content type or file name, falling back to sniffing:
+    /// content whose first line is a JSON object is JSONL, anything else CSV.
+    pub fn detect(content_type: Option<&str>, file_name: Option<&str>, text: &str) -> Self {
+        let content_type = content_type.unwrap_or_default().to_lowercase();
+        let file_name = file_name.unwrap_or_default().to_lowercase();
+        if content_type.contains("csv") || file_name.ends_with(".csv") {
+            DatasetFormat::Csv
+        } else if content_type.contains("ndjson")
+            || content_type.contains("jsonl")
+            || file_name.ends_with(".jsonl")
+            || file_name.ends_with(".ndjson")
+            || text.trim_start().starts_with('{')
+        {
+            DatasetFormat::Jsonl
+        } else {
+            DatasetFormat::Csv
+        }
+    }
+}
+
+/// Parses an uploaded dataset into rows. Errors name the offending line.
+pub fn parse_rows(format: DatasetFormat, text: &str) -> Result<Vec<DatasetRow>, String> {
+    let rows = match format {
+        DatasetFormat::Jsonl => parse_jsonl(text)?,
+        DatasetFormat::Csv => parse_csv(text)?,
+    };
+    if rows.is_empty() {
+        return Err("Dataset contains no rows".to_string());
+    }
+    Ok(rows)
+}
+
+fn parse_jsonl(text: &str) -> Result<Vec<DatasetRow>, String> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| match serde_json::from_str::<JsonValue>(line) {
+            Ok(JsonValue::Object(row)) => Ok(row),
+            Ok(_) => Err(format!("Line {}: expected a JSON object", i + 1)),
+            Err(e) => Err(format!("Line {}: {}", i + 1, e)),
+        })
+        .collect()
+}
+
+/// Parses CSV with a header row. Fields may be quoted, with `""` for a literal quote and
+/// line breaks allowed inside quotes. Every value is kept as a string.
+fn parse_csv(text: &str) -> Result<Vec<DatasetRow>, String> {
+    let mut records = csv_records(text)?.into_iter();
+    let Some((_, header)) = records.next() else {
+        return Ok(Vec::new());
+    };
+    let header: Vec<String> = header.into_iter().map(|h| h.trim().to_string()).collect();
+    if header.iter().any(|h| h.is_empty()) {
+        return Err("Line 1: header contains an empty column name".to_string());
+    }
+
+    records
+        .map(|(line, fields)| {
+            if fields.len() != header.len() {
+                return Err(format!(
+                    "Line {}: expected {} fields, found {}",
+                    line,
+                    header.len(),
+                    fields.len()
+                ));
+            }
+            Ok(header.iter().cloned().zip(fields.into_iter().map(JsonValue::String)).collect())
+        })
+        .collect()
+}
+
+/// Splits CSV text into records, each tagged with the line it starts on. Blank lines are skipped.
+fn csv_records(text: &str) -> Result<Vec<(usize, Vec<String>)>, String> {
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    in_quotes = false;
+                }
+            }
+            '"' if field.is_empty() => in_quotes = true,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes && chars.peek() == Some(&'\n') => {}
+            '\n' if !in_quotes => {
+                fields.push(std::mem::take(&mut field));
+                if !(fields.len() == 1 && fields[0].is_empty()) {
+                    records.push((record_line, std::mem::take(&mut fields)));
+                }
+                fields.clear();
+                line += 1;
+                record_line = line;
+            }
+            c => {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+        }
+    }
+
+    if in_quotes {
+        return Err(format!("Line {}: unterminated quoted field", record_line));
+    }
+    if !field.is_empty() || !fields.is_empty() {
+        fields.push(field);
+        records.push((record_line, fields));
+    }
+    Ok(records)
+}
+
+/// Pulls the uploaded file out of a `multipart/form-data` body: the first part with a
+/// file name, or the first part if none has one. Returns the file name and contents.
+pub fn extract_multipart_file(content_type: &str, body: &[u8]) -> Option<(Option<String>, Vec<u8>)> {
+    let boundary = content_type
+        .split(';')
+        .find_map(|param| param.trim().strip_prefix("boundary="))?
+        .trim_matches('"');
+    let delimiter = format!("--{}", boundary).into_bytes();
+
+    let mut parts = Vec::new();
+    let mut rest = &body[find(body, &delimiter)? + delimiter.len()..];
+    while !rest.starts_with(b"--") {
+        let end = find(rest, &delimiter)?;
+        let part = rest[..end].strip_prefix(b"\r\n").unwrap_or(&rest[..end]);
+        let part = part.strip_suffix(b"\r\n").unwrap_or(part);
+        let header_end = find(part, b"\r\n\r\n")?;
+        let headers = String::from_utf8_lossy(&part[..header_end]).to_string();
+        let file_name = headers
+            .split(';')
+            .find_map(|param| param.trim().strip_prefix("filename="))
+            .map(|name| name.lines().next().unwrap_or_default().trim_matches('"').to_string());
+        parts.push((file_name, part[header_end + 4..].to_vec()));
+        rest = &rest[end + delimiter.len()..];
+    }
+
+    let index = parts.iter().position(|(name, _)| name.is_some()).unwrap_or(0);
+    (index < parts.len()).then(|| parts.swap_remove(index))
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// A dataset row that could not be turned into an eval.
+#[derive(Debug, Clone, Serialize)]
+pub struct RowError {
+    pub row_index: i64,
+    pub error: String,
+}
+
+/// Expands rows × models into eval configs. `template` supplies the prompt template and judge
+/// settings; each row becomes the metadata its placeholders render from. The row's `expected`
+/// column is used unless `template.expected` is set. Rows missing a template variable are
+/// reported instead of expanded.
+pub fn expand(dataset_id: &str, rows: &[StoredRow], template: &EvalConfig, models: &[String]) -> (Vec<EvalConfig>, Vec<RowError>) {
+    let mut variables = crate::template::variables(&template.prompt);
+    if let Some(expected) = &template.expected {
+        variables.extend(crate::template::variables(expected));
+    }
+
+    let mut configs = Vec::with_capacity(rows.len() * models.len());
+    let mut errors = Vec::new();
+    for StoredRow { row_index, data: row, expected_source } in rows {
+        let missing: Vec<&str> = variables
+            .iter()
+            .filter(|v| row.get(v.as_str()).is_none_or(JsonValue::is_null))
+            .map(String::as_str)
+            .collect();
+        if !missing.is_empty() {
+            errors.push(RowError {
+                row_index: *row_index,
+                error: format!("Missing template variables: {}", missing.join(", ")),
+            });
+            continue;
+        }
+
+        let expected = template.expected.clone().or_else(|| row_expected(row));
+        let dataset_row = DatasetRowRef {
+            dataset_id: dataset_id.to_string(),
+            row_index: *row_index,
+            expected_source: expected_source.clone().filter(|_| template.expected.is_none() && expected.is_some()),
+        };
+        for model in models {
+            let mut config = template.clone();
+            config.model = model.clone();
+            config.expected = expected.clone();
+            config.metadata = Some(JsonValue::Object(row.clone()));
+            config.dataset_row = Some(dataset_row.clone());
+            configs.push(config);
+        }
+    }
+    (configs, errors)
+}
+
+fn row_expected(row: &DatasetRow) -> Option<String> {
+    match row.get(EXPECTED_COLUMN) {
+        Some(JsonValue::String(s)) => Some(s.clone()),
+        Some(JsonValue::Null) | None => None,
+        Some(other) => Some(other.to_string()),
+    }
+}
+
+/// The output of a reference run for one dataset row, with its human review.
+#[derive(Debug, Clone)]
+pub struct ReferenceOutput {
+    pub evaluation_id: String,
+    pub batch_id: String,
+    pub model: String,
+    pub row_index: i64,
+    pub output: String,
+    pub human_verdict: Option<String>,
+    pub reviewed_at: Option<String>,
+}
+
+/// An expected value to write into a row.
+#[derive(Debug, Clone, Serialize)]
+pub struct Adoption {
+    pub row_index: i64,
+    pub expected: String,
+    pub expected_source: ExpectedSource,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct AdoptionReport {
+    pub adopted: Vec<Adoption>,
+    /// Evaluations not reviewed yet.
+    pub pending_review: Vec<String>,
+    /// Evaluations a reviewer did not approve.
+    pub rejected: Vec<String>,
+    /// Rows with differing approved outputs, left unchanged.
+    pub conflicting_rows: Vec<i64>,
+    /// Rows whose expected value was written by a person, left unchanged without `overwrite`.
+    pub kept_rows: Vec<i64>,
+}
+
+/// Decides which reviewed reference outputs become their rows' expected values. Only outputs
+/// a reviewer passed are adopted, and a row gets one only when its approved outputs agree.
+/// Values adopted before are replaced; values written by a person only with `overwrite`.
+pub fn plan_adoption(rows: &[StoredRow], outputs: &[ReferenceOutput], overwrite: bool, adopted_at: &str) -> AdoptionReport {
+    let mut report = AdoptionReport::default();
+    let mut approved: BTreeMap<i64, Vec<&ReferenceOutput>> = BTreeMap::new();
+    for output in outputs {
+        match output.human_verdict.as_deref() {
+            Some(APPROVED_VERDICT) => approved.entry(output.row_index).or_default().push(output),
+            Some(_) => report.rejected.push(output.evaluation_id.clone()),
+            None => report.pending_review.push(output.evaluation_id.clone()),
+        }
+    }
+
+    for (row_index, candidates) in approved {
+        let Some(row) = rows.iter().find(|row| row.row_index == row_index) else {
+            continue;
+        };
+        let chosen = candidates[0];
+        if candidates.iter().any(|candidate| candidate.output != chosen.output) {
+            report.conflicting_rows.push(row_index);
+            continue;
+        }
+        if row.expected_source.is_none() && row_expected(&row.data).is_some() && !overwrite {
+            report.kept_rows.push(row_index);
+            continue;
+        }
+        report.adopted.push(Adoption {
+            row_index,
+            expected: chosen.output.clone(),
+            expected_source: ExpectedSource {
+                model: chosen.model.clone(),
+                batch_id: chosen.batch_id.clone(),
+                evaluation_id: chosen.evaluation_id.clone(),
+                reviewed_at: chosen.reviewed_at.clone(),
+                adopted_at: adopted_at.to_string(),
+            },
+        });
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn stored_row(row_index: i64, data: JsonValue, expected_source: Option<ExpectedSource>) -> StoredRow {
+        StoredRow { row_index, data: data.as_object().unwrap().clone(), expected_source }
+    }
+
+    fn source(evaluation_id: &str) -> ExpectedSource {
+        ExpectedSource {
+            model: "openai:gpt-4o".to_string(),
+            batch_id: "batch-0".to_string(),
+            evaluation_id: evaluation_id.to_string(),
+            reviewed_at: None,
+            adopted_at: "2026-01-01T00:00:00+00:00".to_string(),
+        }
+    }
+
+    fn output(evaluation_id: &str, row_index: i64, output: &str, verdict: Option<&str>) -> ReferenceOutput {
+        ReferenceOutput {
+            evaluation_id: evaluation_id.to_string(),
+            batch_id: "batch-1".to_string(),
+            model: "openai:gpt-4o".to_string(),
+            row_index,
+            output: output.to_string(),
+            human_verdict: verdict.map(str::to_string),
+            reviewed_at: verdict.map(|_| "2026-01-02T00:00:00+00:00".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_parse_csv_handles_quotes_and_blank_lines() {
+        let text = "question,expected\r\n\"What is 2+2?\",4\n\n\"Say \"\"hi\"\",\nplease\",hi\n";
+        let rows = parse_rows(DatasetFormat::Csv, text).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0]["question"], "What is 2+2?");
+        assert_eq!(rows[1]["question"], "Say \"hi\",\nplease");
+        assert_eq!(rows[1]["expected"], "hi");
+
+        let err = parse_rows(DatasetFormat::Csv, "a,b\n1,2\n3\n").unwrap_err();
+        assert_eq!(err, "Line 3: expected 2 fields, found 1");
+    }
+
+    #[test]
+    fn test_parse_jsonl_and_detect() {
+        let text = "{\"q\": \"one\", \"expected\": 1}\n\n{\"q\": \"two\"}\n";
+        assert_eq!(DatasetFormat::detect(None, None, text), DatasetFormat::Jsonl);
+        assert_eq!(DatasetFormat::detect(Some("text/csv"), None, text), DatasetFormat::Csv);
+        assert_eq!(parse_rows(DatasetFormat::Jsonl, text).unwrap().len(), 2);
+        assert!(parse_rows(DatasetFormat::Jsonl, "{\"q\": 1}\n[1]\n").unwrap_err().starts_with("Line 2"));
+    }
+
+    #[test]
+    fn test_extract_multipart_file() {
+        let body = b"--XyZ\r\nContent-Disposition: form-data; name=\"name\"\r\n\r\nmy set\r\n--XyZ\r\nContent-Disposition: form-data; name=\"file\"; filename=\"rows.csv\"\r\nContent-Type: text/csv\r\n\r\nq\r\nhello\r\n--XyZ--\r\n";
+        let (name, contents) = extract_multipart_file("multipart/form-data; boundary=XyZ", body).unwrap();
+        assert_eq!(name.as_deref(), Some("rows.csv"));
+        assert_eq!(contents, b"q\r\nhello");
+    }
+
+    #[test]
+    fn test_expand_reports_rows_missing_variables() {
+        let rows: Vec<StoredRow> = [
+            json!({"city": "Paris", "expected": "France"}),
+            json!({"town": "Lyon"}),
+            json!({"city": "Rome"}),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, v)| stored_row(i as i64, v, None))
+        .collect();
+        let template: EvalConfig = serde_json::from_value(json!({
+            "model": "",
+            "prompt": "Which country is {{city}} in?",
+            "judge_model": "ollama:llama3"
+        }))
+        .unwrap();
+        let models = vec!["ollama:llama3".to_string(), "openai:gpt-4o".to_string()];
+
+        let (configs, errors) = expand("ds-1", &rows, &template, &models);
+        assert_eq!(configs.len(), 4);
+        assert_eq!(configs[0].expected.as_deref(), Some("France"));
+        assert_eq!(configs[1].model, "openai:gpt-4o");
+        assert_eq!(configs[2].expected, None);
+        assert_eq!(configs[2].dataset_row.as_ref().map(|row| row.row_index), Some(2));
+        assert_eq!(configs[2].render().unwrap().prompt, "Which country is Rome in?");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].row_index, 1);
+        assert!(errors[0].error.contains("city"));
+    }
+
+    #[test]
+    fn test_expand_carries_the_source_of_adopted_expected_values() {
+        let rows = vec![stored_row(0, json!({"q": "2+2?", "expected": "4"}), Some(source("eval-0")))];
+        let template: EvalConfig = serde_json::from_value(json!({ "model": "", "prompt": "{{q}}" })).unwrap();
+        let models = vec!["ollama:llama3".to_string()];
+
+        let (configs, _) = expand("ds-1", &rows, &template, &models);
+        let row = configs[0].dataset_row.as_ref().unwrap();
+        assert_eq!(row.dataset_id, "ds-1");
+        assert_eq!(row.expected_source, Some(source("eval-0")));
+
+        // An explicit expected template replaces the adopted value, and so its source
+        let template = EvalConfig { expected: Some("{{q}}".to_string()), ..template };
+        let (configs, _) = expand("ds-1", &rows, &template, &models);
+        assert_eq!(configs[0].dataset_row.as_ref().unwrap().expected_source, None);
+    }
+
+    #[test]
+    fn test_plan_adoption_takes_only_agreeing_approved_outputs() {
+        let rows = vec![
+            stored_row(0, json!({"q": "a"}), None),
+            stored_row(1, json!({"q": "b"}), None),
+            stored_row(2, json!({"q": "c", "expected": "written"}), None),
+            stored_row(3, json!({"q": "d", "expected": "old"}), Some(source("eval-0"))),
+            stored_row(4, json!({"q": "e"}), None),
+        ];
+        let outputs = vec![
+            output("e0", 0, "A", Some("Pass")),
+            output("e1a", 1, "B", Some("Pass")),
+            output("e1b", 1, "b!", Some("Pass")),
+            output("e2", 2, "C", Some("Pass")),
+            output("e3", 3, "D", Some("Pass")),
+            output("e4", 4, "E", Some("Fail")),
+            output("e5", 4, "E?", None),
+        ];
+
+        let report = plan_adoption(&rows, &outputs, false, "now");
+        let adopted: Vec<(i64, &str)> = report.adopted.iter().map(|a| (a.row_index, a.expected.as_str())).collect();
+        assert_eq!(adopted, [(0, "A"), (3, "D")]);
+        assert_eq!(report.adopted[0].expected_source.evaluation_id, "e0");
+        assert_eq!(report.adopted[0].expected_source.reviewed_at.as_deref(), Some("2026-01-02T00:00:00+00:00"));
+        assert_eq!(report.conflicting_rows, [1]);
+        assert_eq!(report.kept_rows, [2]);
+        assert_eq!(report.rejected, ["e4"]);
+        assert_eq!(report.pending_review, ["e5"]);
+
+        let report = plan_adoption(&rows, &outputs, true, "now");
+        assert!(report.adopted.iter().any(|a| a.row_index == 2 && a.expected == "C"));
+    }
+}
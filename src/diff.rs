@@ -0,0 +1,215 @@
+// src/diff.rs
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+/// Judge model recorded for verdicts the diff rule gave without calling a judge.
+pub const JSON_DIFF_JUDGE: &str = "json-diff";
+
+/// How `parsed_output` is compared with an `expected` value that is a JSON object or array.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DiffRule {
+    /// Decide the verdict from the diff instead of calling the judge: Pass when every
+    /// difference is under an ignored path, Fail otherwise.
+    #[serde(default)]
+    pub verdict: bool,
+    /// Paths whose differences, their own and those of anything under them, do not count,
+    /// e.g. `$.id` or `$.items[*].created_at`. `*` stands for any key or index.
+    #[serde(default)]
+    pub ignored_paths: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffKind {
+    /// A key of an expected object is missing from the output.
+    MissingKey,
+    /// The output has a key the expected object does not.
+    ExtraKey,
+    /// Both sides have a value of the same type, but different values.
+    ValueMismatch,
+    /// The sides have values of different JSON types, e.g. a string where a number was expected.
+    TypeMismatch,
+    /// Both sides are arrays of different lengths; the common prefix is compared item by item.
+    ArrayLength,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Difference {
+    /// Where the values differ, e.g. `$.items[2].name`.
+    pub path: String,
+    pub kind: DiffKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected: Option<JsonValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actual: Option<JsonValue>,
+    /// Set when the path is under one of the rule's `ignored_paths`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub ignored: bool,
+}
+
+/// Diff of `parsed_output` against `expected`, as stored in `EvalResult::output_diff`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutputDiff {
+    /// Whether every difference is ignored.
+    pub matches: bool,
+    pub differences: Vec<Difference>,
+}
+
+impl DiffRule {
+    /// Diffs `parsed_output` against `expected` when `expected` is a JSON object or array;
+    /// other expected values are left to the match rule and the judge.
+    pub fn check(&self, parsed_output: &JsonValue, expected: &str) -> Option<OutputDiff> {
+        let expected = serde_json::from_str::<JsonValue>(expected)
+            .ok()
+            .filter(|expected| expected.is_object() || expected.is_array())?;
+        let ignored: Vec<Regex> = self.ignored_paths.iter().filter_map(|path| path_pattern(path)).collect();
+        let mut differences = diff(&expected, parsed_output);
+        for difference in &mut differences {
+            difference.ignored = ignored.iter().any(|pattern| pattern.is_match(&difference.path));
+        }
+        Some(OutputDiff { matches: differences.iter().all(|d| d.ignored), differences })
+    }
+}
+
+/// Whether `path` is a JSON path `ignored_paths` accepts: `$` followed by `.key`, `[index]`,
+/// `.*` or `[*]` segments.
+pub fn is_valid_path(path: &str) -> bool {
+    path_pattern(path).is_some()
+}
+
+/// Pattern matching the differences at and under `path`.
+fn path_pattern(path: &str) -> Option<Regex> {
+    let segments = Regex::new(r"^\$(?:\.(?:\*|[^.\[\]]+)|\[(?:\*|\d+)\])*$").ok()?;
+    if !segments.is_match(path) {
+        return None;
+    }
+    let pattern = regex::escape(path).replace(r"\[\*\]", r"\[\d+\]").replace(r"\.\*", r"\.[^.\[]+");
+    Regex::new(&format!(r"^{}(?:$|[.\[])", pattern)).ok()
+}
+
+/// Every difference between `expected` and `actual`, with object keys visited in sorted order.
+pub fn diff(expected: &JsonValue, actual: &JsonValue) -> Vec<Difference> {
+    let mut differences = Vec::new();
+    walk("$", expected, actual, &mut differences);
+    differences
+}
+
+fn walk(path: &str, expected: &JsonValue, actual: &JsonValue, out: &mut Vec<Difference>) {
+    let difference = |kind, expected: Option<&JsonValue>, actual: Option<&JsonValue>| Difference {
+        path: path.to_string(),
+        kind,
+        expected: expected.cloned(),
+        actual: actual.cloned(),
+        ignored: false,
+    };
+    match (expected, actual) {
+        (JsonValue::Object(expected), JsonValue::Object(actual)) => {
+            for (key, value) in expected {
+                let child = format!("{}.{}", path, key);
+                match actual.get(key) {
+                    Some(actual) => walk(&child, value, actual, out),
+                    None => out.push(Difference { path: child, ..difference(DiffKind::MissingKey, Some(value), None) }),
+                }
+            }
+            for (key, value) in actual.iter().filter(|(key, _)| !expected.contains_key(*key)) {
+                out.push(Difference { path: format!("{}.{}", path, key), ..difference(DiffKind::ExtraKey, None, Some(value)) });
+            }
+        }
+        (JsonValue::Array(expected_items), JsonValue::Array(actual_items)) => {
+            if expected_items.len() != actual_items.len() {
+                out.push(difference(
+                    DiffKind::ArrayLength,
+                    Some(&JsonValue::from(expected_items.len())),
+                    Some(&JsonValue::from(actual_items.len())),
+                ));
+            }
+            for (i, (expected, actual)) in expected_items.iter().zip(actual_items).enumerate() {
+                walk(&format!("{}[{}]", path, i), expected, actual, out);
+            }
+        }
+        (JsonValue::Number(e), JsonValue::Number(a)) if e.as_f64() != a.as_f64() => {
+            out.push(difference(DiffKind::ValueMismatch, Some(expected), Some(actual)));
+        }
+        (JsonValue::Number(_), JsonValue::Number(_)) => {}
+        _ if std::mem::discriminant(expected) != std::mem::discriminant(actual) => {
+            out.push(difference(DiffKind::TypeMismatch, Some(expected), Some(actual)));
+        }
+        _ if expected != actual => out.push(difference(DiffKind::ValueMismatch, Some(expected), Some(actual))),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn summary(differences: &[Difference]) -> Vec<(&str, DiffKind)> {
+        differences.iter().map(|d| (d.path.as_str(), d.kind)).collect()
+    }
+
+    #[test]
+    fn test_nested_objects_report_missing_extra_and_changed_keys() {
+        let expected = json!({ "name": "Ada", "address": { "city": "London", "zip": "N1" }, "age": 36 });
+        let actual = json!({ "name": "Ada", "address": { "city": "Paris", "country": "FR" }, "age": 36.0 });
+        let differences = diff(&expected, &actual);
+        assert_eq!(summary(&differences), vec![
+            ("$.address.city", DiffKind::ValueMismatch),
+            ("$.address.zip", DiffKind::MissingKey),
+            ("$.address.country", DiffKind::ExtraKey),
+        ]);
+        assert_eq!(differences[0].expected, Some(json!("London")));
+        assert_eq!(differences[0].actual, Some(json!("Paris")));
+        assert_eq!(differences[2].expected, None);
+        assert!(diff(&expected, &expected).is_empty());
+    }
+
+    #[test]
+    fn test_arrays_report_length_and_item_differences() {
+        let expected = json!({ "items": [{ "id": 1 }, { "id": 2 }, { "id": 3 }] });
+        let actual = json!({ "items": [{ "id": 1 }, { "id": 5 }] });
+        let differences = diff(&expected, &actual);
+        assert_eq!(summary(&differences), vec![
+            ("$.items", DiffKind::ArrayLength),
+            ("$.items[1].id", DiffKind::ValueMismatch),
+        ]);
+        assert_eq!((differences[0].expected.clone(), differences[0].actual.clone()), (Some(json!(3)), Some(json!(2))));
+        assert_eq!(summary(&diff(&json!([1, 2]), &json!([2, 1]))), vec![
+            ("$[0]", DiffKind::ValueMismatch),
+            ("$[1]", DiffKind::ValueMismatch),
+        ]);
+    }
+
+    #[test]
+    fn test_values_of_different_types_are_type_mismatches() {
+        let expected = json!({ "count": 4, "ok": true, "tags": ["a"], "note": null });
+        let actual = json!({ "count": "4", "ok": "yes", "tags": { "0": "a" }, "note": "" });
+        assert_eq!(summary(&diff(&expected, &actual)), vec![
+            ("$.count", DiffKind::TypeMismatch),
+            ("$.note", DiffKind::TypeMismatch),
+            ("$.ok", DiffKind::TypeMismatch),
+            ("$.tags", DiffKind::TypeMismatch),
+        ]);
+    }
+
+    #[test]
+    fn test_ignored_paths_cover_their_descendants_and_wildcards() {
+        let rule = DiffRule { verdict: true, ignored_paths: vec!["$.id".to_string(), "$.items[*].at".to_string()] };
+        let parsed = json!({ "id": 9, "items": [{ "v": 1, "at": "noon" }], "idx": 2 });
+        let diff = rule.check(&parsed, r#"{"id": 1, "items": [{"v": 1, "at": "dawn"}], "idx": 2}"#).unwrap();
+        assert!(diff.matches);
+        assert!(diff.differences.iter().all(|d| d.ignored));
+        assert_eq!(diff.differences.len(), 2);
+
+        let diff = rule.check(&json!({ "id": 1, "items": [], "idx": 3 }), r#"{"id": 1, "items": [], "idx": 2}"#).unwrap();
+        assert!(!diff.matches);
+        assert_eq!(summary(&diff.differences), vec![("$.idx", DiffKind::ValueMismatch)]);
+
+        assert_eq!(rule.check(&json!({ "answer": 4 }), "4"), None);
+        assert_eq!(rule.check(&json!({ "answer": 4 }), "not json"), None);
+        assert!(is_valid_path("$.a[0].b") && is_valid_path("$") && is_valid_path("$.*[*]"));
+        assert!(!is_valid_path("a.b") && !is_valid_path("$.a[x]") && !is_valid_path("$..a"));
+    }
+}
@@ -0,0 +1,206 @@
+// src/drift.rs
+//! Week-over-week drift of a model's output length and pass rate, from the weekly aggregates
+//! of `database::get_weekly_output_tokens`.
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::database::WeeklyOutputTokens;
+
+/// Calendar weeks, the current one included, drift is looked for in.
+pub const LOOKBACK_WEEKS: u32 = 4;
+
+/// How far a model may move from one week to the next before it is flagged.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DriftThresholds {
+    /// Relative change of the mean output tokens, e.g. `0.2` for ±20%.
+    pub output_tokens: f64,
+    /// Absolute change of the pass rate, e.g. `0.1` for ±10 points.
+    pub pass_rate: f64,
+    /// Evaluations a model needs in both weeks to be compared.
+    pub min_evaluations: i64,
+}
+
+impl Default for DriftThresholds {
+    fn default() -> Self {
+        DriftThresholds { output_tokens: 0.2, pass_rate: 0.1, min_evaluations: 10 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftMetric {
+    OutputTokens,
+    PassRate,
+}
+
+/// One metric of a model in the previous and the current week.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MetricShift {
+    pub metric: DriftMetric,
+    pub previous: f64,
+    pub current: f64,
+    /// Relative for `output_tokens`, absolute for `pass_rate`.
+    pub change: f64,
+    pub threshold: f64,
+    pub drifted: bool,
+}
+
+/// A model's latest week against the week before it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModelDrift {
+    pub model: String,
+    /// Monday of the latest week with enough evaluations, `YYYY-MM-DD`.
+    pub week: String,
+    pub previous_week: String,
+    pub evaluations: i64,
+    pub previous_evaluations: i64,
+    /// The metrics both weeks have; the pass rate needs judged evaluations in each.
+    pub shifts: Vec<MetricShift>,
+    /// Whether any metric moved past its threshold.
+    pub drifted: bool,
+}
+
+impl ModelDrift {
+    /// The metrics that moved past their threshold.
+    pub fn drifted_metrics(&self) -> impl Iterator<Item = &MetricShift> {
+        self.shifts.iter().filter(|shift| shift.drifted)
+    }
+}
+
+fn shift(metric: DriftMetric, previous: f64, current: f64, change: f64, threshold: f64) -> MetricShift {
+    MetricShift { metric, previous, current, change, threshold, drifted: change.abs() > threshold }
+}
+
+/// Compares `current` with `previous`, the same model's week before. Output tokens are compared
+/// relative to the previous mean, so they are left out when that mean is zero.
+pub fn compare(previous: &WeeklyOutputTokens, current: &WeeklyOutputTokens, thresholds: &DriftThresholds) -> ModelDrift {
+    let mut shifts = Vec::new();
+    if let (Some(before), Some(after)) = (previous.mean_output_tokens, current.mean_output_tokens)
+        && before > 0.0
+    {
+        shifts.push(shift(DriftMetric::OutputTokens, before, after, (after - before) / before, thresholds.output_tokens));
+    }
+    if let (Some(before), Some(after)) = (previous.pass_rate, current.pass_rate) {
+        shifts.push(shift(DriftMetric::PassRate, before, after, after - before, thresholds.pass_rate));
+    }
+    ModelDrift {
+        model: current.model.clone(),
+        week: current.week.clone(),
+        previous_week: previous.week.clone(),
+        evaluations: current.evaluations,
+        previous_evaluations: previous.evaluations,
+        drifted: shifts.iter().any(|shift| shift.drifted),
+        shifts,
+    }
+}
+
+/// Compares each model's latest week with at least `min_evaluations` evaluations, so a week
+/// that has only just started does not count yet, with the calendar week before it. Models
+/// whose week before has fewer evaluations are left out.
+pub fn detect(weeks: &[WeeklyOutputTokens], thresholds: &DriftThresholds) -> Vec<ModelDrift> {
+    let mut by_model: BTreeMap<&str, Vec<&WeeklyOutputTokens>> = BTreeMap::new();
+    for week in weeks.iter().filter(|week| week.evaluations >= thresholds.min_evaluations) {
+        by_model.entry(&week.model).or_default().push(week);
+    }
+
+    by_model
+        .into_values()
+        .filter_map(|model_weeks| {
+            let current = *model_weeks.iter().max_by(|a, b| a.week.cmp(&b.week))?;
+            let previous_week = NaiveDate::parse_from_str(&current.week, "%Y-%m-%d").ok()? - Duration::weeks(1);
+            let previous_week = previous_week.format("%Y-%m-%d").to_string();
+            let previous = *model_weeks.iter().find(|week| week.week == previous_week)?;
+            Some(compare(previous, current, thresholds))
+        })
+        .collect()
+}
+
+/// Monday of the week `weeks - 1` weeks before the one `today` is in, `YYYY-MM-DD`: the
+/// start of a lookback of `weeks` calendar weeks, the current one included.
+pub fn lookback_start(today: NaiveDate, weeks: u32) -> String {
+    let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+    (monday - Duration::weeks(weeks.saturating_sub(1) as i64)).format("%Y-%m-%d").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn week(model: &str, week: &str, evaluations: i64, mean_output_tokens: Option<f64>, pass_rate: Option<f64>) -> WeeklyOutputTokens {
+        WeeklyOutputTokens {
+            model: model.to_string(),
+            week: week.to_string(),
+            evaluations,
+            mean_output_tokens,
+            max_output_tokens: None,
+            judged_evaluations: if pass_rate.is_some() { evaluations } else { 0 },
+            pass_rate,
+            histogram: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_compare_flags_only_the_metrics_past_their_threshold() {
+        let thresholds = DriftThresholds::default();
+        let drift = compare(
+            &week("ollama:llama3", "2026-10-05", 20, Some(200.0), Some(0.8)),
+            &week("ollama:llama3", "2026-10-12", 25, Some(300.0), Some(0.75)),
+            &thresholds,
+        );
+        assert!(drift.drifted);
+        assert_eq!((drift.week.as_str(), drift.previous_week.as_str()), ("2026-10-12", "2026-10-05"));
+        assert_eq!(drift.shifts[0], MetricShift { metric: DriftMetric::OutputTokens, previous: 200.0, current: 300.0, change: 0.5, threshold: 0.2, drifted: true });
+        assert_eq!(drift.shifts[1].metric, DriftMetric::PassRate);
+        assert!((drift.shifts[1].change + 0.05).abs() < 1e-9);
+        assert!(!drift.shifts[1].drifted);
+        assert_eq!(drift.drifted_metrics().count(), 1);
+
+        // Shorter outputs and a pass-rate drop count as much as growth
+        let drift = compare(
+            &week("ollama:llama3", "2026-10-05", 20, Some(200.0), Some(0.9)),
+            &week("ollama:llama3", "2026-10-12", 20, Some(190.0), Some(0.7)),
+            &thresholds,
+        );
+        let drifted: Vec<_> = drift.drifted_metrics().map(|shift| shift.metric).collect();
+        assert_eq!(drifted, [DriftMetric::PassRate]);
+
+        // Unjudged weeks and a zero mean leave their metric out
+        let drift = compare(&week("m", "2026-10-05", 20, Some(0.0), None), &week("m", "2026-10-12", 20, Some(50.0), Some(1.0)), &thresholds);
+        assert!(drift.shifts.is_empty() && !drift.drifted);
+    }
+
+    #[test]
+    fn test_detect_compares_each_models_latest_week_with_the_one_before() {
+        let thresholds = DriftThresholds { min_evaluations: 5, ..DriftThresholds::default() };
+        let weeks = [
+            week("openai:gpt-4o", "2026-09-28", 30, Some(100.0), Some(0.9)),
+            week("openai:gpt-4o", "2026-10-05", 30, Some(100.0), Some(0.9)),
+            week("openai:gpt-4o", "2026-10-12", 30, Some(130.0), Some(0.9)),
+            // Just started, too few evaluations to stand for the week yet
+            week("openai:gpt-4o", "2026-10-19", 2, Some(900.0), Some(0.0)),
+            // A gap week: nothing to compare the latest week with
+            week("ollama:llama3", "2026-09-28", 30, Some(100.0), Some(0.9)),
+            week("ollama:llama3", "2026-10-12", 30, Some(500.0), Some(0.1)),
+            // Too few evaluations
+            week("ollama:mistral", "2026-10-05", 4, Some(100.0), None),
+            week("ollama:mistral", "2026-10-12", 30, Some(900.0), None),
+        ];
+        let drifts = detect(&weeks, &thresholds);
+        assert_eq!(drifts.len(), 1);
+        assert_eq!((drifts[0].model.as_str(), drifts[0].week.as_str(), drifts[0].previous_week.as_str()), ("openai:gpt-4o", "2026-10-12", "2026-10-05"));
+        assert!(drifts[0].drifted);
+
+        let lenient = DriftThresholds { output_tokens: 0.5, ..thresholds };
+        assert!(!detect(&weeks, &lenient)[0].drifted);
+        assert!(detect(&[], &thresholds).is_empty());
+    }
+
+    #[test]
+    fn test_lookback_starts_on_a_monday() {
+        let sunday = NaiveDate::from_ymd_opt(2026, 10, 18).unwrap();
+        assert_eq!(lookback_start(sunday, 1), "2026-10-12");
+        assert_eq!(lookback_start(sunday, 4), "2026-09-21");
+        assert_eq!(lookback_start(NaiveDate::from_ymd_opt(2026, 10, 12).unwrap(), 2), "2026-10-05");
+    }
+}
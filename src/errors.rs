@@ -1,6 +1,11 @@
 // src/errors.rs
+use actix_web::{error::JsonPayloadError, http::StatusCode, HttpResponse, ResponseError};
 use thiserror::Error;
 
+use crate::budget::BudgetCap;
+use crate::models::{ApiErrorResponse, FieldError};
+use crate::redact;
+
 #[derive(Error, Debug)]
 #[allow(dead_code)]
 pub enum EvalError {
@@ -28,6 +33,9 @@ pub enum EvalError {
     #[error("Received empty text response from model")]
     EmptyResponse,
 
+    #[error("Provider blocked the content ({reason})")]
+    ContentBlocked { reason: String, categories: Vec<String> },
+
     #[error("Model '{model}' failed to respond")]
     ModelFailure { model: String },
 
@@ -43,6 +51,248 @@ pub enum EvalError {
 
     #[error("Provider '{0}' not found")]
     ProviderNotFound(String),
+
+    #[error("Config {index}: provider '{provider}' (model '{model}') is not configured")]
+    ProviderNotConfigured { index: usize, provider: String, model: String },
+
+    #[error("Not run: the batch used up its {cap} budget")]
+    SkippedBudget { cap: BudgetCap },
+}
+
+pub type Result<T> = std::result::Result<T, EvalError>;
+
+impl EvalError {
+    /// The stable code and HTTP status an API client gets for this error.
+    pub fn code(&self) -> (&'static str, StatusCode) {
+        match self {
+            EvalError::FileRead(_) => ("io_error", StatusCode::INTERNAL_SERVER_ERROR),
+            EvalError::TomlParse(_) | EvalError::Config(_) => ("invalid_config", StatusCode::BAD_REQUEST),
+            EvalError::JsonParse(_) => ("invalid_json", StatusCode::BAD_REQUEST),
+            EvalError::Request(e) if e.is_timeout() => ("timeout", StatusCode::GATEWAY_TIMEOUT),
+            EvalError::Request(_) => ("provider_unreachable", StatusCode::BAD_GATEWAY),
+            EvalError::ApiError { status: 429, .. } => ("rate_limited", StatusCode::TOO_MANY_REQUESTS),
+            EvalError::ApiError { .. } | EvalError::ApiResponse(_) => ("provider_error", StatusCode::BAD_GATEWAY),
+            EvalError::UnexpectedResponse(_) => ("unexpected_response", StatusCode::BAD_GATEWAY),
+            EvalError::EmptyResponse => ("empty_response", StatusCode::BAD_GATEWAY),
+            EvalError::ContentBlocked { .. } => ("content_blocked", StatusCode::UNPROCESSABLE_ENTITY),
+            EvalError::ModelFailure { .. } => ("model_failure", StatusCode::BAD_GATEWAY),
+            EvalError::JudgeFailure { .. } => ("judge_failure", StatusCode::BAD_GATEWAY),
+            EvalError::ProviderNotFound(_) | EvalError::ProviderNotConfigured { .. } => {
+                ("provider_not_found", StatusCode::BAD_REQUEST)
+            }
+            EvalError::SkippedBudget { .. } => ("skipped_budget", StatusCode::TOO_MANY_REQUESTS),
+        }
+    }
+
+    /// Machine-readable context for the error, where there is any.
+    fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            EvalError::ApiError { status, .. } => Some(serde_json::json!({ "provider_status": status })),
+            EvalError::ContentBlocked { reason, categories } => {
+                Some(serde_json::json!({ "reason": reason, "categories": categories }))
+            }
+            EvalError::ModelFailure { model } => Some(serde_json::json!({ "model": model })),
+            EvalError::JudgeFailure { model, source } => {
+                Some(serde_json::json!({ "model": model, "cause": source.code().0 }))
+            }
+            EvalError::ProviderNotFound(provider) => Some(serde_json::json!({ "provider": provider })),
+            EvalError::ProviderNotConfigured { index, provider, model } => {
+                Some(serde_json::json!({ "index": index, "provider": provider, "model": model }))
+            }
+            EvalError::SkippedBudget { cap } => Some(serde_json::json!({ "cap": cap })),
+            _ => None,
+        }
+    }
+
+    /// Status an eval that ended in this error is recorded with: `blocked` when the provider
+    /// refused the content, `skipped_budget` when its batch ran out of budget before it
+    /// started, otherwise `error`.
+    pub fn status(&self) -> &'static str {
+        match self {
+            EvalError::ContentBlocked { .. } => "blocked",
+            EvalError::SkippedBudget { .. } => "skipped_budget",
+            _ => "error",
+        }
+    }
+
+    /// The error as answered to a client, with secrets masked; see `redact::redact`.
+    pub fn to_response(&self) -> ApiErrorResponse {
+        ApiErrorResponse { code: self.code().0.to_string(), message: redact::redact(&self.to_string()), details: self.details() }
+    }
+
+    /// The error with every one of `secrets` in its text masked, and without the URL of a
+    /// failed request, for provider keys that must not reach logs, responses or the database.
+    pub fn redacted(self, secrets: &[String]) -> Self {
+        let scrub = |text: String| redact::mask(&text, secrets);
+        match self {
+            EvalError::Request(e) => EvalError::Request(e.without_url()),
+            EvalError::ApiError { status, body } => EvalError::ApiError { status, body: scrub(body) },
+            EvalError::ApiResponse(message) => EvalError::ApiResponse(scrub(message)),
+            EvalError::UnexpectedResponse(message) => EvalError::UnexpectedResponse(scrub(message)),
+            EvalError::Config(message) => EvalError::Config(scrub(message)),
+            EvalError::ContentBlocked { reason, categories } => EvalError::ContentBlocked { reason: scrub(reason), categories },
+            EvalError::JudgeFailure { model, source } => EvalError::JudgeFailure { model, source: Box::new(source.redacted(secrets)) },
+            other => other,
+        }
+    }
+}
+
+impl ResponseError for EvalError {
+    fn status_code(&self) -> StatusCode {
+        self.code().1
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(self.to_response())
+    }
+}
+
+/// An error answered to an API client as an `ApiErrorResponse`, with a stable `code` to
+/// branch on. Handlers return `Result<HttpResponse, ApiError>`; `EvalError`s convert into it.
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub code: &'static str,
+    pub message: String,
+    pub details: Option<serde_json::Value>,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        ApiError { status, code, message: message.into(), details: None }
+    }
+
+    pub fn with_details(mut self, details: serde_json::Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    /// The request is malformed or breaks a rule of the endpoint.
+    pub fn validation(message: impl Into<String>) -> Self {
+        ApiError::new(StatusCode::BAD_REQUEST, "validation_failed", message)
+    }
+
+    /// Fields of the request break its rules; answered as 422 listing each one under
+    /// `details.errors`.
+    pub fn invalid_fields(errors: Vec<FieldError>) -> Self {
+        let summary: Vec<String> = errors.iter().map(|e| format!("{}: {}", e.field, e.message)).collect();
+        ApiError::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "validation_failed",
+            format!("Invalid request: {}", summary.join("; ")),
+        )
+        .with_details(serde_json::json!({ "errors": errors }))
+    }
+
+    /// A JSON body that could not be read. Syntax errors are `invalid_json` with the line and
+    /// column; bodies of the wrong shape are `validation_failed`, naming the field when serde does;
+    /// bodies over the limit are `payload_too_large`, naming it.
+    pub fn json_body(err: &JsonPayloadError) -> Self {
+        match err {
+            JsonPayloadError::Deserialize(e) if e.is_data() => {
+                let mut error = ApiError::invalid_fields(vec![FieldError::from_serde(e)]);
+                if let Some(details) = error.details.as_mut() {
+                    details["line"] = e.line().into();
+                    details["column"] = e.column().into();
+                }
+                error
+            }
+            JsonPayloadError::Deserialize(e) => {
+                ApiError::new(StatusCode::BAD_REQUEST, "invalid_json", format!("Malformed JSON body: {}", e))
+                    .with_details(serde_json::json!({ "line": e.line(), "column": e.column() }))
+            }
+            JsonPayloadError::OverflowKnownLength { limit, .. } | JsonPayloadError::Overflow { limit } => {
+                ApiError::new(
+                    StatusCode::PAYLOAD_TOO_LARGE,
+                    "payload_too_large",
+                    format!("Request body is larger than the limit of {} bytes; raise MAX_BODY_BYTES to accept it", limit),
+                )
+                .with_details(serde_json::json!({ "limit_bytes": limit }))
+            }
+            other => ApiError::new(other.status_code(), "validation_failed", other.to_string()),
+        }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        ApiError::new(StatusCode::NOT_FOUND, "not_found", message)
+    }
+
+    /// The request clashes with the current state, e.g. a name already taken.
+    pub fn conflict(message: impl Into<String>) -> Self {
+        ApiError::new(StatusCode::CONFLICT, "conflict", message)
+    }
+
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        ApiError::new(StatusCode::UNAUTHORIZED, "unauthorized", message)
+    }
+
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        ApiError::new(StatusCode::FORBIDDEN, "forbidden", message)
+    }
+
+    /// The resource existed but has expired or been revoked.
+    pub fn gone(message: impl Into<String>) -> Self {
+        ApiError::new(StatusCode::GONE, "gone", message)
+    }
+
+    /// A model names a provider the server has no configuration for.
+    pub fn provider_not_configured(provider: &str, model: &str) -> Self {
+        ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "provider_not_found",
+            format!("Provider '{}' (model '{}') is not configured", provider, model),
+        )
+        .with_details(serde_json::json!({ "provider": provider, "model": model }))
+    }
+
+    pub fn rate_limited(message: impl Into<String>) -> Self {
+        ApiError::new(StatusCode::TOO_MANY_REQUESTS, "rate_limited", message)
+    }
+
+    /// The server started without a database.
+    pub fn db_unavailable() -> Self {
+        ApiError::new(StatusCode::SERVICE_UNAVAILABLE, "db_unavailable", "Database not initialized")
+    }
+
+    /// A failed database call. The cause is logged; the client only gets `message`.
+    pub fn database(message: impl Into<String>, cause: impl std::fmt::Display) -> Self {
+        let message = message.into();
+        tracing::error!("{}: {}", message, cause);
+        ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "database_error", message)
+    }
+
+    /// Any other server-side failure. The cause is logged; the client only gets `message`.
+    pub fn internal(message: impl Into<String>, cause: impl std::fmt::Display) -> Self {
+        let message = message.into();
+        tracing::error!("{}: {}", message, cause);
+        ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "internal_error", message)
+    }
+
+    /// The error as answered to a client, with secrets masked; see `redact::redact`.
+    pub fn to_response(&self) -> ApiErrorResponse {
+        ApiErrorResponse { code: self.code.to_string(), message: redact::redact(&self.message), details: self.details.clone() }
+    }
 }
 
-pub type Result<T> = std::result::Result<T, EvalError>;
\ No newline at end of file
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
+
+impl From<EvalError> for ApiError {
+    fn from(err: EvalError) -> Self {
+        let (code, status) = err.code();
+        ApiError { status, code, message: err.to_string(), details: err.details() }
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        self.status
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status).json(self.to_response())
+    }
+}
\ No newline at end of file
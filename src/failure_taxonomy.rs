@@ -0,0 +1,280 @@
+// src/failure_taxonomy.rs
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use crate::database::DbPool;
+
+use crate::config::AppConfig;
+use crate::errors::{EvalError, Result};
+use crate::providers::Generation;
+use crate::runner::{self, EvalResult};
+
+/// Classifier recorded for categories set or corrected during human review.
+pub const HUMAN_CLASSIFIER: &str = "human";
+
+/// Classifier recorded for categories the judge gave in its verdict call.
+pub const JUDGE_CLASSIFIER: &str = "judge";
+
+/// Most categories a taxonomy may hold.
+pub const MAX_CATEGORIES: usize = 50;
+
+/// Taxonomy seeded into an empty database, and used when there is no database.
+pub const DEFAULT_TAXONOMY: [(&str, &str); 7] = [
+    ("wrong_answer", "Answers the question, but the answer is wrong"),
+    ("hallucination", "States facts that are not supported by the prompt or the expected output"),
+    ("incomplete", "Leaves out part of what was asked"),
+    ("format_error", "Right content in the wrong format or structure"),
+    ("instruction_violation", "Ignores an explicit instruction or constraint of the prompt"),
+    ("refusal", "Declines or deflects instead of answering"),
+    ("other", "Fits none of the other categories"),
+];
+
+/// Sent to the classifier model; `{{categories}}`, `{{prompt}}`, `{{expected}}`, `{{actual}}`
+/// and `{{reasoning}}` are filled in.
+const CLASSIFY_PROMPT: &str = r#"An AI model's output was judged to FAIL. Pick the one failure category below that best describes why.
+
+Categories:
+{{categories}}
+
+Prompt:
+{{prompt}}
+
+Expected output:
+{{expected}}
+
+Actual output:
+{{actual}}
+
+Judge's reasoning:
+{{reasoning}}
+
+Respond with ONLY a JSON object: {"category": "<category name>", "justification": "<one sentence>"}"#;
+
+/// Added to the judge prompt when the judge classifies failures in its verdict call.
+const JUDGE_CLASSIFICATION_NOTE: &str = r#"If your verdict is FAIL, end your response with these two lines, picking the category that best describes the failure:
+CATEGORY: <category name>
+JUSTIFICATION: <one sentence>
+
+Categories:
+{{categories}}"#;
+
+/// One category of the failure taxonomy.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FailureCategory {
+    pub name: String,
+    pub description: String,
+}
+
+/// The built-in taxonomy.
+pub fn default_taxonomy() -> Vec<FailureCategory> {
+    DEFAULT_TAXONOMY
+        .iter()
+        .map(|(name, description)| FailureCategory { name: name.to_string(), description: description.to_string() })
+        .collect()
+}
+
+/// Checks a taxonomy before it replaces the stored one: it must hold 1 to `MAX_CATEGORIES`
+/// categories with distinct, non-empty names made of lowercase letters, digits and underscores.
+pub fn validate_taxonomy(categories: &[FailureCategory]) -> std::result::Result<(), String> {
+    if !(1..=MAX_CATEGORIES).contains(&categories.len()) {
+        return Err(format!("A taxonomy holds between 1 and {} categories", MAX_CATEGORIES));
+    }
+    for (i, category) in categories.iter().enumerate() {
+        let valid_name = !category.name.is_empty()
+            && category.name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+        if !valid_name {
+            return Err(format!(
+                "Category name '{}' must be lowercase letters, digits and underscores",
+                category.name
+            ));
+        }
+        if categories[..i].iter().any(|other| other.name == category.name) {
+            return Err(format!("Category '{}' is listed twice", category.name));
+        }
+    }
+    Ok(())
+}
+
+/// The stored taxonomy, or the built-in one when there is no database or it cannot be read.
+pub async fn load_taxonomy(db_pool: Option<&DbPool>) -> Vec<FailureCategory> {
+    let Some(pool) = db_pool else {
+        return default_taxonomy();
+    };
+    match crate::database::get_failure_categories(pool).await {
+        Ok(categories) if !categories.is_empty() => categories,
+        Ok(_) => default_taxonomy(),
+        Err(e) => {
+            tracing::warn!("Failed to load the failure taxonomy, using the built-in one: {}", e);
+            default_taxonomy()
+        }
+    }
+}
+
+/// Who classifies failed evals, from `FAILURE_CLASSIFIER`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FailureClassifier {
+    /// The judge names the category in its verdict call. Evals it did not classify there
+    /// (batched judge calls, rule verdicts) fall back to a separate call to the judge model.
+    Judge,
+    /// A separate call to this model for each failed eval.
+    Model(String),
+}
+
+impl FailureClassifier {
+    /// `judge`, or a `provider:model` string.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "" => None,
+            "judge" => Some(FailureClassifier::Judge),
+            model => Some(FailureClassifier::Model(model.to_string())),
+        }
+    }
+}
+
+/// The category a failed eval was put in.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FailureClassification {
+    pub category: String,
+    pub justification: Option<String>,
+    /// The model that classified the eval, `judge` when the judge did it in its verdict call,
+    /// or `human` when set during review.
+    pub classifier: String,
+    /// USD cost of a separate classification call; `None` for the judge's own call, unpriced
+    /// models and unknown usage.
+    #[serde(default)]
+    pub cost_usd: Option<f64>,
+}
+
+fn list_categories(taxonomy: &[FailureCategory]) -> String {
+    taxonomy
+        .iter()
+        .map(|c| format!("- {}: {}", c.name, c.description))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// `template` with the instructions for naming a failure category appended.
+pub fn add_classification_note(template: &str, taxonomy: &[FailureCategory]) -> String {
+    let note = JUDGE_CLASSIFICATION_NOTE.replace("{{categories}}", &list_categories(taxonomy));
+    format!("{}\n\n{}", template.trim_end(), note)
+}
+
+/// The taxonomy name `name` refers to, ignoring case and surrounding quotes or backticks.
+fn match_category(name: &str, taxonomy: &[FailureCategory]) -> Option<String> {
+    let name = name.trim().trim_matches(['"', '\'', '`', '*']).trim();
+    taxonomy
+        .iter()
+        .find(|c| c.name.eq_ignore_ascii_case(name))
+        .map(|c| c.name.clone())
+}
+
+/// Reads a category and justification from a response: a JSON object with `category` and
+/// `justification`, or failing that `CATEGORY:` and `JUSTIFICATION:` lines. `None` when no
+/// category of `taxonomy` is named.
+pub fn parse_classification(response: &str, taxonomy: &[FailureCategory]) -> Option<(String, Option<String>)> {
+    let object: Option<JsonValue> = match (response.find('{'), response.rfind('}')) {
+        (Some(start), Some(end)) if start < end => serde_json::from_str(&response[start..=end]).ok(),
+        _ => None,
+    };
+    let json_field = |name: &str| object.as_ref().and_then(|o| o.get(name)).and_then(JsonValue::as_str);
+    if let Some(category) = json_field("category") {
+        let justification = json_field("justification")
+            .map(|j| j.trim().to_string())
+            .filter(|j| !j.is_empty());
+        return match_category(category, taxonomy).map(|category| (category, justification));
+    }
+
+    let field = |label: &str| {
+        response.lines().rev().find_map(|line| {
+            let line = line.trim().trim_start_matches(['*', '-']).trim_start();
+            let prefix = line.get(..label.len())?;
+            prefix
+                .eq_ignore_ascii_case(label)
+                .then(|| line[label.len()..].trim().to_string())
+                .filter(|value| !value.is_empty())
+        })
+    };
+    let category = match_category(&field("CATEGORY:")?, taxonomy)?;
+    Some((category, field("JUSTIFICATION:")))
+}
+
+/// Asks `model` which category of `taxonomy` the failed `result` falls in.
+pub async fn classify(
+    config: &AppConfig,
+    client: &reqwest::Client,
+    model: &str,
+    taxonomy: &[FailureCategory],
+    result: &EvalResult,
+    db_pool: Option<&DbPool>,
+) -> Result<FailureClassification> {
+    let (provider_name, model_name) = config.parse_model(model);
+    let request = CLASSIFY_PROMPT
+        .replace("{{categories}}", &list_categories(taxonomy))
+        .replace("{{prompt}}", &result.prompt)
+        .replace("{{expected}}", result.expected.as_deref().unwrap_or("(none given)"))
+        .replace("{{actual}}", &result.model_output)
+        .replace(
+            "{{reasoning}}",
+            result
+                .judge_result
+                .as_ref()
+                .and_then(|j| j.reasoning.as_deref())
+                .unwrap_or("(none given)"),
+        );
+    let Generation { output, token_usage, .. } =
+        runner::call_provider(config, client, &provider_name, &model_name, &request, db_pool).await?;
+
+    let (category, justification) = parse_classification(&output, taxonomy).ok_or_else(|| {
+        EvalError::UnexpectedResponse(format!("{} named no category of the failure taxonomy", model))
+    })?;
+    Ok(FailureClassification {
+        category,
+        justification,
+        classifier: model.to_string(),
+        cost_usd: config.pricing.cost(&format!("{}:{}", provider_name, model_name), &token_usage),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_classification_reads_json_or_lines() {
+        let taxonomy = default_taxonomy();
+
+        let json = "```json\n{\"category\": \"Hallucination\", \"justification\": \"Invents a date.\"}\n```";
+        assert_eq!(
+            parse_classification(json, &taxonomy),
+            Some(("hallucination".to_string(), Some("Invents a date.".to_string())))
+        );
+
+        let judged = "The answer is wrong.\nVERDICT: FAIL\nCATEGORY: `wrong_answer`\nJUSTIFICATION: Says 5, not 4.";
+        assert_eq!(
+            parse_classification(judged, &taxonomy),
+            Some(("wrong_answer".to_string(), Some("Says 5, not 4.".to_string())))
+        );
+
+        assert_eq!(parse_classification("CATEGORY: made_up", &taxonomy), None);
+        assert_eq!(parse_classification("VERDICT: FAIL", &taxonomy), None);
+    }
+
+    #[test]
+    fn test_validate_taxonomy() {
+        assert!(validate_taxonomy(&default_taxonomy()).is_ok());
+        assert!(validate_taxonomy(&[]).is_err());
+
+        let category = |name: &str| FailureCategory { name: name.to_string(), description: String::new() };
+        assert!(validate_taxonomy(&[category("Wrong Answer")]).is_err());
+        assert!(validate_taxonomy(&[category("refusal"), category("refusal")]).is_err());
+    }
+
+    #[test]
+    fn test_classifier_parse() {
+        assert_eq!(FailureClassifier::parse("judge"), Some(FailureClassifier::Judge));
+        assert_eq!(
+            FailureClassifier::parse(" openai:gpt-4o-mini "),
+            Some(FailureClassifier::Model("openai:gpt-4o-mini".to_string()))
+        );
+        assert_eq!(FailureClassifier::parse("  "), None);
+    }
+}
@@ -0,0 +1,96 @@
+// src/format_rule.rs
+use serde::{Deserialize, Serialize};
+
+/// A fail-fast constraint on the shape of a model's output.
+/// Rules can be checked incrementally against a partial (streamed) output, so a
+/// generation that has obviously violated the rule can be aborted early.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FormatRule {
+    /// The output, ignoring leading whitespace, must start with `prefix`.
+    StartsWith {
+        prefix: String,
+        #[serde(default)]
+        case_insensitive: bool,
+    },
+    /// The output must never contain `text`.
+    NotContains { text: String },
+}
+
+impl FormatRule {
+    /// Checks a partial output. Returns `Some(reason)` only once a violation is certain,
+    /// i.e. no continuation of `partial` could satisfy the rule.
+    pub fn check_partial(&self, partial: &str) -> Option<String> {
+        match self {
+            FormatRule::StartsWith { prefix, case_insensitive } => {
+                let (text, prefix_cmp) = normalize(partial.trim_start(), prefix, *case_insensitive);
+                let violated = if text.len() >= prefix_cmp.len() {
+                    !text.starts_with(&prefix_cmp)
+                } else {
+                    !prefix_cmp.starts_with(&text)
+                };
+                violated.then(|| format!("output does not start with \"{}\"", prefix))
+            }
+            FormatRule::NotContains { text } => partial
+                .contains(text.as_str())
+                .then(|| format!("output contains \"{}\"", text)),
+        }
+    }
+
+    /// Checks a complete output. Returns `Some(reason)` if the rule is violated.
+    pub fn check_complete(&self, output: &str) -> Option<String> {
+        match self {
+            FormatRule::StartsWith { prefix, case_insensitive } => {
+                let (text, prefix_cmp) = normalize(output.trim_start(), prefix, *case_insensitive);
+                (!text.starts_with(&prefix_cmp))
+                    .then(|| format!("output does not start with \"{}\"", prefix))
+            }
+            FormatRule::NotContains { .. } => self.check_partial(output),
+        }
+    }
+}
+
+fn normalize(text: &str, prefix: &str, case_insensitive: bool) -> (String, String) {
+    if case_insensitive {
+        (text.to_lowercase(), prefix.to_lowercase())
+    } else {
+        (text.to_string(), prefix.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn starts_with(prefix: &str) -> FormatRule {
+        FormatRule::StartsWith { prefix: prefix.to_string(), case_insensitive: false }
+    }
+
+    #[test]
+    fn test_starts_with_waits_until_violation_is_certain() {
+        let rule = starts_with("SELECT");
+
+        assert!(rule.check_partial("").is_none());
+        assert!(rule.check_partial("  SEL").is_none());
+        assert!(rule.check_partial("SELECT * FROM").is_none());
+        assert!(rule.check_partial("Sure").is_some());
+        assert!(rule.check_partial("SELEKT").is_some());
+    }
+
+    #[test]
+    fn test_starts_with_complete_output() {
+        let rule = starts_with("SELECT");
+        assert!(rule.check_complete("SELECT 1").is_none());
+        assert!(rule.check_complete("SEL").is_some());
+
+        let rule = FormatRule::StartsWith { prefix: "SELECT".to_string(), case_insensitive: true };
+        assert!(rule.check_partial("select id").is_none());
+    }
+
+    #[test]
+    fn test_not_contains() {
+        let rule = FormatRule::NotContains { text: "```".to_string() };
+        assert!(rule.check_partial("SELECT 1").is_none());
+        assert!(rule.check_partial("Here you go: ```sql").is_some());
+    }
+}
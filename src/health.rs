@@ -0,0 +1,204 @@
+// src/health.rs
+use futures::future::join_all;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::config::AppConfig;
+use crate::database::{self, DbPool};
+use crate::runner;
+use crate::providers::GenerationParams;
+
+/// How long a provider check is reused, so probes do not hammer the provider APIs.
+const PROVIDER_CHECK_TTL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComponentStatus {
+    Ok,
+    Error,
+    /// Not available to check, such as a database that failed to initialize.
+    Disabled,
+}
+
+/// The outcome of checking one dependency.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ComponentHealth {
+    pub status: ComponentStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ComponentHealth {
+    fn checked<E: std::fmt::Display>(result: Result<(), E>, started: Instant) -> Self {
+        let latency_ms = Some(started.elapsed().as_millis() as u64);
+        match result {
+            Ok(()) => ComponentHealth { status: ComponentStatus::Ok, latency_ms, error: None },
+            Err(e) => ComponentHealth { status: ComponentStatus::Error, latency_ms, error: Some(e.to_string()) },
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.status == ComponentStatus::Ok
+    }
+}
+
+/// Pings the database; `disabled` when the server started without one.
+pub async fn check_database(pool: Option<&DbPool>) -> ComponentHealth {
+    let Some(pool) = pool else {
+        return ComponentHealth {
+            status: ComponentStatus::Disabled,
+            latency_ms: None,
+            error: Some("the database failed to initialize".to_string()),
+        };
+    };
+    let started = Instant::now();
+    ComponentHealth::checked(database::ping(pool).await, started)
+}
+
+/// The last check of each provider, reused for `PROVIDER_CHECK_TTL`.
+pub struct ProviderHealthCache {
+    ttl: Duration,
+    checked: Mutex<HashMap<String, (Instant, ComponentHealth)>>,
+}
+
+impl Default for ProviderHealthCache {
+    fn default() -> Self {
+        ProviderHealthCache::new(PROVIDER_CHECK_TTL)
+    }
+}
+
+impl ProviderHealthCache {
+    pub fn new(ttl: Duration) -> Self {
+        ProviderHealthCache { ttl, checked: Mutex::new(HashMap::new()) }
+    }
+
+    /// The health of every configured provider, checking those without a fresh result concurrently.
+    pub async fn check(&self, config: &AppConfig, client: &reqwest::Client) -> BTreeMap<String, ComponentHealth> {
+        let configured = config.configured_providers();
+        let mut health = BTreeMap::new();
+        let mut stale = Vec::new();
+        {
+            let checked = self.checked.lock().unwrap();
+            for provider in configured {
+                match checked.get(provider) {
+                    Some((at, result)) if at.elapsed() < self.ttl => {
+                        health.insert(provider.to_string(), result.clone());
+                    }
+                    _ => stale.push(provider),
+                }
+            }
+        }
+
+        let fresh = join_all(stale.into_iter().map(|provider| async move {
+            let started = Instant::now();
+            let result = match runner::build_provider(config, client, provider, &GenerationParams::default()) {
+                Ok(built) => built.health().await,
+                Err(e) => Err(e),
+            };
+            (provider, ComponentHealth::checked(result, started))
+        }))
+        .await;
+
+        let mut checked = self.checked.lock().unwrap();
+        for (provider, result) in fresh {
+            if !result.is_ok() {
+                tracing::warn!("🩺 Provider {} failed its health check: {}", provider, result.error.as_deref().unwrap_or_default());
+            }
+            checked.insert(provider.to_string(), (Instant::now(), result.clone()));
+            health.insert(provider.to_string(), result);
+        }
+        health
+    }
+}
+
+/// Whether the server can do its job: the database answers and, when any providers are
+/// configured, at least one of them is reachable.
+#[derive(Debug, Clone, Serialize)]
+pub struct Readiness {
+    pub database: ComponentHealth,
+    pub providers: BTreeMap<String, ComponentHealth>,
+}
+
+impl Readiness {
+    pub fn is_ready(&self) -> bool {
+        self.database.is_ok() && (self.providers.is_empty() || self.providers.values().any(ComponentHealth::is_ok))
+    }
+
+    /// `healthy` when everything is up, `degraded` when ready with some providers down,
+    /// `unhealthy` when not ready.
+    pub fn status(&self) -> &'static str {
+        if !self.is_ready() {
+            "unhealthy"
+        } else if self.providers.values().all(ComponentHealth::is_ok) {
+            "healthy"
+        } else {
+            "degraded"
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{OllamaConfig, OpenAIConfig};
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_provider_checks_are_cached_and_report_failures() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET")).and(path("/api/tags")).respond_with(ResponseTemplate::new(200)).mount(&server).await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .and(header("Authorization", "Bearer bad-key"))
+            .respond_with(ResponseTemplate::new(401).set_body_string("invalid api key"))
+            .mount(&server)
+            .await;
+        let config = AppConfig {
+            ollama: Some(OllamaConfig { api_base: server.uri(), models: vec![] }),
+            openai: Some(OpenAIConfig { api_base: format!("{}/v1", server.uri()), api_key: "bad-key".to_string(), models: vec![] }),
+            ..Default::default()
+        };
+        let cache = ProviderHealthCache::default();
+        let client = reqwest::Client::new();
+
+        let health = cache.check(&config, &client).await;
+        assert_eq!(health.keys().collect::<Vec<_>>(), ["ollama", "openai"]);
+        assert!(health["ollama"].is_ok());
+        assert_eq!(health["openai"].status, ComponentStatus::Error);
+        assert!(health["openai"].error.as_deref().unwrap().contains("401"));
+
+        let again = cache.check(&config, &client).await;
+        assert_eq!(again, health);
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+
+        let expired = ProviderHealthCache::new(Duration::ZERO);
+        expired.check(&config, &client).await;
+        expired.check(&config, &client).await;
+        assert_eq!(server.received_requests().await.unwrap().len(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_ready_needs_the_database_and_one_reachable_provider() {
+        let ok = ComponentHealth { status: ComponentStatus::Ok, latency_ms: Some(1), error: None };
+        let down = ComponentHealth { status: ComponentStatus::Error, latency_ms: Some(1), error: Some("timed out".to_string()) };
+        let readiness = |database: &ComponentHealth, providers: &[&ComponentHealth]| Readiness {
+            database: database.clone(),
+            providers: providers.iter().enumerate().map(|(i, p)| (i.to_string(), (*p).clone())).collect(),
+        };
+
+        assert_eq!(readiness(&ok, &[&ok, &ok]).status(), "healthy");
+        assert_eq!(readiness(&ok, &[]).status(), "healthy");
+        assert_eq!(readiness(&ok, &[&ok, &down]).status(), "degraded");
+        assert_eq!(readiness(&ok, &[&down]).status(), "unhealthy");
+
+        let disabled = check_database(None).await;
+        assert_eq!(disabled.status, ComponentStatus::Disabled);
+        assert!(!readiness(&disabled, &[&ok]).is_ready());
+        assert!(check_database(Some(&database::test_pool().await)).await.is_ok());
+    }
+}
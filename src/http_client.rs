@@ -0,0 +1,160 @@
+// src/http_client.rs
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Certificate, Client, NoProxy, Proxy};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::errors::{EvalError, Result};
+
+/// Hosts reached without the proxy when `NO_PROXY` is unset, so a local Ollama is not sent
+/// through a corporate proxy.
+const DEFAULT_NO_PROXY: &str = "localhost,127.0.0.1,::1";
+
+/// How the shared HTTP client reaches providers.
+#[derive(Debug, Clone, Default)]
+pub struct HttpClientConfig {
+    /// Proxy every request goes through, from `HTTPS_PROXY`; `None` connects directly.
+    pub proxy: Option<String>,
+    /// Comma-separated hosts and domains that bypass the proxy, from `NO_PROXY`.
+    pub no_proxy: String,
+    /// CA certificates that replace the built-in root store, read from the PEM bundle at
+    /// `CA_BUNDLE_PATH`; empty keeps the built-in roots.
+    pub ca_certificates: Vec<Certificate>,
+    /// Longest a provider request may take, response body included, from
+    /// `PROVIDER_TIMEOUT_SECONDS`; `None` waits as long as the provider does.
+    pub timeout: Option<Duration>,
+}
+
+impl HttpClientConfig {
+    /// Reads the proxy and CA bundle settings with `var`, loading the CA bundle, and checks
+    /// that a client can be built from them.
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let set = |name: &str| var(name).or_else(|| var(&name.to_lowercase())).filter(|value| !value.trim().is_empty());
+        let ca_certificates = match set("CA_BUNDLE_PATH") {
+            Some(path) => load_ca_bundle(Path::new(path.trim()))?,
+            None => Vec::new(),
+        };
+        let timeout = match var("PROVIDER_TIMEOUT_SECONDS") {
+            Some(value) => match value.trim().parse::<u64>() {
+                Ok(secs) if secs >= 1 => Some(Duration::from_secs(secs)),
+                _ => {
+                    return Err(EvalError::Config(format!("PROVIDER_TIMEOUT_SECONDS must be a positive integer, got '{}'", value)));
+                }
+            },
+            None => None,
+        };
+        let config = HttpClientConfig {
+            proxy: set("HTTPS_PROXY").map(|proxy| proxy.trim().to_string()),
+            no_proxy: set("NO_PROXY").unwrap_or_else(|| DEFAULT_NO_PROXY.to_string()),
+            ca_certificates,
+            timeout,
+        };
+        config.build()?;
+        Ok(config)
+    }
+
+    /// Builds the client shared by every provider call.
+    pub fn build(&self) -> Result<Client> {
+        let mut builder = Client::builder();
+        if let Some(url) = &self.proxy {
+            let proxy = Proxy::all(url)
+                .map_err(|e| EvalError::Config(format!("HTTPS_PROXY '{}' is not a valid proxy URL: {}", url, e)))?
+                .no_proxy(NoProxy::from_string(&self.no_proxy));
+            builder = builder.proxy(proxy);
+        }
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if !self.ca_certificates.is_empty() {
+            builder = builder.tls_built_in_root_certs(false);
+            for certificate in &self.ca_certificates {
+                builder = builder.add_root_certificate(certificate.clone());
+            }
+        }
+        builder
+            .build()
+            .map_err(|e| EvalError::Config(format!("Could not build the HTTP client: {}", e)))
+    }
+}
+
+/// The certificates of the PEM bundle at `path`.
+fn load_ca_bundle(path: &Path) -> Result<Vec<Certificate>> {
+    let pem = std::fs::read(path)
+        .map_err(|e| EvalError::Config(format!("CA_BUNDLE_PATH '{}' could not be read: {}", path.display(), e)))?;
+    let certificates = Certificate::from_pem_bundle(&pem)
+        .map_err(|e| EvalError::Config(format!("CA_BUNDLE_PATH '{}' is not a PEM bundle: {}", path.display(), e)))?;
+    if certificates.is_empty() {
+        return Err(EvalError::Config(format!("CA_BUNDLE_PATH '{}' holds no certificates", path.display())));
+    }
+    Ok(certificates)
+}
+
+/// Parses headers sent with every request of a provider, `Name: value` pairs separated by
+/// `;`, e.g. `X-Org: evals;X-Env: prod`. `var_name` names the variable in errors.
+pub fn parse_extra_headers(var_name: &str, spec: &str) -> Result<HeaderMap> {
+    let mut headers = HeaderMap::new();
+    for entry in spec.split(';').map(str::trim).filter(|entry| !entry.is_empty()) {
+        let invalid = |reason: &str| EvalError::Config(format!("{} entry '{}' {}", var_name, entry, reason));
+        let (name, value) = entry.split_once(':').ok_or_else(|| invalid("must look like Name: value"))?;
+        let name = HeaderName::from_bytes(name.trim().as_bytes()).map_err(|_| invalid("has an invalid header name"))?;
+        let value = HeaderValue::from_str(value.trim()).map_err(|_| invalid("has an invalid header value"))?;
+        headers.append(name, value);
+    }
+    Ok(headers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_extra_headers_parse_and_reject_invalid_names_and_values() {
+        let headers = parse_extra_headers("OPENAI_EXTRA_HEADERS", "X-Org: evals; X-Env:prod;").unwrap();
+        assert_eq!(headers.len(), 2);
+        assert_eq!(headers["x-org"], "evals");
+        assert_eq!(headers["x-env"], "prod");
+        assert!(parse_extra_headers("OPENAI_EXTRA_HEADERS", "").unwrap().is_empty());
+
+        for spec in ["X-Org evals", "X Org: evals", ": evals", "X-Org: line\nbreak"] {
+            let err = parse_extra_headers("OPENAI_EXTRA_HEADERS", spec).unwrap_err();
+            assert!(matches!(&err, EvalError::Config(message) if message.starts_with("OPENAI_EXTRA_HEADERS entry")), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_client_settings_are_read_from_the_environment() {
+        let vars: HashMap<&str, &str> = HashMap::from([("https_proxy", "http://proxy.corp:3128")]);
+        let config = HttpClientConfig::from_vars(|name| vars.get(name).map(|v| v.to_string())).unwrap();
+        assert_eq!(config.proxy.as_deref(), Some("http://proxy.corp:3128"));
+        assert_eq!(config.no_proxy, DEFAULT_NO_PROXY);
+        assert!(config.ca_certificates.is_empty());
+        assert_eq!(config.timeout, None);
+
+        let vars: HashMap<&str, &str> = HashMap::from([
+            ("HTTPS_PROXY", "http://proxy.corp:3128"),
+            ("NO_PROXY", "ollama.internal"),
+            ("PROVIDER_TIMEOUT_SECONDS", "90"),
+        ]);
+        let config = HttpClientConfig::from_vars(|name| vars.get(name).map(|v| v.to_string())).unwrap();
+        assert_eq!(config.no_proxy, "ollama.internal");
+        assert_eq!(config.timeout, Some(Duration::from_secs(90)));
+        let zero = HttpClientConfig::from_vars(|name| (name == "PROVIDER_TIMEOUT_SECONDS").then(|| "0".to_string()));
+        assert!(matches!(zero, Err(EvalError::Config(message)) if message.contains("PROVIDER_TIMEOUT_SECONDS")));
+
+        let bad_proxy = HttpClientConfig { proxy: Some("not a url".to_string()), ..Default::default() };
+        assert!(matches!(bad_proxy.build(), Err(EvalError::Config(message)) if message.contains("HTTPS_PROXY")));
+
+        let missing = HttpClientConfig::from_vars(|name| (name == "CA_BUNDLE_PATH").then(|| "/nonexistent/ca.pem".to_string()));
+        assert!(matches!(missing, Err(EvalError::Config(message)) if message.contains("could not be read")));
+    }
+
+    #[test]
+    fn test_a_ca_bundle_without_certificates_is_rejected() {
+        let path = std::env::temp_dir().join(format!("evaluate-ca-{}.pem", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "not a certificate\n").unwrap();
+        let err = load_ca_bundle(&path).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(&err, EvalError::Config(message) if message.contains("CA_BUNDLE_PATH")), "{}", err);
+    }
+}
@@ -0,0 +1,361 @@
+// src/jobs.rs
+//! The job queue: batch and suite runs stored in `jobs` and run by a background worker that
+//! records every finished eval, so a run cut short by a restart resumes where it stopped.
+use std::collections::HashSet;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+use crate::api::handlers::{finish_eval, ws, WsBroker};
+use crate::api::progress::ProgressStage;
+use crate::api::AppState;
+use crate::config::EvalConfig;
+use crate::database::{self, DbPool, Job, JobEval};
+use crate::runner;
+use crate::scheduler;
+
+/// How the worker looks for jobs and holds on to them.
+#[derive(Debug, Clone)]
+pub struct WorkerOptions {
+    /// Time between looks at an empty queue.
+    pub poll_interval: Duration,
+    /// A running job whose worker has sent no heartbeat for this long, as after a crash, is
+    /// claimed again. Heartbeats go out every third of it.
+    pub lease: Duration,
+    /// Evals of a job run together; each is checkpointed once its chunk has finished.
+    pub chunk_size: usize,
+}
+
+impl Default for WorkerOptions {
+    fn default() -> Self {
+        WorkerOptions { poll_interval: Duration::from_secs(2), lease: Duration::from_secs(60), chunk_size: 8 }
+    }
+}
+
+/// The running job worker. Call `shutdown` to stop it and hand its job back to the queue.
+pub struct JobWorker {
+    shutdown: watch::Sender<bool>,
+    handle: JoinHandle<()>,
+}
+
+impl JobWorker {
+    /// Starts claiming and running jobs from the database of `state`, one at a time.
+    pub fn spawn(state: AppState, broker: WsBroker, options: WorkerOptions) -> Self {
+        let (shutdown, mut stop) = watch::channel(false);
+        let handle = tokio::spawn(async move {
+            let Some(pool) = state.db_pool.clone() else {
+                tracing::warn!("⚠️  The job worker needs a database; queued jobs will not run");
+                return;
+            };
+            let worker_id = uuid::Uuid::new_v4().to_string();
+            let lease = chrono::Duration::from_std(options.lease).unwrap_or(chrono::Duration::MAX);
+            loop {
+                let stale_before = (chrono::Utc::now() - lease).to_rfc3339();
+                let wait = match database::claim_job(&pool, &worker_id, &stale_before).await {
+                    Ok(Some(job)) => {
+                        run_job(&state, &pool, &broker, &worker_id, &options, job, &mut stop).await;
+                        Duration::ZERO
+                    }
+                    Ok(None) => options.poll_interval,
+                    Err(e) => {
+                        tracing::error!("Failed to claim a job: {}", e);
+                        options.poll_interval
+                    }
+                };
+                if *stop.borrow() {
+                    break;
+                }
+                tokio::select! {
+                    _ = tokio::time::sleep(wait) => {}
+                    _ = stop.changed() => break,
+                }
+            }
+            tracing::info!("📬 Job worker stopped");
+        });
+        JobWorker { shutdown, handle }
+    }
+
+    /// Stops the worker. Evals of its job still running are dropped, and the job goes back to
+    /// the queue to resume from its last checkpoint.
+    pub async fn shutdown(self) {
+        let _ = self.shutdown.send(true);
+        if let Err(e) = self.handle.await {
+            tracing::error!("Job worker panicked: {}", e);
+        }
+    }
+}
+
+/// How a worker's turn at a job ended.
+enum Outcome {
+    Finished,
+    /// The worker is stopping; the job goes back to the queue.
+    Stopped,
+    /// The job was cancelled or claimed by another worker after missed heartbeats.
+    Lost,
+    Failed(String),
+}
+
+/// Runs the evals of `job` that have no checkpoint yet, a chunk at a time, saving and
+/// checkpointing each like a batch's.
+async fn run_job(
+    state: &AppState,
+    pool: &DbPool,
+    broker: &WsBroker,
+    worker_id: &str,
+    options: &WorkerOptions,
+    job: Job,
+    stop: &mut watch::Receiver<bool>,
+) {
+    let _active = state.start_batch();
+    let mut finished = match database::get_job_evals(pool, &job.job_id).await {
+        Ok(finished) => finished,
+        Err(e) => {
+            tracing::error!("Failed to read the checkpoints of job {}: {}", job.job_id, e);
+            release(pool, &job, worker_id).await;
+            return;
+        }
+    };
+    // A resumed job's batch was saved by the worker that started it
+    let batch_saved = match database::get_batch(pool, &job.batch_id).await {
+        Err(sqlx::Error::RowNotFound) => database::create_batch(pool, &job.batch_id, job.configs.len()).await,
+        _ => Ok(()),
+    };
+    if let Err(e) = batch_saved {
+        tracing::error!("Failed to save batch {} to database: {}", job.batch_id, e);
+    }
+
+    let done: HashSet<i64> = finished.iter().map(|eval| eval.position).collect();
+    let pending: Vec<(i64, EvalConfig)> = (0i64..)
+        .zip(job.configs.iter().cloned())
+        .filter(|(position, _)| !done.contains(position))
+        .collect();
+    tracing::info!("📬 Running job {}: {} of {} evals left", job.job_id, pending.len(), job.configs.len());
+
+    let (progress, forwarding) = ws::forward_resumed_progress(broker, &state.progress, &job.batch_id, finished.len(), job.configs.len());
+    let period = (options.lease / 3).max(Duration::from_millis(10));
+    let mut heartbeat = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+    let outcome = 'run: {
+        for chunk in pending.chunks(options.chunk_size.max(1)) {
+            let configs = chunk.iter().map(|(_, config)| config.clone()).collect();
+            let run = runner::run_batch_evals_with_ids(
+                &state.config,
+                configs,
+                Some(&job.batch_id),
+                &state.client,
+                Some(pool),
+                Some(&progress),
+            );
+            tokio::pin!(run);
+            let results = loop {
+                tokio::select! {
+                    results = &mut run => break results,
+                    _ = heartbeat.tick() => match database::heartbeat_job(pool, &job.job_id, worker_id).await {
+                        Ok(true) => {}
+                        Ok(false) => break 'run Outcome::Lost,
+                        Err(e) => tracing::warn!("Failed to refresh the heartbeat of job {}: {}", job.job_id, e),
+                    },
+                    _ = stop.changed() => break 'run Outcome::Stopped,
+                }
+            };
+
+            for ((position, config), (eval_id, outcome)) in chunk.iter().zip(results) {
+                let response = finish_eval(
+                    state,
+                    broker,
+                    eval_id,
+                    outcome,
+                    Some(&job.batch_id),
+                    config.config_hash(),
+                    None,
+                    config.model.clone(),
+                    config.prompt_version,
+                )
+                .await;
+                let eval = JobEval { position: *position, eval_id: response.id, status: response.status };
+                match database::checkpoint_job_eval(pool, &job.job_id, worker_id, &eval).await {
+                    Ok(true) => finished.push(eval),
+                    Ok(false) => break 'run Outcome::Lost,
+                    Err(e) => break 'run Outcome::Failed(format!("Failed to checkpoint eval {}: {}", eval.eval_id, e)),
+                }
+            }
+        }
+        Outcome::Finished
+    };
+    drop(progress);
+    let _ = forwarding.await;
+
+    match outcome {
+        Outcome::Finished => {
+            state.progress.batch_stage(&job.batch_id, ProgressStage::Saving);
+            complete(state, pool, &job, worker_id, &finished).await;
+            state.progress.batch_stage(&job.batch_id, ProgressStage::Done);
+        }
+        Outcome::Stopped => {
+            tracing::info!("📬 Job {} goes back to the queue with {} of {} evals done", job.job_id, finished.len(), job.total);
+            state.progress.batch_stage(&job.batch_id, ProgressStage::Queued);
+            release(pool, &job, worker_id).await;
+        }
+        Outcome::Lost => {
+            tracing::info!("📬 Job {} was cancelled or taken over by another worker", job.job_id);
+            state.progress.forget(&job.batch_id);
+        }
+        Outcome::Failed(error) => {
+            tracing::error!("Job {} failed: {}", job.job_id, error);
+            state.progress.batch_stage(&job.batch_id, ProgressStage::Error);
+            if let Err(e) = database::finish_job(pool, &job.job_id, worker_id, "failed", Some(&error)).await {
+                tracing::error!("Failed to update job {} in database: {}", job.job_id, e);
+            }
+        }
+    }
+}
+
+/// Records the outcome of a job whose evals have all finished and the run of its suite, and
+/// sends the notifications of the schedule that queued it.
+async fn complete(state: &AppState, pool: &DbPool, job: &Job, worker_id: &str, finished: &[JobEval]) {
+    let passed = finished.iter().filter(|eval| eval.status == "passed").count();
+    let failed = finished.iter().filter(|eval| matches!(eval.status.as_str(), "failed" | "error" | "blocked")).count();
+    if let Err(e) = database::finish_batch(pool, &job.batch_id, passed, failed, "completed").await {
+        tracing::error!("Failed to update batch {} in database: {}", job.batch_id, e);
+    }
+    if let Some(suite_id) = &job.suite_id {
+        let mut finished = finished.to_vec();
+        finished.sort_by_key(|eval| eval.position);
+        let evaluation_ids: Vec<String> = finished.into_iter().map(|eval| eval.eval_id).collect();
+        if let Err(e) = database::create_suite_run(pool, suite_id, &job.batch_id, &evaluation_ids).await {
+            tracing::error!("Failed to record the run of suite {} for job {}: {}", suite_id, job.job_id, e);
+        }
+    }
+    match database::finish_job(pool, &job.job_id, worker_id, "completed", None).await {
+        Ok(true) => {
+            tracing::info!("📬 Job {} completed: {} passed, {} failed", job.job_id, passed, failed);
+            scheduler::report_run(state, pool, job).await;
+        }
+        Ok(false) => tracing::info!("📬 Job {} finished after it was cancelled", job.job_id),
+        Err(e) => tracing::error!("Failed to update job {} in database: {}", job.job_id, e),
+    }
+}
+
+async fn release(pool: &DbPool, job: &Job, worker_id: &str) {
+    if let Err(e) = database::release_job(pool, &job.job_id, worker_id).await {
+        tracing::error!("Failed to hand job {} back to the queue: {}", job.job_id, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn configs(prompts: &[&str]) -> Vec<EvalConfig> {
+        prompts.iter().map(|prompt| serde_json::from_value(json!({ "model": "ollama:qwen3", "prompt": prompt })).unwrap()).collect()
+    }
+
+    fn later(seconds: i64) -> String {
+        (chrono::Utc::now() + chrono::Duration::seconds(seconds)).to_rfc3339()
+    }
+
+    #[tokio::test]
+    async fn test_jobs_are_claimed_by_one_worker_until_its_heartbeats_stop() {
+        let pool = database::test_pool().await;
+        let job = database::enqueue_job(&pool, None, &configs(&["2+2?"])).await.unwrap();
+        assert_eq!((job.status.as_str(), job.kind.as_str(), job.total), ("queued", "batch", 1));
+
+        let claimed = database::claim_job(&pool, "a", &later(-60)).await.unwrap().unwrap();
+        assert_eq!((claimed.job_id.as_str(), claimed.status.as_str()), (job.job_id.as_str(), "running"));
+        assert_eq!(claimed.configs[0].prompt, "2+2?");
+        assert!(database::claim_job(&pool, "b", &later(-60)).await.unwrap().is_none());
+        let eval = JobEval { position: 0, eval_id: "e1".to_string(), status: "passed".to_string() };
+        assert!(!database::checkpoint_job_eval(&pool, &job.job_id, "b", &eval).await.unwrap());
+
+        // Once the lease runs out, as it does when a's server dies, b takes the job over
+        let taken = database::claim_job(&pool, "b", &later(60)).await.unwrap().unwrap();
+        assert_eq!(taken.started_at, claimed.started_at);
+        assert!(!database::heartbeat_job(&pool, &job.job_id, "a").await.unwrap());
+        assert!(database::checkpoint_job_eval(&pool, &job.job_id, "b", &eval).await.unwrap());
+        assert!(database::finish_job(&pool, &job.job_id, "b", "completed", None).await.unwrap());
+        assert!(database::claim_job(&pool, "c", &later(60)).await.unwrap().is_none());
+        assert_eq!(database::get_job_evals(&pool, &job.job_id).await.unwrap(), [eval]);
+
+        let queued = database::enqueue_job(&pool, None, &configs(&["3+3?"])).await.unwrap();
+        assert!(database::cancel_job(&pool, &queued.job_id).await.unwrap());
+        assert!(!database::cancel_job(&pool, &queued.job_id).await.unwrap());
+        assert!(database::claim_job(&pool, "c", &later(60)).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_a_restarted_worker_resumes_from_the_last_checkpoint() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "response": "4", "done": true }))
+                    .set_delay(Duration::from_millis(200)),
+            )
+            .mount(&server)
+            .await;
+        let pool = Arc::new(database::test_pool().await);
+        let state = AppState {
+            config: Arc::new(crate::config::AppConfig {
+                ollama: Some(crate::config::OllamaConfig { api_base: server.uri(), models: vec![] }),
+                ..Default::default()
+            }),
+            client: reqwest::Client::new(),
+            db_pool: Some(pool.clone()),
+            backup_in_progress: Default::default(),
+            active_batches: Default::default(),
+            retention: Default::default(),
+            rate_limiter: Default::default(),
+            provider_health: Default::default(),
+            progress: Default::default(),
+        };
+        let options = WorkerOptions { poll_interval: Duration::from_millis(10), chunk_size: 1, ..Default::default() };
+        let job = database::enqueue_job(&pool, None, &configs(&["Question 0", "Question 1", "Question 2"])).await.unwrap();
+
+        let wait_for = |done: fn(&Job, &[JobEval]) -> bool| {
+            let pool = pool.clone();
+            let job_id = job.job_id.clone();
+            async move {
+                for _ in 0..500 {
+                    let job = database::get_job(&pool, &job_id).await.unwrap();
+                    let evals = database::get_job_evals(&pool, &job_id).await.unwrap();
+                    if done(&job, &evals) {
+                        return evals;
+                    }
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+                panic!("job {} did not get there", job_id);
+            }
+        };
+
+        let worker = JobWorker::spawn(state.clone(), WsBroker::new(), options.clone());
+        let checkpointed = wait_for(|_, evals| !evals.is_empty()).await;
+        worker.shutdown().await;
+        let stopped = database::get_job(&pool, &job.job_id).await.unwrap();
+        assert_eq!(stopped.status, "queued");
+        let before_restart = database::get_job_evals(&pool, &job.job_id).await.unwrap();
+        assert!(before_restart.len() < 3);
+
+        let worker = JobWorker::spawn(state.clone(), WsBroker::new(), options);
+        let evals = wait_for(|job, _| job.status == "completed").await;
+        worker.shutdown().await;
+        assert_eq!(evals.iter().map(|eval| eval.position).collect::<Vec<_>>(), [0, 1, 2]);
+        assert_eq!(evals[0], checkpointed[0]);
+        assert!(evals.iter().all(|eval| eval.status == "completed"));
+
+        // The checkpointed eval was not run again, and every eval was saved under the job's batch
+        let requests = server.received_requests().await.unwrap();
+        let asked = |prompt: &str| requests.iter().filter(|r| String::from_utf8_lossy(&r.body).contains(prompt)).count();
+        assert_eq!(asked("Question 0"), 1);
+        let batch = database::get_batch(&pool, &job.batch_id).await.unwrap();
+        assert_eq!((batch.total, batch.status.as_str()), (3, "completed"));
+        for eval in &evals {
+            let stored = database::get_evaluation(&pool, &eval.eval_id).await.unwrap();
+            assert_eq!(stored.batch_id.as_deref(), Some(job.batch_id.as_str()));
+        }
+    }
+}
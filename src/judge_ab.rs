@@ -0,0 +1,357 @@
+// src/judge_ab.rs
+use serde::Serialize;
+use crate::database::DbPool;
+
+use crate::config::AppConfig;
+use crate::database::{HistoryEntry, JudgePromptStats};
+use crate::errors::Result;
+use crate::runner::{self, JudgeVerdict, Judgement, UncertainReason};
+
+/// One judge prompt version's verdict on a stored evaluation.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ArmJudgement {
+    pub verdict: Option<JudgeVerdict>,
+    pub confidence: Option<f32>,
+    pub uncertain_reason: Option<UncertainReason>,
+    pub reasoning: Option<String>,
+    pub latency_ms: Option<u64>,
+    pub cost_usd: Option<f64>,
+    /// Set when the judge call failed, in which case there is no verdict.
+    pub error: Option<String>,
+}
+
+impl From<Result<Judgement>> for ArmJudgement {
+    fn from(judgement: Result<Judgement>) -> Self {
+        match judgement {
+            Ok(judgement) => ArmJudgement {
+                verdict: Some(judgement.result.verdict),
+                confidence: judgement.result.confidence,
+                uncertain_reason: judgement.result.uncertain_reason,
+                reasoning: judgement.result.reasoning,
+                latency_ms: Some(judgement.latency_ms),
+                cost_usd: judgement.cost_usd,
+                error: None,
+            },
+            Err(e) => ArmJudgement { error: Some(e.to_string()), ..Default::default() },
+        }
+    }
+}
+
+/// A stored evaluation re-judged with both versions.
+#[derive(Debug, Clone, Serialize)]
+pub struct AbComparison {
+    pub evaluation_id: String,
+    pub judge_model: String,
+    /// The verdict stored when the evaluation first ran.
+    pub original_verdict: Option<String>,
+    pub a: ArmJudgement,
+    pub b: ArmJudgement,
+}
+
+/// Why a requested evaluation was left out of the test.
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedEvaluation {
+    pub evaluation_id: String,
+    pub reason: String,
+}
+
+/// The stored output, expected output and judge model to re-judge `entry` with, or why it
+/// cannot be. `judge_model` overrides the model the evaluation was originally judged by.
+pub fn judge_inputs<'a>(entry: &'a HistoryEntry, judge_model: Option<&'a str>) -> std::result::Result<(&'a str, &'a str, &'a str), String> {
+    let model_output = entry.model_output.as_deref().ok_or("No stored model output")?;
+    let expected = entry.expected.as_deref().ok_or("No expected output")?;
+    let judge_model = judge_model
+        .or(entry.judge_model.as_deref())
+        .filter(|m| !m.is_empty() && !runner::RULE_JUDGES.contains(m))
+        .ok_or("No judge model; pass judge_model to choose one")?;
+    Ok((judge_model, model_output, expected))
+}
+
+/// Re-judges one stored evaluation with both templates concurrently. Nothing is regenerated.
+#[allow(clippy::too_many_arguments)]
+pub async fn compare(
+    config: &AppConfig,
+    client: &reqwest::Client,
+    db_pool: Option<&DbPool>,
+    entry: &HistoryEntry,
+    judge_model: &str,
+    model_output: &str,
+    expected: &str,
+    templates: (&str, &str),
+) -> AbComparison {
+    let (a, b) = tokio::join!(
+        runner::judge_only(config, client, judge_model, templates.0, expected, model_output, None, db_pool),
+        runner::judge_only(config, client, judge_model, templates.1, expected, model_output, None, db_pool),
+    );
+    AbComparison {
+        evaluation_id: entry.id.clone(),
+        judge_model: judge_model.to_string(),
+        original_verdict: entry.judge_verdict.clone(),
+        a: a.into(),
+        b: b.into(),
+    }
+}
+
+/// How often a verdict from version A became a different verdict under version B.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VerdictFlip {
+    pub from: JudgeVerdict,
+    pub to: JudgeVerdict,
+    pub count: usize,
+}
+
+/// Verdict counts, latency and cost of one version over the test.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct VersionSummary {
+    pub version: i64,
+    pub judged: usize,
+    pub errors: usize,
+    pub passed: usize,
+    pub failed: usize,
+    pub uncertain: usize,
+    /// `passed / judged`
+    pub pass_rate: f64,
+    pub avg_latency_ms: Option<f64>,
+    pub avg_cost_usd: Option<f64>,
+    pub total_cost_usd: f64,
+}
+
+impl VersionSummary {
+    fn from_arms<'a>(version: i64, arms: impl Iterator<Item = &'a ArmJudgement>) -> Self {
+        let mut summary = VersionSummary { version, ..Default::default() };
+        let mut latencies = Vec::new();
+        let mut costs = Vec::new();
+        for arm in arms {
+            match arm.verdict {
+                Some(JudgeVerdict::Pass) => summary.passed += 1,
+                Some(JudgeVerdict::Fail) => summary.failed += 1,
+                Some(JudgeVerdict::Uncertain) => summary.uncertain += 1,
+                None => {
+                    summary.errors += 1;
+                    continue;
+                }
+            }
+            summary.judged += 1;
+            latencies.extend(arm.latency_ms);
+            costs.extend(arm.cost_usd);
+        }
+        if summary.judged > 0 {
+            summary.pass_rate = summary.passed as f64 / summary.judged as f64;
+        }
+        if !latencies.is_empty() {
+            summary.avg_latency_ms = Some(latencies.iter().sum::<u64>() as f64 / latencies.len() as f64);
+        }
+        summary.total_cost_usd = costs.iter().sum();
+        if !costs.is_empty() {
+            summary.avg_cost_usd = Some(summary.total_cost_usd / costs.len() as f64);
+        }
+        summary
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AbSummary {
+    pub evaluations: usize,
+    /// Evaluations both versions returned a verdict for.
+    pub compared: usize,
+    pub agreements: usize,
+    /// `agreements / compared`; `None` when nothing could be compared.
+    pub agreement_rate: Option<f64>,
+    /// Disagreements by direction, A's verdict first. Every direction is listed, even at zero.
+    pub flips: Vec<VerdictFlip>,
+    pub version_a: VersionSummary,
+    pub version_b: VersionSummary,
+}
+
+/// Agreement between the two versions, flips in each direction and per-version stats.
+pub fn summarize(version_a: i64, version_b: i64, comparisons: &[AbComparison]) -> AbSummary {
+    let pairs: Vec<(JudgeVerdict, JudgeVerdict)> = comparisons
+        .iter()
+        .filter_map(|c| Some((c.a.verdict?, c.b.verdict?)))
+        .collect();
+    let agreements = pairs.iter().filter(|(a, b)| a == b).count();
+
+    AbSummary {
+        evaluations: comparisons.len(),
+        compared: pairs.len(),
+        agreements,
+        agreement_rate: (!pairs.is_empty()).then(|| agreements as f64 / pairs.len() as f64),
+        flips: verdict_flips(&pairs),
+        version_a: VersionSummary::from_arms(version_a, comparisons.iter().map(|c| &c.a)),
+        version_b: VersionSummary::from_arms(version_b, comparisons.iter().map(|c| &c.b)),
+    }
+}
+
+/// Disagreements among `(A, B)` verdict pairs by direction, every direction listed.
+fn verdict_flips(pairs: &[(JudgeVerdict, JudgeVerdict)]) -> Vec<VerdictFlip> {
+    let verdicts = JudgeVerdict::ALL;
+    verdicts
+        .iter()
+        .flat_map(|from| verdicts.iter().filter(move |to| *to != from).map(move |to| (*from, *to)))
+        .map(|(from, to)| VerdictFlip {
+            from,
+            to,
+            count: pairs.iter().filter(|pair| **pair == (from, to)).count(),
+        })
+        .collect()
+}
+
+/// Verdict counts and the share of each; the shares are zero when there are no verdicts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct VerdictDistribution {
+    pub passed: i64,
+    pub failed: i64,
+    pub uncertain: i64,
+    pub pass_rate: f64,
+    pub fail_rate: f64,
+    pub uncertain_rate: f64,
+}
+
+impl VerdictDistribution {
+    pub fn new(passed: i64, failed: i64, uncertain: i64) -> Self {
+        let total = passed + failed + uncertain;
+        let share = |count: i64| if total > 0 { count as f64 / total as f64 } else { 0.0 };
+        VerdictDistribution {
+            passed,
+            failed,
+            uncertain,
+            pass_rate: share(passed),
+            fail_rate: share(failed),
+            uncertain_rate: share(uncertain),
+        }
+    }
+
+    fn of<'a>(verdicts: impl Iterator<Item = &'a JudgeVerdict>) -> Self {
+        let (mut passed, mut failed, mut uncertain) = (0, 0, 0);
+        for verdict in verdicts {
+            match verdict {
+                JudgeVerdict::Pass => passed += 1,
+                JudgeVerdict::Fail => failed += 1,
+                JudgeVerdict::Uncertain => uncertain += 1,
+            }
+        }
+        Self::new(passed, failed, uncertain)
+    }
+
+    /// How the shares moved from `self` to `other`.
+    fn deltas_to(&self, other: &VerdictDistribution) -> RateDeltas {
+        RateDeltas {
+            pass_rate: other.pass_rate - self.pass_rate,
+            fail_rate: other.fail_rate - self.fail_rate,
+            uncertain_rate: other.uncertain_rate - self.uncertain_rate,
+        }
+    }
+}
+
+/// Version B's verdict shares minus version A's.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct RateDeltas {
+    pub pass_rate: f64,
+    pub fail_rate: f64,
+    pub uncertain_rate: f64,
+}
+
+/// Evaluations judged under both versions, each version's latest verdict on them set side by side.
+#[derive(Debug, Clone, Serialize)]
+pub struct PairedVerdicts {
+    pub evaluations: usize,
+    pub agreements: usize,
+    /// `agreements / evaluations`; zero when no evaluation was judged under both.
+    pub agreement_rate: f64,
+    /// Disagreements by direction, A's verdict first. Every direction is listed, even at zero.
+    pub flips: Vec<VerdictFlip>,
+    pub version_a: VerdictDistribution,
+    pub version_b: VerdictDistribution,
+    pub deltas: RateDeltas,
+}
+
+/// Two judge prompt versions over every verdict each gave, and over the evaluations both judged.
+#[derive(Debug, Clone, Serialize)]
+pub struct VersionComparison {
+    pub version_a: JudgePromptStats,
+    pub version_b: JudgePromptStats,
+    /// Over all verdicts, which may be of different evaluations.
+    pub deltas: RateDeltas,
+    pub paired: PairedVerdicts,
+}
+
+/// Compares the stats of two versions, and their `(A, B)` verdicts on the evaluations both judged.
+pub fn compare_versions(
+    version_a: JudgePromptStats,
+    version_b: JudgePromptStats,
+    pairs: &[(JudgeVerdict, JudgeVerdict)],
+) -> VersionComparison {
+    let agreements = pairs.iter().filter(|(a, b)| a == b).count();
+    let paired_a = VerdictDistribution::of(pairs.iter().map(|(a, _)| a));
+    let paired_b = VerdictDistribution::of(pairs.iter().map(|(_, b)| b));
+    VersionComparison {
+        deltas: version_a.verdicts.deltas_to(&version_b.verdicts),
+        version_a,
+        version_b,
+        paired: PairedVerdicts {
+            evaluations: pairs.len(),
+            agreements,
+            agreement_rate: if pairs.is_empty() { 0.0 } else { agreements as f64 / pairs.len() as f64 },
+            flips: verdict_flips(pairs),
+            deltas: paired_a.deltas_to(&paired_b),
+            version_a: paired_a,
+            version_b: paired_b,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arm(verdict: Option<JudgeVerdict>, latency_ms: u64, cost_usd: Option<f64>) -> ArmJudgement {
+        ArmJudgement {
+            verdict,
+            latency_ms: verdict.map(|_| latency_ms),
+            cost_usd,
+            error: verdict.is_none().then(|| "judge unavailable".to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn comparison(a: ArmJudgement, b: ArmJudgement) -> AbComparison {
+        AbComparison {
+            evaluation_id: uuid::Uuid::new_v4().to_string(),
+            judge_model: "ollama:llama3".to_string(),
+            original_verdict: None,
+            a,
+            b,
+        }
+    }
+
+    #[test]
+    fn test_summarize_counts_agreement_and_flips() {
+        use JudgeVerdict::*;
+        let comparisons = vec![
+            comparison(arm(Some(Pass), 100, Some(0.01)), arm(Some(Pass), 200, Some(0.02))),
+            comparison(arm(Some(Pass), 100, Some(0.01)), arm(Some(Fail), 300, Some(0.02))),
+            comparison(arm(Some(Pass), 100, Some(0.01)), arm(Some(Fail), 100, None)),
+            comparison(arm(Some(Fail), 100, Some(0.01)), arm(Some(Pass), 200, Some(0.02))),
+            comparison(arm(Some(Fail), 100, Some(0.01)), arm(None, 0, None)),
+        ];
+
+        let summary = summarize(1, 2, &comparisons);
+        assert_eq!((summary.evaluations, summary.compared, summary.agreements), (5, 4, 1));
+        assert_eq!(summary.agreement_rate, Some(0.25));
+
+        let flip = |from: JudgeVerdict, to: JudgeVerdict| summary.flips.iter().find(|f| f.from == from && f.to == to).unwrap().count;
+        assert_eq!(summary.flips.len(), 6);
+        assert_eq!(flip(Pass, Fail), 2);
+        assert_eq!(flip(Fail, Pass), 1);
+        assert_eq!(flip(Pass, Uncertain), 0);
+
+        assert_eq!((summary.version_a.judged, summary.version_a.passed), (5, 3));
+        assert_eq!(summary.version_a.avg_latency_ms, Some(100.0));
+        assert_eq!((summary.version_b.judged, summary.version_b.errors), (4, 1));
+        assert_eq!(summary.version_b.avg_latency_ms, Some(200.0));
+        assert!((summary.version_b.total_cost_usd - 0.06).abs() < 1e-9);
+        assert!((summary.version_b.avg_cost_usd.unwrap() - 0.02).abs() < 1e-9);
+
+        assert_eq!(summarize(1, 2, &[]).agreement_rate, None);
+    }
+}
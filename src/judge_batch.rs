@@ -0,0 +1,353 @@
+// src/judge_batch.rs
+use futures::future;
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use crate::database::DbPool;
+
+use crate::config::{AppConfig, JudgeParams};
+use crate::errors::Result;
+use crate::providers::{Generation, TokenUsage};
+use crate::runner::{self, JudgeResult, JudgeVerdict, Judgement, PendingJudge, UncertainReason};
+
+/// Put before the items of a batched judge call; `{{count}}` is the number of items.
+const BATCH_PREAMBLE: &str = "You will evaluate {{count}} independent items. Each item below is a complete \
+evaluation task. Judge every item on its own, without letting the other items influence your verdict.";
+
+/// Put after the items, replacing the answer format each item's judge prompt asks for.
+const BATCH_RESPONSE_FORMAT: &str = r#"Ignore the answer format the items ask for. Respond with ONLY a JSON array holding one object per item, in item order:
+[{"item": 1, "verdict": "PASS", "confidence": 0.9, "reasoning": "..."}]
+"verdict" is "PASS", "FAIL" or "UNCERTAIN" and "confidence" is a number from 0 to 1."#;
+
+/// An eval waiting for its judge verdict in a batch.
+#[derive(Debug, Clone)]
+pub(crate) struct JudgeTask {
+    /// Position of the eval in its batch.
+    pub index: usize,
+    pub pending: PendingJudge,
+    pub model_output: String,
+}
+
+impl JudgeTask {
+    fn render(&self) -> String {
+        runner::render_judge_prompt(
+            &self.pending.template,
+            &self.pending.expected,
+            &self.model_output,
+            self.pending.criteria.as_deref(),
+        )
+    }
+}
+
+/// One prompt asking for a verdict on each of `prompts`, which are rendered judge prompts.
+pub fn build_batch_prompt(prompts: &[String]) -> String {
+    let mut batch = BATCH_PREAMBLE.replace("{{count}}", &prompts.len().to_string());
+    for (i, prompt) in prompts.iter().enumerate() {
+        batch.push_str(&format!("\n\n### Item {}\n\n{}", i + 1, prompt.trim()));
+    }
+    batch.push_str("\n\n### Response format\n\n");
+    batch.push_str(BATCH_RESPONSE_FORMAT);
+    batch
+}
+
+#[derive(Deserialize)]
+struct BatchItem {
+    item: Option<usize>,
+    verdict: Option<String>,
+    confidence: Option<f32>,
+    reasoning: Option<String>,
+}
+
+/// A verdict parsed out of a batched judge response.
+#[derive(Debug, Clone)]
+pub struct ParsedItem {
+    pub result: JudgeResult,
+    /// Length of the item's JSON in the response, used to split output tokens.
+    pub response_len: usize,
+}
+
+/// The JSON array in a judge response, allowing for text or a code fence around it.
+fn json_array(response: &str) -> Option<Vec<JsonValue>> {
+    let start = response.find('[')?;
+    let end = response.rfind(']')?;
+    match serde_json::from_str(response.get(start..=end)?).ok()? {
+        JsonValue::Array(items) => Some(items),
+        _ => None,
+    }
+}
+
+/// The verdict for each of `items` items in a batched judge response; `None` for items the
+/// response has no usable verdict for. Items are matched by their `item` number, or by
+/// position when it is missing.
+pub fn parse_batch_response(response: &str, items: usize) -> Vec<Option<ParsedItem>> {
+    let mut parsed = vec![None; items];
+    let Some(array) = json_array(response) else {
+        return parsed;
+    };
+
+    for (position, value) in array.into_iter().enumerate() {
+        let raw = value.to_string();
+        let Ok(item) = serde_json::from_value::<BatchItem>(value) else {
+            continue;
+        };
+        let index = item.item.and_then(|n| n.checked_sub(1)).unwrap_or(position);
+        let Some(slot) = parsed.get_mut(index) else {
+            continue;
+        };
+        if slot.is_some() {
+            continue;
+        }
+        let (verdict, uncertain_reason) = match item.verdict.as_deref().map(|v| v.trim().to_ascii_lowercase()).as_deref() {
+            Some("pass") => (JudgeVerdict::Pass, None),
+            Some("fail") => (JudgeVerdict::Fail, None),
+            Some("uncertain") => (JudgeVerdict::Uncertain, Some(UncertainReason::JudgeAbstained)),
+            _ => continue,
+        };
+        let confidence = item.confidence.map(|c| {
+            let c = if c > 1.0 { c / 100.0 } else { c };
+            c.clamp(0.0, 1.0)
+        });
+
+        *slot = Some(ParsedItem {
+            result: JudgeResult {
+                judge_model: "unknown".to_string(),
+                verdict,
+                reasoning: item.reasoning,
+                confidence,
+                raw_response: Some(raw.clone()),
+                uncertain_reason,
+            },
+            response_len: raw.len(),
+        });
+    }
+    parsed
+}
+
+/// Splits `total` in proportion to `weights` so the shares add up to `total` exactly.
+/// All-zero weights split it evenly.
+pub fn apportion(total: u32, weights: &[usize]) -> Vec<u32> {
+    if weights.is_empty() {
+        return Vec::new();
+    }
+    let weights: Vec<u64> = match weights.iter().sum::<usize>() {
+        0 => vec![1; weights.len()],
+        _ => weights.iter().map(|w| *w as u64).collect(),
+    };
+    let sum: u64 = weights.iter().sum();
+
+    let mut shares: Vec<u32> = weights.iter().map(|w| (total as u64 * w / sum) as u32).collect();
+    let mut remainder = total - shares.iter().sum::<u32>();
+    let mut by_fraction: Vec<usize> = (0..weights.len()).collect();
+    by_fraction.sort_by_key(|&i| std::cmp::Reverse(total as u64 * weights[i] % sum));
+    for i in by_fraction {
+        if remainder == 0 {
+            break;
+        }
+        shares[i] += 1;
+        remainder -= 1;
+    }
+    shares
+}
+
+/// Judges `prompts` with one call to `judge_model`, allowing `params.max_tokens` per item. Every
+/// verdict gets the latency of the whole call. Tokens are split over the items with a verdict in
+/// proportion to their prompt and response lengths. Items without a verdict, or all items when
+/// the call fails, come back `None`.
+pub async fn judge_batch(
+    config: &AppConfig,
+    client: &reqwest::Client,
+    judge_model: &str,
+    prompts: &[String],
+    params: &JudgeParams,
+    db_pool: Option<&DbPool>,
+) -> Vec<Option<Judgement>> {
+    let judge_model = config.resolve_model(judge_model);
+    let (provider_name, model_name) = runner::parse_model_string(judge_model);
+    let batch_prompt = build_batch_prompt(prompts);
+    let mut generation_params = params.generation_params();
+    generation_params.max_output_tokens = params.max_tokens.map(|tokens| tokens.saturating_mul(prompts.len() as u32));
+    let Generation { output: response, latency_ms, token_usage: usage, .. } =
+        match runner::call_provider_logged(config, client, &provider_name, &model_name, &batch_prompt, &generation_params, db_pool, None).await {
+            Ok(generation) => generation,
+            Err(e) => {
+                tracing::warn!("⚠️  Batched judge call for {} items failed: {}", prompts.len(), e);
+                return vec![None; prompts.len()];
+            }
+        };
+    tracing::info!("⚖️  Batched judge response for {} items ({}ms):\n{}", prompts.len(), latency_ms, &response);
+
+    let parsed: Vec<(usize, ParsedItem)> = parse_batch_response(&response, prompts.len())
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, item)| item.map(|item| (i, item)))
+        .collect();
+    let input_weights: Vec<usize> = parsed.iter().map(|(i, _)| prompts[*i].len()).collect();
+    let output_weights: Vec<usize> = parsed.iter().map(|(_, item)| item.response_len).collect();
+    let input_tokens = usage.input_tokens.map(|total| apportion(total, &input_weights));
+    let output_tokens = usage.output_tokens.map(|total| apportion(total, &output_weights));
+
+    let mut judgements = vec![None; prompts.len()];
+    for (k, (i, item)) in parsed.into_iter().enumerate() {
+        let mut result = item.result;
+        result.judge_model = judge_model.to_string();
+        let token_usage = TokenUsage {
+            input_tokens: input_tokens.as_ref().map(|shares| shares[k]),
+            output_tokens: output_tokens.as_ref().map(|shares| shares[k]),
+        };
+        judgements[i] = Some(Judgement {
+            cost_usd: config.pricing.cost(&format!("{}:{}", provider_name, model_name), &token_usage),
+            result,
+            prompt: prompts[i].clone(),
+            params: *params,
+            latency_ms,
+            token_usage,
+        });
+    }
+    judgements
+}
+
+/// Runs the judge calls of `tasks`, up to `batch_size` tasks per call for each judge model and
+/// judge params. Tasks a batched call returns no verdict for are judged again with a call of
+/// their own, as are Uncertain verdicts of tasks whose params retry them. Returns each task's
+/// judgement with the size of the batch it was judged in, `None` when judged on its own.
+pub(crate) async fn run_judges(
+    config: &AppConfig,
+    client: &reqwest::Client,
+    db_pool: Option<&DbPool>,
+    batch_size: usize,
+    tasks: &[JudgeTask],
+) -> Vec<(Result<Judgement>, Option<usize>)> {
+    let mut groups: Vec<((&str, JudgeParams), Vec<usize>)> = Vec::new();
+    for (i, task) in tasks.iter().enumerate() {
+        let key = (task.pending.judge_model.as_str(), task.pending.params);
+        match groups.iter_mut().find(|(group, _)| *group == key) {
+            Some((_, members)) => members.push(i),
+            None => groups.push((key, vec![i])),
+        }
+    }
+    let batches: Vec<(&(&str, JudgeParams), &[usize])> = groups
+        .iter()
+        .flat_map(|(key, members)| members.chunks(batch_size.max(1)).map(move |chunk| (key, chunk)))
+        .filter(|(_, chunk)| chunk.len() > 1)
+        .collect();
+
+    let batched = future::join_all(batches.iter().map(|&(&(judge_model, params), chunk)| async move {
+        let prompts: Vec<String> = chunk.iter().map(|&i| tasks[i].render()).collect();
+        (chunk, judge_batch(config, client, judge_model, &prompts, &params, db_pool).await)
+    }))
+    .await;
+
+    let mut outcomes: Vec<Option<(Result<Judgement>, Option<usize>)>> = (0..tasks.len()).map(|_| None).collect();
+    let mut uncertain: Vec<Option<(Judgement, usize)>> = vec![None; tasks.len()];
+    for (chunk, judgements) in batched {
+        for (&i, judgement) in chunk.iter().zip(judgements) {
+            match judgement {
+                Some(judgement) if judgement.result.verdict == JudgeVerdict::Uncertain && judgement.params.retries_uncertain() => {
+                    uncertain[i] = Some((judgement, chunk.len()));
+                }
+                Some(judgement) => outcomes[i] = Some((Ok(judgement), Some(chunk.len()))),
+                None => {}
+            }
+        }
+    }
+
+    let individual: Vec<usize> = (0..tasks.len()).filter(|&i| outcomes[i].is_none()).collect();
+    let batched_tasks: usize = batches.iter().map(|(_, chunk)| chunk.len()).sum();
+    let retries = uncertain.iter().flatten().count();
+    let fallbacks = individual.len() + batched_tasks - tasks.len() - retries;
+    if fallbacks > 0 {
+        tracing::info!("🔁 {} batched verdicts could not be parsed; judging them individually", fallbacks);
+    }
+    if retries > 0 {
+        tracing::info!("🔁 {} batched verdicts were Uncertain; asking the judge again individually", retries);
+    }
+    let singles = future::join_all(individual.iter().map(|&i| {
+        let task = &tasks[i];
+        // A verdict that is already a retry is not retried again
+        let params = match uncertain[i] {
+            Some(_) => JudgeParams { retry_uncertain: Some(false), ..task.pending.params },
+            None => task.pending.params,
+        };
+        async move {
+            runner::judge_logged(
+                config,
+                client,
+                &task.pending.judge_model,
+                &task.pending.template,
+                &task.pending.expected,
+                &task.model_output,
+                task.pending.criteria.as_deref(),
+                &params,
+                db_pool,
+                None,
+                false,
+            )
+            .await
+        }
+    }))
+    .await;
+    for (i, judgement) in individual.into_iter().zip(singles) {
+        let outcome = match (uncertain[i].take(), judgement) {
+            (Some((first, _)), Ok(retry)) => (Ok(first.retried(retry)), None),
+            (Some((first, batch_size)), Err(e)) => {
+                tracing::warn!("⚠️  Judge retry failed, keeping the Uncertain verdict: {}", e);
+                (Ok(first), Some(batch_size))
+            }
+            (None, judgement) => (judgement, None),
+        };
+        outcomes[i] = Some(outcome);
+    }
+
+    outcomes.into_iter().map(|outcome| outcome.expect("every task is judged")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_batch_prompt_numbers_items() {
+        let prompt = build_batch_prompt(&["first judge prompt".to_string(), "second judge prompt".to_string()]);
+        assert!(prompt.starts_with("You will evaluate 2 independent items."));
+        let first = prompt.find("### Item 1\n\nfirst judge prompt").unwrap();
+        let second = prompt.find("### Item 2\n\nsecond judge prompt").unwrap();
+        assert!(first < second && second < prompt.find("### Response format").unwrap());
+    }
+
+    #[test]
+    fn test_parse_batch_response_matches_items_and_skips_unusable_ones() {
+        let response = r#"Here are the verdicts:
+```json
+[
+  {"item": 2, "verdict": "FAIL", "confidence": 80, "reasoning": "Wrong number"},
+  {"item": 1, "verdict": "pass", "confidence": 0.9, "reasoning": "Same answer"},
+  {"item": 3, "verdict": "maybe"},
+  {"item": 1, "verdict": "FAIL"},
+  {"item": 9, "verdict": "PASS"}
+]
+```"#;
+        let parsed = parse_batch_response(response, 4);
+        assert_eq!(parsed.len(), 4);
+
+        let first = parsed[0].as_ref().unwrap();
+        assert_eq!(first.result.verdict, JudgeVerdict::Pass);
+        assert_eq!(first.result.confidence, Some(0.9));
+        assert_eq!(first.result.reasoning.as_deref(), Some("Same answer"));
+        let second = parsed[1].as_ref().unwrap();
+        assert_eq!(second.result.verdict, JudgeVerdict::Fail);
+        assert_eq!(second.result.confidence, Some(0.8));
+        // An unknown verdict and a missing item both fall back to individual judging
+        assert!(parsed[2].is_none());
+        assert!(parsed[3].is_none());
+
+        assert!(parse_batch_response("Verdict: PASS", 2).iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn test_apportion_preserves_the_total() {
+        assert_eq!(apportion(100, &[1, 1, 2]), [25, 25, 50]);
+        let shares = apportion(10, &[1, 1, 1]);
+        assert_eq!(shares.iter().sum::<u32>(), 10);
+        assert_eq!(apportion(7, &[0, 0]), [4, 3]);
+        assert!(apportion(5, &[]).is_empty());
+    }
+}
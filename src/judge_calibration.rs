@@ -0,0 +1,188 @@
+// src/judge_calibration.rs
+//! Calibration of a judge prompt version against human verdicts: how often the judge agrees
+//! with reviewers, and whether its verdicts move with the order of the outputs it compares
+//! or with the length of the output.
+use serde::{Deserialize, Serialize};
+use crate::config::AppConfig;
+use crate::database::{DbPool, HistoryEntry};
+use crate::runner::{self, JudgeVerdict};
+use crate::stats::{self, VerdictAgreement};
+
+/// One reviewed evaluation judged again for a calibration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalibrationSample {
+    pub evaluation_id: String,
+    pub judge_model: String,
+    pub human_verdict: JudgeVerdict,
+    /// The judge's verdict on the stored output; `None` when the judge call failed.
+    pub verdict: Option<JudgeVerdict>,
+    /// The judge's verdict with the expected and actual outputs swapped.
+    pub swapped_verdict: Option<JudgeVerdict>,
+    /// Length of the stored output in characters.
+    pub output_chars: usize,
+    #[serde(default)]
+    pub cost_usd: f64,
+    /// Why a judge call failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// How much the judge's verdicts depend on which side of the prompt each output is on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct PositionBias {
+    /// Evaluations with a verdict in both orders.
+    pub compared: usize,
+    pub flips: usize,
+    /// `flips / compared`; an unbiased judge of symmetric criteria keeps its verdict.
+    pub flip_rate: Option<f64>,
+}
+
+/// How the judge's verdicts follow the length of the output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct VerbosityBias {
+    /// Correlation of output length with a Pass verdict, from -1 to 1; positive when longer
+    /// outputs pass more often. `None` when lengths or verdicts never vary.
+    pub length_pass_correlation: Option<f64>,
+    /// The same correlation with the human verdicts, against which the judge's is read.
+    pub human_length_pass_correlation: Option<f64>,
+    pub mean_chars_passed: Option<f64>,
+    pub mean_chars_failed: Option<f64>,
+}
+
+/// The statistics of a calibration.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CalibrationSummary {
+    pub evaluations: usize,
+    /// Evaluations whose judge call failed.
+    pub errors: usize,
+    #[serde(flatten)]
+    pub agreement: VerdictAgreement,
+    pub position_bias: PositionBias,
+    pub verbosity_bias: VerbosityBias,
+    pub total_cost_usd: f64,
+}
+
+/// The human verdict of `entry` when a reviewer passed or failed it.
+pub fn human_verdict(entry: &HistoryEntry) -> Option<JudgeVerdict> {
+    entry
+        .human_verdict
+        .as_deref()
+        .and_then(JudgeVerdict::parse)
+        .filter(|verdict| *verdict != JudgeVerdict::Uncertain)
+}
+
+/// Judges the stored output of a reviewed evaluation with `template`, once as it was and once
+/// with the expected and actual outputs swapped. Nothing is regenerated or saved.
+#[allow(clippy::too_many_arguments)]
+pub async fn calibrate_one(
+    config: &AppConfig,
+    client: &reqwest::Client,
+    db_pool: Option<&DbPool>,
+    entry: &HistoryEntry,
+    human_verdict: JudgeVerdict,
+    judge_model: &str,
+    model_output: &str,
+    expected: &str,
+    template: &str,
+) -> CalibrationSample {
+    let criteria = entry.criteria.as_deref();
+    let (judged, swapped) = tokio::join!(
+        runner::judge_only(config, client, judge_model, template, expected, model_output, criteria, db_pool),
+        runner::judge_only(config, client, judge_model, template, model_output, expected, criteria, db_pool),
+    );
+    let cost_usd = [&judged, &swapped]
+        .iter()
+        .filter_map(|judgement| judgement.as_ref().ok().and_then(|j| j.cost_usd))
+        .sum();
+    let error = [&judged, &swapped].iter().find_map(|judgement| judgement.as_ref().err().map(|e| e.to_string()));
+    CalibrationSample {
+        evaluation_id: entry.id.clone(),
+        judge_model: judge_model.to_string(),
+        human_verdict,
+        verdict: judged.ok().map(|j| j.result.verdict),
+        swapped_verdict: swapped.ok().map(|j| j.result.verdict),
+        output_chars: model_output.chars().count(),
+        cost_usd,
+        error,
+    }
+}
+
+fn mean(values: &[f64]) -> Option<f64> {
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+/// Agreement with the human verdicts and both bias probes over `samples`.
+pub fn summarize(samples: &[CalibrationSample]) -> CalibrationSummary {
+    let judged: Vec<_> = samples.iter().filter_map(|s| Some((s, s.verdict?))).collect();
+    let pairs: Vec<_> = judged.iter().map(|(s, verdict)| (s.human_verdict, *verdict)).collect();
+    let orders: Vec<_> = samples.iter().filter_map(|s| Some((s.verdict?, s.swapped_verdict?))).collect();
+
+    let passes = |verdict: JudgeVerdict| if verdict == JudgeVerdict::Pass { 1.0 } else { 0.0 };
+    let lengths = |verdict: JudgeVerdict| {
+        judged.iter().filter(|(_, v)| *v == verdict).map(|(s, _)| s.output_chars as f64).collect::<Vec<_>>()
+    };
+    let judge_points: Vec<_> = judged.iter().map(|(s, verdict)| (s.output_chars as f64, passes(*verdict))).collect();
+    let human_points: Vec<_> = samples.iter().map(|s| (s.output_chars as f64, passes(s.human_verdict))).collect();
+
+    CalibrationSummary {
+        evaluations: samples.len(),
+        errors: samples.iter().filter(|s| s.error.is_some()).count(),
+        agreement: stats::verdict_agreement(&pairs),
+        position_bias: PositionBias {
+            compared: orders.len(),
+            flips: orders.iter().filter(|(a, b)| a != b).count(),
+            flip_rate: stats::flip_rate(&orders),
+        },
+        verbosity_bias: VerbosityBias {
+            length_pass_correlation: stats::pearson_correlation(&judge_points),
+            human_length_pass_correlation: stats::pearson_correlation(&human_points),
+            mean_chars_passed: mean(&lengths(JudgeVerdict::Pass)),
+            mean_chars_failed: mean(&lengths(JudgeVerdict::Fail)),
+        },
+        total_cost_usd: samples.iter().map(|s| s.cost_usd).sum(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use JudgeVerdict::*;
+
+    fn sample(human: JudgeVerdict, verdict: Option<JudgeVerdict>, swapped: Option<JudgeVerdict>, output_chars: usize) -> CalibrationSample {
+        CalibrationSample {
+            evaluation_id: uuid::Uuid::new_v4().to_string(),
+            judge_model: "ollama:llama3".to_string(),
+            human_verdict: human,
+            verdict,
+            swapped_verdict: swapped,
+            output_chars,
+            cost_usd: 0.001,
+            error: verdict.is_none().then(|| "judge unavailable".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_summarize_reports_agreement_and_both_probes() {
+        let samples = [
+            sample(Pass, Some(Pass), Some(Pass), 800),
+            sample(Fail, Some(Pass), Some(Fail), 900),
+            sample(Fail, Some(Fail), Some(Fail), 100),
+            sample(Pass, Some(Fail), Some(Pass), 120),
+            sample(Pass, None, None, 500),
+        ];
+        let summary = summarize(&samples);
+        assert_eq!((summary.evaluations, summary.errors), (5, 1));
+        assert_eq!((summary.agreement.judged, summary.agreement.correct), (4, 2));
+        assert_eq!(summary.agreement.false_pass_rate, Some(0.5));
+        assert_eq!(summary.agreement.false_fail_rate, Some(0.5));
+        assert_eq!(summary.position_bias, PositionBias { compared: 4, flips: 2, flip_rate: Some(0.5) });
+        // The judge passes the long outputs, the reviewers did not care for length
+        let bias = summary.verbosity_bias;
+        assert!(bias.length_pass_correlation.unwrap() > 0.9, "{:?}", bias);
+        assert!(bias.human_length_pass_correlation.unwrap().abs() < bias.length_pass_correlation.unwrap());
+        assert_eq!((bias.mean_chars_passed, bias.mean_chars_failed), (Some(850.0), Some(110.0)));
+        assert!((summary.total_cost_usd - 0.005).abs() < 1e-9);
+
+        assert_eq!(summarize(&[]).agreement.accuracy, None);
+    }
+}
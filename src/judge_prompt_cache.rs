@@ -0,0 +1,116 @@
+// src/judge_prompt_cache.rs
+//! The judge prompts evals are judged with, loaded from the database once and reused until a
+//! judge prompt or canary change invalidates them.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::database::{self, DbPool, JudgePrompt};
+use crate::runner::{self, JudgePromptArm};
+
+/// The active judge prompt and running canary as they were when the snapshot was taken. A
+/// batch judges all of its evals from one snapshot, so a prompt change mid-batch does not
+/// split it between prompts.
+#[derive(Debug, Clone, Default)]
+pub struct JudgePromptSnapshot {
+    /// The active prompt; `None` judges with the built-in template.
+    active: Option<JudgePrompt>,
+    /// Share of evals (0-100) the running canary judges with its candidate.
+    canary_percentage: Option<f64>,
+    /// The canary's candidate; `None` when it could not be loaded, which judges the canary
+    /// arm with the active prompt.
+    candidate: Option<JudgePrompt>,
+}
+
+impl JudgePromptSnapshot {
+    /// Loads the active prompt and running canary. Without a database, or when the active
+    /// prompt cannot be loaded, evals are judged with the built-in template.
+    pub async fn load(db_pool: Option<&DbPool>) -> Self {
+        let Some(pool) = db_pool else {
+            tracing::info!("📋 Using default judge prompt template");
+            return JudgePromptSnapshot::default();
+        };
+
+        let mut snapshot = JudgePromptSnapshot::default();
+        match database::get_active_canary(pool).await {
+            Ok(Some(canary)) => {
+                snapshot.canary_percentage = Some(canary.percentage);
+                match database::get_judge_prompt_by_version(pool, canary.candidate_version).await {
+                    Ok(prompt) => {
+                        tracing::info!("🐤 Using canary judge prompt v{} for {}% of evals: {}", prompt.version, canary.percentage, prompt.name);
+                        snapshot.candidate = Some(prompt);
+                    }
+                    Err(e) => {
+                        tracing::warn!("Could not load canary judge prompt v{}: {}. Using active prompt.", canary.candidate_version, e);
+                    }
+                }
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Could not load judge prompt canary: {}", e),
+        }
+
+        match database::get_active_judge_prompt(pool).await {
+            Ok(prompt) => {
+                tracing::info!("📋 Using judge prompt v{}: {}", prompt.version, prompt.name);
+                snapshot.active = Some(prompt);
+            }
+            Err(e) => {
+                tracing::warn!("Could not load judge prompt from database: {}. Using default.", e);
+            }
+        }
+        snapshot
+    }
+
+    /// The template, version and canary arm an eval is judged with. While a canary is running,
+    /// evals whose id falls in its percentage get the candidate; everything else gets the
+    /// active prompt. Evals without an id always get the active one.
+    pub fn template_for(&self, eval_id: Option<&str>) -> (String, Option<i64>, Option<JudgePromptArm>) {
+        let arm = self.canary_percentage.zip(eval_id).map(|(percentage, id)| {
+            if runner::in_canary(id, percentage) { JudgePromptArm::Canary } else { JudgePromptArm::Control }
+        });
+        if let (Some(JudgePromptArm::Canary), Some(candidate)) = (arm, &self.candidate) {
+            return (candidate.template.clone(), Some(candidate.version), arm);
+        }
+        match &self.active {
+            Some(prompt) => (prompt.template.clone(), Some(prompt.version), arm.map(|_| JudgePromptArm::Control)),
+            None => (runner::get_default_judge_prompt_template(), None, None),
+        }
+    }
+}
+
+/// The current `JudgePromptSnapshot`, loaded on first use. Clones of the config share the
+/// cache, so every eval and batch of the server reuses one load until the judge prompt
+/// handlers call `invalidate`.
+#[derive(Debug, Default)]
+pub struct JudgePromptCache {
+    snapshot: Mutex<Option<Arc<JudgePromptSnapshot>>>,
+    /// Bumped by every invalidation, so a load that raced one is not cached.
+    generation: AtomicU64,
+}
+
+impl JudgePromptCache {
+    /// The cached snapshot, loading it when there is none. Nothing is cached without a database.
+    pub async fn snapshot(&self, db_pool: Option<&DbPool>) -> Arc<JudgePromptSnapshot> {
+        if db_pool.is_none() {
+            return Arc::new(JudgePromptSnapshot::load(None).await);
+        }
+        if let Some(snapshot) = self.snapshot.lock().unwrap().clone() {
+            return snapshot;
+        }
+        let generation = self.generation.load(Ordering::SeqCst);
+        let loaded = Arc::new(JudgePromptSnapshot::load(db_pool).await);
+        let mut cached = self.snapshot.lock().unwrap();
+        if self.generation.load(Ordering::SeqCst) == generation {
+            *cached = Some(loaded.clone());
+        }
+        loaded
+    }
+
+    /// Drops the cached snapshot, so the next eval or batch loads the prompts again. Snapshots
+    /// already handed out are unaffected.
+    pub fn invalidate(&self) {
+        let mut cached = self.snapshot.lock().unwrap();
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        *cached = None;
+    }
+}
@@ -0,0 +1,263 @@
+// src/leaderboard.rs
+use serde::{Deserialize, Serialize};
+
+use crate::database::LeaderboardEntry;
+
+/// Judged evaluations a model needs before it is ranked, when `min_samples` is not given.
+pub const DEFAULT_MIN_SAMPLES: u32 = 10;
+
+/// z for a two-sided 95% confidence interval.
+const Z_95: f64 = 1.959_963_984_540_054;
+
+/// What the leaderboard ranks models by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RankingMetric {
+    PassRate,
+    AvgScore,
+    CostPerPass,
+}
+
+/// How a metric is computed, returned with every leaderboard so it explains itself.
+#[derive(Debug, Serialize)]
+pub struct MetricDefinition {
+    pub name: &'static str,
+    pub definition: &'static str,
+    pub higher_is_better: bool,
+    /// How `confidence_interval` is computed; `None` when the metric has none.
+    pub confidence_interval: Option<&'static str>,
+}
+
+impl RankingMetric {
+    pub fn definition(self) -> MetricDefinition {
+        match self {
+            RankingMetric::PassRate => MetricDefinition {
+                name: "pass_rate",
+                definition: "Pass verdicts divided by judged evaluations",
+                higher_is_better: true,
+                confidence_interval: Some("95% Wilson score interval"),
+            },
+            RankingMetric::AvgScore => MetricDefinition {
+                name: "avg_score",
+                definition: "Mean score of judged evaluations, scoring Pass as 1, Uncertain as 0.5 and Fail as 0",
+                higher_is_better: true,
+                confidence_interval: Some("95% normal approximation, clamped to [0, 1]"),
+            },
+            RankingMetric::CostPerPass => MetricDefinition {
+                name: "cost_per_pass",
+                definition: "Model plus judge cost (USD) of all priced evaluations divided by Pass verdicts",
+                higher_is_better: false,
+                confidence_interval: None,
+            },
+        }
+    }
+
+    fn value(self, entry: &LeaderboardEntry) -> Option<f64> {
+        match self {
+            RankingMetric::PassRate => entry.pass_rate,
+            RankingMetric::AvgScore => avg_score(entry),
+            RankingMetric::CostPerPass => cost_per_pass(entry),
+        }
+    }
+
+    fn confidence_interval(self, entry: &LeaderboardEntry) -> Option<ConfidenceInterval> {
+        match self {
+            RankingMetric::PassRate => wilson_interval(entry.passed_evaluations, entry.judged_evaluations),
+            RankingMetric::AvgScore => score_interval(entry),
+            RankingMetric::CostPerPass => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ConfidenceInterval {
+    pub low: f64,
+    pub high: f64,
+}
+
+/// 95% Wilson score interval of a proportion; `None` without trials.
+pub fn wilson_interval(successes: i64, trials: i64) -> Option<ConfidenceInterval> {
+    if trials <= 0 {
+        return None;
+    }
+    let n = trials as f64;
+    let p = successes as f64 / n;
+    let z2 = Z_95 * Z_95;
+    let denominator = 1.0 + z2 / n;
+    let center = (p + z2 / (2.0 * n)) / denominator;
+    let margin = Z_95 * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt() / denominator;
+    Some(ConfidenceInterval { low: (center - margin).max(0.0), high: (center + margin).min(1.0) })
+}
+
+fn avg_score(entry: &LeaderboardEntry) -> Option<f64> {
+    (entry.judged_evaluations > 0).then(|| {
+        (entry.passed_evaluations as f64 + 0.5 * entry.uncertain_evaluations as f64) / entry.judged_evaluations as f64
+    })
+}
+
+fn score_interval(entry: &LeaderboardEntry) -> Option<ConfidenceInterval> {
+    let mean = avg_score(entry)?;
+    let n = entry.judged_evaluations as f64;
+    let mean_of_squares = (entry.passed_evaluations as f64 + 0.25 * entry.uncertain_evaluations as f64) / n;
+    let margin = Z_95 * ((mean_of_squares - mean * mean).max(0.0) / n).sqrt();
+    Some(ConfidenceInterval { low: (mean - margin).max(0.0), high: (mean + margin).min(1.0) })
+}
+
+fn cost_per_pass(entry: &LeaderboardEntry) -> Option<f64> {
+    let total = entry.total_cost_usd?;
+    (entry.passed_evaluations > 0).then(|| total / entry.passed_evaluations as f64)
+}
+
+#[derive(Debug, Serialize)]
+pub struct RankedModel {
+    /// Shared by models with equal values, skipping the ranks after a tie (1, 2, 2, 4).
+    /// `None` for models below the minimum sample size or without a value.
+    pub rank: Option<usize>,
+    pub model: String,
+    pub value: Option<f64>,
+    pub confidence_interval: Option<ConfidenceInterval>,
+    pub evaluations: i64,
+    pub judged_evaluations: i64,
+    pub pass_rate: Option<f64>,
+    pub p95_latency_ms: Option<i64>,
+    pub cost_per_pass_usd: Option<f64>,
+    pub below_min_samples: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Leaderboard {
+    pub metric: MetricDefinition,
+    pub min_samples: u32,
+    /// Ranked models first, then the unranked ones, each by value.
+    pub models: Vec<RankedModel>,
+}
+
+/// Ranks per-model aggregates by `metric`. Models with fewer than `min_samples` judged
+/// evaluations are listed, flagged and unranked, so small samples cannot top the table.
+pub fn rank(entries: Vec<LeaderboardEntry>, metric: RankingMetric, min_samples: u32) -> Leaderboard {
+    let definition = metric.definition();
+    let mut models: Vec<RankedModel> = entries
+        .into_iter()
+        .map(|entry| RankedModel {
+            rank: None,
+            value: metric.value(&entry),
+            confidence_interval: metric.confidence_interval(&entry),
+            evaluations: entry.total_evaluations,
+            judged_evaluations: entry.judged_evaluations,
+            pass_rate: entry.pass_rate,
+            p95_latency_ms: entry.p95_latency_ms,
+            cost_per_pass_usd: cost_per_pass(&entry),
+            below_min_samples: entry.judged_evaluations < i64::from(min_samples),
+            model: entry.model,
+        })
+        .collect();
+
+    // Better values first; missing values last
+    let key = |model: &RankedModel| model.value.map(|v| if definition.higher_is_better { -v } else { v });
+    let rankable = |model: &RankedModel| !model.below_min_samples && model.value.is_some();
+    models.sort_by(|a, b| {
+        rankable(b)
+            .cmp(&rankable(a))
+            .then_with(|| match (key(a), key(b)) {
+                (Some(x), Some(y)) => x.total_cmp(&y),
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => std::cmp::Ordering::Equal,
+            })
+            .then_with(|| a.model.cmp(&b.model))
+    });
+
+    let mut previous: Option<(f64, usize)> = None;
+    for (i, model) in models.iter_mut().enumerate() {
+        if !rankable(model) {
+            break;
+        }
+        let value = model.value.unwrap_or_default();
+        let rank = match previous {
+            Some((v, rank)) if v == value => rank,
+            _ => i + 1,
+        };
+        model.rank = Some(rank);
+        previous = Some((value, rank));
+    }
+
+    Leaderboard { metric: definition, min_samples, models }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(model: &str, judged: i64, passed: i64, uncertain: i64, total_cost_usd: Option<f64>) -> LeaderboardEntry {
+        LeaderboardEntry {
+            model: model.to_string(),
+            total_evaluations: judged,
+            judged_evaluations: judged,
+            passed_evaluations: passed,
+            uncertain_evaluations: uncertain,
+            pass_rate: (judged > 0).then(|| passed as f64 / judged as f64),
+            uncertain_rate: (judged > 0).then(|| uncertain as f64 / judged as f64),
+            avg_latency_ms: None,
+            p95_latency_ms: None,
+            avg_output_tokens: None,
+            avg_cost_usd: None,
+            total_cost_usd,
+        }
+    }
+
+    #[test]
+    fn test_wilson_interval_matches_reference_values() {
+        let interval = wilson_interval(8, 10).unwrap();
+        assert!((interval.low - 0.4902).abs() < 1e-4, "{:?}", interval);
+        assert!((interval.high - 0.9433).abs() < 1e-4, "{:?}", interval);
+
+        let all = wilson_interval(5, 5).unwrap();
+        assert!(all.low > 0.5 && all.high == 1.0);
+        assert_eq!(wilson_interval(0, 0), None);
+    }
+
+    #[test]
+    fn test_ties_share_a_rank_and_small_samples_are_flagged() {
+        let entries = vec![
+            entry("b", 10, 8, 0, None),
+            entry("a", 20, 16, 0, None),
+            entry("c", 10, 5, 0, None),
+            entry("tiny", 2, 2, 0, None),
+        ];
+        let board = rank(entries, RankingMetric::PassRate, 10);
+
+        let ranks: Vec<(&str, Option<usize>)> = board.models.iter().map(|m| (m.model.as_str(), m.rank)).collect();
+        assert_eq!(ranks, [("a", Some(1)), ("b", Some(1)), ("c", Some(3)), ("tiny", None)]);
+        assert!(board.models[3].below_min_samples);
+        assert_eq!(board.metric.name, "pass_rate");
+        // More samples narrow the interval around the same rate
+        let (a, b) = (board.models[0].confidence_interval.unwrap(), board.models[1].confidence_interval.unwrap());
+        assert!(a.high - a.low < b.high - b.low);
+    }
+
+    #[test]
+    fn test_cost_per_pass_ranks_cheapest_first_and_needs_a_pass() {
+        let entries = vec![
+            entry("pricey", 10, 5, 0, Some(1.0)),
+            entry("cheap", 10, 4, 0, Some(0.4)),
+            entry("never_passes", 10, 0, 0, Some(0.1)),
+            entry("unpriced", 10, 9, 0, None),
+        ];
+        let board = rank(entries, RankingMetric::CostPerPass, 1);
+
+        let ranked: Vec<(&str, Option<usize>)> = board.models.iter().map(|m| (m.model.as_str(), m.rank)).collect();
+        assert_eq!(ranked[..2], [("cheap", Some(1)), ("pricey", Some(2))]);
+        assert!((board.models[0].value.unwrap() - 0.1).abs() < 1e-9);
+        assert!(board.models[2..].iter().all(|m| m.rank.is_none() && m.value.is_none()));
+        assert!(!board.metric.higher_is_better);
+    }
+
+    #[test]
+    fn test_avg_score_counts_uncertain_as_half() {
+        let board = rank(vec![entry("m", 4, 2, 2, None)], RankingMetric::AvgScore, 1);
+        let model = &board.models[0];
+        assert_eq!(model.value, Some(0.75));
+        let interval = model.confidence_interval.unwrap();
+        assert!(interval.low < 0.75 && interval.high > 0.75 && interval.high <= 1.0);
+    }
+}
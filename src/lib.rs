@@ -1,9 +1,52 @@
 // src/lib.rs
+pub mod budget;
+pub mod chain;
+pub mod cli;
+pub mod compare;
+pub mod dataset;
+pub mod diff;
+pub mod drift;
 pub mod config;
+pub mod config_file;
+pub mod failure_taxonomy;
+pub mod format_rule;
+pub mod health;
+pub mod http_client;
+pub mod jobs;
+pub mod judge_ab;
+pub mod judge_batch;
+pub mod judge_calibration;
+pub mod judge_prompt_cache;
+pub mod leaderboard;
+pub mod logging;
+pub mod match_rule;
+pub mod matrix;
+pub mod notifier;
+pub mod output_parser;
+pub mod preset;
+pub mod pricing;
+pub mod provider_override;
+pub mod reasoning;
+pub mod redact;
+pub mod rejudge;
+pub mod report;
+pub mod robustness;
+pub mod scheduler;
+pub mod self_consistency;
+pub mod share;
+pub mod stats;
+pub mod template;
+pub mod text_diff;
+pub mod trace;
+pub mod truncation;
+pub mod webhook;
 pub mod errors;
 pub mod providers;
 pub mod runner;
 pub mod models;
 pub mod database;
+pub mod backup;
+pub mod retention;
 pub mod banner;
+pub mod static_files;
 pub mod api;
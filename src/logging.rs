@@ -0,0 +1,53 @@
+// src/logging.rs
+use tracing_subscriber::{fmt, fmt::MakeWriter, EnvFilter};
+
+use crate::redact::Redacting;
+
+/// Filter used when `RUST_LOG` is unset.
+const DEFAULT_FILTER: &str = "info,actix_web=warn";
+
+/// How log lines are written, picked with `LOG_FORMAT`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human-readable lines prefixed with the spans they happened in (the default).
+    Pretty,
+    /// One JSON object per line, carrying the fields of the current span and its parents.
+    Json,
+}
+
+impl LogFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "pretty" | "text" => Some(LogFormat::Pretty),
+            "json" => Some(LogFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Installs the global subscriber, filtered by `RUST_LOG` and formatted by `LOG_FORMAT`.
+/// Records of dependencies logging through the `log` crate are forwarded to it.
+pub fn init() {
+    init_with_writer(std::io::stdout);
+}
+
+/// Like `init`, writing log lines to `writer`. The CLI logs to stderr, keeping stdout for results.
+/// Secrets are masked from every line; see `redact::redact`.
+pub fn init_with_writer<W>(writer: W)
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let requested = std::env::var("LOG_FORMAT").ok();
+    let format = requested.as_deref().and_then(LogFormat::parse).unwrap_or(LogFormat::Pretty);
+
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_FILTER));
+    let builder = fmt().with_env_filter(filter).with_writer(Redacting(writer));
+    match format {
+        LogFormat::Pretty => builder.with_target(false).init(),
+        LogFormat::Json => builder.json().flatten_event(true).with_current_span(true).with_span_list(true).init(),
+    }
+
+    if let Some(value) = requested.filter(|value| LogFormat::parse(value).is_none()) {
+        tracing::warn!("⚠️  Unknown LOG_FORMAT '{}'; expected 'pretty' or 'json'. Using pretty.", value);
+    }
+}
@@ -1,23 +1,64 @@
 // src/main.rs
+mod budget;
+mod chain;
+mod cli;
+mod compare;
+mod dataset;
+mod diff;
+mod drift;
 mod config;
+mod config_file;
+mod failure_taxonomy;
+mod format_rule;
+mod health;
+mod http_client;
+mod jobs;
+mod judge_ab;
+mod judge_batch;
+mod judge_calibration;
+mod judge_prompt_cache;
+mod leaderboard;
+mod logging;
+mod match_rule;
+mod matrix;
+mod notifier;
+mod output_parser;
+mod preset;
+mod pricing;
+mod provider_override;
+mod reasoning;
+mod redact;
+mod rejudge;
+mod report;
+mod robustness;
+mod scheduler;
+mod self_consistency;
+mod share;
+mod stats;
+mod template;
+mod text_diff;
+mod trace;
+mod truncation;
+mod webhook;
 mod api;
 mod errors;
 mod providers;
 mod runner;
 mod models;
 mod database;
+mod backup;
+mod retention;
 mod banner;
+mod static_files;
  
-use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer, middleware, Responder};
+use actix_web::{web, App, HttpServer, middleware};
 use actix_cors::Cors;
-use api::{configure_routes, AppState};
+use api::{routes_with_body_limit, AppState};
 use api::handlers::WsBroker;
-use rust_embed::RustEmbed;
-use std::borrow::Cow;
-
-#[derive(RustEmbed)]
-#[folder = "static/"]
-struct StaticAssets;
+use clap::Parser;
+use static_files::{static_file_handler, StaticOverrides};
+use std::path::Path;
+use std::process::ExitCode;
 
 /// Load environment variables with .env file taking priority over system env vars
 fn load_env_with_priority() {
@@ -32,28 +73,106 @@ fn load_env_with_priority() {
     
     // Verify critical env vars
     match std::env::var("DATABASE_URL") {
-        Ok(url) => println!("✅ DATABASE_URL set to: {}", url),
+        Ok(url) => println!("✅ DATABASE_URL set to: {}", redact::redact(&url)),
         Err(_) => eprintln!("❌ DATABASE_URL not set!"),
     }
 }
 
+/// Loads the app configuration from the config file at `path`, merged with the environment,
+/// or from the environment alone when there is none, and registers its secrets to be masked
+/// from logs and errors.
+fn load_config(path: Option<&Path>) -> errors::Result<config::AppConfig> {
+    let config = match path {
+        Some(path) => config::AppConfig::from_file(path),
+        None => config::AppConfig::from_env(),
+    }?;
+    redact::register(config.secrets());
+    Ok(config)
+}
+
 #[actix_web::main]
-async fn main() -> std::io::Result<()> {
+async fn main() -> std::io::Result<ExitCode> {
+    let mut cli = cli::Cli::parse();
+    match cli.command.take() {
+        None | Some(cli::Command::Serve) => serve(&cli).await.map(|()| ExitCode::SUCCESS),
+        Some(command) => {
+            // Results go to stdout, so the CLI stays quiet about .env and logs to stderr
+            let _ = dotenvy::from_filename_override(".env");
+            logging::init_with_writer(std::io::stderr);
+            let app_config = match load_config(cli.config_path().as_deref()) {
+                Ok(config) => config,
+                Err(e) => {
+                    eprintln!("❌ Failed to load app configuration: {}", redact::redact(&e.to_string()));
+                    return Ok(cli::Exit::Invalid.into());
+                }
+            };
+            let state = AppState::new(app_config).await;
+            Ok(cli::execute(command, state).await.into())
+        }
+    }
+}
+
+async fn serve(cli: &cli::Cli) -> std::io::Result<()> {
     banner::print_banner();
 
     load_env_with_priority();
     
-    env_logger::init_from_env(env_logger::Env::new().default_filter_or("info,actix_web=warn"));
+    logging::init();
     
-    let app_config = config::AppConfig::from_env()
-        .expect("Failed to load app configuration from environment");
+    let config_path = cli.config_path();
+    if let Some(path) = &config_path {
+        tracing::info!("📄 Loading configuration from {} (environment variables win over it)", path.display());
+    }
+    let app_config = load_config(config_path.as_deref())
+        .expect("Failed to load app configuration");
     
+    if app_config.admin_api_key.is_some() {
+        tracing::info!("🔐 API key authentication enabled");
+    } else {
+        tracing::info!("🔓 ADMIN_API_KEY not set: the API is open to anyone who can reach it");
+    }
+
     let state = AppState::new(app_config).await;
     let ws_broker = WsBroker::new();
+
+    match &state.db_pool {
+        Some(pool) => {
+            for suite in &state.config.suites {
+                match database::upsert_suite(pool, suite.name.clone(), suite.description.clone(), &suite.evals).await {
+                    Ok(_) => tracing::info!("🧪 Registered suite '{}' ({} evals) from the config file", suite.name, suite.evals.len()),
+                    Err(e) => tracing::error!("Failed to register suite '{}' from the config file: {}", suite.name, e),
+                }
+            }
+        }
+        None if !state.config.suites.is_empty() => {
+            tracing::warn!("⚠️  The config file's suites are not registered, as there is no database");
+        }
+        None => {}
+    }
+
+    let retention_task = match (state.config.retention.clone(), state.db_pool.clone()) {
+        (Some(policy), Some(pool)) => {
+            tracing::info!("🗄️  Purging evaluations older than {} days every {} minutes", policy.days, policy.interval.as_secs() / 60);
+            Some(retention::RetentionTask::spawn(pool, policy, state.active_batches.clone(), state.retention.clone()))
+        }
+        _ => None,
+    };
+
+    let job_worker = state
+        .db_pool
+        .is_some()
+        .then(|| jobs::JobWorker::spawn(state.clone(), ws_broker.clone(), jobs::WorkerOptions::default()));
+
+    let scheduler = state.db_pool.clone().map(|pool| scheduler::Scheduler::spawn(pool, scheduler::SchedulerOptions::default()));
+
+    let static_overrides = StaticOverrides::from_env();
+    if let Some(dir) = static_overrides.dir() {
+        tracing::info!("🎨 Found {} static override file(s) in {}", static_overrides.count(), dir.display());
+    }
     
-    println!("🚀 Starting server...");
-    println!("📊 Frontend available at http://127.0.0.1:8080");
-    println!("🔌 WebSocket endpoint at ws://127.0.0.1:8080/api/v1/ws");
+    tracing::info!("🚀 Starting server...");
+    tracing::info!("📊 Frontend available at http://127.0.0.1:8080");
+    tracing::info!("🔌 WebSocket endpoint at ws://127.0.0.1:8080/api/v1/ws");
 
     HttpServer::new(move || {
         let cors = Cors::permissive(); // for local deployment
@@ -61,28 +180,32 @@ async fn main() -> std::io::Result<()> {
         App::new()
             .app_data(web::Data::new(state.clone()))
             .app_data(web::Data::new(ws_broker.clone()))
+            .app_data(web::Data::new(static_overrides.clone()))
+            // Inside authorize, so limits are keyed by the caller it identified
+            .wrap(middleware::from_fn(api::rate_limit::rate_limit))
+            .wrap(middleware::from_fn(api::auth::authorize))
+            // gzip, brotli or zstd, as the client's Accept-Encoding allows
+            .wrap(middleware::Compress::default())
             .wrap(cors)
-            .wrap(middleware::Logger::default())
-            .configure(configure_routes)
+            // The default format plus the request id, as access lines are written after the request's span closes
+            .wrap(middleware::Logger::new(r#"%a "%r" %s %b "%{Referer}i" "%{User-Agent}i" %T request_id=%{x-request-id}o"#))
+            .wrap(middleware::from_fn(api::request_id::propagate_request_id))
+            .configure(routes_with_body_limit(state.config.max_body_bytes))
             .route("/{_:.*}", web::get().to(static_file_handler))
     })
     .bind(("0.0.0.0", 8080))?
     .run()
-    .await
-}
-
-async fn static_file_handler(req: HttpRequest) -> impl Responder {
-    let path = if req.path() == "/" {
-        "index.html"
-    } else {
-        &req.path()[1..]
-    };
+    .await?;
 
-    match StaticAssets::get(path) {
-        Some(content) => {
-            let mime = mime_guess::from_path(path).first_or_octet_stream();
-            HttpResponse::Ok().content_type(mime.as_ref()).body(Cow::into_owned(content.data))
-        }
-        None => HttpResponse::NotFound().body("404 Not Found"),
+    if let Some(task) = retention_task {
+        task.shutdown().await;
+    }
+    // Stopped before the worker, so no job is queued behind a worker that has gone
+    if let Some(scheduler) = scheduler {
+        scheduler.shutdown().await;
+    }
+    if let Some(worker) = job_worker {
+        worker.shutdown().await;
     }
+    Ok(())
 }
@@ -0,0 +1,128 @@
+// src/match_rule.rs
+use serde::{Deserialize, Serialize};
+
+/// Tolerance of a numeric comparison when the rule sets none.
+pub const DEFAULT_TOLERANCE: f64 = 1e-9;
+
+/// Judge model recorded for verdicts the match rule gave without calling a judge.
+pub const EXACT_MATCH_JUDGE: &str = "exact-match";
+
+/// How a model output is compared with the expected output before the judge is called.
+/// A match records a Pass without a judge call; anything else goes to the judge.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MatchRule {
+    #[serde(default)]
+    pub mode: MatchMode,
+    /// Largest absolute difference at which two numbers still match; defaults to
+    /// `DEFAULT_TOLERANCE`.
+    #[serde(default)]
+    pub tolerance: Option<f64>,
+    /// Ignore punctuation at the end of either side in `normalized` and `numeric` mode, so
+    /// "Paris." matches "Paris".
+    #[serde(default = "default_strip_punctuation")]
+    pub strip_trailing_punctuation: bool,
+}
+
+fn default_strip_punctuation() -> bool {
+    true
+}
+
+impl Default for MatchRule {
+    fn default() -> Self {
+        MatchRule { mode: MatchMode::default(), tolerance: None, strip_trailing_punctuation: true }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchMode {
+    /// The output equals the expected output byte for byte.
+    Exact,
+    /// The outputs are equal after trimming, collapsing whitespace and folding case, or both
+    /// are numbers within the tolerance.
+    #[default]
+    Normalized,
+    /// Both outputs are numbers within the tolerance.
+    Numeric,
+    /// Always call the judge.
+    Off,
+}
+
+impl MatchRule {
+    /// Why `output` matches `expected`, or `None` when the judge has to decide.
+    pub fn check(&self, output: &str, expected: &str) -> Option<String> {
+        let tolerance = self.tolerance.unwrap_or(DEFAULT_TOLERANCE);
+        match self.mode {
+            MatchMode::Off => None,
+            MatchMode::Exact => (output == expected).then(|| "Output equals the expected output exactly".to_string()),
+            MatchMode::Normalized => {
+                let (output, expected) = (self.normalize(output), self.normalize(expected));
+                if output == expected {
+                    return Some("Output equals the expected output after normalization".to_string());
+                }
+                numbers_match(&output, &expected, tolerance)
+                    .then(|| format!("Output is the expected number within {}", tolerance))
+            }
+            MatchMode::Numeric => numbers_match(&self.normalize(output), &self.normalize(expected), tolerance)
+                .then(|| format!("Output is the expected number within {}", tolerance)),
+        }
+    }
+
+    /// `text` trimmed, with runs of whitespace (Unicode included) collapsed to one space,
+    /// case folded and, if the rule says so, trailing punctuation removed.
+    pub fn normalize(&self, text: &str) -> String {
+        let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+        if self.strip_trailing_punctuation {
+            collapsed.trim_end_matches(|c: char| c.is_ascii_punctuation() || c.is_whitespace()).to_string()
+        } else {
+            collapsed
+        }
+    }
+}
+
+/// Whether both sides parse as finite numbers no more than `tolerance` apart.
+fn numbers_match(output: &str, expected: &str, tolerance: f64) -> bool {
+    match (output.parse::<f64>(), expected.parse::<f64>()) {
+        (Ok(output), Ok(expected)) if output.is_finite() && expected.is_finite() => (output - expected).abs() <= tolerance,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(mode: MatchMode) -> MatchRule {
+        MatchRule { mode, ..Default::default() }
+    }
+
+    #[test]
+    fn test_normalization_folds_case_and_unicode_whitespace() {
+        let normalized = rule(MatchMode::Normalized);
+        assert_eq!(normalized.normalize("  The\u{00A0}Answer\u{2003}\n is\tParis. "), "the answer is paris");
+        assert_eq!(normalized.normalize("Wait!?"), "wait");
+        assert!(normalized.check("\u{3000}PARIS.\u{2029}", "paris").is_some());
+        assert!(normalized.check("Paris, France", "Paris").is_none());
+
+        let keep_punctuation = MatchRule { strip_trailing_punctuation: false, ..rule(MatchMode::Normalized) };
+        assert!(keep_punctuation.check("Paris.", "Paris").is_none());
+        assert!(rule(MatchMode::Exact).check(" Paris", "Paris").is_none());
+        assert!(rule(MatchMode::Exact).check("Paris", "Paris").is_some());
+        assert!(rule(MatchMode::Off).check("Paris", "Paris").is_none());
+    }
+
+    #[test]
+    fn test_numbers_match_within_the_tolerance() {
+        let numeric = MatchRule { tolerance: Some(0.001), ..rule(MatchMode::Numeric) };
+        assert!(numeric.check("3.1416", "3.1415926").is_some());
+        assert!(numeric.check("3.15", "3.1415926").is_none());
+        assert!(numeric.check(" 42. ", "42").is_some());
+        assert!(numeric.check("forty-two", "42").is_none());
+        assert!(numeric.check("NaN", "NaN").is_none());
+
+        assert!(rule(MatchMode::Normalized).check("4.0", "4").is_some());
+        assert!(rule(MatchMode::Normalized).check("4.0001", "4").is_none());
+        assert!(rule(MatchMode::Numeric).check("four", "four").is_none());
+    }
+}
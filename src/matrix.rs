@@ -0,0 +1,394 @@
+// src/matrix.rs
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+
+use crate::config::{AppConfig, EvalConfig};
+use crate::format_rule::FormatRule;
+use crate::models::FieldError;
+
+/// Metadata key under which each generated eval records its place in the matrix.
+pub const MATRIX_METADATA_KEY: &str = "matrix";
+
+/// One row of the matrix: a prompt run against every model.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MatrixPrompt {
+    pub prompt: String,
+    pub expected: Option<String>,
+    pub criteria: Option<String>,
+    /// Template variables for the prompt; must be a JSON object when set
+    pub metadata: Option<JsonValue>,
+}
+
+/// Runs every prompt against every model with the same judge settings.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MatrixRequest {
+    pub models: Vec<String>,
+    pub prompts: Vec<MatrixPrompt>,
+    pub judge_model: Option<String>,
+    pub format_rule: Option<FormatRule>,
+    pub min_judge_confidence: Option<f32>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// URL POSTed a signed `batch.completed` summary once the matrix finishes
+    pub callback_url: Option<String>,
+}
+
+/// Where a generated eval sits: the prompt's row and the model's column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatrixCell {
+    pub prompt_index: usize,
+    pub model: String,
+}
+
+/// Expands the request into one eval per prompt × model, row by row. Each eval's metadata
+/// gains a `matrix` object with its `prompt_index` and `model`.
+pub fn expand(req: &MatrixRequest) -> Result<(Vec<MatrixCell>, Vec<EvalConfig>), String> {
+    if req.models.is_empty() {
+        return Err("At least one model is required".to_string());
+    }
+    if req.prompts.is_empty() {
+        return Err("At least one prompt is required".to_string());
+    }
+
+    let mut cells = Vec::with_capacity(req.prompts.len() * req.models.len());
+    let mut configs = Vec::with_capacity(cells.capacity());
+    for (prompt_index, prompt) in req.prompts.iter().enumerate() {
+        let mut metadata = match &prompt.metadata {
+            Some(JsonValue::Object(map)) => map.clone(),
+            None | Some(JsonValue::Null) => serde_json::Map::new(),
+            Some(_) => return Err(format!("Prompt {}: metadata must be a JSON object", prompt_index)),
+        };
+
+        for model in &req.models {
+            metadata.insert(
+                MATRIX_METADATA_KEY.to_string(),
+                json!({ "prompt_index": prompt_index, "model": model }),
+            );
+            cells.push(MatrixCell { prompt_index, model: model.clone() });
+            configs.push(EvalConfig {
+                model: model.clone(),
+                prompt: prompt.prompt.clone(),
+                prompt_version: None,
+                expected: prompt.expected.clone(),
+                judge_model: req.judge_model.clone(),
+                criteria: prompt.criteria.clone(),
+                tags: req.tags.clone(),
+                metadata: Some(JsonValue::Object(metadata.clone())),
+                format_rule: req.format_rule.clone(),
+                stream: false,
+                min_judge_confidence: req.min_judge_confidence,
+                truncation: None,
+                truncate_field: None,
+                context_window: None,
+                on_length_limit: None,
+                trace: false,
+                skip_failure_classification: false,
+                capture_raw: false,
+                require_review: false,
+                cache: false,
+                cache_judge: false,
+                safety_threshold: None,
+                response_format: None,
+                stop: None,
+                max_output_tokens: None,
+                thinking: None,
+                images: Vec::new(),
+                include_reasoning: false,
+                allow_missing_vars: false,
+                judge_params: None,
+                match_rule: None,
+                diff_rule: None,
+                self_consistency: None,
+                preset: None,
+                dataset_row: None,
+                chain_step: None,
+                provider_overrides: Default::default(),
+                overrides_supplied_by: None,
+            });
+        }
+    }
+    Ok((cells, configs))
+}
+
+/// Validates the expanded evals, naming the request field at fault: `models[1]`,
+/// `prompts[0].prompt` or `judge_model`. A rule broken by many cells is reported once.
+pub fn validate(
+    req: &MatrixRequest,
+    cells: &[MatrixCell],
+    configs: &[EvalConfig],
+    config: &AppConfig,
+) -> Result<(), Vec<FieldError>> {
+    let mut errors: Vec<FieldError> = Vec::new();
+    for (cell, eval) in cells.iter().zip(configs) {
+        for error in eval.validate(config).err().unwrap_or_default() {
+            let field = match error.field.as_str() {
+                "model" => {
+                    let column = req.models.iter().position(|model| *model == cell.model).unwrap_or_default();
+                    format!("models[{}]", column)
+                }
+                "judge_model" => error.field,
+                field => format!("prompts[{}].{}", cell.prompt_index, field),
+            };
+            let error = FieldError::new(field, error.message);
+            if !errors.contains(&error) {
+                errors.push(error);
+            }
+        }
+    }
+    if errors.is_empty() { Ok(()) } else { Err(errors) }
+}
+
+/// What happened to one cell once the batch ran.
+#[derive(Debug, Clone, Serialize)]
+pub struct CellOutcome {
+    pub eval_id: String,
+    pub status: String,
+    /// Model latency; `None` when the eval errored.
+    pub latency_ms: Option<u64>,
+}
+
+/// Pass counts and latency for a group of cells.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct GroupStats {
+    pub total: usize,
+    pub passed: usize,
+    pub failed: usize,
+    pub needs_review: usize,
+    pub errors: usize,
+    /// `passed / total`; errored evals count against it.
+    pub pass_rate: f64,
+    /// Average over evals that returned a result; `None` when none did.
+    pub average_latency_ms: Option<u64>,
+}
+
+impl GroupStats {
+    fn from_outcomes<'a>(outcomes: impl IntoIterator<Item = &'a CellOutcome>) -> Self {
+        let mut stats = GroupStats::default();
+        let mut latency_total = 0;
+        let mut latency_count = 0;
+        for outcome in outcomes {
+            stats.total += 1;
+            match outcome.status.as_str() {
+                "passed" => stats.passed += 1,
+                "failed" => stats.failed += 1,
+                "needs_review" => stats.needs_review += 1,
+                "error" | "blocked" => stats.errors += 1,
+                _ => {}
+            }
+            if let Some(latency) = outcome.latency_ms {
+                latency_total += latency;
+                latency_count += 1;
+            }
+        }
+        stats.pass_rate = if stats.total > 0 { stats.passed as f64 / stats.total as f64 } else { 0.0 };
+        stats.average_latency_ms = latency_total.checked_div(latency_count);
+        stats
+    }
+}
+
+/// A model's column: its stats and its result for every prompt.
+#[derive(Debug, Serialize)]
+pub struct ModelGroup {
+    pub model: String,
+    #[serde(flatten)]
+    pub stats: GroupStats,
+    pub results: Vec<ModelCell>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModelCell {
+    pub prompt_index: usize,
+    #[serde(flatten)]
+    pub outcome: CellOutcome,
+}
+
+/// A prompt's row: its stats and its result for every model.
+#[derive(Debug, Serialize)]
+pub struct PromptGroup {
+    pub prompt_index: usize,
+    pub prompt: String,
+    #[serde(flatten)]
+    pub stats: GroupStats,
+    pub results: Vec<PromptCell>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PromptCell {
+    pub model: String,
+    #[serde(flatten)]
+    pub outcome: CellOutcome,
+}
+
+/// One leaderboard line per model.
+#[derive(Debug, Serialize)]
+pub struct LeaderboardEntry {
+    pub rank: usize,
+    pub model: String,
+    pub pass_rate: f64,
+    pub average_latency_ms: Option<u64>,
+    pub passed: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MatrixGrouping {
+    /// Models ranked by pass rate, then by average latency (faster first).
+    pub leaderboard: Vec<LeaderboardEntry>,
+    /// In request order.
+    pub by_model: Vec<ModelGroup>,
+    /// In request order.
+    pub by_prompt: Vec<PromptGroup>,
+}
+
+/// Groups outcomes, given in the same order as `cells`, by model and by prompt.
+pub fn group(req: &MatrixRequest, cells: &[MatrixCell], outcomes: &[CellOutcome]) -> MatrixGrouping {
+    let by_model: Vec<ModelGroup> = req
+        .models
+        .iter()
+        .map(|model| {
+            let results: Vec<ModelCell> = cells
+                .iter()
+                .zip(outcomes)
+                .filter(|(cell, _)| &cell.model == model)
+                .map(|(cell, outcome)| ModelCell { prompt_index: cell.prompt_index, outcome: outcome.clone() })
+                .collect();
+            ModelGroup {
+                model: model.clone(),
+                stats: GroupStats::from_outcomes(results.iter().map(|r| &r.outcome)),
+                results,
+            }
+        })
+        .collect();
+
+    let by_prompt = req
+        .prompts
+        .iter()
+        .enumerate()
+        .map(|(prompt_index, prompt)| {
+            let results: Vec<PromptCell> = cells
+                .iter()
+                .zip(outcomes)
+                .filter(|(cell, _)| cell.prompt_index == prompt_index)
+                .map(|(cell, outcome)| PromptCell { model: cell.model.clone(), outcome: outcome.clone() })
+                .collect();
+            PromptGroup {
+                prompt_index,
+                prompt: prompt.prompt.clone(),
+                stats: GroupStats::from_outcomes(results.iter().map(|r| &r.outcome)),
+                results,
+            }
+        })
+        .collect();
+
+    let mut ranked: Vec<&ModelGroup> = by_model.iter().collect();
+    ranked.sort_by(|a, b| {
+        b.stats
+            .pass_rate
+            .total_cmp(&a.stats.pass_rate)
+            .then_with(|| a.stats.average_latency_ms.unwrap_or(u64::MAX).cmp(&b.stats.average_latency_ms.unwrap_or(u64::MAX)))
+    });
+    let leaderboard = ranked
+        .into_iter()
+        .enumerate()
+        .map(|(i, group)| LeaderboardEntry {
+            rank: i + 1,
+            model: group.model.clone(),
+            pass_rate: group.stats.pass_rate,
+            average_latency_ms: group.stats.average_latency_ms,
+            passed: group.stats.passed,
+            total: group.stats.total,
+        })
+        .collect();
+
+    MatrixGrouping { leaderboard, by_model, by_prompt }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(models: &[&str], prompts: &[&str]) -> MatrixRequest {
+        serde_json::from_value(json!({
+            "models": models,
+            "prompts": prompts.iter().map(|p| json!({"prompt": p, "metadata": {"topic": "math"}})).collect::<Vec<_>>(),
+            "judge_model": "ollama:llama3"
+        }))
+        .unwrap()
+    }
+
+    fn outcome(status: &str, latency_ms: Option<u64>) -> CellOutcome {
+        CellOutcome { eval_id: format!("{}-{:?}", status, latency_ms), status: status.to_string(), latency_ms }
+    }
+
+    #[test]
+    fn test_expand_is_row_major_and_tags_cells() {
+        let req = request(&["a:1", "b:2"], &["p0", "p1", "p2"]);
+        let (cells, configs) = expand(&req).unwrap();
+        assert_eq!(configs.len(), 6);
+        assert_eq!(cells[3], MatrixCell { prompt_index: 1, model: "b:2".to_string() });
+        assert_eq!(configs[3].prompt, "p1");
+        assert_eq!(configs[3].judge_model.as_deref(), Some("ollama:llama3"));
+
+        let metadata = configs[3].metadata.as_ref().unwrap();
+        assert_eq!(metadata["topic"], "math");
+        assert_eq!(metadata[MATRIX_METADATA_KEY], json!({"prompt_index": 1, "model": "b:2"}));
+
+        assert!(expand(&request(&[], &["p0"])).is_err());
+        let mut bad = request(&["a:1"], &["p0"]);
+        bad.prompts[0].metadata = Some(json!("not an object"));
+        assert!(expand(&bad).is_err());
+    }
+
+    #[test]
+    fn test_validate_names_the_request_field_at_fault() {
+        let config = AppConfig {
+            ollama: Some(crate::config::OllamaConfig { api_base: String::new(), models: vec![] }),
+            ..Default::default()
+        };
+        let mut req = request(&["ollama:llama3", "openai:gpt-4o"], &["p0", " "]);
+        req.prompts[0].expected = Some("4".to_string());
+        req.judge_model = Some("anthropic:claude".to_string());
+        let (cells, configs) = expand(&req).unwrap();
+
+        let fields: Vec<String> = validate(&req, &cells, &configs, &config).unwrap_err().into_iter().map(|e| e.field).collect();
+        assert_eq!(fields, ["judge_model", "models[1]", "prompts[1].prompt"]);
+
+        let ok = request(&["ollama:llama3"], &["p0"]);
+        let (cells, configs) = expand(&ok).unwrap();
+        assert_eq!(validate(&ok, &cells, &configs, &config), Ok(()));
+    }
+
+    #[test]
+    fn test_group_computes_pass_rates_and_ranks_models() {
+        let req = request(&["slow", "fast", "broken"], &["p0", "p1"]);
+        let (cells, _) = expand(&req).unwrap();
+        // Row-major: p0 × (slow, fast, broken), then p1 × (slow, fast, broken)
+        let outcomes = vec![
+            outcome("passed", Some(300)),
+            outcome("passed", Some(100)),
+            outcome("error", None),
+            outcome("passed", Some(500)),
+            outcome("passed", Some(200)),
+            outcome("failed", Some(50)),
+        ];
+
+        let grouping = group(&req, &cells, &outcomes);
+
+        let slow = &grouping.by_model[0];
+        assert_eq!((slow.stats.passed, slow.stats.total), (2, 2));
+        assert_eq!(slow.stats.average_latency_ms, Some(400));
+        let broken = &grouping.by_model[2];
+        assert_eq!((broken.stats.errors, broken.stats.failed), (1, 1));
+        assert_eq!(broken.stats.pass_rate, 0.0);
+        assert_eq!(broken.stats.average_latency_ms, Some(50));
+
+        let p1 = &grouping.by_prompt[1];
+        assert_eq!(p1.stats.total, 3);
+        assert!((p1.stats.pass_rate - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(p1.results[2].model, "broken");
+
+        let ranking: Vec<&str> = grouping.leaderboard.iter().map(|e| e.model.as_str()).collect();
+        assert_eq!(ranking, ["fast", "slow", "broken"]);
+        assert_eq!(grouping.leaderboard[0].rank, 1);
+    }
+}
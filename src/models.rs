@@ -1,16 +1,54 @@
 // src/models.rs
 use crate::runner;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Clone)]
 pub enum EvalResult {
     Success(runner::EvalResult),
-    Error(ApiError),
+    Error(ApiErrorResponse),
 }
 
-#[derive(Serialize, Clone, Debug)]
-pub struct ApiError {
+/// Body of every API error response, and of failed evals.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ApiErrorResponse {
+    /// Stable, machine-readable code such as `provider_not_found`; see `errors::ApiError`.
+    pub code: String,
+    /// Human-readable description, which may change between releases.
     pub message: String,
+    /// Context specific to the code, such as the model that failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
+}
+
+/// One broken rule of a request, listed under `details.errors` of a `validation_failed` error.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct FieldError {
+    /// The offending field, e.g. `prompt`, or `[2].model` for the third config of a batch.
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        FieldError { field: field.into(), message: message.into() }
+    }
+
+    /// The same error, for a field nested under `prefix`, such as a batch index.
+    pub fn within(mut self, prefix: &str) -> Self {
+        self.field = format!("{}.{}", prefix, self.field);
+        self
+    }
+
+    /// The error of a body that failed to deserialize, naming the field when serde does.
+    pub fn from_serde(err: &serde_json::Error) -> Self {
+        let message = err.to_string();
+        let field = ["missing field `", "unknown field `", "duplicate field `"]
+            .iter()
+            .find_map(|prefix| message.split_once(prefix))
+            .and_then(|(_, rest)| rest.split_once('`'))
+            .map_or_else(|| "body".to_string(), |(field, _)| field.to_string());
+        FieldError::new(field, message)
+    }
 }
 
 #[derive(Serialize, Clone)]
@@ -18,4 +56,49 @@ pub struct ApiResponse {
     pub id: String,
     pub status: String,
     pub result: EvalResult,
-}
\ No newline at end of file
+    /// The batch this evaluation ran in, if any.
+    pub batch_id: Option<String>,
+    /// `EvalConfig::config_hash` of the config that produced this evaluation.
+    pub config_hash: Option<String>,
+    /// The preset the request was merged with, if it named one.
+    pub preset: Option<crate::preset::PresetApplication>,
+}
+/// Body POSTed to a run's `callback_url` once it finishes, tagged by `event`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "event")]
+pub enum WebhookPayload {
+    #[serde(rename = "eval.completed")]
+    EvalCompleted(EvalWebhook),
+    #[serde(rename = "batch.completed")]
+    BatchCompleted(BatchWebhook),
+}
+
+/// Summary of a finished single eval.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct EvalWebhook {
+    pub eval_id: String,
+    /// As in `EvalResponse`: `passed`, `failed`, `uncertain`, `needs_review`, `judge_error` or `error`.
+    pub status: String,
+    pub model: String,
+    pub verdict: Option<String>,
+    pub latency_ms: Option<u64>,
+    pub cost_usd: Option<f64>,
+    /// Set when `status` is `error` or `judge_error`.
+    pub error: Option<ApiErrorResponse>,
+    pub completed_at: String,
+}
+
+/// Summary of a finished batch or matrix.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BatchWebhook {
+    pub batch_id: String,
+    pub status: String,
+    pub total: usize,
+    pub completed: usize,
+    pub passed: usize,
+    pub failed: usize,
+    pub needs_review: usize,
+    pub total_cost_usd: f64,
+    pub average_model_latency_ms: u64,
+    pub completed_at: String,
+}
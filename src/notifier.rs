@@ -0,0 +1,360 @@
+// src/notifier.rs
+use async_trait::async_trait;
+use serde_json::{json, Value as JsonValue};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::Instrument;
+
+use crate::config::AppConfig;
+use crate::drift::{DriftMetric, ModelDrift};
+
+/// Failing evals listed in a notification.
+pub const MAX_LISTED_FAILURES: usize = 3;
+
+/// Characters of a failing eval's prompt shown in a notification.
+const PROMPT_PREVIEW_CHARS: usize = 80;
+
+/// How long a channel has to accept a notification.
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A failed or errored eval of a batch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FailedEval {
+    pub eval_id: String,
+    pub model: String,
+    pub prompt: String,
+    /// `failed` or `error`.
+    pub status: String,
+}
+
+/// A finished batch, as reported to notifiers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchReport {
+    /// What ran, e.g. `Suite 'smoke'` or `Batch 3f2a…`.
+    pub name: String,
+    pub batch_id: String,
+    pub total: usize,
+    pub passed: usize,
+    pub failed: usize,
+    /// The first `MAX_LISTED_FAILURES` failed or errored evals.
+    pub failures: Vec<FailedEval>,
+    /// The results dashboard, when the server's address is known.
+    pub link: Option<String>,
+}
+
+impl BatchReport {
+    pub fn pass_rate(&self) -> f64 {
+        if self.total == 0 { 0.0 } else { self.passed as f64 / self.total as f64 }
+    }
+}
+
+/// Models that drifted since the week before, found after a run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DriftReport {
+    /// The run that found them, e.g. `Scheduled run of suite 'smoke'`.
+    pub name: String,
+    pub batch_id: String,
+    /// Only the models that drifted.
+    pub models: Vec<ModelDrift>,
+}
+
+/// A channel batch reports are sent to.
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// Name used in logs, e.g. `slack`.
+    fn name(&self) -> &'static str;
+
+    async fn notify(&self, report: &BatchReport) -> Result<(), String>;
+
+    async fn notify_drift(&self, report: &DriftReport) -> Result<(), String>;
+}
+
+/// Posts reports to a Slack incoming webhook.
+pub struct SlackNotifier {
+    client: reqwest::Client,
+    webhook_url: String,
+}
+
+impl SlackNotifier {
+    pub fn new(client: reqwest::Client, webhook_url: String) -> Self {
+        SlackNotifier { client, webhook_url }
+    }
+
+    /// The Block Kit message for `report`, with a plain `text` fallback for notifications.
+    pub fn message(report: &BatchReport) -> JsonValue {
+        let summary = format!(
+            "{} finished with a {:.1}% pass rate ({} of {} passed, {} failed)",
+            report.name,
+            report.pass_rate() * 100.0,
+            report.passed,
+            report.total,
+            report.failed
+        );
+
+        let mut blocks = vec![
+            json!({ "type": "header", "text": { "type": "plain_text", "text": format!("❌ {} has failing evals", report.name) } }),
+            json!({
+                "type": "section",
+                "fields": [
+                    { "type": "mrkdwn", "text": format!("*Pass rate*\n{:.1}%", report.pass_rate() * 100.0) },
+                    { "type": "mrkdwn", "text": format!("*Passed*\n{} / {}", report.passed, report.total) },
+                    { "type": "mrkdwn", "text": format!("*Failed*\n{}", report.failed) },
+                    { "type": "mrkdwn", "text": format!("*Batch*\n`{}`", report.batch_id) },
+                ]
+            }),
+        ];
+        if !report.failures.is_empty() {
+            let lines: Vec<String> = report
+                .failures
+                .iter()
+                .map(|f| format!("• *{}* ({}): {}", f.model, f.status, preview(&f.prompt)))
+                .collect();
+            blocks.push(json!({
+                "type": "section",
+                "text": { "type": "mrkdwn", "text": format!("*Top failures*\n{}", lines.join("\n")) }
+            }));
+        }
+        if let Some(link) = &report.link {
+            blocks.push(json!({
+                "type": "actions",
+                "elements": [{ "type": "button", "text": { "type": "plain_text", "text": "Open results" }, "url": link }]
+            }));
+        }
+
+        json!({ "text": summary, "blocks": blocks })
+    }
+
+    /// The Block Kit message for a drift report, one line per drifted metric.
+    pub fn drift_message(report: &DriftReport) -> JsonValue {
+        let names: Vec<&str> = report.models.iter().map(|m| m.model.as_str()).collect();
+        let summary = format!("{} found drift in {}", report.name, names.join(", "));
+        let lines: Vec<String> = report
+            .models
+            .iter()
+            .flat_map(|m| m.drifted_metrics().map(move |shift| (m, shift)))
+            .map(|(m, shift)| match shift.metric {
+                DriftMetric::OutputTokens => format!(
+                    "• *{}*: mean output tokens {:.0} → {:.0} ({:+.0}%) since the week of {}",
+                    m.model, shift.previous, shift.current, shift.change * 100.0, m.previous_week
+                ),
+                DriftMetric::PassRate => format!(
+                    "• *{}*: pass rate {:.1}% → {:.1}% ({:+.1} points) since the week of {}",
+                    m.model, shift.previous * 100.0, shift.current * 100.0, shift.change * 100.0, m.previous_week
+                ),
+            })
+            .collect();
+
+        json!({
+            "text": summary,
+            "blocks": [
+                { "type": "header", "text": { "type": "plain_text", "text": "📈 Model drift detected" } },
+                { "type": "section", "text": { "type": "mrkdwn", "text": lines.join("\n") } },
+                { "type": "context", "elements": [{ "type": "mrkdwn", "text": format!("{} · batch `{}`", report.name, report.batch_id) }] },
+            ]
+        })
+    }
+
+    async fn post(&self, message: &JsonValue) -> Result<(), String> {
+        let response = self
+            .client
+            .post(&self.webhook_url)
+            .timeout(SEND_TIMEOUT)
+            .json(message)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!("Slack answered {}", response.status()))
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for SlackNotifier {
+    fn name(&self) -> &'static str {
+        "slack"
+    }
+
+    async fn notify(&self, report: &BatchReport) -> Result<(), String> {
+        self.post(&SlackNotifier::message(report)).await
+    }
+
+    async fn notify_drift(&self, report: &DriftReport) -> Result<(), String> {
+        self.post(&SlackNotifier::drift_message(report)).await
+    }
+}
+
+/// The first `PROMPT_PREVIEW_CHARS` of a prompt on one line, with an ellipsis when cut.
+fn preview(prompt: &str) -> String {
+    let flat = prompt.split_whitespace().collect::<Vec<_>>().join(" ");
+    if flat.chars().count() <= PROMPT_PREVIEW_CHARS {
+        return flat;
+    }
+    let cut: String = flat.chars().take(PROMPT_PREVIEW_CHARS).collect();
+    format!("{}…", cut.trim_end())
+}
+
+/// The notifiers configured in `config`.
+pub fn from_config(config: &AppConfig, client: &reqwest::Client) -> Vec<Arc<dyn Notifier>> {
+    let mut notifiers: Vec<Arc<dyn Notifier>> = Vec::new();
+    if let Some(url) = &config.slack_webhook_url {
+        notifiers.push(Arc::new(SlackNotifier::new(client.clone(), url.clone())));
+    }
+    notifiers
+}
+
+/// Whether a report is bad enough to send: some evals failed and the pass rate is below
+/// `NOTIFY_MIN_PASS_RATE`.
+pub fn should_notify(config: &AppConfig, report: &BatchReport) -> bool {
+    report.failed > 0 && report.pass_rate() < config.notify_min_pass_rate
+}
+
+/// Sends `report` to every notifier from background tasks, so the batch response never waits
+/// on a channel. A channel that is down only costs a logged error.
+pub fn dispatch(notifiers: Vec<Arc<dyn Notifier>>, report: BatchReport) {
+    let report = Arc::new(report);
+    for notifier in notifiers {
+        let report = Arc::clone(&report);
+        tokio::spawn(
+            async move {
+                match notifier.notify(&report).await {
+                    Ok(()) => tracing::info!("🔔 Sent {} notification for batch {}", notifier.name(), report.batch_id),
+                    Err(e) => tracing::error!("Failed to send {} notification for batch {}: {}", notifier.name(), report.batch_id, e),
+                }
+            }
+            .in_current_span(),
+        );
+    }
+}
+
+/// Sends a drift report to every notifier from background tasks, like `dispatch`.
+pub fn dispatch_drift(notifiers: Vec<Arc<dyn Notifier>>, report: DriftReport) {
+    let report = Arc::new(report);
+    for notifier in notifiers {
+        let report = Arc::clone(&report);
+        tokio::spawn(
+            async move {
+                match notifier.notify_drift(&report).await {
+                    Ok(()) => tracing::info!("🔔 Sent {} drift notification for batch {}", notifier.name(), report.batch_id),
+                    Err(e) => tracing::error!("Failed to send {} drift notification for batch {}: {}", notifier.name(), report.batch_id, e),
+                }
+            }
+            .in_current_span(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn report() -> BatchReport {
+        BatchReport {
+            name: "Suite 'smoke'".to_string(),
+            batch_id: "batch-1".to_string(),
+            total: 4,
+            passed: 1,
+            failed: 3,
+            failures: vec![
+                FailedEval {
+                    eval_id: "e1".to_string(),
+                    model: "ollama:llama3".to_string(),
+                    prompt: format!("Summarize\nthis: {}", "word ".repeat(40)),
+                    status: "failed".to_string(),
+                },
+                FailedEval {
+                    eval_id: "e2".to_string(),
+                    model: "openai:gpt-4o".to_string(),
+                    prompt: "What is 2+2?".to_string(),
+                    status: "error".to_string(),
+                },
+            ],
+            link: Some("http://localhost:8080/results.html".to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_slack_message_summarizes_the_batch_and_its_failures() {
+        let slack = MockServer::start().await;
+        Mock::given(method("POST")).and(path("/services/T0/B0/x")).respond_with(ResponseTemplate::new(200)).mount(&slack).await;
+
+        let notifier = SlackNotifier::new(reqwest::Client::new(), format!("{}/services/T0/B0/x", slack.uri()));
+        notifier.notify(&report()).await.unwrap();
+
+        let requests = slack.received_requests().await.unwrap();
+        assert_eq!(requests.len(), 1);
+        let message: JsonValue = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(message["text"], "Suite 'smoke' finished with a 25.0% pass rate (1 of 4 passed, 3 failed)");
+
+        let blocks = message["blocks"].as_array().unwrap();
+        let types: Vec<&str> = blocks.iter().map(|b| b["type"].as_str().unwrap()).collect();
+        assert_eq!(types, ["header", "section", "section", "actions"]);
+        assert_eq!(blocks[0]["text"]["text"], "❌ Suite 'smoke' has failing evals");
+        assert_eq!(blocks[1]["fields"][0]["text"], "*Pass rate*\n25.0%");
+
+        let failures = blocks[2]["text"]["text"].as_str().unwrap();
+        let lines: Vec<&str> = failures.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with("• *ollama:llama3* (failed): Summarize this: word"));
+        assert!(lines[1].ends_with('…'));
+        assert_eq!(lines[2], "• *openai:gpt-4o* (error): What is 2+2?");
+        assert_eq!(blocks[3]["elements"][0]["url"], "http://localhost:8080/results.html");
+    }
+
+    #[tokio::test]
+    async fn test_slack_being_down_is_an_error_not_a_panic() {
+        let slack = MockServer::start().await;
+        Mock::given(method("POST")).respond_with(ResponseTemplate::new(503)).mount(&slack).await;
+        let notifier = SlackNotifier::new(reqwest::Client::new(), slack.uri());
+        assert_eq!(notifier.notify(&report()).await, Err("Slack answered 503 Service Unavailable".to_string()));
+
+        let unreachable = SlackNotifier::new(reqwest::Client::new(), "http://127.0.0.1:9".to_string());
+        assert!(unreachable.notify(&report()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_slack_drift_message_lists_each_drifted_metric() {
+        use crate::drift::{self, DriftThresholds};
+        use crate::database::WeeklyOutputTokens;
+
+        let week = |week: &str, mean: f64, pass_rate: f64| WeeklyOutputTokens {
+            model: "ollama:llama3".to_string(),
+            week: week.to_string(),
+            evaluations: 20,
+            mean_output_tokens: Some(mean),
+            max_output_tokens: None,
+            judged_evaluations: 20,
+            pass_rate: Some(pass_rate),
+            histogram: Vec::new(),
+        };
+        let drifted = drift::compare(&week("2026-10-05", 200.0, 0.9), &week("2026-10-12", 300.0, 0.6), &DriftThresholds::default());
+        let report = DriftReport { name: "Scheduled run of suite 'smoke'".to_string(), batch_id: "batch-1".to_string(), models: vec![drifted] };
+
+        let slack = MockServer::start().await;
+        Mock::given(method("POST")).respond_with(ResponseTemplate::new(200)).mount(&slack).await;
+        SlackNotifier::new(reqwest::Client::new(), slack.uri()).notify_drift(&report).await.unwrap();
+
+        let requests = slack.received_requests().await.unwrap();
+        let message: JsonValue = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(message["text"], "Scheduled run of suite 'smoke' found drift in ollama:llama3");
+        let lines: Vec<&str> = message["blocks"][1]["text"]["text"].as_str().unwrap().lines().collect();
+        assert_eq!(lines, [
+            "• *ollama:llama3*: mean output tokens 200 → 300 (+50%) since the week of 2026-10-05",
+            "• *ollama:llama3*: pass rate 90.0% → 60.0% (-30.0 points) since the week of 2026-10-05",
+        ]);
+    }
+
+    #[test]
+    fn test_only_batches_below_the_pass_rate_threshold_are_reported() {
+        let config = AppConfig { notify_min_pass_rate: 0.5, ..Default::default() };
+        assert!(should_notify(&config, &report()));
+        let mostly_passing = BatchReport { passed: 3, failed: 1, ..report() };
+        assert!(!should_notify(&config, &mostly_passing));
+        let passing = BatchReport { passed: 4, failed: 0, ..report() };
+        assert!(!should_notify(&AppConfig { notify_min_pass_rate: 1.0, ..Default::default() }, &passing));
+    }
+}
@@ -0,0 +1,332 @@
+// src/output_parser.rs
+//! Extracts a structured `parsed_output` from a model's free-text output. Each extractor
+//! looks for one shape; `parse` tries them from the most to the least specific and records
+//! which one matched.
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+/// The extractor a `parsed_output` came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParseMethod {
+    /// The whole output is JSON.
+    Json,
+    /// JSON inside a fenced code block, whatever its language tag.
+    FencedBlock,
+    /// The first balanced JSON object or array found in the text.
+    EmbeddedJson,
+    /// A markdown table, as an array of row objects keyed by the header.
+    Table,
+    /// A bullet or numbered list, as an array of its items.
+    List,
+    Number,
+    Boolean,
+    /// A multiple-choice letter.
+    Choice,
+    /// `key: value` lines.
+    KeyValue,
+}
+
+impl ParseMethod {
+    pub const ALL: [ParseMethod; 9] = [
+        ParseMethod::Json,
+        ParseMethod::FencedBlock,
+        ParseMethod::EmbeddedJson,
+        ParseMethod::Table,
+        ParseMethod::List,
+        ParseMethod::Number,
+        ParseMethod::Boolean,
+        ParseMethod::Choice,
+        ParseMethod::KeyValue,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ParseMethod::Json => "json",
+            ParseMethod::FencedBlock => "fenced_block",
+            ParseMethod::EmbeddedJson => "embedded_json",
+            ParseMethod::Table => "table",
+            ParseMethod::List => "list",
+            ParseMethod::Number => "number",
+            ParseMethod::Boolean => "boolean",
+            ParseMethod::Choice => "choice",
+            ParseMethod::KeyValue => "key_value",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|method| method.as_str() == s)
+    }
+}
+
+/// Parses `raw_output` with the first extractor that finds its shape in it.
+pub fn parse(raw_output: &str) -> Option<(JsonValue, ParseMethod)> {
+    if let Ok(json) = serde_json::from_str::<JsonValue>(raw_output) {
+        return Some((json, ParseMethod::Json));
+    }
+    if let Some(json) = extract_fenced_json(raw_output) {
+        return Some((json, ParseMethod::FencedBlock));
+    }
+    if let Some(json) = extract_embedded_json(raw_output) {
+        return Some((json, ParseMethod::EmbeddedJson));
+    }
+    if let Some(rows) = extract_markdown_table(raw_output) {
+        return Some((rows, ParseMethod::Table));
+    }
+    if let Some(items) = extract_list(raw_output) {
+        return Some((JsonValue::from(items), ParseMethod::List));
+    }
+    if let Some(number) = extract_number(raw_output) {
+        return Some((serde_json::json!({ "answer": number }), ParseMethod::Number));
+    }
+    if let Some(boolean) = extract_boolean(raw_output) {
+        return Some((serde_json::json!({ "answer": boolean }), ParseMethod::Boolean));
+    }
+    if let Some(choice) = extract_multiple_choice(raw_output) {
+        return Some((serde_json::json!({ "answer": choice }), ParseMethod::Choice));
+    }
+    extract_key_value_pairs(raw_output).map(|pairs| (pairs, ParseMethod::KeyValue))
+}
+
+/// The JSON in the first fenced code block holding any, e.g. ```` ```json ````, ```` ```jsonc ````
+/// or an untagged block.
+fn extract_fenced_json(text: &str) -> Option<JsonValue> {
+    let re = Regex::new(r"```[\w+.-]*[^\S\n]*\n([\s\S]*?)\n[^\S\n]*```").ok()?;
+    re.captures_iter(text)
+        .find_map(|caps| serde_json::from_str::<JsonValue>(caps[1].trim()).ok())
+}
+
+/// The first balanced `{...}` or `[...]` in `text` that parses as JSON. Brackets inside
+/// strings do not count towards the balance.
+fn extract_embedded_json(text: &str) -> Option<JsonValue> {
+    text.char_indices()
+        .filter(|(_, c)| matches!(c, '{' | '['))
+        .find_map(|(start, _)| {
+            let end = balanced_end(&text[start..])?;
+            serde_json::from_str(&text[start..start + end]).ok()
+        })
+}
+
+/// Length of the bracketed span `text` starts with, up to and including its closing bracket.
+fn balanced_end(text: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' | '[' => depth += 1,
+            '}' | ']' => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The first markdown table in `text` as an array of objects keyed by its header, with
+/// numeric cells as numbers. Needs a header row, a `|---|` separator row and a data row.
+fn extract_markdown_table(text: &str) -> Option<JsonValue> {
+    let lines: Vec<&str> = text.lines().map(str::trim).collect();
+    let cells = |line: &str| -> Vec<String> {
+        line.trim_matches('|').split('|').map(|cell| cell.trim().to_string()).collect()
+    };
+    let is_separator = |line: &str| {
+        line.contains('-') && line.chars().all(|c| matches!(c, '|' | '-' | ':' | ' ' | '\t'))
+    };
+    let start = (0..lines.len().saturating_sub(1))
+        .find(|&i| lines[i].contains('|') && is_separator(lines[i + 1]))?;
+    let header = cells(lines[start]);
+    let rows: Vec<JsonValue> = lines[start + 2..]
+        .iter()
+        .take_while(|line| line.contains('|'))
+        .map(|line| {
+            let row = header.iter().cloned().zip(cells(line).into_iter().map(|cell| scalar(&cell))).collect();
+            JsonValue::Object(row)
+        })
+        .collect();
+    (!rows.is_empty()).then_some(JsonValue::Array(rows))
+}
+
+/// `cell` as a number when it is one, otherwise as a string.
+fn scalar(cell: &str) -> JsonValue {
+    cell.parse::<f64>()
+        .ok()
+        .and_then(serde_json::Number::from_f64)
+        .map_or_else(|| JsonValue::String(cell.to_string()), JsonValue::Number)
+}
+
+/// The items of the first bullet (`-`, `*`, `+`) or numbered (`1.`, `1)`) list in `text`
+/// with at least two items.
+fn extract_list(text: &str) -> Option<Vec<String>> {
+    let re = Regex::new(r"^\s*(?:[-*+•]|\d+[.)])\s+(.+?)\s*$").ok()?;
+    let mut items = Vec::new();
+    for line in text.lines() {
+        match re.captures(line) {
+            Some(caps) => items.push(caps[1].to_string()),
+            None if items.len() >= 2 => break,
+            None => items.clear(),
+        }
+    }
+    (items.len() >= 2).then_some(items)
+}
+
+fn extract_number(text: &str) -> Option<f64> {
+    let patterns = [
+        r"(?:answer|result|solution)(?:\s+is)?[:\s]+(-?\d+\.?\d*)",
+        r"^(-?\d+\.?\d*)$",
+        r"\b(-?\d+\.?\d*)\b(?:\s*$)",
+    ];
+
+    for pattern in &patterns {
+        if let Ok(re) = Regex::new(pattern) {
+            if let Some(caps) = re.captures(text) {
+                if let Some(num_str) = caps.get(1) {
+                    if let Ok(num) = num_str.as_str().parse::<f64>() {
+                        return Some(num);
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+fn extract_boolean(text: &str) -> Option<bool> {
+    let text_lower = text.to_lowercase().trim().to_string();
+
+    if text_lower.starts_with("yes") || text_lower.contains("answer is yes") || text_lower == "true" {
+        return Some(true);
+    }
+
+    if text_lower.starts_with("no") || text_lower.contains("answer is no") || text_lower == "false" {
+        return Some(false);
+    }
+
+    None
+}
+
+fn extract_multiple_choice(text: &str) -> Option<String> {
+    let re = Regex::new(r"(?:answer|choice)(?:\s+is)?[:\s]+([A-Za-z])").ok()?;
+    re.captures(text)
+        .and_then(|caps| caps.get(1))
+        .map(|m| m.as_str().to_uppercase())
+}
+
+fn extract_key_value_pairs(text: &str) -> Option<JsonValue> {
+    let mut map = serde_json::Map::new();
+    let re = Regex::new(r"(?m)^([A-Za-z\s]+):\s*(.+)$").ok()?;
+
+    for caps in re.captures_iter(text) {
+        if let (Some(key), Some(value)) = (caps.get(1), caps.get(2)) {
+            let key_str = key.as_str().trim().to_lowercase().replace(' ', "_");
+            let value_str = value.as_str().trim();
+
+            if let Ok(num) = value_str.parse::<f64>() {
+                map.insert(key_str, JsonValue::Number(serde_json::Number::from_f64(num)?));
+            } else if let Some(bool_val) = extract_boolean(value_str) {
+                map.insert(key_str, JsonValue::Bool(bool_val));
+            } else {
+                map.insert(key_str, JsonValue::String(value_str.to_string()));
+            }
+        }
+    }
+
+    if !map.is_empty() {
+        Some(JsonValue::Object(map))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_fenced_blocks_are_read_whatever_their_language_tag() {
+        assert_eq!(extract_fenced_json("Here:\n```json\n{\"a\": 1}\n```"), Some(json!({ "a": 1 })));
+        assert_eq!(extract_fenced_json("```jsonc\n[1, 2]\n```"), Some(json!([1, 2])));
+        assert_eq!(extract_fenced_json("```\n{\"a\": true}\n```"), Some(json!({ "a": true })));
+        assert_eq!(
+            extract_fenced_json("```python\nprint('hi')\n```\nthen\n```JSON\n{\"b\": 2}\n```"),
+            Some(json!({ "b": 2 }))
+        );
+        assert_eq!(extract_fenced_json("```python\nprint('hi')\n```"), None);
+    }
+
+    #[test]
+    fn test_embedded_json_is_found_by_matching_brackets() {
+        assert_eq!(
+            extract_embedded_json("The result is {\"city\": \"Paris\", \"tags\": [\"a\", \"b\"]} as requested."),
+            Some(json!({ "city": "Paris", "tags": ["a", "b"] }))
+        );
+        assert_eq!(
+            extract_embedded_json("Note {not json} but {\"text\": \"a } in a string\", \"n\": {\"m\": 1}}"),
+            Some(json!({ "text": "a } in a string", "n": { "m": 1 } }))
+        );
+        assert_eq!(extract_embedded_json("Scores: [3, 4, 5]."), Some(json!([3, 4, 5])));
+        assert_eq!(extract_embedded_json("An unclosed {\"a\": 1"), None);
+        assert_eq!(extract_embedded_json("No brackets here"), None);
+    }
+
+    #[test]
+    fn test_markdown_tables_become_row_objects() {
+        let table = "Results:\n\n| City | Population |\n|:-----|-----------:|\n| Paris | 2.1 |\n| Lyon | 0.5 |\n\nDone.";
+        assert_eq!(
+            extract_markdown_table(table),
+            Some(json!([{ "City": "Paris", "Population": 2.1 }, { "City": "Lyon", "Population": 0.5 }]))
+        );
+        assert_eq!(extract_markdown_table("| A | B |\n|---|---|"), None);
+        assert_eq!(extract_markdown_table("a | b\nc | d"), None);
+    }
+
+    #[test]
+    fn test_lists_become_string_arrays() {
+        assert_eq!(extract_list("Steps:\n1. Boil water\n2) Add pasta\n3. Drain"), Some(vec![
+            "Boil water".to_string(),
+            "Add pasta".to_string(),
+            "Drain".to_string(),
+        ]));
+        assert_eq!(extract_list("- red\n* green\n+ blue\n\nThat's all."), Some(vec![
+            "red".to_string(),
+            "green".to_string(),
+            "blue".to_string(),
+        ]));
+        assert_eq!(extract_list("- only one item"), None);
+        assert_eq!(extract_list("Paris is the capital."), None);
+    }
+
+    #[test]
+    fn test_parse_records_which_extractor_matched() {
+        let method = |text: &str| parse(text).map(|(_, method)| method);
+        assert_eq!(method("{\"answer\": 4}"), Some(ParseMethod::Json));
+        assert_eq!(method("```js\n{\"answer\": 4}\n```"), Some(ParseMethod::FencedBlock));
+        assert_eq!(method("Sure! {\"answer\": 4} Hope that helps."), Some(ParseMethod::EmbeddedJson));
+        assert_eq!(method("| a | b |\n|---|---|\n| 1 | 2 |"), Some(ParseMethod::Table));
+        assert_eq!(method("- 3\n- 4"), Some(ParseMethod::List));
+        assert_eq!(method("The answer is 4"), Some(ParseMethod::Number));
+        assert_eq!(method("Yes, it is."), Some(ParseMethod::Boolean));
+        assert_eq!(method("My choice: B"), Some(ParseMethod::Choice));
+        assert_eq!(method("Name: Alice\nCity: Paris"), Some(ParseMethod::KeyValue));
+        assert_eq!(method("Paris is lovely in spring"), None);
+        for method in ParseMethod::ALL {
+            assert_eq!(ParseMethod::parse(method.as_str()), Some(method));
+        }
+    }
+}
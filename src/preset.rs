@@ -0,0 +1,176 @@
+// src/preset.rs
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value as JsonValue};
+
+use crate::config::EvalConfig;
+use crate::database::Preset;
+
+/// Field of a run or batch request naming the preset to merge beneath it.
+pub const PRESET_FIELD: &str = "preset";
+
+/// A field the preset and the request both set, to different values. The request's value is used.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PresetOverride {
+    pub field: String,
+    pub preset_value: JsonValue,
+    pub request_value: JsonValue,
+}
+
+/// How a preset shaped an evaluation's request, recorded with the evaluation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PresetApplication {
+    pub preset_id: String,
+    pub name: String,
+    /// Whether the preset is shared rather than owned by the calling key.
+    pub shared: bool,
+    /// Fields taken from the preset, in name order.
+    pub applied: Vec<String>,
+    /// Fields the request set itself over a different preset value, in name order.
+    pub overrides: Vec<PresetOverride>,
+    /// The request after the merge, as the evaluation ran it.
+    pub effective_config: JsonValue,
+}
+
+/// Fields of an `EvalConfig`, which are the fields a preset may set.
+fn config_fields() -> Vec<String> {
+    let config: Result<EvalConfig, _> = serde_json::from_value(json!({ "model": "", "prompt": "" }));
+    match config.ok().and_then(|config| serde_json::to_value(config).ok()) {
+        Some(JsonValue::Object(fields)) => fields.into_iter().map(|(field, _)| field).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Checks that preset defaults are an object of eval config fields holding valid values.
+pub fn validate_defaults(defaults: &JsonValue) -> Result<(), String> {
+    let Some(fields) = defaults.as_object() else {
+        return Err("defaults must be a JSON object of eval config fields".to_string());
+    };
+    if fields.contains_key(PRESET_FIELD) {
+        return Err("A preset cannot name another preset".to_string());
+    }
+    // Serde would silently drop unknown fields, so a misspelt default would never apply
+    let known = config_fields();
+    if let Some(unknown) = fields.keys().find(|field| !known.contains(field)) {
+        return Err(format!("Unknown eval config field '{}' in defaults", unknown));
+    }
+
+    let mut probe = fields.clone();
+    probe.entry("model").or_insert_with(|| json!(""));
+    probe.entry("prompt").or_insert_with(|| json!(""));
+    serde_json::from_value::<EvalConfig>(JsonValue::Object(probe))
+        .map(|_| ())
+        .map_err(|e| format!("Invalid defaults: {}", e))
+}
+
+/// Merges `preset`'s defaults beneath a request. Fields the request leaves out are taken from
+/// the preset; fields the request sends, `null` included, win, and are noted as overrides
+/// when the preset holds a different value.
+pub fn merge(preset: &Preset, mut request: Map<String, JsonValue>) -> (Map<String, JsonValue>, PresetApplication) {
+    request.remove(PRESET_FIELD);
+
+    let mut defaults: Vec<(&String, &JsonValue)> = preset.defaults.as_object().into_iter().flatten().collect();
+    defaults.sort_by(|a, b| a.0.cmp(b.0));
+
+    let mut applied = Vec::new();
+    let mut overrides = Vec::new();
+    for (field, preset_value) in defaults {
+        match request.get(field) {
+            None => {
+                request.insert(field.clone(), preset_value.clone());
+                applied.push(field.clone());
+            }
+            Some(request_value) if request_value != preset_value => overrides.push(PresetOverride {
+                field: field.clone(),
+                preset_value: preset_value.clone(),
+                request_value: request_value.clone(),
+            }),
+            Some(_) => {}
+        }
+    }
+
+    let application = PresetApplication {
+        preset_id: preset.id.clone(),
+        name: preset.name.clone(),
+        shared: preset.owner_key_id.is_none(),
+        applied,
+        overrides,
+        effective_config: JsonValue::Object(request.clone()),
+    };
+    (request, application)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preset(defaults: JsonValue) -> Preset {
+        Preset {
+            id: "p1".to_string(),
+            name: "nightly".to_string(),
+            owner_key_id: Some("key-1".to_string()),
+            description: None,
+            defaults,
+            created_by: "ci".to_string(),
+            created_at: String::new(),
+            updated_at: String::new(),
+        }
+    }
+
+    fn object(value: JsonValue) -> Map<String, JsonValue> {
+        value.as_object().cloned().unwrap()
+    }
+
+    #[test]
+    fn test_request_fields_win_and_conflicts_are_noted() {
+        let preset = preset(json!({
+            "judge_model": "anthropic:claude-sonnet-4",
+            "criteria": "Be strict",
+            "tags": ["nightly"],
+            "trace": true,
+        }));
+        let request = object(json!({
+            "preset": "nightly",
+            "model": "ollama:llama3",
+            "prompt": "2+2?",
+            "judge_model": "openai:gpt-4",
+            "trace": true,
+        }));
+
+        let (merged, application) = merge(&preset, request);
+
+        assert_eq!(merged["judge_model"], "openai:gpt-4");
+        assert_eq!(merged["criteria"], "Be strict");
+        assert_eq!(merged["tags"], json!(["nightly"]));
+        assert!(!merged.contains_key(PRESET_FIELD));
+        assert_eq!(application.applied, ["criteria", "tags"]);
+        // Equal values are no conflict
+        assert_eq!(application.overrides, [PresetOverride {
+            field: "judge_model".to_string(),
+            preset_value: json!("anthropic:claude-sonnet-4"),
+            request_value: json!("openai:gpt-4"),
+        }]);
+        assert_eq!(application.effective_config, JsonValue::Object(merged));
+        assert!(!application.shared);
+    }
+
+    #[test]
+    fn test_explicit_null_overrides_a_preset_value() {
+        let (merged, application) = merge(
+            &preset(json!({ "judge_model": "openai:gpt-4" })),
+            object(json!({ "model": "m", "prompt": "p", "judge_model": null })),
+        );
+        assert_eq!(merged["judge_model"], JsonValue::Null);
+        assert!(application.applied.is_empty());
+        assert_eq!(application.overrides[0].request_value, JsonValue::Null);
+    }
+
+    #[test]
+    fn test_defaults_must_be_known_well_typed_config_fields() {
+        assert!(validate_defaults(&json!({ "judge_model": "openai:gpt-4", "tags": ["a"], "stream": true })).is_ok());
+        assert!(validate_defaults(&json!(["judge_model"])).is_err());
+        assert!(validate_defaults(&json!({ "preset": "other" })).is_err());
+        let unknown = validate_defaults(&json!({ "judge_modle": "openai:gpt-4" })).unwrap_err();
+        assert!(unknown.contains("judge_modle"), "{}", unknown);
+        assert!(validate_defaults(&json!({ "stream": "yes" })).is_err());
+    }
+}
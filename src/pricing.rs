@@ -0,0 +1,262 @@
+// src/pricing.rs
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::errors::{EvalError, Result};
+use crate::providers::TokenUsage;
+
+/// Default location of the pricing override file, relative to the working directory.
+const DEFAULT_PRICING_FILE: &str = "pricing.toml";
+
+/// USD price per 1K tokens for one model.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct ModelPrice {
+    pub input_per_1k: f64,
+    pub output_per_1k: f64,
+}
+
+impl ModelPrice {
+    /// Cost of a single call. Missing token counts are treated as zero,
+    /// but a call with no token counts at all has no cost.
+    pub fn cost(&self, usage: &TokenUsage) -> Option<f64> {
+        if usage.input_tokens.is_none() && usage.output_tokens.is_none() {
+            return None;
+        }
+        let input = usage.input_tokens.unwrap_or(0) as f64;
+        let output = usage.output_tokens.unwrap_or(0) as f64;
+        Some(input / 1000.0 * self.input_per_1k + output / 1000.0 * self.output_per_1k)
+    }
+}
+
+/// Per-model prices keyed by `provider:model`.
+/// A `provider:*` entry prices every model of that provider not listed explicitly.
+#[derive(Debug, Clone, Default)]
+pub struct PricingTable {
+    prices: HashMap<String, ModelPrice>,
+}
+
+#[derive(Deserialize)]
+struct PricingFile {
+    #[serde(default)]
+    models: HashMap<String, ModelPrice>,
+}
+
+impl PricingTable {
+    /// Built-in prices for common models.
+    pub fn builtin() -> Self {
+        let defaults: [(&str, f64, f64); 16] = [
+            ("openai:gpt-4o", 0.0025, 0.01),
+            ("openai:gpt-4o-mini", 0.00015, 0.0006),
+            ("openai:gpt-3.5-turbo", 0.0005, 0.0015),
+            ("anthropic:claude-opus-4", 0.015, 0.075),
+            ("anthropic:claude-sonnet-4", 0.003, 0.015),
+            ("anthropic:claude-sonnet-4-5", 0.003, 0.015),
+            ("anthropic:claude-3-5-haiku-latest", 0.0008, 0.004),
+            ("gemini:gemini-2.5-pro", 0.00125, 0.01),
+            ("gemini:gemini-2.5-flash", 0.0003, 0.0025),
+            ("gemini:gemini-2.0-flash", 0.0001, 0.0004),
+            ("gemini:gemini-1.5-pro-latest", 0.00125, 0.005),
+            ("gemini:gemini-1.5-flash-latest", 0.000075, 0.0003),
+            ("gemini:gemini-1.5-pro", 0.00125, 0.005),
+            ("gemini:gemini-1.5-flash", 0.000075, 0.0003),
+            ("groq:llama-3.3-70b-versatile", 0.00059, 0.00079),
+            // Local models cost nothing per token
+            ("ollama:*", 0.0, 0.0),
+        ];
+
+        Self {
+            prices: defaults
+                .iter()
+                .map(|(model, input, output)| {
+                    (model.to_string(), ModelPrice { input_per_1k: *input, output_per_1k: *output })
+                })
+                .collect(),
+        }
+    }
+
+    /// Built-in prices overridden by a TOML file.
+    /// The file is read from `PRICING_FILE` if set (and must exist), otherwise from
+    /// `pricing.toml` in the working directory when present.
+    pub fn load() -> Result<Self> {
+        let mut table = Self::builtin();
+
+        let path = match std::env::var("PRICING_FILE") {
+            Ok(path) => Some(path),
+            Err(_) => std::path::Path::new(DEFAULT_PRICING_FILE)
+                .exists()
+                .then(|| DEFAULT_PRICING_FILE.to_string()),
+        };
+
+        if let Some(path) = path {
+            let contents = std::fs::read_to_string(&path)?;
+            table.merge_toml(&contents)?;
+            tracing::info!("💲 Loaded model pricing overrides from {}", path);
+        }
+
+        Ok(table)
+    }
+
+    /// Merges prices from TOML of the form:
+    ///
+    /// ```toml
+    /// [models."openai:gpt-4o"]
+    /// input_per_1k = 0.0025
+    /// output_per_1k = 0.01
+    /// ```
+    pub fn merge_toml(&mut self, contents: &str) -> Result<()> {
+        let file: PricingFile = toml::from_str(contents)?;
+        for (model, price) in file.models {
+            if !model.contains(':') {
+                return Err(EvalError::Config(format!(
+                    "Pricing key '{}' must be in the format provider:model",
+                    model
+                )));
+            }
+            self.prices.insert(model, price);
+        }
+        Ok(())
+    }
+
+    /// Price for a `provider:model` string, falling back to a `provider:*` entry.
+    pub fn price_for(&self, model: &str) -> Option<&ModelPrice> {
+        self.prices.get(model).or_else(|| {
+            let (provider, _) = model.split_once(':')?;
+            self.prices.get(&format!("{}:*", provider))
+        })
+    }
+
+    /// Cost of a call to `model`, or `None` when the model is unpriced or usage is unknown.
+    pub fn cost(&self, model: &str, usage: &TokenUsage) -> Option<f64> {
+        self.price_for(model)?.cost(usage)
+    }
+}
+
+/// Estimates token counts locally, without calling a provider.
+pub trait TokenEstimator: Send + Sync {
+    fn estimate_tokens(&self, text: &str) -> u32;
+}
+
+/// The common "about four characters per token" approximation for English text.
+#[derive(Debug, Clone, Copy)]
+pub struct CharsPerToken(pub f64);
+
+impl Default for CharsPerToken {
+    fn default() -> Self {
+        Self(4.0)
+    }
+}
+
+impl TokenEstimator for CharsPerToken {
+    fn estimate_tokens(&self, text: &str) -> u32 {
+        (text.chars().count() as f64 / self.0).ceil() as u32
+    }
+}
+
+/// Estimated usage and cost for one model in one role (`target` or `judge`).
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelEstimate {
+    pub model: String,
+    pub role: String,
+    pub calls: usize,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// `None` when the model has no price in the pricing table.
+    pub cost_usd: Option<f64>,
+}
+
+/// Dry-run estimate for a batch of evals.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BatchEstimate {
+    pub total_configs: usize,
+    pub unconfigured_provider_configs: usize,
+    pub models: Vec<ModelEstimate>,
+    pub unpriced_models: Vec<String>,
+    pub total_input_tokens: u64,
+    pub total_output_tokens: u64,
+    /// Sum over priced models only.
+    pub total_cost_usd: f64,
+}
+
+impl BatchEstimate {
+    /// Adds one call to `model` in `role` with the estimated token counts.
+    pub fn add_call(&mut self, pricing: &PricingTable, model: &str, role: &str, usage: &TokenUsage) {
+        let cost = pricing.cost(model, usage);
+        let input = usage.input_tokens.unwrap_or(0) as u64;
+        let output = usage.output_tokens.unwrap_or(0) as u64;
+
+        match self.models.iter_mut().find(|m| m.model == model && m.role == role) {
+            Some(entry) => {
+                entry.calls += 1;
+                entry.input_tokens += input;
+                entry.output_tokens += output;
+                entry.cost_usd = entry.cost_usd.zip(cost).map(|(a, b)| a + b);
+            }
+            None => self.models.push(ModelEstimate {
+                model: model.to_string(),
+                role: role.to_string(),
+                calls: 1,
+                input_tokens: input,
+                output_tokens: output,
+                cost_usd: cost,
+            }),
+        }
+
+        if cost.is_none() && !self.unpriced_models.iter().any(|m| m == model) {
+            self.unpriced_models.push(model.to_string());
+        }
+        self.total_input_tokens += input;
+        self.total_output_tokens += output;
+        self.total_cost_usd += cost.unwrap_or(0.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(input: u32, output: u32) -> TokenUsage {
+        TokenUsage { input_tokens: Some(input), output_tokens: Some(output) }
+    }
+
+    #[test]
+    fn test_cost_for_known_and_unknown_models() {
+        let table = PricingTable::builtin();
+
+        let cost = table.cost("openai:gpt-4o", &usage(1000, 2000)).unwrap();
+        assert!((cost - 0.0225).abs() < 1e-9);
+
+        assert_eq!(table.cost("ollama:llama3", &usage(500, 500)), Some(0.0));
+        assert_eq!(table.cost("openai:unreleased-model", &usage(500, 500)), None);
+        assert_eq!(table.cost("openai:gpt-4o", &TokenUsage::default()), None);
+    }
+
+    #[test]
+    fn test_toml_overrides_builtin_prices() {
+        let mut table = PricingTable::builtin();
+        table
+            .merge_toml(
+                r#"
+                [models."openai:gpt-4o"]
+                input_per_1k = 1.0
+                output_per_1k = 2.0
+
+                [models."groq:*"]
+                input_per_1k = 0.0005
+                output_per_1k = 0.0008
+                "#,
+            )
+            .unwrap();
+
+        assert_eq!(table.price_for("openai:gpt-4o"), Some(&ModelPrice { input_per_1k: 1.0, output_per_1k: 2.0 }));
+        assert!(table.price_for("groq:llama-3.3-70b-versatile").is_some());
+        assert!(table.merge_toml("[models.gpt-4o]\ninput_per_1k = 1.0\noutput_per_1k = 1.0").is_err());
+    }
+
+    #[test]
+    fn test_chars_per_token_estimate() {
+        let estimator = CharsPerToken::default();
+        assert_eq!(estimator.estimate_tokens(""), 0);
+        assert_eq!(estimator.estimate_tokens("abcd"), 1);
+        assert_eq!(estimator.estimate_tokens("abcde"), 2);
+        assert_eq!(estimator.estimate_tokens("ééééé"), 2);
+    }
+}
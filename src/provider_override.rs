@@ -0,0 +1,172 @@
+// src/provider_override.rs
+//! Provider credentials a request brings for itself, e.g. a team member's own OpenAI key for
+//! billing. They replace the configured key and base URL for that request only and are never
+//! saved or logged; provider errors that echo a key back are scrubbed of it.
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::config::AppConfig;
+use crate::models::FieldError;
+use crate::redact::REDACTED;
+
+/// The key and base URL a provider is called with instead of the configured ones.
+#[derive(Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProviderOverride {
+    pub api_key: Option<String>,
+    pub api_base: Option<String>,
+}
+
+impl fmt::Debug for ProviderOverride {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProviderOverride")
+            .field("api_key", &self.api_key.as_ref().map(|_| REDACTED))
+            .field("api_base", &self.api_base)
+            .finish()
+    }
+}
+
+/// Overrides by provider name, e.g. `openai`.
+pub type ProviderOverrides = BTreeMap<String, ProviderOverride>;
+
+/// The API key whose request supplied an eval's overrides.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SuppliedBy {
+    /// `None` for `ADMIN_API_KEY`, and when authentication is off.
+    pub key_id: Option<String>,
+    pub key_name: String,
+}
+
+/// What is saved of the overrides an eval ran with: the providers and who supplied them,
+/// never the credentials.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OverrideUse {
+    pub providers: Vec<String>,
+    pub supplied_by: Option<SuppliedBy>,
+}
+
+impl OverrideUse {
+    /// The record of `overrides`; `None` when there are none.
+    pub fn of(overrides: &ProviderOverrides, supplied_by: Option<&SuppliedBy>) -> Option<Self> {
+        (!overrides.is_empty()).then(|| OverrideUse {
+            providers: overrides.keys().cloned().collect(),
+            supplied_by: supplied_by.cloned(),
+        })
+    }
+}
+
+/// Errors for overrides of providers not in `used`, keys for Ollama, which takes none, base
+/// URLs that are not http(s), and base URLs without a key for providers that take one, which
+/// would send the configured key to a host the caller chose.
+pub fn validate(overrides: &ProviderOverrides, used: &[String]) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    for (provider, credentials) in overrides {
+        let field = |name: &str| format!("provider_overrides.{}{}", provider, name);
+        if !used.contains(provider) {
+            errors.push(FieldError::new(field(""), "is not a provider this request uses"));
+        }
+        if credentials.api_key.is_none() && credentials.api_base.is_none() {
+            errors.push(FieldError::new(field(""), "must set api_key or api_base"));
+        }
+        if provider == "ollama" && credentials.api_key.is_some() {
+            errors.push(FieldError::new(field(".api_key"), "is not taken by ollama"));
+        }
+        if provider != "ollama" && credentials.api_base.is_some() && credentials.api_key.is_none() {
+            errors.push(FieldError::new(field(".api_key"), "must be set with api_base"));
+        }
+        if credentials.api_key.as_ref().is_some_and(|key| key.trim().is_empty()) {
+            errors.push(FieldError::new(field(".api_key"), "must not be empty"));
+        }
+        let base = credentials.api_base.as_deref().map(reqwest::Url::parse);
+        if base.is_some_and(|parsed| !parsed.is_ok_and(|url| matches!(url.scheme(), "http" | "https"))) {
+            errors.push(FieldError::new(field(".api_base"), "must be an http or https URL"));
+        }
+    }
+    errors
+}
+
+/// `config` with each override in place of its provider's configured credentials, and the
+/// overriding keys listed in `request_secrets` so provider errors are scrubbed of them.
+/// Providers that are not configured stay unconfigured, and a base URL overridden without a
+/// key clears the configured key rather than sending it there.
+pub fn apply(config: &AppConfig, overrides: &ProviderOverrides) -> AppConfig {
+    let mut config = config.clone();
+    for (provider, credentials) in overrides {
+        let (api_base, api_key) = match provider.as_str() {
+            "anthropic" => config.anthropic.as_mut().map(|c| (&mut c.api_base, Some(&mut c.api_key))),
+            "gemini" => config.gemini.as_mut().map(|c| (&mut c.api_base, Some(&mut c.api_key))),
+            "groq" => config.groq.as_mut().map(|c| (&mut c.api_base, Some(&mut c.api_key))),
+            "ollama" => config.ollama.as_mut().map(|c| (&mut c.api_base, None)),
+            "openai" => config.openai.as_mut().map(|c| (&mut c.api_base, Some(&mut c.api_key))),
+            "openrouter" => config.openrouter.as_mut().map(|c| (&mut c.api_base, Some(&mut c.api_key))),
+            name => config.openai_compat.get_mut(name).map(|c| (&mut c.api_base, Some(&mut c.api_key))),
+        }
+        .unzip();
+        if let (Some(base), Some(api_base)) = (&credentials.api_base, api_base) {
+            *api_base = base.trim_end_matches('/').to_string();
+        }
+        match (&credentials.api_key, api_key) {
+            (Some(key), Some(Some(api_key))) => {
+                *api_key = key.clone();
+                config.request_secrets.push(key.clone());
+            }
+            (None, Some(Some(api_key))) if credentials.api_base.is_some() => api_key.clear(),
+            _ => {}
+        }
+    }
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{OllamaConfig, OpenAIConfig};
+
+    fn overrides(json: serde_json::Value) -> ProviderOverrides {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_overrides_replace_only_the_configured_credentials_they_name() {
+        let config = AppConfig {
+            openai: Some(OpenAIConfig { api_base: "https://api.openai.com/v1".to_string(), api_key: "server".to_string(), models: vec![] }),
+            ollama: Some(OllamaConfig { api_base: "http://localhost:11434".to_string(), models: vec![] }),
+            ..Default::default()
+        };
+        let applied = apply(&config, &overrides(serde_json::json!({
+            "openai": { "api_key": "sk-alice" },
+            "ollama": { "api_base": "http://gpu-box:11434/" },
+            "groq": { "api_key": "gsk-unused" },
+        })));
+        let openai = applied.openai.as_ref().unwrap();
+        assert_eq!((openai.api_key.as_str(), openai.api_base.as_str()), ("sk-alice", "https://api.openai.com/v1"));
+        assert_eq!(applied.ollama.unwrap().api_base, "http://gpu-box:11434");
+        assert!(applied.groq.is_none());
+        assert_eq!(applied.request_secrets, ["sk-alice"]);
+        assert_eq!(config.openai.as_ref().unwrap().api_key, "server");
+
+        assert_eq!(crate::redact::mask("Incorrect API key provided: sk-alice.", &applied.request_secrets), "Incorrect API key provided: [redacted].");
+        let debug = format!("{:?}", overrides(serde_json::json!({ "openai": { "api_key": "sk-alice" } })));
+        assert!(!debug.contains("sk-alice"), "{}", debug);
+
+        // A base URL without a key never carries the configured key along
+        let applied = apply(&config, &overrides(serde_json::json!({ "openai": { "api_base": "https://elsewhere.example/v1" } })));
+        let openai = applied.openai.unwrap();
+        assert_eq!((openai.api_key.as_str(), openai.api_base.as_str()), ("", "https://elsewhere.example/v1"));
+    }
+
+    #[test]
+    fn test_overrides_must_be_for_providers_the_request_uses() {
+        let used = ["openai".to_string(), "ollama".to_string()];
+        let fields = |json| validate(&overrides(json), &used).into_iter().map(|e| e.field).collect::<Vec<_>>();
+        assert!(fields(serde_json::json!({ "openai": { "api_key": "sk-alice", "api_base": "https://proxy.example/v1" } })).is_empty());
+        assert_eq!(fields(serde_json::json!({ "anthropic": { "api_key": "sk-ant" } })), ["provider_overrides.anthropic"]);
+        assert_eq!(
+            fields(serde_json::json!({ "ollama": { "api_key": "k", "api_base": "ftp://host" }, "openai": {} })),
+            ["provider_overrides.ollama.api_key", "provider_overrides.ollama.api_base", "provider_overrides.openai"]
+        );
+        assert_eq!(fields(serde_json::json!({ "openai": { "api_base": "https://attacker.example/v1" } })), ["provider_overrides.openai.api_key"]);
+        assert!(fields(serde_json::json!({ "ollama": { "api_base": "http://gpu-box:11434" } })).is_empty());
+    }
+}
@@ -1,17 +1,50 @@
 // src/providers/anthropic.rs
 
+use async_trait::async_trait;
+use reqwest::header::HeaderMap;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use std::time::Instant;
 
 use crate::config::AnthropicConfig;
 use crate::errors::{EvalError, Result};
-use crate::providers::{LlmProvider, TokenUsage};
+use crate::providers::images::{self, Image};
+use crate::providers::{
+    check_health, recording, FinishReason, Generation, GenerationParams, LlmProvider, ThinkingParams, TokenUsage,
+    DEFAULT_TEMPERATURE,
+};
+
+/// `max_tokens` of a request whose eval sets no `max_output_tokens`; Anthropic requires one.
+/// With thinking on, the thinking budget is added to it.
+const DEFAULT_MAX_TOKENS: u32 = 4096;
+
+/// Smallest thinking budget Anthropic accepts.
+pub const MIN_THINKING_BUDGET: u32 = 1024;
+
+/// Most tokens a model may generate, by model name prefix, the more specific prefixes first.
+/// Models not listed are sent whatever limit is asked for.
+const MAX_OUTPUT_TOKENS: &[(&str, u32)] = &[
+    ("claude-opus-4-5", 64_000),
+    ("claude-opus-4", 32_000),
+    ("claude-sonnet-4", 64_000),
+    ("claude-haiku-4", 64_000),
+    ("claude-3-7-sonnet", 64_000),
+    ("claude-3-5-sonnet", 8_192),
+    ("claude-3-5-haiku", 8_192),
+    ("claude-3-opus", 4_096),
+    ("claude-3-haiku", 4_096),
+];
 
 /// A provider for interacting with Anthropic Claude models.
 pub struct AnthropicProvider {
     client: Client,
     config: AnthropicConfig,
+    /// Stop sequences, output limit, thinking and images sent with every request. Anthropic has no
+    /// JSON mode, so a response format is asked for in the prompt instead.
+    params: GenerationParams,
+    /// Headers from `<PREFIX>_EXTRA_HEADERS`, sent with every request.
+    extra_headers: HeaderMap,
 }
 
 #[derive(Serialize)]
@@ -21,17 +54,30 @@ struct AnthropicRequest<'a> {
     max_tokens: u32,
     #[serde(skip_serializing_if = "Option::is_none")]
     temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop_sequences: Option<&'a [String]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thinking: Option<Thinking>,
+}
+
+#[derive(Serialize)]
+struct Thinking {
+    #[serde(rename = "type")]
+    thinking_type: &'static str,
+    budget_tokens: u32,
 }
 
 #[derive(Serialize)]
 struct Message<'a> {
     role: &'a str,
-    content: &'a str,
+    /// The prompt as a string, or as content blocks when images come with it.
+    content: serde_json::Value,
 }
 
 #[derive(Deserialize)]
 struct AnthropicResponse {
     content: Vec<ContentBlock>,
+    stop_reason: Option<String>,
     usage: ApiUsage,
 }
 
@@ -40,6 +86,8 @@ struct ContentBlock {
     #[serde(rename = "type")]
     content_type: String,
     text: Option<String>,
+    /// The reasoning of a `thinking` block, with extended thinking on.
+    thinking: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -51,74 +99,287 @@ struct ApiUsage {
 impl AnthropicProvider {
     /// Creates a new `AnthropicProvider`.
     pub fn new(client: Client, config: AnthropicConfig) -> Self {
-        Self { client, config }
+        Self { client, config, params: GenerationParams::default(), extra_headers: HeaderMap::new() }
+    }
+
+    /// Sends the stop sequences and output limit of `params` with every request.
+    pub fn with_params(mut self, params: GenerationParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    /// Sends `headers` with every request, e.g. the ones an API gateway requires.
+    pub fn with_extra_headers(mut self, headers: HeaderMap) -> Self {
+        self.extra_headers = headers;
+        self
+    }
+}
+
+/// The content of the user message: `prompt`, preceded by an image block per image when there
+/// are any, as Anthropic recommends images before the text.
+fn content(prompt: &str, images: &[String]) -> serde_json::Value {
+    if images.is_empty() {
+        return json!(prompt);
+    }
+    let mut blocks: Vec<serde_json::Value> = images::parse_all(images)
+        .map(|image| match image {
+            Image::Url(url) => json!({ "type": "image", "source": { "type": "url", "url": url } }),
+            Image::Inline { media_type, data } => {
+                json!({ "type": "image", "source": { "type": "base64", "media_type": media_type, "data": data } })
+            }
+        })
+        .collect();
+    blocks.push(json!({ "type": "text", "text": prompt }));
+    json!(blocks)
+}
+
+/// The `max_tokens` of a request: `max_output_tokens`, or the default plus the thinking budget.
+pub fn max_tokens(max_output_tokens: Option<u32>, thinking: Option<ThinkingParams>) -> u32 {
+    max_output_tokens.unwrap_or_else(|| DEFAULT_MAX_TOKENS.saturating_add(thinking.map_or(0, |t| t.budget_tokens)))
+}
+
+/// Most tokens `model` may generate, when it is a model Anthropic documents a limit for.
+pub fn max_output_ceiling(model: &str) -> Option<u32> {
+    MAX_OUTPUT_TOKENS.iter().find(|(prefix, _)| model.starts_with(prefix)).map(|(_, ceiling)| *ceiling)
+}
+
+/// Checks that `model` may generate the `max_tokens` the request would ask for, which the API
+/// otherwise rejects with a bare `400`.
+pub fn check_max_tokens(model: &str, max_output_tokens: Option<u32>, thinking: Option<ThinkingParams>) -> std::result::Result<(), String> {
+    let max_tokens = max_tokens(max_output_tokens, thinking);
+    match max_output_ceiling(model) {
+        Some(ceiling) if max_tokens > ceiling => {
+            let asked = match (max_output_tokens, thinking) {
+                (None, Some(thinking)) => format!(
+                    "{} tokens ({} of thinking budget plus {} for the answer; set max_output_tokens to lower it)",
+                    max_tokens, thinking.budget_tokens, DEFAULT_MAX_TOKENS
+                ),
+                _ => format!("{} tokens", max_tokens),
+            };
+            Err(format!("{} generates at most {} tokens, but {} were asked for", model, ceiling, asked))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Maps an Anthropic `stop_reason`.
+fn finish_reason(raw: &str) -> FinishReason {
+    match raw {
+        "end_turn" => FinishReason::Stop,
+        "stop_sequence" => FinishReason::StopSequence,
+        "max_tokens" => FinishReason::Length,
+        "tool_use" => FinishReason::ToolCalls,
+        "refusal" => FinishReason::Refusal,
+        other => FinishReason::Other(other.to_string()),
     }
 }
 
+/// The answer and the reasoning of the response, walking its blocks in order: text blocks are
+/// joined into the answer as they come, as a long or cited answer spans several, and thinking
+/// blocks into the reasoning. Redacted thinking and tool blocks are skipped. The answer is `None`
+/// when there are no text blocks, the reasoning when there are no thinking blocks.
+fn split_content(content: &[ContentBlock]) -> (Option<String>, Option<String>) {
+    let mut text: Option<String> = None;
+    let mut thinking: Vec<&str> = Vec::new();
+    for block in content {
+        match (block.content_type.as_str(), &block.text, &block.thinking) {
+            ("text", Some(part), _) => text.get_or_insert_with(String::new).push_str(part),
+            ("thinking", _, Some(part)) => thinking.push(part),
+            _ => {}
+        }
+    }
+    let reasoning = if thinking.is_empty() { None } else { Some(thinking.join("\n\n")) };
+    (text, reasoning)
+}
+
+#[async_trait]
 impl LlmProvider for AnthropicProvider {
     /// Calls the Anthropic API with a given prompt and returns the model's response text and latency.
-    async fn generate(&self, model: &str, prompt: &str) -> Result<(String, u64, TokenUsage)> {
+    async fn generate(&self, model: &str, prompt: &str) -> Result<Generation> {
         let url = format!("{}/v1/messages", self.config.api_base.trim_end_matches('/'));
 
-        println!("📡 Calling Anthropic: {} with model: {}", url, model);
+        tracing::info!("📡 Calling Anthropic: {} with model: {}", url, model);
 
+        let thinking = self.params.thinking;
+        check_max_tokens(model, self.params.max_output_tokens, thinking).map_err(EvalError::Config)?;
         let body = AnthropicRequest {
             model,
             messages: vec![Message {
                 role: "user",
-                content: prompt,
+                content: content(prompt, &self.params.images),
             }],
-            max_tokens: 4096,
-            temperature: Some(0.7),
+            max_tokens: max_tokens(self.params.max_output_tokens, thinking),
+            // Anthropic rejects a temperature with thinking on
+            temperature: thinking.is_none().then(|| self.params.temperature.unwrap_or(DEFAULT_TEMPERATURE)),
+            stop_sequences: self.params.stop.as_deref(),
+            thinking: thinking.map(|t| Thinking { thinking_type: "enabled", budget_tokens: t.budget_tokens }),
         };
 
         let start = Instant::now();
 
-        let resp = self
+        let request = self
             .client
             .post(&url)
             // .header("x-api-key", &self.config.api_key)
             // .header("anthropic-version", "2023-06-01")
             .header("Authorization", &format!("Bearer {}", &self.config.api_key))
             .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await?;
+            .headers(self.extra_headers.clone())
+            .json(&body);
+        let (status, text) = recording::send(&self.client, request, "anthropic", model, prompt).await?;
 
-        let status = resp.status();
         let latency_ms = start.elapsed().as_millis() as u64;
 
-        println!("📥 Anthropic response status: {} ({}ms)", status, latency_ms);
+        tracing::info!("📥 Anthropic response status: {} ({}ms)", status, latency_ms);
 
         if !status.is_success() {
-            let error_body = resp
-                .text()
-                .await
-                .unwrap_or_else(|_| "Could not read error body".to_string());
             return Err(EvalError::ApiError {
                 status: status.as_u16(),
-                body: error_body,
+                body: text,
             });
         }
 
-        let anthropic_resp: AnthropicResponse = resp.json().await?;
+        let anthropic_resp: AnthropicResponse = serde_json::from_str(&text)?;
 
         let token_usage = TokenUsage {
             input_tokens: Some(anthropic_resp.usage.input_tokens),
             output_tokens: Some(anthropic_resp.usage.output_tokens),
         };
         
-        let output = anthropic_resp
-            .content
-            .iter()
-            .find(|block| block.content_type == "text")
-            .and_then(|block| block.text.as_ref())
-            .ok_or_else(|| EvalError::UnexpectedResponse("No text content in response".to_string()))?;
-
-        if output.is_empty() {
-            return Err(EvalError::EmptyResponse);
-        }
+        let reason = anthropic_resp.stop_reason.as_deref().map(finish_reason);
+        let (output, reasoning) = split_content(&anthropic_resp.content);
+
+        // A refusal may come without any text, which is still a complete answer, as may a
+        // response cut off while thinking
+        let output = match (output, &reason) {
+            (Some(output), _) if !output.is_empty() => output,
+            (_, Some(FinishReason::Refusal)) => String::new(),
+            (None, Some(FinishReason::Length)) if reasoning.is_some() => String::new(),
+            (Some(_), _) => return Err(EvalError::EmptyResponse),
+            (None, _) => return Err(EvalError::UnexpectedResponse("No text content in response".to_string())),
+        };
+
+        Ok(Generation {
+            output,
+            latency_ms,
+            token_usage,
+            finish_reason: reason,
+            resolved_model: None,
+            reasoning,
+        })
+    }
+
+    /// Lists the models, which needs a valid key but costs nothing.
+    async fn health(&self) -> Result<()> {
+        let url = format!("{}/v1/models", self.config.api_base.trim_end_matches('/'));
+        let request = self.client.get(&url).header("Authorization", format!("Bearer {}", &self.config.api_key));
+        check_health(request.headers(self.extra_headers.clone())).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_finish_reason_from_raw_payloads() {
+        let truncated: AnthropicResponse = serde_json::from_str(
+            r#"{"type": "message", "role": "assistant", "content": [{"type": "text", "text": "The answer is"}],
+                "stop_reason": "max_tokens", "stop_sequence": null, "usage": {"input_tokens": 21, "output_tokens": 4096}}"#,
+        )
+        .unwrap();
+        assert_eq!(truncated.stop_reason.as_deref().map(finish_reason), Some(FinishReason::Length));
+
+        let refused: AnthropicResponse = serde_json::from_str(
+            r#"{"type": "message", "role": "assistant", "content": [],
+                "stop_reason": "refusal", "stop_sequence": null, "usage": {"input_tokens": 18, "output_tokens": 0}}"#,
+        )
+        .unwrap();
+        assert_eq!(refused.stop_reason.as_deref().map(finish_reason), Some(FinishReason::Refusal));
+
+        assert_eq!(finish_reason("end_turn"), FinishReason::Stop);
+        assert_eq!(finish_reason("stop_sequence"), FinishReason::StopSequence);
+        assert_eq!(finish_reason("tool_use"), FinishReason::ToolCalls);
+        assert_eq!(finish_reason("refusal"), FinishReason::Refusal);
+        assert_eq!(finish_reason("pause_turn"), FinishReason::Other("pause_turn".to_string()));
+    }
+
+    #[test]
+    fn test_images_are_sent_as_image_blocks_before_the_text() {
+        assert_eq!(content("What is 2+2?", &[]), json!("What is 2+2?"));
+        let images = ["https://example.com/cat.png".to_string(), "data:image/png;base64,iVBORw0KGgo=".to_string()];
+        assert_eq!(
+            content("What animal is this?", &images),
+            json!([
+                { "type": "image", "source": { "type": "url", "url": "https://example.com/cat.png" } },
+                { "type": "image", "source": { "type": "base64", "media_type": "image/png", "data": "iVBORw0KGgo=" } },
+                { "type": "text", "text": "What animal is this?" }
+            ])
+        );
+    }
+
+    #[test]
+    fn test_thinking_blocks_are_reasoning() {
+        let response: AnthropicResponse = serde_json::from_str(
+            r#"{"type": "message", "role": "assistant", "content": [
+                    {"type": "thinking", "thinking": "The user wants 2+2.", "signature": "EqQBCgIYAhIM1gbcDa9GJwZA2b3h"},
+                    {"type": "redacted_thinking", "data": "EmwKAhgBEgy3va3pzix"},
+                    {"type": "text", "text": "4"}
+                ],
+                "stop_reason": "end_turn", "usage": {"input_tokens": 40, "output_tokens": 90}}"#,
+        )
+        .unwrap();
+        assert_eq!(split_content(&response.content), (Some("4".to_string()), Some("The user wants 2+2.".to_string())));
+        let plain: AnthropicResponse = serde_json::from_str(
+            r#"{"content": [{"type": "text", "text": "4"}], "stop_reason": "end_turn", "usage": {"input_tokens": 1, "output_tokens": 1}}"#,
+        )
+        .unwrap();
+        assert_eq!(split_content(&plain.content), (Some("4".to_string()), None));
+    }
+
+    #[test]
+    fn test_interleaved_blocks_keep_their_order() {
+        let response: AnthropicResponse = serde_json::from_str(
+            r#"{"content": [
+                    {"type": "thinking", "thinking": "First, the units.", "signature": "a"},
+                    {"type": "text", "text": "It is 4 km"},
+                    {"type": "thinking", "thinking": "Now round it.", "signature": "b"},
+                    {"type": "text", "text": ", about 2.5 miles."}
+                ],
+                "stop_reason": "end_turn", "usage": {"input_tokens": 40, "output_tokens": 90}}"#,
+        )
+        .unwrap();
+        let (output, reasoning) = split_content(&response.content);
+        assert_eq!(output.as_deref(), Some("It is 4 km, about 2.5 miles."));
+        assert_eq!(reasoning.as_deref(), Some("First, the units.\n\nNow round it."));
+
+        let thinking_only: AnthropicResponse = serde_json::from_str(
+            r#"{"content": [{"type": "thinking", "thinking": "Let me see", "signature": "c"}],
+                "stop_reason": "max_tokens", "usage": {"input_tokens": 40, "output_tokens": 2048}}"#,
+        )
+        .unwrap();
+        assert_eq!(split_content(&thinking_only.content), (None, Some("Let me see".to_string())));
+    }
+
+    #[test]
+    fn test_max_tokens_are_checked_against_the_model_ceiling() {
+        let thinking = Some(ThinkingParams { budget_tokens: 2048 });
+        assert_eq!(max_tokens(None, None), DEFAULT_MAX_TOKENS);
+        assert_eq!(max_tokens(None, thinking), 2048 + DEFAULT_MAX_TOKENS);
+        assert_eq!(max_tokens(Some(3000), thinking), 3000);
+
+        assert_eq!(max_output_ceiling("claude-opus-4-5-20251101"), Some(64_000));
+        assert_eq!(max_output_ceiling("claude-opus-4-1-20250805"), Some(32_000));
+        assert_eq!(max_output_ceiling("claude-3-5-haiku-latest"), Some(8_192));
+        assert_eq!(max_output_ceiling("claude-next"), None);
 
-        Ok((output.to_string(), latency_ms, token_usage))
+        assert!(check_max_tokens("claude-sonnet-4-5", Some(64_000), None).is_ok());
+        assert!(check_max_tokens("claude-next", Some(1_000_000), None).is_ok());
+        assert_eq!(
+            check_max_tokens("claude-3-5-haiku-latest", Some(10_000), None).unwrap_err(),
+            "claude-3-5-haiku-latest generates at most 8192 tokens, but 10000 tokens were asked for"
+        );
+        let error = check_max_tokens("claude-3-haiku-20240307", None, thinking).unwrap_err();
+        assert!(error.contains("2048 of thinking budget"), "{}", error);
     }
 }
@@ -0,0 +1,191 @@
+// src/providers/capture.rs
+//! Debug capture of raw provider exchanges. Inside `capturing`, every non-streaming provider
+//! request sent through `recording::send` is kept: the request URL and body, the response
+//! status, the response headers worth seeing when a call fails, and the raw response body.
+//! Secrets are masked throughout with `redact::redact`, and bodies cut to `MAX_CAPTURED_BODY_BYTES`.
+
+use reqwest::header::HeaderMap;
+use reqwest::{Request, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use crate::redact::redact;
+
+/// Set to `1` or `true` to capture the exchanges of every eval, not just those asking for it.
+pub const CAPTURE_RAW_ENV: &str = "EVAL_CAPTURE_RAW";
+
+/// Longest request or response body kept, in bytes; longer ones are cut and marked truncated.
+pub const MAX_CAPTURED_BODY_BYTES: usize = 64 * 1024;
+
+/// Most exchanges kept per capture; retries past this are dropped.
+pub const MAX_CAPTURED_EXCHANGES: usize = 20;
+
+/// Response headers kept, besides those starting with one of `KEPT_HEADER_PREFIXES`.
+const KEPT_HEADERS: &[&str] = &["content-type", "retry-after", "request-id", "x-request-id"];
+const KEPT_HEADER_PREFIXES: &[&str] = &["x-ratelimit-", "anthropic-ratelimit-"];
+
+/// One provider call as captured.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RawExchange {
+    pub provider: String,
+    pub model: String,
+    pub method: String,
+    pub url: String,
+    pub request_body: Option<String>,
+    pub request_truncated: bool,
+    pub status: u16,
+    /// Rate limit, request id, retry and content type headers of the response.
+    pub response_headers: BTreeMap<String, String>,
+    pub response_body: String,
+    pub response_truncated: bool,
+    pub captured_at: String,
+}
+
+tokio::task_local! {
+    static CAPTURED: Arc<Mutex<Vec<RawExchange>>>;
+}
+
+/// Runs `future`, returning what it returned and the provider exchanges it made, in order.
+pub async fn capturing<F: Future>(future: F) -> (F::Output, Vec<RawExchange>) {
+    let captured = Arc::new(Mutex::new(Vec::new()));
+    let output = CAPTURED.scope(captured.clone(), future).await;
+    let exchanges = std::mem::take(&mut *captured.lock().unwrap_or_else(|poisoned| poisoned.into_inner()));
+    (output, exchanges)
+}
+
+/// The request half of an exchange being captured, taken before the request is sent.
+pub(crate) struct CapturedRequest {
+    method: String,
+    url: String,
+    body: Option<String>,
+}
+
+impl CapturedRequest {
+    /// The request, when a capture is running.
+    pub(crate) fn of(request: &Request) -> Option<Self> {
+        CAPTURED.try_with(|_| ()).ok()?;
+        let body = request.body().and_then(|body| body.as_bytes()).map(|bytes| redact(&String::from_utf8_lossy(bytes)));
+        Some(CapturedRequest { method: request.method().to_string(), url: redact(request.url().as_str()), body })
+    }
+
+    /// Keeps the exchange with the capture running, unless it already holds `MAX_CAPTURED_EXCHANGES`.
+    pub(crate) fn finish(self, provider: &str, model: &str, status: StatusCode, headers: &HeaderMap, body: &str) {
+        let (request_body, request_truncated) = match self.body {
+            Some(body) => {
+                let (body, truncated) = cap(body);
+                (Some(body), truncated)
+            }
+            None => (None, false),
+        };
+        let (response_body, response_truncated) = cap(redact(body));
+        let exchange = RawExchange {
+            provider: provider.to_string(),
+            model: model.to_string(),
+            method: self.method,
+            url: self.url,
+            request_body,
+            request_truncated,
+            status: status.as_u16(),
+            response_headers: kept_headers(headers),
+            response_body,
+            response_truncated,
+            captured_at: chrono::Utc::now().to_rfc3339(),
+        };
+        let _ = CAPTURED.try_with(|captured| {
+            let mut captured = captured.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            if captured.len() < MAX_CAPTURED_EXCHANGES {
+                captured.push(exchange);
+            }
+        });
+    }
+}
+
+/// `text` cut to at most `MAX_CAPTURED_BODY_BYTES` on a character boundary, and whether it was cut.
+fn cap(mut text: String) -> (String, bool) {
+    if text.len() <= MAX_CAPTURED_BODY_BYTES {
+        return (text, false);
+    }
+    let end = (0..=MAX_CAPTURED_BODY_BYTES).rev().find(|&i| text.is_char_boundary(i)).unwrap_or(0);
+    text.truncate(end);
+    (text, true)
+}
+
+fn kept_headers(headers: &HeaderMap) -> BTreeMap<String, String> {
+    headers
+        .iter()
+        .filter(|(name, _)| {
+            let name = name.as_str();
+            KEPT_HEADERS.contains(&name) || KEPT_HEADER_PREFIXES.iter().any(|prefix| name.starts_with(prefix))
+        })
+        .map(|(name, value)| (name.to_string(), redact(&String::from_utf8_lossy(value.as_bytes()))))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::redact::REDACTED;
+    use reqwest::header::HeaderValue;
+    use reqwest::Client;
+
+    fn request(body: serde_json::Value) -> Request {
+        Client::new()
+            .post("https://generativelanguage.googleapis.com/v1beta/models/gemini:generateContent?key=AIzaSyD-secret-key-of-39-characters-long&alt=json")
+            .header("Authorization", "Bearer sk-secret")
+            .json(&body)
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_captured_requests_are_redacted() {
+        let body = serde_json::json!({
+            "model": "m",
+            "messages": [{ "role": "user", "content": "hi" }],
+            "auth": { "api_key": "sk-proj-secret-key", "token": "t-secret-token" }
+        });
+        let ((), exchanges) = capturing(async {
+            let captured = CapturedRequest::of(&request(body)).unwrap();
+            let mut headers = HeaderMap::new();
+            headers.insert("x-ratelimit-remaining-requests", HeaderValue::from_static("0"));
+            headers.insert("set-cookie", HeaderValue::from_static("session=secret"));
+            captured.finish("gemini", "gemini", StatusCode::UNAUTHORIZED, &headers, "Incorrect API key provided: sk-proj-secret-key");
+        })
+        .await;
+
+        let [exchange] = exchanges.as_slice() else { panic!("expected one exchange, got {:?}", exchanges) };
+        assert!(!exchange.url.contains("AIzaSyD"), "{}", exchange.url);
+        assert!(exchange.url.contains("alt=json"), "{}", exchange.url);
+        let sent: serde_json::Value = serde_json::from_str(exchange.request_body.as_deref().unwrap()).unwrap();
+        assert_eq!(sent["auth"], serde_json::json!({ "api_key": REDACTED, "token": REDACTED }));
+        assert_eq!(sent["messages"][0]["content"], "hi");
+        assert_eq!(exchange.status, 401);
+        assert_eq!(exchange.response_headers.keys().collect::<Vec<_>>(), ["x-ratelimit-remaining-requests"]);
+        assert_eq!(exchange.response_body, format!("Incorrect API key provided: {}", REDACTED));
+    }
+
+    #[tokio::test]
+    async fn test_capture_caps_body_size_and_exchange_count() {
+        // Nothing is captured outside a capture
+        assert!(CapturedRequest::of(&request(serde_json::json!({}))).is_none());
+
+        let long = format!("{}é", "x".repeat(MAX_CAPTURED_BODY_BYTES - 1));
+        let ((), exchanges) = capturing(async {
+            for _ in 0..MAX_CAPTURED_EXCHANGES + 5 {
+                let captured = CapturedRequest::of(&request(serde_json::json!({ "prompt": "hi" }))).unwrap();
+                captured.finish("openai", "gpt-4o", StatusCode::OK, &HeaderMap::new(), &long);
+            }
+        })
+        .await;
+
+        assert_eq!(exchanges.len(), MAX_CAPTURED_EXCHANGES);
+        let exchange = &exchanges[0];
+        assert!(exchange.response_truncated);
+        assert!(!exchange.request_truncated);
+        // Cut before the two-byte character that would cross the cap
+        assert_eq!(exchange.response_body.len(), MAX_CAPTURED_BODY_BYTES - 1);
+        assert_eq!(cap("short".to_string()), ("short".to_string(), false));
+    }
+}
@@ -1,100 +1,418 @@
 // src/providers/gemini.rs
 
+use async_trait::async_trait;
+use reqwest::header::HeaderMap;
 use reqwest::Client;
 use serde_json::json;
 use std::time::Instant;
 
-use crate::config::GeminiConfig;
+use crate::config::{GeminiConfig, SafetyThreshold};
 use crate::errors::{EvalError, Result};
-use crate::providers::{LlmProvider, TokenUsage};
+use crate::providers::images::{self, Image};
+use crate::providers::{check_health, recording, FinishReason, Generation, GenerationParams, LlmProvider, TokenUsage};
+
+/// Harm categories `safetySettings` sets a threshold for.
+const HARM_CATEGORIES: [&str; 4] = [
+    "HARM_CATEGORY_HARASSMENT",
+    "HARM_CATEGORY_HATE_SPEECH",
+    "HARM_CATEGORY_SEXUALLY_EXPLICIT",
+    "HARM_CATEGORY_DANGEROUS_CONTENT",
+];
+
+/// Raw finish reasons of a candidate Gemini withheld for safety reasons.
+const SAFETY_FINISH_REASONS: [&str; 6] = ["SAFETY", "RECITATION", "BLOCKLIST", "PROHIBITED_CONTENT", "SPII", "IMAGE_SAFETY"];
 
 /// A provider for interacting with Google's Gemini models.
 pub struct GeminiProvider {
     client: Client,
     config: GeminiConfig,
+    /// Sent with every request in `generationConfig`, and images with the prompt.
+    params: GenerationParams,
+    /// Headers from `<PREFIX>_EXTRA_HEADERS`, sent with every request.
+    extra_headers: HeaderMap,
 }
 
 impl GeminiProvider {
     /// Creates a new `GeminiProvider`.
     pub fn new(client: Client, config: GeminiConfig) -> Self {
-        Self { client, config }
+        Self { client, config, params: GenerationParams::default(), extra_headers: HeaderMap::new() }
+    }
+
+    /// Sends `params` with every request. A response format asks for JSON, matching the
+    /// format's schema if it has one.
+    pub fn with_params(mut self, params: GenerationParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    /// Sends `headers` with every request, e.g. the ones an API gateway requires.
+    pub fn with_extra_headers(mut self, headers: HeaderMap) -> Self {
+        self.extra_headers = headers;
+        self
+    }
+}
+
+/// The `generationConfig` for `params`, or `None` when they set nothing.
+fn generation_config(params: &GenerationParams) -> Option<serde_json::Value> {
+    let mut config = serde_json::Map::new();
+    if let Some(format) = &params.response_format {
+        config.insert("responseMimeType".to_string(), json!("application/json"));
+        if let Some(schema) = format.schema() {
+            config.insert("responseSchema".to_string(), schema.clone());
+        }
+    }
+    if let Some(stop) = &params.stop {
+        config.insert("stopSequences".to_string(), json!(stop));
+    }
+    if let Some(max_output_tokens) = params.max_output_tokens {
+        config.insert("maxOutputTokens".to_string(), json!(max_output_tokens));
+    }
+    if let Some(temperature) = params.temperature {
+        config.insert("temperature".to_string(), json!(temperature));
+    }
+    if let Some(seed) = params.seed {
+        config.insert("seed".to_string(), json!(seed));
+    }
+    (!config.is_empty()).then_some(serde_json::Value::Object(config))
+}
+
+/// The parts of the request's content: an `inlineData` part per image, then `prompt`. Gemini
+/// takes inline images only, which `images::check_support` ensures.
+fn parts(prompt: &str, images: &[String]) -> serde_json::Value {
+    let mut parts: Vec<serde_json::Value> = images::parse_all(images)
+        .filter_map(|image| match image {
+            Image::Inline { media_type, data } => Some(json!({"inlineData": {"mimeType": media_type, "data": data}})),
+            Image::Url(_) => None,
+        })
+        .collect();
+    parts.push(json!({"text": prompt}));
+    json!(parts)
+}
+
+/// Maps the `finishReason` of a response's first candidate.
+fn finish_reason(response: &serde_json::Value) -> Option<FinishReason> {
+    let raw = response
+        .get("candidates")
+        .and_then(|c| c.get(0))
+        .and_then(|c| c.get("finishReason"))
+        .and_then(|r| r.as_str())?;
+    Some(match raw {
+        "STOP" => FinishReason::Stop,
+        "MAX_TOKENS" => FinishReason::Length,
+        raw if SAFETY_FINISH_REASONS.contains(&raw) => FinishReason::ContentFilter,
+        other => FinishReason::Other(other.to_string()),
+    })
+}
+
+/// The `safetySettings` of a request, one entry per harm category.
+fn safety_settings(threshold: SafetyThreshold) -> serde_json::Value {
+    HARM_CATEGORIES
+        .iter()
+        .map(|category| json!({"category": category, "threshold": threshold.as_str()}))
+        .collect()
+}
+
+/// Categories of `ratings` that caused a block: those Gemini marks `blocked`, or failing
+/// that, those rated a medium or high probability of harm.
+fn blocked_categories(ratings: Option<&serde_json::Value>) -> Vec<String> {
+    let ratings = ratings.and_then(|r| r.as_array()).map(Vec::as_slice).unwrap_or_default();
+    let category = |rating: &serde_json::Value| rating.get("category").and_then(|c| c.as_str()).map(str::to_string);
+    let blocked: Vec<String> = ratings
+        .iter()
+        .filter(|r| r.get("blocked").and_then(|b| b.as_bool()) == Some(true))
+        .filter_map(category)
+        .collect();
+    if !blocked.is_empty() {
+        return blocked;
+    }
+    ratings
+        .iter()
+        .filter(|r| matches!(r.get("probability").and_then(|p| p.as_str()), Some("MEDIUM" | "HIGH")))
+        .filter_map(category)
+        .collect()
+}
+
+/// Reads the answer out of a `generateContent` response. A blocked prompt, or an answer
+/// withheld for safety reasons, is `ContentBlocked`; an answer cut off at `MAX_TOKENS` is
+/// returned as far as it got, with finish reason `length`.
+fn parse_generation(response_json: &serde_json::Value, latency_ms: u64) -> Result<Generation> {
+    if let Some(error) = response_json.get("error") {
+        return Err(EvalError::ApiResponse(error.to_string()));
+    }
+
+    let prompt_feedback = response_json.get("promptFeedback");
+    if let Some(reason) = prompt_feedback.and_then(|f| f.get("blockReason")).and_then(|r| r.as_str()) {
+        return Err(EvalError::ContentBlocked {
+            reason: reason.to_string(),
+            categories: blocked_categories(prompt_feedback.and_then(|f| f.get("safetyRatings"))),
+        });
     }
+
+    let usage_metadata = response_json.get("usageMetadata");
+    let token_usage = TokenUsage {
+        input_tokens: usage_metadata.and_then(|m| m.get("promptTokenCount")).and_then(|t| t.as_u64()).map(|t| t as u32),
+        output_tokens: usage_metadata.and_then(|m| m.get("candidatesTokenCount")).and_then(|t| t.as_u64()).map(|t| t as u32),
+    };
+
+    let candidate = response_json
+        .get("candidates")
+        .and_then(|c| c.get(0))
+        .ok_or_else(|| EvalError::UnexpectedResponse(response_json.to_string()))?;
+    let raw_reason = candidate.get("finishReason").and_then(|r| r.as_str());
+    // Thought summaries are not part of the answer but its reasoning
+    let parts = candidate.get("content").and_then(|c| c.get("parts")).and_then(|p| p.as_array());
+    let texts = |thought: bool| -> Option<String> {
+        parts.map(|parts| {
+            parts
+                .iter()
+                .filter(|p| (p.get("thought").and_then(|t| t.as_bool()) == Some(true)) == thought)
+                .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+                .collect()
+        })
+    };
+    let output = texts(false);
+    let reasoning = texts(true).filter(|reasoning| !reasoning.is_empty());
+
+    let output = match (output, raw_reason) {
+        (Some(output), _) if !output.is_empty() => output,
+        (_, Some(reason)) if SAFETY_FINISH_REASONS.contains(&reason) => {
+            return Err(EvalError::ContentBlocked {
+                reason: reason.to_string(),
+                categories: blocked_categories(candidate.get("safetyRatings")),
+            });
+        }
+        // The output token limit was used up, e.g. by thinking, before any answer
+        (output, Some("MAX_TOKENS")) => output.unwrap_or_default(),
+        (Some(_), _) => return Err(EvalError::EmptyResponse),
+        (None, _) => return Err(EvalError::UnexpectedResponse(response_json.to_string())),
+    };
+
+    Ok(Generation {
+        output,
+        latency_ms,
+        token_usage,
+        finish_reason: finish_reason(response_json),
+        resolved_model: None,
+        reasoning,
+    })
 }
 
+#[async_trait]
 impl LlmProvider for GeminiProvider {
     /// Calls the Gemini API with a given prompt and returns the model's response text and latency.
-    async fn generate(&self, model: &str, prompt: &str) -> Result<(String, u64, TokenUsage)> {
+    async fn generate(&self, model: &str, prompt: &str) -> Result<Generation> {
         let url = format!(
             "{}/v1beta/models/{}:generateContent",
             self.config.api_base.trim_end_matches('/'),
             model
         );
 
-        println!("📡 Calling Gemini: {} with model: {}", url, model);
-
-        let body = json!({
-            // Add safety settings to prevent content blocking
-            "safetySettings": [
-                {"category": "HARM_CATEGORY_HARASSMENT", "threshold": "BLOCK_NONE"},
-                {"category": "HARM_CATEGORY_HATE_SPEECH", "threshold": "BLOCK_NONE"},
-                {"category": "HARM_CATEGORY_SEXUALLY_EXPLICIT", "threshold": "BLOCK_NONE"},
-                {"category": "HARM_CATEGORY_DANGEROUS_CONTENT", "threshold": "BLOCK_NONE"}
-            ],
-            "contents": [{"parts": [{"text": prompt}]}]
+        tracing::info!("📡 Calling Gemini: {} with model: {}", url, model);
+
+        let mut body = json!({
+            "safetySettings": safety_settings(self.config.safety_threshold),
+            "contents": [{"parts": parts(prompt, &self.params.images)}]
         });
+        if let Some(config) = generation_config(&self.params) {
+            body["generationConfig"] = config;
+        }
 
         let start = Instant::now();
 
-        let resp = self
+        let request = self
             .client
             .post(&url)
             .header("x-goog-api-key", &self.config.api_key)
-            .json(&body)
-            .send()
-            .await?;
+            .headers(self.extra_headers.clone())
+            .json(&body);
+        let (status, text) = recording::send(&self.client, request, "gemini", model, prompt).await?;
 
-        let status = resp.status();
         let latency_ms = start.elapsed().as_millis() as u64;
 
-        println!("📥 Gemini response status: {} ({}ms)", status, latency_ms);
+        tracing::info!("📥 Gemini response status: {} ({}ms)", status, latency_ms);
 
         if !status.is_success() {
-            let error_body = resp
-                .text()
-                .await
-                .unwrap_or_else(|_| "Could not read error body".to_string());
             return Err(EvalError::ApiError {
                 status: status.as_u16(),
-                body: error_body,
+                body: text,
             });
         }
 
-        let response_json: serde_json::Value = resp.json().await?;
+        let response_json: serde_json::Value = serde_json::from_str(&text)?;
+        let generation = parse_generation(&response_json, latency_ms);
+        if let Err(EvalError::ContentBlocked { reason, categories }) = &generation {
+            tracing::warn!("🛑 Gemini blocked the content: {} {:?}", reason, categories);
+        }
+        generation
+    }
+
+    /// Lists the models, which needs a valid key but costs nothing.
+    async fn health(&self) -> Result<()> {
+        let url = format!("{}/v1beta/models", self.config.api_base.trim_end_matches('/'));
+        let request = self.client.get(&url).header("x-goog-api-key", &self.config.api_key);
+        check_health(request.headers(self.extra_headers.clone())).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::response_format::ResponseFormat;
+
+    fn candidate(reason: &str) -> serde_json::Value {
+        json!({
+            "candidates": [{"content": {"parts": [{"text": "The answer is"}], "role": "model"}, "finishReason": reason, "index": 0}],
+            "usageMetadata": {"promptTokenCount": 12, "candidatesTokenCount": 8192}
+        })
+    }
+
+    #[test]
+    fn test_finish_reason_from_raw_payloads() {
+        assert_eq!(finish_reason(&candidate("STOP")), Some(FinishReason::Stop));
+        assert_eq!(finish_reason(&candidate("MAX_TOKENS")), Some(FinishReason::Length));
+        assert_eq!(finish_reason(&candidate("SAFETY")), Some(FinishReason::ContentFilter));
+        assert_eq!(finish_reason(&candidate("RECITATION")), Some(FinishReason::ContentFilter));
+        assert_eq!(
+            finish_reason(&candidate("MALFORMED_FUNCTION_CALL")),
+            Some(FinishReason::Other("MALFORMED_FUNCTION_CALL".to_string()))
+        );
+        assert_eq!(finish_reason(&json!({"candidates": [{"content": {"parts": []}}]})), None);
+    }
 
-        if let Some(error) = response_json.get("error") {
-            return Err(EvalError::ApiResponse(error.to_string()));
+    #[test]
+    fn test_blocked_prompt_is_content_blocked() {
+        let response = json!({
+            "promptFeedback": {
+                "blockReason": "SAFETY",
+                "safetyRatings": [
+                    {"category": "HARM_CATEGORY_SEXUALLY_EXPLICIT", "probability": "NEGLIGIBLE"},
+                    {"category": "HARM_CATEGORY_HATE_SPEECH", "probability": "LOW"},
+                    {"category": "HARM_CATEGORY_HARASSMENT", "probability": "NEGLIGIBLE"},
+                    {"category": "HARM_CATEGORY_DANGEROUS_CONTENT", "probability": "HIGH", "blocked": true}
+                ]
+            },
+            "usageMetadata": {"promptTokenCount": 9, "totalTokenCount": 9},
+            "modelVersion": "gemini-1.5-flash-002"
+        });
+        match parse_generation(&response, 120) {
+            Err(EvalError::ContentBlocked { reason, categories }) => {
+                assert_eq!(reason, "SAFETY");
+                assert_eq!(categories, ["HARM_CATEGORY_DANGEROUS_CONTENT"]);
+            }
+            other => panic!("expected ContentBlocked, got {:?}", other.map(|g| g.output)),
         }
 
-        let usage_metadata = response_json.get("usageMetadata");
-        let token_usage = TokenUsage {
-            input_tokens: usage_metadata.and_then(|m| m.get("promptTokenCount")).and_then(|t| t.as_u64()).map(|t| t as u32),
-            output_tokens: usage_metadata.and_then(|m| m.get("candidatesTokenCount")).and_then(|t| t.as_u64()).map(|t| t as u32),
-        };
-
-        let output = response_json
-            .get("candidates")
-            .and_then(|c| c.get(0))
-            .and_then(|c| c.get("content"))
-            .and_then(|c| c.get("parts"))
-            .and_then(|p| p.get(0))
-            .and_then(|p| p.get("text"))
-            .and_then(|t| t.as_str())
-            .ok_or_else(|| EvalError::UnexpectedResponse(response_json.to_string()))?;
-
-        if output.is_empty() {
-            return Err(EvalError::EmptyResponse);
+        let other_reason = json!({"promptFeedback": {"blockReason": "PROHIBITED_CONTENT"}});
+        match parse_generation(&other_reason, 120) {
+            Err(e @ EvalError::ContentBlocked { .. }) => {
+                assert_eq!(e.code().0, "content_blocked");
+                assert_eq!(e.code().1.as_u16(), 422);
+                assert_eq!(e.to_response().details, Some(json!({"reason": "PROHIBITED_CONTENT", "categories": []})));
+            }
+            other => panic!("expected ContentBlocked, got {:?}", other.map(|g| g.output)),
         }
+    }
 
-        Ok((output.to_string(), latency_ms, token_usage))
+    #[test]
+    fn test_answer_withheld_for_safety_is_content_blocked() {
+        let response = json!({
+            "candidates": [{
+                "finishReason": "SAFETY",
+                "index": 0,
+                "safetyRatings": [
+                    {"category": "HARM_CATEGORY_SEXUALLY_EXPLICIT", "probability": "NEGLIGIBLE"},
+                    {"category": "HARM_CATEGORY_HATE_SPEECH", "probability": "MEDIUM"},
+                    {"category": "HARM_CATEGORY_HARASSMENT", "probability": "HIGH"},
+                    {"category": "HARM_CATEGORY_DANGEROUS_CONTENT", "probability": "NEGLIGIBLE"}
+                ]
+            }],
+            "usageMetadata": {"promptTokenCount": 14, "totalTokenCount": 14}
+        });
+        match parse_generation(&response, 80) {
+            Err(EvalError::ContentBlocked { reason, categories }) => {
+                assert_eq!(reason, "SAFETY");
+                assert_eq!(categories, ["HARM_CATEGORY_HATE_SPEECH", "HARM_CATEGORY_HARASSMENT"]);
+            }
+            other => panic!("expected ContentBlocked, got {:?}", other.map(|g| g.output)),
+        }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_max_tokens_returns_the_partial_answer() {
+        let response = json!({
+            "candidates": [{
+                "content": {"parts": [{"text": "Step 1: add the "}, {"text": "numbers"}], "role": "model"},
+                "finishReason": "MAX_TOKENS",
+                "index": 0
+            }],
+            "usageMetadata": {"promptTokenCount": 12, "candidatesTokenCount": 16}
+        });
+        let generation = parse_generation(&response, 300).unwrap();
+        assert_eq!(generation.output, "Step 1: add the numbers");
+        assert_eq!(generation.finish_reason, Some(FinishReason::Length));
+        assert_eq!(generation.token_usage.output_tokens, Some(16));
+
+        // Thinking used up the whole budget, so there is no text at all
+        let thought_out = json!({
+            "candidates": [{"content": {"role": "model"}, "finishReason": "MAX_TOKENS", "index": 0}],
+            "usageMetadata": {"promptTokenCount": 12, "thoughtsTokenCount": 63}
+        });
+        let generation = parse_generation(&thought_out, 300).unwrap();
+        assert_eq!(generation.output, "");
+        assert_eq!(generation.finish_reason, Some(FinishReason::Length));
+
+        let empty = json!({"candidates": [{"content": {"parts": [{"text": ""}]}, "finishReason": "STOP"}]});
+        assert!(matches!(parse_generation(&empty, 300), Err(EvalError::EmptyResponse)));
+    }
+
+    #[test]
+    fn test_thought_summaries_are_reasoning() {
+        let response = json!({
+            "candidates": [{
+                "content": {"parts": [{"text": "Adding 2 and 2.", "thought": true}, {"text": "4"}], "role": "model"},
+                "finishReason": "STOP",
+                "index": 0
+            }]
+        });
+        let generation = parse_generation(&response, 300).unwrap();
+        assert_eq!(generation.output, "4");
+        assert_eq!(generation.reasoning.as_deref(), Some("Adding 2 and 2."));
+    }
+
+    #[test]
+    fn test_generation_config_asks_for_json_and_the_schema() {
+        let params = |format| GenerationParams { response_format: Some(format), ..Default::default() };
+        assert_eq!(generation_config(&params(ResponseFormat::Json)), Some(json!({"responseMimeType": "application/json"})));
+        let schema = json!({"type": "object", "properties": {"answer": {"type": "integer"}}});
+        let config = generation_config(&params(ResponseFormat::JsonSchema { schema: schema.clone() })).unwrap();
+        assert_eq!(config["responseMimeType"], "application/json");
+        assert_eq!(config["responseSchema"], schema);
+        assert_eq!(generation_config(&GenerationParams::default()), None);
+    }
+
+    #[test]
+    fn test_generation_config_sets_stop_sequences_and_the_token_limit() {
+        let params = GenerationParams { stop: Some(vec!["\n\n".to_string()]), max_output_tokens: Some(64), ..Default::default() };
+        assert_eq!(generation_config(&params), Some(json!({"stopSequences": ["\n\n"], "maxOutputTokens": 64})));
+    }
+
+    #[test]
+    fn test_images_are_sent_as_inline_data_parts() {
+        assert_eq!(parts("What is 2+2?", &[]), json!([{"text": "What is 2+2?"}]));
+        let images = ["data:image/jpeg;base64,/9j/4AAQ".to_string()];
+        assert_eq!(
+            parts("What animal is this?", &images),
+            json!([{"inlineData": {"mimeType": "image/jpeg", "data": "/9j/4AAQ"}}, {"text": "What animal is this?"}])
+        );
+    }
+
+    #[test]
+    fn test_safety_settings_use_the_threshold_for_every_category() {
+        let settings = safety_settings(SafetyThreshold::BlockOnlyHigh);
+        let settings = settings.as_array().unwrap();
+        assert_eq!(settings.len(), HARM_CATEGORIES.len());
+        assert!(settings.iter().all(|s| s["threshold"] == "BLOCK_ONLY_HIGH"));
+        assert_eq!(SafetyThreshold::parse("block_medium_and_above"), Some(SafetyThreshold::BlockMediumAndAbove));
+        assert_eq!(SafetyThreshold::parse("BLOCK_SOME"), None);
+    }
+}
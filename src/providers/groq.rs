@@ -0,0 +1,186 @@
+// src/providers/groq.rs
+
+use async_trait::async_trait;
+use reqwest::header::HeaderMap;
+use reqwest::Client;
+
+use crate::config::{GroqConfig, OpenAIConfig};
+use crate::errors::Result;
+use crate::providers::openai::OpenAIProvider;
+use crate::providers::{AbortCheck, Generation, GenerationParams, LlmProvider, StreamedGeneration};
+
+/// Share of the request quota left at which Groq's rate-limit headers are logged as a warning.
+const LOW_REMAINING_SHARE: f64 = 0.1;
+
+/// A provider for Groq, whose chat-completions API is OpenAI-compatible. Its own provider so
+/// it can be used next to OpenAI, with its rate-limit headers watched.
+pub struct GroqProvider {
+    inner: OpenAIProvider,
+}
+
+impl GroqProvider {
+    /// Creates a new `GroqProvider`.
+    pub fn new(client: Client, config: GroqConfig) -> Self {
+        let config = OpenAIConfig { api_base: config.api_base, api_key: config.api_key, models: config.models };
+        Self { inner: OpenAIProvider::compatible(client, "groq", config).with_header_hook(log_rate_limits) }
+    }
+
+    /// Sends `params` with every request; see `OpenAIProvider::with_params`.
+    pub fn with_params(self, params: GenerationParams) -> Self {
+        Self { inner: self.inner.with_params(params) }
+    }
+
+    /// Sends `headers` with every request; see `OpenAIProvider::with_extra_headers`.
+    pub fn with_extra_headers(self, headers: HeaderMap) -> Self {
+        Self { inner: self.inner.with_extra_headers(headers) }
+    }
+}
+
+fn header_number(headers: &HeaderMap, name: &str) -> Option<u64> {
+    headers.get(name)?.to_str().ok()?.trim().parse().ok()
+}
+
+/// A warning when `x-ratelimit-remaining-requests` is down to `LOW_REMAINING_SHARE` of
+/// `x-ratelimit-limit-requests` (or to zero when the limit is not sent).
+fn rate_limit_warning(headers: &HeaderMap) -> Option<String> {
+    let remaining = header_number(headers, "x-ratelimit-remaining-requests")?;
+    let limit = header_number(headers, "x-ratelimit-limit-requests");
+    let low = match limit {
+        Some(limit) => (remaining as f64) <= limit as f64 * LOW_REMAINING_SHARE,
+        None => remaining == 0,
+    };
+    if !low {
+        return None;
+    }
+    let reset = headers.get("x-ratelimit-reset-requests").and_then(|v| v.to_str().ok()).unwrap_or("unknown");
+    Some(match limit {
+        Some(limit) => format!("Groq request quota low: {} of {} left, resets in {}", remaining, limit, reset),
+        None => format!("Groq request quota low: {} left, resets in {}", remaining, reset),
+    })
+}
+
+fn log_rate_limits(headers: &HeaderMap) {
+    if let Some(warning) = rate_limit_warning(headers) {
+        tracing::warn!("🚦 {}", warning);
+    }
+}
+
+#[async_trait]
+impl LlmProvider for GroqProvider {
+    async fn generate(&self, model: &str, prompt: &str) -> Result<Generation> {
+        self.inner.generate(model, prompt).await
+    }
+
+    async fn generate_streaming(
+        &self,
+        model: &str,
+        prompt: &str,
+        should_abort: AbortCheck<'_>,
+    ) -> Result<StreamedGeneration> {
+        self.inner.generate_streaming(model, prompt, should_abort).await
+    }
+
+    async fn health(&self) -> Result<()> {
+        self.inner.health().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::EvalError;
+    use crate::providers::middleware::{RetryPolicy, RetryProvider};
+    use std::time::Duration;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn provider(server: &MockServer) -> GroqProvider {
+        let config = GroqConfig {
+            api_base: format!("{}/openai/v1", server.uri()),
+            api_key: "gsk-test".to_string(),
+            models: vec!["llama-3.3-70b-versatile".to_string()],
+        };
+        GroqProvider::new(Client::new(), config)
+    }
+
+    fn completion() -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "choices": [{ "message": { "role": "assistant", "content": "Verdict: PASS" }, "finish_reason": "stop" }],
+            "usage": { "prompt_tokens": 42, "completion_tokens": 3, "total_tokens": 45 }
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_generate_parses_output_and_usage() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/openai/v1/chat/completions"))
+            .and(header("Authorization", "Bearer gsk-test"))
+            .respond_with(completion().insert_header("x-ratelimit-remaining-requests", "14399"))
+            .mount(&server)
+            .await;
+
+        let generation = provider(&server).generate("llama-3.3-70b-versatile", "Judge this").await.unwrap();
+        assert_eq!(generation.output, "Verdict: PASS");
+        assert_eq!(generation.token_usage.input_tokens, Some(42));
+        assert_eq!(generation.token_usage.output_tokens, Some(3));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_calls_fail_with_429_and_are_retried() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(
+                ResponseTemplate::new(429)
+                    .insert_header("x-ratelimit-limit-requests", "30")
+                    .insert_header("x-ratelimit-remaining-requests", "0")
+                    .set_body_string(r#"{"error":{"message":"Rate limit reached","type":"requests","code":"rate_limit_exceeded"}}"#),
+            )
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST")).respond_with(completion()).mount(&server).await;
+
+        match provider(&server).generate("llama-3.3-70b-versatile", "Judge this").await {
+            Err(EvalError::ApiError { status: 429, body }) => assert!(body.contains("rate_limit_exceeded")),
+            other => panic!("expected a 429, got {:?}", other.map(|g| g.output)),
+        }
+
+        let policy = RetryPolicy { max_retries: 1, initial_backoff: Duration::from_millis(10) };
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(429))
+            .up_to_n_times(1)
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        let retried = RetryProvider::new(provider(&server), policy).generate("llama-3.3-70b-versatile", "Judge this").await;
+        assert_eq!(retried.unwrap().output, "Verdict: PASS");
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_low_request_quotas_are_warned_about() {
+        let headers = |pairs: &[(&'static str, &'static str)]| {
+            let mut map = HeaderMap::new();
+            for (name, value) in pairs {
+                map.insert(*name, value.parse().unwrap());
+            }
+            map
+        };
+        assert_eq!(
+            rate_limit_warning(&headers(&[
+                ("x-ratelimit-limit-requests", "14400"),
+                ("x-ratelimit-remaining-requests", "1000"),
+                ("x-ratelimit-reset-requests", "2m59.56s"),
+            ])),
+            Some("Groq request quota low: 1000 of 14400 left, resets in 2m59.56s".to_string())
+        );
+        assert_eq!(
+            rate_limit_warning(&headers(&[("x-ratelimit-limit-requests", "14400"), ("x-ratelimit-remaining-requests", "14399")])),
+            None
+        );
+        assert!(rate_limit_warning(&headers(&[("x-ratelimit-remaining-requests", "0")])).is_some());
+        assert_eq!(rate_limit_warning(&headers(&[("x-ratelimit-remaining-requests", "3")])), None);
+        assert_eq!(rate_limit_warning(&HeaderMap::new()), None);
+    }
+}
@@ -0,0 +1,130 @@
+// src/providers/images.rs
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::errors::{EvalError, Result};
+
+/// Most images a single eval may carry.
+pub const MAX_IMAGES: usize = 8;
+
+/// Largest inline image an eval may carry, in decoded bytes.
+pub const MAX_IMAGE_BYTES: usize = 5 * 1024 * 1024;
+
+/// Providers that take inline images only; they cannot fetch an image from a URL.
+const INLINE_ONLY_PROVIDERS: [&str; 2] = ["gemini", "ollama"];
+
+/// An image input of an eval: a URL the provider fetches, or a base64 `data:` URI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Image<'a> {
+    Url(&'a str),
+    Inline { media_type: &'a str, data: &'a str },
+}
+
+impl<'a> Image<'a> {
+    /// Reads an `http(s)://` URL or a `data:image/...;base64,...` URI; `None` for anything else.
+    pub fn parse(image: &'a str) -> Option<Self> {
+        if image.starts_with("https://") || image.starts_with("http://") {
+            return Some(Image::Url(image));
+        }
+        let (header, data) = image.strip_prefix("data:")?.split_once(',')?;
+        let media_type = header.strip_suffix(";base64")?;
+        let valid = media_type.starts_with("image/") && is_base64(data);
+        valid.then_some(Image::Inline { media_type, data })
+    }
+
+    /// Size of the decoded image; zero for a URL, whose size is not known.
+    pub fn byte_size(&self) -> usize {
+        match self {
+            Image::Url(_) => 0,
+            Image::Inline { data, .. } => data.len() / 4 * 3 - data.chars().rev().take_while(|&c| c == '=').count(),
+        }
+    }
+
+    /// How the image is stored with an evaluation: inline images by hash and size only.
+    pub fn record(&self) -> ImageRecord {
+        match self {
+            Image::Url(url) => ImageRecord::Url { url: url.to_string() },
+            Image::Inline { media_type, data } => ImageRecord::Inline {
+                media_type: media_type.to_string(),
+                sha256: format!("{:x}", Sha256::digest(data.as_bytes())),
+                bytes: self.byte_size(),
+            },
+        }
+    }
+}
+
+/// An image as stored with an evaluation, so base64 payloads never reach the database.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ImageRecord {
+    Url { url: String },
+    /// `sha256` is the hex SHA-256 of the base64 payload; `bytes` its decoded size.
+    Inline { media_type: String, sha256: String, bytes: usize },
+}
+
+/// Whether `data` is padded standard base64.
+fn is_base64(data: &str) -> bool {
+    let unpadded = data.trim_end_matches('=');
+    !data.is_empty()
+        && data.len().is_multiple_of(4)
+        && data.len() - unpadded.len() <= 2
+        && unpadded.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'+' || b == b'/')
+}
+
+/// The parsed `images`, skipping any that are neither URLs nor data URIs, which validation rejects.
+pub fn parse_all(images: &[String]) -> impl Iterator<Item = Image<'_>> {
+    images.iter().filter_map(|image| Image::parse(image))
+}
+
+/// Fails with a `Config` error naming `provider` when it cannot take `images`: Gemini and
+/// Ollama only take inline images.
+pub fn check_support(provider: &str, images: &[String]) -> Result<()> {
+    let has_url = parse_all(images).any(|image| matches!(image, Image::Url(_)));
+    if has_url && INLINE_ONLY_PROVIDERS.contains(&provider) {
+        return Err(EvalError::Config(format!(
+            "provider '{}' does not fetch image URLs; pass images as base64 data: URIs",
+            provider
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_images_parse_as_urls_or_base64_data_uris() {
+        assert_eq!(Image::parse("https://example.com/cat.png"), Some(Image::Url("https://example.com/cat.png")));
+        let inline = Image::parse("data:image/png;base64,iVBORw0KGgo=").unwrap();
+        assert_eq!(inline, Image::Inline { media_type: "image/png", data: "iVBORw0KGgo=" });
+        assert_eq!(inline.byte_size(), 8);
+
+        assert_eq!(Image::parse("ftp://example.com/cat.png"), None);
+        assert_eq!(Image::parse("data:text/plain;base64,aGk="), None);
+        assert_eq!(Image::parse("data:image/png,not-base64"), None);
+        assert_eq!(Image::parse("data:image/png;base64,iVBOR"), None);
+    }
+
+    #[test]
+    fn test_inline_images_are_recorded_by_hash_and_size() {
+        let record = Image::parse("data:image/jpeg;base64,/9j/4AAQ").unwrap().record();
+        let ImageRecord::Inline { media_type, sha256, bytes } = &record else { panic!("{:?}", record) };
+        assert_eq!((media_type.as_str(), sha256.len(), *bytes), ("image/jpeg", 64, 6));
+        assert_eq!(
+            serde_json::to_value(Image::Url("https://example.com/cat.png").record()).unwrap(),
+            serde_json::json!({ "type": "url", "url": "https://example.com/cat.png" })
+        );
+    }
+
+    #[test]
+    fn test_url_images_are_a_config_error_for_inline_only_providers() {
+        let images = vec!["https://example.com/cat.png".to_string()];
+        let err = check_support("ollama", &images).unwrap_err();
+        assert!(matches!(&err, EvalError::Config(message) if message.contains("'ollama'")), "{}", err);
+        assert!(check_support("gemini", &images).is_err());
+        assert!(check_support("openai", &images).is_ok());
+        assert!(check_support("gemini", &["data:image/png;base64,iVBORw0KGgo=".to_string()]).is_ok());
+    }
+}
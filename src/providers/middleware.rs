@@ -0,0 +1,351 @@
+// src/providers/middleware.rs
+
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::errors::{EvalError, Result};
+use crate::providers::{AbortCheck, Generation, LlmProvider, StreamedGeneration};
+
+/// Wraps a provider in another provider that adds behaviour around its calls.
+/// Middleware composes by wrapping the result of the previous layer.
+pub trait ProviderMiddleware: Send + Sync {
+    fn wrap(&self, inner: Box<dyn LlmProvider>) -> Box<dyn LlmProvider>;
+}
+
+/// Applies `layers` in order, so the last layer is the outermost.
+pub fn with_middleware(
+    provider: Box<dyn LlmProvider>,
+    layers: &[&dyn ProviderMiddleware],
+) -> Box<dyn LlmProvider> {
+    layers.iter().fold(provider, |inner, layer| layer.wrap(inner))
+}
+
+/// How many times to retry a failed call and how long to wait before the first retry.
+/// The wait doubles after every attempt.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(500),
+        }
+    }
+}
+
+impl ProviderMiddleware for RetryPolicy {
+    fn wrap(&self, inner: Box<dyn LlmProvider>) -> Box<dyn LlmProvider> {
+        Box::new(RetryProvider::new(inner, *self))
+    }
+}
+
+/// Characters of an error body kept in a `ProviderErrorEvent`.
+const MAX_ERROR_BODY_CHARS: usize = 2000;
+
+/// One failed provider call attempt, reported once the call as a whole has finished.
+#[derive(Debug, Clone)]
+pub struct ProviderErrorEvent {
+    pub provider: String,
+    pub model: String,
+    pub status_code: Option<u16>,
+    pub error_class: &'static str,
+    /// Error body, truncated to `MAX_ERROR_BODY_CHARS`.
+    pub body: String,
+    /// Zero-based attempt the error occurred on.
+    pub attempt: u32,
+    /// A later retry of the same call succeeded.
+    pub recovered: bool,
+}
+
+/// Receives the errors a `RetryProvider` saw, e.g. to persist them.
+#[async_trait]
+pub trait ProviderErrorSink: Send + Sync {
+    async fn record(&self, event: ProviderErrorEvent);
+}
+
+/// Coarse category of a provider error, for aggregation.
+pub fn error_class(err: &EvalError) -> &'static str {
+    match err {
+        EvalError::ApiError { status: 429, .. } => "rate_limited",
+        EvalError::ApiError { status, .. } if *status >= 500 => "server_error",
+        EvalError::ApiError { .. } => "client_error",
+        EvalError::Request(e) if e.is_timeout() => "timeout",
+        EvalError::Request(e) if e.is_connect() => "connection",
+        EvalError::Request(_) => "request",
+        EvalError::JsonParse(_) | EvalError::UnexpectedResponse(_) => "malformed_response",
+        EvalError::EmptyResponse => "empty_response",
+        EvalError::ContentBlocked { .. } => "content_blocked",
+        EvalError::ApiResponse(_) => "api_response",
+        _ => "other",
+    }
+}
+
+/// Retries rate-limited (429), server-side (5xx) and connection failures of any inner provider.
+pub struct RetryProvider<P> {
+    inner: P,
+    policy: RetryPolicy,
+    /// Provider name given to reported errors, and the sinks they go to.
+    error_sinks: Option<(String, Vec<Arc<dyn ProviderErrorSink>>)>,
+}
+
+impl<P: LlmProvider> RetryProvider<P> {
+    pub fn new(inner: P, policy: RetryPolicy) -> Self {
+        Self { inner, policy, error_sinks: None }
+    }
+
+    /// Reports every error seen while calling `provider`, including ones a retry recovered from.
+    /// Can be called more than once; every sink gets every error.
+    pub fn with_error_sink(mut self, provider: impl Into<String>, sink: Arc<dyn ProviderErrorSink>) -> Self {
+        match &mut self.error_sinks {
+            Some((_, sinks)) => sinks.push(sink),
+            None => self.error_sinks = Some((provider.into(), vec![sink])),
+        }
+        self
+    }
+
+    /// Captures `err` for the error sink, if there is one.
+    fn note_error(&self, errors: &mut Vec<ProviderErrorEvent>, model: &str, attempt: u32, err: &EvalError) {
+        let Some((provider, _)) = &self.error_sinks else {
+            return;
+        };
+        let (status_code, body) = match err {
+            EvalError::ApiError { status, body } => (Some(*status), body.clone()),
+            other => (None, other.to_string()),
+        };
+        errors.push(ProviderErrorEvent {
+            provider: provider.clone(),
+            model: model.to_string(),
+            status_code,
+            error_class: error_class(err),
+            body: body.chars().take(MAX_ERROR_BODY_CHARS).collect(),
+            attempt,
+            recovered: false,
+        });
+    }
+
+    async fn report<T>(&self, mut errors: Vec<ProviderErrorEvent>, model: &str, attempt: u32, result: &Result<T>) {
+        if let Err(e) = result {
+            self.note_error(&mut errors, model, attempt, e);
+        }
+        let Some((_, sinks)) = &self.error_sinks else {
+            return;
+        };
+        for mut event in errors {
+            event.recovered = result.is_ok();
+            for sink in sinks {
+                sink.record(event.clone()).await;
+            }
+        }
+    }
+
+    async fn backoff(&self, attempt: u32, err: &EvalError) {
+        let delay = self.policy.initial_backoff * 2u32.saturating_pow(attempt);
+        tracing::info!("🔁 Retrying after error ({}), attempt {} of {} in {}ms", err, attempt + 1, self.policy.max_retries, delay.as_millis());
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Scrubs `secrets` from the errors of the inner provider, so keys a request supplied for
+/// itself are not echoed into logs, responses or the database by a provider that repeats them.
+pub struct RedactingProvider<P> {
+    inner: P,
+    secrets: Vec<String>,
+}
+
+impl<P: LlmProvider> RedactingProvider<P> {
+    pub fn new(inner: P, secrets: Vec<String>) -> Self {
+        Self { inner, secrets }
+    }
+}
+
+#[async_trait]
+impl<P: LlmProvider> LlmProvider for RedactingProvider<P> {
+    async fn generate(&self, model: &str, prompt: &str) -> Result<Generation> {
+        self.inner.generate(model, prompt).await.map_err(|e| e.redacted(&self.secrets))
+    }
+
+    async fn generate_streaming(
+        &self,
+        model: &str,
+        prompt: &str,
+        should_abort: AbortCheck<'_>,
+    ) -> Result<StreamedGeneration> {
+        self.inner.generate_streaming(model, prompt, should_abort).await.map_err(|e| e.redacted(&self.secrets))
+    }
+
+    async fn health(&self) -> Result<()> {
+        self.inner.health().await.map_err(|e| e.redacted(&self.secrets))
+    }
+}
+
+/// Whether a failed call is worth repeating unchanged.
+pub fn is_retryable(err: &EvalError) -> bool {
+    match err {
+        EvalError::ApiError { status, .. } => *status == 429 || *status >= 500,
+        EvalError::Request(e) => e.is_timeout() || e.is_connect(),
+        _ => false,
+    }
+}
+
+#[async_trait]
+impl<P: LlmProvider> LlmProvider for RetryProvider<P> {
+    async fn generate(&self, model: &str, prompt: &str) -> Result<Generation> {
+        let mut attempt = 0;
+        let mut errors = Vec::new();
+        let result = loop {
+            match self.inner.generate(model, prompt).await {
+                Err(e) if attempt < self.policy.max_retries && is_retryable(&e) => {
+                    self.note_error(&mut errors, model, attempt, &e);
+                    self.backoff(attempt, &e).await;
+                    attempt += 1;
+                }
+                result => break result,
+            }
+        };
+        self.report(errors, model, attempt, &result).await;
+        result
+    }
+
+    async fn generate_streaming(
+        &self,
+        model: &str,
+        prompt: &str,
+        should_abort: AbortCheck<'_>,
+    ) -> Result<StreamedGeneration> {
+        let mut attempt = 0;
+        let mut errors = Vec::new();
+        let result = loop {
+            match self.inner.generate_streaming(model, prompt, should_abort).await {
+                Err(e) if attempt < self.policy.max_retries && is_retryable(&e) => {
+                    self.note_error(&mut errors, model, attempt, &e);
+                    self.backoff(attempt, &e).await;
+                    attempt += 1;
+                }
+                result => break result,
+            }
+        };
+        self.report(errors, model, attempt, &result).await;
+        result
+    }
+
+    /// Not retried: a health check should report the provider as it is right now.
+    async fn health(&self) -> Result<()> {
+        self.inner.health().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::TokenUsage;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    /// Fails with `status` for the first `failures` calls, then answers "ok".
+    struct FlakyProvider {
+        failures: u32,
+        status: u16,
+        calls: Arc<AtomicU32>,
+    }
+
+    #[async_trait]
+    impl LlmProvider for FlakyProvider {
+        async fn generate(&self, _model: &str, _prompt: &str) -> Result<Generation> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            if call < self.failures {
+                return Err(EvalError::ApiError { status: self.status, body: "flaky".to_string() });
+            }
+            Ok(Generation {
+                output: "ok".to_string(),
+                latency_ms: 1,
+                token_usage: TokenUsage::default(),
+                finish_reason: None,
+                resolved_model: None,
+                reasoning: None,
+            })
+        }
+    }
+
+    fn flaky(failures: u32, status: u16) -> (FlakyProvider, Arc<AtomicU32>) {
+        let calls = Arc::new(AtomicU32::new(0));
+        (FlakyProvider { failures, status, calls: calls.clone() }, calls)
+    }
+
+    const FAST: RetryPolicy = RetryPolicy { max_retries: 2, initial_backoff: Duration::from_millis(1) };
+
+    #[tokio::test]
+    async fn test_retry_provider_recovers_from_rate_limits() {
+        let (inner, calls) = flaky(2, 429);
+        let provider = RetryProvider::new(inner, FAST);
+
+        let generation = provider.generate("m", "p").await.unwrap();
+        assert_eq!(generation.output, "ok");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_provider_gives_up_and_skips_client_errors() {
+        let (inner, calls) = flaky(5, 503);
+        let result = RetryProvider::new(inner, FAST).generate("m", "p").await;
+        assert!(matches!(result, Err(EvalError::ApiError { status: 503, .. })));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        let (inner, calls) = flaky(1, 400);
+        let result = RetryProvider::new(inner, FAST).generate("m", "p").await;
+        assert!(matches!(result, Err(EvalError::ApiError { status: 400, .. })));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[derive(Default)]
+    struct CollectingSink(std::sync::Mutex<Vec<ProviderErrorEvent>>);
+
+    #[async_trait]
+    impl ProviderErrorSink for CollectingSink {
+        async fn record(&self, event: ProviderErrorEvent) {
+            self.0.lock().unwrap().push(event);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_error_sink_marks_recovered_and_terminal_errors() {
+        let sink = Arc::new(CollectingSink::default());
+
+        let (inner, _) = flaky(1, 429);
+        let provider = RetryProvider::new(inner, FAST).with_error_sink("gemini", sink.clone());
+        provider.generate("m", "p").await.unwrap();
+
+        let (inner, _) = flaky(5, 500);
+        let provider = RetryProvider::new(inner, FAST).with_error_sink("gemini", sink.clone());
+        assert!(provider.generate("m", "p").await.is_err());
+
+        let events = sink.0.lock().unwrap();
+        let summary: Vec<(Option<u16>, &str, u32, bool)> = events
+            .iter()
+            .map(|e| (e.status_code, e.error_class, e.attempt, e.recovered))
+            .collect();
+        assert_eq!(summary, [
+            (Some(429), "rate_limited", 0, true),
+            (Some(500), "server_error", 0, false),
+            (Some(500), "server_error", 1, false),
+            (Some(500), "server_error", 2, false),
+        ]);
+        assert_eq!(events[0].provider, "gemini");
+        assert_eq!(events[0].body, "flaky");
+    }
+
+    #[tokio::test]
+    async fn test_middleware_composes_over_boxed_providers() {
+        let (inner, calls) = flaky(3, 500);
+        let provider = with_middleware(inner.boxed(), &[&FAST, &FAST]);
+
+        // The outer layer retries the inner layer, which itself retries the flaky provider.
+        let generation = provider.generate_streaming("m", "p", &|_| false).await.unwrap();
+        assert_eq!(generation.output, "ok");
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+}
@@ -1,24 +1,184 @@
 // src/providers/mod.rs
 
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use crate::errors::Result;
+use std::time::Duration;
+use crate::errors::{EvalError, Result};
+use crate::providers::response_format::ResponseFormat;
 
 pub mod anthropic;
+pub mod capture;
 pub mod gemini;
+pub mod groq;
+pub mod images;
+pub mod middleware;
 pub mod ollama;
 pub mod openai;
+pub mod openrouter;
+pub mod rate_limit;
+pub mod recording;
+pub mod response_format;
 
 /// Represents token usage for a single API call.
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TokenUsage {
     pub input_tokens: Option<u32>,
     pub output_tokens: Option<u32>,
 }
 
+/// Why a provider stopped generating, normalized across providers.
+///
+/// Stored and serialized as `stop`, `stop_sequence`, `length`, `content_filter`, `refusal` or
+/// `tool_calls`; provider values without an equivalent pass through verbatim as `Other`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum FinishReason {
+    /// The model finished its answer, or hit a stop sequence with providers that do not
+    /// tell the two apart.
+    Stop,
+    /// The model wrote one of the requested stop sequences.
+    StopSequence,
+    /// Generation hit the output token limit, so the output is cut off.
+    Length,
+    /// The provider withheld or cut off the output for safety reasons.
+    ContentFilter,
+    /// The model declined to answer.
+    Refusal,
+    /// The model stopped to call a tool.
+    ToolCalls,
+    Other(String),
+}
+
+impl FinishReason {
+    pub fn as_str(&self) -> &str {
+        match self {
+            FinishReason::Stop => "stop",
+            FinishReason::StopSequence => "stop_sequence",
+            FinishReason::Length => "length",
+            FinishReason::ContentFilter => "content_filter",
+            FinishReason::Refusal => "refusal",
+            FinishReason::ToolCalls => "tool_calls",
+            FinishReason::Other(raw) => raw,
+        }
+    }
+}
+
+impl From<String> for FinishReason {
+    fn from(s: String) -> Self {
+        match s.as_str() {
+            "stop" => FinishReason::Stop,
+            "stop_sequence" => FinishReason::StopSequence,
+            "length" => FinishReason::Length,
+            "content_filter" => FinishReason::ContentFilter,
+            "refusal" => FinishReason::Refusal,
+            "tool_calls" => FinishReason::ToolCalls,
+            _ => FinishReason::Other(s),
+        }
+    }
+}
+
+impl From<FinishReason> for String {
+    fn from(reason: FinishReason) -> Self {
+        match reason {
+            FinishReason::Other(raw) => raw,
+            reason => reason.as_str().to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for FinishReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Per-eval settings a provider sends with every generation request.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct GenerationParams {
+    /// Output format asked of the model; see [`ResponseFormat`].
+    pub response_format: Option<ResponseFormat>,
+    /// Sequences that end the generation when the model writes them.
+    pub stop: Option<Vec<String>>,
+    /// Most tokens the model may generate.
+    pub max_output_tokens: Option<u32>,
+    /// Sampling temperature; `None` keeps the provider's default, 0.7 for OpenAI and Anthropic.
+    pub temperature: Option<f32>,
+    /// Sampling seed, for providers that take one: OpenAI-compatible ones, Gemini and Ollama.
+    pub seed: Option<u64>,
+    /// Images sent along with the prompt, as URLs or base64 data URIs; see [`images::Image`].
+    pub images: Vec<String>,
+    /// Extended thinking before the answer, for Anthropic; other providers ignore it.
+    pub thinking: Option<ThinkingParams>,
+}
+
+/// Extended thinking: the model reasons in up to `budget_tokens` tokens before it answers.
+/// The budget counts towards the output token limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ThinkingParams {
+    pub budget_tokens: u32,
+}
+
+/// The outcome of a generation.
+#[derive(Debug, Clone)]
+pub struct Generation {
+    pub output: String,
+    pub latency_ms: u64,
+    pub token_usage: TokenUsage,
+    /// Why the provider stopped generating; `None` when it did not say.
+    pub finish_reason: Option<FinishReason>,
+    /// The model that answered, when the provider reports it, e.g. the model OpenRouter
+    /// routed the request to.
+    pub resolved_model: Option<String>,
+    /// Reasoning the provider returned apart from `output`, e.g. Anthropic thinking blocks.
+    /// Reasoning inside the output, in `<think>` tags, is split off by the runner.
+    pub reasoning: Option<String>,
+}
+
+/// The outcome of a streamed generation, which the caller may have cut short.
+#[derive(Debug, Clone)]
+pub struct StreamedGeneration {
+    pub output: String,
+    pub latency_ms: u64,
+    pub token_usage: TokenUsage,
+    /// `None` when the provider did not say, or the stream was aborted.
+    pub finish_reason: Option<FinishReason>,
+    pub resolved_model: Option<String>,
+    pub reasoning: Option<String>,
+    /// Number of tokens (stream chunks) received before the stream was aborted, if it was.
+    pub aborted_after_tokens: Option<u32>,
+}
+
+impl From<Generation> for StreamedGeneration {
+    fn from(generation: Generation) -> Self {
+        StreamedGeneration {
+            output: generation.output,
+            latency_ms: generation.latency_ms,
+            token_usage: generation.token_usage,
+            finish_reason: generation.finish_reason,
+            resolved_model: generation.resolved_model,
+            reasoning: generation.reasoning,
+            aborted_after_tokens: None,
+        }
+    }
+}
+
+/// Sampling temperature of OpenAI and Anthropic requests whose params set none.
+pub const DEFAULT_TEMPERATURE: f32 = 0.7;
+
+/// How long a provider has to answer a health check.
+pub const HEALTH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Callback invoked with the accumulated output after every streamed chunk.
+/// Returning `true` aborts the stream.
+pub type AbortCheck<'a> = &'a (dyn Fn(&str) -> bool + Send + Sync);
+
 /// A common trait for Large Language Model (LLM) providers.
 /// This allows for a unified interface to different model backends like Gemini, Ollama, OpenAI, Anthropic, etc.
-/// 
-/// Note: We're not using async_trait here, so implementers must handle async directly.
+///
+/// The trait is object safe, so providers can be held as `Box<dyn LlmProvider>` and wrapped
+/// by middleware such as [`middleware::RetryProvider`].
+#[async_trait]
 pub trait LlmProvider: Send + Sync {
     /// Generates a response from the LLM based on a given prompt.
     ///
@@ -27,6 +187,105 @@ pub trait LlmProvider: Send + Sync {
     /// * `prompt` - The input prompt to send to the model.
     ///
     /// # Returns
-    /// A `Result` containing a tuple of the generated `String`, latency in milliseconds (`u64`), and `TokenUsage`.
-    fn generate(&self, model: &str, prompt: &str) -> impl std::future::Future<Output = Result<(String, u64, TokenUsage)>> + Send;
+    /// A `Result` containing the generated text, latency in milliseconds, `TokenUsage` and finish reason.
+    async fn generate(&self, model: &str, prompt: &str) -> Result<Generation>;
+
+    /// Streams a response, calling `should_abort` with the accumulated output after each chunk
+    /// and dropping the connection as soon as it returns `true`.
+    ///
+    /// Providers without streaming support keep this default, which simply calls `generate`
+    /// and never aborts.
+    async fn generate_streaming(
+        &self,
+        model: &str,
+        prompt: &str,
+        should_abort: AbortCheck<'_>,
+    ) -> Result<StreamedGeneration> {
+        let _ = should_abort;
+        Ok(self.generate(model, prompt).await?.into())
+    }
+
+    /// Checks that the provider is reachable and accepts its credentials, without generating
+    /// anything. Providers without a cheap check keep this default, which assumes they are.
+    async fn health(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Boxes a concrete provider for use where a `Box<dyn LlmProvider>` is expected.
+    fn boxed(self) -> Box<dyn LlmProvider>
+    where
+        Self: Sized + 'static,
+    {
+        Box::new(self)
+    }
+}
+
+/// Lets boxed providers be used anywhere a generic `P: LlmProvider` is accepted,
+/// e.g. `RetryProvider<Box<dyn LlmProvider>>`.
+#[async_trait]
+impl<P: LlmProvider + ?Sized> LlmProvider for Box<P> {
+    async fn generate(&self, model: &str, prompt: &str) -> Result<Generation> {
+        (**self).generate(model, prompt).await
+    }
+
+    async fn generate_streaming(
+        &self,
+        model: &str,
+        prompt: &str,
+        should_abort: AbortCheck<'_>,
+    ) -> Result<StreamedGeneration> {
+        (**self).generate_streaming(model, prompt, should_abort).await
+    }
+
+    async fn health(&self) -> Result<()> {
+        (**self).health().await
+    }
+}
+
+/// Sends a health check request, failing on a non-2xx status or after `HEALTH_TIMEOUT`.
+pub(crate) async fn check_health(request: reqwest::RequestBuilder) -> Result<()> {
+    let response = request.timeout(HEALTH_TIMEOUT).send().await?;
+    let status = response.status();
+    if status.is_success() {
+        Ok(())
+    } else {
+        Err(EvalError::ApiError { status: status.as_u16(), body: response.text().await.unwrap_or_default() })
+    }
+}
+
+/// Splits every complete line off the front of `buffer`, leaving a trailing partial line in place.
+/// Works on raw bytes so multi-byte characters split across network chunks are not corrupted.
+pub(crate) fn drain_lines(buffer: &mut Vec<u8>) -> Vec<String> {
+    let mut lines = Vec::new();
+    while let Some(pos) = buffer.iter().position(|&b| b == b'\n') {
+        let line: Vec<u8> = buffer.drain(..=pos).collect();
+        let line = String::from_utf8_lossy(&line).trim().to_string();
+        if !line.is_empty() {
+            lines.push(line);
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_finish_reason_round_trips_and_keeps_unknown_values() {
+        for reason in [
+            FinishReason::Stop,
+            FinishReason::StopSequence,
+            FinishReason::Length,
+            FinishReason::ContentFilter,
+            FinishReason::Refusal,
+            FinishReason::ToolCalls,
+        ] {
+            assert_eq!(FinishReason::from(reason.as_str().to_string()), reason);
+        }
+        let other: FinishReason = serde_json::from_str("\"pause_turn\"").unwrap();
+        assert_eq!(other, FinishReason::Other("pause_turn".to_string()));
+        assert_eq!(serde_json::to_string(&other).unwrap(), "\"pause_turn\"");
+        assert_eq!(serde_json::to_string(&FinishReason::ContentFilter).unwrap(), "\"content_filter\"");
+    }
 }
@@ -1,17 +1,26 @@
 // src/providers/ollama.rs
 
+use async_trait::async_trait;
+use reqwest::header::HeaderMap;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::time::Instant;
 
 use crate::config::OllamaConfig;
 use crate::errors::{EvalError, Result};
-use crate::providers::{LlmProvider, TokenUsage};
+use crate::providers::images::{self, Image};
+use crate::providers::response_format::ResponseFormat;
+use crate::providers::{check_health, drain_lines, recording, AbortCheck, FinishReason, Generation, GenerationParams, LlmProvider, StreamedGeneration, TokenUsage};
 
 /// A provider for interacting with local Ollama models.
 pub struct OllamaProvider {
     client: Client,
     config: OllamaConfig,
+    /// Sent with every request as `format`, `options` and `images`.
+    params: GenerationParams,
+    /// Headers from `<PREFIX>_EXTRA_HEADERS`, sent with every request.
+    extra_headers: HeaderMap,
 }
 
 #[derive(Serialize)]
@@ -19,42 +28,188 @@ struct OllamaRequest<'a> {
     model: &'a str,
     prompt: &'a str,
     stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<Options<'a>>,
+    /// Base64 images, without the `data:` URI header.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    images: Vec<&'a str>,
+}
+
+/// Model options of a request; only those an eval sets are sent.
+#[derive(Serialize)]
+struct Options<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop: Option<&'a [String]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_predict: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
 }
 
 #[derive(Deserialize)]
 struct OllamaResponse {
     response: String,
+    done_reason: Option<String>,
+    /// Reasoning of thinking models, when Ollama separates it from the response.
+    thinking: Option<String>,
+    prompt_eval_count: Option<u32>,
+    eval_count: Option<u32>,
+}
+
+/// One line of Ollama's newline-delimited streaming response.
+#[derive(Deserialize)]
+struct OllamaStreamChunk {
+    #[serde(default)]
+    response: String,
+    #[serde(default)]
+    done: bool,
+    thinking: Option<String>,
+    prompt_eval_count: Option<u32>,
+    eval_count: Option<u32>,
+    done_reason: Option<String>,
 }
 
 impl OllamaProvider {
     /// Creates a new `OllamaProvider`.
     pub fn new(client: Client, config: OllamaConfig) -> Self {
-        Self { client, config }
+        Self { client, config, params: GenerationParams::default(), extra_headers: HeaderMap::new() }
+    }
+
+    /// Sends `params` with every request. A response format asks for JSON mode, which knows
+    /// no schema, so a schema is also given in the prompt.
+    pub fn with_params(mut self, params: GenerationParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    /// Sends `headers` with every request, e.g. the ones an API gateway requires.
+    pub fn with_extra_headers(mut self, headers: HeaderMap) -> Self {
+        self.extra_headers = headers;
+        self
+    }
+
+    /// `prompt`, followed by the instruction to match the schema when there is one.
+    fn prompt<'a>(&self, prompt: &'a str) -> Cow<'a, str> {
+        match &self.params.response_format {
+            Some(format @ ResponseFormat::JsonSchema { .. }) => Cow::Owned(format.instruct(prompt)),
+            _ => Cow::Borrowed(prompt),
+        }
+    }
+
+    fn format(&self) -> Option<&'static str> {
+        self.params.response_format.as_ref().map(|_| "json")
+    }
+
+    fn options(&self) -> Option<Options<'_>> {
+        let GenerationParams { stop, max_output_tokens, temperature, seed, .. } = &self.params;
+        (stop.is_some() || max_output_tokens.is_some() || temperature.is_some() || seed.is_some()).then_some(Options {
+            stop: stop.as_deref(),
+            num_predict: *max_output_tokens,
+            temperature: *temperature,
+            seed: *seed,
+        })
+    }
+
+    /// The base64 data of the images. Ollama takes inline images only, which
+    /// `images::check_support` ensures.
+    fn images(&self) -> Vec<&str> {
+        images::parse_all(&self.params.images)
+            .filter_map(|image| match image {
+                Image::Inline { data, .. } => Some(data),
+                Image::Url(_) => None,
+            })
+            .collect()
     }
 }
 
+/// Maps an Ollama `done_reason`; `stop` and `length` match the normalized names, while
+/// `load` and `unload` (model management replies) pass through.
+fn finish_reason(raw: &str) -> FinishReason {
+    FinishReason::from(raw.to_string())
+}
+
+#[async_trait]
 impl LlmProvider for OllamaProvider {
     /// Calls the Ollama API with a given prompt and returns the model's response text and latency.
-    async fn generate(&self, model: &str, prompt: &str) -> Result<(String, u64, TokenUsage)> {
+    async fn generate(&self, model: &str, prompt: &str) -> Result<Generation> {
         let url = format!("{}/api/generate", self.config.api_base.trim_end_matches('/'));
 
-        println!("📡 Calling Ollama: {} with model: {}", url, model);
+        tracing::info!("📡 Calling Ollama: {} with model: {}", url, model);
 
+        let prompt = self.prompt(prompt);
         let body = OllamaRequest {
             model,
-            prompt,
+            prompt: &prompt,
             stream: false,
+            format: self.format(),
+            options: self.options(),
+            images: self.images(),
         };
 
         let start = Instant::now();
 
-        let resp = self.client.post(&url).json(&body).send().await?;
+        let request = self.client.post(&url).headers(self.extra_headers.clone()).json(&body);
+        let (status, text) = recording::send(&self.client, request, "ollama", model, &prompt).await?;
 
-        let status = resp.status();
         let latency_ms = start.elapsed().as_millis() as u64;
 
-        println!("📥 Ollama response status: {} ({}ms)", status, latency_ms);
+        tracing::info!("📥 Ollama response status: {} ({}ms)", status, latency_ms);
+
+        if !status.is_success() {
+            return Err(EvalError::ApiError {
+                status: status.as_u16(),
+                body: text,
+            });
+        }
+
+        let ollama_resp: OllamaResponse = serde_json::from_str(&text)?;
+        if ollama_resp.response.is_empty() {
+            return Err(EvalError::EmptyResponse);
+        }
+
+        Ok(Generation {
+            finish_reason: ollama_resp.done_reason.as_deref().map(finish_reason),
+            output: ollama_resp.response,
+            latency_ms,
+            token_usage: TokenUsage {
+                input_tokens: ollama_resp.prompt_eval_count,
+                output_tokens: ollama_resp.eval_count,
+            },
+            resolved_model: None,
+            reasoning: ollama_resp.thinking.filter(|thinking| !thinking.is_empty()),
+        })
+    }
+
+    /// Streams from the Ollama API, aborting as soon as `should_abort` returns `true`.
+    async fn generate_streaming(
+        &self,
+        model: &str,
+        prompt: &str,
+        should_abort: AbortCheck<'_>,
+    ) -> Result<StreamedGeneration> {
+        let url = format!("{}/api/generate", self.config.api_base.trim_end_matches('/'));
 
+        tracing::info!("📡 Streaming from Ollama: {} with model: {}", url, model);
+
+        let prompt = self.prompt(prompt);
+        let body = OllamaRequest {
+            model,
+            prompt: &prompt,
+            stream: true,
+            format: self.format(),
+            options: self.options(),
+            images: self.images(),
+        };
+
+        let start = Instant::now();
+
+        let mut resp = self.client.post(&url).headers(self.extra_headers.clone()).json(&body).send().await?;
+
+        let status = resp.status();
         if !status.is_success() {
             let error_body = resp
                 .text()
@@ -66,11 +221,129 @@ impl LlmProvider for OllamaProvider {
             });
         }
 
-        let ollama_resp: OllamaResponse = resp.json().await?;
-        if ollama_resp.response.is_empty() {
+        let mut buffer = Vec::new();
+        let mut output = String::new();
+        let mut tokens = 0u32;
+        let mut token_usage = TokenUsage::default();
+        let mut stop_reason = None;
+        let mut thinking = String::new();
+
+        while let Some(bytes) = resp.chunk().await? {
+            buffer.extend_from_slice(&bytes);
+            for line in drain_lines(&mut buffer) {
+                let chunk: OllamaStreamChunk = serde_json::from_str(&line)?;
+                if let Some(delta) = &chunk.thinking {
+                    thinking.push_str(delta);
+                }
+                if !chunk.response.is_empty() {
+                    output.push_str(&chunk.response);
+                    tokens += 1;
+                    if should_abort(&output) {
+                        let latency_ms = start.elapsed().as_millis() as u64;
+                        tracing::info!("✂️  Aborted Ollama stream after {} tokens ({}ms)", tokens, latency_ms);
+                        return Ok(StreamedGeneration {
+                            output,
+                            latency_ms,
+                            token_usage: TokenUsage { input_tokens: None, output_tokens: Some(tokens) },
+                            finish_reason: None,
+                            resolved_model: None,
+                            reasoning: Some(thinking).filter(|thinking| !thinking.is_empty()),
+                            aborted_after_tokens: Some(tokens),
+                        });
+                    }
+                }
+                if chunk.done {
+                    token_usage = TokenUsage {
+                        input_tokens: chunk.prompt_eval_count,
+                        output_tokens: chunk.eval_count,
+                    };
+                    stop_reason = chunk.done_reason.as_deref().map(finish_reason);
+                }
+            }
+        }
+
+        let latency_ms = start.elapsed().as_millis() as u64;
+        tracing::info!("📥 Ollama stream finished ({}ms)", latency_ms);
+
+        if output.is_empty() {
             return Err(EvalError::EmptyResponse);
         }
 
-        Ok((ollama_resp.response, latency_ms, TokenUsage::default()))
+        Ok(StreamedGeneration {
+            output,
+            latency_ms,
+            token_usage,
+            finish_reason: stop_reason,
+            resolved_model: None,
+            reasoning: Some(thinking).filter(|thinking| !thinking.is_empty()),
+            aborted_after_tokens: None,
+        })
+    }
+
+    /// Lists the locally installed models.
+    async fn health(&self) -> Result<()> {
+        let url = format!("{}/api/tags", self.config.api_base.trim_end_matches('/'));
+        check_health(self.client.get(&url).headers(self.extra_headers.clone())).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_images_are_sent_as_base64_without_the_data_uri_header() {
+        let params = GenerationParams { images: vec!["data:image/png;base64,iVBORw0KGgo=".to_string()], ..Default::default() };
+        let provider = OllamaProvider::new(Client::new(), OllamaConfig { api_base: String::new(), models: vec![] }).with_params(params);
+        let request = OllamaRequest {
+            model: "llava",
+            prompt: "What animal is this?",
+            stream: false,
+            format: None,
+            options: None,
+            images: provider.images(),
+        };
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            serde_json::json!({ "model": "llava", "prompt": "What animal is this?", "stream": false, "images": ["iVBORw0KGgo="] })
+        );
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_finish_reason_from_raw_payloads() {
+        let truncated: OllamaResponse = serde_json::from_str(
+            r#"{"model": "llama3", "response": "The answer is", "done": true, "done_reason": "length", "eval_count": 128}"#,
+        )
+        .unwrap();
+        assert_eq!(truncated.done_reason.as_deref().map(finish_reason), Some(FinishReason::Length));
+
+        let last_chunk: OllamaStreamChunk = serde_json::from_str(
+            r#"{"model": "llama3", "response": "", "done": true, "done_reason": "stop", "prompt_eval_count": 21, "eval_count": 2}"#,
+        )
+        .unwrap();
+        assert_eq!(last_chunk.done_reason.as_deref().map(finish_reason), Some(FinishReason::Stop));
+
+        assert_eq!(finish_reason("unload"), FinishReason::Other("unload".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_generate_parses_output_and_usage() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "model": "llama3", "response": "4", "done": true, "done_reason": "stop",
+                "prompt_eval_count": 26, "eval_count": 2
+            })))
+            .mount(&server)
+            .await;
+
+        let provider = OllamaProvider::new(Client::new(), OllamaConfig { api_base: server.uri(), models: vec!["llama3".to_string()] });
+        let generation = provider.generate("llama3", "2+2?").await.unwrap();
+        assert_eq!(generation.output, "4");
+        assert_eq!(generation.token_usage.input_tokens, Some(26));
+        assert_eq!(generation.token_usage.output_tokens, Some(2));
+    }
+}
@@ -1,17 +1,33 @@
 // src/providers/openai.rs
 
-use reqwest::Client;
+use async_trait::async_trait;
+use reqwest::header::HeaderMap;
+use reqwest::{Client, RequestBuilder};
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use std::time::Instant;
 
 use crate::config::OpenAIConfig;
 use crate::errors::{EvalError, Result};
-use crate::providers::{LlmProvider, TokenUsage};
+use crate::providers::response_format::ResponseFormat;
+use crate::providers::{check_health, drain_lines, recording, AbortCheck, FinishReason, Generation, GenerationParams, LlmProvider, StreamedGeneration, TokenUsage, DEFAULT_TEMPERATURE};
 
-/// A provider for interacting with OpenAI models.
+/// A provider for interacting with OpenAI models, or with any server speaking the OpenAI
+/// chat-completions protocol.
 pub struct OpenAIProvider {
     client: Client,
     config: OpenAIConfig,
+    /// Provider name used in logs and recordings: `openai`, or the name of a compatible server.
+    name: String,
+    /// Sees the headers of every response, e.g. to watch rate limits.
+    on_headers: Option<fn(&HeaderMap)>,
+    /// Sent with every request, after the bearer token.
+    extra_headers: Vec<(&'static str, String)>,
+    /// Headers from `<PREFIX>_EXTRA_HEADERS`, sent last so they can override any of the above.
+    configured_headers: HeaderMap,
+    /// Sent with every request as `response_format`, `stop`, `max_tokens`, `temperature` and
+    /// `seed`, and images as content parts of the message.
+    params: GenerationParams,
 }
 
 #[derive(Serialize)]
@@ -19,28 +35,54 @@ struct OpenAIRequest<'a> {
     model: &'a str,
     messages: Vec<Message<'a>>,
     temperature: f32,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<StreamOptions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop: Option<&'a [String]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct StreamOptions {
+    include_usage: bool,
 }
 
 #[derive(Serialize)]
 struct Message<'a> {
     role: &'a str,
-    content: &'a str,
+    /// The prompt as a string, or as content parts when images come with it.
+    content: serde_json::Value,
 }
 
 #[derive(Deserialize)]
 struct OpenAIResponse {
     choices: Vec<Choice>,
     usage: Option<ApiUsage>,
+    /// The model that answered, which a router may have picked.
+    model: Option<String>,
 }
 
 #[derive(Deserialize)]
 struct Choice {
     message: MessageContent,
+    finish_reason: Option<String>,
 }
 
 #[derive(Deserialize)]
 struct MessageContent {
-    content: String,
+    /// `null` when the model refused.
+    content: Option<String>,
+    /// Why the model declined to answer, which structured outputs report instead of `content`.
+    refusal: Option<String>,
+    /// Reasoning that DeepSeek-R1 and compatible servers return apart from the answer.
+    reasoning_content: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -49,57 +91,155 @@ struct ApiUsage {
     completion_tokens: u32,
 }
 
+/// One `data:` event of a streamed chat completion.
+#[derive(Deserialize)]
+struct OpenAIStreamChunk {
+    #[serde(default)]
+    choices: Vec<StreamChoice>,
+    usage: Option<ApiUsage>,
+    model: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct StreamChoice {
+    delta: StreamDelta,
+    finish_reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct StreamDelta {
+    content: Option<String>,
+    refusal: Option<String>,
+    reasoning_content: Option<String>,
+}
+
 impl OpenAIProvider {
     /// Creates a new `OpenAIProvider`.
     pub fn new(client: Client, config: OpenAIConfig) -> Self {
-        Self { client, config }
+        Self::compatible(client, "openai", config)
+    }
+
+    /// Creates a provider for the OpenAI-compatible server `name`, e.g. a vLLM instance.
+    pub fn compatible(client: Client, name: &str, config: OpenAIConfig) -> Self {
+        Self { client, config, name: name.to_string(), on_headers: None, extra_headers: Vec::new(), configured_headers: HeaderMap::new(), params: GenerationParams::default() }
+    }
+
+    /// Sends `params` with every request. A response format asks for JSON, or JSON matching a
+    /// schema with structured outputs.
+    pub fn with_params(mut self, params: GenerationParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    /// Passes the headers of every response to `hook`.
+    pub(crate) fn with_header_hook(mut self, hook: fn(&HeaderMap)) -> Self {
+        self.on_headers = Some(hook);
+        self
+    }
+
+    /// Sends `name: value` with every request.
+    pub(crate) fn with_header(mut self, name: &'static str, value: String) -> Self {
+        self.extra_headers.push((name, value));
+        self
+    }
+
+    /// Sends `headers` with every request, e.g. the ones an API gateway requires.
+    pub fn with_extra_headers(mut self, headers: HeaderMap) -> Self {
+        self.configured_headers = headers;
+        self
+    }
+
+    fn inspect(&self, headers: &HeaderMap) {
+        if let Some(hook) = self.on_headers {
+            hook(headers);
+        }
+    }
+
+    /// `request` with the bearer token, unless the server needs no key, and the extra headers.
+    fn authorized(&self, request: RequestBuilder) -> RequestBuilder {
+        let request = if self.config.api_key.is_empty() {
+            request
+        } else {
+            request.header("Authorization", format!("Bearer {}", self.config.api_key))
+        };
+        let request = self.extra_headers.iter().fold(request, |request, (name, value)| request.header(*name, value));
+        request.headers(self.configured_headers.clone())
     }
 }
 
+/// The content of the user message: `prompt`, followed by an `image_url` part per image when
+/// there are any. OpenAI takes URLs and data URIs alike.
+fn content(prompt: &str, images: &[String]) -> serde_json::Value {
+    if images.is_empty() {
+        return json!(prompt);
+    }
+    let mut parts = vec![json!({ "type": "text", "text": prompt })];
+    parts.extend(images.iter().map(|image| json!({ "type": "image_url", "image_url": { "url": image } })));
+    json!(parts)
+}
+
+/// The output and finish reason of `choice`. A refusal is recorded as the output, so it can be
+/// read back from the history, with finish reason `refusal`.
+fn answer(choice: Choice) -> (String, Option<FinishReason>) {
+    match choice.message.refusal.filter(|refusal| !refusal.is_empty()) {
+        Some(refusal) => (refusal, Some(FinishReason::Refusal)),
+        None => (choice.message.content.unwrap_or_default(), choice.finish_reason.as_deref().map(finish_reason)),
+    }
+}
+
+/// Maps an OpenAI `finish_reason`, whose values the normalized names were taken from.
+fn finish_reason(raw: &str) -> FinishReason {
+    match raw {
+        // Deprecated predecessor of `tool_calls`
+        "function_call" => FinishReason::ToolCalls,
+        _ => FinishReason::from(raw.to_string()),
+    }
+}
+
+#[async_trait]
 impl LlmProvider for OpenAIProvider {
     /// Calls the OpenAI API with a given prompt and returns the model's response text and latency.
-    async fn generate(&self, model: &str, prompt: &str) -> Result<(String, u64, TokenUsage)> {
+    async fn generate(&self, model: &str, prompt: &str) -> Result<Generation> {
         let url = format!("{}/chat/completions", self.config.api_base.trim_end_matches('/'));
 
-        println!("📡 Calling OpenAI: {} with model: {}", url, model);
+        tracing::info!("📡 Calling {}: {} with model: {}", self.name, url, model);
 
         let body = OpenAIRequest {
             model,
             messages: vec![Message {
                 role: "user",
-                content: prompt,
+                content: content(prompt, &self.params.images),
             }],
-            temperature: 0.7,
+            temperature: self.params.temperature.unwrap_or(DEFAULT_TEMPERATURE),
+            stream: false,
+            stream_options: None,
+            response_format: self.params.response_format.as_ref().map(ResponseFormat::openai),
+            stop: self.params.stop.as_deref(),
+            max_tokens: self.params.max_output_tokens,
+            seed: self.params.seed,
         };
 
         let start = Instant::now();
 
-        let resp = self
-            .client
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", self.config.api_key))
+        let request = self
+            .authorized(self.client.post(&url))
             .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await?;
+            .json(&body);
+        let (status, headers, text) = recording::send_with_headers(&self.client, request, &self.name, model, prompt).await?;
+        self.inspect(&headers);
 
-        let status = resp.status();
         let latency_ms = start.elapsed().as_millis() as u64;
 
-        println!("📥 OpenAI response status: {} ({}ms)", status, latency_ms);
+        tracing::info!("📥 {} response status: {} ({}ms)", self.name, status, latency_ms);
 
         if !status.is_success() {
-            let error_body = resp
-                .text()
-                .await
-                .unwrap_or_else(|_| "Could not read error body".to_string());
             return Err(EvalError::ApiError {
                 status: status.as_u16(),
-                body: error_body,
+                body: text,
             });
         }
 
-        let openai_resp: OpenAIResponse = resp.json().await?;
+        let openai_resp: OpenAIResponse = serde_json::from_str(&text)?;
 
         let token_usage = openai_resp.usage.map_or_else(
             TokenUsage::default,
@@ -109,16 +249,298 @@ impl LlmProvider for OpenAIProvider {
             },
         );
         
-        let output = openai_resp
+        let choice = openai_resp
             .choices
-            .get(0)
-            .map(|c| c.message.content.clone())
+            .into_iter()
+            .next()
             .ok_or_else(|| EvalError::UnexpectedResponse("No choices in response".to_string()))?;
+        let reasoning = choice.message.reasoning_content.clone().filter(|reasoning| !reasoning.is_empty());
+        let (output, reason) = answer(choice);
 
         if output.is_empty() {
             return Err(EvalError::EmptyResponse);
         }
 
-        Ok((output, latency_ms, token_usage))
+        Ok(Generation {
+            output,
+            latency_ms,
+            token_usage,
+            finish_reason: reason,
+            resolved_model: openai_resp.model,
+            reasoning,
+        })
+    }
+
+    /// Streams a chat completion over server-sent events, aborting as soon as `should_abort` returns `true`.
+    async fn generate_streaming(
+        &self,
+        model: &str,
+        prompt: &str,
+        should_abort: AbortCheck<'_>,
+    ) -> Result<StreamedGeneration> {
+        let url = format!("{}/chat/completions", self.config.api_base.trim_end_matches('/'));
+
+        tracing::info!("📡 Streaming from {}: {} with model: {}", self.name, url, model);
+
+        let body = OpenAIRequest {
+            model,
+            messages: vec![Message {
+                role: "user",
+                content: content(prompt, &self.params.images),
+            }],
+            temperature: self.params.temperature.unwrap_or(DEFAULT_TEMPERATURE),
+            stream: true,
+            stream_options: Some(StreamOptions { include_usage: true }),
+            response_format: self.params.response_format.as_ref().map(ResponseFormat::openai),
+            stop: self.params.stop.as_deref(),
+            max_tokens: self.params.max_output_tokens,
+            seed: self.params.seed,
+        };
+
+        let start = Instant::now();
+
+        let mut resp = self
+            .authorized(self.client.post(&url))
+            .header("Content-Type", "application/json")
+            .json(&body)
+            .send()
+            .await?;
+
+        self.inspect(resp.headers());
+        let status = resp.status();
+        if !status.is_success() {
+            let error_body = resp
+                .text()
+                .await
+                .unwrap_or_else(|_| "Could not read error body".to_string());
+            return Err(EvalError::ApiError {
+                status: status.as_u16(),
+                body: error_body,
+            });
+        }
+
+        let mut buffer = Vec::new();
+        let mut output = String::new();
+        let mut tokens = 0u32;
+        let mut token_usage = TokenUsage::default();
+        let mut stop_reason = None;
+        let mut resolved_model = None;
+        let mut reasoning = String::new();
+        let mut refusal = String::new();
+
+        'stream: while let Some(bytes) = resp.chunk().await? {
+            buffer.extend_from_slice(&bytes);
+            for line in drain_lines(&mut buffer) {
+                let Some(data) = line.strip_prefix("data:").map(str::trim) else {
+                    continue;
+                };
+                if data == "[DONE]" {
+                    break 'stream;
+                }
+                let chunk: OpenAIStreamChunk = serde_json::from_str(data)?;
+                if chunk.model.is_some() {
+                    resolved_model = chunk.model;
+                }
+                if let Some(u) = chunk.usage {
+                    token_usage = TokenUsage {
+                        input_tokens: Some(u.prompt_tokens),
+                        output_tokens: Some(u.completion_tokens),
+                    };
+                }
+                let Some(choice) = chunk.choices.into_iter().next() else {
+                    continue;
+                };
+                if let Some(reason) = choice.finish_reason.as_deref() {
+                    stop_reason = Some(finish_reason(reason));
+                }
+                if let Some(delta) = &choice.delta.reasoning_content {
+                    reasoning.push_str(delta);
+                }
+                if let Some(delta) = &choice.delta.refusal {
+                    refusal.push_str(delta);
+                }
+                if let Some(delta) = choice.delta.content.filter(|d| !d.is_empty()) {
+                    output.push_str(&delta);
+                    tokens += 1;
+                    if should_abort(&output) {
+                        let latency_ms = start.elapsed().as_millis() as u64;
+                        tracing::info!("✂️  Aborted {} stream after {} tokens ({}ms)", self.name, tokens, latency_ms);
+                        return Ok(StreamedGeneration {
+                            output,
+                            latency_ms,
+                            token_usage: TokenUsage { input_tokens: None, output_tokens: Some(tokens) },
+                            finish_reason: None,
+                            resolved_model,
+                            reasoning: Some(reasoning).filter(|reasoning| !reasoning.is_empty()),
+                            aborted_after_tokens: Some(tokens),
+                        });
+                    }
+                }
+            }
+        }
+
+        let latency_ms = start.elapsed().as_millis() as u64;
+        tracing::info!("📥 {} stream finished ({}ms)", self.name, latency_ms);
+
+        if !refusal.is_empty() {
+            output = refusal;
+            stop_reason = Some(FinishReason::Refusal);
+        }
+        if output.is_empty() {
+            return Err(EvalError::EmptyResponse);
+        }
+
+        Ok(StreamedGeneration {
+            output,
+            latency_ms,
+            token_usage,
+            finish_reason: stop_reason,
+            resolved_model,
+            reasoning: Some(reasoning).filter(|reasoning| !reasoning.is_empty()),
+            aborted_after_tokens: None,
+        })
+    }
+
+    /// Lists the models, which needs a valid key but costs nothing.
+    async fn health(&self) -> Result<()> {
+        let url = format!("{}/models", self.config.api_base.trim_end_matches('/'));
+        check_health(self.authorized(self.client.get(&url))).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_finish_reason_from_raw_payloads() {
+        let truncated: OpenAIResponse = serde_json::from_str(
+            r#"{"choices": [{"index": 0, "message": {"role": "assistant", "content": "The answer is"}, "finish_reason": "length"}],
+                "usage": {"prompt_tokens": 19, "completion_tokens": 16, "total_tokens": 35}}"#,
+        )
+        .unwrap();
+        let choice = truncated.choices.into_iter().next().unwrap();
+        assert_eq!(answer(choice), ("The answer is".to_string(), Some(FinishReason::Length)));
+
+        let chunk: OpenAIStreamChunk = serde_json::from_str(
+            r#"{"choices": [{"index": 0, "delta": {}, "finish_reason": "content_filter"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(chunk.choices[0].finish_reason.as_deref().map(finish_reason), Some(FinishReason::ContentFilter));
+
+        assert_eq!(finish_reason("stop"), FinishReason::Stop);
+        assert_eq!(finish_reason("function_call"), FinishReason::ToolCalls);
+        assert_eq!(finish_reason("tool_calls"), FinishReason::ToolCalls);
+        assert_eq!(finish_reason("new_reason"), FinishReason::Other("new_reason".to_string()));
+    }
+
+    #[test]
+    fn test_images_are_sent_as_image_url_parts() {
+        assert_eq!(content("What is 2+2?", &[]), json!("What is 2+2?"));
+        let images = ["https://example.com/cat.png".to_string(), "data:image/png;base64,iVBORw0KGgo=".to_string()];
+        assert_eq!(
+            content("What animal is this?", &images),
+            json!([
+                { "type": "text", "text": "What animal is this?" },
+                { "type": "image_url", "image_url": { "url": "https://example.com/cat.png" } },
+                { "type": "image_url", "image_url": { "url": "data:image/png;base64,iVBORw0KGgo=" } }
+            ])
+        );
+    }
+
+    #[test]
+    fn test_refusals_are_the_output_with_finish_reason_refusal() {
+        let refused: OpenAIResponse = serde_json::from_str(
+            r#"{"choices": [{"index": 0, "message": {"role": "assistant", "content": null,
+                    "refusal": "I'm sorry, I can't help with that."}, "finish_reason": "stop"}],
+                "usage": {"prompt_tokens": 14, "completion_tokens": 10, "total_tokens": 24}}"#,
+        )
+        .unwrap();
+        let choice = refused.choices.into_iter().next().unwrap();
+        assert_eq!(answer(choice), ("I'm sorry, I can't help with that.".to_string(), Some(FinishReason::Refusal)));
+
+        let chunk: OpenAIStreamChunk = serde_json::from_str(
+            r#"{"choices": [{"index": 0, "delta": {"refusal": "I'm sorry"}, "finish_reason": null}]}"#,
+        )
+        .unwrap();
+        assert_eq!(chunk.choices[0].delta.refusal.as_deref(), Some("I'm sorry"));
+    }
+
+    #[test]
+    fn test_deepseek_reasoning_content_is_read_apart_from_the_answer() {
+        let response: OpenAIResponse = serde_json::from_str(
+            r#"{"id": "a1b2", "object": "chat.completion", "model": "deepseek-reasoner",
+                "choices": [{"index": 0, "message": {"role": "assistant", "content": "4",
+                    "reasoning_content": "The user asks for 2+2, which is 4."}, "finish_reason": "stop"}],
+                "usage": {"prompt_tokens": 12, "completion_tokens": 20, "total_tokens": 32}}"#,
+        )
+        .unwrap();
+        assert_eq!(response.choices[0].message.content.as_deref(), Some("4"));
+        assert_eq!(response.choices[0].message.reasoning_content.as_deref(), Some("The user asks for 2+2, which is 4."));
+
+        let chunk: OpenAIStreamChunk = serde_json::from_str(
+            r#"{"choices": [{"index": 0, "delta": {"role": "assistant", "content": null, "reasoning_content": "The user"}, "finish_reason": null}]}"#,
+        )
+        .unwrap();
+        assert_eq!(chunk.choices[0].delta.reasoning_content.as_deref(), Some("The user"));
+        assert_eq!(chunk.choices[0].delta.content, None);
+    }
+
+    #[tokio::test]
+    async fn test_compatible_servers_are_called_at_their_base_url_without_a_key() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{ "message": { "content": "4" }, "finish_reason": "stop" }],
+                "usage": { "prompt_tokens": 7, "completion_tokens": 1 }
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET")).and(path("/v1/models")).respond_with(ResponseTemplate::new(200)).mount(&server).await;
+
+        let config = OpenAIConfig { api_base: format!("{}/v1/", server.uri()), api_key: String::new(), models: vec![] };
+        let provider = OpenAIProvider::compatible(Client::new(), "vllm", config.clone());
+        let generation = provider.generate("qwen2.5-7b", "What is 2+2?").await.unwrap();
+        assert_eq!(generation.output, "4");
+        assert_eq!(generation.token_usage.output_tokens, Some(1));
+        provider.health().await.unwrap();
+
+        let keyed = OpenAIProvider::compatible(Client::new(), "vllm", OpenAIConfig { api_key: "secret".to_string(), ..config });
+        keyed.generate("qwen2.5-7b", "What is 2+2?").await.unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let auth: Vec<_> = requests.iter().map(|r| r.headers.get("Authorization").map(|v| v.to_str().unwrap().to_string())).collect();
+        assert_eq!(auth, [None, None, Some("Bearer secret".to_string())]);
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(body["model"], "qwen2.5-7b");
+    }
+
+    #[tokio::test]
+    async fn test_extra_headers_are_sent_with_every_request_and_override_the_defaults() {
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(header("X-Org", "evals"))
+            .and(header("Authorization", "Gateway token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{ "message": { "content": "4" }, "finish_reason": "stop" }],
+                "usage": { "prompt_tokens": 7, "completion_tokens": 1 }
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET")).and(path("/v1/models")).and(header("X-Org", "evals")).respond_with(ResponseTemplate::new(200)).mount(&server).await;
+
+        let headers = crate::http_client::parse_extra_headers("OPENAI_EXTRA_HEADERS", "X-Org: evals; Authorization: Gateway token").unwrap();
+        let config = OpenAIConfig { api_base: format!("{}/v1", server.uri()), api_key: "secret".to_string(), models: vec![] };
+        let provider = OpenAIProvider::new(Client::new(), config).with_extra_headers(headers);
+        assert_eq!(provider.generate("gpt-4o", "What is 2+2?").await.unwrap().output, "4");
+        provider.health().await.unwrap();
     }
 }
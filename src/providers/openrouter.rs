@@ -0,0 +1,104 @@
+// src/providers/openrouter.rs
+
+use async_trait::async_trait;
+use reqwest::header::HeaderMap;
+use reqwest::Client;
+
+use crate::config::{OpenAIConfig, OpenRouterConfig};
+use crate::errors::Result;
+use crate::providers::openai::OpenAIProvider;
+use crate::providers::{AbortCheck, Generation, GenerationParams, LlmProvider, StreamedGeneration};
+
+/// A provider for OpenRouter, which serves many vendors' models behind an OpenAI-compatible
+/// API. Models are named `vendor/model`, and the model that answered is reported in
+/// `resolved_model`, as OpenRouter may route a request to a different model.
+pub struct OpenRouterProvider {
+    inner: OpenAIProvider,
+}
+
+impl OpenRouterProvider {
+    /// Creates a new `OpenRouterProvider`, identifying the app with the `HTTP-Referer` and
+    /// `X-Title` headers OpenRouter recommends.
+    pub fn new(client: Client, config: OpenRouterConfig) -> Self {
+        let OpenRouterConfig { api_base, api_key, models, referer, title } = config;
+        let inner = OpenAIProvider::compatible(client, "openrouter", OpenAIConfig { api_base, api_key, models })
+            .with_header("HTTP-Referer", referer)
+            .with_header("X-Title", title);
+        Self { inner }
+    }
+
+    /// Sends `params` with every request; see `OpenAIProvider::with_params`.
+    pub fn with_params(self, params: GenerationParams) -> Self {
+        Self { inner: self.inner.with_params(params) }
+    }
+
+    /// Sends `headers` with every request; see `OpenAIProvider::with_extra_headers`.
+    pub fn with_extra_headers(self, headers: HeaderMap) -> Self {
+        Self { inner: self.inner.with_extra_headers(headers) }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenRouterProvider {
+    async fn generate(&self, model: &str, prompt: &str) -> Result<Generation> {
+        self.inner.generate(model, prompt).await
+    }
+
+    async fn generate_streaming(
+        &self,
+        model: &str,
+        prompt: &str,
+        should_abort: AbortCheck<'_>,
+    ) -> Result<StreamedGeneration> {
+        self.inner.generate_streaming(model, prompt, should_abort).await
+    }
+
+    async fn health(&self) -> Result<()> {
+        self.inner.health().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_generate_sends_app_headers_and_reports_the_routed_model() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/chat/completions"))
+            .and(header("Authorization", "Bearer sk-or-test"))
+            .and(header("HTTP-Referer", "https://evals.example.com"))
+            .and(header("X-Title", "evaluate"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "gen-1",
+                "model": "anthropic/claude-3.5-sonnet-20240620",
+                "choices": [{ "message": { "role": "assistant", "content": "4" }, "finish_reason": "stop" }],
+                "usage": { "prompt_tokens": 12, "completion_tokens": 1, "total_tokens": 13 }
+            })))
+            .mount(&server)
+            .await;
+
+        let provider = OpenRouterProvider::new(
+            Client::new(),
+            OpenRouterConfig {
+                api_base: format!("{}/api/v1", server.uri()),
+                api_key: "sk-or-test".to_string(),
+                models: vec!["anthropic/claude-3.5-sonnet".to_string()],
+                referer: "https://evals.example.com".to_string(),
+                title: "evaluate".to_string(),
+            },
+        );
+        let generation = provider.generate("anthropic/claude-3.5-sonnet", "What is 2+2?").await.unwrap();
+        assert_eq!(generation.output, "4");
+        assert_eq!(generation.token_usage.input_tokens, Some(12));
+        assert_eq!(generation.token_usage.output_tokens, Some(1));
+        assert_eq!(generation.resolved_model.as_deref(), Some("anthropic/claude-3.5-sonnet-20240620"));
+
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(body["model"], "anthropic/claude-3.5-sonnet");
+    }
+}
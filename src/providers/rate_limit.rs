@@ -0,0 +1,263 @@
+// src/providers/rate_limit.rs
+
+use async_trait::async_trait;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::errors::Result;
+use crate::providers::{AbortCheck, Generation, LlmProvider, StreamedGeneration, TokenUsage};
+
+/// Requests and tokens a provider may be sent per minute; `None` leaves either unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ProviderRateLimit {
+    pub rpm: Option<u32>,
+    pub tpm: Option<u32>,
+}
+
+#[derive(Debug)]
+struct LimiterState {
+    /// Earliest moment the next request may be sent.
+    next_request: Instant,
+    /// Tokens left this minute; negative while calls that ran over the limit are paid back.
+    tokens: f64,
+    updated: Instant,
+}
+
+/// A leaky bucket for one provider. Requests leave it evenly spaced at `rpm`; with `tpm`, the
+/// tokens calls used are only known afterwards, so a call waits while earlier ones have used
+/// more tokens than have refilled since.
+#[derive(Debug)]
+pub struct ProviderLimiter {
+    limit: ProviderRateLimit,
+    state: Mutex<LimiterState>,
+    /// Calls waiting for their turn right now.
+    queued: AtomicUsize,
+}
+
+/// Takes a call off the queue count even when the waiting call is dropped.
+struct QueueGuard<'a>(&'a AtomicUsize);
+
+impl Drop for QueueGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl ProviderLimiter {
+    pub fn new(limit: ProviderRateLimit) -> Self {
+        let now = Instant::now();
+        ProviderLimiter {
+            limit,
+            state: Mutex::new(LimiterState {
+                next_request: now,
+                tokens: limit.tpm.map_or(0.0, f64::from),
+                updated: now,
+            }),
+            queued: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn limit(&self) -> ProviderRateLimit {
+        self.limit
+    }
+
+    pub fn queue_depth(&self) -> usize {
+        self.queued.load(Ordering::Relaxed)
+    }
+
+    /// Reserves the next free slot and waits for it. Slots are handed out in the order calls
+    /// arrive, so excess calls queue up instead of failing.
+    pub async fn acquire(&self) {
+        let wait = self.reserve(Instant::now());
+        if wait.is_zero() {
+            return;
+        }
+        self.queued.fetch_add(1, Ordering::Relaxed);
+        let _queued = QueueGuard(&self.queued);
+        tokio::time::sleep(wait).await;
+    }
+
+    /// How long a call arriving at `now` waits for its slot.
+    fn reserve(&self, now: Instant) -> Duration {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let mut start = now.max(state.next_request);
+        if let Some(tpm) = self.limit.tpm {
+            let per_second = f64::from(tpm) / 60.0;
+            let refilled = now.saturating_duration_since(state.updated).as_secs_f64() * per_second;
+            state.tokens = (state.tokens + refilled).min(f64::from(tpm));
+            state.updated = now;
+            if state.tokens < 0.0 {
+                start = start.max(now + Duration::from_secs_f64(-state.tokens / per_second));
+            }
+        }
+        if let Some(rpm) = self.limit.rpm {
+            state.next_request = start + Duration::from_secs_f64(60.0 / f64::from(rpm));
+        }
+        start - now
+    }
+
+    /// Counts the tokens a finished call used against the token limit.
+    pub fn record_usage(&self, usage: &TokenUsage) {
+        if self.limit.tpm.is_none() {
+            return;
+        }
+        let used = usage.input_tokens.unwrap_or(0) + usage.output_tokens.unwrap_or(0);
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.tokens -= f64::from(used);
+    }
+}
+
+/// One provider's limits and queue, as reported by the health and metrics endpoints.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProviderQueue {
+    #[serde(flatten)]
+    pub limit: ProviderRateLimit,
+    pub queue_depth: usize,
+}
+
+/// The limiters of every rate-limited provider, by provider name. One set serves the whole
+/// server, so evals, judges and concurrent batches all queue on the same limits.
+#[derive(Debug, Default)]
+pub struct ProviderLimiters {
+    limiters: HashMap<String, Arc<ProviderLimiter>>,
+}
+
+impl ProviderLimiters {
+    /// Providers whose limits leave both rates unlimited get no limiter.
+    pub fn new(limits: impl IntoIterator<Item = (String, ProviderRateLimit)>) -> Self {
+        let limiters = limits
+            .into_iter()
+            .filter(|(_, limit)| limit.rpm.is_some() || limit.tpm.is_some())
+            .map(|(provider, limit)| (provider, Arc::new(ProviderLimiter::new(limit))))
+            .collect();
+        ProviderLimiters { limiters }
+    }
+
+    pub fn get(&self, provider: &str) -> Option<&Arc<ProviderLimiter>> {
+        self.limiters.get(provider)
+    }
+
+    /// `inner`, waiting for `provider`'s limiter before every call when it has one.
+    pub fn wrap(&self, provider: &str, inner: Box<dyn LlmProvider>) -> Box<dyn LlmProvider> {
+        match self.get(provider) {
+            Some(limiter) => RateLimitedProvider::new(inner, Arc::clone(limiter)).boxed(),
+            None => inner,
+        }
+    }
+
+    /// Limits and current queue depth per rate-limited provider.
+    pub fn queues(&self) -> BTreeMap<String, ProviderQueue> {
+        self.limiters
+            .iter()
+            .map(|(provider, limiter)| {
+                (provider.clone(), ProviderQueue { limit: limiter.limit(), queue_depth: limiter.queue_depth() })
+            })
+            .collect()
+    }
+}
+
+/// Waits for a permit from the provider's limiter before each call of the inner provider.
+pub struct RateLimitedProvider<P> {
+    inner: P,
+    limiter: Arc<ProviderLimiter>,
+}
+
+impl<P: LlmProvider> RateLimitedProvider<P> {
+    pub fn new(inner: P, limiter: Arc<ProviderLimiter>) -> Self {
+        Self { inner, limiter }
+    }
+}
+
+#[async_trait]
+impl<P: LlmProvider> LlmProvider for RateLimitedProvider<P> {
+    async fn generate(&self, model: &str, prompt: &str) -> Result<Generation> {
+        self.limiter.acquire().await;
+        let generation = self.inner.generate(model, prompt).await?;
+        self.limiter.record_usage(&generation.token_usage);
+        Ok(generation)
+    }
+
+    async fn generate_streaming(
+        &self,
+        model: &str,
+        prompt: &str,
+        should_abort: AbortCheck<'_>,
+    ) -> Result<StreamedGeneration> {
+        self.limiter.acquire().await;
+        let generation = self.inner.generate_streaming(model, prompt, should_abort).await?;
+        self.limiter.record_usage(&generation.token_usage);
+        Ok(generation)
+    }
+
+    /// Not limited: health checks do not generate anything.
+    async fn health(&self) -> Result<()> {
+        self.inner.health().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(rpm: Option<u32>, tpm: Option<u32>) -> ProviderLimiter {
+        ProviderLimiter::new(ProviderRateLimit { rpm, tpm })
+    }
+
+    #[test]
+    fn test_requests_are_spaced_at_the_rpm() {
+        let limiter = limiter(Some(60), None);
+        let now = Instant::now();
+        assert_eq!(limiter.reserve(now), Duration::ZERO);
+        assert_eq!(limiter.reserve(now), Duration::from_secs(1));
+        assert_eq!(limiter.reserve(now), Duration::from_secs(2));
+        // Slots that passed unused are not saved up
+        let later = now + Duration::from_secs(10);
+        assert_eq!(limiter.reserve(later), Duration::ZERO);
+        assert_eq!(limiter.reserve(later), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_calls_wait_while_the_tokens_used_are_paid_back() {
+        let limiter = limiter(None, Some(600));
+        let now = Instant::now();
+        assert_eq!(limiter.reserve(now), Duration::ZERO);
+        limiter.record_usage(&TokenUsage { input_tokens: Some(500), output_tokens: Some(200) });
+        // 100 tokens over at 10 tokens a second
+        let wait = limiter.reserve(now);
+        assert!(wait > Duration::from_millis(9_900) && wait <= Duration::from_secs(10), "{:?}", wait);
+    }
+
+    #[tokio::test]
+    async fn test_waiting_calls_are_counted_in_the_queue() {
+        let limiter = Arc::new(limiter(Some(600), None));
+        limiter.acquire().await;
+        let waiting: Vec<_> = (0..2)
+            .map(|_| {
+                let limiter = Arc::clone(&limiter);
+                tokio::spawn(async move { limiter.acquire().await })
+            })
+            .collect();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(limiter.queue_depth(), 2);
+        for call in waiting {
+            call.await.unwrap();
+        }
+        assert_eq!(limiter.queue_depth(), 0);
+    }
+
+    #[test]
+    fn test_unlimited_providers_get_no_limiter() {
+        let limiters = ProviderLimiters::new([
+            ("openai".to_string(), ProviderRateLimit { rpm: Some(60), tpm: None }),
+            ("ollama".to_string(), ProviderRateLimit::default()),
+        ]);
+        assert!(limiters.get("openai").is_some());
+        assert!(limiters.get("ollama").is_none());
+        let queues = limiters.queues();
+        assert_eq!(queues.len(), 1);
+        assert_eq!(queues["openai"], ProviderQueue { limit: ProviderRateLimit { rpm: Some(60), tpm: None }, queue_depth: 0 });
+    }
+}
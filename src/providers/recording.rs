@@ -0,0 +1,181 @@
+// src/providers/recording.rs
+
+use reqwest::header::{HeaderMap, CONTENT_TYPE};
+use reqwest::{Client, Request, RequestBuilder, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::errors::Result;
+use crate::providers::capture::CapturedRequest;
+
+/// When set, every provider exchange is written as a fixture under this directory.
+pub const RECORD_DIR_ENV: &str = "EVAL_RECORD_DIR";
+/// Names the fixture file for the exchanges being recorded, e.g. `success` or `rate_limited`.
+pub const RECORD_SCENARIO_ENV: &str = "EVAL_RECORD_SCENARIO";
+
+/// Request headers whose values are replaced before a fixture is written.
+const SECRET_HEADERS: &[&str] = &["authorization", "x-api-key", "x-goog-api-key"];
+pub const REDACTED: &str = "<redacted>";
+
+/// One provider HTTP call and its response, as stored in a fixture file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedExchange {
+    pub provider: String,
+    pub model: String,
+    pub prompt: String,
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    /// Lowercased header names; secret values are replaced with `<redacted>`.
+    pub headers: BTreeMap<String, String>,
+    pub body: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    pub status: u16,
+    pub content_type: Option<String>,
+    /// Kept as text so malformed responses can be recorded verbatim.
+    pub body: String,
+}
+
+impl RecordedRequest {
+    fn from_request(request: &Request) -> Self {
+        let headers = request
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                let name = name.as_str().to_lowercase();
+                let value = if SECRET_HEADERS.contains(&name.as_str()) {
+                    REDACTED.to_string()
+                } else {
+                    String::from_utf8_lossy(value.as_bytes()).to_string()
+                };
+                (name, value)
+            })
+            .collect();
+        let body = request
+            .body()
+            .and_then(|b| b.as_bytes())
+            .and_then(|bytes| serde_json::from_slice(bytes).ok());
+
+        Self {
+            method: request.method().to_string(),
+            path: request.url().path().to_string(),
+            headers,
+            body,
+        }
+    }
+}
+
+/// Sends a non-streaming provider request and returns the status and full response body.
+/// When `EVAL_RECORD_DIR` is set, the sanitized exchange is also written as a fixture, and
+/// inside `capture::capturing` it is kept with the capture.
+pub(crate) async fn send(
+    client: &Client,
+    request: RequestBuilder,
+    provider: &str,
+    model: &str,
+    prompt: &str,
+) -> Result<(StatusCode, String)> {
+    let (status, _, body) = send_with_headers(client, request, provider, model, prompt).await?;
+    Ok((status, body))
+}
+
+/// `send`, also returning the response headers.
+pub(crate) async fn send_with_headers(
+    client: &Client,
+    request: RequestBuilder,
+    provider: &str,
+    model: &str,
+    prompt: &str,
+) -> Result<(StatusCode, HeaderMap, String)> {
+    let request = request.build()?;
+    let record_dir = std::env::var_os(RECORD_DIR_ENV).map(PathBuf::from);
+    let recorded_request = record_dir.as_ref().map(|_| RecordedRequest::from_request(&request));
+    let captured_request = CapturedRequest::of(&request);
+
+    let resp = client.execute(request).await?;
+    let status = resp.status();
+    let headers = resp.headers().clone();
+    let content_type = resp
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let body = resp.text().await?;
+
+    if let Some(captured) = captured_request {
+        captured.finish(provider, model, status, &headers, &body);
+    }
+
+    if let (Some(dir), Some(request)) = (record_dir, recorded_request) {
+        let exchange = RecordedExchange {
+            provider: provider.to_string(),
+            model: model.to_string(),
+            prompt: prompt.to_string(),
+            request,
+            response: RecordedResponse {
+                status: status.as_u16(),
+                content_type,
+                body: body.clone(),
+            },
+        };
+        match write_fixture(&dir, &exchange) {
+            Ok(path) => tracing::info!("📼 Recorded {} exchange to {}", provider, path.display()),
+            Err(e) => tracing::warn!("⚠️  Failed to record {} exchange: {}", provider, e),
+        }
+    }
+
+    Ok((status, headers, body))
+}
+
+/// Writes `exchange` to `<dir>/<provider>/<scenario>.json`, where the scenario comes from
+/// `EVAL_RECORD_SCENARIO` (default `recorded`).
+fn write_fixture(dir: &Path, exchange: &RecordedExchange) -> std::io::Result<PathBuf> {
+    let scenario = std::env::var(RECORD_SCENARIO_ENV).unwrap_or_else(|_| "recorded".to_string());
+    let provider_dir = dir.join(&exchange.provider);
+    std::fs::create_dir_all(&provider_dir)?;
+
+    let path = provider_dir.join(format!("{}.json", scenario));
+    let json = serde_json::to_string_pretty(exchange).map_err(std::io::Error::other)?;
+    std::fs::write(&path, json + "\n")?;
+    Ok(path)
+}
+
+/// Loads a fixture written by `send`.
+pub fn load_fixture(path: &Path) -> Result<RecordedExchange> {
+    let content = std::fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recorded_request_redacts_secrets() {
+        let client = Client::new();
+        let request = client
+            .post("http://localhost:1234/v1/messages")
+            .header("Authorization", "Bearer sk-secret")
+            .header("x-goog-api-key", "AIza-secret")
+            .json(&serde_json::json!({"model": "m", "prompt": "hi"}))
+            .build()
+            .unwrap();
+
+        let recorded = RecordedRequest::from_request(&request);
+        assert_eq!(recorded.method, "POST");
+        assert_eq!(recorded.path, "/v1/messages");
+        assert_eq!(recorded.headers["authorization"], REDACTED);
+        assert_eq!(recorded.headers["x-goog-api-key"], REDACTED);
+        assert_eq!(recorded.headers["content-type"], "application/json");
+        assert_eq!(recorded.body, Some(serde_json::json!({"model": "m", "prompt": "hi"})));
+    }
+}
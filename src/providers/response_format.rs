@@ -0,0 +1,129 @@
+// src/providers/response_format.rs
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+
+use crate::errors::Result;
+use crate::providers::{AbortCheck, Generation, LlmProvider, StreamedGeneration};
+
+/// Output format asked of the model. Providers with a JSON mode enforce it; the others are
+/// told in the prompt.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseFormat {
+    /// Any valid JSON.
+    Json,
+    /// JSON matching the inline JSON `schema`.
+    JsonSchema { schema: JsonValue },
+}
+
+impl ResponseFormat {
+    /// The name stored with an evaluation: `json` or `json_schema`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ResponseFormat::Json => "json",
+            ResponseFormat::JsonSchema { .. } => "json_schema",
+        }
+    }
+
+    /// The `response_format` of an OpenAI chat completion request.
+    pub fn openai(&self) -> JsonValue {
+        match self {
+            ResponseFormat::Json => json!({ "type": "json_object" }),
+            ResponseFormat::JsonSchema { schema } => {
+                json!({ "type": "json_schema", "json_schema": { "name": "response", "schema": schema } })
+            }
+        }
+    }
+
+    /// The schema the output must match, if any.
+    pub fn schema(&self) -> Option<&JsonValue> {
+        match self {
+            ResponseFormat::Json => None,
+            ResponseFormat::JsonSchema { schema } => Some(schema),
+        }
+    }
+
+    /// The instruction appended to the prompt of a model that cannot be asked for JSON directly.
+    pub fn instruction(&self) -> String {
+        match self {
+            ResponseFormat::Json => "Respond only with valid JSON, without any other text.".to_string(),
+            ResponseFormat::JsonSchema { schema } => format!(
+                "Respond only with JSON matching this JSON schema, without any other text:\n{}",
+                schema
+            ),
+        }
+    }
+
+    /// `prompt` followed by the instruction.
+    pub fn instruct(&self, prompt: &str) -> String {
+        format!("{}\n\n{}", prompt, self.instruction())
+    }
+}
+
+/// Asks the inner provider, which has no JSON mode, for `format` in the prompt.
+pub struct InstructedFormat<P> {
+    inner: P,
+    format: ResponseFormat,
+}
+
+impl<P: LlmProvider> InstructedFormat<P> {
+    pub fn new(inner: P, format: ResponseFormat) -> Self {
+        Self { inner, format }
+    }
+}
+
+#[async_trait]
+impl<P: LlmProvider> LlmProvider for InstructedFormat<P> {
+    async fn generate(&self, model: &str, prompt: &str) -> Result<Generation> {
+        self.inner.generate(model, &self.format.instruct(prompt)).await
+    }
+
+    async fn generate_streaming(
+        &self,
+        model: &str,
+        prompt: &str,
+        should_abort: AbortCheck<'_>,
+    ) -> Result<StreamedGeneration> {
+        self.inner.generate_streaming(model, &self.format.instruct(prompt), should_abort).await
+    }
+
+    async fn health(&self) -> Result<()> {
+        self.inner.health().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> JsonValue {
+        json!({ "type": "object", "properties": { "answer": { "type": "integer" } }, "required": ["answer"] })
+    }
+
+    #[test]
+    fn test_formats_are_read_as_tagged_objects() {
+        let json: ResponseFormat = serde_json::from_value(json!({ "type": "json" })).unwrap();
+        assert_eq!(json, ResponseFormat::Json);
+        let with_schema: ResponseFormat = serde_json::from_value(json!({ "type": "json_schema", "schema": schema() })).unwrap();
+        assert_eq!(with_schema, ResponseFormat::JsonSchema { schema: schema() });
+        assert_eq!(with_schema.as_str(), "json_schema");
+        assert!(serde_json::from_value::<ResponseFormat>(json!({ "type": "json_schema" })).is_err());
+    }
+
+    #[test]
+    fn test_openai_request_fields() {
+        assert_eq!(ResponseFormat::Json.openai(), json!({ "type": "json_object" }));
+        let structured = ResponseFormat::JsonSchema { schema: schema() }.openai();
+        assert_eq!(structured["type"], "json_schema");
+        assert_eq!(structured["json_schema"]["schema"], schema());
+    }
+
+    #[test]
+    fn test_instruction_names_the_schema() {
+        let prompt = ResponseFormat::JsonSchema { schema: schema() }.instruct("What is 2+2?");
+        assert!(prompt.starts_with("What is 2+2?\n\nRespond only with JSON matching this JSON schema"));
+        assert!(prompt.ends_with(&schema().to_string()));
+    }
+}
@@ -0,0 +1,150 @@
+// src/reasoning.rs
+
+/// Tags reasoning models such as DeepSeek-R1 and Qwen wrap their chain of thought in.
+const OPEN_TAG: &str = "<think>";
+const CLOSE_TAG: &str = "</think>";
+
+/// A model output split into the model's reasoning and its final answer.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ReasoningSplit {
+    pub reasoning: Option<String>,
+    pub answer: String,
+}
+
+/// Splits the `<think>…</think>` blocks out of `output`, ignoring the tags' case. An output
+/// cut off while thinking leaves its unclosed block to the reasoning, and a closing tag
+/// without an opening one (chat templates that put `<think>` in the prompt) ends reasoning
+/// that started with the output. Outputs without tags are returned untouched.
+pub fn split_think_tags(output: &str) -> ReasoningSplit {
+    // ASCII lowercasing keeps byte offsets, so positions found in `lower` index `output` too
+    let lower = output.to_ascii_lowercase();
+    if !lower.contains(OPEN_TAG) && !lower.contains(CLOSE_TAG) {
+        return ReasoningSplit { reasoning: None, answer: output.to_string() };
+    }
+
+    let mut reasoning = Vec::new();
+    let mut answer = String::new();
+    let mut pos = 0;
+    let unopened = lower.find(CLOSE_TAG).filter(|&close| lower.find(OPEN_TAG).is_none_or(|open| close < open));
+    if let Some(close) = unopened {
+        reasoning.push(&output[..close]);
+        pos = close + CLOSE_TAG.len();
+    }
+    loop {
+        let Some(open) = lower[pos..].find(OPEN_TAG).map(|open| pos + open) else {
+            answer.push_str(&output[pos..]);
+            break;
+        };
+        answer.push_str(&output[pos..open]);
+        let body = open + OPEN_TAG.len();
+        match lower[body..].find(CLOSE_TAG) {
+            Some(close) => {
+                reasoning.push(&output[body..body + close]);
+                pos = body + close + CLOSE_TAG.len();
+            }
+            None => {
+                reasoning.push(&output[body..]);
+                break;
+            }
+        }
+    }
+
+    ReasoningSplit { reasoning: join(reasoning), answer: answer.trim().to_string() }
+}
+
+/// Splits `output`, adding the reasoning a provider returned apart from it (Anthropic
+/// thinking blocks, DeepSeek's `reasoning_content`, ...) before any found in its tags.
+pub fn extract(provider_reasoning: Option<&str>, output: &str) -> ReasoningSplit {
+    let split = split_think_tags(output);
+    let reasoning = join(provider_reasoning.into_iter().chain(split.reasoning.as_deref()).collect());
+    ReasoningSplit { reasoning, answer: split.answer }
+}
+
+/// The answer so far of a partial streamed output: empty while the model is still thinking,
+/// including while an opening tag is only partly received.
+pub fn partial_answer(partial: &str) -> String {
+    let answer = split_think_tags(partial).answer;
+    let start = answer.trim_start().to_ascii_lowercase();
+    if !start.is_empty() && OPEN_TAG.starts_with(&start) {
+        return String::new();
+    }
+    answer
+}
+
+/// The output shown to the judge: the answer, preceded by the reasoning in `<think>` tags
+/// when `include_reasoning` is set and there is any.
+pub fn judged_output(reasoning: Option<&str>, answer: &str, include_reasoning: bool) -> String {
+    match reasoning {
+        Some(reasoning) if include_reasoning => format!("{}\n{}\n{}\n\n{}", OPEN_TAG, reasoning, CLOSE_TAG, answer),
+        _ => answer.to_string(),
+    }
+}
+
+/// Non-empty parts, trimmed and separated by blank lines.
+fn join(parts: Vec<&str>) -> Option<String> {
+    let parts: Vec<&str> = parts.into_iter().map(str::trim).filter(|part| !part.is_empty()).collect();
+    if parts.is_empty() { None } else { Some(parts.join("\n\n")) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(output: &str) -> (Option<String>, String) {
+        let split = split_think_tags(output);
+        (split.reasoning, split.answer)
+    }
+
+    #[test]
+    fn test_think_blocks_are_split_from_the_answer() {
+        assert_eq!(
+            split("<think>\n2 plus 2 is 4.\n</think>\n\nThe answer is 4."),
+            (Some("2 plus 2 is 4.".to_string()), "The answer is 4.".to_string())
+        );
+        assert_eq!(
+            split("<THINK>first</THINK>Partial <think>second</think> answer"),
+            (Some("first\n\nsecond".to_string()), "Partial  answer".to_string())
+        );
+        // Empty blocks, as Qwen emits with thinking turned off, leave no reasoning
+        assert_eq!(split("<think>\n\n</think>\n\n4"), (None, "4".to_string()));
+        assert_eq!(split("  4  "), (None, "  4  ".to_string()));
+    }
+
+    #[test]
+    fn test_unclosed_and_unopened_blocks() {
+        // Cut off while thinking: there is no answer yet
+        assert_eq!(split("<think>Let me add 2 and"), (Some("Let me add 2 and".to_string()), String::new()));
+        // The opening tag was part of the prompt template
+        assert_eq!(
+            split("Adding the numbers.</think>\nThe answer is 4."),
+            (Some("Adding the numbers.".to_string()), "The answer is 4.".to_string())
+        );
+        assert_eq!(
+            split("Hmm.</think>4 <think>check</think>"),
+            (Some("Hmm.\n\ncheck".to_string()), "4".to_string())
+        );
+    }
+
+    #[test]
+    fn test_provider_reasoning_comes_before_tagged_reasoning() {
+        let split = extract(Some("The user asks for a sum."), "<think>2+2=4</think>4");
+        assert_eq!(split.reasoning.as_deref(), Some("The user asks for a sum.\n\n2+2=4"));
+        assert_eq!(split.answer, "4");
+        assert_eq!(extract(Some("  "), "4"), ReasoningSplit { reasoning: None, answer: "4".to_string() });
+    }
+
+    #[test]
+    fn test_partial_answers_are_empty_while_thinking() {
+        assert_eq!(partial_answer("<thi"), "");
+        assert_eq!(partial_answer("<think>Let me"), "");
+        assert_eq!(partial_answer("<think>Done.</think>SELECT"), "SELECT");
+        assert_eq!(partial_answer("SELECT"), "SELECT");
+    }
+
+    #[test]
+    fn test_judged_output_includes_reasoning_only_when_asked() {
+        assert_eq!(judged_output(Some("2+2=4"), "4", false), "4");
+        assert_eq!(judged_output(Some("2+2=4"), "4", true), "<think>\n2+2=4\n</think>\n\n4");
+        assert_eq!(judged_output(None, "4", true), "4");
+    }
+}
@@ -0,0 +1,144 @@
+// src/redact.rs
+//! Masks API keys and other secrets in text bound for logs, error responses, WebSocket events
+//! and the database: the secrets of the loaded config, registered at startup, and anything
+//! shaped like a key, e.g. `sk-...` or a `Bearer` token, that a provider or gateway echoes back.
+use regex::{Captures, Regex};
+use std::io;
+use std::sync::{LazyLock, RwLock};
+use tracing_subscriber::fmt::MakeWriter;
+
+/// What a masked secret is replaced with.
+pub const REDACTED: &str = "[redacted]";
+
+/// Secrets shorter than this are not masked, so a placeholder key such as `test` does not
+/// mask every occurrence of the word.
+const MIN_SECRET_LEN: usize = 8;
+
+/// Secrets of the loaded config; see `register`.
+static CONFIGURED: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/// Key-shaped text, masked whatever its source. Each pattern keeps its first group, the name
+/// the secret is given under, and its second, if any, and masks the rest of the match.
+static PATTERNS: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    [
+        // Bearer tokens, e.g. an echoed `Authorization` header
+        r"(?i)(\bbearer\s+)[A-Za-z0-9._~+/=-]{8,}",
+        // Header and query parameter values, e.g. `x-goog-api-key: ...` or `?key=...`
+        r#"(?i)(\b(?:x-goog-api-key|x-api-key|api[_-]?key|key|token|secret)["']?\s*[:=]\s*["']?)[A-Za-z0-9._~+/-]{8,}"#,
+        // OpenAI, Anthropic, OpenRouter and Groq keys
+        r"()\b(?:sk|gsk)[-_][A-Za-z0-9_-]{8,}",
+        // Google API keys
+        r"()\bAIza[A-Za-z0-9_-]{30,}",
+        // The password of a URL such as `DATABASE_URL`
+        r"(://[^:/\s@]+:)[^@\s/]+(@)",
+    ]
+    .iter()
+    .map(|pattern| Regex::new(pattern).expect("redaction pattern"))
+    .collect()
+});
+
+/// Replaces the configured secrets `redact` masks, e.g. with `AppConfig::secrets` once the
+/// config is loaded.
+pub fn register(secrets: impl IntoIterator<Item = String>) {
+    let mut configured = CONFIGURED.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+    *configured = secrets.into_iter().filter(|secret| secret.len() >= MIN_SECRET_LEN).collect();
+    // Longer secrets first, so one that contains another is masked whole
+    configured.sort_by_key(|secret| std::cmp::Reverse(secret.len()));
+}
+
+/// `text` with the registered secrets and key-shaped text masked.
+pub fn redact(text: &str) -> String {
+    let configured = CONFIGURED.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+    mask(text, &configured)
+}
+
+/// `text` with every one of `secrets`, and key-shaped text, masked.
+pub fn mask(text: &str, secrets: &[String]) -> String {
+    let text = secrets
+        .iter()
+        .filter(|secret| !secret.is_empty())
+        .fold(text.to_string(), |text, secret| text.replace(secret.as_str(), REDACTED));
+    PATTERNS.iter().fold(text, |text, pattern| {
+        pattern
+            .replace_all(&text, |caps: &Captures| {
+                format!("{}{}{}", &caps[1], REDACTED, caps.get(2).map_or("", |suffix| suffix.as_str()))
+            })
+            .into_owned()
+    })
+}
+
+/// Makes log writers that mask what `redact` masks before writing to those `M` makes.
+pub struct Redacting<M>(pub M);
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for Redacting<M> {
+    type Writer = RedactingWriter<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter(self.0.make_writer())
+    }
+}
+
+pub struct RedactingWriter<W>(W);
+
+impl<W: io::Write> io::Write for RedactingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // Each log line arrives in one write
+        self.0.write_all(redact(&String::from_utf8_lossy(buf)).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_configured_keys_and_key_shaped_text_are_masked() {
+        let config = crate::config::AppConfig {
+            openai: Some(crate::config::OpenAIConfig {
+                api_base: "https://api.openai.com/v1".to_string(),
+                api_key: "sk-proj-Xy7Qm2Lk9".to_string(),
+                models: vec![],
+            }),
+            webhook_secret: Some("team-gateway-9f2c41".to_string()),
+            ..Default::default()
+        };
+        let secrets = config.secrets();
+        let cases = [
+            (
+                r#"{"error": {"message": "Incorrect API key provided: sk-proj-Xy7Qm2Lk9."}}"#,
+                r#"{"error": {"message": "Incorrect API key provided: [redacted]."}}"#,
+            ),
+            ("upstream rejected Authorization: Bearer team-gateway-9f2c41", "upstream rejected Authorization: Bearer [redacted]"),
+            ("upstream rejected Authorization: Bearer abcdef0123456789", "upstream rejected Authorization: Bearer [redacted]"),
+            (
+                "GET https://generativelanguage.googleapis.com/v1beta/models?key=AIzaSyD3xAmpLe0123456789abcdefghijkl failed",
+                "GET https://generativelanguage.googleapis.com/v1beta/models?key=[redacted] failed",
+            ),
+            (r#"{"x-goog-api-key": "g00gle-secret-value"}"#, r#"{"x-goog-api-key": "[redacted]"}"#),
+            ("invalid key gsk_live_0123456789", "invalid key [redacted]"),
+            ("connecting to postgres://evals:hunter2hunter2@db:5432/evals", "connecting to postgres://evals:[redacted]@db:5432/evals"),
+        ];
+        for (text, masked) in cases {
+            assert_eq!(mask(text, &secrets), masked);
+        }
+    }
+
+    #[test]
+    fn test_ordinary_error_text_is_untouched() {
+        let secrets = vec!["team-gateway-9f2c41".to_string()];
+        for text in [
+            "API request failed with status 500: {\"error\": \"internal server error\"}",
+            "Model 'openai:gpt-4o-mini' failed to respond",
+            "The model 'gpt-4o-mini' does not exist or you do not have access to it. Check your key and skip tasks.",
+            "Config 2: provider 'anthropic' (model 'claude-3-haiku') is not configured",
+            "HTTP request failed: error sending request: connection refused",
+        ] {
+            assert_eq!(mask(text, &secrets), text);
+        }
+    }
+}
@@ -0,0 +1,186 @@
+// src/rejudge.rs
+use serde::Serialize;
+use crate::database::DbPool;
+
+use crate::config::AppConfig;
+use crate::database::{self, HistoryEntry};
+use crate::runner::{self, JudgeVerdict, UncertainReason};
+
+/// The judge prompt a re-judge renders.
+#[derive(Debug, Clone)]
+pub struct RejudgePrompt {
+    pub template: String,
+    pub version: i64,
+}
+
+/// What re-judging one stored evaluation changed.
+#[derive(Debug, Clone, Serialize)]
+pub struct RejudgeOutcome {
+    pub evaluation_id: String,
+    pub judge_model: String,
+    /// The judge verdict stored before the re-judge.
+    pub previous_verdict: Option<String>,
+    /// The new verdict, or `None` when the judge call or the save failed and the previous verdict was kept.
+    pub verdict: Option<JudgeVerdict>,
+    pub confidence: Option<f32>,
+    pub uncertain_reason: Option<UncertainReason>,
+    /// The evaluation's status after the re-judge.
+    pub status: Option<String>,
+    pub error: Option<String>,
+}
+
+impl RejudgeOutcome {
+    /// The judge verdict stored once the re-judge finished.
+    fn stored_verdict(&self) -> Option<String> {
+        match self.verdict {
+            Some(verdict) => Some(verdict.to_string()),
+            None => self.previous_verdict.clone(),
+        }
+    }
+
+    pub fn changed(&self) -> bool {
+        self.verdict.is_some() && self.stored_verdict() != self.previous_verdict
+    }
+}
+
+/// Re-judges one stored evaluation with `prompt` and saves the new verdict, keeping the previous
+/// one in the `judgements` table. The model is not called again.
+#[allow(clippy::too_many_arguments)]
+pub async fn rejudge(
+    config: &AppConfig,
+    client: &reqwest::Client,
+    pool: &DbPool,
+    entry: &HistoryEntry,
+    judge_model: &str,
+    model_output: &str,
+    expected: &str,
+    criteria: Option<&str>,
+    prompt: &RejudgePrompt,
+) -> RejudgeOutcome {
+    let mut outcome = RejudgeOutcome {
+        evaluation_id: entry.id.clone(),
+        judge_model: judge_model.to_string(),
+        previous_verdict: entry.judge_verdict.clone(),
+        verdict: None,
+        confidence: None,
+        uncertain_reason: None,
+        status: entry.status.clone(),
+        error: None,
+    };
+
+    let mut judgement = match runner::judge_only(
+        config,
+        client,
+        judge_model,
+        &prompt.template,
+        expected,
+        model_output,
+        criteria.or(entry.criteria.as_deref()),
+        Some(pool),
+    ).await {
+        Ok(judgement) => judgement,
+        Err(e) => {
+            outcome.error = Some(e.to_string());
+            return outcome;
+        }
+    };
+    let needs_review = runner::apply_review_threshold(&mut judgement.result, config.min_judge_confidence);
+
+    match database::save_rejudgement(pool, &entry.id, &judgement, Some(prompt.version), needs_review).await {
+        Ok(status) => {
+            outcome.verdict = Some(judgement.result.verdict);
+            outcome.confidence = judgement.result.confidence;
+            outcome.uncertain_reason = judgement.result.uncertain_reason;
+            outcome.status = Some(status.to_string());
+        }
+        Err(e) => {
+            tracing::error!("Failed to save re-judged verdict for {}: {}", entry.id, e);
+            outcome.error = Some(format!("Failed to save verdict: {}", e));
+        }
+    }
+    outcome
+}
+
+/// Stored judge verdicts by kind.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct VerdictCounts {
+    pub passed: usize,
+    pub failed: usize,
+    pub uncertain: usize,
+}
+
+impl VerdictCounts {
+    fn from_verdicts(verdicts: impl Iterator<Item = Option<String>>) -> Self {
+        let mut counts = VerdictCounts::default();
+        for verdict in verdicts {
+            match verdict.as_deref() {
+                Some("Pass") => counts.passed += 1,
+                Some("Fail") => counts.failed += 1,
+                Some(_) => counts.uncertain += 1,
+                None => {}
+            }
+        }
+        counts
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RejudgeSummary {
+    pub evaluations: usize,
+    pub rejudged: usize,
+    /// Evaluations whose judge call or save failed; their previous verdict is kept.
+    pub errors: usize,
+    /// Re-judged evaluations whose verdict differs from before.
+    pub changed: usize,
+    pub before: VerdictCounts,
+    /// Verdicts stored after the run, including kept verdicts of failed re-judges.
+    pub after: VerdictCounts,
+}
+
+/// Verdict counts before and after a bulk re-judge.
+pub fn summarize(outcomes: &[RejudgeOutcome]) -> RejudgeSummary {
+    let rejudged = outcomes.iter().filter(|o| o.verdict.is_some()).count();
+    RejudgeSummary {
+        evaluations: outcomes.len(),
+        rejudged,
+        errors: outcomes.len() - rejudged,
+        changed: outcomes.iter().filter(|o| o.changed()).count(),
+        before: VerdictCounts::from_verdicts(outcomes.iter().map(|o| o.previous_verdict.clone())),
+        after: VerdictCounts::from_verdicts(outcomes.iter().map(RejudgeOutcome::stored_verdict)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outcome(previous: &str, verdict: Option<JudgeVerdict>) -> RejudgeOutcome {
+        RejudgeOutcome {
+            evaluation_id: uuid::Uuid::new_v4().to_string(),
+            judge_model: "ollama:llama3".to_string(),
+            previous_verdict: Some(previous.to_string()),
+            verdict,
+            confidence: None,
+            uncertain_reason: None,
+            status: None,
+            error: verdict.is_none().then(|| "judge unavailable".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_summarize_counts_verdicts_before_and_after() {
+        use JudgeVerdict::*;
+        let outcomes = vec![
+            outcome("Uncertain", Some(Pass)),
+            outcome("Uncertain", Some(Fail)),
+            outcome("Uncertain", None),
+            outcome("Pass", Some(Pass)),
+        ];
+
+        let summary = summarize(&outcomes);
+        assert_eq!((summary.evaluations, summary.rejudged, summary.errors, summary.changed), (4, 3, 1, 2));
+        assert_eq!(summary.before, VerdictCounts { passed: 1, failed: 0, uncertain: 3 });
+        // The failed re-judge keeps its Uncertain verdict
+        assert_eq!(summary.after, VerdictCounts { passed: 2, failed: 1, uncertain: 1 });
+    }
+}
@@ -0,0 +1,365 @@
+// src/report.rs
+//! Human-readable reports of a batch, for pasting into docs and tickets: a summary table per
+//! model, then a section per failed evaluation with its prompt, expected and actual output and
+//! the judge's verdict and reasoning. Rendering is pure over rows already fetched, so the API
+//! and the CLI share it.
+use serde::Deserialize;
+
+use crate::chain::FAILED_STATUSES;
+use crate::database::{BatchSummary, HistoryEntry};
+
+/// Label of evaluations saved without a model, as failed evals are.
+const UNKNOWN_MODEL: &str = "unknown";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    #[default]
+    Markdown,
+    Html,
+}
+
+impl ReportFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ReportFormat::Markdown => "text/markdown; charset=utf-8",
+            ReportFormat::Html => "text/html; charset=utf-8",
+        }
+    }
+
+    /// The format of a report file by its extension: HTML for `.html` and `.htm`, else Markdown.
+    pub fn of(path: &std::path::Path) -> ReportFormat {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("html") | Some("htm") => ReportFormat::Html,
+            _ => ReportFormat::Markdown,
+        }
+    }
+}
+
+/// One row of the summary table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelSummary {
+    pub model: String,
+    pub evaluations: usize,
+    pub passed: usize,
+    pub pass_rate: f64,
+    /// `None` when no evaluation of the model has a latency.
+    pub avg_latency_ms: Option<f64>,
+    /// Model and judge cost of the priced evaluations; `None` when none was priced.
+    pub cost_usd: Option<f64>,
+}
+
+/// Evaluations per model, in the order each model first appears.
+pub fn summarize(entries: &[HistoryEntry]) -> Vec<ModelSummary> {
+    let mut models: Vec<(&str, Vec<&HistoryEntry>)> = Vec::new();
+    for entry in entries {
+        let model = entry.model.as_deref().unwrap_or(UNKNOWN_MODEL);
+        match models.iter_mut().find(|(m, _)| *m == model) {
+            Some((_, group)) => group.push(entry),
+            None => models.push((model, vec![entry])),
+        }
+    }
+
+    models
+        .into_iter()
+        .map(|(model, group)| {
+            let passed = group.iter().filter(|e| e.status.as_deref() == Some("passed")).count();
+            let latencies: Vec<i64> = group.iter().filter_map(|e| e.latency_ms).collect();
+            let costs: Vec<f64> = group
+                .iter()
+                .filter(|e| e.cost_usd.is_some() || e.judge_cost_usd.is_some())
+                .map(|e| e.cost_usd.unwrap_or(0.0) + e.judge_cost_usd.unwrap_or(0.0))
+                .collect();
+            ModelSummary {
+                model: model.to_string(),
+                evaluations: group.len(),
+                passed,
+                pass_rate: passed as f64 / group.len() as f64,
+                avg_latency_ms: (!latencies.is_empty()).then(|| latencies.iter().sum::<i64>() as f64 / latencies.len() as f64),
+                cost_usd: (!costs.is_empty()).then(|| costs.iter().sum()),
+            }
+        })
+        .collect()
+}
+
+/// Whether the report gives `entry` a section of its own.
+fn is_failure(entry: &HistoryEntry) -> bool {
+    entry.status.as_deref().is_some_and(|status| FAILED_STATUSES.contains(&status))
+}
+
+/// The report of `batch` and its evaluations, in creation order, in `format`.
+pub fn render(batch: &BatchSummary, entries: &[HistoryEntry], format: ReportFormat) -> String {
+    let summaries = summarize(entries);
+    let failures: Vec<&HistoryEntry> = entries.iter().filter(|e| is_failure(e)).collect();
+    match format {
+        ReportFormat::Markdown => markdown(batch, &summaries, &failures),
+        ReportFormat::Html => html(batch, &summaries, &failures),
+    }
+}
+
+fn percent(rate: f64) -> String {
+    format!("{:.1}%", rate * 100.0)
+}
+
+fn latency(ms: Option<f64>) -> String {
+    ms.map_or_else(|| "-".to_string(), |ms| format!("{:.0} ms", ms))
+}
+
+fn cost(usd: Option<f64>) -> String {
+    usd.map_or_else(|| "-".to_string(), |usd| format!("${:.4}", usd))
+}
+
+/// The verdict line of a failure: the judge's verdict, and the human's when one was recorded.
+fn verdict(entry: &HistoryEntry) -> String {
+    let judge = entry.judge_verdict.as_deref().unwrap_or("none");
+    match entry.human_verdict.as_deref() {
+        Some(human) => format!("{} (human review: {})", judge, human),
+        None => judge.to_string(),
+    }
+}
+
+/// The labelled texts shown for a failure, skipping the ones it does not have.
+fn failure_fields(entry: &HistoryEntry) -> Vec<(&'static str, &str)> {
+    [
+        ("Prompt", entry.prompt.as_deref()),
+        ("Expected", entry.expected.as_deref()),
+        ("Actual output", entry.model_output.as_deref()),
+        ("Judge reasoning", entry.judge_reasoning.as_deref()),
+        ("Error", entry.error_message.as_deref()),
+    ]
+    .into_iter()
+    .filter_map(|(label, text)| text.map(|text| (label, text)))
+    .collect()
+}
+
+// =======================================================
+// Markdown
+// =======================================================
+
+/// `text` for a table cell or heading: Markdown punctuation escaped, on one line.
+fn escape_markdown_inline(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '|' | '#' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\r' => {}
+            '\n' => escaped.push(' '),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// `text` as a fenced block whose fence is longer than any backtick run inside it, so the
+/// text is shown verbatim.
+fn markdown_block(text: &str) -> String {
+    let longest_run = text
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+    format!("{}text\n{}\n{}\n", fence, text.trim_end_matches('\n'), fence)
+}
+
+fn markdown(batch: &BatchSummary, summaries: &[ModelSummary], failures: &[&HistoryEntry]) -> String {
+    let mut out = format!("# Batch {}\n\n", escape_markdown_inline(&batch.batch_id));
+    out.push_str(&format!(
+        "Created {}, {}: {} evaluations, {} passed, {} failed or errored.\n\n",
+        escape_markdown_inline(&batch.created_at),
+        escape_markdown_inline(&batch.status),
+        batch.total,
+        batch.passed,
+        batch.failed
+    ));
+
+    out.push_str("## Summary\n\n");
+    out.push_str("| Model | Evaluations | Passed | Pass rate | Avg latency | Cost |\n");
+    out.push_str("|-------|------------:|-------:|----------:|------------:|-----:|\n");
+    for summary in summaries {
+        out.push_str(&format!(
+            "| {} | {} | {} | {} | {} | {} |\n",
+            escape_markdown_inline(&summary.model),
+            summary.evaluations,
+            summary.passed,
+            percent(summary.pass_rate),
+            latency(summary.avg_latency_ms),
+            cost(summary.cost_usd)
+        ));
+    }
+
+    out.push_str(&format!("\n## Failures ({})\n", failures.len()));
+    if failures.is_empty() {
+        out.push_str("\nNo evaluation failed.\n");
+    }
+    for entry in failures {
+        out.push_str(&format!(
+            "\n### {}\n\n- Model: {}\n- Status: {}\n- Verdict: {}\n",
+            escape_markdown_inline(&entry.id),
+            escape_markdown_inline(entry.model.as_deref().unwrap_or(UNKNOWN_MODEL)),
+            escape_markdown_inline(entry.status.as_deref().unwrap_or_default()),
+            escape_markdown_inline(&verdict(entry))
+        ));
+        for (label, text) in failure_fields(entry) {
+            out.push_str(&format!("\n**{}**\n\n{}", label, markdown_block(text)));
+        }
+    }
+    out
+}
+
+// =======================================================
+// HTML
+// =======================================================
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn html(batch: &BatchSummary, summaries: &[ModelSummary], failures: &[&HistoryEntry]) -> String {
+    let batch_id = escape_html(&batch.batch_id);
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Batch {}</title>\n</head>\n<body>\n<h1>Batch {}</h1>\n",
+        batch_id, batch_id
+    );
+    out.push_str(&format!(
+        "<p>Created {}, {}: {} evaluations, {} passed, {} failed or errored.</p>\n",
+        escape_html(&batch.created_at),
+        escape_html(&batch.status),
+        batch.total,
+        batch.passed,
+        batch.failed
+    ));
+
+    out.push_str("<h2>Summary</h2>\n<table>\n");
+    out.push_str("<tr><th>Model</th><th>Evaluations</th><th>Passed</th><th>Pass rate</th><th>Avg latency</th><th>Cost</th></tr>\n");
+    for summary in summaries {
+        out.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            escape_html(&summary.model),
+            summary.evaluations,
+            summary.passed,
+            percent(summary.pass_rate),
+            latency(summary.avg_latency_ms),
+            cost(summary.cost_usd)
+        ));
+    }
+    out.push_str("</table>\n");
+
+    out.push_str(&format!("<h2>Failures ({})</h2>\n", failures.len()));
+    if failures.is_empty() {
+        out.push_str("<p>No evaluation failed.</p>\n");
+    }
+    for entry in failures {
+        out.push_str(&format!(
+            "<section>\n<h3>{}</h3>\n<ul>\n<li>Model: {}</li>\n<li>Status: {}</li>\n<li>Verdict: {}</li>\n</ul>\n",
+            escape_html(&entry.id),
+            escape_html(entry.model.as_deref().unwrap_or(UNKNOWN_MODEL)),
+            escape_html(entry.status.as_deref().unwrap_or_default()),
+            escape_html(&verdict(entry))
+        ));
+        for (label, text) in failure_fields(entry) {
+            out.push_str(&format!("<h4>{}</h4>\n<pre>{}</pre>\n", label, escape_html(text)));
+        }
+        out.push_str("</section>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A batch of two models with a pass, a failure whose texts need escaping, and an error.
+    fn batch() -> (BatchSummary, Vec<HistoryEntry>) {
+        let summary = BatchSummary {
+            batch_id: "batch-1".to_string(),
+            created_at: "2026-10-17T09:00:00Z".to_string(),
+            total: 3,
+            passed: 1,
+            failed: 2,
+            status: "completed".to_string(),
+        };
+        let entry = |id: &str, model: Option<&str>, status: &str| HistoryEntry {
+            id: id.to_string(),
+            model: model.map(str::to_string),
+            status: Some(status.to_string()),
+            created_at: "2026-10-17T09:00:00Z".to_string(),
+            ..Default::default()
+        };
+        let passed = HistoryEntry {
+            prompt: Some("What is 2+2?".to_string()),
+            expected: Some("4".to_string()),
+            model_output: Some("4".to_string()),
+            judge_verdict: Some("Pass".to_string()),
+            latency_ms: Some(100),
+            cost_usd: Some(0.001),
+            judge_cost_usd: Some(0.0005),
+            ..entry("eval-1", Some("openai:gpt-4o"), "passed")
+        };
+        let failed = HistoryEntry {
+            prompt: Some("Render <b>bold</b> & a | pipe".to_string()),
+            expected: Some("```rust\nfn main() {}\n```".to_string()),
+            model_output: Some("<script>alert('x')</script>\n**not bold**".to_string()),
+            judge_verdict: Some("Fail".to_string()),
+            judge_reasoning: Some("Output is \"unsafe\" HTML, not the code block".to_string()),
+            latency_ms: Some(300),
+            ..entry("eval-2", Some("openai:gpt-4o"), "failed")
+        };
+        let errored = HistoryEntry {
+            error_message: Some("Provider returned 500: [internal]".to_string()),
+            ..entry("eval-3", None, "error")
+        };
+        (summary, vec![passed, failed, errored])
+    }
+
+    /// Compares `rendered` with the golden file `name`, or rewrites it when `UPDATE_GOLDEN` is set.
+    fn assert_golden(name: &str, rendered: &str) {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/reports").join(name);
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            std::fs::write(&path, rendered).unwrap();
+        }
+        let golden = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        assert_eq!(rendered, golden, "{} differs; rerun with UPDATE_GOLDEN=1 to accept the change", name);
+    }
+
+    #[test]
+    fn test_summary_groups_by_model_and_prices_only_priced_evaluations() {
+        let (_, entries) = batch();
+        let summaries = summarize(&entries);
+        assert_eq!(summaries.len(), 2);
+        assert_eq!((summaries[0].model.as_str(), summaries[0].evaluations, summaries[0].passed), ("openai:gpt-4o", 2, 1));
+        assert_eq!((summaries[0].pass_rate, summaries[0].avg_latency_ms), (0.5, Some(200.0)));
+        assert!((summaries[0].cost_usd.unwrap() - 0.0015).abs() < 1e-12);
+        assert_eq!((summaries[1].model.as_str(), summaries[1].avg_latency_ms, summaries[1].cost_usd), (UNKNOWN_MODEL, None, None));
+        assert_eq!(escape_markdown_inline("a|b\n*c*"), "a\\|b \\*c\\*");
+    }
+
+    #[test]
+    fn test_markdown_report_matches_golden_file() {
+        let (summary, entries) = batch();
+        assert_golden("batch.md", &render(&summary, &entries, ReportFormat::Markdown));
+    }
+
+    #[test]
+    fn test_html_report_matches_golden_file() {
+        let (summary, entries) = batch();
+        let rendered = render(&summary, &entries, ReportFormat::Html);
+        assert!(!rendered.contains("<script>"));
+        assert_golden("batch.html", &rendered);
+    }
+}
@@ -0,0 +1,306 @@
+// src/retention.rs
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+use crate::database::{self, DbPool};
+
+/// Evaluations purged per transaction, so writers are never locked out for long.
+const PURGE_CHUNK: i64 = 500;
+
+/// How long a run deferred by an active batch waits before trying again.
+const BATCH_RETRY: Duration = Duration::from_secs(60);
+
+/// Which evaluations the retention task purges, and how often it looks.
+#[derive(Debug, Clone)]
+pub struct RetentionPolicy {
+    /// Evaluations older than this many days are purged.
+    pub days: u32,
+    /// Time between runs.
+    pub interval: Duration,
+    /// Directory purged evaluations are appended to as JSONL first; they are deleted outright when unset.
+    pub archive_dir: Option<PathBuf>,
+    /// Least time between two `VACUUM`s, which rewrite the whole SQLite file.
+    pub vacuum_interval: Duration,
+}
+
+/// What the retention task has done so far, reported by `GET /api/v1/admin/retention`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RetentionStatus {
+    pub enabled: bool,
+    pub retention_days: Option<u32>,
+    pub interval_minutes: Option<u64>,
+    pub archive_dir: Option<String>,
+    pub last_run_at: Option<String>,
+    /// Evaluations created before this were purged by the last run.
+    pub last_cutoff: Option<String>,
+    pub last_purged: u64,
+    /// JSONL file the last run archived to, when it purged anything.
+    pub last_archive: Option<String>,
+    pub total_purged: u64,
+    pub last_vacuum_at: Option<String>,
+    /// When a run last waited for an active batch to finish.
+    pub last_deferred_at: Option<String>,
+    pub last_error: Option<String>,
+}
+
+impl RetentionStatus {
+    pub fn new(policy: Option<&RetentionPolicy>) -> Self {
+        match policy {
+            Some(policy) => RetentionStatus {
+                enabled: true,
+                retention_days: Some(policy.days),
+                interval_minutes: Some(policy.interval.as_secs() / 60),
+                archive_dir: policy.archive_dir.as_ref().map(|dir| dir.to_string_lossy().to_string()),
+                ..Default::default()
+            },
+            None => RetentionStatus::default(),
+        }
+    }
+}
+
+/// Result of one retention run.
+#[derive(Debug)]
+pub struct RetentionRun {
+    pub cutoff: String,
+    pub purged: u64,
+    pub archive: Option<PathBuf>,
+    /// Set when a batch started or the server began shutting down before the run finished.
+    pub interrupted: bool,
+}
+
+/// The running retention task. Call `shutdown` to stop it between chunks.
+pub struct RetentionTask {
+    shutdown: watch::Sender<bool>,
+    handle: JoinHandle<()>,
+}
+
+impl RetentionTask {
+    /// Starts purging on `policy`'s schedule, the first run right away. Runs wait while
+    /// `active_batches` is non-zero, so a batch never loses rows it is reading or judging.
+    pub fn spawn(
+        pool: Arc<DbPool>,
+        policy: RetentionPolicy,
+        active_batches: Arc<AtomicUsize>,
+        status: Arc<Mutex<RetentionStatus>>,
+    ) -> Self {
+        let (shutdown, mut stop) = watch::channel(false);
+        let handle = tokio::spawn(async move {
+            let mut last_vacuum: Option<std::time::Instant> = None;
+            loop {
+                let wait = if active_batches.load(Ordering::SeqCst) > 0 {
+                    tracing::info!("🗄️  Retention run deferred: a batch is running");
+                    lock(&status).last_deferred_at = Some(chrono::Utc::now().to_rfc3339());
+                    BATCH_RETRY
+                } else {
+                    let outcome = purge_expired(&pool, &policy, &active_batches, &stop).await;
+                    let purged = matches!(&outcome, Ok(run) if run.purged > 0);
+                    record(&status, outcome);
+
+                    let vacuum_due = last_vacuum.is_none_or(|at| at.elapsed() >= policy.vacuum_interval);
+                    if purged && vacuum_due && !*stop.borrow() && active_batches.load(Ordering::SeqCst) == 0 {
+                        match database::vacuum(&pool).await {
+                            Ok(()) => {
+                                last_vacuum = Some(std::time::Instant::now());
+                                lock(&status).last_vacuum_at = Some(chrono::Utc::now().to_rfc3339());
+                            }
+                            Err(e) => tracing::error!("Retention VACUUM failed: {}", e),
+                        }
+                    }
+                    policy.interval
+                };
+
+                tokio::select! {
+                    _ = tokio::time::sleep(wait) => {}
+                    _ = stop.changed() => break,
+                }
+            }
+            tracing::info!("🗄️  Retention task stopped");
+        });
+        RetentionTask { shutdown, handle }
+    }
+
+    /// Stops the task, letting the chunk being purged commit first.
+    pub async fn shutdown(self) {
+        let _ = self.shutdown.send(true);
+        if let Err(e) = self.handle.await {
+            tracing::error!("Retention task panicked: {}", e);
+        }
+    }
+}
+
+fn lock(status: &Mutex<RetentionStatus>) -> std::sync::MutexGuard<'_, RetentionStatus> {
+    status.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn record(status: &Mutex<RetentionStatus>, outcome: Result<RetentionRun, Box<dyn std::error::Error + Send + Sync>>) {
+    let mut status = lock(status);
+    status.last_run_at = Some(chrono::Utc::now().to_rfc3339());
+    match outcome {
+        Ok(run) => {
+            if run.purged > 0 {
+                tracing::info!("🗄️  Retention purged {} evaluation(s) created before {}", run.purged, run.cutoff);
+            }
+            status.last_cutoff = Some(run.cutoff);
+            status.last_purged = run.purged;
+            status.total_purged += run.purged;
+            status.last_archive = run.archive.map(|path| path.to_string_lossy().to_string());
+            status.last_error = None;
+        }
+        Err(e) => {
+            tracing::error!("Retention run failed: {}", e);
+            status.last_error = Some(e.to_string());
+        }
+    }
+}
+
+/// Purges evaluations older than `policy.days`, oldest first and a chunk at a time,
+/// archiving each chunk before deleting it, then raw exchanges captured before the cutoff.
+/// Stops early when a batch starts or `stop` is set.
+pub async fn purge_expired(
+    pool: &DbPool,
+    policy: &RetentionPolicy,
+    active_batches: &AtomicUsize,
+    stop: &watch::Receiver<bool>,
+) -> Result<RetentionRun, Box<dyn std::error::Error + Send + Sync>> {
+    let now = chrono::Utc::now();
+    let cutoff = (now - chrono::Duration::days(i64::from(policy.days))).to_rfc3339();
+    let archive = policy
+        .archive_dir
+        .as_ref()
+        .map(|dir| dir.join(format!("evaluations-{}.jsonl", now.format("%Y%m%dT%H%M%S%.3fZ"))));
+
+    let mut run = RetentionRun { cutoff, purged: 0, archive: None, interrupted: false };
+    loop {
+        if *stop.borrow() || active_batches.load(Ordering::SeqCst) > 0 {
+            run.interrupted = true;
+            break;
+        }
+        let expired = database::get_evaluations_before(pool, &run.cutoff, PURGE_CHUNK).await?;
+        if expired.is_empty() {
+            break;
+        }
+        if let Some(path) = &archive {
+            append_archive(path, &expired)?;
+            run.archive = Some(path.clone());
+        }
+        let ids: Vec<String> = expired.into_iter().map(|entry| entry.id).collect();
+        run.purged += database::purge_evaluations(pool, &ids).await?;
+    }
+    if !run.interrupted {
+        // Exchanges of evals that were never saved have no evaluation to go with
+        database::purge_raw_exchanges_before(pool, &run.cutoff).await?;
+    }
+    Ok(run)
+}
+
+/// Appends evaluations to a JSONL archive, one per line, creating its directory if needed.
+fn append_archive(path: &Path, entries: &[database::HistoryEntry]) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut file = std::io::BufWriter::new(std::fs::OpenOptions::new().create(true).append(true).open(path)?);
+    for entry in entries {
+        serde_json::to_writer(&mut file, entry)?;
+        file.write_all(b"\n")?;
+    }
+    file.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ApiResponse, EvalResult};
+
+    async fn save(pool: &DbPool, id: &str, created_at: &str) {
+        let result: crate::runner::EvalResult = serde_json::from_value(serde_json::json!({
+            "model": "ollama:llama3",
+            "prompt": "2+2?",
+            "model_output": "4",
+            "expected": null,
+            "judge_result": null,
+            "timestamp": created_at,
+            "latency_ms": 1,
+            "judge_latency_ms": null,
+            "total_latency_ms": 1,
+        }))
+        .unwrap();
+        let response = ApiResponse {
+            id: id.to_string(),
+            status: "completed".to_string(),
+            result: EvalResult::Success(result),
+            batch_id: None,
+            config_hash: None,
+            preset: None,
+        };
+        database::save_evaluation(pool, &response).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_purges_and_archives_only_expired_evaluations() {
+        let pool = database::test_pool().await;
+        let old = (chrono::Utc::now() - chrono::Duration::days(40)).to_rfc3339();
+        let recent = chrono::Utc::now().to_rfc3339();
+        save(&pool, "old-1", &old).await;
+        save(&pool, "old-2", &old).await;
+        save(&pool, "recent", &recent).await;
+        database::review_evaluation(&pool, "old-1", "Pass", None).await.unwrap();
+        // Rows belonging to a purged evaluation go with it
+        database::create_share(&pool, "old-1", "ci", chrono::Utc::now() + chrono::Duration::days(1)).await.unwrap();
+        let exchange = |captured_at: &str| crate::providers::capture::RawExchange {
+            provider: "ollama".to_string(),
+            model: "llama3".to_string(),
+            method: "POST".to_string(),
+            url: "http://localhost:11434/api/generate".to_string(),
+            request_body: None,
+            request_truncated: false,
+            status: 200,
+            response_headers: Default::default(),
+            response_body: "{}".to_string(),
+            response_truncated: false,
+            captured_at: captured_at.to_string(),
+        };
+        database::save_raw_exchanges(&pool, "old-1", &[exchange(&recent)]).await.unwrap();
+        database::save_raw_exchanges(&pool, "never-saved", &[exchange(&old)]).await.unwrap();
+        database::save_raw_exchanges(&pool, "still-running", &[exchange(&recent)]).await.unwrap();
+
+        let dir = std::env::temp_dir().join(format!("evaluate-retention-test-{}", uuid::Uuid::new_v4()));
+        let policy = RetentionPolicy {
+            days: 30,
+            interval: Duration::from_secs(3600),
+            archive_dir: Some(dir.clone()),
+            vacuum_interval: Duration::from_secs(3600),
+        };
+        let (_shutdown, stop) = watch::channel(false);
+
+        // An active batch defers the purge
+        let busy = purge_expired(&pool, &policy, &AtomicUsize::new(1), &stop).await.unwrap();
+        assert!(busy.interrupted);
+        assert_eq!(busy.purged, 0);
+
+        let run = purge_expired(&pool, &policy, &AtomicUsize::new(0), &stop).await.unwrap();
+        assert_eq!(run.purged, 2);
+        assert!(!run.interrupted);
+        assert!(database::get_evaluation(&pool, "old-1").await.is_err());
+        assert!(database::get_evaluation(&pool, "recent").await.is_ok());
+        assert!(database::get_raw_exchanges(&pool, "old-1").await.unwrap().is_empty());
+        assert!(database::get_raw_exchanges(&pool, "never-saved").await.unwrap().is_empty());
+        assert_eq!(database::get_raw_exchanges(&pool, "still-running").await.unwrap().len(), 1);
+
+        let archived = std::fs::read_to_string(run.archive.unwrap()).unwrap();
+        let ids: Vec<String> = archived
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["id"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains(&"old-1".to_string()) && ids.contains(&"old-2".to_string()));
+
+        database::vacuum(&pool).await.unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
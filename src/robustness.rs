@@ -0,0 +1,423 @@
+// src/robustness.rs
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use crate::database::DbPool;
+
+use crate::config::{AppConfig, EvalConfig};
+use crate::errors::{EvalError, Result};
+use crate::format_rule::FormatRule;
+use crate::providers::{Generation, TokenUsage};
+use crate::runner;
+
+/// Metadata key under which each generated eval records its place in the robustness group.
+pub const ROBUSTNESS_METADATA_KEY: &str = "robustness";
+
+/// Most phrasings one robustness run covers besides the base prompt.
+pub const MAX_VARIANTS: usize = 20;
+const DEFAULT_PARAPHRASES: usize = 5;
+
+/// Sent to the paraphrase model; `{{count}}` and `{{prompt}}` are filled in.
+const PARAPHRASE_PROMPT: &str = r#"Rewrite the prompt below in {{count}} different ways. Every rewrite must ask for exactly the same thing and keep all facts, numbers, names and {{placeholders}} unchanged; only the wording may change.
+
+Respond with ONLY a JSON array of {{count}} strings.
+
+Prompt:
+{{prompt}}"#;
+
+/// Runs one model against a base prompt and rephrasings of it, graded against the same expected.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RobustnessRequest {
+    pub model: String,
+    pub prompt: String,
+    pub expected: Option<String>,
+    pub judge_model: Option<String>,
+    pub criteria: Option<String>,
+    /// Phrasings to run instead of generated paraphrases
+    pub variants: Option<Vec<String>>,
+    /// Number of paraphrases to generate when no `variants` are given (default 5)
+    pub paraphrases: Option<usize>,
+    /// Model that writes the paraphrases; defaults to `PARAPHRASE_MODEL`, then to `judge_model`
+    pub paraphrase_model: Option<String>,
+    pub format_rule: Option<FormatRule>,
+    pub min_judge_confidence: Option<f32>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl RobustnessRequest {
+    /// Checks the request can be graded and that its variant count is in range.
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        if self.prompt.trim().is_empty() {
+            return Err("prompt must not be empty".to_string());
+        }
+        let judged = self.expected.is_some() && self.judge_model.is_some();
+        if !judged && self.format_rule.is_none() {
+            return Err("Give expected and judge_model, or a format_rule, so every phrasing can be graded".to_string());
+        }
+        let count = match &self.variants {
+            Some(variants) => variants.len(),
+            None => self.paraphrase_count(),
+        };
+        if !(1..=MAX_VARIANTS).contains(&count) {
+            return Err(format!("Between 1 and {} variants are allowed besides the base prompt", MAX_VARIANTS));
+        }
+        Ok(())
+    }
+
+    pub fn paraphrase_count(&self) -> usize {
+        self.paraphrases.unwrap_or(DEFAULT_PARAPHRASES)
+    }
+
+    /// The model that writes paraphrases: the request's, the configured default, then the judge.
+    pub fn resolve_paraphrase_model(&self, config: &AppConfig) -> Option<String> {
+        self.paraphrase_model
+            .clone()
+            .or_else(|| config.paraphrase_model.clone())
+            .or_else(|| self.judge_model.clone())
+    }
+}
+
+/// Where a phrasing came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VariantSource {
+    Base,
+    Paraphrase,
+    User,
+}
+
+impl VariantSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VariantSource::Base => "base",
+            VariantSource::Paraphrase => "paraphrase",
+            VariantSource::User => "user",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "base" => Some(VariantSource::Base),
+            "paraphrase" => Some(VariantSource::Paraphrase),
+            "user" => Some(VariantSource::User),
+            _ => None,
+        }
+    }
+}
+
+/// One phrasing of the prompt; index 0 is always the base prompt.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Variant {
+    pub index: usize,
+    pub prompt: String,
+    pub source: VariantSource,
+}
+
+/// Puts the base prompt first, then `phrasings` with blanks, repeats and copies of the base dropped.
+pub fn variants(base: &str, phrasings: Vec<String>, source: VariantSource) -> Vec<Variant> {
+    let mut variants = vec![Variant { index: 0, prompt: base.to_string(), source: VariantSource::Base }];
+    for phrasing in phrasings {
+        let prompt = phrasing.trim();
+        if prompt.is_empty() || variants.iter().any(|v| v.prompt.trim() == prompt) {
+            continue;
+        }
+        variants.push(Variant { index: variants.len(), prompt: prompt.to_string(), source });
+    }
+    variants
+}
+
+/// Reads paraphrases from the paraphrase model's response: a JSON array of strings, or failing
+/// that one paraphrase per line with list markers stripped.
+pub fn parse_paraphrases(response: &str) -> Vec<String> {
+    if let (Some(start), Some(end)) = (response.find('['), response.rfind(']'))
+        && start < end
+        && let Ok(items) = serde_json::from_str::<Vec<String>>(&response[start..=end])
+    {
+        return items;
+    }
+
+    response
+        .lines()
+        .map(|line| {
+            let line = line.trim().trim_start_matches(['-', '*', '•']).trim_start();
+            let unnumbered = line.trim_start_matches(|c: char| c.is_ascii_digit());
+            let line = match unnumbered.strip_prefix(['.', ')']) {
+                Some(rest) if unnumbered.len() < line.len() => rest,
+                _ => line,
+            };
+            line.trim().trim_matches('"').to_string()
+        })
+        .filter(|line| !line.is_empty() && !line.ends_with(':') && !line.starts_with("```"))
+        .collect()
+}
+
+/// Paraphrases returned by the paraphrase model, with what the call cost.
+#[derive(Debug, Clone, Serialize)]
+pub struct Paraphrasing {
+    pub model: String,
+    pub paraphrases: Vec<String>,
+    pub latency_ms: u64,
+    pub token_usage: TokenUsage,
+    /// `None` when the paraphrase model is unpriced or usage unknown.
+    pub cost_usd: Option<f64>,
+}
+
+/// Asks `model` for `count` paraphrases of `prompt`.
+pub async fn paraphrase(
+    config: &AppConfig,
+    client: &reqwest::Client,
+    model: &str,
+    prompt: &str,
+    count: usize,
+    db_pool: Option<&DbPool>,
+) -> Result<Paraphrasing> {
+    let (provider_name, model_name) = config.parse_model(model);
+    let request = PARAPHRASE_PROMPT
+        .replace("{{count}}", &count.to_string())
+        .replace("{{prompt}}", prompt);
+    let Generation { output, latency_ms, token_usage, .. } =
+        runner::call_provider(config, client, &provider_name, &model_name, &request, db_pool).await?;
+
+    let mut paraphrases = parse_paraphrases(&output);
+    paraphrases.truncate(count);
+    if paraphrases.is_empty() {
+        return Err(EvalError::UnexpectedResponse(format!("{} returned no paraphrases", model)));
+    }
+
+    Ok(Paraphrasing {
+        cost_usd: config.pricing.cost(&format!("{}:{}", provider_name, model_name), &token_usage),
+        model: model.to_string(),
+        paraphrases,
+        latency_ms,
+        token_usage,
+    })
+}
+
+/// One eval per variant, in variant order. Each eval's metadata gains a `robustness` object with
+/// the `group_id`, its `variant_index` and its `source`.
+pub fn expand(req: &RobustnessRequest, group_id: &str, variants: &[Variant]) -> Vec<EvalConfig> {
+    variants
+        .iter()
+        .map(|variant| EvalConfig {
+            model: req.model.clone(),
+            prompt: variant.prompt.clone(),
+            prompt_version: None,
+            expected: req.expected.clone(),
+            judge_model: req.judge_model.clone(),
+            criteria: req.criteria.clone(),
+            tags: req.tags.clone(),
+            metadata: Some(json!({
+                ROBUSTNESS_METADATA_KEY: {
+                    "group_id": group_id,
+                    "variant_index": variant.index,
+                    "source": variant.source,
+                }
+            })),
+            format_rule: req.format_rule.clone(),
+            stream: false,
+            min_judge_confidence: req.min_judge_confidence,
+            truncation: None,
+            truncate_field: None,
+            context_window: None,
+            on_length_limit: None,
+            trace: false,
+            skip_failure_classification: false,
+            capture_raw: false,
+            require_review: false,
+            cache: false,
+            cache_judge: false,
+            safety_threshold: None,
+            response_format: None,
+            stop: None,
+            max_output_tokens: None,
+            thinking: None,
+            images: Vec::new(),
+            include_reasoning: false,
+            allow_missing_vars: false,
+            judge_params: None,
+            match_rule: None,
+            diff_rule: None,
+            self_consistency: None,
+            preset: None,
+            dataset_row: None,
+            chain_step: None,
+            provider_overrides: Default::default(),
+            overrides_supplied_by: None,
+        })
+        .collect()
+}
+
+/// How one phrasing fared.
+#[derive(Debug, Clone, Serialize)]
+pub struct VariantOutcome {
+    pub variant_index: usize,
+    pub prompt: String,
+    pub source: VariantSource,
+    pub eval_id: String,
+    pub status: String,
+    /// The human verdict when reviewed, otherwise the judge verdict.
+    pub verdict: Option<String>,
+    /// Whether the variant passed when the base prompt did not, or the other way round;
+    /// `None` for the base prompt and when either eval errored.
+    pub flips_verdict: Option<bool>,
+}
+
+impl VariantOutcome {
+    fn passed(&self) -> bool {
+        self.status == "passed"
+    }
+
+    fn errored(&self) -> bool {
+        matches!(self.status.as_str(), "error" | "blocked")
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RobustnessReport {
+    pub group_id: String,
+    pub variant_count: usize,
+    pub passing: usize,
+    /// `passing / variant_count`, base prompt included; errored evals count against it.
+    pub robustness_score: f64,
+    /// `None` when the base prompt's eval errored.
+    pub base_passed: Option<bool>,
+    /// Indexes of the variants whose outcome differs from the base prompt's.
+    pub flipped: Vec<usize>,
+    pub variants: Vec<VariantOutcome>,
+}
+
+/// Scores the outcomes, given in variant order with the base prompt first, and marks the
+/// variants that flip the base prompt's outcome.
+pub fn report(group_id: &str, mut outcomes: Vec<VariantOutcome>) -> RobustnessReport {
+    let base = outcomes
+        .iter()
+        .find(|o| o.source == VariantSource::Base)
+        .filter(|o| !o.errored())
+        .map(VariantOutcome::passed);
+    for outcome in &mut outcomes {
+        outcome.flips_verdict = match base {
+            Some(base_passed) if outcome.source != VariantSource::Base && !outcome.errored() => {
+                Some(outcome.passed() != base_passed)
+            }
+            _ => None,
+        };
+    }
+
+    let passing = outcomes.iter().filter(|o| o.passed()).count();
+    RobustnessReport {
+        group_id: group_id.to_string(),
+        variant_count: outcomes.len(),
+        passing,
+        robustness_score: if outcomes.is_empty() { 0.0 } else { passing as f64 / outcomes.len() as f64 },
+        base_passed: base,
+        flipped: outcomes.iter().filter(|o| o.flips_verdict == Some(true)).map(|o| o.variant_index).collect(),
+        variants: outcomes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value as JsonValue;
+
+    fn request(body: JsonValue) -> RobustnessRequest {
+        serde_json::from_value(body).unwrap()
+    }
+
+    fn outcome(index: usize, status: &str) -> VariantOutcome {
+        VariantOutcome {
+            variant_index: index,
+            prompt: format!("phrasing {}", index),
+            source: if index == 0 { VariantSource::Base } else { VariantSource::Paraphrase },
+            eval_id: uuid::Uuid::new_v4().to_string(),
+            status: status.to_string(),
+            verdict: None,
+            flips_verdict: None,
+        }
+    }
+
+    #[test]
+    fn test_validate_requires_a_way_to_grade() {
+        let base = json!({ "model": "ollama:llama3", "prompt": "What is 2+2?" });
+        assert!(request(base.clone()).validate().is_err());
+
+        let mut judged = base;
+        judged["expected"] = json!("4");
+        judged["judge_model"] = json!("ollama:llama3");
+        assert!(request(judged.clone()).validate().is_ok());
+
+        judged["variants"] = json!([]);
+        assert!(request(judged.clone()).validate().is_err());
+        judged["variants"] = JsonValue::Null;
+        judged["paraphrases"] = json!(MAX_VARIANTS + 1);
+        assert!(request(judged).validate().is_err());
+    }
+
+    #[test]
+    fn test_parse_paraphrases_reads_json_or_lines() {
+        let json_response = "Here you go:\n```json\n[\"What's 2 plus 2?\", \"Add 2 and 2.\"]\n```";
+        assert_eq!(parse_paraphrases(json_response), vec!["What's 2 plus 2?", "Add 2 and 2."]);
+
+        let listed = "Paraphrases:\n1. What's 2 plus 2?\n2) Add 2 and 2.\n- \"Sum two and two.\"\n";
+        assert_eq!(parse_paraphrases(listed), vec!["What's 2 plus 2?", "Add 2 and 2.", "Sum two and two."]);
+    }
+
+    #[test]
+    fn test_variants_put_base_first_and_drop_repeats() {
+        let phrasings = vec![
+            "What is 2+2?".to_string(),
+            " Add 2 and 2. ".to_string(),
+            "Add 2 and 2.".to_string(),
+            "".to_string(),
+        ];
+        let variants = variants("What is 2+2?", phrasings, VariantSource::User);
+        assert_eq!(variants.len(), 2);
+        assert_eq!(variants[0].source, VariantSource::Base);
+        assert_eq!(variants[1], Variant { index: 1, prompt: "Add 2 and 2.".to_string(), source: VariantSource::User });
+    }
+
+    #[test]
+    fn test_expand_tags_each_eval_with_its_variant() {
+        let req = request(json!({
+            "model": "ollama:llama3",
+            "prompt": "What is 2+2?",
+            "expected": "4",
+            "judge_model": "ollama:llama3"
+        }));
+        let variants = variants(&req.prompt, vec!["Add 2 and 2.".to_string()], VariantSource::Paraphrase);
+
+        let configs = expand(&req, "group-1", &variants);
+        assert_eq!(configs.len(), 2);
+        assert_eq!(configs[1].prompt, "Add 2 and 2.");
+        assert_eq!(configs[1].expected.as_deref(), Some("4"));
+        let metadata = &configs[1].metadata.as_ref().unwrap()[ROBUSTNESS_METADATA_KEY];
+        assert_eq!(metadata["group_id"], "group-1");
+        assert_eq!(metadata["variant_index"], 1);
+        assert_eq!(metadata["source"], "paraphrase");
+    }
+
+    #[test]
+    fn test_report_scores_and_flags_flipped_variants() {
+        let report = report("group-1", vec![
+            outcome(0, "passed"),
+            outcome(1, "passed"),
+            outcome(2, "failed"),
+            outcome(3, "error"),
+        ]);
+
+        assert_eq!((report.variant_count, report.passing), (4, 2));
+        assert!((report.robustness_score - 0.5).abs() < f64::EPSILON);
+        assert_eq!(report.base_passed, Some(true));
+        assert_eq!(report.flipped, vec![2]);
+        let flips: Vec<Option<bool>> = report.variants.iter().map(|v| v.flips_verdict).collect();
+        assert_eq!(flips, vec![None, Some(false), Some(true), None]);
+    }
+
+    #[test]
+    fn test_report_flags_nothing_when_base_errored() {
+        let report = report("group-1", vec![outcome(0, "error"), outcome(1, "passed")]);
+        assert_eq!(report.base_passed, None);
+        assert!(report.flipped.is_empty());
+        assert!((report.robustness_score - 0.5).abs() < f64::EPSILON);
+    }
+}
@@ -1,20 +1,56 @@
 // src/runner.rs
-use crate::config::{AppConfig, EvalConfig};
+use crate::budget::{Budget, BudgetTracker, BudgetUsage, BUDGETED_BATCH_CONCURRENCY};
+use crate::config::{AppConfig, EvalConfig, JudgeParams, LengthLimitPolicy, SafetyThreshold};
+use crate::database::{HistoryEntry, ProviderErrorLog};
 use crate::errors::{EvalError, Result};
-use crate::providers::{anthropic::AnthropicProvider, gemini::GeminiProvider, ollama::OllamaProvider, openai::OpenAIProvider, LlmProvider, TokenUsage};
-use futures::future;
+use crate::models::ApiErrorResponse;
+use crate::failure_taxonomy::{self, FailureCategory, FailureClassification, FailureClassifier};
+use crate::judge_batch::{self, JudgeTask};
+use crate::judge_prompt_cache::JudgePromptSnapshot;
+use crate::diff::{self, OutputDiff};
+use crate::match_rule;
+use crate::output_parser::{self, ParseMethod};
+use crate::provider_override::{self, OverrideUse};
+use crate::pricing::{BatchEstimate, CharsPerToken, PricingTable, TokenEstimator};
+use crate::reasoning::{self, ReasoningSplit};
+use crate::self_consistency::{self, SelfConsistencyReport};
+use crate::trace::{self, RetryLog, Span, TraceEvent, TracePhase};
+use crate::truncation::{self, TruncationRecord};
+use crate::providers::middleware::{RedactingProvider, RetryProvider};
+use crate::providers::capture;
+use crate::providers::images::{self, ImageRecord};
+use crate::providers::response_format::InstructedFormat;
+use crate::providers::{anthropic::AnthropicProvider, gemini::GeminiProvider, groq::GroqProvider, ollama::OllamaProvider, openai::OpenAIProvider, openrouter::OpenRouterProvider, AbortCheck, FinishReason, Generation, GenerationParams, LlmProvider, StreamedGeneration, TokenUsage};
+use futures::stream::{FuturesUnordered, StreamExt};
+use futures::{future, FutureExt};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::sync::Arc;
 use std::time::Instant;
 use regex::Regex;
-use sqlx::SqlitePool;
+use tracing::Instrument;
+use crate::chain::{self, ChainStepRef};
+use crate::database::DbPool;
+use crate::dataset::DatasetRowRef;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct EvalResult {
+    /// The id the eval was logged, broadcast and saved under.
+    #[serde(default)]
+    pub id: String,
     pub model: String,
     pub prompt: String,
     pub model_output: String,
     pub parsed_output: Option<JsonValue>,
+    /// Which extractor produced `parsed_output`.
+    #[serde(default)]
+    pub parse_method: Option<ParseMethod>,
+    /// Structural diff of `parsed_output` against `expected`, when `expected` is a JSON
+    /// object or array; an `OutputDiff`.
+    #[serde(default)]
+    pub output_diff: Option<JsonValue>,
     pub expected: Option<String>,
     pub judge_result: Option<JudgeResult>,
     pub timestamp: String,
@@ -22,8 +58,147 @@ pub struct EvalResult {
     pub judge_latency_ms: Option<u64>,
     pub token_usage: Option<TokenUsage>,
     pub judge_token_usage: Option<TokenUsage>,
+    /// USD cost of the model call, `None` when the model is unpriced or usage unknown.
+    #[serde(default)]
+    pub cost_usd: Option<f64>,
+    #[serde(default)]
+    pub judge_cost_usd: Option<f64>,
     pub total_latency_ms: u64,
+    /// Time the model call spent beyond `latency_ms`: waiting for a rate limit permit, on
+    /// failed attempts and backing off between retries. `None` for cached outputs.
+    #[serde(default)]
+    pub wait_ms: Option<u64>,
     pub judge_prompt_version: Option<i64>,  // NEW: Track which judge prompt was used
+    /// Prompt version whose template the prompt was rendered from; `None` for a literal prompt.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_version: Option<i64>,
+    /// The prompt before the metadata was filled in; `None` when there was no metadata.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_template: Option<String>,
+    /// Which side of a judge prompt canary the eval landed on; `None` when no canary was running.
+    #[serde(default)]
+    pub judge_prompt_arm: Option<JudgePromptArm>,
+    /// Number of evals judged together in one batched judge call; `None` when judged on its own.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub judge_batch_size: Option<usize>,
+    pub early_abort: Option<EarlyAbort>,
+    /// Why the model stopped generating; `None` when the provider did not say or the stream was aborted.
+    #[serde(default)]
+    pub finish_reason: Option<FinishReason>,
+    /// True when the judge's confidence fell below the threshold and a human must confirm the verdict.
+    #[serde(default)]
+    pub needs_review: bool,
+    /// Set when a metadata field was truncated to fit the model's context window.
+    #[serde(default)]
+    pub truncation: Option<TruncationRecord>,
+    /// Tags of the eval config, kept for filtering stats.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Criteria of the eval config, as rendered for the judge.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub criteria: Option<String>,
+    /// Metadata of the eval config.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<JsonValue>,
+    /// Timed phases of the eval, when the eval config asked for a trace.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace: Option<Vec<TraceEvent>>,
+    /// Failure category of a failed eval, when a failure classifier is configured.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure_classification: Option<FailureClassification>,
+    /// The dataset row the eval was expanded from, with the source of an adopted expected output.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dataset_row: Option<DatasetRowRef>,
+    /// The chain and step the eval ran as; see `run_chain`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_step: Option<ChainStepRef>,
+    /// The providers whose credentials the request overrode, and the API key that supplied them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider_override: Option<OverrideUse>,
+    /// Set on the saved copy of an eval whose output was cut to `MAX_STORED_OUTPUT_CHARS`:
+    /// the length of the whole output in characters.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub full_output_chars: Option<usize>,
+    /// True when the model output came from the response cache; `latency_ms` and
+    /// `token_usage` are then those of the call that was cached, and `cost_usd` is zero.
+    #[serde(default)]
+    pub cache_hit: bool,
+    /// The model that answered as reported by the provider, which routing providers such as
+    /// OpenRouter may pick differently from `model`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolved_model: Option<String>,
+    /// The alias the eval config named its model by, e.g. `fast`; `model` holds the
+    /// `provider:model` it resolved to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_alias: Option<String>,
+    /// The `response_format` the model was asked for: `json` or `json_schema`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<String>,
+    /// The model's chain of thought, split from its answer: returned apart by the provider or
+    /// found in `<think>` tags of the output. `model_output` holds only the answer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<String>,
+    /// The images sent with the prompt; inline images by hash and size, never their data.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<ImageRecord>,
+    /// The judge prompt as rendered and sent to the judge: template, criteria, expected and
+    /// actual output. `None` when no judge was called.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub judge_prompt_rendered: Option<String>,
+    /// Temperature, max tokens, seed and Uncertain retry the judge was called with. `None`
+    /// when no judge was called.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub judge_params: Option<JudgeParams>,
+    /// Why the judge call failed; the eval then has no `judge_result` and the status
+    /// `judge_error`. `None` when the judge answered or none was asked for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub judge_error: Option<JudgeError>,
+    /// The samples of a self-consistency eval and how their verdicts combined; the rest of
+    /// the result is that of one sample agreeing with the verdict, with summed tokens and costs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub self_consistency: Option<SelfConsistencyReport>,
+}
+
+/// A judge call that failed, kept with the eval so it is not mistaken for one never judged.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct JudgeError {
+    pub judge_model: String,
+    /// The `ApiErrorResponse` code of the failure, e.g. `rate_limited` or `provider_error`.
+    pub code: String,
+    pub message: String,
+    /// The HTTP status the judge's provider answered with, when it answered with an error.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+}
+
+impl JudgeError {
+    fn new(judge_model: &str, error: &EvalError) -> Self {
+        JudgeError {
+            judge_model: judge_model.to_string(),
+            code: error.code().0.to_string(),
+            message: error.to_string(),
+            status: match error {
+                EvalError::ApiError { status, .. } => Some(*status),
+                _ => None,
+            },
+        }
+    }
+
+    /// The error as `EvalError::JudgeFailure` would answer it.
+    pub fn to_response(&self) -> ApiErrorResponse {
+        ApiErrorResponse {
+            code: "judge_failure".to_string(),
+            message: format!("Judge model '{}' failed: {}", self.judge_model, self.message),
+            details: Some(serde_json::json!({ "model": self.judge_model, "cause": self.code })),
+        }
+    }
+}
+
+/// Recorded when a streamed generation was cut short by a fail-fast format rule.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EarlyAbort {
+    pub tokens_generated: u32,
+    pub reason: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -33,15 +208,30 @@ pub struct JudgeResult {
     #[serde(rename = "reasoning")]
     pub reasoning: Option<String>,
     pub confidence: Option<f32>,
+    /// The judge's full response text, as received.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_response: Option<String>,
+    /// Why the verdict is uncertain, or why it was routed to review.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uncertain_reason: Option<UncertainReason>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 pub enum JudgeVerdict {
     Pass,
     Fail,
     Uncertain,
 }
 
+impl JudgeVerdict {
+    pub const ALL: [JudgeVerdict; 3] = [JudgeVerdict::Pass, JudgeVerdict::Fail, JudgeVerdict::Uncertain];
+
+    /// The verdict stored as `s`, as `Display` writes it.
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|verdict| verdict.to_string() == s)
+    }
+}
+
 impl std::fmt::Display for JudgeVerdict {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -52,19 +242,164 @@ impl std::fmt::Display for JudgeVerdict {
     }
 }
 
-/// Parse judge response to extract verdict and reasoning
+/// Why a verdict is uncertain, so uncertain results can be traced back to the judge prompt,
+/// the response parser or the review threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UncertainReason {
+    /// The judge answered but declined to pick pass or fail.
+    JudgeAbstained,
+    /// No verdict could be found in the judge's response.
+    Unparseable,
+    /// The judge's confidence fell below the review threshold.
+    LowConfidence,
+    /// A human reviewer recorded the verdict as uncertain.
+    HumanReview,
+    /// The model's output was cut off at the output token limit.
+    LengthLimit,
+    /// The provider withheld or cut off the model's output for safety reasons.
+    ContentFilter,
+    /// The model declined to answer.
+    Refusal,
+}
+
+impl UncertainReason {
+    pub const ALL: [UncertainReason; 7] = [
+        UncertainReason::JudgeAbstained,
+        UncertainReason::Unparseable,
+        UncertainReason::LowConfidence,
+        UncertainReason::HumanReview,
+        UncertainReason::LengthLimit,
+        UncertainReason::ContentFilter,
+        UncertainReason::Refusal,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UncertainReason::JudgeAbstained => "judge_abstained",
+            UncertainReason::Unparseable => "unparseable",
+            UncertainReason::LowConfidence => "low_confidence",
+            UncertainReason::HumanReview => "human_review",
+            UncertainReason::LengthLimit => "length_limit",
+            UncertainReason::ContentFilter => "content_filter",
+            UncertainReason::Refusal => "refusal",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|reason| reason.as_str() == s)
+    }
+}
+
+/// Judge models recorded for verdicts that a rule decided without calling a judge.
+pub const RULE_JUDGES: [&str; 3] = ["format-rule", "finish-reason", diff::JSON_DIFF_JUDGE];
+
+/// Openings with which a judge answers both ways or neither, e.g. "Yes and no, ...".
+const AMBIGUOUS_OPENINGS: [&str; 5] = ["yes and no", "yes/no", "no and yes", "partially", "partly"];
+
+/// Lines at the start and at the end of a judge response searched for a verdict on its own.
+const VERDICT_SEARCH_LINES: usize = 3;
+
+/// Highest confidence kept for a verdict the rest of the judge response contradicts.
+const CONFLICTING_SIGNALS_CONFIDENCE: f32 = 0.5;
+
+/// Side of a judge prompt canary: the active prompt, or the candidate under test.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JudgePromptArm {
+    Control,
+    Canary,
+}
+
+impl JudgePromptArm {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JudgePromptArm::Control => "control",
+            JudgePromptArm::Canary => "canary",
+        }
+    }
+}
+
+/// Whether `eval_id` falls in the canary's `percentage` (0-100) of evaluations.
+/// Derived from a hash of the id, so the same eval always lands on the same arm.
+pub fn in_canary(eval_id: &str, percentage: f64) -> bool {
+    let digest = Sha256::digest(eval_id.as_bytes());
+    let bucket = u64::from_be_bytes(digest[..8].try_into().expect("SHA-256 digest is 32 bytes")) % 10_000;
+    (bucket as f64) < percentage * 100.0
+}
+
+/// The verdict a verdict keyword stands for, if `word` is one.
+fn verdict_keyword(word: &str) -> Option<JudgeVerdict> {
+    match word {
+        "pass" | "passed" | "passes" | "yes" | "correct" => Some(JudgeVerdict::Pass),
+        "fail" | "failed" | "fails" | "no" | "incorrect" => Some(JudgeVerdict::Fail),
+        "uncertain" | "unsure" | "unclear" | "undetermined" => Some(JudgeVerdict::Uncertain),
+        _ => None,
+    }
+}
+
+/// The verdicts named after "Verdict:" anywhere in a judge response, e.g. `**Verdict:** PASS`.
+fn labelled_verdicts(response: &str) -> Vec<JudgeVerdict> {
+    let Ok(re) = Regex::new(r"(?i)verdict\W{0,4}(?:is\W{1,4})?([a-z]+)\b") else {
+        return Vec::new();
+    };
+    re.captures_iter(response)
+        .filter_map(|caps| verdict_keyword(&caps[1].to_lowercase()))
+        .collect()
+}
+
+/// The verdict of a line that gives one on its own: a verdict keyword that is the whole
+/// line or is followed by punctuation, as in "PASS" or "No, the totals differ.". A line
+/// opening with e.g. "Yes and no" is Uncertain; "Note that ..." or "No difference ..."
+/// give none.
+fn standalone_verdict(line: &str) -> Option<JudgeVerdict> {
+    let line = line.trim_start_matches(|c: char| c.is_whitespace() || "*#>-_`".contains(c)).to_lowercase();
+    if AMBIGUOUS_OPENINGS.iter().any(|opening| line.starts_with(opening)) {
+        return Some(JudgeVerdict::Uncertain);
+    }
+    let word_end = line.find(|c: char| !c.is_alphabetic()).unwrap_or(line.len());
+    let verdict = verdict_keyword(&line[..word_end])?;
+    let rest = line[word_end..].trim_start_matches(['*', '_', '`']);
+    let ends_the_word = rest.trim().is_empty() || rest.starts_with(['.', ',', ':', ';', '!', '-', '–', '—', ')']);
+    ends_the_word.then_some(verdict)
+}
+
+/// Parses a judge response into a verdict. A verdict counts when it follows "Verdict:", or
+/// else when it stands on its own at the start of one of the first or last lines; words
+/// that merely start with a keyword, such as "Note" or "Yesterday", do not count. Responses
+/// naming different verdicts at the same level are Uncertain, and a verdict contradicted
+/// elsewhere in the response keeps at most `CONFLICTING_SIGNALS_CONFIDENCE`.
 fn parse_judge_response(response: &str) -> JudgeResult {
-    let response_lower = response.to_lowercase();
-    
-    let verdict = if response_lower.contains("verdict: pass") || 
-                     (response_lower.starts_with("yes") || response_lower.contains("yes, they")) {
-        JudgeVerdict::Pass
-    } else if response_lower.contains("verdict: fail") || 
-              (response_lower.starts_with("no") || response_lower.contains("no, they")) {
-        JudgeVerdict::Fail
+    let labelled = labelled_verdicts(response);
+    let lines: Vec<&str> = response.lines().filter(|line| !line.trim().is_empty()).collect();
+    let searched = if lines.len() > 2 * VERDICT_SEARCH_LINES {
+        [&lines[..VERDICT_SEARCH_LINES], &lines[lines.len() - VERDICT_SEARCH_LINES..]].concat()
     } else {
-        JudgeVerdict::Uncertain
+        lines
     };
+    let standalone: Vec<JudgeVerdict> = searched.iter().filter_map(|line| standalone_verdict(line)).collect();
+
+    let (signals, others) = if labelled.is_empty() { (&standalone, &labelled) } else { (&labelled, &standalone) };
+    let mut distinct: Vec<JudgeVerdict> = Vec::new();
+    for verdict in signals {
+        if !distinct.contains(verdict) {
+            distinct.push(*verdict);
+        }
+    }
+    let (verdict, uncertain_reason) = match distinct.as_slice() {
+        [] => (JudgeVerdict::Uncertain, Some(UncertainReason::Unparseable)),
+        [JudgeVerdict::Uncertain] => (JudgeVerdict::Uncertain, Some(UncertainReason::JudgeAbstained)),
+        [verdict] => (*verdict, None),
+        // Verdicts given with the same weight contradict each other
+        _ => (JudgeVerdict::Uncertain, Some(UncertainReason::Unparseable)),
+    };
+    let conflicting = distinct.len() > 1 || others.iter().any(|other| *other != verdict);
+
+    let mut confidence = extract_confidence(response);
+    if conflicting {
+        tracing::warn!("⚠️  Judge response gives conflicting verdicts; recording {} with lowered confidence", verdict);
+        confidence = Some(confidence.map_or(CONFLICTING_SIGNALS_CONFIDENCE, |c| c.min(CONFLICTING_SIGNALS_CONFIDENCE)));
+    }
 
     let reasoning = if response.len() > 20 {
         Some(response.to_string())
@@ -76,12 +411,65 @@ fn parse_judge_response(response: &str) -> JudgeResult {
         judge_model: "unknown".to_string(),
         verdict,
         reasoning,
-        confidence: None,
+        confidence,
+        raw_response: Some(response.to_string()),
+        uncertain_reason,
+    }
+}
+
+/// Extract a self-reported confidence such as "Confidence: 0.85" or "Confidence: 85%",
+/// normalized to the 0.0-1.0 range.
+fn extract_confidence(response: &str) -> Option<f32> {
+    let re = Regex::new(r"(?i)confidence\s*(?:level|score)?\s*[:=]?\s*(\d+(?:\.\d+)?)\s*(%)?").ok()?;
+    let caps = re.captures(response)?;
+    let value: f32 = caps.get(1)?.as_str().parse().ok()?;
+    let value = if caps.get(2).is_some() || value > 1.0 { value / 100.0 } else { value };
+    Some(value.clamp(0.0, 1.0))
+}
+
+/// Whether a judge verdict must be routed to human review under `min_confidence`.
+/// A verdict without a parsed confidence never meets a threshold.
+pub fn requires_review(judge: &JudgeResult, min_confidence: Option<f32>) -> bool {
+    match min_confidence {
+        Some(min) => judge.confidence.is_none_or(|c| c < min),
+        None => false,
+    }
+}
+
+/// Applies the review threshold to a judge verdict, recording `LowConfidence` as the
+/// uncertain reason unless the verdict already has one. Returns whether review is required.
+pub fn apply_review_threshold(judge: &mut JudgeResult, min_confidence: Option<f32>) -> bool {
+    let needs_review = requires_review(judge, min_confidence);
+    if needs_review {
+        judge.uncertain_reason.get_or_insert(UncertainReason::LowConfidence);
+    }
+    needs_review
+}
+
+/// Status string reported and stored for a completed evaluation: `judge_error` when its judge
+/// call failed, otherwise that of its verdict.
+pub fn eval_status(result: &EvalResult) -> &'static str {
+    if result.judge_error.is_some() {
+        return "judge_error";
+    }
+    verdict_status(result.judge_result.as_ref().map(|j| j.verdict), result.needs_review)
+}
+
+/// Status string for a judge verdict, or for no verdict at all.
+pub fn verdict_status(verdict: Option<JudgeVerdict>, needs_review: bool) -> &'static str {
+    if needs_review {
+        return "needs_review";
+    }
+    match verdict {
+        Some(JudgeVerdict::Pass) => "passed",
+        Some(JudgeVerdict::Fail) => "failed",
+        Some(JudgeVerdict::Uncertain) => "uncertain",
+        None => "completed",
     }
 }
 
-/// Default judge prompt template (fallback if database is unavailable)
-fn get_default_judge_prompt_template() -> String {
+/// Default judge prompt template, seeded into an empty database and used when none is available
+pub(crate) fn get_default_judge_prompt_template() -> String {
     r#"You are an expert evaluator comparing two text outputs.
 
 EVALUATION CRITERIA:
@@ -103,188 +491,378 @@ Your evaluation:"#.to_string()
 }
 
 /// Render judge prompt template with actual values
-fn render_judge_prompt(template: &str, expected: &str, actual: &str, criteria: Option<&str>) -> String {
+pub(crate) fn render_judge_prompt(template: &str, expected: &str, actual: &str, criteria: Option<&str>) -> String {
     let base_criteria = criteria.unwrap_or(
         "The outputs should convey the same core meaning, even if phrased differently."
     );
     
-    template
-        .replace("{{criteria}}", base_criteria)
-        .replace("{{expected}}", expected)
-        .replace("{{actual}}", actual)
+    let values = serde_json::json!({
+        "criteria": base_criteria,
+        "expected": expected,
+        "actual": actual,
+        "truncation_note": "",
+    });
+    // Placeholders the judge prompt has no value for are sent as they are
+    crate::template::render(template, Some(&values)).0
 }
 
-/// Load judge prompt from database or use default
-async fn get_judge_prompt_template(db_pool: Option<&SqlitePool>) -> (String, Option<i64>) {
-    if let Some(pool) = db_pool {
-        match crate::database::get_active_judge_prompt(pool).await {
-            Ok(prompt) => {
-                println!("📋 Using judge prompt v{}: {}", prompt.version, prompt.name);
-                return (prompt.template, Some(prompt.version));
-            }
-            Err(e) => {
-                log::warn!("Could not load judge prompt from database: {}. Using default.", e);
-            }
-        }
+/// Puts `note` where the template has a `{{truncation_note}}` placeholder, or at the top otherwise.
+fn add_truncation_note(template: &str, note: &str) -> String {
+    if template.contains("{{truncation_note}}") {
+        template.replace("{{truncation_note}}", note)
+    } else {
+        format!("{}\n\n{}", note, template)
     }
-    
-    println!("📋 Using default judge prompt template");
-    (get_default_judge_prompt_template(), None)
 }
 
 /// Enhanced judge prompt with better structure (DEPRECATED - kept for compatibility)
-#[deprecated(note = "Use get_judge_prompt_template and render_judge_prompt instead")]
+#[deprecated(note = "Use JudgePromptSnapshot::template_for and render_judge_prompt instead")]
 fn create_judge_prompt(expected: &str, actual: &str, criteria: Option<&str>) -> String {
     let template = get_default_judge_prompt_template();
     render_judge_prompt(&template, expected, actual, criteria)
 }
 
-/// Attempt to parse model output into structured JSON
+/// Attempt to parse model output into structured JSON; see `output_parser::parse`.
 pub fn parse_model_output(raw_output: &str) -> Option<JsonValue> {
-    if let Ok(json) = serde_json::from_str::<JsonValue>(raw_output) {
-        return Some(json);
+    output_parser::parse(raw_output).map(|(value, _)| value)
+}
+
+pub fn parse_model_string(model_str: &str) -> (String, String) {
+    match model_str.split_once(':') {
+        Some((provider, model)) => (provider.to_string(), model.to_string()),
+        None => ("gemini".to_string(), model_str.to_string()),
     }
+}
 
-    if let Some(json) = extract_json_code_block(raw_output) {
-        if let Ok(parsed) = serde_json::from_str::<JsonValue>(&json) {
-            return Some(parsed);
+/// Builds the configured provider for `provider_name`, sending `params` with every request.
+/// Anthropic has no JSON mode, so its prompts carry the response format's instruction instead.
+/// Fails with a `Config` error when the provider cannot take the images of `params`.
+pub fn build_provider(
+    config: &AppConfig,
+    client: &reqwest::Client,
+    provider_name: &str,
+    params: &GenerationParams,
+) -> Result<Box<dyn LlmProvider>> {
+    let not_found = || EvalError::ProviderNotFound(provider_name.to_string());
+    images::check_support(provider_name, &params.images)?;
+    let params = params.clone();
+    let headers = config.extra_headers(provider_name);
+    match provider_name {
+        "anthropic" => {
+            let anthropic_config = config.anthropic.as_ref().ok_or_else(not_found)?;
+            let format = params.response_format.clone();
+            let provider = AnthropicProvider::new(client.clone(), anthropic_config.clone()).with_params(params).with_extra_headers(headers);
+            Ok(match format {
+                Some(format) => InstructedFormat::new(provider, format).boxed(),
+                None => provider.boxed(),
+            })
+        }
+        "gemini" => {
+            let gemini_config = config.gemini.as_ref().ok_or_else(not_found)?;
+            Ok(GeminiProvider::new(client.clone(), gemini_config.clone()).with_params(params).with_extra_headers(headers).boxed())
+        }
+        "groq" => {
+            let groq_config = config.groq.as_ref().ok_or_else(not_found)?;
+            Ok(GroqProvider::new(client.clone(), groq_config.clone()).with_params(params).with_extra_headers(headers).boxed())
+        }
+        "ollama" => {
+            let ollama_config = config.ollama.as_ref().ok_or_else(not_found)?;
+            Ok(OllamaProvider::new(client.clone(), ollama_config.clone()).with_params(params).with_extra_headers(headers).boxed())
+        }
+        "openai" => {
+            let openai_config = config.openai.as_ref().ok_or_else(not_found)?;
+            Ok(OpenAIProvider::new(client.clone(), openai_config.clone()).with_params(params).with_extra_headers(headers).boxed())
+        }
+        "openrouter" => {
+            let openrouter_config = config.openrouter.as_ref().ok_or_else(not_found)?;
+            Ok(OpenRouterProvider::new(client.clone(), openrouter_config.clone()).with_params(params).with_extra_headers(headers).boxed())
+        }
+        name => {
+            let compat_config = config.openai_compat.get(name).ok_or_else(not_found)?;
+            Ok(OpenAIProvider::compatible(client.clone(), name, compat_config.clone()).with_params(params).with_extra_headers(headers).boxed())
         }
     }
+}
 
-    if let Some(number) = extract_number(raw_output) {
-        return Some(serde_json::json!({ "answer": number }));
+/// The provider wrapped in its rate limiter, if it has one, and the configured retry policy,
+/// so every attempt waits for a permit. Errors are scrubbed of the config's request secrets
+/// before anything sees them. With a pool, every error it sees
+/// (including ones a retry recovered from) is logged to the `provider_errors` table; with a
+/// retry log, it is also collected there for the eval's trace.
+fn retrying_provider(
+    config: &AppConfig,
+    client: &reqwest::Client,
+    provider_name: &str,
+    params: &GenerationParams,
+    db_pool: Option<&DbPool>,
+    retry_log: Option<&RetryLog>,
+) -> Result<Box<dyn LlmProvider>> {
+    let mut provider = build_provider(config, client, provider_name, params)?;
+    if !config.request_secrets.is_empty() {
+        provider = RedactingProvider::new(provider, config.request_secrets.clone()).boxed();
     }
-
-    if let Some(boolean) = extract_boolean(raw_output) {
-        return Some(serde_json::json!({ "answer": boolean }));
+    let limited = config.provider_limiters.wrap(provider_name, provider);
+    let mut provider = RetryProvider::new(limited, config.provider_retry);
+    if let Some(pool) = db_pool {
+        provider = provider.with_error_sink(provider_name, Arc::new(ProviderErrorLog::new(pool.clone())));
     }
-
-    if let Some(choice) = extract_multiple_choice(raw_output) {
-        return Some(serde_json::json!({ "answer": choice }));
+    if let Some(log) = retry_log {
+        provider = provider.with_error_sink(provider_name, Arc::new(log.clone()));
     }
+    Ok(provider.boxed())
+}
 
-    if let Some(structured) = extract_key_value_pairs(raw_output) {
-        return Some(structured);
-    }
+pub(crate) async fn call_provider(
+    config: &AppConfig,
+    client: &reqwest::Client,
+    provider_name: &str,
+    model_name: &str,
+    prompt: &str,
+    db_pool: Option<&DbPool>,
+) -> Result<Generation> {
+    call_provider_logged(config, client, provider_name, model_name, prompt, &GenerationParams::default(), db_pool, None).await
+}
 
-    None
+/// `call_provider`, sending `params` and collecting failed attempts in `retry_log`.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn call_provider_logged(
+    config: &AppConfig,
+    client: &reqwest::Client,
+    provider_name: &str,
+    model_name: &str,
+    prompt: &str,
+    params: &GenerationParams,
+    db_pool: Option<&DbPool>,
+    retry_log: Option<&RetryLog>,
+) -> Result<Generation> {
+    retrying_provider(config, client, provider_name, params, db_pool, retry_log)?
+        .generate(model_name, prompt)
+        .await
 }
 
-fn extract_json_code_block(text: &str) -> Option<String> {
-    let re = Regex::new(r"```(?:json)?\s*\n([\s\S]*?)\n```").ok()?;
-    re.captures(text)
-        .and_then(|caps| caps.get(1))
-        .map(|m| m.as_str().to_string())
+/// Streaming counterpart of `call_provider_logged`, used when a fail-fast format rule is configured.
+#[allow(clippy::too_many_arguments)]
+async fn call_provider_streaming(
+    config: &AppConfig,
+    client: &reqwest::Client,
+    provider_name: &str,
+    model_name: &str,
+    prompt: &str,
+    should_abort: AbortCheck<'_>,
+    params: &GenerationParams,
+    db_pool: Option<&DbPool>,
+    retry_log: Option<&RetryLog>,
+) -> Result<StreamedGeneration> {
+    retrying_provider(config, client, provider_name, params, db_pool, retry_log)?
+        .generate_streaming(model_name, prompt, should_abort)
+        .await
 }
 
-fn extract_number(text: &str) -> Option<f64> {
-    let patterns = [
-        r"(?:answer|result|solution)(?:\s+is)?[:\s]+(-?\d+\.?\d*)",
-        r"^(-?\d+\.?\d*)$",
-        r"\b(-?\d+\.?\d*)\b(?:\s*$)",
-    ];
+/// Key of a response in the response cache: a hash of everything that decides the response,
+/// i.e. the provider, model and prompt, and the generation params, sampling settings and
+/// images that were set.
+pub fn response_cache_key(
+    provider_name: &str,
+    model_name: &str,
+    prompt: &str,
+    params: &GenerationParams,
+) -> String {
+    let mut hasher = Sha256::new();
+    for part in [provider_name, model_name, prompt] {
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part.as_bytes());
+    }
+    // Keys of responses without a format or limits stay as they were before these existed
+    if let Some(format) = params.response_format.as_ref().and_then(|format| serde_json::to_string(format).ok()) {
+        hasher.update((format.len() as u64).to_le_bytes());
+        hasher.update(format.as_bytes());
+    }
+    if params.stop.is_some() || params.max_output_tokens.is_some() {
+        let limits = serde_json::json!({ "stop": params.stop, "max_output_tokens": params.max_output_tokens }).to_string();
+        hasher.update((limits.len() as u64).to_le_bytes());
+        hasher.update(limits.as_bytes());
+    }
+    if params.temperature.is_some() || params.seed.is_some() {
+        let sampling = serde_json::json!({ "temperature": params.temperature, "seed": params.seed }).to_string();
+        hasher.update((sampling.len() as u64).to_le_bytes());
+        hasher.update(sampling.as_bytes());
+    }
+    for image in &params.images {
+        hasher.update((image.len() as u64).to_le_bytes());
+        hasher.update(image.as_bytes());
+    }
+    format!("{:x}", hasher.finalize())
+}
 
-    for pattern in &patterns {
-        if let Ok(re) = Regex::new(pattern) {
-            if let Some(caps) = re.captures(text) {
-                if let Some(num_str) = caps.get(1) {
-                    if let Ok(num) = num_str.as_str().parse::<f64>() {
-                        return Some(num);
-                    }
-                }
+/// The cached response under `key`, when `cache` is on, a database is available and the
+/// response is younger than `CACHE_TTL_SECONDS`.
+async fn cached_response(config: &AppConfig, db_pool: Option<&DbPool>, cache: bool, key: &str) -> Option<Generation> {
+    let pool = db_pool.filter(|_| cache)?;
+    match crate::database::get_cached_response(pool, key, config.cache_ttl_seconds).await {
+        Ok(cached) => {
+            if cached.is_some() {
+                tracing::info!("♻️  Using cached response {}", &key[..12]);
             }
+            cached
+        }
+        Err(e) => {
+            tracing::warn!("⚠️  Failed to read the response cache: {}", e);
+            None
         }
     }
-    None
 }
 
-fn extract_boolean(text: &str) -> Option<bool> {
-    let text_lower = text.to_lowercase().trim().to_string();
-    
-    if text_lower.starts_with("yes") || text_lower.contains("answer is yes") || text_lower == "true" {
-        return Some(true);
-    }
-    
-    if text_lower.starts_with("no") || text_lower.contains("answer is no") || text_lower == "false" {
-        return Some(false);
+/// Caches `generation` under `key` when `cache` is on and a database is available.
+async fn cache_response(db_pool: Option<&DbPool>, cache: bool, key: &str, model: &str, generation: &Generation) {
+    let Some(pool) = db_pool.filter(|_| cache) else {
+        return;
+    };
+    if let Err(e) = crate::database::save_cached_response(pool, key, model, generation).await {
+        tracing::warn!("⚠️  Failed to cache the response of {}: {}", model, e);
     }
-    
-    None
 }
 
-fn extract_multiple_choice(text: &str) -> Option<String> {
-    let re = Regex::new(r"(?:answer|choice)(?:\s+is)?[:\s]+([A-Za-z])").ok()?;
-    re.captures(text)
-        .and_then(|caps| caps.get(1))
-        .map(|m| m.as_str().to_uppercase())
+/// A judge verdict together with what the judge call cost.
+#[derive(Debug, Clone)]
+pub struct Judgement {
+    pub result: JudgeResult,
+    /// The judge prompt the verdict was given for, as rendered.
+    pub prompt: String,
+    /// The generation settings the judge was called with.
+    pub params: JudgeParams,
+    /// Both calls together when an Uncertain verdict was asked for again.
+    pub latency_ms: u64,
+    pub token_usage: TokenUsage,
+    pub cost_usd: Option<f64>,
 }
 
-fn extract_key_value_pairs(text: &str) -> Option<JsonValue> {
-    let mut map = serde_json::Map::new();
-    let re = Regex::new(r"(?m)^([A-Za-z\s]+):\s*(.+)$").ok()?;
-    
-    for caps in re.captures_iter(text) {
-        if let (Some(key), Some(value)) = (caps.get(1), caps.get(2)) {
-            let key_str = key.as_str().trim().to_lowercase().replace(' ', "_");
-            let value_str = value.as_str().trim();
-            
-            if let Ok(num) = value_str.parse::<f64>() {
-                map.insert(key_str, JsonValue::Number(serde_json::Number::from_f64(num)?));
-            } else if let Some(bool_val) = extract_boolean(value_str) {
-                map.insert(key_str, JsonValue::Bool(bool_val));
-            } else {
-                map.insert(key_str, JsonValue::String(value_str.to_string()));
-            }
-        }
-    }
-    
-    if !map.is_empty() {
-        Some(JsonValue::Object(map))
-    } else {
-        None
-    }
+/// Runs only the judge step: renders `template` for an existing model output and expected
+/// output, calls `judge_model` with the global judge params and parses its verdict. Nothing
+/// is generated or saved.
+#[allow(clippy::too_many_arguments)]
+pub async fn judge_only(
+    config: &AppConfig,
+    client: &reqwest::Client,
+    judge_model: &str,
+    template: &str,
+    expected: &str,
+    model_output: &str,
+    criteria: Option<&str>,
+    db_pool: Option<&DbPool>,
+) -> Result<Judgement> {
+    judge_logged(config, client, judge_model, template, expected, model_output, criteria, &config.judge_params, db_pool, None, false).await
 }
 
-fn parse_model_string(model_str: &str) -> (String, String) {
-    match model_str.split_once(':') {
-        Some((provider, model)) => (provider.to_string(), model.to_string()),
-        None => ("gemini".to_string(), model_str.to_string()),
+/// `judge_only` with the given judge params, collecting failed attempts of the judge call in
+/// `retry_log`. An Uncertain verdict is asked for again, bypassing the cache, when the params
+/// say so; the second verdict stands unless that call fails.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn judge_logged(
+    config: &AppConfig,
+    client: &reqwest::Client,
+    judge_model: &str,
+    template: &str,
+    expected: &str,
+    model_output: &str,
+    criteria: Option<&str>,
+    params: &JudgeParams,
+    db_pool: Option<&DbPool>,
+    retry_log: Option<&RetryLog>,
+    cache: bool,
+) -> Result<Judgement> {
+    let judge_prompt = render_judge_prompt(template, expected, model_output, criteria);
+    let judgement = judge_once(config, client, judge_model, judge_prompt.clone(), params, db_pool, retry_log, cache).await?;
+    if judgement.result.verdict != JudgeVerdict::Uncertain || !params.retries_uncertain() {
+        return Ok(judgement);
+    }
+    tracing::info!("🔁 Uncertain verdict: asking the judge again");
+    match judge_once(config, client, judge_model, judge_prompt, params, db_pool, retry_log, false).await {
+        Ok(retry) => Ok(judgement.retried(retry)),
+        Err(e) => {
+            tracing::warn!("⚠️  Judge retry failed, keeping the Uncertain verdict: {}", e);
+            Ok(judgement)
+        }
     }
 }
 
-async fn call_provider(
+/// One judge call for the rendered `judge_prompt`, through the response cache when `cache` is on.
+#[allow(clippy::too_many_arguments)]
+async fn judge_once(
     config: &AppConfig,
     client: &reqwest::Client,
-    provider_name: &str,
-    model_name: &str,
-    prompt: &str,
-) -> Result<(String, u64, TokenUsage)> {
-    match provider_name {
-        "anthropic" => {
-            let anthropic_config = config.anthropic.as_ref()
-                .ok_or_else(|| EvalError::ProviderNotFound("anthropic".to_string()))?;
-            let provider = AnthropicProvider::new(client.clone(), anthropic_config.clone());
-            provider.generate(model_name, prompt).await
-        }
-        "gemini" => {
-            let gemini_config = config.gemini.as_ref()
-                .ok_or_else(|| EvalError::ProviderNotFound("gemini".to_string()))?;
-            let provider = GeminiProvider::new(client.clone(), gemini_config.clone());
-            provider.generate(model_name, prompt).await
+    judge_model: &str,
+    judge_prompt: String,
+    params: &JudgeParams,
+    db_pool: Option<&DbPool>,
+    retry_log: Option<&RetryLog>,
+    cache: bool,
+) -> Result<Judgement> {
+    let judge_model = config.resolve_model(judge_model);
+    let (judge_provider_name, judge_model_name) = parse_model_string(judge_model);
+    let generation_params = params.generation_params();
+
+    let cache_key = response_cache_key(&judge_provider_name, &judge_model_name, &judge_prompt, &generation_params);
+    let cached = cached_response(config, db_pool, cache, &cache_key).await;
+    let cache_hit = cached.is_some();
+    let generation = match cached {
+        Some(generation) => generation,
+        None => {
+            let generation = call_provider_logged(
+                config,
+                client,
+                &judge_provider_name,
+                &judge_model_name,
+                &judge_prompt,
+                &generation_params,
+                db_pool,
+                retry_log,
+            ).await?;
+            cache_response(db_pool, cache, &cache_key, judge_model, &generation).await;
+            generation
         }
-        "ollama" => {
-            let ollama_config = config.ollama.as_ref()
-                .ok_or_else(|| EvalError::ProviderNotFound("ollama".to_string()))?;
-            let provider = OllamaProvider::new(client.clone(), ollama_config.clone());
-            provider.generate(model_name, prompt).await
+    };
+    let Generation { output: judge_response, latency_ms, token_usage, .. } = generation;
+    tracing::info!("⚖️  Judge Response ({}ms):\n{}", latency_ms, &judge_response);
+
+    let mut result = parse_judge_response(&judge_response);
+    result.judge_model = judge_model.to_string();
+    match result.verdict {
+        JudgeVerdict::Pass => tracing::info!("✅ VERDICT: PASS"),
+        JudgeVerdict::Fail => tracing::info!("❌ VERDICT: FAIL"),
+        JudgeVerdict::Uncertain => tracing::info!("⚠️  VERDICT: UNCERTAIN"),
+    }
+
+    let cost_usd = config.pricing.cost(&format!("{}:{}", judge_provider_name, judge_model_name), &token_usage);
+    Ok(Judgement {
+        cost_usd: if cache_hit { cost_usd.map(|_| 0.0) } else { cost_usd },
+        result,
+        prompt: judge_prompt,
+        params: *params,
+        latency_ms,
+        token_usage,
+    })
+}
+
+impl Judgement {
+    /// The verdict of `retry`, a second call after this one's Uncertain verdict, with the
+    /// latency, tokens and cost of both calls.
+    pub(crate) fn retried(self, retry: Judgement) -> Judgement {
+        fn sum<T: std::ops::Add<Output = T>>(first: Option<T>, second: Option<T>) -> Option<T> {
+            match (first, second) {
+                (Some(first), Some(second)) => Some(first + second),
+                (first, second) => first.or(second),
+            }
         }
-        "openai" => {
-            let openai_config = config.openai.as_ref()
-                .ok_or_else(|| EvalError::ProviderNotFound("openai".to_string()))?;
-            let provider = OpenAIProvider::new(client.clone(), openai_config.clone());
-            provider.generate(model_name, prompt).await
+        Judgement {
+            latency_ms: self.latency_ms + retry.latency_ms,
+            token_usage: TokenUsage {
+                input_tokens: sum(self.token_usage.input_tokens, retry.token_usage.input_tokens),
+                output_tokens: sum(self.token_usage.output_tokens, retry.token_usage.output_tokens),
+            },
+            cost_usd: sum(self.cost_usd, retry.cost_usd),
+            params: self.params,
+            ..retry
         }
-        _ => Err(EvalError::ProviderNotFound(provider_name.to_string())),
     }
 }
 
@@ -302,135 +880,975 @@ pub async fn run_eval_with_pool(
     config: &AppConfig,
     eval: &EvalConfig,
     client: &reqwest::Client,
-    db_pool: Option<&SqlitePool>,
+    db_pool: Option<&DbPool>,
 ) -> Result<EvalResult> {
+    run_eval_with_id(config, eval, client, db_pool, None, None).await
+}
+
+/// A fresh id for an eval, which its log lines, live updates and stored record share.
+pub fn new_eval_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+/// A phase of a running eval finishing, reported for live updates.
+#[derive(Debug, Clone)]
+pub enum EvalProgress {
+    /// The eval started, under the id it will be saved with.
+    Started { eval_id: String, model: String },
+    /// The model answered.
+    ModelCompleted {
+        eval_id: String,
+        model: String,
+        latency_ms: u64,
+        token_usage: Option<TokenUsage>,
+        finish_reason: Option<FinishReason>,
+    },
+    /// The eval got its verdict, from the judge or from a format rule or finish reason.
+    JudgeCompleted {
+        eval_id: String,
+        model: String,
+        judge: JudgeResult,
+        judge_latency_ms: Option<u64>,
+    },
+    /// The eval of a batch finished, with a result or an error.
+    Finished { eval_id: String },
+}
+
+/// Where running evals report their `EvalProgress`.
+pub type ProgressSink = tokio::sync::mpsc::UnboundedSender<EvalProgress>;
+
+/// Sends `event` to `progress`, if any. Nobody listening any more is not an error.
+fn report(progress: Option<&ProgressSink>, event: EvalProgress) {
+    if let Some(sink) = progress {
+        let _ = sink.send(event);
+    }
+}
+
+fn report_model_completed(progress: Option<&ProgressSink>, result: &EvalResult) {
+    report(progress, EvalProgress::ModelCompleted {
+        eval_id: result.id.clone(),
+        model: result.model.clone(),
+        latency_ms: result.latency_ms,
+        token_usage: result.token_usage.clone(),
+        finish_reason: result.finish_reason.clone(),
+    });
+}
+
+fn report_judge_completed(progress: Option<&ProgressSink>, result: &EvalResult) {
+    if let Some(judge) = &result.judge_result {
+        report(progress, EvalProgress::JudgeCompleted {
+            eval_id: result.id.clone(),
+            model: result.model.clone(),
+            judge: judge.clone(),
+            judge_latency_ms: result.judge_latency_ms,
+        });
+    }
+}
+
+/// Like `run_eval_with_pool`, for an eval that will be saved under `eval_id`; without one the
+/// eval gets a `new_eval_id`. The id decides which arm of a running judge prompt canary the
+/// eval lands on and is returned in the result. The start and finished phases are reported
+/// to `progress`, if given.
+pub async fn run_eval_with_id(
+    config: &AppConfig,
+    eval: &EvalConfig,
+    client: &reqwest::Client,
+    db_pool: Option<&DbPool>,
+    eval_id: Option<&str>,
+    progress: Option<&ProgressSink>,
+) -> Result<EvalResult> {
+    let eval_id = eval_id.map_or_else(new_eval_id, str::to_string);
+    report(progress, EvalProgress::Started { eval_id: eval_id.clone(), model: eval.model.clone() });
+    let judge_prompts = config.judge_prompts.snapshot(db_pool).await;
+    let steps = run_eval_steps(config, eval, client, db_pool, &judge_prompts, &eval_id, progress);
+    with_raw_capture(config, eval, db_pool, &eval_id, steps)
+        .instrument(eval_span(eval, &eval_id, None))
+        .await
+}
+
+/// Runs `steps` of the eval saved under `eval_id`, saving the raw provider exchanges they make
+/// when the eval or `EVAL_CAPTURE_RAW` asks for them and there is a database to keep them in.
+async fn with_raw_capture<T>(
+    config: &AppConfig,
+    eval: &EvalConfig,
+    db_pool: Option<&DbPool>,
+    eval_id: &str,
+    steps: impl std::future::Future<Output = T>,
+) -> T {
+    let pool = match db_pool {
+        Some(pool) if eval.capture_raw || config.capture_raw => pool,
+        _ => return steps.await,
+    };
+    let (output, exchanges) = capture::capturing(steps).await;
+    if !exchanges.is_empty() {
+        match crate::database::save_raw_exchanges(pool, eval_id, &exchanges).await {
+            Ok(()) => tracing::info!("🔬 Captured {} raw provider exchange(s)", exchanges.len()),
+            Err(e) => tracing::warn!("⚠️  Failed to save raw provider exchanges: {}", e),
+        }
+    }
+    output
+}
+
+/// The span an eval's log lines are recorded under, so concurrent evals can be told apart.
+fn eval_span(eval: &EvalConfig, eval_id: &str, batch_id: Option<&str>) -> tracing::Span {
+    let (provider, model) = parse_model_string(&eval.model);
+    tracing::info_span!("eval", eval_id, model = %model, provider = %provider, batch_id)
+}
+
+/// `config` with `eval`'s provider overrides applied, if it has any.
+fn with_overrides<'a>(config: &'a AppConfig, eval: &EvalConfig) -> Cow<'a, AppConfig> {
+    if eval.provider_overrides.is_empty() {
+        Cow::Borrowed(config)
+    } else {
+        Cow::Owned(provider_override::apply(config, &eval.provider_overrides))
+    }
+}
+
+/// Generates, judges and classifies one eval, logging into the current span. A
+/// self-consistency eval generates and judges all its samples at once before they are
+/// combined into one result.
+async fn run_eval_steps(
+    config: &AppConfig,
+    eval: &EvalConfig,
+    client: &reqwest::Client,
+    db_pool: Option<&DbPool>,
+    judge_prompts: &JudgePromptSnapshot,
+    eval_id: &str,
+    progress: Option<&ProgressSink>,
+) -> Result<EvalResult> {
+    let config = with_overrides(config, eval);
+    let config = config.as_ref();
+    let mut result = match eval.self_consistency {
+        None => generate_and_judge(config, eval, client, db_pool, judge_prompts, eval_id, progress).await?,
+        Some(consistency) => {
+            tracing::info!("🎲 Sampling {} outputs ({})", consistency.samples, consistency.strategy.as_str());
+            let samples = (0..consistency.samples)
+                .map(|_| generate_and_judge(config, eval, client, db_pool, judge_prompts, eval_id, None));
+            let result = self_consistency::aggregate(consistency, future::join_all(samples).await)?;
+            report_model_completed(progress, &result);
+            report_judge_completed(progress, &result);
+            result
+        }
+    };
+    classify_failure(config, client, eval, &mut result, db_pool).await;
+    print_eval_summary(&result);
+    Ok(result)
+}
+
+/// Generates one output of an eval and judges it, if it needs a judge.
+async fn generate_and_judge(
+    config: &AppConfig,
+    eval: &EvalConfig,
+    client: &reqwest::Client,
+    db_pool: Option<&DbPool>,
+    judge_prompts: &JudgePromptSnapshot,
+    eval_id: &str,
+    progress: Option<&ProgressSink>,
+) -> Result<EvalResult> {
+    let (mut result, pending) = prepare_eval(config, eval, client, db_pool, judge_prompts, eval_id).await?;
+    report_model_completed(progress, &result);
+    if let Some(pending) = &pending {
+        tracing::info!("⚖️  Running judge evaluation with model: {}", pending.judge_model);
+        let span = Span::start(TracePhase::JudgeCall);
+        let retry_log = result.trace.is_some().then(RetryLog::default);
+        let judgement = judge_logged(
+            config,
+            client,
+            &pending.judge_model,
+            &pending.template,
+            &pending.expected,
+            &reasoning::judged_output(result.reasoning.as_deref(), &result.model_output, pending.include_reasoning),
+            pending.criteria.as_deref(),
+            &pending.params,
+            db_pool,
+            retry_log.as_ref(),
+            pending.cache,
+        ).await;
+        let detail = match &judgement {
+            Ok(_) => retry_log.and_then(|log| log.take_detail()),
+            Err(e) => Some(format!("failed: {}", e)),
+        };
+        trace::record(&mut result.trace, span, detail);
+        apply_judgement(&mut result, pending, judgement, None);
+    }
+    report_judge_completed(progress, &result);
+    Ok(result)
+}
+
+/// The model a failed eval would be classified with: the configured classifier model, or the
+/// eval's judge model. `None` when the eval skips classification or none is configured.
+fn failure_classifier_model(config: &AppConfig, eval: &EvalConfig) -> Option<String> {
+    if eval.skip_failure_classification {
+        return None;
+    }
+    match config.failure_classifier.as_ref()? {
+        FailureClassifier::Judge => eval.judge_model.clone(),
+        FailureClassifier::Model(model) => Some(model.clone()),
+    }
+}
+
+/// Puts a failed eval in a category of the failure taxonomy with a separate classifier call,
+/// unless the judge already did so in its verdict call. A failed classification leaves the
+/// eval unclassified.
+async fn classify_failure(
+    config: &AppConfig,
+    client: &reqwest::Client,
+    eval: &EvalConfig,
+    result: &mut EvalResult,
+    db_pool: Option<&DbPool>,
+) {
+    let failed = result.judge_result.as_ref().is_some_and(|j| j.verdict == JudgeVerdict::Fail);
+    if !failed || result.failure_classification.is_some() {
+        return;
+    }
+    let Some(model) = failure_classifier_model(config, eval) else {
+        return;
+    };
+
+    let span = Span::start(TracePhase::FailureClassification);
+    let taxonomy = failure_taxonomy::load_taxonomy(db_pool).await;
+    match failure_taxonomy::classify(config, client, &model, &taxonomy, result, db_pool).await {
+        Ok(classification) => {
+            tracing::info!("🏷️  Failure category: {}", classification.category);
+            trace::record(&mut result.trace, span, Some(classification.category.clone()));
+            result.failure_classification = Some(classification);
+        }
+        Err(e) => {
+            tracing::warn!("⚠️  Failure classification failed: {}", e);
+            trace::record(&mut result.trace, span, Some(format!("failed: {}", e)));
+        }
+    }
+}
+
+/// A judge call an eval still needs once its output is in.
+#[derive(Debug, Clone)]
+pub(crate) struct PendingJudge {
+    pub judge_model: String,
+    /// The judge prompt template, with any truncation note already added.
+    pub template: String,
+    pub expected: String,
+    pub criteria: Option<String>,
+    pub min_confidence: Option<f32>,
+    /// Taxonomy the judge picks a failure category from in its verdict call, when it classifies failures.
+    pub taxonomy: Option<Vec<FailureCategory>>,
+    /// Whether the judge response may come from, and goes to, the response cache.
+    pub cache: bool,
+    /// Whether the judge is shown the model's reasoning before its answer.
+    pub include_reasoning: bool,
+    /// The eval's judge params over the global ones.
+    pub params: JudgeParams,
+}
+
+/// Fills in the judge fields of `result` from a finished judge call. `batch_size` is the
+/// number of evals judged in the same call, `None` for a call of its own.
+pub(crate) fn apply_judgement(
+    result: &mut EvalResult,
+    pending: &PendingJudge,
+    judgement: Result<Judgement>,
+    batch_size: Option<usize>,
+) {
+    match judgement {
+        Ok(judgement) => {
+            result.judge_latency_ms = Some(judgement.latency_ms);
+            result.judge_cost_usd = judgement.cost_usd;
+            result.judge_token_usage = Some(judgement.token_usage);
+            result.total_latency_ms += judgement.latency_ms;
+            result.judge_batch_size = batch_size;
+            result.judge_prompt_rendered = Some(judgement.prompt);
+            result.judge_params = Some(judgement.params);
+            let mut judge = judgement.result;
+
+            if apply_review_threshold(&mut judge, pending.min_confidence) {
+                result.needs_review = true;
+                tracing::info!("🔎 Confidence {:?} below threshold {:?}: routed to review", judge.confidence, pending.min_confidence);
+            }
+            if let (Some(taxonomy), JudgeVerdict::Fail) = (&pending.taxonomy, judge.verdict) {
+                result.failure_classification = judge
+                    .raw_response
+                    .as_deref()
+                    .and_then(|response| failure_taxonomy::parse_classification(response, taxonomy))
+                    .map(|(category, justification)| FailureClassification {
+                        category,
+                        justification,
+                        classifier: failure_taxonomy::JUDGE_CLASSIFIER.to_string(),
+                        cost_usd: None,
+                    });
+            }
+            result.judge_result = Some(judge);
+        }
+        Err(e) => {
+            result.judge_error = Some(JudgeError::new(&pending.judge_model, &e));
+            let judge_error = EvalError::JudgeFailure {
+                model: pending.judge_model.clone(),
+                source: Box::new(e),
+            };
+            tracing::warn!("⚠️  Judge evaluation failed: {}", judge_error);
+        }
+    }
+}
+
+fn print_eval_summary(result: &EvalResult) {
+    if let Some(cost) = result.cost_usd.map(|c| c + result.judge_cost_usd.unwrap_or(0.0)) {
+        tracing::info!("💲 Estimated cost: ${:.6}", cost);
+    }
+    tracing::info!("⏱️  Total evaluation time: {}ms", result.total_latency_ms);
+}
+
+/// `config` with Gemini's safety threshold replaced by an eval's own, when it sets one.
+fn with_safety_threshold(config: &AppConfig, threshold: Option<SafetyThreshold>) -> Cow<'_, AppConfig> {
+    match (threshold, &config.gemini) {
+        (Some(threshold), Some(gemini)) if gemini.safety_threshold != threshold => {
+            let mut config = config.clone();
+            if let Some(gemini) = config.gemini.as_mut() {
+                gemini.safety_threshold = threshold;
+            }
+            Cow::Owned(config)
+        }
+        _ => Cow::Borrowed(config),
+    }
+}
+
+/// Verdict to record without a judge for an output that was cut off, filtered or refused, which
+/// a judge would otherwise grade as a complete answer. `on_length_limit` decides for cut-off
+/// outputs whether or not the eval is judged; otherwise judged evals become `Uncertain`.
+fn unjudgeable_verdict(
+    eval: &EvalConfig,
+    finish_reason: Option<&FinishReason>,
+) -> Option<(JudgeVerdict, Option<UncertainReason>, &'static str)> {
+    let (uncertain_reason, reasoning) = match finish_reason? {
+        FinishReason::Length => (UncertainReason::LengthLimit, "Output was cut off at the output token limit (finish_reason=length)"),
+        FinishReason::ContentFilter => (UncertainReason::ContentFilter, "Output was withheld by the provider's content filter (finish_reason=content_filter)"),
+        FinishReason::Refusal => (UncertainReason::Refusal, "Model refused to answer (finish_reason=refusal)"),
+        _ => return None,
+    };
+    match (uncertain_reason, eval.on_length_limit) {
+        (UncertainReason::LengthLimit, Some(LengthLimitPolicy::Fail)) => Some((JudgeVerdict::Fail, None, reasoning)),
+        (UncertainReason::LengthLimit, Some(LengthLimitPolicy::Uncertain)) => {
+            Some((JudgeVerdict::Uncertain, Some(uncertain_reason), reasoning))
+        }
+        _ if eval.expected.is_some() && eval.judge_model.is_some() => {
+            Some((JudgeVerdict::Uncertain, Some(uncertain_reason), reasoning))
+        }
+        _ => None,
+    }
+}
+
+/// Verdict and reasoning the diff rule records instead of a judge call.
+fn diff_verdict(output_diff: &OutputDiff) -> (JudgeVerdict, String) {
+    if output_diff.matches {
+        return (JudgeVerdict::Pass, "Parsed output matches the expected JSON".to_string());
+    }
+    let paths: Vec<&str> = output_diff.differences.iter().filter(|d| !d.ignored).map(|d| d.path.as_str()).collect();
+    (JudgeVerdict::Fail, format!("Parsed output differs from the expected JSON at {}", paths.join(", ")))
+}
+
+/// Runs an eval up to its judge step: renders it, generates the output and checks the format
+/// rule. Returns the judge call still needed, if any, so callers can run or batch it. The
+/// judge prompt is taken from `judge_prompts`.
+async fn prepare_eval(
+    config: &AppConfig,
+    eval: &EvalConfig,
+    client: &reqwest::Client,
+    db_pool: Option<&DbPool>,
+    judge_prompts: &JudgePromptSnapshot,
+    eval_id: &str,
+) -> Result<(EvalResult, Option<PendingJudge>)> {
+    let mut trace = eval.trace.then(Vec::new);
+    let mut eval = eval.clone();
+    let model_alias = config.model_aliases.contains_key(&eval.model).then(|| eval.model.clone());
+    eval.model = config.resolve_model(&eval.model).to_string();
+    eval.judge_model = eval.judge_model.or_else(|| config.default_judge_model.clone()).map(|judge| config.resolve_model(&judge).to_string());
+    let span = Span::start(TracePhase::Validation);
+    let truncation = truncation::apply(&mut eval, &CharsPerToken::default())?;
+    if let Some(record) = &truncation {
+        tracing::info!(
+            "✂️  Truncated '{}' with {} ({} -> {} tokens)",
+            record.field, record.strategy.as_str(), record.original_tokens, record.truncated_tokens
+        );
+    }
+    trace::record(&mut trace, span, truncation.as_ref().map(|record| format!("truncated '{}'", record.field)));
+
+    let span = Span::start(TracePhase::TemplateRender);
     let rendered_eval = eval.render()?;
+    trace::record(&mut trace, span, None);
     let eval_start = Instant::now();
-    let separator = "=".repeat(60);
-    
-    println!("\n{}", separator);
-    println!("🎯 Starting evaluation for model: {}", rendered_eval.model);
-    println!("{}\n", separator);
+
+    tracing::info!("🎯 Starting evaluation for model: {}", rendered_eval.model);
 
     let (provider_name, model_name) = parse_model_string(&rendered_eval.model);
     
-    println!("📝 Prompt: {}", rendered_eval.prompt);
+    tracing::info!("📝 Prompt: {}", rendered_eval.prompt);
     
-    let (model_output_str, latency_ms, token_usage) = match call_provider(
-        config,
-        client,
-        &provider_name,
-        &model_name,
-        &rendered_eval.prompt,
-    ).await {
+    let span = Span::start(TracePhase::ProviderCall);
+    let retry_log = trace.is_some().then(RetryLog::default);
+    let params = rendered_eval.generation_params();
+    let cache_key = response_cache_key(&provider_name, &model_name, &rendered_eval.prompt, &params);
+    let cached = cached_response(config, db_pool, rendered_eval.cache, &cache_key).await;
+    let cache_hit = cached.is_some();
+    let model_config = with_safety_threshold(config, rendered_eval.safety_threshold);
+    let call_start = Instant::now();
+    let generation = match (cached, &rendered_eval.format_rule, rendered_eval.stream) {
+        (Some(generation), _, _) => Ok(StreamedGeneration::from(generation)),
+        (None, Some(rule), true) => {
+            // Only the answer is held to the rule, not the reasoning before it
+            let should_abort = |partial: &str| rule.check_partial(&reasoning::partial_answer(partial)).is_some();
+            call_provider_streaming(
+                &model_config,
+                client,
+                &provider_name,
+                &model_name,
+                &rendered_eval.prompt,
+                &should_abort,
+                &params,
+                db_pool,
+                retry_log.as_ref(),
+            ).await
+        }
+        _ => call_provider_logged(
+            &model_config,
+            client,
+            &provider_name,
+            &model_name,
+            &rendered_eval.prompt,
+            &params,
+            db_pool,
+            retry_log.as_ref(),
+        ).await.map(StreamedGeneration::from),
+    };
+    let call_ms = call_start.elapsed().as_millis() as u64;
+    let detail = if cache_hit { Some("cached response".to_string()) } else { retry_log.and_then(|log| log.take_detail()) };
+    trace::record(&mut trace, span, detail);
+    // A stream cut short by the format rule is not the model's whole answer
+    match &generation {
+        Ok(streamed) if !cache_hit && streamed.aborted_after_tokens.is_none() => {
+            let generation = Generation {
+                output: streamed.output.clone(),
+                latency_ms: streamed.latency_ms,
+                token_usage: streamed.token_usage.clone(),
+                finish_reason: streamed.finish_reason.clone(),
+                resolved_model: streamed.resolved_model.clone(),
+                reasoning: streamed.reasoning.clone(),
+            };
+            cache_response(db_pool, rendered_eval.cache, &cache_key, &rendered_eval.model, &generation).await;
+        }
+        _ => {}
+    }
+
+    let StreamedGeneration {
+        output: raw_output,
+        latency_ms,
+        token_usage,
+        finish_reason,
+        resolved_model,
+        reasoning: provider_reasoning,
+        aborted_after_tokens,
+    } = match generation {
         Ok(result) => result,
         Err(e @ EvalError::ProviderNotFound(_)) => {
-            eprintln!("❌ Provider not configured: {}", e);
+            tracing::error!("❌ Provider not configured: {}", e);
+            return Err(e);
+        }
+        Err(e @ EvalError::ContentBlocked { .. }) => {
+            tracing::error!("🛑 {}", e);
+            return Err(e);
+        }
+        Err(e @ EvalError::Config(_)) => {
+            tracing::error!("❌ {}", e);
             return Err(e);
         }
         Err(e) => {
-            eprintln!("❌ Model failed: {}", e);
+            tracing::error!("❌ Model failed: {}", e);
             return Err(EvalError::ModelFailure {
                 model: rendered_eval.model.clone(),
             });
         }
     };
 
-    println!("\n✅ Model Output ({}ms):\n{}\n", latency_ms, &model_output_str);
+    tracing::info!("✅ Model Output ({}ms):\n{}", latency_ms, &raw_output);
+    let ReasoningSplit { reasoning, answer: model_output_str } = reasoning::extract(provider_reasoning.as_deref(), &raw_output);
+    if let Some(reasoning) = &reasoning {
+        tracing::info!("💭 Split off {} characters of reasoning from the answer", reasoning.len());
+    }
+    if finish_reason == Some(FinishReason::Length) {
+        tracing::info!("✂️  Output was cut off at the output token limit (finish_reason=length)");
+    }
     
-    let parsed_output = parse_model_output(&model_output_str);
-    if let Some(ref parsed) = parsed_output {
-        println!("📊 Parsed Output: {}", serde_json::to_string_pretty(parsed).unwrap_or_else(|_| "Unable to display".to_string()));
+    let span = Span::start(TracePhase::Parse);
+    let (parsed_output, parse_method) = output_parser::parse(&model_output_str).unzip();
+    if let (Some(parsed), Some(method)) = (&parsed_output, parse_method) {
+        tracing::info!("📊 Parsed Output ({}): {}", method.as_str(), serde_json::to_string_pretty(parsed).unwrap_or_else(|_| "Unable to display".to_string()));
     } else {
-        println!("⚠️  Could not parse output into structured format");
+        tracing::info!("⚠️  Could not parse output into structured format");
     }
+    let diff_rule = rendered_eval.diff_rule.clone().unwrap_or_default();
+    let output_diff = parsed_output
+        .as_ref()
+        .zip(rendered_eval.expected.as_deref())
+        .and_then(|(parsed, expected)| diff_rule.check(parsed, expected));
+
+    // Step 2: Check the fail-fast format rule, which short-circuits the judge on violation
+    let format_violation = rendered_eval.format_rule.as_ref().and_then(|rule| match aborted_after_tokens {
+        Some(_) => rule.check_partial(&model_output_str),
+        None => rule.check_complete(&model_output_str),
+    });
+    let early_abort = aborted_after_tokens.map(|tokens_generated| EarlyAbort {
+        tokens_generated,
+        reason: format_violation.clone().unwrap_or_default(),
+    });
+    trace::record(&mut trace, span, format_violation.as_ref().map(|_| "format rule violated".to_string()));
 
-    // Step 2: Run judge evaluation with dynamic prompt loading
-    let mut judge_latency_ms = None;
-    let mut judge_token_usage = None;
+    // Step 3: Load the judge prompt for the judge evaluation
     let mut judge_prompt_version = None;
+    let mut judge_prompt_arm = None;
+    let mut pending_judge = None;
     
-    let judge_result = if let (Some(expected), Some(judge_model)) =
+    let judge_result = if let Some(violation) = &format_violation {
+        let reasoning = match &early_abort {
+            Some(abort) => format!("Aborted early after {} tokens: {}", abort.tokens_generated, violation),
+            None => format!("Format rule violated: {}", violation),
+        };
+        tracing::info!("❌ VERDICT: FAIL ({})", reasoning);
+        Some(JudgeResult {
+            judge_model: "format-rule".to_string(),
+            verdict: JudgeVerdict::Fail,
+            reasoning: Some(reasoning),
+            confidence: Some(1.0),
+            raw_response: None,
+            uncertain_reason: None,
+        })
+    } else if let Some((verdict, uncertain_reason, reasoning)) = unjudgeable_verdict(&rendered_eval, finish_reason.as_ref()) {
+        tracing::info!("✂️  VERDICT: {} ({})", verdict, reasoning);
+        Some(JudgeResult {
+            judge_model: "finish-reason".to_string(),
+            verdict,
+            reasoning: Some(reasoning.to_string()),
+            confidence: Some(1.0),
+            raw_response: None,
+            uncertain_reason,
+        })
+    } else if let Some(output_diff) = output_diff.as_ref().filter(|_| diff_rule.verdict) {
+        let (verdict, reasoning) = diff_verdict(output_diff);
+        tracing::info!("🧮 VERDICT: {} ({}, no judge call)", verdict, reasoning);
+        Some(JudgeResult {
+            judge_model: diff::JSON_DIFF_JUDGE.to_string(),
+            verdict,
+            reasoning: Some(reasoning),
+            confidence: Some(1.0),
+            raw_response: None,
+            uncertain_reason: None,
+        })
+    } else if let Some(reasoning) = rendered_eval
+        .expected
+        .as_deref()
+        .filter(|_| rendered_eval.judge_model.is_some())
+        .and_then(|expected| rendered_eval.match_rule.unwrap_or_default().check(&model_output_str, expected))
+    {
+        tracing::info!("✅ VERDICT: PASS ({}, no judge call)", reasoning);
+        Some(JudgeResult {
+            judge_model: match_rule::EXACT_MATCH_JUDGE.to_string(),
+            verdict: JudgeVerdict::Pass,
+            reasoning: Some(reasoning),
+            confidence: Some(1.0),
+            raw_response: None,
+            uncertain_reason: None,
+        })
+    } else if let (Some(expected), Some(judge_model)) =
         (&rendered_eval.expected, &rendered_eval.judge_model) {
         
-        println!("⚖️  Running judge evaluation with model: {}", judge_model);
-        
-        // 🆕 Load judge prompt from database
-        let (judge_prompt_template, version) = get_judge_prompt_template(db_pool).await;
+        let span = Span::start(TracePhase::JudgePromptLoad);
+        let (mut judge_prompt_template, version, arm) = judge_prompts.template_for(Some(eval_id));
+        let loaded = match version {
+            Some(version) => format!("judge prompt v{}", version),
+            None => "built-in judge prompt".to_string(),
+        };
+        trace::record(&mut trace, span, Some(loaded));
         judge_prompt_version = version;
-        
-        // Render the template with actual values
-        let judge_prompt = render_judge_prompt(
-            &judge_prompt_template,
-            expected,
-            &model_output_str,
-            rendered_eval.criteria.as_deref()
-        );
-
-        let (judge_provider_name, judge_model_name) = parse_model_string(judge_model);
-        
-        let judge_result = call_provider(
-            config,
-            client,
-            &judge_provider_name,
-            &judge_model_name,
-            &judge_prompt,
-        ).await;
-
-        match judge_result {
-            Ok((judge_response, judge_latency, tokens)) => {
-                judge_latency_ms = Some(judge_latency);
-                judge_token_usage = Some(tokens);
-                println!("\n⚖️  Judge Response ({}ms):\n{}\n", judge_latency, &judge_response);
-                
-                let mut result = parse_judge_response(&judge_response);
-                result.judge_model = judge_model.clone();
-                
-                match result.verdict {
-                    JudgeVerdict::Pass => println!("✅ VERDICT: PASS"),
-                    JudgeVerdict::Fail => println!("❌ VERDICT: FAIL"),
-                    JudgeVerdict::Uncertain => println!("⚠️  VERDICT: UNCERTAIN"),
-                }
-                
-                Some(result)
-            }
-            Err(e) => {
-                let judge_error = EvalError::JudgeFailure {
-                    model: judge_model.clone(),
-                    source: Box::new(e),
-                };
-                eprintln!("⚠️  Judge evaluation failed: {}", judge_error);
-                None
-            }
+        judge_prompt_arm = arm;
+        if let Some(record) = &truncation {
+            judge_prompt_template = add_truncation_note(&judge_prompt_template, &record.judge_note());
         }
+        let taxonomy = if config.failure_classifier == Some(FailureClassifier::Judge) && !eval.skip_failure_classification {
+            let taxonomy = failure_taxonomy::load_taxonomy(db_pool).await;
+            judge_prompt_template = failure_taxonomy::add_classification_note(&judge_prompt_template, &taxonomy);
+            Some(taxonomy)
+        } else {
+            None
+        };
+
+        pending_judge = Some(PendingJudge {
+            judge_model: judge_model.clone(),
+            template: judge_prompt_template,
+            expected: expected.clone(),
+            criteria: rendered_eval.criteria.clone(),
+            min_confidence: rendered_eval.min_judge_confidence.or(config.min_judge_confidence),
+            taxonomy,
+            cache: rendered_eval.cache_judge,
+            include_reasoning: rendered_eval.include_reasoning,
+            params: rendered_eval.judge_params.map_or(config.judge_params, |params| params.or(config.judge_params)),
+        });
+        None
     } else {
-        println!("ℹ️  No judge evaluation (no expected output or judge model specified)");
+        tracing::info!("ℹ️  No judge evaluation (no expected output or judge model specified)");
         None
     };
 
+    let cost_usd = config.pricing.cost(&format!("{}:{}", provider_name, model_name), &token_usage);
+    let cost_usd = if cache_hit { cost_usd.map(|_| 0.0) } else { cost_usd };
     let total_latency_ms = eval_start.elapsed().as_millis() as u64;
-    println!("⏱️  Total evaluation time: {}ms", total_latency_ms);
-    println!("\n{}\n", separator);
+    let matched = judge_result
+        .as_ref()
+        .is_some_and(|judge| [match_rule::EXACT_MATCH_JUDGE, diff::JSON_DIFF_JUDGE].contains(&judge.judge_model.as_str()));
 
-    Ok(EvalResult {
+    let result = EvalResult {
+        id: eval_id.to_string(),
         model: rendered_eval.model.clone(),
         prompt: rendered_eval.prompt.clone(),
         model_output: model_output_str.to_string(),
         parsed_output,
+        parse_method,
+        output_diff: output_diff.and_then(|diff| serde_json::to_value(diff).ok()),
         expected: rendered_eval.expected.clone(),
         judge_result,
         timestamp: chrono::Utc::now().to_rfc3339(),
         latency_ms,
-        judge_latency_ms,
+        judge_latency_ms: matched.then_some(0),
         token_usage: if token_usage.input_tokens.is_some() || token_usage.output_tokens.is_some() { 
             Some(token_usage) 
         } else { 
             None 
         },
-        judge_token_usage,
+        judge_token_usage: None,
+        cost_usd,
+        judge_cost_usd: matched.then_some(0.0),
         total_latency_ms,
+        wait_ms: (!cache_hit).then(|| call_ms.saturating_sub(latency_ms)),
         judge_prompt_version,  // 🆕 Store which version was used
-    })
+        prompt_version: rendered_eval.prompt_version,
+        prompt_template: rendered_eval.metadata.as_ref().map(|_| eval.prompt.clone()),
+        judge_prompt_arm,
+        judge_batch_size: None,
+        early_abort,
+        finish_reason,
+        needs_review: rendered_eval.require_review,
+        truncation,
+        tags: rendered_eval.tags.clone(),
+        criteria: rendered_eval.criteria.clone(),
+        metadata: rendered_eval.metadata.clone(),
+        trace,
+        failure_classification: None,
+        dataset_row: rendered_eval.dataset_row.clone(),
+        chain_step: rendered_eval.chain_step.clone(),
+        provider_override: OverrideUse::of(&rendered_eval.provider_overrides, rendered_eval.overrides_supplied_by.as_ref()),
+        full_output_chars: None,
+        cache_hit,
+        resolved_model,
+        model_alias,
+        response_format: rendered_eval.response_format.as_ref().map(|format| format.as_str().to_string()),
+        images: images::parse_all(&rendered_eval.images).map(|image| image.record()).collect(),
+        reasoning,
+        judge_prompt_rendered: None,
+        judge_params: None,
+        judge_error: None,
+        self_consistency: None,
+    };
+    Ok((result, pending_judge))
+}
+
+/// Result of re-running the deterministic parts of the pipeline for a stored evaluation.
+#[derive(Debug, Serialize)]
+pub struct ExplainReport {
+    pub evaluation_id: String,
+    /// The prompt as the stored template renders today with the stored metadata.
+    pub prompt: Option<String>,
+    /// The judge prompt as the stored judge prompt version renders today.
+    pub judge_prompt: Option<String>,
+    pub judge_prompt_version: Option<i64>,
+    /// What `parse_model_output` extracts from the stored output today.
+    pub parsed_output: Option<JsonValue>,
+    pub judge: Option<ExplainJudge>,
+    /// Places where the current code disagrees with what was stored.
+    pub differences: Vec<String>,
+    /// Steps that could not be replayed because their inputs were not stored.
+    pub skipped: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExplainJudge {
+    /// `raw_response`, or `reasoning` for rows saved before raw responses were kept.
+    pub source: String,
+    pub stored_verdict: Option<String>,
+    pub current_verdict: String,
+    pub stored_confidence: Option<f64>,
+    pub current_confidence: Option<f32>,
+}
+
+/// Replays rendering and parsing for a stored evaluation with the current code, without
+/// calling any provider. `prompt_template` is the template the prompt was rendered from:
+/// the stored one, or that of the stored prompt version. `judge_template` is the template
+/// of the stored judge prompt version, when it can still be loaded.
+pub fn explain_evaluation(entry: &HistoryEntry, prompt_template: Option<&str>, judge_template: Option<&str>) -> ExplainReport {
+    let mut report = ExplainReport {
+        evaluation_id: entry.id.clone(),
+        prompt: None,
+        judge_prompt: None,
+        judge_prompt_version: entry.judge_prompt_version,
+        parsed_output: None,
+        judge: None,
+        differences: Vec::new(),
+        skipped: Vec::new(),
+    };
+
+    match prompt_template {
+        Some(template) => {
+            let (prompt, unresolved) = crate::template::render(template, entry.metadata.as_ref());
+            if entry.prompt.as_deref() != Some(prompt.as_str()) {
+                let mut difference = "re-rendering the prompt template with the stored metadata gives a different prompt than was stored".to_string();
+                if !unresolved.is_empty() {
+                    difference.push_str(&format!("; unfilled placeholders: {}", unresolved.join(", ")));
+                }
+                report.differences.push(difference);
+            }
+            report.prompt = Some(prompt);
+        }
+        // Without metadata there was nothing to fill in, so the stored prompt is the template
+        None if entry.metadata.is_none() => report.prompt = entry.prompt.clone(),
+        None => report.skipped.push("prompt rendering: the prompt template is not stored".to_string()),
+    }
+
+    let Some(model_output) = entry.model_output.as_deref() else {
+        report.skipped.push("output parsing and judging: no model output was stored".to_string());
+        return report;
+    };
+    report.parsed_output = parse_model_output(model_output);
+    if entry.parsed_output.is_some() && entry.parsed_output != report.parsed_output {
+        report.differences.push("current parser extracts different JSON from the output than was stored".to_string());
+    }
+
+    let Some(expected) = entry.expected.as_deref() else {
+        return report;
+    };
+    if let Some(rule) = entry.judge_model.as_deref().filter(|m| RULE_JUDGES.contains(m)) {
+        report.skipped.push(format!("judging: the verdict came from the {} rule, not a judge", rule));
+        return report;
+    }
+
+    match (entry.judge_prompt_version, judge_template) {
+        (Some(_), Some(template)) => {
+            report.judge_prompt = Some(render_judge_prompt(template, expected, model_output, entry.criteria.as_deref()));
+        }
+        (Some(version), None) => {
+            report.skipped.push(format!("judge prompt rendering: judge prompt v{} no longer exists", version));
+        }
+        (None, _) => {
+            report.judge_prompt = Some(render_judge_prompt(
+                &get_default_judge_prompt_template(),
+                expected,
+                model_output,
+                entry.criteria.as_deref(),
+            ));
+        }
+    }
+
+    let (source, judge_text) = match (&entry.judge_raw_response, &entry.judge_reasoning) {
+        (Some(raw), _) => ("raw_response", raw),
+        (None, Some(reasoning)) => ("reasoning", reasoning),
+        (None, None) => {
+            if entry.judge_verdict.is_some() {
+                report.skipped.push("judge parsing: the judge response was not stored".to_string());
+            }
+            return report;
+        }
+    };
+
+    let current = parse_judge_response(judge_text);
+    let current_verdict = current.verdict.to_string();
+    if entry.judge_verdict.as_deref() != Some(current_verdict.as_str()) {
+        report.differences.push(format!(
+            "current parser would classify this verdict as {}, stored verdict was {}",
+            current_verdict,
+            entry.judge_verdict.as_deref().unwrap_or("none")
+        ));
+    }
+    let confidence_matches = match (entry.judge_confidence, current.confidence) {
+        (Some(stored), Some(now)) => (stored - now as f64).abs() < 1e-6,
+        (stored, now) => stored.is_none() && now.is_none(),
+    };
+    if !confidence_matches {
+        report.differences.push(format!(
+            "current parser extracts confidence {:?}, stored confidence was {:?}",
+            current.confidence, entry.judge_confidence
+        ));
+    }
+
+    report.judge = Some(ExplainJudge {
+        source: source.to_string(),
+        stored_verdict: entry.judge_verdict.clone(),
+        current_verdict,
+        stored_confidence: entry.judge_confidence,
+        current_confidence: current.confidence,
+    });
+    report
+}
+
+/// Checks that every model and judge model in `evals` names a configured provider.
+pub fn validate_providers(config: &AppConfig, evals: &[EvalConfig]) -> Result<()> {
+    for (index, eval) in evals.iter().enumerate() {
+        for model in std::iter::once(&eval.model).chain(eval.judge_model.as_ref()) {
+            let (provider_name, _) = config.parse_model(model);
+            if !config.is_provider_configured(&provider_name) {
+                return Err(EvalError::ProviderNotConfigured { index, provider: provider_name, model: model.clone() });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Output tokens assumed for an eval with no `expected` text to size the answer by.
+const DEFAULT_ESTIMATED_OUTPUT_TOKENS: u32 = 256;
+
+/// Judge output tokens assumed: a verdict line plus a few sentences of reasoning.
+const ESTIMATED_JUDGE_OUTPUT_TOKENS: u32 = 120;
+
+/// Estimate tokens and cost for a batch without calling any provider.
+/// Prompts are rendered exactly as `run_eval` would render them; the model's answer is
+/// sized by the `expected` text when present. Configs whose provider is not configured
+/// are counted but not priced.
+pub async fn estimate_batch(
+    config: &AppConfig,
+    evals: &[EvalConfig],
+    estimator: &dyn TokenEstimator,
+    db_pool: Option<&DbPool>,
+) -> Result<BatchEstimate> {
+    let mut estimate = BatchEstimate {
+        total_configs: evals.len(),
+        ..Default::default()
+    };
+
+    let needs_judge = evals.iter().any(|e| e.expected.is_some() && e.judge_model.is_some());
+    let judge_template = if needs_judge {
+        Some(config.judge_prompts.snapshot(db_pool).await.template_for(None).0)
+    } else {
+        None
+    };
+
+    for eval in evals {
+        let rendered = eval.render()?;
+        let (provider_name, model_name) = config.parse_model(&rendered.model);
+        if !config.is_provider_configured(&provider_name) {
+            estimate.unconfigured_provider_configs += 1;
+            continue;
+        }
+
+        let output_tokens = rendered
+            .expected
+            .as_deref()
+            .map(|e| estimator.estimate_tokens(e))
+            .unwrap_or(DEFAULT_ESTIMATED_OUTPUT_TOKENS);
+        estimate.add_call(
+            &config.pricing,
+            &format!("{}:{}", provider_name, model_name),
+            "target",
+            &TokenUsage {
+                input_tokens: Some(estimator.estimate_tokens(&rendered.prompt)),
+                output_tokens: Some(output_tokens),
+            },
+        );
+
+        if let (Some(expected), Some(judge_model), Some(template)) =
+            (&rendered.expected, &rendered.judge_model, &judge_template) {
+            let (judge_provider, judge_model_name) = config.parse_model(judge_model);
+            // The judge sees the model's answer, which we approximate by the expected text
+            let judge_prompt = render_judge_prompt(template, expected, expected, rendered.criteria.as_deref());
+            estimate.add_call(
+                &config.pricing,
+                &format!("{}:{}", judge_provider, judge_model_name),
+                "judge",
+                &TokenUsage {
+                    input_tokens: Some(estimator.estimate_tokens(&judge_prompt)),
+                    output_tokens: Some(ESTIMATED_JUDGE_OUTPUT_TOKENS),
+                },
+            );
+        }
+    }
+
+    Ok(estimate)
+}
+
+/// Savings from fail-fast format rules across a batch.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct EarlyAbortSavings {
+    pub early_aborts: usize,
+    pub estimated_tokens_saved: u64,
+    pub estimated_cost_saved_usd: f64,
+}
+
+/// Estimates output tokens (and their cost) saved by early aborts across a batch.
+/// Each aborted eval is credited with the mean output tokens of the completed (non-aborted)
+/// evals for the same model, minus the tokens it actually generated.
+pub fn estimate_early_abort_savings<'a>(
+    results: impl IntoIterator<Item = &'a EvalResult>,
+    pricing: &PricingTable,
+) -> EarlyAbortSavings {
+    let results: Vec<&EvalResult> = results.into_iter().collect();
+    let mut savings = EarlyAbortSavings::default();
+
+    for result in results.iter().filter(|r| r.early_abort.is_some()) {
+        savings.early_aborts += 1;
+        let reference: Vec<u64> = results
+            .iter()
+            .filter(|r| r.early_abort.is_none() && r.model == result.model)
+            .filter_map(|r| r.token_usage.as_ref().and_then(|u| u.output_tokens))
+            .map(u64::from)
+            .collect();
+        if reference.is_empty() {
+            continue;
+        }
+        let mean = reference.iter().sum::<u64>() / reference.len() as u64;
+        let generated = result.early_abort.as_ref().map_or(0, |a| a.tokens_generated as u64);
+        let saved = mean.saturating_sub(generated);
+        savings.estimated_tokens_saved += saved;
+
+        let (provider, model) = parse_model_string(&result.model);
+        if let Some(price) = pricing.price_for(&format!("{}:{}", provider, model)) {
+            savings.estimated_cost_saved_usd += saved as f64 / 1000.0 * price.output_per_1k;
+        }
+    }
+
+    savings
+}
+
+/// Runs the steps of a chain one after another under `chain_id`, each step rendered with the
+/// outputs of the steps before it; see `chain::prepare_step`. Returns each step that ran,
+/// in order, with the `new_eval_id` it ran and should be saved under. With `stop_on_failure`
+/// the chain ends after the first step whose status is in `chain::FAILED_STATUSES`.
+pub async fn run_chain(
+    config: &AppConfig,
+    steps: &[EvalConfig],
+    chain_id: &str,
+    stop_on_failure: bool,
+    client: &reqwest::Client,
+    db_pool: Option<&DbPool>,
+    progress: Option<&ProgressSink>,
+) -> Vec<(String, Result<EvalResult>)> {
+    let mut outputs: Vec<Option<String>> = Vec::with_capacity(steps.len());
+    let mut results = Vec::with_capacity(steps.len());
+    for (index, step) in steps.iter().enumerate() {
+        let step = chain::prepare_step(step, chain_id, index, &outputs);
+        let eval_id = new_eval_id();
+        tracing::info!(chain_id, "🔗 Running step {} of {}", index + 1, steps.len());
+        let outcome = run_eval_with_id(config, &step, client, db_pool, Some(&eval_id), progress).await;
+        let failed = match &outcome {
+            Ok(result) => chain::FAILED_STATUSES.contains(&eval_status(result)),
+            Err(_) => true,
+        };
+        outputs.push(outcome.as_ref().ok().map(|result| result.model_output.clone()));
+        results.push((eval_id, outcome));
+        if failed && stop_on_failure {
+            tracing::info!(chain_id, "⛔ Stopping the chain after step {} failed", index + 1);
+            break;
+        }
+    }
+    results
 }
 
 /// Run multiple evals and aggregate results concurrently
@@ -447,20 +1865,494 @@ pub async fn run_batch_evals_with_pool(
     config: &AppConfig,
     evals: Vec<EvalConfig>,
     client: &reqwest::Client,
-    db_pool: Option<&SqlitePool>,
+    db_pool: Option<&DbPool>,
 ) -> Vec<Result<EvalResult>> {
+    run_batch_evals_with_ids(config, evals, None, client, db_pool, None)
+        .await
+        .into_iter()
+        .map(|(_, result)| result)
+        .collect()
+}
+
+/// Like `run_batch_evals_with_pool`, returning each result, in config order, with the
+/// `new_eval_id` its eval ran under and should be saved under, failed evals included.
+/// `batch_id` is only logged. Every eval's start and finished phases, and every finished
+/// eval, are reported to `progress`, if given.
+pub async fn run_batch_evals_with_ids(
+    config: &AppConfig,
+    evals: Vec<EvalConfig>,
+    batch_id: Option<&str>,
+    client: &reqwest::Client,
+    db_pool: Option<&DbPool>,
+    progress: Option<&ProgressSink>,
+) -> Vec<(String, Result<EvalResult>)> {
     let batch_start = Instant::now();
     let total_evals = evals.len();
+    let eval_ids: Vec<String> = evals.iter().map(|_| new_eval_id()).collect();
+    for (eval_id, eval) in eval_ids.iter().zip(&evals) {
+        report(progress, EvalProgress::Started { eval_id: eval_id.clone(), model: eval.model.clone() });
+    }
+    // Evals of the batch are judged with the prompts in force when it started
+    let judge_prompts = config.judge_prompts.snapshot(db_pool).await;
+    let spans: Vec<tracing::Span> = evals.iter().zip(&eval_ids).map(|(eval, eval_id)| eval_span(eval, eval_id, batch_id)).collect();
 
-    let futures: Vec<_> = evals
-        .iter()
-        .map(|eval| run_eval_with_pool(config, eval, client, db_pool))
-        .collect();
-
-    let results = future::join_all(futures).await;
+    // Batched judge calls are shared across evals, so overriding credentials could leak from
+    // one eval's judging into another's; such batches judge each eval on its own
+    let judge_batch_size = config.judge_batch_size.filter(|_| evals.iter().all(|eval| eval.provider_overrides.is_empty() && eval.self_consistency.is_none()));
+    let results = match judge_batch_size {
+        Some(batch_size) => {
+            run_batch_with_batched_judging(config, &evals, &eval_ids, &spans, batch_id, client, db_pool, &judge_prompts, batch_size, progress).await
+        }
+        None => {
+            let futures: Vec<_> = evals
+                .iter()
+                .zip(&eval_ids)
+                .zip(&spans)
+                .map(|((eval, eval_id), span)| {
+                    let steps = run_eval_steps(config, eval, client, db_pool, &judge_prompts, eval_id, progress);
+                    with_raw_capture(config, eval, db_pool, eval_id, steps)
+                        .instrument(span.clone())
+                        .inspect(move |_| report(progress, EvalProgress::Finished { eval_id: eval_id.clone() }))
+                })
+                .collect();
+            future::join_all(futures).await
+        }
+    };
 
     let batch_total_ms = batch_start.elapsed().as_millis() as u64;
-    println!("\n📊 Batch of {} completed concurrently in {}ms", total_evals, batch_total_ms);
+    tracing::info!(batch_id, "📊 Batch of {} completed concurrently in {}ms", total_evals, batch_total_ms);
+
+    eval_ids.into_iter().zip(results).collect()
+}
+
+/// Like `run_batch_evals_with_ids`, starting at most `BUDGETED_BATCH_CONCURRENCY` evals at a
+/// time in config order, and no more once the finished ones have used up a cap of `budget`.
+/// Evals in flight then still finish; those never started end in `EvalError::SkippedBudget`.
+/// Each eval is judged on its own, so its judge cost counts as soon as it finishes.
+#[allow(clippy::too_many_arguments)]
+pub async fn run_batch_evals_within_budget(
+    config: &AppConfig,
+    evals: Vec<EvalConfig>,
+    batch_id: Option<&str>,
+    client: &reqwest::Client,
+    db_pool: Option<&DbPool>,
+    progress: Option<&ProgressSink>,
+    budget: Budget,
+) -> (Vec<(String, Result<EvalResult>)>, BudgetUsage) {
+    let total_evals = evals.len();
+    let eval_ids: Vec<String> = evals.iter().map(|_| new_eval_id()).collect();
+    for (eval_id, eval) in eval_ids.iter().zip(&evals) {
+        report(progress, EvalProgress::Started { eval_id: eval_id.clone(), model: eval.model.clone() });
+    }
+    let judge_prompts = config.judge_prompts.snapshot(db_pool).await;
+
+    let mut tracker = BudgetTracker::new(budget);
+    let mut outcomes: Vec<Option<Result<EvalResult>>> = evals.iter().map(|_| None).collect();
+    let mut skipped = 0;
+    {
+        let mut in_flight = FuturesUnordered::new();
+        let mut next = 0;
+        loop {
+            while in_flight.len() < BUDGETED_BATCH_CONCURRENCY && next < total_evals {
+                if let Some(cap) = tracker.exhausted() {
+                    skipped = total_evals - next;
+                    tracing::info!(batch_id, "💸 The {} budget is used up; skipping {} of {} evals", cap, skipped, total_evals);
+                    for (outcome, eval_id) in outcomes[next..].iter_mut().zip(&eval_ids[next..]) {
+                        *outcome = Some(Err(EvalError::SkippedBudget { cap }));
+                        report(progress, EvalProgress::Finished { eval_id: eval_id.clone() });
+                    }
+                    next = total_evals;
+                    break;
+                }
+                let (index, eval, eval_id) = (next, &evals[next], &eval_ids[next]);
+                let steps = run_eval_steps(config, eval, client, db_pool, &judge_prompts, eval_id, progress);
+                in_flight.push(
+                    with_raw_capture(config, eval, db_pool, eval_id, steps)
+                        .instrument(eval_span(eval, eval_id, batch_id))
+                        .map(move |outcome| (index, outcome)),
+                );
+                next += 1;
+            }
+            let Some((index, outcome)) = in_flight.next().await else {
+                break;
+            };
+            if let Ok(result) = &outcome {
+                tracker.record(result);
+            }
+            report(progress, EvalProgress::Finished { eval_id: eval_ids[index].clone() });
+            outcomes[index] = Some(outcome);
+        }
+    }
+
+    let usage = tracker.usage(skipped);
+    tracing::info!(batch_id, "📊 Budgeted batch of {} finished: ${:.4}, {} tokens", total_evals, usage.cost_usd, usage.total_tokens);
+    // Every eval has finished or been skipped
+    (eval_ids.into_iter().zip(outcomes.into_iter().flatten()).collect(), usage)
+}
+
+/// Generates every eval's output first, then judges the outputs up to `batch_size` per judge call.
+/// `eval_ids[i]` and `spans[i]` are the id and log span of `evals[i]`.
+#[allow(clippy::too_many_arguments)]
+async fn run_batch_with_batched_judging(
+    config: &AppConfig,
+    evals: &[EvalConfig],
+    eval_ids: &[String],
+    spans: &[tracing::Span],
+    batch_id: Option<&str>,
+    client: &reqwest::Client,
+    db_pool: Option<&DbPool>,
+    judge_prompts: &JudgePromptSnapshot,
+    batch_size: usize,
+    progress: Option<&ProgressSink>,
+) -> Vec<Result<EvalResult>> {
+    let prepared = future::join_all(evals.iter().zip(eval_ids).zip(spans).map(|((eval, eval_id), span)| {
+        async move {
+            // Judge calls shared by several evals are not captured
+            let steps = prepare_eval(config, eval, client, db_pool, judge_prompts, eval_id);
+            let prepared = with_raw_capture(config, eval, db_pool, eval_id, steps).await;
+            if let Ok((result, _)) = &prepared {
+                report_model_completed(progress, result);
+            }
+            // Outputs wait here until every output of the batch is in
+            (prepared, Span::start(TracePhase::QueueWait))
+        }
+        .instrument(span.clone())
+    }))
+    .await;
 
+    let mut results = Vec::with_capacity(prepared.len());
+    let mut tasks = Vec::new();
+    for (index, (prepared, waiting)) in prepared.into_iter().enumerate() {
+        results.push(prepared.map(|(mut result, pending)| {
+            if let Some(pending) = pending {
+                trace::record(&mut result.trace, waiting, None);
+                let model_output = reasoning::judged_output(result.reasoning.as_deref(), &result.model_output, pending.include_reasoning);
+                tasks.push(JudgeTask { index, pending, model_output });
+            }
+            result
+        }));
+    }
+
+    tracing::info!(batch_id, "⚖️  Judging {} outputs in batches of up to {}", tasks.len(), batch_size);
+    let span = Span::start(TracePhase::JudgeCall);
+    let judgements = judge_batch::run_judges(config, client, db_pool, batch_size, &tasks).await;
+    for (task, (judgement, judged_in_batch_of)) in tasks.iter().zip(judgements) {
+        if let Ok(result) = &mut results[task.index] {
+            let detail = match (&judgement, judged_in_batch_of) {
+                (Err(e), _) => format!("failed: {}", e),
+                (Ok(_), Some(size)) => format!("judged in a batch of {}", size),
+                (Ok(_), None) => "judged on its own".to_string(),
+            };
+            trace::record(&mut result.trace, span.clone(), Some(detail));
+            spans[task.index].in_scope(|| apply_judgement(result, &task.pending, judgement, judged_in_batch_of));
+        }
+    }
+    future::join_all(results.iter_mut().zip(evals).zip(eval_ids).zip(spans).map(|(((result, eval), eval_id), span)| {
+        async move {
+            if let Ok(result) = result {
+                report_judge_completed(progress, result);
+                classify_failure(config, client, eval, result, db_pool).await;
+                print_eval_summary(result);
+            }
+            report(progress, EvalProgress::Finished { eval_id: eval_id.clone() });
+        }
+        .instrument(span.clone())
+    }))
+    .await;
     results
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::OllamaConfig;
+    use crate::pricing::CharsPerToken;
+
+    fn eval(model: &str, prompt: &str, expected: Option<&str>, judge_model: Option<&str>) -> EvalConfig {
+        serde_json::from_value(serde_json::json!({
+            "model": model,
+            "prompt": prompt,
+            "expected": expected,
+            "judge_model": judge_model,
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_estimate_batch_prices_targets_and_judges() {
+        let config = AppConfig {
+            ollama: Some(OllamaConfig { api_base: "http://localhost:11434".to_string(), models: vec![] }),
+            pricing: PricingTable::builtin(),
+            ..Default::default()
+        };
+        let evals = vec![
+            eval("ollama:llama3", "abcdabcd", Some("abcd"), Some("ollama:llama3")),
+            eval("ollama:llama3", "abcd", None, None),
+            eval("openai:gpt-4o", "abcd", None, None),
+        ];
+
+        let estimate = estimate_batch(&config, &evals, &CharsPerToken::default(), None).await.unwrap();
+
+        assert_eq!(estimate.total_configs, 3);
+        assert_eq!(estimate.unconfigured_provider_configs, 1);
+        let target = estimate.models.iter().find(|m| m.role == "target").unwrap();
+        assert_eq!(target.calls, 2);
+        assert_eq!(target.input_tokens, 3);
+        assert_eq!(target.output_tokens, 1 + DEFAULT_ESTIMATED_OUTPUT_TOKENS as u64);
+        let judge = estimate.models.iter().find(|m| m.role == "judge").unwrap();
+        assert_eq!(judge.calls, 1);
+        assert_eq!(judge.output_tokens, ESTIMATED_JUDGE_OUTPUT_TOKENS as u64);
+        assert_eq!(estimate.total_cost_usd, 0.0);
+        assert!(estimate.unpriced_models.is_empty());
+    }
+
+    #[test]
+    fn test_judge_prompts_fill_their_placeholders_once() {
+        let prompt = render_judge_prompt(&get_default_judge_prompt_template(), "4", "{{expected}}", None);
+        assert!(prompt.contains("EXPECTED OUTPUT:\n4\n"), "{}", prompt);
+        assert!(prompt.contains("ACTUAL OUTPUT:\n{{expected}}\n"), "{}", prompt);
+        assert!(prompt.contains("The outputs should convey the same core meaning"), "{}", prompt);
+
+        let template = "{{ criteria | upper }} {{expected}}/{{actual}}{{truncation_note}} {{rubric}}";
+        assert_eq!(render_judge_prompt(template, "4", "5", Some("be exact")), "BE EXACT 4/5 {{rubric}}");
+    }
+
+    #[test]
+    fn test_parse_model_string_splits_at_the_first_colon_only() {
+        assert_eq!(parse_model_string("openai:gpt-4o"), ("openai".to_string(), "gpt-4o".to_string()));
+        assert_eq!(
+            parse_model_string("openrouter:meta-llama/llama-3.1-8b-instruct:free"),
+            ("openrouter".to_string(), "meta-llama/llama-3.1-8b-instruct:free".to_string())
+        );
+        assert_eq!(parse_model_string("gemini-2.5-flash"), ("gemini".to_string(), "gemini-2.5-flash".to_string()));
+    }
+
+    #[test]
+    fn test_in_canary_is_deterministic_and_proportional() {
+        let ids: Vec<String> = (0..2000).map(|i| format!("eval-{}", i)).collect();
+        let canary = ids.iter().filter(|id| in_canary(id, 25.0)).count();
+        assert!((400..600).contains(&canary), "{} of 2000 in a 25% canary", canary);
+
+        assert!(ids.iter().all(|id| in_canary(id, 25.0) == in_canary(id, 25.0)));
+        // Raising the percentage only adds evals to the canary arm
+        assert!(ids.iter().filter(|id| in_canary(id, 25.0)).all(|id| in_canary(id, 50.0)));
+        assert!(!ids.iter().any(|id| in_canary(id, 0.0)));
+        assert!(ids.iter().all(|id| in_canary(id, 100.0)));
+    }
+
+    #[test]
+    fn test_validate_providers_rejects_unconfigured_models() {
+        let config = AppConfig {
+            ollama: Some(OllamaConfig { api_base: "http://localhost:11434".to_string(), models: vec![] }),
+            ..Default::default()
+        };
+
+        assert!(validate_providers(&config, &[eval("ollama:llama3", "hi", None, Some("ollama:llama3"))]).is_ok());
+        let err = validate_providers(&config, &[
+            eval("ollama:llama3", "hi", None, None),
+            eval("ollama:llama3", "hi", Some("hello"), Some("openai:gpt-4o")),
+        ])
+        .unwrap_err();
+        assert!(err.to_string().contains("Config 1: provider 'openai'"));
+    }
+
+    #[test]
+    fn test_explain_reports_parser_differences() {
+        let mut entry = HistoryEntry {
+            id: "e1".to_string(),
+            model_output: Some("{\"answer\": 4}".to_string()),
+            expected: Some("4".to_string()),
+            judge_model: Some("ollama:llama3".to_string()),
+            judge_verdict: Some("Uncertain".to_string()),
+            judge_raw_response: Some("Verdict: PASS\nConfidence: 0.9".to_string()),
+            judge_confidence: Some(0.9),
+            ..Default::default()
+        };
+
+        let report = explain_evaluation(&entry, None, None);
+        assert_eq!(report.parsed_output, Some(serde_json::json!({"answer": 4})));
+        assert!(report.judge_prompt.unwrap().contains("{\"answer\": 4}"));
+        assert_eq!(report.judge.as_ref().unwrap().source, "raw_response");
+        assert_eq!(report.differences.len(), 1);
+        assert!(report.differences[0].contains("classify this verdict as Pass, stored verdict was Uncertain"));
+
+        entry.judge_verdict = Some("Pass".to_string());
+        assert!(explain_evaluation(&entry, None, None).differences.is_empty());
+
+        // Stored criteria are rendered, and a stored parse is checked against the current one
+        entry.criteria = Some("Answer with a bare number".to_string());
+        entry.parsed_output = Some(serde_json::json!({"answer": 5}));
+        let report = explain_evaluation(&entry, None, None);
+        assert!(report.judge_prompt.unwrap().contains("Answer with a bare number"));
+        assert_eq!(report.differences.len(), 1);
+        assert!(report.differences[0].contains("different JSON"));
+    }
+
+    #[test]
+    fn test_explain_re_renders_the_prompt_from_its_template_and_metadata() {
+        let mut entry = HistoryEntry {
+            id: "e1".to_string(),
+            prompt: Some("What is 2+2?".to_string()),
+            model_output: Some("4".to_string()),
+            metadata: Some(serde_json::json!({ "a": 2, "b": 2 })),
+            ..Default::default()
+        };
+        let report = explain_evaluation(&entry, Some("What is {{a}}+{{b}}?"), None);
+        assert_eq!(report.prompt.as_deref(), Some("What is 2+2?"));
+        assert!(report.differences.is_empty() && report.skipped.is_empty());
+
+        // The template or the metadata changed since the prompt was sent
+        entry.prompt = Some("What is 2+3?".to_string());
+        let report = explain_evaluation(&entry, Some("What is {{a}}+{{b}}?"), None);
+        assert_eq!(report.prompt.as_deref(), Some("What is 2+2?"));
+        assert_eq!(report.differences.len(), 1);
+        assert!(report.differences[0].contains("different prompt than was stored"));
+
+        let report = explain_evaluation(&entry, Some("What is {{a}}+{{c}}?"), None);
+        assert!(report.differences[0].contains("unfilled placeholders: {{c}}"), "{}", report.differences[0]);
+
+        // Metadata without a template to fill it into cannot be replayed
+        let report = explain_evaluation(&entry, None, None);
+        assert_eq!(report.skipped, ["prompt rendering: the prompt template is not stored"]);
+        entry.metadata = None;
+        assert!(explain_evaluation(&entry, None, None).skipped.is_empty());
+    }
+
+    #[test]
+    fn test_apply_judgement_keeps_the_judges_failure_category() {
+        let mut result: EvalResult = serde_json::from_value(serde_json::json!({
+            "model": "ollama:llama3", "prompt": "2+2?", "model_output": "5", "parsed_output": null,
+            "expected": "4", "judge_result": null, "timestamp": "", "latency_ms": 1, "judge_latency_ms": null,
+            "token_usage": null, "judge_token_usage": null, "total_latency_ms": 1, "judge_prompt_version": null,
+            "early_abort": null,
+        }))
+        .unwrap();
+        let pending = PendingJudge {
+            judge_model: "ollama:llama3".to_string(),
+            template: String::new(),
+            expected: "4".to_string(),
+            criteria: None,
+            min_confidence: None,
+            taxonomy: Some(failure_taxonomy::default_taxonomy()),
+            cache: false,
+            include_reasoning: false,
+            params: JudgeParams::default(),
+        };
+        let judgement = |response: &str| Judgement {
+            result: parse_judge_response(response),
+            prompt: String::new(),
+            params: JudgeParams::default(),
+            latency_ms: 1,
+            token_usage: TokenUsage::default(),
+            cost_usd: None,
+        };
+
+        apply_judgement(&mut result, &pending, Ok(judgement("Verdict: FAIL\nCATEGORY: wrong_answer\nJUSTIFICATION: Says 5")), None);
+        let classification = result.failure_classification.take().unwrap();
+        assert_eq!(classification.category, "wrong_answer");
+        assert_eq!(classification.classifier, failure_taxonomy::JUDGE_CLASSIFIER);
+
+        apply_judgement(&mut result, &pending, Ok(judgement("Verdict: PASS\nCATEGORY: wrong_answer")), None);
+        assert!(result.failure_classification.is_none());
+    }
+
+    #[test]
+    fn test_extract_confidence() {
+        assert_eq!(extract_confidence("Verdict: PASS\nConfidence: 0.85"), Some(0.85));
+        assert_eq!(extract_confidence("Verdict: FAIL (confidence: 70%)"), Some(0.7));
+        assert_eq!(extract_confidence("Confidence level = 90"), Some(0.9));
+        assert_eq!(extract_confidence("Verdict: PASS"), None);
+    }
+
+    #[test]
+    fn test_judge_responses_are_parsed_into_the_verdict_they_give() {
+        use JudgeVerdict::{Fail, Pass, Uncertain};
+        let cases: [(&str, JudgeVerdict); 26] = [
+            ("Verdict: PASS\nBoth outputs state that 2+2 is 4.", Pass),
+            ("Verdict: FAIL\nThe actual output names Lyon, not Paris.", Fail),
+            ("verdict: pass", Pass),
+            ("**Verdict:** FAIL\n\nThe output omits the second step.", Fail),
+            ("## Verdict\nPASS\n\nThe answers agree.", Pass),
+            ("Final verdict - Pass. The wording differs but the meaning is the same.", Pass),
+            ("The outputs convey the same meaning.\nVerdict: PASS", Pass),
+            ("Note that both outputs mention Paris, but the actual output adds Lyon.\nVerdict: FAIL", Fail),
+            ("Note that both outputs list the same three steps in the same order.", Uncertain),
+            ("Yes and no — the output partially matches the expected answer.", Uncertain),
+            ("Yes, they convey the same meaning.", Pass),
+            ("No, the actual output gives a different total.", Fail),
+            ("No difference in meaning between the two outputs; both say 4.", Uncertain),
+            ("Yesterday's data is not part of either output.", Uncertain),
+            ("PASS", Pass),
+            ("FAIL.", Fail),
+            ("Comparing the outputs:\n1. Both name Paris.\n2. Both give the population.\n3. Units match.\n4. Formatting differs.\n\nPass.", Pass),
+            ("The expected output says 4.\nThe actual output says 5.\nThese are different numbers.\nThe arithmetic is wrong.\nSo the answer is wrong.\nFail", Fail),
+            ("Verdict: UNCERTAIN\nThe expected output is ambiguous.", Uncertain),
+            ("Verdict: unclear, the expected output could be read two ways.", Uncertain),
+            ("Verdict: PASS\nOn reflection the units differ.\nVerdict: FAIL", Uncertain),
+            ("Partially. The output covers two of the three points.", Uncertain),
+            ("I compared both outputs carefully and found them close.", Uncertain),
+            ("Correct. The capital of France is Paris.", Pass),
+            ("Incorrect: the output answers a different question.", Fail),
+            ("Verdict: PASS\nConfidence: 0.8\n\nNo.", Pass),
+        ];
+        for (response, expected) in cases {
+            assert_eq!(parse_judge_response(response).verdict, expected, "{:?}", response);
+        }
+
+        assert_eq!(parse_judge_response("Verdict: PASS\nConfidence: 0.9").confidence, Some(0.9));
+        // A labelled verdict the last line contradicts is kept with lowered confidence
+        let contradicted = parse_judge_response("Verdict: PASS\nConfidence: 0.8\n\nNo.");
+        assert_eq!(contradicted.confidence, Some(CONFLICTING_SIGNALS_CONFIDENCE));
+        assert_eq!(contradicted.uncertain_reason, None);
+        let conflicting = parse_judge_response("Verdict: PASS\nOn reflection the units differ.\nVerdict: FAIL");
+        assert_eq!(conflicting.uncertain_reason, Some(UncertainReason::Unparseable));
+        assert_eq!(conflicting.confidence, Some(CONFLICTING_SIGNALS_CONFIDENCE));
+        let ambiguous = parse_judge_response("Yes and no — the output partially matches the expected answer.");
+        assert_eq!(ambiguous.uncertain_reason, Some(UncertainReason::JudgeAbstained));
+        assert_eq!(ambiguous.confidence, None);
+    }
+
+    #[test]
+    fn test_requires_review_threshold() {
+        let mut judge = parse_judge_response("Verdict: PASS\nConfidence: 0.6");
+        assert!(!requires_review(&judge, None));
+        assert!(requires_review(&judge, Some(0.8)));
+        assert!(!requires_review(&judge, Some(0.5)));
+
+        judge.confidence = None;
+        assert!(requires_review(&judge, Some(0.5)));
+    }
+
+    #[test]
+    fn test_uncertain_reasons() {
+        assert_eq!(parse_judge_response("Verdict: PASS").uncertain_reason, None);
+
+        let abstained = parse_judge_response("Verdict: UNCERTAIN\nThe expected output is ambiguous.");
+        assert!(matches!(abstained.verdict, JudgeVerdict::Uncertain));
+        assert_eq!(abstained.uncertain_reason, Some(UncertainReason::JudgeAbstained));
+
+        let garbled = parse_judge_response("I compared both outputs carefully.");
+        assert!(matches!(garbled.verdict, JudgeVerdict::Uncertain));
+        assert_eq!(garbled.uncertain_reason, Some(UncertainReason::Unparseable));
+        assert_eq!(parse_judge_response("").uncertain_reason, Some(UncertainReason::Unparseable));
+
+        let mut confident = parse_judge_response("Verdict: FAIL\nConfidence: 0.9");
+        assert!(!apply_review_threshold(&mut confident, Some(0.8)));
+        assert_eq!(confident.uncertain_reason, None);
+
+        let mut hesitant = parse_judge_response("Verdict: FAIL\nConfidence: 0.4");
+        assert!(apply_review_threshold(&mut hesitant, Some(0.8)));
+        assert_eq!(hesitant.uncertain_reason, Some(UncertainReason::LowConfidence));
+
+        // An unparseable response also lacks a confidence, but keeps the more specific reason
+        let mut garbled = garbled;
+        assert!(apply_review_threshold(&mut garbled, Some(0.8)));
+        assert_eq!(garbled.uncertain_reason, Some(UncertainReason::Unparseable));
+
+        for reason in UncertainReason::ALL {
+            assert_eq!(UncertainReason::parse(reason.as_str()), Some(reason));
+            assert_eq!(serde_json::to_value(reason).unwrap(), reason.as_str());
+        }
+        assert_eq!(UncertainReason::parse("bogus"), None);
+    }
+}
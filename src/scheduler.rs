@@ -0,0 +1,397 @@
+// src/scheduler.rs
+//! Recurring suite runs: a background task that queues a job on the job queue for every
+//! schedule whose cron expression has come due, and the notifications sent once such a job
+//! has finished.
+use chrono::{DateTime, Utc};
+use std::collections::BTreeSet;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+use crate::api::AppState;
+use crate::database::{self, DbPool, Job, Schedule};
+use crate::drift::{self, ModelDrift};
+use crate::models::{BatchWebhook, WebhookPayload};
+use crate::notifier::{self, BatchReport, DriftReport, FailedEval};
+use crate::webhook;
+
+/// How the scheduler looks for due schedules.
+#[derive(Debug, Clone)]
+pub struct SchedulerOptions {
+    /// Time between looks. Schedules firing more often than this run once per look.
+    pub tick: Duration,
+}
+
+impl Default for SchedulerOptions {
+    fn default() -> Self {
+        SchedulerOptions { tick: Duration::from_secs(60) }
+    }
+}
+
+/// The running scheduler. Call `shutdown` to stop it.
+pub struct Scheduler {
+    shutdown: watch::Sender<bool>,
+    handle: JoinHandle<()>,
+}
+
+impl Scheduler {
+    /// Starts queueing the runs of due schedules, the first look right away.
+    pub fn spawn(pool: Arc<DbPool>, options: SchedulerOptions) -> Self {
+        let (shutdown, mut stop) = watch::channel(false);
+        let handle = tokio::spawn(async move {
+            loop {
+                run_due(&pool, Utc::now()).await;
+                tokio::select! {
+                    _ = tokio::time::sleep(options.tick) => {}
+                    _ = stop.changed() => break,
+                }
+            }
+            tracing::info!("⏰ Scheduler stopped");
+        });
+        Scheduler { shutdown, handle }
+    }
+
+    /// Stops the scheduler once the look in progress, if any, has queued its jobs.
+    pub async fn shutdown(self) {
+        let _ = self.shutdown.send(true);
+        if let Err(e) = self.handle.await {
+            tracing::error!("Scheduler panicked: {}", e);
+        }
+    }
+}
+
+/// Parses a cron expression of 5 fields (minute to day of week), 6 with a leading seconds
+/// field, or 7 with a trailing year, or a shorthand such as `@daily`. Times are UTC.
+pub fn parse_cron(expression: &str) -> Result<cron::Schedule, String> {
+    let expression = expression.trim();
+    let expression = if expression.split_whitespace().count() == 5 {
+        format!("0 {}", expression)
+    } else {
+        expression.to_string()
+    };
+    cron::Schedule::from_str(&expression).map_err(|e| e.to_string())
+}
+
+/// The first time `schedule` fires after it last ran, or was created, when that is no later
+/// than `now`. A scheduler that was down for several firings runs the schedule once.
+pub fn due_at(schedule: &Schedule, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    next_run_after(schedule, schedule.last_run_at.as_deref().unwrap_or(&schedule.created_at)).filter(|at| *at <= now)
+}
+
+/// The first time `schedule` fires after the RFC 3339 time `since`.
+pub fn next_run_after(schedule: &Schedule, since: &str) -> Option<DateTime<Utc>> {
+    let cron = parse_cron(&schedule.cron).ok()?;
+    let since = DateTime::parse_from_rfc3339(since).ok()?.with_timezone(&Utc);
+    cron.after(&since).next()
+}
+
+/// Queues a run of the suite of every enabled schedule due at `now`, skipping schedules whose
+/// last run is still queued or running. Returns the jobs queued.
+pub async fn run_due(pool: &DbPool, now: DateTime<Utc>) -> Vec<Job> {
+    let schedules = match database::get_enabled_schedules(pool).await {
+        Ok(schedules) => schedules,
+        Err(e) => {
+            tracing::error!("Failed to read schedules: {}", e);
+            return Vec::new();
+        }
+    };
+
+    let mut queued = Vec::new();
+    for schedule in schedules {
+        if due_at(&schedule, now).is_none() {
+            continue;
+        }
+        // Claimed before anything runs, so of several servers on one database only one queues it
+        match database::claim_schedule_run(pool, &schedule, &now.to_rfc3339()).await {
+            Ok(true) => {}
+            Ok(false) => continue,
+            Err(e) => {
+                tracing::error!("Failed to claim the run of schedule {}: {}", schedule.id, e);
+                continue;
+            }
+        }
+        if let Some(job_id) = &schedule.last_job_id {
+            match database::get_job(pool, job_id).await {
+                Ok(job) if matches!(job.status.as_str(), "queued" | "running") => {
+                    tracing::warn!("⏰ Skipped a run of schedule {}: its last run, job {}, is still {}", schedule.id, job_id, job.status);
+                    continue;
+                }
+                Ok(_) | Err(sqlx::Error::RowNotFound) => {}
+                Err(e) => {
+                    tracing::error!("Failed to read job {} of schedule {}: {}", job_id, schedule.id, e);
+                    continue;
+                }
+            }
+        }
+        let suite = match database::get_suite(pool, &schedule.suite_id).await {
+            Ok(suite) => suite,
+            Err(e) => {
+                tracing::error!("Failed to read suite {} of schedule {}: {}", schedule.suite_id, schedule.id, e);
+                continue;
+            }
+        };
+        match database::enqueue_scheduled_job(pool, &suite, &schedule.id).await {
+            Ok(job) => {
+                if let Err(e) = database::set_schedule_job(pool, &schedule.id, &job.job_id).await {
+                    tracing::error!("Failed to record job {} on schedule {}: {}", job.job_id, schedule.id, e);
+                }
+                tracing::info!("⏰ Schedule {} queued job {} to run suite '{}'", schedule.id, job.job_id, suite.name);
+                queued.push(job);
+            }
+            Err(e) => tracing::error!("Failed to queue the run of schedule {}: {}", schedule.id, e),
+        }
+    }
+    queued
+}
+
+/// The models that drifted past the server's thresholds since the week before.
+async fn run_drift(state: &AppState, pool: &DbPool) -> Vec<ModelDrift> {
+    let since = drift::lookback_start(Utc::now().date_naive(), drift::LOOKBACK_WEEKS);
+    match database::get_weekly_output_tokens(pool, None, Some(&since), None).await {
+        Ok(weeks) => drift::detect(&weeks, &state.config.drift_thresholds).into_iter().filter(|m| m.drifted).collect(),
+        Err(e) => {
+            tracing::error!("Failed to read weekly output tokens for drift: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Sends the notifications `job`'s schedule asks for, once the job has completed: Slack when
+/// the run is bad enough for `notifier::should_notify` or one of its models drifted, and a
+/// `batch.completed` webhook.
+pub async fn report_run(state: &AppState, pool: &DbPool, job: &Job) {
+    let Some(schedule_id) = &job.schedule_id else {
+        return;
+    };
+    let schedule = match database::get_schedule(pool, schedule_id).await {
+        Ok(schedule) => schedule,
+        // Deleted while its run was going
+        Err(sqlx::Error::RowNotFound) => return,
+        Err(e) => {
+            tracing::error!("Failed to read schedule {} of job {}: {}", schedule_id, job.job_id, e);
+            return;
+        }
+    };
+    let notifiers = if schedule.notify_slack { notifier::from_config(&state.config, &state.client) } else { Vec::new() };
+    let callback = schedule.callback_url.as_ref().zip(state.config.webhook_secret.as_ref());
+    if notifiers.is_empty() && callback.is_none() {
+        return;
+    }
+
+    let evaluations = match database::get_batch_evaluations(pool, &job.batch_id).await {
+        Ok(evaluations) => evaluations,
+        Err(e) => {
+            tracing::error!("Failed to read the evaluations of job {}: {}", job.job_id, e);
+            return;
+        }
+    };
+    let has_status = |entry: &database::HistoryEntry, statuses: &[&str]| entry.status.as_deref().is_some_and(|status| statuses.contains(&status));
+    let failed_statuses = ["failed", "error", "blocked"];
+    let passed = evaluations.iter().filter(|entry| has_status(entry, &["passed"])).count();
+    let failed = evaluations.iter().filter(|entry| has_status(entry, &failed_statuses)).count();
+
+    if !notifiers.is_empty() {
+        let name = match database::get_suite(pool, &schedule.suite_id).await {
+            Ok(suite) => format!("Scheduled run of suite '{}'", suite.name),
+            Err(_) => format!("Schedule {}", schedule.id),
+        };
+        let report = BatchReport {
+            name: name.clone(),
+            batch_id: job.batch_id.clone(),
+            total: job.total as usize,
+            passed,
+            failed,
+            failures: evaluations
+                .iter()
+                .filter(|entry| has_status(entry, &failed_statuses))
+                .take(notifier::MAX_LISTED_FAILURES)
+                .map(|entry| FailedEval {
+                    eval_id: entry.id.clone(),
+                    model: entry.model.clone().unwrap_or_default(),
+                    prompt: entry.prompt.clone().unwrap_or_default(),
+                    status: entry.status.clone().unwrap_or_default(),
+                })
+                .collect(),
+            link: None,
+        };
+        if notifier::should_notify(&state.config, &report) {
+            notifier::dispatch(notifiers.clone(), report);
+        }
+
+        let models: BTreeSet<&str> = evaluations.iter().filter_map(|entry| entry.model.as_deref()).collect();
+        let drifted = run_drift(state, pool).await.into_iter().filter(|m| models.contains(m.model.as_str())).collect::<Vec<_>>();
+        if !drifted.is_empty() {
+            notifier::dispatch_drift(notifiers, DriftReport { name, batch_id: job.batch_id.clone(), models: drifted });
+        }
+    }
+
+    if let Some((url, secret)) = callback {
+        let latencies: Vec<i64> = evaluations.iter().filter_map(|entry| entry.latency_ms).collect();
+        let payload = BatchWebhook {
+            batch_id: job.batch_id.clone(),
+            status: "completed".to_string(),
+            total: job.total as usize,
+            completed: evaluations.len(),
+            passed,
+            failed,
+            needs_review: evaluations.iter().filter(|entry| has_status(entry, &["needs_review"])).count(),
+            total_cost_usd: evaluations.iter().map(|entry| entry.cost_usd.unwrap_or(0.0) + entry.judge_cost_usd.unwrap_or(0.0)).sum(),
+            average_model_latency_ms: if latencies.is_empty() { 0 } else { (latencies.iter().sum::<i64>() / latencies.len() as i64) as u64 },
+            completed_at: Utc::now().to_rfc3339(),
+        };
+        webhook::spawn(state.client.clone(), secret.clone(), url.clone(), WebhookPayload::BatchCompleted(payload));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::handlers::WsBroker;
+    use crate::config::EvalConfig;
+    use crate::database::ScheduleSettings;
+    use crate::jobs::{JobWorker, WorkerOptions};
+    use serde_json::json;
+
+    fn at(time: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(time).unwrap().with_timezone(&Utc)
+    }
+
+    fn schedule(cron: &str, created_at: &str, last_run_at: Option<&str>) -> Schedule {
+        Schedule {
+            id: "s1".to_string(),
+            suite_id: "suite".to_string(),
+            cron: cron.to_string(),
+            enabled: true,
+            notify_slack: false,
+            callback_url: None,
+            last_run_at: last_run_at.map(str::to_string),
+            last_job_id: None,
+            created_at: created_at.to_string(),
+            updated_at: created_at.to_string(),
+        }
+    }
+
+    async fn suite(pool: &DbPool) -> database::Suite {
+        let configs: Vec<EvalConfig> = vec![serde_json::from_value(json!({ "model": "ollama:qwen3", "prompt": "2+2?" })).unwrap()];
+        database::create_suite(pool, "nightly".to_string(), None, &configs).await.unwrap()
+    }
+
+    fn settings(suite_id: &str, cron: &str) -> ScheduleSettings {
+        ScheduleSettings { suite_id: suite_id.to_string(), cron: cron.to_string(), enabled: true, notify_slack: false, callback_url: None }
+    }
+
+    #[test]
+    fn test_cron_expressions_take_5_to_7_fields() {
+        assert!(parse_cron("0 2 * * *").is_ok());
+        assert!(parse_cron("*/5 0 2 * * Mon-Fri").is_ok());
+        assert!(parse_cron("@daily").is_ok());
+        assert!(parse_cron("every night").is_err());
+        assert!(parse_cron("61 * * * *").is_err());
+    }
+
+    #[test]
+    fn test_a_schedule_is_due_once_its_next_firing_has_passed() {
+        let nightly = schedule("0 2 * * *", "2026-10-16T12:00:00Z", None);
+        assert_eq!(due_at(&nightly, at("2026-10-17T01:59:59Z")), None);
+        assert_eq!(due_at(&nightly, at("2026-10-17T02:00:00Z")), Some(at("2026-10-17T02:00:00Z")));
+
+        // A scheduler that was down for days catches up with one run, not one per missed night
+        let ran = schedule("0 2 * * *", "2026-10-01T12:00:00Z", Some("2026-10-17T02:00:00Z"));
+        assert_eq!(due_at(&ran, at("2026-10-17T23:00:00Z")), None);
+        assert_eq!(due_at(&ran, at("2026-10-20T09:00:00Z")), Some(at("2026-10-18T02:00:00Z")));
+        assert_eq!(due_at(&schedule("not cron", "2026-10-16T12:00:00Z", None), at("2027-01-01T00:00:00Z")), None);
+    }
+
+    #[tokio::test]
+    async fn test_due_schedules_queue_a_suite_job_and_overlapping_runs_are_skipped() {
+        let pool = database::test_pool().await;
+        let suite = suite(&pool).await;
+        let every_second = database::create_schedule(&pool, &settings(&suite.id, "* * * * * *")).await.unwrap();
+        let yearly = database::create_schedule(&pool, &settings(&suite.id, "0 0 1 1 *")).await.unwrap();
+        let disabled = ScheduleSettings { enabled: false, ..settings(&suite.id, "* * * * * *") };
+        database::create_schedule(&pool, &disabled).await.unwrap();
+        let created = at(&every_second.created_at);
+
+        let queued = run_due(&pool, created + chrono::Duration::seconds(2)).await;
+        assert_eq!(queued.len(), 1);
+        let job = &queued[0];
+        assert_eq!((job.kind.as_str(), job.suite_id.as_deref(), job.schedule_id.as_deref()), ("suite", Some(suite.id.as_str()), Some(every_second.id.as_str())));
+        let stored = database::get_schedule(&pool, &every_second.id).await.unwrap();
+        assert_eq!(stored.last_job_id.as_deref(), Some(job.job_id.as_str()));
+        assert_eq!(database::get_schedule(&pool, &yearly.id).await.unwrap().last_run_at, None);
+
+        // The first run is still queued, so the next firing is skipped but still counts as run
+        let later = created + chrono::Duration::seconds(4);
+        assert!(run_due(&pool, later).await.is_empty());
+        let skipped = database::get_schedule(&pool, &every_second.id).await.unwrap();
+        assert_eq!((skipped.last_run_at, skipped.last_job_id.as_deref()), (Some(later.to_rfc3339()), Some(job.job_id.as_str())));
+
+        assert!(database::cancel_job(&pool, &job.job_id).await.unwrap());
+        let queued = run_due(&pool, created + chrono::Duration::seconds(6)).await;
+        assert_eq!(queued.len(), 1);
+        assert_ne!(queued[0].job_id, job.job_id);
+
+        // A schedule read before another scheduler claimed its run is not queued twice
+        let stale = database::get_schedule(&pool, &every_second.id).await.unwrap();
+        assert!(database::claim_schedule_run(&pool, &stale, &(created + chrono::Duration::seconds(8)).to_rfc3339()).await.unwrap());
+        assert!(!database::claim_schedule_run(&pool, &stale, &(created + chrono::Duration::seconds(8)).to_rfc3339()).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_a_scheduled_run_goes_through_the_job_worker_and_calls_its_webhook() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "response": "4", "done": true })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST")).and(path("/hooks/nightly")).respond_with(ResponseTemplate::new(200)).mount(&server).await;
+
+        let pool = Arc::new(database::test_pool().await);
+        let state = AppState {
+            config: Arc::new(crate::config::AppConfig {
+                ollama: Some(crate::config::OllamaConfig { api_base: server.uri(), models: vec![] }),
+                webhook_secret: Some("secret".to_string()),
+                ..Default::default()
+            }),
+            client: reqwest::Client::new(),
+            db_pool: Some(pool.clone()),
+            backup_in_progress: Default::default(),
+            active_batches: Default::default(),
+            retention: Default::default(),
+            rate_limiter: Default::default(),
+            provider_health: Default::default(),
+            progress: Default::default(),
+        };
+        let suite = suite(&pool).await;
+        let callback = ScheduleSettings { callback_url: Some(format!("{}/hooks/nightly", server.uri())), ..settings(&suite.id, "* * * * * *") };
+        let schedule = database::create_schedule(&pool, &callback).await.unwrap();
+
+        let scheduler = Scheduler::spawn(pool.clone(), SchedulerOptions { tick: Duration::from_millis(100) });
+        let worker = JobWorker::spawn(state, WsBroker::new(), WorkerOptions { poll_interval: Duration::from_millis(10), ..Default::default() });
+        let mut delivered = Vec::new();
+        for _ in 0..500 {
+            let requests = server.received_requests().await.unwrap();
+            delivered = requests.into_iter().filter(|r| r.url.path() == "/hooks/nightly").collect();
+            if !delivered.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        scheduler.shutdown().await;
+        worker.shutdown().await;
+
+        let WebhookPayload::BatchCompleted(batch) = serde_json::from_slice(&delivered[0].body).unwrap() else {
+            panic!("expected batch.completed");
+        };
+        assert_eq!((batch.total, batch.completed), (1, 1));
+        assert!(database::get_schedule(&pool, &schedule.id).await.unwrap().last_job_id.is_some());
+        let runs = database::get_suite_runs(&pool, &suite.id).await.unwrap();
+        assert!(runs.iter().any(|run| run.batch_id == batch.batch_id));
+    }
+}
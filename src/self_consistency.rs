@@ -0,0 +1,331 @@
+// src/self_consistency.rs
+//! Self-consistency evals: the model is sampled several times for the same prompt, each
+//! sample is judged on its own and the verdicts are combined by a strategy into one result.
+use serde::{Deserialize, Serialize};
+use crate::errors::Result;
+use crate::providers::TokenUsage;
+use crate::runner::{self, EvalResult, JudgeResult, JudgeVerdict};
+
+/// Most samples one eval may ask for.
+pub const MAX_SAMPLES: u32 = 16;
+
+/// Judge model recorded for an aggregate verdict no sample's judge gave.
+pub const SELF_CONSISTENCY_JUDGE: &str = "self-consistency";
+
+/// `self_consistency` of an eval config: how many samples to generate and how their
+/// verdicts make the eval's.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SelfConsistency {
+    /// Number of outputs generated for the prompt, from 2 to `MAX_SAMPLES`.
+    pub samples: u32,
+    #[serde(default)]
+    pub strategy: ConsistencyStrategy,
+}
+
+/// How the verdicts of the samples combine into the eval's.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConsistencyStrategy {
+    /// Pass when more than half the samples pass.
+    #[default]
+    Majority,
+    /// Pass when at least one sample passes.
+    Any,
+    /// Pass only when every sample passes.
+    All,
+}
+
+impl ConsistencyStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConsistencyStrategy::Majority => "majority",
+            ConsistencyStrategy::Any => "any",
+            ConsistencyStrategy::All => "all",
+        }
+    }
+
+    /// Whether `passed` passing samples out of `samples` satisfy the strategy.
+    fn satisfied(&self, passed: usize, samples: usize) -> bool {
+        match self {
+            ConsistencyStrategy::Majority => passed * 2 > samples,
+            ConsistencyStrategy::Any => passed > 0,
+            ConsistencyStrategy::All => passed == samples,
+        }
+    }
+}
+
+/// One sample of a self-consistency eval, as kept in its `SelfConsistencyReport`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SampleOutcome {
+    /// Position of the sample, from 0.
+    pub sample: usize,
+    /// Status the sample would have had as an eval of its own, or `error`.
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_output: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verdict: Option<JudgeVerdict>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_usage: Option<TokenUsage>,
+    /// Cost of the sample's model and judge calls.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_usd: Option<f64>,
+    /// Why the sample failed to generate or be judged.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// How the samples of a self-consistency eval went; `EvalResult::self_consistency`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SelfConsistencyReport {
+    pub strategy: ConsistencyStrategy,
+    pub samples: usize,
+    pub passed: usize,
+    pub failed: usize,
+    /// Samples that were judged Uncertain, or whose judge call failed.
+    pub uncertain: usize,
+    /// Samples whose model call failed.
+    pub errored: usize,
+    pub details: Vec<SampleOutcome>,
+}
+
+/// The verdict `strategy` gives samples with `verdicts`, `None` for a sample without one.
+/// Pass when the passing samples satisfy the strategy, Fail when it could not be satisfied
+/// even if every sample without a Pass or Fail had passed, Uncertain otherwise.
+pub fn aggregate_verdict(strategy: ConsistencyStrategy, verdicts: &[Option<JudgeVerdict>]) -> JudgeVerdict {
+    let samples = verdicts.len();
+    let passed = verdicts.iter().filter(|v| **v == Some(JudgeVerdict::Pass)).count();
+    let failed = verdicts.iter().filter(|v| **v == Some(JudgeVerdict::Fail)).count();
+    if strategy.satisfied(passed, samples) {
+        JudgeVerdict::Pass
+    } else if !strategy.satisfied(samples - failed, samples) {
+        JudgeVerdict::Fail
+    } else {
+        JudgeVerdict::Uncertain
+    }
+}
+
+/// The verdict of one sample, `None` when it was not judged Pass or Fail.
+fn sample_verdict(result: &EvalResult) -> Option<JudgeVerdict> {
+    result.judge_result.as_ref().map(|judge| judge.verdict).filter(|verdict| *verdict != JudgeVerdict::Uncertain)
+}
+
+fn sum_usage(usages: impl Iterator<Item = Option<TokenUsage>>) -> Option<TokenUsage> {
+    let add = |a: Option<u32>, b: Option<u32>| match (a, b) {
+        (None, None) => None,
+        (a, b) => Some(a.unwrap_or(0) + b.unwrap_or(0)),
+    };
+    usages.flatten().reduce(|total, usage| TokenUsage {
+        input_tokens: add(total.input_tokens, usage.input_tokens),
+        output_tokens: add(total.output_tokens, usage.output_tokens),
+    })
+}
+
+fn sum_cost(costs: impl Iterator<Item = Option<f64>>) -> Option<f64> {
+    costs.flatten().reduce(|total, cost| total + cost)
+}
+
+/// Combines the judged `samples` of an eval into its result under `consistency`. The result
+/// is that of the first sample whose verdict agrees with the aggregate one, with tokens and
+/// costs summed over every sample and latencies those of the slowest, as the samples ran at
+/// once. Fails with the first sample's error when no sample produced an output.
+pub fn aggregate(consistency: SelfConsistency, samples: Vec<Result<EvalResult>>) -> Result<EvalResult> {
+    if samples.iter().all(|sample| sample.is_err()) {
+        return Err(samples.into_iter().find_map(|sample| sample.err()).expect("at least one sample"));
+    }
+
+    let verdicts: Vec<_> = samples.iter().map(|sample| sample.as_ref().ok().and_then(sample_verdict)).collect();
+    let verdict = aggregate_verdict(consistency.strategy, &verdicts);
+    let ok: Vec<&EvalResult> = samples.iter().filter_map(|sample| sample.as_ref().ok()).collect();
+    let representative = ok
+        .iter()
+        .find(|result| sample_verdict(result) == Some(verdict))
+        .unwrap_or(&ok[0]);
+    let mut result = (*representative).clone();
+
+    let details: Vec<SampleOutcome> = samples
+        .iter()
+        .enumerate()
+        .map(|(sample, outcome)| match outcome {
+            Ok(result) => SampleOutcome {
+                sample,
+                status: runner::eval_status(result).to_string(),
+                model_output: Some(result.model_output.clone()),
+                verdict: result.judge_result.as_ref().map(|judge| judge.verdict),
+                reasoning: result.judge_result.as_ref().and_then(|judge| judge.reasoning.clone()),
+                latency_ms: Some(result.latency_ms),
+                token_usage: result.token_usage.clone(),
+                cost_usd: sum_cost([result.cost_usd, result.judge_cost_usd].into_iter()),
+                error: result.judge_error.as_ref().map(|error| error.message.clone()),
+            },
+            Err(e) => SampleOutcome {
+                sample,
+                status: "error".to_string(),
+                model_output: None,
+                verdict: None,
+                reasoning: None,
+                latency_ms: None,
+                token_usage: None,
+                cost_usd: None,
+                error: Some(e.to_string()),
+            },
+        })
+        .collect();
+    let report = SelfConsistencyReport {
+        strategy: consistency.strategy,
+        samples: samples.len(),
+        passed: verdicts.iter().filter(|v| **v == Some(JudgeVerdict::Pass)).count(),
+        failed: verdicts.iter().filter(|v| **v == Some(JudgeVerdict::Fail)).count(),
+        uncertain: ok.iter().filter(|result| sample_verdict(result).is_none()).count(),
+        errored: samples.len() - ok.len(),
+        details,
+    };
+
+    result.token_usage = sum_usage(ok.iter().map(|result| result.token_usage.clone()));
+    result.judge_token_usage = sum_usage(ok.iter().map(|result| result.judge_token_usage.clone()));
+    result.cost_usd = sum_cost(ok.iter().map(|result| result.cost_usd));
+    result.judge_cost_usd = sum_cost(ok.iter().map(|result| result.judge_cost_usd));
+    result.latency_ms = ok.iter().map(|result| result.latency_ms).max().unwrap_or_default();
+    result.judge_latency_ms = ok.iter().filter_map(|result| result.judge_latency_ms).max();
+    result.total_latency_ms = ok.iter().map(|result| result.total_latency_ms).max().unwrap_or_default();
+    result.wait_ms = ok.iter().filter_map(|result| result.wait_ms).max();
+
+    let summary = format!(
+        "{} of {} samples passed ({})",
+        report.passed, report.samples, consistency.strategy.as_str()
+    );
+    let agrees = sample_verdict(representative) == Some(verdict);
+    result.judge_result = Some(match result.judge_result.take().filter(|_| agrees) {
+        Some(judge) => JudgeResult {
+            reasoning: Some(match judge.reasoning {
+                Some(reasoning) => format!("{}. {}", summary, reasoning),
+                None => summary,
+            }),
+            ..judge
+        },
+        None => JudgeResult {
+            judge_model: SELF_CONSISTENCY_JUDGE.to_string(),
+            verdict,
+            reasoning: Some(summary),
+            confidence: None,
+            raw_response: None,
+            uncertain_reason: None,
+        },
+    });
+    if !agrees {
+        // The verdict is the samples', not that of a failed judge call of the representative
+        result.judge_error = None;
+        result.failure_classification = None;
+    }
+    result.needs_review = ok.iter().any(|result| result.needs_review);
+    result.self_consistency = Some(report);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::EvalError;
+    use JudgeVerdict::{Fail, Pass, Uncertain};
+
+    #[test]
+    fn test_aggregate_verdict_follows_the_strategy() {
+        let cases = [
+            (ConsistencyStrategy::Majority, vec![Some(Pass), Some(Pass), Some(Fail)], Pass),
+            (ConsistencyStrategy::Majority, vec![Some(Pass), Some(Fail), Some(Fail)], Fail),
+            (ConsistencyStrategy::Majority, vec![Some(Pass), Some(Fail)], Fail),
+            (ConsistencyStrategy::Majority, vec![Some(Pass), Some(Fail), None], Uncertain),
+            (ConsistencyStrategy::Any, vec![Some(Fail), Some(Pass), Some(Fail)], Pass),
+            (ConsistencyStrategy::Any, vec![Some(Fail), Some(Fail), Some(Fail)], Fail),
+            (ConsistencyStrategy::Any, vec![Some(Fail), None, Some(Fail)], Uncertain),
+            (ConsistencyStrategy::All, vec![Some(Pass), Some(Pass), Some(Pass)], Pass),
+            (ConsistencyStrategy::All, vec![Some(Pass), None, Some(Fail)], Fail),
+            (ConsistencyStrategy::All, vec![Some(Pass), None, Some(Pass)], Uncertain),
+        ];
+        for (strategy, verdicts, expected) in cases {
+            assert_eq!(aggregate_verdict(strategy, &verdicts), expected, "{:?} {:?}", strategy, verdicts);
+        }
+    }
+
+    #[test]
+    fn test_self_consistency_config_defaults_to_majority() {
+        let consistency: SelfConsistency = serde_json::from_str(r#"{"samples": 3}"#).unwrap();
+        assert_eq!(consistency, SelfConsistency { samples: 3, strategy: ConsistencyStrategy::Majority });
+        assert!(serde_json::from_str::<SelfConsistency>(r#"{"samples": 3, "strategy": "most"}"#).is_err());
+    }
+
+    fn sample(output: &str, verdict: JudgeVerdict) -> EvalResult {
+        let mut result: EvalResult = serde_json::from_value(serde_json::json!({
+            "model": "ollama:llama3",
+            "prompt": "2+2?",
+            "model_output": output,
+            "parsed_output": null,
+            "expected": "4",
+            "timestamp": "2026-10-17T00:00:00Z",
+            "latency_ms": output.len() as u64 * 10,
+            "judge_latency_ms": 5,
+            "token_usage": { "input_tokens": 10, "output_tokens": 2 },
+            "judge_token_usage": { "input_tokens": 30, "output_tokens": 5 },
+            "cost_usd": 0.01,
+            "judge_cost_usd": 0.002,
+            "total_latency_ms": output.len() as u64 * 10 + 5,
+            "judge_prompt_version": null,
+            "early_abort": null,
+        }))
+        .unwrap();
+        result.judge_result = Some(JudgeResult {
+            judge_model: "ollama:judge".to_string(),
+            verdict,
+            reasoning: Some("Compared".to_string()),
+            confidence: None,
+            raw_response: None,
+            uncertain_reason: None,
+        });
+        result
+    }
+
+    #[test]
+    fn test_aggregate_sums_usage_and_keeps_a_sample_that_agrees() {
+        let consistency = SelfConsistency { samples: 3, strategy: ConsistencyStrategy::Majority };
+        let samples = vec![
+            Ok(sample("5", Fail)),
+            Ok(sample("4", Pass)),
+            Err(EvalError::Config("timed out".to_string())),
+        ];
+        let result = aggregate(consistency, samples).unwrap();
+
+        // One pass and one fail with the third sample unknown could go either way
+        let judge = result.judge_result.as_ref().unwrap();
+        assert_eq!((judge.verdict, judge.judge_model.as_str()), (Uncertain, SELF_CONSISTENCY_JUDGE));
+        assert_eq!(judge.reasoning.as_deref(), Some("1 of 3 samples passed (majority)"));
+        assert_eq!(result.model_output, "5");
+        assert_eq!(result.token_usage, Some(TokenUsage { input_tokens: Some(20), output_tokens: Some(4) }));
+        assert!((result.cost_usd.unwrap() - 0.02).abs() < 1e-9);
+        assert_eq!(result.judge_token_usage, Some(TokenUsage { input_tokens: Some(60), output_tokens: Some(10) }));
+        assert!((result.judge_cost_usd.unwrap() - 0.004).abs() < 1e-9);
+        let report = result.self_consistency.unwrap();
+        assert_eq!((report.passed, report.failed, report.uncertain, report.errored), (1, 1, 0, 1));
+        assert_eq!(report.details[2].status, "error");
+        assert_eq!(report.details[1].verdict, Some(Pass));
+
+        let consistency = SelfConsistency { samples: 2, strategy: ConsistencyStrategy::Any };
+        let result = aggregate(consistency, vec![Ok(sample("5", Fail)), Ok(sample("4", Pass))]).unwrap();
+        let judge = result.judge_result.unwrap();
+        assert_eq!((judge.verdict, judge.judge_model.as_str()), (Pass, "ollama:judge"));
+        assert_eq!(judge.reasoning.as_deref(), Some("1 of 2 samples passed (any). Compared"));
+        assert_eq!(result.model_output, "4");
+    }
+
+    #[test]
+    fn test_aggregate_fails_when_every_sample_failed() {
+        let consistency = SelfConsistency { samples: 2, strategy: ConsistencyStrategy::All };
+        let samples = vec![Err(EvalError::Config("first".to_string())), Err(EvalError::Config("second".to_string()))];
+        let error = aggregate(consistency, samples).unwrap_err();
+        assert!(error.to_string().contains("first"), "{}", error);
+    }
+}
@@ -0,0 +1,146 @@
+// src/share.rs
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+
+use crate::database::HistoryEntry;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Lifetime of a share link when neither the request nor `SHARE_TTL_HOURS` sets one.
+pub const DEFAULT_SHARE_TTL_HOURS: u64 = 72;
+
+/// Why a share token was not accepted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenError {
+    /// Not a token, or not signed with the current secret.
+    Invalid,
+    /// Correctly signed, but past its expiry.
+    Expired,
+}
+
+/// The share a valid token points to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShareToken {
+    pub share_id: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+fn signature(secret: &str, payload: &str) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(payload.as_bytes());
+    mac
+}
+
+fn hex_decode(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// A token for a share: `<share id>.<expiry as unix seconds>.<hex HMAC-SHA256 of both>`.
+pub fn sign(secret: &str, share_id: &str, expires_at: DateTime<Utc>) -> String {
+    let payload = format!("{}.{}", share_id, expires_at.timestamp());
+    let mac = signature(secret, &payload).finalize().into_bytes();
+    let hex: String = mac.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}.{}", payload, hex)
+}
+
+/// Checks a token's signature and expiry. Expiry is only reported for correctly signed
+/// tokens, so a forged token cannot tell an expired share from a missing one.
+pub fn verify(secret: &str, token: &str, now: DateTime<Utc>) -> Result<ShareToken, TokenError> {
+    let (payload, hex) = token.rsplit_once('.').ok_or(TokenError::Invalid)?;
+    let mac = hex_decode(hex).ok_or(TokenError::Invalid)?;
+    signature(secret, payload).verify_slice(&mac).map_err(|_| TokenError::Invalid)?;
+
+    let (share_id, expires_at) = payload.split_once('.').ok_or(TokenError::Invalid)?;
+    let expires_at = expires_at
+        .parse::<i64>()
+        .ok()
+        .and_then(|seconds| DateTime::from_timestamp(seconds, 0))
+        .ok_or(TokenError::Invalid)?;
+    if expires_at <= now {
+        return Err(TokenError::Expired);
+    }
+
+    Ok(ShareToken { share_id: share_id.to_string(), expires_at })
+}
+
+/// The read-only view of an evaluation a share link exposes; nothing else of the
+/// evaluation (costs, judge internals, batch or tags) leaves the server.
+#[derive(Debug, Serialize)]
+pub struct SharedEvaluation {
+    pub id: String,
+    pub model: Option<String>,
+    pub prompt: Option<String>,
+    pub output: Option<String>,
+    pub expected: Option<String>,
+    /// The human verdict when the evaluation was reviewed, the judge's otherwise.
+    pub verdict: Option<String>,
+    pub reasoning: Option<String>,
+    pub created_at: String,
+    /// When the link stops working.
+    pub expires_at: String,
+}
+
+impl SharedEvaluation {
+    pub fn new(entry: HistoryEntry, expires_at: String) -> Self {
+        Self {
+            id: entry.id,
+            model: entry.model,
+            prompt: entry.prompt,
+            output: entry.model_output,
+            expected: entry.expected,
+            verdict: entry.human_verdict.or(entry.judge_verdict),
+            reasoning: entry.judge_reasoning,
+            created_at: entry.created_at,
+            expires_at,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    const SECRET: &str = "test-secret";
+
+    #[test]
+    fn test_signed_token_verifies_until_it_expires() {
+        let now = Utc::now();
+        let expires_at = DateTime::from_timestamp(now.timestamp() + 3600, 0).unwrap();
+        let token = sign(SECRET, "share-1", expires_at);
+
+        let verified = verify(SECRET, &token, now).unwrap();
+        assert_eq!(verified, ShareToken { share_id: "share-1".to_string(), expires_at });
+        assert_eq!(verify(SECRET, &token, now + Duration::hours(2)), Err(TokenError::Expired));
+    }
+
+    #[test]
+    fn test_tampered_or_foreign_tokens_are_invalid() {
+        let now = Utc::now();
+        let token = sign(SECRET, "share-1", now + Duration::hours(1));
+
+        assert_eq!(verify("other-secret", &token, now), Err(TokenError::Invalid));
+        let retargeted = token.replacen("share-1", "share-2", 1);
+        assert_eq!(verify(SECRET, &retargeted, now), Err(TokenError::Invalid));
+        let (payload, _) = token.rsplit_once('.').unwrap();
+        let extended = format!("{}9.{}", payload, token.rsplit_once('.').unwrap().1);
+        assert_eq!(verify(SECRET, &extended, now), Err(TokenError::Invalid));
+        assert_eq!(verify(SECRET, "not-a-token", now), Err(TokenError::Invalid));
+        assert_eq!(verify(SECRET, "a.b.zz", now), Err(TokenError::Invalid));
+    }
+
+    #[test]
+    fn test_expired_forgeries_are_invalid_not_expired() {
+        let now = Utc::now();
+        let token = sign("other-secret", "share-1", now - Duration::hours(1));
+        assert_eq!(verify(SECRET, &token, now), Err(TokenError::Invalid));
+    }
+}
@@ -0,0 +1,176 @@
+// src/static_files.rs
+use actix_web::{http::header, web, HttpRequest, HttpResponse};
+use rust_embed::RustEmbed;
+use sha2::{Digest, Sha256};
+use std::path::{Component, Path, PathBuf};
+
+#[derive(RustEmbed)]
+#[folder = "static/"]
+struct StaticAssets;
+
+/// Optional directory whose files take precedence over the embedded dashboard assets,
+/// so the dashboard can be customized without rebuilding the binary.
+#[derive(Debug, Clone, Default)]
+pub struct StaticOverrides {
+    /// Canonicalized, so resolved files can be checked to lie inside it.
+    dir: Option<PathBuf>,
+}
+
+impl StaticOverrides {
+    /// Overrides from `dir`; a directory that does not exist disables overrides with a warning.
+    pub fn new(dir: Option<PathBuf>) -> Self {
+        let dir = dir.and_then(|dir| match dir.canonicalize() {
+            Ok(canonical) if canonical.is_dir() => Some(canonical),
+            _ => {
+                tracing::warn!("STATIC_OVERRIDE_DIR {} is not a directory; serving embedded assets only", dir.display());
+                None
+            }
+        });
+        Self { dir }
+    }
+
+    /// Reads `STATIC_OVERRIDE_DIR`.
+    pub fn from_env() -> Self {
+        Self::new(
+            std::env::var("STATIC_OVERRIDE_DIR")
+                .ok()
+                .filter(|dir| !dir.trim().is_empty())
+                .map(PathBuf::from),
+        )
+    }
+
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    /// Number of files in the override directory, including subdirectories.
+    pub fn count(&self) -> usize {
+        fn count_files(dir: &Path) -> usize {
+            std::fs::read_dir(dir)
+                .map(|entries| {
+                    entries
+                        .flatten()
+                        .map(|entry| {
+                            let path = entry.path();
+                            if path.is_dir() { count_files(&path) } else { 1 }
+                        })
+                        .sum()
+                })
+                .unwrap_or(0)
+        }
+        self.dir.as_deref().map_or(0, count_files)
+    }
+
+    /// The override file for `relative`, if one exists inside the override directory.
+    /// Symlinks pointing outside the directory are ignored.
+    fn resolve(&self, relative: &Path) -> Option<PathBuf> {
+        let dir = self.dir.as_ref()?;
+        let candidate = dir.join(relative).canonicalize().ok()?;
+        (candidate.starts_with(dir) && candidate.is_file()).then_some(candidate)
+    }
+}
+
+/// `path` as a relative path made only of normal components, or `None` for anything that
+/// could escape the asset root (`..`, absolute paths, drive prefixes) or is empty.
+pub fn safe_relative_path(path: &str) -> Option<PathBuf> {
+    let path = Path::new(path);
+    let safe = path.components().next().is_some()
+        && path.components().all(|c| matches!(c, Component::Normal(_)));
+    safe.then(|| path.to_path_buf())
+}
+
+/// A static file ready to serve.
+#[derive(Debug)]
+pub struct Asset {
+    pub data: Vec<u8>,
+    /// Quoted content hash.
+    pub etag: String,
+    /// Served from the override directory rather than the embedded assets.
+    pub overridden: bool,
+}
+
+/// Looks `path` up in the override directory first, then in the embedded assets.
+pub async fn find_asset(overrides: &StaticOverrides, path: &str) -> Option<Asset> {
+    let relative = safe_relative_path(path)?;
+
+    if let Some(file) = overrides.resolve(&relative) {
+        match tokio::fs::read(&file).await {
+            Ok(data) => {
+                let etag = format!("\"{:x}\"", Sha256::digest(&data));
+                return Some(Asset { data, etag, overridden: true });
+            }
+            Err(e) => tracing::warn!("Could not read static override {}: {}", file.display(), e),
+        }
+    }
+
+    let embedded = StaticAssets::get(path)?;
+    let hash: String = embedded.metadata.sha256_hash().iter().map(|b| format!("{:02x}", b)).collect();
+    Some(Asset {
+        data: embedded.data.into_owned(),
+        etag: format!("\"{}\"", hash),
+        overridden: false,
+    })
+}
+
+/// Serves the dashboard. Assets are revalidated on every use (`no-cache`) against their ETag,
+/// so edits to overrides show up immediately while unchanged files cost a 304.
+pub async fn static_file_handler(req: HttpRequest, overrides: web::Data<StaticOverrides>) -> HttpResponse {
+    let path = if req.path() == "/" {
+        "index.html"
+    } else {
+        &req.path()[1..]
+    };
+
+    let Some(asset) = find_asset(&overrides, path).await else {
+        return HttpResponse::NotFound().body("404 Not Found");
+    };
+
+    let unchanged = req
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|tags| tags.split(',').any(|tag| tag.trim() == asset.etag));
+    if unchanged {
+        return HttpResponse::NotModified()
+            .insert_header((header::ETAG, asset.etag))
+            .finish();
+    }
+
+    let mime = mime_guess::from_path(path).first_or_octet_stream();
+    HttpResponse::Ok()
+        .content_type(mime.as_ref())
+        .insert_header((header::ETAG, asset.etag))
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        .body(asset.data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_overrides_shadow_embedded_files_without_escaping_the_directory() {
+        let root = std::env::temp_dir().join(format!("evaluate-static-test-{}", uuid::Uuid::new_v4()));
+        let dir = root.join("overrides");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("index.html"), "<h1>Custom</h1>").unwrap();
+        std::fs::write(root.join("secret.txt"), "secret").unwrap();
+
+        let overrides = StaticOverrides::new(Some(dir.clone()));
+        assert_eq!(overrides.count(), 1);
+
+        let index = find_asset(&overrides, "index.html").await.unwrap();
+        assert!(index.overridden);
+        assert_eq!(index.data, b"<h1>Custom</h1>");
+
+        let script = find_asset(&overrides, "script.js").await.unwrap();
+        assert!(!script.overridden);
+        assert_ne!(script.etag, index.etag);
+
+        for attempt in ["../secret.txt", "../../etc/passwd", "/etc/passwd", "css/../../secret.txt", ""] {
+            assert!(find_asset(&overrides, attempt).await.is_none(), "{} was served", attempt);
+        }
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,253 @@
+// src/stats.rs
+//! Latency distributions over the results of a batch, and the agreement and bias statistics
+//! of judge calibrations.
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::runner::{EvalResult, JudgeVerdict};
+
+/// Nearest-rank percentile of ascending `sorted` values.
+pub fn percentile<T: Copy>(sorted: &[T], p: f64) -> Option<T> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
+
+/// Spread of a set of latencies in milliseconds, with nearest-rank percentiles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Distribution {
+    pub count: usize,
+    pub min: u64,
+    pub p50: u64,
+    pub p90: u64,
+    pub p99: u64,
+    pub max: u64,
+}
+
+impl Distribution {
+    /// The distribution of `values`; `None` when there are none.
+    pub fn of(mut values: Vec<u64>) -> Option<Self> {
+        values.sort_unstable();
+        Some(Distribution {
+            count: values.len(),
+            min: *values.first()?,
+            p50: percentile(&values, 50.0)?,
+            p90: percentile(&values, 90.0)?,
+            p99: percentile(&values, 99.0)?,
+            max: *values.last()?,
+        })
+    }
+}
+
+/// Latency distributions of a set of eval results. Evals without a judge call are left out
+/// of `judge`; cached outputs, which waited for nothing, are left out of `wait`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LatencyStats {
+    /// `EvalResult::latency_ms`, the time the provider took to answer.
+    pub model: Option<Distribution>,
+    pub judge: Option<Distribution>,
+    pub total: Option<Distribution>,
+    /// `EvalResult::wait_ms`, the time spent on rate limits and retries before the answer.
+    pub wait: Option<Distribution>,
+}
+
+impl LatencyStats {
+    pub fn of<'a>(results: impl IntoIterator<Item = &'a EvalResult> + Clone) -> Self {
+        let collect = |field: fn(&EvalResult) -> Option<u64>| Distribution::of(results.clone().into_iter().filter_map(field).collect());
+        LatencyStats {
+            model: collect(|r| Some(r.latency_ms)),
+            judge: collect(|r| r.judge_latency_ms),
+            total: collect(|r| Some(r.total_latency_ms)),
+            wait: collect(|r| r.wait_ms),
+        }
+    }
+}
+
+/// Latency distributions of a batch, overall and, when it mixes models, per model.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BatchLatency {
+    #[serde(flatten)]
+    pub overall: LatencyStats,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub by_model: BTreeMap<String, LatencyStats>,
+}
+
+/// Latency distributions of the evals that produced `results`; failed evals have none.
+pub fn batch_latency(results: &[&EvalResult]) -> BatchLatency {
+    let mut by_model: BTreeMap<&str, Vec<&EvalResult>> = BTreeMap::new();
+    for result in results {
+        by_model.entry(result.model.as_str()).or_default().push(result);
+    }
+    let by_model = if by_model.len() > 1 {
+        by_model.into_iter().map(|(model, results)| (model.to_string(), LatencyStats::of(results.iter().copied()))).collect()
+    } else {
+        BTreeMap::new()
+    };
+    BatchLatency { overall: LatencyStats::of(results.iter().copied()), by_model }
+}
+
+/// How a judge's verdicts agree with human verdicts of Pass or Fail. The judge's Uncertain
+/// verdicts count against its accuracy but as neither a false pass nor a false fail.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct VerdictAgreement {
+    /// Evaluations the judge gave a verdict on.
+    pub judged: usize,
+    pub correct: usize,
+    /// Judged Pass where the human verdict is Fail.
+    pub false_passes: usize,
+    /// Judged Fail where the human verdict is Pass.
+    pub false_fails: usize,
+    /// `correct / judged`
+    pub accuracy: Option<f64>,
+    /// `false_passes` over the evaluations humans failed.
+    pub false_pass_rate: Option<f64>,
+    /// `false_fails` over the evaluations humans passed.
+    pub false_fail_rate: Option<f64>,
+}
+
+/// `numerator / denominator`, `None` when there is nothing to divide by.
+fn rate(numerator: usize, denominator: usize) -> Option<f64> {
+    (denominator > 0).then(|| numerator as f64 / denominator as f64)
+}
+
+/// Agreement of `(human, judge)` verdict pairs. Pairs whose human verdict is neither Pass
+/// nor Fail are left out.
+pub fn verdict_agreement(pairs: &[(JudgeVerdict, JudgeVerdict)]) -> VerdictAgreement {
+    let pairs: Vec<_> = pairs.iter().filter(|(human, _)| *human != JudgeVerdict::Uncertain).collect();
+    let count = |human: JudgeVerdict, judge: JudgeVerdict| pairs.iter().filter(|pair| ***pair == (human, judge)).count();
+    let human_fails = pairs.iter().filter(|(human, _)| *human == JudgeVerdict::Fail).count();
+    let correct = pairs.iter().filter(|(human, judge)| human == judge).count();
+    let false_passes = count(JudgeVerdict::Fail, JudgeVerdict::Pass);
+    let false_fails = count(JudgeVerdict::Pass, JudgeVerdict::Fail);
+    VerdictAgreement {
+        judged: pairs.len(),
+        correct,
+        false_passes,
+        false_fails,
+        accuracy: rate(correct, pairs.len()),
+        false_pass_rate: rate(false_passes, human_fails),
+        false_fail_rate: rate(false_fails, pairs.len() - human_fails),
+    }
+}
+
+/// Share of `(before, after)` verdict pairs that differ; `None` when there are none.
+pub fn flip_rate(pairs: &[(JudgeVerdict, JudgeVerdict)]) -> Option<f64> {
+    rate(pairs.iter().filter(|(before, after)| before != after).count(), pairs.len())
+}
+
+/// Pearson correlation of `(x, y)` points, from -1 to 1. `None` for fewer than two points or
+/// when either coordinate never varies.
+pub fn pearson_correlation(points: &[(f64, f64)]) -> Option<f64> {
+    if points.len() < 2 {
+        return None;
+    }
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let (mut covariance, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+    for (x, y) in points {
+        covariance += (x - mean_x) * (y - mean_y);
+        var_x += (x - mean_x).powi(2);
+        var_y += (y - mean_y).powi(2);
+    }
+    (var_x > 0.0 && var_y > 0.0).then(|| covariance / (var_x * var_y).sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(model: &str, latency_ms: u64, judge_latency_ms: Option<u64>, wait_ms: Option<u64>) -> EvalResult {
+        serde_json::from_value(serde_json::json!({
+            "model": model, "prompt": "", "model_output": "", "parsed_output": null, "expected": null,
+            "judge_result": null, "timestamp": "", "latency_ms": latency_ms, "judge_latency_ms": judge_latency_ms,
+            "token_usage": null, "judge_token_usage": null, "total_latency_ms": latency_ms + judge_latency_ms.unwrap_or(0),
+            "judge_prompt_version": null, "wait_ms": wait_ms,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_percentiles_use_the_nearest_rank() {
+        let values: Vec<u64> = (1..=100).collect();
+        assert_eq!(
+            Distribution::of(values),
+            Some(Distribution { count: 100, min: 1, p50: 50, p90: 90, p99: 99, max: 100 })
+        );
+        // A single slow call dominates p99 of a small batch but not p50 or p90
+        assert_eq!(
+            Distribution::of(vec![120, 80, 100, 90, 110, 95, 105, 85, 115, 5000]),
+            Some(Distribution { count: 10, min: 80, p50: 100, p90: 120, p99: 5000, max: 5000 })
+        );
+        assert_eq!(Distribution::of(vec![7]), Some(Distribution { count: 1, min: 7, p50: 7, p90: 7, p99: 7, max: 7 }));
+        assert_eq!(Distribution::of(Vec::new()), None);
+        assert_eq!(percentile(&[1i64, 2, 3, 4], 95.0), Some(4));
+    }
+
+    #[test]
+    fn test_batch_latency_breaks_mixed_batches_down_by_model() {
+        let results = [
+            result("ollama:llama3", 100, Some(40), Some(0)),
+            result("ollama:llama3", 300, None, Some(250)),
+            result("openai:gpt-4o", 900, Some(60), None),
+        ];
+        let latency = batch_latency(&results.iter().collect::<Vec<_>>());
+        assert_eq!(latency.overall.model.map(|d| (d.min, d.p50, d.max)), Some((100, 300, 900)));
+        assert_eq!(latency.overall.judge.map(|d| (d.count, d.p50)), Some((2, 40)));
+        assert_eq!(latency.overall.total.map(|d| d.max), Some(960));
+        assert_eq!(latency.overall.wait.map(|d| (d.count, d.max)), Some((2, 250)));
+        assert_eq!(latency.by_model.keys().collect::<Vec<_>>(), ["ollama:llama3", "openai:gpt-4o"]);
+        assert_eq!(latency.by_model["ollama:llama3"].model.map(|d| d.p90), Some(300));
+        assert_eq!(latency.by_model["openai:gpt-4o"].judge.map(|d| d.count), Some(1));
+
+        let single = batch_latency(&results[..2].iter().collect::<Vec<_>>());
+        assert!(single.by_model.is_empty());
+        assert_eq!(batch_latency(&[]).overall.model, None);
+    }
+
+    #[test]
+    fn test_verdict_agreement_counts_false_passes_and_fails() {
+        use JudgeVerdict::*;
+        let pairs = [
+            (Pass, Pass), (Pass, Pass), (Pass, Fail), (Pass, Uncertain),
+            (Fail, Fail), (Fail, Pass),
+            // Not a human Pass or Fail, so left out
+            (Uncertain, Pass),
+        ];
+        let agreement = verdict_agreement(&pairs);
+        assert_eq!((agreement.judged, agreement.correct, agreement.false_passes, agreement.false_fails), (6, 3, 1, 1));
+        assert_eq!(agreement.accuracy, Some(0.5));
+        assert_eq!(agreement.false_pass_rate, Some(0.5));
+        assert_eq!(agreement.false_fail_rate, Some(0.25));
+
+        let passes_only = verdict_agreement(&[(Pass, Pass)]);
+        assert_eq!((passes_only.accuracy, passes_only.false_pass_rate, passes_only.false_fail_rate), (Some(1.0), None, Some(0.0)));
+        assert_eq!(verdict_agreement(&[]), VerdictAgreement::default());
+    }
+
+    #[test]
+    fn test_flip_rate_is_the_share_of_changed_verdicts() {
+        use JudgeVerdict::*;
+        assert_eq!(flip_rate(&[(Pass, Pass), (Pass, Fail), (Fail, Fail), (Fail, Uncertain)]), Some(0.5));
+        assert_eq!(flip_rate(&[]), None);
+    }
+
+    #[test]
+    fn test_pearson_correlation_of_synthetic_points() {
+        let close = |a: Option<f64>, b: f64| a.is_some_and(|a| (a - b).abs() < 1e-9);
+        let line: Vec<_> = (0..10).map(|x| (x as f64, 3.0 * x as f64 + 1.0)).collect();
+        assert!(close(pearson_correlation(&line), 1.0));
+        let falling: Vec<_> = line.iter().map(|(x, y)| (*x, -y)).collect();
+        assert!(close(pearson_correlation(&falling), -1.0));
+        // Long outputs pass and short ones fail: the point-biserial case of a verbosity bias
+        let lengths = [(900.0, 1.0), (800.0, 1.0), (850.0, 1.0), (100.0, 0.0), (150.0, 0.0), (120.0, 1.0)];
+        let correlation = pearson_correlation(&lengths).unwrap();
+        assert!(correlation > 0.6 && correlation < 1.0, "{}", correlation);
+        // Symmetric around the mean: no linear relation
+        assert!(close(pearson_correlation(&[(1.0, 1.0), (2.0, 0.0), (3.0, 1.0)]), 0.0));
+        assert_eq!(pearson_correlation(&[(1.0, 1.0), (2.0, 1.0), (3.0, 1.0)]), None);
+        assert_eq!(pearson_correlation(&[(1.0, 2.0)]), None);
+    }
+}
@@ -0,0 +1,309 @@
+// src/template.rs
+//! `{{placeholder}}` templates, as used by eval prompts and judge prompts.
+//!
+//! A placeholder is a path into the data followed by any number of filters:
+//! `{{country}}`, `{{user.name}}`, `{{items[0].title}}`, `{{text | upper}}`, `{{examples | json}}`.
+//! Text between `{{` and `}}` that is not such an expression is left as it is.
+
+use serde_json::Value;
+
+/// A step of a placeholder's path: an object key or an array index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// A filter applied to a placeholder's value, left to right.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Filter {
+    Upper,
+    Lower,
+    Trim,
+    /// The value as pretty-printed JSON.
+    Json,
+}
+
+impl Filter {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "upper" => Some(Filter::Upper),
+            "lower" => Some(Filter::Lower),
+            "trim" => Some(Filter::Trim),
+            "json" => Some(Filter::Json),
+            _ => None,
+        }
+    }
+}
+
+/// A parsed placeholder. Filter names are kept as written so an unknown one can be reported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Expression<'a> {
+    path: Vec<Segment>,
+    filters: Vec<&'a str>,
+}
+
+/// Whether `c` may appear in a key, as in the `\w` of the flat `{{key}}` syntax.
+fn is_key_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Parses the text between `{{` and `}}`; `None` when it is not a placeholder expression.
+fn parse_expression(source: &str) -> Option<Expression<'_>> {
+    let mut parts = source.split('|');
+    let path = parse_path(parts.next()?.trim())?;
+    let filters = parts
+        .map(str::trim)
+        .map(|name| (!name.is_empty() && name.chars().all(is_key_char)).then_some(name))
+        .collect::<Option<Vec<_>>>()?;
+    Some(Expression { path, filters })
+}
+
+/// Parses `key(.key|[index])*`.
+fn parse_path(source: &str) -> Option<Vec<Segment>> {
+    let mut path = Vec::new();
+    let mut rest = source;
+    let mut expect_key = true;
+    while !rest.is_empty() || expect_key {
+        if expect_key {
+            let end = rest.find(|c: char| !is_key_char(c)).unwrap_or(rest.len());
+            if end == 0 {
+                return None;
+            }
+            path.push(Segment::Key(rest[..end].to_string()));
+            rest = &rest[end..];
+            expect_key = false;
+        } else if let Some(after) = rest.strip_prefix('.') {
+            rest = after;
+            expect_key = true;
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let (index, after) = after.split_once(']')?;
+            if index.is_empty() || !index.chars().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            path.push(Segment::Index(index.parse().ok()?));
+            rest = after;
+        } else {
+            return None;
+        }
+    }
+    Some(path)
+}
+
+/// The value at `path` in `data`, if there is one.
+fn lookup<'a>(data: &'a Value, path: &[Segment]) -> Option<&'a Value> {
+    path.iter().try_fold(data, |value, segment| match segment {
+        Segment::Key(key) => value.as_object()?.get(key),
+        Segment::Index(index) => value.as_array()?.get(*index),
+    })
+}
+
+/// The text a value renders as: strings as they are, lists of plain values joined with
+/// commas, and anything else as JSON. `None` for null, which has no text.
+pub fn value_text(value: &Value) -> Option<String> {
+    let plain = |value: &Value| match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    };
+    match value {
+        Value::Null => None,
+        Value::Array(items) => Some(
+            items
+                .iter()
+                .map(plain)
+                .collect::<Option<Vec<_>>>()
+                .map(|items| items.join(", "))
+                .unwrap_or_else(|| value.to_string()),
+        ),
+        Value::Object(_) => Some(value.to_string()),
+        _ => plain(value),
+    }
+}
+
+/// The text of `expression` filled from `data`, or why it cannot be filled.
+fn evaluate(expression: &Expression, data: Option<&Value>) -> Result<String, String> {
+    let mut value = data
+        .and_then(|data| lookup(data, &expression.path))
+        .cloned()
+        .ok_or_else(|| "missing".to_string())?;
+    for name in &expression.filters {
+        let filter = Filter::parse(name).ok_or_else(|| format!("unknown filter '{}'", name))?;
+        let text = match filter {
+            Filter::Json => serde_json::to_string_pretty(&value).unwrap_or_else(|_| value.to_string()),
+            _ => {
+                let text = value_text(&value).ok_or_else(|| "null".to_string())?;
+                match filter {
+                    Filter::Upper => text.to_uppercase(),
+                    Filter::Lower => text.to_lowercase(),
+                    _ => text.trim().to_string(),
+                }
+            }
+        };
+        value = Value::String(text);
+    }
+    value_text(&value).ok_or_else(|| "null".to_string())
+}
+
+/// A piece of a template: literal text, or a placeholder with its source between the braces.
+enum Part<'a> {
+    Text(&'a str),
+    Placeholder(&'a str, Expression<'a>),
+}
+
+/// Splits `template` into literal text and placeholders.
+fn parts(template: &str) -> Vec<Part<'_>> {
+    let mut parts = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(length) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let source = &rest[start + 2..start + 2 + length];
+        match parse_expression(source) {
+            Some(expression) => {
+                parts.push(Part::Text(&rest[..start]));
+                parts.push(Part::Placeholder(source, expression));
+                rest = &rest[start + 2 + length + 2..];
+            }
+            // Not an expression: keep the first brace and look again from the next one
+            None => {
+                parts.push(Part::Text(&rest[..start + 1]));
+                rest = &rest[start + 1..];
+            }
+        }
+    }
+    parts.push(Part::Text(rest));
+    parts
+}
+
+/// Fills the placeholders of `template` from `data`. Placeholders that cannot be filled are
+/// left as they are, and described in the returned list, e.g. `{{user.name}} (missing)`,
+/// each once.
+pub fn render(template: &str, data: Option<&Value>) -> (String, Vec<String>) {
+    let mut rendered = String::with_capacity(template.len());
+    let mut unresolved: Vec<String> = Vec::new();
+    for part in parts(template) {
+        match part {
+            Part::Text(text) => rendered.push_str(text),
+            Part::Placeholder(source, expression) => match evaluate(&expression, data) {
+                Ok(text) => rendered.push_str(&text),
+                Err(reason) => {
+                    let problem = format!("{{{{{}}}}} ({})", normalized(source), reason);
+                    if !unresolved.contains(&problem) {
+                        unresolved.push(problem);
+                    }
+                    rendered.push_str(&format!("{{{{{}}}}}", source));
+                }
+            },
+        }
+    }
+    (rendered, unresolved)
+}
+
+/// `source` with the spacing around its path and filters made uniform, so `{{ a|upper }}` and
+/// `{{a | upper}}` are reported alike.
+fn normalized(source: &str) -> String {
+    source.split('|').map(str::trim).collect::<Vec<_>>().join(" | ")
+}
+
+/// Top-level keys the placeholders of `template` read, in order of first appearance.
+pub fn variables(template: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for part in parts(template) {
+        if let Part::Placeholder(_, Expression { path, .. }) = part {
+            match path.into_iter().next() {
+                Some(Segment::Key(name)) if !names.contains(&name) => names.push(name),
+                _ => {}
+            }
+        }
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn filled(template: &str, data: Value) -> String {
+        let (text, unresolved) = render(template, Some(&data));
+        assert!(unresolved.is_empty(), "{:?}", unresolved);
+        text
+    }
+
+    #[test]
+    fn test_flat_keys_render_as_before() {
+        let data = json!({ "country": "France", "count": 3, "ok": true, "price": 4.5 });
+        assert_eq!(filled("Capital of {{country}}?", data.clone()), "Capital of France?");
+        assert_eq!(filled("{{ country }}{{count}} {{ok}} {{price}}", data), "France3 true 4.5");
+        assert_eq!(filled("No placeholders", json!({})), "No placeholders");
+    }
+
+    #[test]
+    fn test_paths_reach_into_objects_and_arrays() {
+        let data = json!({
+            "user": { "name": "Ann", "address": { "city": "Oslo" } },
+            "items": [{ "title": "First" }, { "title": "Second" }],
+            "matrix": [[1, 2], [3, 4]],
+        });
+        assert_eq!(filled("{{user.name}} from {{ user.address.city }}", data.clone()), "Ann from Oslo");
+        assert_eq!(filled("{{items[1].title}}", data.clone()), "Second");
+        assert_eq!(filled("{{matrix[1][0]}} {{matrix[0]}}", data.clone()), "3 1, 2");
+        assert_eq!(filled("{{user.address}}", data), r#"{"city":"Oslo"}"#);
+    }
+
+    #[test]
+    fn test_filters_apply_left_to_right() {
+        let data = json!({ "text": "  Hello World ", "tags": ["a", "b"], "shots": [{ "q": "2+2", "a": 4 }] });
+        assert_eq!(filled("{{text | upper}}", data.clone()), "  HELLO WORLD ");
+        assert_eq!(filled("{{text|trim|lower}}", data.clone()), "hello world");
+        assert_eq!(filled("{{tags | upper}}", data.clone()), "A, B");
+        assert_eq!(filled("{{shots | json}}", data.clone()), "[\n  {\n    \"a\": 4,\n    \"q\": \"2+2\"\n  }\n]");
+        assert_eq!(filled("{{shots[0].q | json}}", data.clone()), "\"2+2\"");
+        assert_eq!(filled("{{shots[0] | json | upper}}", data), "{\n  \"A\": 4,\n  \"Q\": \"2+2\"\n}");
+        assert_eq!(filled("{{nothing | json}}", json!({ "nothing": null })), "null");
+    }
+
+    #[test]
+    fn test_unfilled_placeholders_are_kept_and_reported_once() {
+        let data = json!({ "user": { "name": "Ann" }, "items": [], "gone": null });
+        let (text, unresolved) = render(
+            "{{user.email}} {{items[0]}} {{gone}} {{ user.name|shout }} {{user.email}} {{user.name.first}}",
+            Some(&data),
+        );
+        assert_eq!(text, "{{user.email}} {{items[0]}} {{gone}} {{ user.name|shout }} {{user.email}} {{user.name.first}}");
+        assert_eq!(
+            unresolved,
+            [
+                "{{user.email}} (missing)",
+                "{{items[0]}} (missing)",
+                "{{gone}} (null)",
+                "{{user.name | shout}} (unknown filter 'shout')",
+                "{{user.name.first}} (missing)",
+            ]
+        );
+        assert_eq!(render("{{a}}", None).1, ["{{a}} (missing)"]);
+    }
+
+    #[test]
+    fn test_text_that_is_not_an_expression_is_left_alone() {
+        let template = "{{}} {{ a b }} {{a.}} {{a[x]}} {{a | }} {{a[-1]}} {{ unclosed";
+        let (text, unresolved) = render(template, Some(&json!({ "a": [1] })));
+        assert_eq!(text, template);
+        assert!(unresolved.is_empty());
+        assert_eq!(filled("{{{a}}}", json!({ "a": "x" })), "{x}");
+    }
+
+    #[test]
+    fn test_values_are_not_rendered_again() {
+        assert_eq!(filled("{{a}} {{b}}", json!({ "a": "{{b}}", "b": "B" })), "{{b}} B");
+    }
+
+    #[test]
+    fn test_variables_are_the_top_level_keys_read() {
+        let template = "{{user.name}} {{ items[0] | json }} {{user.email}} {{not an expression}} {{country}}";
+        assert_eq!(variables(template), ["user", "items", "country"]);
+    }
+}
@@ -0,0 +1,225 @@
+// src/text_diff.rs
+//! Line-based unified diffs of two texts, as `diff -u` prints them, for comparing prompt
+//! templates. Lines are compared with their line endings, so a text that gained or lost its
+//! final newline shows its last line changed, marked `\ No newline at end of file`.
+use serde::Serialize;
+
+/// Unchanged lines shown around each change.
+pub const CONTEXT_LINES: usize = 3;
+
+/// Most cells of the longest-common-subsequence table computed. Past this, the changed middle
+/// of the texts is shown removed and added whole rather than matched line by line.
+const MAX_LCS_CELLS: usize = 4_000_000;
+
+const NO_NEWLINE_MARKER: &str = "\\ No newline at end of file\n";
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LineDiff {
+    /// The diff in unified format; empty when the texts are the same.
+    pub unified: String,
+    pub added: usize,
+    pub removed: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Edit {
+    Keep,
+    Remove,
+    Add,
+}
+
+/// One line of either text, with the lines of each text that come before it.
+struct Line<'a> {
+    edit: Edit,
+    text: &'a str,
+    old_before: usize,
+    new_before: usize,
+}
+
+/// The unified diff turning `old` into `new`, with `---` and `+++` headers naming them
+/// `old_label` and `new_label`.
+pub fn unified_diff(old: &str, new: &str, old_label: &str, new_label: &str) -> LineDiff {
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+    let lines = align(&old_lines, &new_lines);
+
+    let added = lines.iter().filter(|line| line.edit == Edit::Add).count();
+    let removed = lines.iter().filter(|line| line.edit == Edit::Remove).count();
+    if added == 0 && removed == 0 {
+        return LineDiff { unified: String::new(), added, removed };
+    }
+
+    let mut unified = format!("--- {}\n+++ {}\n", old_label, new_label);
+    for (start, end) in hunks(&lines) {
+        let hunk = &lines[start..end];
+        let old_count = hunk.iter().filter(|line| line.edit != Edit::Add).count();
+        let new_count = hunk.iter().filter(|line| line.edit != Edit::Remove).count();
+        unified.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            range(hunk[0].old_before, old_count),
+            range(hunk[0].new_before, new_count)
+        ));
+        for line in hunk {
+            unified.push(match line.edit {
+                Edit::Keep => ' ',
+                Edit::Remove => '-',
+                Edit::Add => '+',
+            });
+            unified.push_str(line.text);
+            if !line.text.ends_with('\n') {
+                unified.push('\n');
+                unified.push_str(NO_NEWLINE_MARKER);
+            }
+        }
+    }
+    LineDiff { unified, added, removed }
+}
+
+/// Both texts line by line, kept lines matched along a longest common subsequence and
+/// removed lines before added ones where they differ.
+fn align<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Line<'a>> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+
+    let mut edits = vec![Edit::Keep; prefix];
+    edits.extend(middle_edits(old_middle, new_middle));
+    edits.extend(std::iter::repeat_n(Edit::Keep, suffix));
+
+    let (mut old_before, mut new_before) = (0, 0);
+    edits
+        .into_iter()
+        .map(|edit| {
+            let text = match edit {
+                Edit::Add => new[new_before],
+                _ => old[old_before],
+            };
+            let line = Line { edit, text, old_before, new_before };
+            if edit != Edit::Add {
+                old_before += 1;
+            }
+            if edit != Edit::Remove {
+                new_before += 1;
+            }
+            line
+        })
+        .collect()
+}
+
+fn middle_edits(old: &[&str], new: &[&str]) -> Vec<Edit> {
+    if (old.len() + 1).saturating_mul(new.len() + 1) > MAX_LCS_CELLS {
+        let mut edits = vec![Edit::Remove; old.len()];
+        edits.extend(std::iter::repeat_n(Edit::Add, new.len()));
+        return edits;
+    }
+
+    // lcs[i][j]: the longest common subsequence of old[i..] and new[j..]
+    let width = new.len() + 1;
+    let mut lcs = vec![0u32; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i * width + j] = if old[i] == new[j] {
+                lcs[(i + 1) * width + j + 1] + 1
+            } else {
+                lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut edits = Vec::with_capacity(old.len() + new.len());
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            edits.push(Edit::Keep);
+            i += 1;
+            j += 1;
+        } else if j == new.len() || (i < old.len() && lcs[(i + 1) * width + j] >= lcs[i * width + j + 1]) {
+            edits.push(Edit::Remove);
+            i += 1;
+        } else {
+            edits.push(Edit::Add);
+            j += 1;
+        }
+    }
+    edits
+}
+
+/// The ranges of `lines` shown as hunks: each change with `CONTEXT_LINES` around it, merging
+/// changes whose context would touch or overlap.
+fn hunks(lines: &[Line]) -> Vec<(usize, usize)> {
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for (i, _) in lines.iter().enumerate().filter(|(_, line)| line.edit != Edit::Keep) {
+        let start = i.saturating_sub(CONTEXT_LINES);
+        let end = (i + 1 + CONTEXT_LINES).min(lines.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+    hunks
+}
+
+/// A hunk header range: the first line, 1-based, and the count unless it is 1. An empty range
+/// names the line before it, as `diff -u` does.
+fn range(before: usize, count: usize) -> String {
+    match count {
+        0 => format!("{},0", before),
+        1 => format!("{}", before + 1),
+        _ => format!("{},{}", before + 1, count),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diff(old: &str, new: &str) -> String {
+        unified_diff(old, new, "a", "b").unified
+    }
+
+    #[test]
+    fn test_multi_line_templates_diff_in_hunks_with_context() {
+        let old = "You are a tutor.\nAnswer briefly.\n\n{{prompt}}\n\nOne\nTwo\nThree\nFour\nFive\nEnd\n";
+        let new = "You are a patient tutor.\nAnswer briefly.\n\n{{prompt}}\n\nOne\nTwo\nThree\nFour\nFive\nEnd\nShow your work.\n";
+        let result = unified_diff(old, new, "v4", "v7");
+        assert_eq!((result.added, result.removed), (2, 1));
+        assert_eq!(
+            result.unified,
+            "--- v4\n+++ v7\n\
+             @@ -1,4 +1,4 @@\n-You are a tutor.\n+You are a patient tutor.\n Answer briefly.\n \n {{prompt}}\n\
+             @@ -9,3 +9,4 @@\n Four\n Five\n End\n+Show your work.\n"
+        );
+
+        // Changes whose context meets share a hunk
+        assert_eq!(
+            diff("a\nb\nc\nd\ne\nf\ng\nh\n", "A\nb\nc\nd\ne\nf\ng\nH\n"),
+            "--- a\n+++ b\n@@ -1,8 +1,8 @@\n-a\n+A\n b\n c\n d\n e\n f\n g\n-h\n+H\n"
+        );
+        assert_eq!(diff("a\nb\nc\n", "a\nc\n"), "--- a\n+++ b\n@@ -1,3 +1,2 @@\n a\n-b\n c\n");
+    }
+
+    #[test]
+    fn test_trailing_newlines_are_compared() {
+        assert_eq!(diff("same\n", "same\n"), "");
+        assert_eq!(unified_diff("x\ny", "x\ny", "a", "b"), LineDiff { unified: String::new(), added: 0, removed: 0 });
+
+        assert_eq!(
+            diff("Answer:\n{{prompt}}\n", "Answer:\n{{prompt}}"),
+            "--- a\n+++ b\n@@ -1,2 +1,2 @@\n Answer:\n-{{prompt}}\n+{{prompt}}\n\\ No newline at end of file\n"
+        );
+        assert_eq!(
+            diff("Answer:\n{{prompt}}", "Answer:\n{{prompt}}\nThanks\n"),
+            "--- a\n+++ b\n@@ -1,2 +1,3 @@\n Answer:\n-{{prompt}}\n\\ No newline at end of file\n+{{prompt}}\n+Thanks\n"
+        );
+        // A trailing blank line is a line of its own
+        assert_eq!(diff("a\n", "a\n\n"), "--- a\n+++ b\n@@ -1 +1,2 @@\n a\n+\n");
+    }
+
+    #[test]
+    fn test_empty_texts_diff_against_whole_texts() {
+        assert_eq!(diff("", ""), "");
+        assert_eq!(diff("", "one\ntwo\n"), "--- a\n+++ b\n@@ -0,0 +1,2 @@\n+one\n+two\n");
+        assert_eq!(diff("one", ""), "--- a\n+++ b\n@@ -1 +0,0 @@\n-one\n\\ No newline at end of file\n");
+    }
+}
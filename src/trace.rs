@@ -0,0 +1,280 @@
+// src/trace.rs
+use async_trait::async_trait;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::providers::middleware::{ProviderErrorEvent, ProviderErrorSink};
+
+/// Most bytes of trace JSON stored with an evaluation.
+pub const MAX_STORED_TRACE_BYTES: usize = 8 * 1024;
+
+/// A step of an eval's execution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TracePhase {
+    /// Fitting the metadata into the model's context window.
+    Validation,
+    TemplateRender,
+    /// Waiting for the rest of the batch before a batched judge call.
+    QueueWait,
+    ProviderCall,
+    /// Parsing the output and checking the format rule.
+    Parse,
+    JudgePromptLoad,
+    JudgeCall,
+    /// A separate call putting a failed eval in a failure category.
+    FailureClassification,
+    Persistence,
+}
+
+impl TracePhase {
+    /// Every phase, in execution order.
+    pub const ALL: [TracePhase; 9] = [
+        TracePhase::Validation,
+        TracePhase::TemplateRender,
+        TracePhase::QueueWait,
+        TracePhase::ProviderCall,
+        TracePhase::Parse,
+        TracePhase::JudgePromptLoad,
+        TracePhase::JudgeCall,
+        TracePhase::FailureClassification,
+        TracePhase::Persistence,
+    ];
+}
+
+/// One timed phase of an eval.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceEvent {
+    pub phase: TracePhase,
+    /// When the phase started (RFC 3339, millisecond precision).
+    pub started_at: String,
+    pub duration_ms: u64,
+    /// Retries of a provider call, the judge prompt version loaded and the like.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/// A phase being timed.
+#[derive(Debug, Clone)]
+pub struct Span {
+    phase: TracePhase,
+    started_at: DateTime<Utc>,
+    start: Instant,
+}
+
+impl Span {
+    pub fn start(phase: TracePhase) -> Self {
+        Self { phase, started_at: Utc::now(), start: Instant::now() }
+    }
+
+    pub fn finish(self, detail: Option<String>) -> TraceEvent {
+        TraceEvent {
+            phase: self.phase,
+            started_at: self.started_at.to_rfc3339_opts(SecondsFormat::Millis, true),
+            duration_ms: self.start.elapsed().as_millis() as u64,
+            detail,
+        }
+    }
+}
+
+/// Ends `span` and appends it to `trace`; does nothing when tracing is off (`trace` is `None`).
+pub fn record(trace: &mut Option<Vec<TraceEvent>>, span: Span, detail: Option<String>) {
+    if let Some(events) = trace {
+        events.push(span.finish(detail));
+    }
+}
+
+/// Collects the failed attempts of a provider call, to describe its retries in the trace.
+#[derive(Clone, Default)]
+pub struct RetryLog(Arc<Mutex<Vec<ProviderErrorEvent>>>);
+
+impl RetryLog {
+    /// Describes and clears the failed attempts collected so far; `None` when there were none.
+    pub fn take_detail(&self) -> Option<String> {
+        let events = std::mem::take(&mut *self.0.lock().unwrap());
+        let last = events.last()?;
+        let attempts = if last.recovered { events.len() + 1 } else { events.len() };
+        let errors: Vec<String> = events
+            .iter()
+            .map(|e| match e.status_code {
+                Some(status) => format!("{} ({})", e.error_class, status),
+                None => e.error_class.to_string(),
+            })
+            .collect();
+        Some(format!("{} attempts; failed with {}", attempts, errors.join(", ")))
+    }
+}
+
+#[async_trait]
+impl ProviderErrorSink for RetryLog {
+    async fn record(&self, event: ProviderErrorEvent) {
+        self.0.lock().unwrap().push(event);
+    }
+}
+
+/// The trace as stored JSON, cut to `MAX_STORED_TRACE_BYTES`: details are dropped first, then
+/// events from the end. `None` for an empty trace.
+pub fn to_stored_json(events: &[TraceEvent]) -> Option<String> {
+    let mut events = events.to_vec();
+    let fits = |events: &[TraceEvent]| {
+        serde_json::to_string(events).ok().filter(|json| json.len() <= MAX_STORED_TRACE_BYTES)
+    };
+    if let Some(json) = fits(&events) {
+        return (!events.is_empty()).then_some(json);
+    }
+    for event in &mut events {
+        event.detail = None;
+    }
+    while !events.is_empty() {
+        if let Some(json) = fits(&events) {
+            return Some(json);
+        }
+        events.pop();
+    }
+    None
+}
+
+/// Time spent in one phase across the traced evals of a batch.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PhaseTiming {
+    pub phase: TracePhase,
+    /// Traced evals that went through the phase.
+    pub evals: usize,
+    pub total_ms: u64,
+    /// Per eval that went through the phase.
+    pub average_ms: u64,
+    pub max_ms: u64,
+    /// Fraction of all traced time spent in the phase.
+    pub share: f64,
+}
+
+/// Sums each phase over `traces`, in execution order. Phases no trace went through are left out.
+pub fn aggregate<'a>(traces: impl IntoIterator<Item = &'a [TraceEvent]>) -> Vec<PhaseTiming> {
+    let traces: Vec<&[TraceEvent]> = traces.into_iter().collect();
+    let mut timings: Vec<PhaseTiming> = TracePhase::ALL
+        .iter()
+        .filter_map(|&phase| {
+            let per_eval: Vec<u64> = traces
+                .iter()
+                .filter(|events| events.iter().any(|e| e.phase == phase))
+                .map(|events| events.iter().filter(|e| e.phase == phase).map(|e| e.duration_ms).sum())
+                .collect();
+            if per_eval.is_empty() {
+                return None;
+            }
+            let total_ms: u64 = per_eval.iter().sum();
+            Some(PhaseTiming {
+                phase,
+                evals: per_eval.len(),
+                total_ms,
+                average_ms: total_ms / per_eval.len() as u64,
+                max_ms: per_eval.iter().copied().max().unwrap_or(0),
+                share: 0.0,
+            })
+        })
+        .collect();
+
+    let traced_ms: u64 = timings.iter().map(|t| t.total_ms).sum();
+    if traced_ms > 0 {
+        for timing in &mut timings {
+            timing.share = timing.total_ms as f64 / traced_ms as f64;
+        }
+    }
+    timings
+}
+
+/// Where the time of a batch's traced evals went.
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchTrace {
+    /// Wall-clock time of the whole batch. Evals run concurrently, so phase totals can exceed it.
+    pub wall_clock_ms: u64,
+    pub traced_evals: usize,
+    pub phases: Vec<PhaseTiming>,
+}
+
+/// Aggregates the traces of a batch; `None` when no eval of the batch was traced.
+pub fn summarize<'a>(traces: impl IntoIterator<Item = &'a [TraceEvent]>, wall_clock_ms: u64) -> Option<BatchTrace> {
+    let traces: Vec<&[TraceEvent]> = traces.into_iter().collect();
+    if traces.is_empty() {
+        return None;
+    }
+    Some(BatchTrace { wall_clock_ms, traced_evals: traces.len(), phases: aggregate(traces) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(phase: TracePhase, duration_ms: u64) -> TraceEvent {
+        TraceEvent {
+            phase,
+            started_at: "2026-10-16T10:00:00.000Z".to_string(),
+            duration_ms,
+            detail: Some("x".repeat(100)),
+        }
+    }
+
+    #[test]
+    fn test_aggregate_sums_phases_per_eval() {
+        let first = vec![
+            event(TracePhase::ProviderCall, 300),
+            event(TracePhase::JudgeCall, 100),
+        ];
+        let second = vec![
+            event(TracePhase::ProviderCall, 500),
+            event(TracePhase::QueueWait, 50),
+            event(TracePhase::QueueWait, 50),
+        ];
+
+        let timings = aggregate([first.as_slice(), second.as_slice()]);
+        let phases: Vec<TracePhase> = timings.iter().map(|t| t.phase).collect();
+        assert_eq!(phases, vec![TracePhase::QueueWait, TracePhase::ProviderCall, TracePhase::JudgeCall]);
+
+        let provider = &timings[1];
+        assert_eq!((provider.evals, provider.total_ms, provider.average_ms, provider.max_ms), (2, 800, 400, 500));
+        assert_eq!((timings[0].evals, timings[0].max_ms), (1, 100));
+        assert!((provider.share - 0.8).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_stored_json_drops_details_then_events_to_fit() {
+        assert_eq!(to_stored_json(&[]), None);
+
+        let short = vec![event(TracePhase::Parse, 1)];
+        assert_eq!(to_stored_json(&short), serde_json::to_string(&short).ok());
+
+        let long: Vec<TraceEvent> = (0..200).map(|i| event(TracePhase::ProviderCall, i)).collect();
+        let json = to_stored_json(&long).unwrap();
+        assert!(json.len() <= MAX_STORED_TRACE_BYTES);
+        let stored: Vec<TraceEvent> = serde_json::from_str(&json).unwrap();
+        assert!(stored.len() < long.len());
+        assert_eq!(stored[0].duration_ms, 0);
+        assert!(stored.iter().all(|e| e.detail.is_none()));
+    }
+
+    #[tokio::test]
+    async fn test_retry_log_describes_attempts() {
+        let log = RetryLog::default();
+        assert_eq!(log.take_detail(), None);
+
+        for (attempt, status) in [(0, 429), (1, 503)] {
+            log.record(ProviderErrorEvent {
+                provider: "openai".to_string(),
+                model: "gpt-4o".to_string(),
+                status_code: Some(status),
+                error_class: if status == 429 { "rate_limited" } else { "server_error" },
+                body: String::new(),
+                attempt,
+                recovered: true,
+            })
+            .await;
+        }
+        assert_eq!(
+            log.take_detail().as_deref(),
+            Some("3 attempts; failed with rate_limited (429), server_error (503)")
+        );
+        assert_eq!(log.take_detail(), None);
+    }
+}
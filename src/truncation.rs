@@ -0,0 +1,211 @@
+// src/truncation.rs
+use serde::{Deserialize, Serialize};
+
+use crate::config::EvalConfig;
+use crate::errors::{EvalError, Result};
+use crate::pricing::TokenEstimator;
+
+/// Metadata field truncated when an eval does not name one.
+pub const DEFAULT_TRUNCATE_FIELD: &str = "context";
+/// Tokens left free in the context window for the model's answer.
+pub const ANSWER_RESERVE_TOKENS: u32 = 1024;
+
+/// Marker inserted where `middle_out` removed text.
+const MIDDLE_OUT_MARKER: &str = "\n[...]\n";
+
+/// How to shorten a metadata field that would overflow the model's context window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TruncationStrategy {
+    /// Keep the beginning and drop the end.
+    Head,
+    /// Keep the end and drop the beginning.
+    Tail,
+    /// Keep both ends and drop the middle.
+    MiddleOut,
+    /// Keep the beginning, cut back to the last complete sentence that fits.
+    SentenceBoundary,
+}
+
+impl TruncationStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TruncationStrategy::Head => "head",
+            TruncationStrategy::Tail => "tail",
+            TruncationStrategy::MiddleOut => "middle_out",
+            TruncationStrategy::SentenceBoundary => "sentence_boundary",
+        }
+    }
+}
+
+/// Recorded on an eval whose input was truncated.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TruncationRecord {
+    pub strategy: TruncationStrategy,
+    pub field: String,
+    pub original_tokens: u32,
+    pub truncated_tokens: u32,
+}
+
+impl TruncationRecord {
+    /// Note added to the judge prompt so the verdict does not penalize content the model never saw.
+    pub fn judge_note(&self) -> String {
+        format!(
+            "NOTE: The \"{}\" input given to the model was truncated ({}, about {} of {} tokens kept) to fit its context window. Do not penalize the output for missing information that was cut.",
+            self.field,
+            self.strategy.as_str(),
+            self.truncated_tokens,
+            self.original_tokens
+        )
+    }
+}
+
+/// Context window in tokens for a `provider:model` string, used when the eval does not set one.
+pub fn context_window(model: &str) -> u32 {
+    let (provider, name) = model.split_once(':').unwrap_or(("gemini", model));
+    match provider {
+        "anthropic" => 200_000,
+        "gemini" => 1_048_576,
+        "groq" => 131_072,
+        "openai" if name.starts_with("gpt-4.1") => 1_047_576,
+        "openai" if name.starts_with("gpt-3.5") => 16_385,
+        "openai" => 128_000,
+        // Ollama's default context length, regardless of what the model supports
+        "ollama" => 4_096,
+        _ => 8_192,
+    }
+}
+
+/// Truncates the eval's designated metadata field in place when the rendered prompt would not
+/// fit the model's context window minus `ANSWER_RESERVE_TOKENS`. Returns what was done, or
+/// `None` when no truncation was configured or needed.
+pub fn apply(eval: &mut EvalConfig, estimator: &dyn TokenEstimator) -> Result<Option<TruncationRecord>> {
+    let Some(strategy) = eval.truncation else {
+        return Ok(None);
+    };
+    let field = eval.truncate_field.clone().unwrap_or_else(|| DEFAULT_TRUNCATE_FIELD.to_string());
+
+    let text = match eval.metadata.as_ref().and_then(|m| m.get(&field)) {
+        Some(serde_json::Value::String(text)) => text.clone(),
+        Some(_) => return Err(EvalError::Config(format!("Truncation field '{}' must be a string", field))),
+        None => return Err(EvalError::Config(format!("Truncation field '{}' not found in metadata", field))),
+    };
+
+    // Tokens the rest of the prompt takes up, measured with the field emptied
+    let mut without_field = eval.clone();
+    if let Some(metadata) = without_field.metadata.as_mut() {
+        metadata[&field] = serde_json::Value::String(String::new());
+    }
+    let overhead = estimator.estimate_tokens(&without_field.render()?.prompt);
+
+    let window = eval.context_window.unwrap_or_else(|| context_window(&eval.model));
+    let budget = window.saturating_sub(ANSWER_RESERVE_TOKENS).saturating_sub(overhead);
+    let original_tokens = estimator.estimate_tokens(&text);
+    if original_tokens <= budget {
+        return Ok(None);
+    }
+
+    let truncated = truncate(&text, budget, strategy, estimator);
+    let truncated_tokens = estimator.estimate_tokens(&truncated);
+    if let Some(metadata) = eval.metadata.as_mut() {
+        metadata[&field] = serde_json::Value::String(truncated);
+    }
+
+    Ok(Some(TruncationRecord { strategy, field, original_tokens, truncated_tokens }))
+}
+
+/// Shortens `text` to at most `budget` estimated tokens using `strategy`.
+pub fn truncate(text: &str, budget: u32, strategy: TruncationStrategy, estimator: &dyn TokenEstimator) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let head = |n: usize| chars[..n].iter().collect::<String>();
+    let tail = |n: usize| chars[chars.len() - n..].iter().collect::<String>();
+
+    match strategy {
+        TruncationStrategy::Head => longest_fit(chars.len(), budget, estimator, head),
+        TruncationStrategy::Tail => longest_fit(chars.len(), budget, estimator, tail),
+        TruncationStrategy::MiddleOut => longest_fit(chars.len(), budget, estimator, |n| {
+            if n == 0 {
+                return String::new();
+            }
+            format!("{}{}{}", head(n.div_ceil(2)), MIDDLE_OUT_MARKER, tail(n / 2))
+        }),
+        TruncationStrategy::SentenceBoundary => {
+            let kept = longest_fit(chars.len(), budget, estimator, head);
+            match last_sentence_end(&kept) {
+                Some(end) => kept[..end].to_string(),
+                None => kept,
+            }
+        }
+    }
+}
+
+/// `build(n)` for the largest `n <= len` whose result fits in `budget` tokens.
+/// Assumes the estimate never decreases as `n` grows.
+fn longest_fit(len: usize, budget: u32, estimator: &dyn TokenEstimator, build: impl Fn(usize) -> String) -> String {
+    let (mut low, mut high) = (0, len);
+    while low < high {
+        let mid = (low + high).div_ceil(2);
+        if estimator.estimate_tokens(&build(mid)) <= budget {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    build(low)
+}
+
+/// Byte offset just past the last sentence-ending punctuation in `text`.
+fn last_sentence_end(text: &str) -> Option<usize> {
+    text.char_indices()
+        .rev()
+        .find(|(_, c)| matches!(c, '.' | '!' | '?'))
+        .map(|(i, c)| i + c.len_utf8())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pricing::CharsPerToken;
+    use serde_json::json;
+
+    /// One token per character keeps the arithmetic readable.
+    const PER_CHAR: CharsPerToken = CharsPerToken(1.0);
+
+    #[test]
+    fn test_strategies_fit_budget() {
+        let text = "First sentence. Second sentence. Third one";
+        assert_eq!(truncate(text, 10, TruncationStrategy::Head, &PER_CHAR), "First sent");
+        assert_eq!(truncate(text, 9, TruncationStrategy::Tail, &PER_CHAR), "Third one");
+        assert_eq!(truncate(text, 40, TruncationStrategy::SentenceBoundary, &PER_CHAR), "First sentence. Second sentence.");
+
+        let middle = truncate(text, 17, TruncationStrategy::MiddleOut, &PER_CHAR);
+        assert_eq!(middle, format!("First{}d one", MIDDLE_OUT_MARKER));
+        assert!(PER_CHAR.estimate_tokens(&middle) <= 17);
+    }
+
+    #[test]
+    fn test_apply_truncates_only_the_designated_field() {
+        let mut eval: EvalConfig = serde_json::from_value(json!({
+            "model": "ollama:llama3",
+            "prompt": "Context: {{context}}\nQuestion: {{question}}",
+            "metadata": {"context": "x".repeat(5000), "question": "Why?"},
+            "truncation": "tail",
+            "context_window": 1100
+        }))
+        .unwrap();
+
+        let record = apply(&mut eval, &PER_CHAR).unwrap().unwrap();
+        let overhead = "Context: \nQuestion: Why?".len() as u32;
+        assert_eq!(record.strategy, TruncationStrategy::Tail);
+        assert_eq!(record.original_tokens, 5000);
+        assert_eq!(record.truncated_tokens, 1100 - ANSWER_RESERVE_TOKENS - overhead);
+        assert_eq!(eval.metadata.as_ref().unwrap()["question"], "Why?");
+        assert!(record.judge_note().contains("\"context\" input"));
+
+        // Already fits now, so a second pass leaves it alone
+        assert!(apply(&mut eval, &PER_CHAR).unwrap().is_none());
+
+        eval.truncate_field = Some("missing".to_string());
+        assert!(apply(&mut eval, &PER_CHAR).is_err());
+    }
+}
@@ -0,0 +1,172 @@
+// src/webhook.rs
+use hmac::{Hmac, Mac};
+use reqwest::header::CONTENT_TYPE;
+use sha2::Sha256;
+use std::time::Duration;
+use tracing::Instrument;
+
+use crate::config::AppConfig;
+use crate::models::{FieldError, WebhookPayload};
+use crate::providers::middleware::RetryPolicy;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Header carrying `sha256=<hex HMAC-SHA256 of the raw body>`, keyed with `WEBHOOK_SECRET`.
+pub const SIGNATURE_HEADER: &str = "X-Signature";
+
+/// How long a receiver has to answer one delivery attempt.
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The `X-Signature` value of `body`.
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    let hex: String = mac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect();
+    format!("sha256={}", hex)
+}
+
+/// Checks a request's `callback_url`: webhooks must be enabled and the URL must be http(s).
+pub fn check_callback_url(config: &AppConfig, url: &str) -> Result<(), FieldError> {
+    if config.webhook_secret.is_none() {
+        return Err(FieldError::new("callback_url", "webhooks are disabled; set WEBHOOK_SECRET to enable them"));
+    }
+    match reqwest::Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => Ok(()),
+        Ok(_) => Err(FieldError::new("callback_url", "must be an http or https URL")),
+        Err(e) => Err(FieldError::new("callback_url", format!("is not a valid URL: {}", e))),
+    }
+}
+
+/// Delivers `payload` from a background task, so the run's response never waits on the receiver.
+pub fn spawn(client: reqwest::Client, secret: String, url: String, payload: WebhookPayload) {
+    tokio::spawn(
+        async move {
+            deliver(&client, &secret, &url, &payload, RetryPolicy::default()).await;
+        }
+        .in_current_span(),
+    );
+}
+
+/// POSTs `payload` signed with `secret`, retrying failed attempts with doubling backoff.
+/// Failures are logged; returns whether the receiver accepted the payload.
+pub async fn deliver(
+    client: &reqwest::Client,
+    secret: &str,
+    url: &str,
+    payload: &WebhookPayload,
+    retry: RetryPolicy,
+) -> bool {
+    let body = match serde_json::to_vec(payload) {
+        Ok(body) => body,
+        Err(e) => {
+            tracing::error!("Failed to serialize webhook payload: {}", e);
+            return false;
+        }
+    };
+    let signature = sign(secret, &body);
+
+    let mut backoff = retry.initial_backoff;
+    for attempt in 1..=retry.max_retries + 1 {
+        let sent = client
+            .post(url)
+            .header(CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, &signature)
+            .timeout(DELIVERY_TIMEOUT)
+            .body(body.clone())
+            .send()
+            .await;
+        let failure = match sent {
+            Ok(response) if response.status().is_success() => {
+                tracing::info!("🪝 Webhook delivered to {}", url);
+                return true;
+            }
+            Ok(response) => format!("receiver answered {}", response.status()),
+            Err(e) => e.to_string(),
+        };
+
+        if attempt > retry.max_retries {
+            tracing::error!("🪝 Webhook to {} failed after {} attempt(s): {}", url, attempt, failure);
+        } else {
+            tracing::warn!("🪝 Webhook attempt {} to {} failed: {}; retrying in {:?}", attempt, url, failure, backoff);
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::BatchWebhook;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const SECRET: &str = "webhook-test-secret";
+
+    fn payload() -> WebhookPayload {
+        WebhookPayload::BatchCompleted(BatchWebhook {
+            batch_id: "batch-1".to_string(),
+            status: "completed".to_string(),
+            total: 2,
+            completed: 2,
+            passed: 1,
+            failed: 1,
+            needs_review: 0,
+            total_cost_usd: 0.0,
+            average_model_latency_ms: 10,
+            completed_at: "2025-01-01T00:00:00Z".to_string(),
+        })
+    }
+
+    fn fast_retry(max_retries: u32) -> RetryPolicy {
+        RetryPolicy { max_retries, initial_backoff: Duration::from_millis(10) }
+    }
+
+    #[tokio::test]
+    async fn test_delivery_is_signed_and_retried_until_accepted() {
+        let receiver = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .mount(&receiver)
+            .await;
+        Mock::given(method("POST")).and(path("/hook")).respond_with(ResponseTemplate::new(200)).mount(&receiver).await;
+
+        let url = format!("{}/hook", receiver.uri());
+        assert!(deliver(&reqwest::Client::new(), SECRET, &url, &payload(), fast_retry(3)).await);
+
+        let requests = receiver.received_requests().await.unwrap();
+        assert_eq!(requests.len(), 3);
+        for request in &requests {
+            let signature = request.headers.get(SIGNATURE_HEADER).unwrap().to_str().unwrap();
+            assert_eq!(signature, sign(SECRET, &request.body));
+            assert_ne!(signature, sign("another-secret", &request.body));
+        }
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(body["event"], "batch.completed");
+        assert_eq!(body["batch_id"], "batch-1");
+        assert_eq!(serde_json::from_value::<WebhookPayload>(body).unwrap(), payload());
+    }
+
+    #[tokio::test]
+    async fn test_delivery_gives_up_after_its_retries() {
+        let receiver = MockServer::start().await;
+        Mock::given(method("POST")).respond_with(ResponseTemplate::new(500)).mount(&receiver).await;
+
+        assert!(!deliver(&reqwest::Client::new(), SECRET, &receiver.uri(), &payload(), fast_retry(2)).await);
+        assert_eq!(receiver.received_requests().await.unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_callback_urls_need_a_secret_and_http() {
+        let mut config = AppConfig::default();
+        assert!(check_callback_url(&config, "https://ci.example.com/hook").is_err());
+
+        config.webhook_secret = Some(SECRET.to_string());
+        assert!(check_callback_url(&config, "https://ci.example.com/hook").is_ok());
+        assert!(check_callback_url(&config, "ftp://ci.example.com/hook").is_err());
+        assert!(check_callback_url(&config, "not a url").is_err());
+    }
+}
@@ -145,8 +145,8 @@ function initSingleEvalForm() {
 
             const data = await response.json();
             
-            if (data.error) {
-                displayError(resultContainer, data.error, payload.model);
+            if (!response.ok) {
+                displayError(resultContainer, data.message || `Request failed (${response.status})`, payload.model);
             } else {
                 displaySingleResult(data);
             }
@@ -468,19 +468,19 @@ function updateHistoryList() {
             html += `<span class="verdict-badge verdict-${verdict === 'pass' ? 'pass' : verdict === 'fail' ? 'fail' : 'uncertain'}">
                 ${verdict.toUpperCase()}
             </span>`;
-        } else if (result.error_message) {
+        } else if (result.error_preview) {
             html += `<span class="verdict-badge verdict-fail">ERROR</span>`;
         }
         html += '</div>';
 
-        if (result.prompt) {
+        if (result.prompt_preview) {
             html += `<div class="result-item-content">`;
-            html += `<strong>Prompt:</strong> ${escapeHtml(result.prompt.substring(0, 100))}${result.prompt.length > 100 ? '...' : ''}`;
+            html += `<strong>Prompt:</strong> ${escapeHtml(result.prompt_preview)}`;
             html += `</div>`;
-        } else if (result.error_message) {
+        } else if (result.error_preview) {
             html += `<div class="result-item-content" style="color: #dc3545;">`;
-            html += `<strong>Error:</strong> ${escapeHtml(result.error_message.substring(0, 100))}`;
-            if (isApiKeyError(result.error_message)) {
+            html += `<strong>Error:</strong> ${escapeHtml(result.error_preview)}`;
+            if (isApiKeyError(result.error_preview)) {
                 html += ` <small>(Check API key)</small>`;
             }
             html += `</div>`;
@@ -515,9 +515,15 @@ function goToHistoryPage(pageNumber) {
     updateHistoryPagination();
 }
 
-function showHistoryDetail(index) {
+async function showHistoryDetail(index) {
     if (index < 0 || index >= allHistory.length) return;
-    const result = allHistory[index];
+    // The history lists summaries; the full entry is fetched on demand
+    const response = await fetch(`${API_BASE}/evals/${encodeURIComponent(allHistory[index].id)}`);
+    if (!response.ok) {
+        displayError(document.getElementById('history-list'), `Failed to load evaluation: HTTP ${response.status}`);
+        return;
+    }
+    const result = await response.json();
     // Switch to single eval tab and display result
     document.querySelector('.tab-btn[data-tab="single"]').click();
     displaySingleResult(transformHistoryEntryToEvalResponse(result));
@@ -674,8 +680,8 @@ function displaySummaryResultsTable(results) {
                 ${results.slice(0, 50).map(result => `
                     <tr>
                         <td><strong>${result.model || 'N/A'}</strong></td>
-                        <td class="prompt-cell" title="${escapeHtml(result.prompt || '')}">${truncate(result.prompt, 60)}</td>
-                        <td class="output-cell" title="${escapeHtml(result.model_output || '')}">${truncate(result.model_output, 50)}</td>
+                        <td class="prompt-cell" title="${escapeHtml(result.prompt_preview || '')}">${truncate(result.prompt_preview, 60)}</td>
+                        <td class="output-cell" title="${escapeHtml(result.output_preview || '')}">${truncate(result.output_preview, 50)}</td>
                         <td class="status-${getStatusClass(result.judge_verdict)}">${result.judge_verdict || 'N/A'}</td>
                         <td>${result.judge_model || 'N/A'}</td>
                         <td>${result.input_tokens || 'N/A'} / ${result.output_tokens || 'N/A'}</td>
@@ -844,7 +850,7 @@ function updateResultsTable() {
             <tr>
                 <td>${new Date(e.created_at).toLocaleString()}</td>
                 <td>${e.model || 'N/A'}</td>
-                <td title="${escapeHtml(e.prompt || '')}">${(e.prompt || '').substring(0, 50)}...</td>
+                <td title="${escapeHtml(e.prompt_preview || '')}">${(e.prompt_preview || '').substring(0, 50)}...</td>
                 <td><span class="verdict-badge ${badgeClass}">${verdict.toUpperCase()}</span></td>
                 <td>${latency > 0 ? latency + 'ms' : 'N/A'}</td>
             </tr>
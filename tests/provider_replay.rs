@@ -0,0 +1,206 @@
+// tests/provider_replay.rs
+//
+// Replays recorded provider exchanges from tests/fixtures/providers through a local HTTP stub,
+// so each provider's full request/response path runs offline. Re-record a fixture against the
+// real API with `EVAL_RECORD_DIR=tests/fixtures/providers EVAL_RECORD_SCENARIO=<scenario>`.
+
+use evaluate::config::{AnthropicConfig, GeminiConfig, OllamaConfig, OpenAIConfig};
+use evaluate::errors::{EvalError, Result};
+use evaluate::providers::anthropic::AnthropicProvider;
+use evaluate::providers::gemini::GeminiProvider;
+use evaluate::providers::ollama::OllamaProvider;
+use evaluate::providers::openai::OpenAIProvider;
+use evaluate::providers::recording::{load_fixture, RecordedExchange, REDACTED};
+use evaluate::providers::{FinishReason, Generation, GenerationParams, LlmProvider, ThinkingParams};
+use reqwest::{Client, StatusCode};
+use std::collections::HashMap;
+use std::path::Path;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+const SCENARIOS: &[&str] = &["success", "rate_limited", "server_error", "empty_response", "malformed_json"];
+
+/// Serves the recorded response to a single request, after checking that the request
+/// matches the recorded one. The handle resolves to a description of any mismatch.
+async fn serve_once(exchange: RecordedExchange) -> (String, JoinHandle<std::result::Result<(), String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+    let handle = tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.map_err(|e| e.to_string())?;
+
+        let mut buffer = Vec::new();
+        let header_end = loop {
+            let mut chunk = [0u8; 4096];
+            let n = socket.read(&mut chunk).await.map_err(|e| e.to_string())?;
+            if n == 0 {
+                return Err("connection closed before headers were received".to_string());
+            }
+            buffer.extend_from_slice(&chunk[..n]);
+            if let Some(pos) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+                break pos + 4;
+            }
+        };
+
+        let head = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+        let mut lines = head.split("\r\n");
+        let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+        let method = request_line.next().unwrap_or_default().to_string();
+        let path = request_line.next().unwrap_or_default().to_string();
+        let headers: HashMap<String, String> = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+            .collect();
+
+        let content_length: usize = headers.get("content-length").and_then(|v| v.parse().ok()).unwrap_or(0);
+        while buffer.len() < header_end + content_length {
+            let mut chunk = [0u8; 4096];
+            let n = socket.read(&mut chunk).await.map_err(|e| e.to_string())?;
+            if n == 0 {
+                break;
+            }
+            buffer.extend_from_slice(&chunk[..n]);
+        }
+        let body = &buffer[header_end..];
+
+        let mut mismatches = Vec::new();
+        let expected = &exchange.request;
+        if method != expected.method {
+            mismatches.push(format!("method {} != {}", method, expected.method));
+        }
+        if path != expected.path {
+            mismatches.push(format!("path {} != {}", path, expected.path));
+        }
+        for (name, value) in &expected.headers {
+            match headers.get(name) {
+                None => mismatches.push(format!("missing header {}", name)),
+                Some(actual) if value != REDACTED && actual != value => {
+                    mismatches.push(format!("header {}: {} != {}", name, actual, value))
+                }
+                Some(_) => {}
+            }
+        }
+        let actual_body: Option<serde_json::Value> = serde_json::from_slice(body).ok();
+        if actual_body != expected.body {
+            mismatches.push(format!("body {:?} != {:?}", actual_body, expected.body));
+        }
+
+        let response = &exchange.response;
+        let reason = StatusCode::from_u16(response.status)
+            .ok()
+            .and_then(|s| s.canonical_reason())
+            .unwrap_or("Recorded");
+        let mut raw = format!("HTTP/1.1 {} {}\r\n", response.status, reason);
+        if let Some(content_type) = &response.content_type {
+            raw.push_str(&format!("Content-Type: {}\r\n", content_type));
+        }
+        raw.push_str(&format!("Content-Length: {}\r\nConnection: close\r\n\r\n", response.body.len()));
+        raw.push_str(&response.body);
+        socket.write_all(raw.as_bytes()).await.map_err(|e| e.to_string())?;
+        socket.shutdown().await.ok();
+
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(mismatches.join("; "))
+        }
+    });
+
+    (base_url, handle)
+}
+
+async fn replay(provider: &str, scenario: &str) -> Result<Generation> {
+    replay_with(provider, scenario, GenerationParams::default()).await
+}
+
+/// Replays a scenario with a provider sending `params` with its request.
+async fn replay_with(provider: &str, scenario: &str, params: GenerationParams) -> Result<Generation> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/providers")
+        .join(provider)
+        .join(format!("{}.json", scenario));
+    let exchange = load_fixture(&path).unwrap();
+    let (model, prompt) = (exchange.model.clone(), exchange.prompt.clone());
+    let (base_url, stub) = serve_once(exchange).await;
+
+    let client = Client::new();
+    let api_key = "test-key".to_string();
+    let result = match provider {
+        "anthropic" => {
+            let config = AnthropicConfig { api_base: base_url, api_key, models: vec![] };
+            AnthropicProvider::new(client, config).with_params(params).generate(&model, &prompt).await
+        }
+        "gemini" => {
+            let config = GeminiConfig { api_base: base_url, api_key, models: vec![], safety_threshold: Default::default() };
+            GeminiProvider::new(client, config).with_params(params).generate(&model, &prompt).await
+        }
+        "ollama" => {
+            let config = OllamaConfig { api_base: base_url, models: vec![] };
+            OllamaProvider::new(client, config).with_params(params).generate(&model, &prompt).await
+        }
+        "openai" => {
+            let config = OpenAIConfig { api_base: format!("{}/v1", base_url), api_key, models: vec![] };
+            OpenAIProvider::new(client, config).with_params(params).generate(&model, &prompt).await
+        }
+        other => panic!("no replay wiring for provider {}", other),
+    };
+
+    if let Err(mismatch) = stub.await.unwrap() {
+        panic!("{}/{}: request did not match the recording: {}", provider, scenario, mismatch);
+    }
+    result
+}
+
+/// Replays every scenario for `provider` and checks how each one is surfaced.
+async fn assert_scenarios(provider: &str, expected_usage: (Option<u32>, Option<u32>)) {
+    for scenario in SCENARIOS {
+        let result = replay(provider, scenario).await;
+        match (*scenario, result) {
+            ("success", Ok(generation)) => {
+                let usage = &generation.token_usage;
+                assert_eq!(generation.output, "4", "{}/success", provider);
+                assert_eq!((usage.input_tokens, usage.output_tokens), expected_usage, "{}/success", provider);
+                assert_eq!(generation.finish_reason, Some(FinishReason::Stop), "{}/success", provider);
+            }
+            ("rate_limited", Err(EvalError::ApiError { status: 429, .. })) => {}
+            ("server_error", Err(EvalError::ApiError { status: 500, .. })) => {}
+            ("empty_response", Err(EvalError::EmptyResponse)) => {}
+            ("malformed_json", Err(EvalError::JsonParse(_))) => {}
+            (scenario, other) => panic!("{}/{}: unexpected result {:?}", provider, scenario, other),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_anthropic_replay() {
+    assert_scenarios("anthropic", (Some(21), Some(5))).await;
+}
+
+#[tokio::test]
+async fn test_anthropic_thinking_replay() {
+    let params = GenerationParams { thinking: Some(ThinkingParams { budget_tokens: 2048 }), ..Default::default() };
+    let generation = replay_with("anthropic", "thinking", params).await.unwrap();
+    assert_eq!(generation.output, "4");
+    assert_eq!(
+        generation.reasoning.as_deref(),
+        Some("The user asks for 2+2, which is 4. They want just the number.")
+    );
+    assert_eq!((generation.token_usage.input_tokens, generation.token_usage.output_tokens), (Some(46), Some(38)));
+    assert_eq!(generation.finish_reason, Some(FinishReason::Stop));
+}
+
+#[tokio::test]
+async fn test_gemini_replay() {
+    assert_scenarios("gemini", (Some(12), Some(1))).await;
+}
+
+#[tokio::test]
+async fn test_ollama_replay() {
+    assert_scenarios("ollama", (Some(21), Some(2))).await;
+}
+
+#[tokio::test]
+async fn test_openai_replay() {
+    assert_scenarios("openai", (Some(19), Some(1))).await;
+}